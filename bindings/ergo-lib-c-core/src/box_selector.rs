@@ -0,0 +1,157 @@
+//! Box selection delegated to the host application (coin control of the platform wallets)
+
+use std::ffi::CString;
+use std::os::raw::{c_char, c_void};
+
+use ergo_lib::ergotree_ir::chain::ergo_box::box_value::BoxValue;
+use ergo_lib::ergotree_ir::chain::ergo_box::ErgoBox;
+use ergo_lib::ergotree_ir::chain::token::Token;
+use ergo_lib::wallet::box_selector::select_by_indices;
+use ergo_lib::wallet::box_selector::sum_value;
+use ergo_lib::wallet::box_selector::BoxSelectorError;
+use thiserror::Error;
+
+use crate::{const_ptr_as_mut, const_ptr_as_ref, ErgoBoxes, Error};
+
+/// Host callback selecting the boxes to spend.
+/// `user_data` - pointer passed on the callback registration,
+/// `inputs_json` - JSON array of the spendable boxes,
+/// `inputs_len` - number of the spendable boxes (capacity of `selected_indices_out`),
+/// `target_balance` - value (in nanoERGs) to cover,
+/// `target_tokens_json` - JSON array of the tokens to cover,
+/// `selected_indices_out` - indices of the selected boxes in `inputs_json`,
+/// `selected_len_out` - number of the selected boxes.
+/// Returns false to reject the selection (e.g. the coin-control policy cannot be satisfied).
+pub type BoxSelectorCallback = unsafe extern "C" fn(
+    user_data: *mut c_void,
+    inputs_json: *const c_char,
+    inputs_len: usize,
+    target_balance: u64,
+    target_tokens_json: *const c_char,
+    selected_indices_out: *mut usize,
+    selected_len_out: *mut usize,
+) -> bool;
+
+/// Errors of the box selection by the host callback
+#[derive(Error, PartialEq, Eq, Debug, Clone)]
+pub enum CallbackBoxSelectorError {
+    /// Callback rejected the selection
+    #[error("Box selection is rejected by the callback")]
+    Rejected,
+    /// Callback selected more boxes than there are inputs
+    #[error("Callback selected {0} boxes, more than the number of inputs")]
+    TooManySelected(usize),
+    /// Selected indices are invalid or the selected boxes do not cover the targets
+    #[error("Box selector error: {0}")]
+    BoxSelectorError(#[from] BoxSelectorError),
+}
+
+/// Box selector calling the host application to select the boxes, the change is calculated for
+/// the selected boxes (all of them are spent)
+#[derive(Clone, Copy)]
+pub struct CallbackBoxSelector {
+    callback: BoxSelectorCallback,
+    user_data: *mut c_void,
+}
+
+impl CallbackBoxSelector {
+    /// `user_data` is passed to the callback as is (the host keeps it valid while the selector is
+    /// set)
+    pub fn new(callback: BoxSelectorCallback, user_data: *mut c_void) -> Self {
+        CallbackBoxSelector {
+            callback,
+            user_data,
+        }
+    }
+
+    /// Selects the boxes with the callback
+    pub fn select(
+        &self,
+        inputs: Vec<ErgoBox>,
+        target_balance: BoxValue,
+        target_tokens: &[Token],
+    ) -> Result<ergo_lib::wallet::box_selector::BoxSelection<ErgoBox>, Error> {
+        let inputs_json = CString::new(serde_json::to_string(&inputs).map_err(Error::misc)?)
+            .map_err(Error::misc)?;
+        let target_tokens_json =
+            CString::new(serde_json::to_string(target_tokens).map_err(Error::misc)?)
+                .map_err(Error::misc)?;
+        let mut selected_indices = vec![0usize; inputs.len()];
+        let mut selected_len = 0usize;
+        let accepted = unsafe {
+            (self.callback)(
+                self.user_data,
+                inputs_json.as_ptr(),
+                inputs.len(),
+                *target_balance.as_u64(),
+                target_tokens_json.as_ptr(),
+                selected_indices.as_mut_ptr(),
+                &mut selected_len,
+            )
+        };
+        if !accepted {
+            return Err(Error::misc(CallbackBoxSelectorError::Rejected));
+        }
+        select_by_indices(
+            inputs,
+            selected_indices.get(..selected_len).ok_or_else(|| {
+                Error::misc(CallbackBoxSelectorError::TooManySelected(selected_len))
+            })?,
+            target_balance,
+            target_tokens,
+        )
+        .map_err(|e| Error::misc(CallbackBoxSelectorError::from(e)))
+    }
+}
+
+pub struct BoxSelection(pub ergo_lib::wallet::box_selector::BoxSelection<ErgoBox>);
+pub type BoxSelectionPtr = *mut BoxSelection;
+pub type ConstBoxSelectionPtr = *const BoxSelection;
+
+/// Selected boxes to spend
+pub unsafe fn box_selection_boxes(
+    box_selection: ConstBoxSelectionPtr,
+    ergo_boxes_out: *mut crate::ErgoBoxesPtr,
+) -> Result<(), Error> {
+    let box_selection = const_ptr_as_ref(box_selection, "box_selection")?;
+    let ergo_boxes_out = const_ptr_as_mut(ergo_boxes_out, "ergo_boxes_out")?;
+    *ergo_boxes_out = Box::into_raw(Box::new(ErgoBoxes(box_selection.0.boxes.clone())));
+    Ok(())
+}
+
+/// Total value (in nanoERGs) of the change boxes
+pub unsafe fn box_selection_change_value(
+    box_selection: ConstBoxSelectionPtr,
+    change_value_out: *mut u64,
+) -> Result<(), Error> {
+    let box_selection = const_ptr_as_ref(box_selection, "box_selection")?;
+    let change_value_out = const_ptr_as_mut(change_value_out, "change_value_out")?;
+    *change_value_out = sum_value(box_selection.0.change_boxes.as_slice());
+    Ok(())
+}
+
+/// Change boxes as JSON array of `{"value": <nanoERGs>, "assets": [<tokens>]}`
+pub unsafe fn box_selection_change_boxes_json(
+    box_selection: ConstBoxSelectionPtr,
+) -> Result<String, Error> {
+    let box_selection = const_ptr_as_ref(box_selection, "box_selection")?;
+    let change_boxes: Vec<serde_json::Value> = box_selection
+        .0
+        .change_boxes
+        .iter()
+        .map(|b| {
+            serde_json::json!({
+                "value": b.value.as_u64(),
+                "assets": b.tokens.as_ref().map(|t| t.as_vec().clone()).unwrap_or_default(),
+            })
+        })
+        .collect();
+    serde_json::to_string(&change_boxes).map_err(Error::misc)
+}
+
+pub fn box_selection_delete(box_selection: BoxSelectionPtr) {
+    if !box_selection.is_null() {
+        let boxed = unsafe { Box::from_raw(box_selection) };
+        std::mem::drop(boxed);
+    }
+}
@@ -9,6 +9,8 @@ pub enum Error {
     Misc(Box<dyn error::Error + 'static>),
     #[error("invalid argument: {0}")]
     InvalidArgument(&'static str),
+    #[error("not implemented: {0}")]
+    NotImplemented(&'static str),
 }
 
 pub type ErrorPtr = *mut Error;
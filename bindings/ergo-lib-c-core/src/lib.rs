@@ -9,14 +9,29 @@
 #![deny(unused_imports)]
 // #![deny(missing_docs)]
 #![allow(clippy::missing_safety_doc)]
+#![deny(clippy::unwrap_used)]
+#![deny(clippy::expect_used)]
+#![deny(clippy::todo)]
+#![deny(clippy::unimplemented)]
+#![deny(clippy::panic)]
 
+mod box_selector;
 mod error;
+mod wallet;
+pub use box_selector::*;
 pub use error::*;
+pub use wallet::*;
 
 use ergo_lib::ergotree_ir::chain::address::{AddressEncoder, NetworkPrefix};
+use ergo_lib::ergotree_ir::chain::ergo_box::ErgoBox;
+use ergo_lib::ergotree_ir::chain::header::Header;
+use ergo_lib::wallet::mnemonic_generator::detect_mnemonic_language;
+use ergo_lib::wallet::mnemonic_generator::Language;
+use ergo_lib::wallet::mnemonic_generator::MnemonicGenerator;
 
 pub struct Address(ergo_lib::ergotree_ir::chain::address::Address);
 pub type AddressPtr = *mut Address;
+pub type ConstAddressPtr = *const Address;
 
 pub unsafe fn address_from_testnet(
     address_str: &str,
@@ -46,3 +61,157 @@ pub fn address_delete(address: AddressPtr) {
         std::mem::drop(boxed);
     }
 }
+
+pub struct ErgoStateContext(pub ergo_lib::chain::ergo_state_context::ErgoStateContext);
+pub type ErgoStateContextPtr = *mut ErgoStateContext;
+pub type ConstErgoStateContextPtr = *const ErgoStateContext;
+
+/// Parse the state context from JSON array of the last 10 block headers (node REST API format)
+pub unsafe fn ergo_state_context_from_headers_json(
+    headers_json: &str,
+    ergo_state_context_out: *mut ErgoStateContextPtr,
+) -> Result<(), Error> {
+    let ergo_state_context_out =
+        const_ptr_as_mut(ergo_state_context_out, "ergo_state_context_out")?;
+    let headers: Vec<Header> = serde_json::from_str(headers_json).map_err(Error::misc)?;
+    let ctx = ergo_lib::chain::ergo_state_context::ErgoStateContext::from_last_headers(headers)
+        .map_err(Error::misc)?;
+    *ergo_state_context_out = Box::into_raw(Box::new(ErgoStateContext(ctx)));
+    Ok(())
+}
+
+pub fn ergo_state_context_delete(ergo_state_context: ErgoStateContextPtr) {
+    if !ergo_state_context.is_null() {
+        let boxed = unsafe { Box::from_raw(ergo_state_context) };
+        std::mem::drop(boxed);
+    }
+}
+
+pub struct Transaction(pub ergo_lib::chain::transaction::Transaction);
+pub type TransactionPtr = *mut Transaction;
+pub type ConstTransactionPtr = *const Transaction;
+
+/// Parse the signed transaction from JSON
+pub unsafe fn transaction_from_json(
+    json: &str,
+    transaction_out: *mut TransactionPtr,
+) -> Result<(), Error> {
+    let transaction_out = const_ptr_as_mut(transaction_out, "transaction_out")?;
+    let tx = serde_json::from_str(json).map_err(Error::misc)?;
+    *transaction_out = Box::into_raw(Box::new(Transaction(tx)));
+    Ok(())
+}
+
+pub fn transaction_delete(transaction: TransactionPtr) {
+    if !transaction.is_null() {
+        let boxed = unsafe { Box::from_raw(transaction) };
+        std::mem::drop(boxed);
+    }
+}
+
+pub struct ErgoBoxes(pub Vec<ErgoBox>);
+pub type ErgoBoxesPtr = *mut ErgoBoxes;
+pub type ConstErgoBoxesPtr = *const ErgoBoxes;
+
+/// Parse the boxes from JSON array of boxes
+pub unsafe fn ergo_boxes_from_json(
+    json: &str,
+    ergo_boxes_out: *mut ErgoBoxesPtr,
+) -> Result<(), Error> {
+    let ergo_boxes_out = const_ptr_as_mut(ergo_boxes_out, "ergo_boxes_out")?;
+    let boxes = serde_json::from_str(json).map_err(Error::misc)?;
+    *ergo_boxes_out = Box::into_raw(Box::new(ErgoBoxes(boxes)));
+    Ok(())
+}
+
+pub fn ergo_boxes_delete(ergo_boxes: ErgoBoxesPtr) {
+    if !ergo_boxes.is_null() {
+        let boxed = unsafe { Box::from_raw(ergo_boxes) };
+        std::mem::drop(boxed);
+    }
+}
+
+/// Result of the transaction input proof verification
+#[repr(C)]
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct VerificationResult {
+    /// true if the proof satisfies the spent box script
+    pub result: bool,
+    /// estimated cost of the script execution (accumulated during the script reduction)
+    pub cost: u64,
+}
+
+/// Verify the proof of the transaction input with the given index against the spent box script
+/// evaluated in the context of the transaction. The activated script version is taken from the
+/// block version of the state context pre-header.
+/// `boxes_to_spend` - boxes spent by the transaction inputs (in the order of the inputs),
+/// `data_boxes` - boxes of the transaction data inputs (can be null if there are none)
+pub unsafe fn verify_tx_input(
+    tx: ConstTransactionPtr,
+    input_index: usize,
+    state_context: ConstErgoStateContextPtr,
+    boxes_to_spend: ConstErgoBoxesPtr,
+    data_boxes: ConstErgoBoxesPtr,
+    verification_result_out: *mut VerificationResult,
+) -> Result<(), Error> {
+    let tx = const_ptr_as_ref(tx, "tx")?;
+    let state_context = const_ptr_as_ref(state_context, "state_context")?;
+    let boxes_to_spend = const_ptr_as_ref(boxes_to_spend, "boxes_to_spend")?;
+    let data_boxes: &[ErgoBox] = match data_boxes.as_ref() {
+        Some(data_boxes) => &data_boxes.0,
+        None => &[],
+    };
+    let verification_result_out =
+        const_ptr_as_mut(verification_result_out, "verification_result_out")?;
+    let res = ergo_lib::wallet::verifier::verify_tx_input_proof(
+        &tx.0,
+        input_index,
+        &state_context.0,
+        &boxes_to_spend.0,
+        data_boxes,
+    )
+    .map_err(Error::misc)?;
+    *verification_result_out = VerificationResult {
+        result: res.result,
+        cost: res.cost,
+    };
+    Ok(())
+}
+
+/// Verify the signature of an arbitrary message made by the owner of the address (P2PK or P2S
+/// with a constant sigma proposition), `result_out` is false if the signature is not valid
+pub unsafe fn verify_signature(
+    address: ConstAddressPtr,
+    message: &[u8],
+    signature: &[u8],
+    result_out: *mut bool,
+) -> Result<(), Error> {
+    let address = const_ptr_as_ref(address, "address")?;
+    let result_out = const_ptr_as_mut(result_out, "result_out")?;
+    *result_out = ergo_lib::wallet::verifier::verify_signature(&address.0, message, signature)
+        .map_err(Error::misc)?;
+    Ok(())
+}
+
+/// Generate a new BIP-39 mnemonic phrase with the given strength (entropy size in bits: 128, 160,
+/// 192, 224 or 256) and wordlist language name (e.g. "english", "japanese")
+pub fn mnemonic_generate(strength: u32, language: &str) -> Result<String, Error> {
+    let language: Language = language.parse().map_err(Error::misc)?;
+    let generator = MnemonicGenerator::new(language, strength).map_err(Error::misc)?;
+    Ok(generator.generate())
+}
+
+/// Check the words and the checksum of the BIP-39 mnemonic phrase in any supported language
+pub fn mnemonic_validate(phrase: &str) -> Result<(), Error> {
+    detect_mnemonic_language(phrase)
+        .map(|_| ())
+        .map_err(Error::misc)
+}
+
+unsafe fn const_ptr_as_ref<'a, T>(ptr: *const T, ptr_name: &'static str) -> Result<&'a T, Error> {
+    ptr.as_ref().ok_or(Error::InvalidArgument(ptr_name))
+}
+
+unsafe fn const_ptr_as_mut<'a, T>(ptr: *mut T, ptr_name: &'static str) -> Result<&'a mut T, Error> {
+    ptr.as_mut().ok_or(Error::InvalidArgument(ptr_name))
+}
@@ -0,0 +1,78 @@
+//! Wallet handle of the C API (wallet settings, e.g. the host box selector)
+
+use std::convert::TryFrom;
+use std::os::raw::c_void;
+
+use ergo_lib::ergotree_ir::chain::ergo_box::box_value::BoxValue;
+use ergo_lib::ergotree_ir::chain::token::Token;
+use ergo_lib::wallet::box_selector::BoxSelector;
+use ergo_lib::wallet::box_selector::SimpleBoxSelector;
+
+use crate::{
+    const_ptr_as_mut, const_ptr_as_ref, BoxSelection, BoxSelectionPtr, BoxSelectorCallback,
+    CallbackBoxSelector, ConstErgoBoxesPtr, Error,
+};
+
+/// Wallet settings of the C API
+#[derive(Default)]
+pub struct Wallet {
+    /// Host box selector (the simple box selector is used if not set)
+    box_selector: Option<CallbackBoxSelector>,
+}
+pub type WalletPtr = *mut Wallet;
+pub type ConstWalletPtr = *const Wallet;
+
+/// Create the wallet with the default settings
+pub unsafe fn wallet_new(wallet_out: *mut WalletPtr) -> Result<(), Error> {
+    let wallet_out = const_ptr_as_mut(wallet_out, "wallet_out")?;
+    *wallet_out = Box::into_raw(Box::new(Wallet::default()));
+    Ok(())
+}
+
+/// Set the host callback to select the boxes for the wallet transactions, null callback restores
+/// the default (simple) box selector
+pub unsafe fn wallet_set_box_selector_callback(
+    wallet: WalletPtr,
+    callback: Option<BoxSelectorCallback>,
+    user_data: *mut c_void,
+) -> Result<(), Error> {
+    let wallet = const_ptr_as_mut(wallet, "wallet")?;
+    wallet.box_selector = callback.map(|callback| CallbackBoxSelector::new(callback, user_data));
+    Ok(())
+}
+
+/// Select the boxes to spend out of the unspent boxes with the wallet box selector.
+/// `target_tokens_json` - JSON array of the tokens to cover (None if there are none)
+pub unsafe fn wallet_select_boxes(
+    wallet: ConstWalletPtr,
+    unspent_boxes: ConstErgoBoxesPtr,
+    target_balance: u64,
+    target_tokens_json: Option<&str>,
+    box_selection_out: *mut BoxSelectionPtr,
+) -> Result<(), Error> {
+    let wallet = const_ptr_as_ref(wallet, "wallet")?;
+    let unspent_boxes = const_ptr_as_ref(unspent_boxes, "unspent_boxes")?;
+    let box_selection_out = const_ptr_as_mut(box_selection_out, "box_selection_out")?;
+    let target_balance = BoxValue::try_from(target_balance).map_err(Error::misc)?;
+    let target_tokens: Vec<Token> = match target_tokens_json {
+        Some(json) => serde_json::from_str(json).map_err(Error::misc)?,
+        None => vec![],
+    };
+    let selection = match wallet.box_selector {
+        Some(selector) => {
+            selector.select(unspent_boxes.0.clone(), target_balance, &target_tokens)?
+        }
+        None => SimpleBoxSelector::new()
+            .select(unspent_boxes.0.clone(), target_balance, &target_tokens)
+            .map_err(Error::misc)?,
+    };
+    *box_selection_out = Box::into_raw(Box::new(BoxSelection(selection)));
+    Ok(())
+}
+
+pub fn wallet_delete(wallet: WalletPtr) {
+    if !wallet.is_null() {
+        let boxed = unsafe { Box::from_raw(wallet) };
+        std::mem::drop(boxed);
+    }
+}
@@ -9,35 +9,50 @@
 #![deny(unused_imports)]
 // #![deny(missing_docs)]
 #![allow(clippy::missing_safety_doc)]
+#![deny(clippy::unwrap_used)]
+#![deny(clippy::expect_used)]
+#![deny(clippy::todo)]
+#![deny(clippy::unimplemented)]
+#![deny(clippy::panic)]
 
 use ergo_lib::ergotree_ir::chain;
 
-use ergo_lib_c_core::{address_delete, address_from_testnet};
-pub use ergo_lib_c_core::{Address, Error};
+use ergo_lib_c_core::{
+    address_delete, address_from_testnet, box_selection_boxes, box_selection_change_boxes_json,
+    box_selection_change_value, box_selection_delete, ergo_boxes_delete, ergo_boxes_from_json,
+    ergo_state_context_delete, ergo_state_context_from_headers_json, mnemonic_generate,
+    mnemonic_validate, transaction_delete, transaction_from_json, verify_signature,
+    verify_tx_input, wallet_delete, wallet_new, wallet_select_boxes,
+    wallet_set_box_selector_callback,
+};
+pub use ergo_lib_c_core::{
+    Address, BoxSelection, BoxSelectionPtr, BoxSelectorCallback, ConstAddressPtr,
+    ConstBoxSelectionPtr, ConstErgoBoxesPtr, ConstErgoStateContextPtr, ConstTransactionPtr,
+    ConstWalletPtr, ErgoBoxes, ErgoBoxesPtr, ErgoStateContext, ErgoStateContextPtr, Error,
+    Transaction, TransactionPtr, VerificationResult, Wallet, WalletPtr,
+};
 use std::{
     ffi::{CStr, CString},
-    os::raw::c_char,
+    os::raw::{c_char, c_void},
 };
 
 pub type ErrorPtr = *mut Error;
 pub type AddressPtr = *mut Address;
 
-pub struct ErgoStateContext(ergo_lib::chain::ergo_state_context::ErgoStateContext);
-pub type ErgoStateContextPtr = *mut ErgoStateContext;
-
+/// Parse the state context from JSON array of the last 10 block headers
 #[no_mangle]
 pub unsafe extern "C" fn ergo_wallet_ergo_state_context_from_json(
-    _json_str: *const c_char,
-    _ergo_state_context_out: *mut ErgoStateContextPtr,
+    json_str: *const c_char,
+    ergo_state_context_out: *mut ErgoStateContextPtr,
 ) -> ErrorPtr {
-    todo!()
+    let json = CStr::from_ptr(json_str).to_string_lossy();
+    let res = ergo_state_context_from_headers_json(&json, ergo_state_context_out);
+    Error::c_api_from(res)
 }
 
 #[no_mangle]
-pub unsafe extern "C" fn ergo_wallet_ergo_state_context_delete(
-    _ergo_state_context: ErgoStateContextPtr,
-) -> ErrorPtr {
-    todo!()
+pub extern "C" fn ergo_wallet_ergo_state_context_delete(ergo_state_context: ErgoStateContextPtr) {
+    ergo_state_context_delete(ergo_state_context)
 }
 
 pub struct ErgoBoxCandidate(chain::ergo_box::ErgoBoxCandidate);
@@ -50,14 +65,20 @@ pub extern "C" fn ergo_wallet_ergo_box_candidate_new_pay_to_address(
     _creation_height: u32,
     _ergo_box_candidate_out: *mut ErgoBoxCandidatePtr,
 ) -> ErrorPtr {
-    todo!()
+    Error::c_api_from(Err(Error::NotImplemented(
+        "ergo_wallet_ergo_box_candidate_new_pay_to_address",
+    )))
 }
 
 #[no_mangle]
 pub extern "C" fn ergo_wallet_ergo_box_candidate_delete(
-    _ergo_box_candidate: ErgoBoxCandidatePtr,
+    ergo_box_candidate: ErgoBoxCandidatePtr,
 ) -> ErrorPtr {
-    todo!()
+    if !ergo_box_candidate.is_null() {
+        let boxed = unsafe { Box::from_raw(ergo_box_candidate) };
+        std::mem::drop(boxed);
+    }
+    std::ptr::null_mut()
 }
 
 #[no_mangle]
@@ -83,12 +104,18 @@ pub extern "C" fn ergo_wallet_unspent_boxes_from_json(
     _json_str: *const c_char,
     _unspent_boxes_out: *mut UnspentBoxesPtr,
 ) -> ErrorPtr {
-    todo!()
+    Error::c_api_from(Err(Error::NotImplemented(
+        "ergo_wallet_unspent_boxes_from_json",
+    )))
 }
 
 #[no_mangle]
-pub extern "C" fn ergo_wallet_unspent_boxes_delete(_unspent_boxes: UnspentBoxesPtr) -> ErrorPtr {
-    todo!()
+pub extern "C" fn ergo_wallet_unspent_boxes_delete(unspent_boxes: UnspentBoxesPtr) -> ErrorPtr {
+    if !unspent_boxes.is_null() {
+        let boxed = unsafe { Box::from_raw(unspent_boxes) };
+        std::mem::drop(boxed);
+    }
+    std::ptr::null_mut()
 }
 
 pub struct DataInputBoxes(Vec<chain::ergo_box::ErgoBoxCandidate>);
@@ -102,16 +129,25 @@ pub extern "C" fn ergo_wallet_output_boxes_new(
     _ergo_box_candidate: ErgoBoxCandidatePtr,
     _output_boxes_out: *mut OutputBoxesPtr,
 ) -> ErrorPtr {
-    todo!()
+    Error::c_api_from(Err(Error::NotImplemented("ergo_wallet_output_boxes_new")))
 }
 
 #[no_mangle]
-pub extern "C" fn ergo_wallet_output_boxes_delete(_output_boxes: OutputBoxesPtr) -> ErrorPtr {
-    todo!()
+pub extern "C" fn ergo_wallet_output_boxes_delete(output_boxes: OutputBoxesPtr) -> ErrorPtr {
+    if !output_boxes.is_null() {
+        let boxed = unsafe { Box::from_raw(output_boxes) };
+        std::mem::drop(boxed);
+    }
+    std::ptr::null_mut()
 }
 
-pub struct Wallet();
-pub type WalletPtr = *mut Wallet;
+/// Create the wallet with the default settings (simple box selector), delete it with
+/// `ergo_wallet_wallet_delete`
+#[no_mangle]
+pub unsafe extern "C" fn ergo_wallet_wallet_new(wallet_out: *mut WalletPtr) -> ErrorPtr {
+    let res = wallet_new(wallet_out);
+    Error::c_api_from(res)
+}
 
 #[no_mangle]
 pub extern "C" fn ergo_wallet_wallet_from_mnemonic(
@@ -120,12 +156,103 @@ pub extern "C" fn ergo_wallet_wallet_from_mnemonic(
     _mnemonic_password_length: usize,
     _wallet_out: *mut WalletPtr,
 ) -> ErrorPtr {
-    todo!()
+    Error::c_api_from(Err(Error::NotImplemented(
+        "ergo_wallet_wallet_from_mnemonic",
+    )))
+}
+
+#[no_mangle]
+pub extern "C" fn ergo_wallet_wallet_delete(wallet: WalletPtr) -> ErrorPtr {
+    wallet_delete(wallet);
+    std::ptr::null_mut()
+}
+
+/// Set the callback selecting the boxes to spend in the wallet transactions (coin control).
+/// The callback gets `user_data`, the spendable boxes (JSON array and its length), the target
+/// value (in nanoERGs) and tokens (JSON array), writes the indices of the selected boxes to
+/// `selected_indices_out` (room for all the boxes) and their number to `selected_len_out`, and
+/// returns false to reject the selection. All the selected boxes are spent, the change is
+/// calculated by the library. `user_data` must stay valid while the callback is set.
+/// Null `callback` restores the default (simple) box selector.
+#[no_mangle]
+pub unsafe extern "C" fn ergo_wallet_wallet_set_box_selector_callback(
+    wallet: WalletPtr,
+    callback: Option<BoxSelectorCallback>,
+    user_data: *mut c_void,
+) -> ErrorPtr {
+    let res = wallet_set_box_selector_callback(wallet, callback, user_data);
+    Error::c_api_from(res)
+}
+
+/// Select the boxes to spend out of `unspent_boxes` with the wallet box selector.
+/// `target_balance` - value (in nanoERGs) to cover,
+/// `target_tokens_json` - JSON array of the tokens to cover (can be null).
+/// On success `box_selection_out` holds the selection, delete it with
+/// `ergo_wallet_box_selection_delete`.
+#[no_mangle]
+pub unsafe extern "C" fn ergo_wallet_wallet_select_boxes(
+    wallet: ConstWalletPtr,
+    unspent_boxes: ConstErgoBoxesPtr,
+    target_balance: u64,
+    target_tokens_json: *const c_char, // can be null
+    box_selection_out: *mut BoxSelectionPtr,
+) -> ErrorPtr {
+    let target_tokens_json = if target_tokens_json.is_null() {
+        None
+    } else {
+        Some(CStr::from_ptr(target_tokens_json).to_string_lossy())
+    };
+    let res = wallet_select_boxes(
+        wallet,
+        unspent_boxes,
+        target_balance,
+        target_tokens_json.as_deref(),
+        box_selection_out,
+    );
+    Error::c_api_from(res)
+}
+
+/// Selected boxes to spend, delete them with `ergo_wallet_ergo_boxes_delete`
+#[no_mangle]
+pub unsafe extern "C" fn ergo_wallet_box_selection_boxes(
+    box_selection: ConstBoxSelectionPtr,
+    ergo_boxes_out: *mut ErgoBoxesPtr,
+) -> ErrorPtr {
+    let res = box_selection_boxes(box_selection, ergo_boxes_out);
+    Error::c_api_from(res)
+}
+
+/// Total value (in nanoERGs) of the change to return
+#[no_mangle]
+pub unsafe extern "C" fn ergo_wallet_box_selection_change_value(
+    box_selection: ConstBoxSelectionPtr,
+    change_value_out: *mut u64,
+) -> ErrorPtr {
+    let res = box_selection_change_value(box_selection, change_value_out);
+    Error::c_api_from(res)
+}
+
+/// Change boxes to return as JSON array of `{"value": <nanoERGs>, "assets": [<tokens>]}` (the
+/// tokens are in the format of `target_tokens_json` of `ergo_wallet_wallet_select_boxes`).
+/// On success `change_json_out` holds the JSON, delete it with `ergo_wallet_delete_string`.
+#[no_mangle]
+pub unsafe extern "C" fn ergo_wallet_box_selection_change_boxes_json(
+    box_selection: ConstBoxSelectionPtr,
+    change_json_out: *mut *mut c_char,
+) -> ErrorPtr {
+    let res = box_selection_change_boxes_json(box_selection).and_then(|json| {
+        let change_json_out = change_json_out
+            .as_mut()
+            .ok_or(Error::InvalidArgument("change_json_out"))?;
+        *change_json_out = CString::new(json).map_err(Error::misc)?.into_raw();
+        Ok(())
+    });
+    Error::c_api_from(res)
 }
 
 #[no_mangle]
-pub extern "C" fn ergo_wallet_wallet_delete(_wallet: WalletPtr) -> ErrorPtr {
-    todo!()
+pub extern "C" fn ergo_wallet_box_selection_delete(box_selection: BoxSelectionPtr) {
+    box_selection_delete(box_selection)
 }
 
 #[no_mangle]
@@ -140,15 +267,14 @@ pub extern "C" fn ergo_wallet_wallet_new_signed_tx(
     _tx_fee_amount: u64,
     _transaction_out: *mut TransactionPtr,
 ) -> ErrorPtr {
-    todo!()
+    Error::c_api_from(Err(Error::NotImplemented(
+        "ergo_wallet_wallet_new_signed_tx",
+    )))
 }
 
-pub struct Transaction(ergo_lib::chain::transaction::Transaction);
-pub type TransactionPtr = *mut Transaction;
-
 #[no_mangle]
-pub extern "C" fn ergo_wallet_delete_signed_tx(_transaction: TransactionPtr) -> ErrorPtr {
-    todo!()
+pub extern "C" fn ergo_wallet_delete_signed_tx(transaction: TransactionPtr) {
+    transaction_delete(transaction)
 }
 
 #[no_mangle]
@@ -156,7 +282,107 @@ pub extern "C" fn ergo_wallet_signed_tx_to_json(
     _transaction: TransactionPtr,
     _json_str_out: *mut *const c_char,
 ) -> ErrorPtr {
-    todo!()
+    Error::c_api_from(Err(Error::NotImplemented("ergo_wallet_signed_tx_to_json")))
+}
+
+/// Parse the signed transaction from JSON
+#[no_mangle]
+pub unsafe extern "C" fn ergo_wallet_signed_tx_from_json(
+    json_str: *const c_char,
+    transaction_out: *mut TransactionPtr,
+) -> ErrorPtr {
+    let json = CStr::from_ptr(json_str).to_string_lossy();
+    let res = transaction_from_json(&json, transaction_out);
+    Error::c_api_from(res)
+}
+
+/// Parse the boxes from JSON array of boxes
+#[no_mangle]
+pub unsafe extern "C" fn ergo_wallet_ergo_boxes_from_json(
+    json_str: *const c_char,
+    ergo_boxes_out: *mut ErgoBoxesPtr,
+) -> ErrorPtr {
+    let json = CStr::from_ptr(json_str).to_string_lossy();
+    let res = ergo_boxes_from_json(&json, ergo_boxes_out);
+    Error::c_api_from(res)
+}
+
+#[no_mangle]
+pub extern "C" fn ergo_wallet_ergo_boxes_delete(ergo_boxes: ErgoBoxesPtr) {
+    ergo_boxes_delete(ergo_boxes)
+}
+
+/// Verify the proof of the transaction input with the given index.
+/// `boxes_to_spend` - boxes spent by the transaction inputs,
+/// `data_boxes` - boxes of the transaction data inputs (can be null).
+/// On success `verification_result_out` holds the verification result and the script cost.
+#[no_mangle]
+pub unsafe extern "C" fn ergo_wallet_verify_tx_input(
+    tx: ConstTransactionPtr,
+    input_index: usize,
+    state_context: ConstErgoStateContextPtr,
+    boxes_to_spend: ConstErgoBoxesPtr,
+    data_boxes: ConstErgoBoxesPtr, // can be null
+    verification_result_out: *mut VerificationResult,
+) -> ErrorPtr {
+    let res = verify_tx_input(
+        tx,
+        input_index,
+        state_context,
+        boxes_to_spend,
+        data_boxes,
+        verification_result_out,
+    );
+    Error::c_api_from(res)
+}
+
+/// Verify the signature of an arbitrary message (e.g. a "sign-in" challenge) made by the owner of
+/// the address (P2PK or P2S with a constant sigma proposition).
+/// On success `result_out` is true if the signature is valid for the message and the address.
+#[no_mangle]
+pub unsafe extern "C" fn ergo_wallet_verify_signature(
+    address: ConstAddressPtr,
+    message: *const u8,
+    message_len: usize,
+    signature: *const u8,
+    signature_len: usize,
+    result_out: *mut bool,
+) -> ErrorPtr {
+    let message = std::slice::from_raw_parts(message, message_len);
+    let signature = std::slice::from_raw_parts(signature, signature_len);
+    let res = verify_signature(address, message, signature, result_out);
+    Error::c_api_from(res)
+}
+
+/// Generate a new BIP-39 mnemonic phrase.
+/// `strength` - entropy size in bits (128, 160, 192, 224 or 256 for 12 to 24 words),
+/// `lang` - wordlist language: "english", "chinese_simplified", "chinese_traditional", "czech",
+/// "french", "italian", "japanese", "korean", "portuguese" or "spanish".
+/// On success `mnemonic_out` holds the phrase, delete it with `ergo_wallet_delete_string`.
+#[no_mangle]
+pub unsafe extern "C" fn ergo_wallet_mnemonic_generate(
+    strength: u32,
+    lang: *const c_char,
+    mnemonic_out: *mut *mut c_char,
+) -> ErrorPtr {
+    let lang = CStr::from_ptr(lang).to_string_lossy();
+    let res = mnemonic_generate(strength, &lang).and_then(|phrase| {
+        let mnemonic_out = mnemonic_out
+            .as_mut()
+            .ok_or(Error::InvalidArgument("mnemonic_out"))?;
+        *mnemonic_out = CString::new(phrase).map_err(Error::misc)?.into_raw();
+        Ok(())
+    });
+    Error::c_api_from(res)
+}
+
+/// Validate the BIP-39 mnemonic phrase (the words and the checksum) in any supported language.
+/// Returns null if the phrase is valid, otherwise the error describing the problem.
+#[no_mangle]
+pub unsafe extern "C" fn ergo_wallet_mnemonic_validate(phrase: *const c_char) -> ErrorPtr {
+    let phrase = CStr::from_ptr(phrase).to_string_lossy();
+    let res = mnemonic_validate(&phrase);
+    Error::c_api_from(res)
 }
 
 #[no_mangle]
@@ -178,8 +404,13 @@ pub extern "C" fn ergo_wallet_delete_error(error: ErrorPtr) {
 #[no_mangle]
 pub unsafe extern "C" fn ergo_wallet_error_to_string(error: ErrorPtr) -> *mut c_char {
     if let Some(error) = error.as_ref() {
-        CString::new(error.to_string()).unwrap().into_raw()
+        // error messages have no interior nul bytes, empty string otherwise
+        CString::new(error.to_string())
+            .unwrap_or_default()
+            .into_raw()
     } else {
-        CString::new(b"success".to_vec()).unwrap().into_raw()
+        CString::new(b"success".to_vec())
+            .unwrap_or_default()
+            .into_raw()
     }
 }
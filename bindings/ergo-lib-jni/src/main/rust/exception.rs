@@ -27,8 +27,9 @@ pub fn unwrap_exc_or<T>(env: &JNIEnv, res: ExceptionResult<T>, error_val: T) ->
                 Ok(val) => val,
                 Err(jni_error) => {
                     // Do nothing if there is a pending Java-exception that will be thrown
-                    // automatically by the JVM when the native method returns.
-                    if !env.exception_check().unwrap() {
+                    // automatically by the JVM when the native method returns (throw if the
+                    // check itself fails).
+                    if !env.exception_check().unwrap_or(false) {
                         // Throw a Java exception manually in case of an internal error.
                         throw(env, &jni_error.to_string())
                     }
@@ -79,6 +80,7 @@ pub fn any_to_string(any: &Box<dyn Any + Send>) -> String {
 }
 
 #[cfg(test)]
+#[allow(clippy::unwrap_used)]
 mod tests {
     use super::*;
     use std::error::Error;
@@ -9,6 +9,11 @@
 #![deny(unused_imports)]
 // #![deny(missing_docs)]
 #![allow(clippy::missing_safety_doc)]
+#![deny(clippy::unwrap_used)]
+#![deny(clippy::expect_used)]
+#![deny(clippy::todo)]
+#![deny(clippy::unimplemented)]
+#![deny(clippy::panic)]
 
 #[macro_use]
 extern crate log;
@@ -31,9 +36,7 @@ pub unsafe extern "system" fn Java_org_ergoplatform_wallet_jni_WalletLib_address
     address_str: JString,
 ) -> jlong {
     let res = panic::catch_unwind(|| {
-        let address_str_j = env
-            .get_string(address_str)
-            .expect("Couldn't get address String");
+        let address_str_j = env.get_string(address_str)?;
 
         let mut address: AddressPtr = null_mut();
         let result = address_from_testnet(&address_str_j.to_string_lossy(), &mut address);
@@ -19,6 +19,8 @@ pub enum NetworkPrefix {
     Mainnet = 0,
     /// Testnet
     Testnet = 16,
+    /// Devnet (local development network)
+    Devnet = 32,
 }
 
 impl From<NetworkPrefix> for ergo_lib::ergotree_ir::chain::address::NetworkPrefix {
@@ -27,6 +29,7 @@ impl From<NetworkPrefix> for ergo_lib::ergotree_ir::chain::address::NetworkPrefi
         match v {
             NetworkPrefix::Mainnet => Mainnet,
             NetworkPrefix::Testnet => Testnet,
+            NetworkPrefix::Devnet => Devnet,
         }
     }
 }
@@ -37,6 +40,7 @@ impl From<ergo_lib::ergotree_ir::chain::address::NetworkPrefix> for NetworkPrefi
         match v {
             ergo_lib::ergotree_ir::chain::address::NetworkPrefix::Mainnet => Mainnet,
             ergo_lib::ergotree_ir::chain::address::NetworkPrefix::Testnet => Testnet,
+            ergo_lib::ergotree_ir::chain::address::NetworkPrefix::Devnet => Devnet,
         }
     }
 }
@@ -1,10 +1,14 @@
 //! Ergo blockchain state (for ErgoTree evaluation)
 use ergo_lib::chain;
+use ergo_lib::chain::parameters::Parameters;
+use ergo_lib::ergotree_ir::chain::header::Header;
 use wasm_bindgen::prelude::*;
 
 extern crate derive_more;
 use derive_more::{From, Into};
 
+use crate::block_header::BlockHeaders;
+use crate::error_conversion::to_js;
 use crate::header::PreHeader;
 
 /// Blockchain state (last headers, etc.)
@@ -22,6 +26,27 @@ impl ErgoStateContext {
         ergo_state_context.into()
     }
 
+    /// Create new context from the last 10 block headers (in any order), pre-header is of the
+    /// next block (its parent is the newest header)
+    pub fn from_block_headers(headers: BlockHeaders) -> Result<ErgoStateContext, JsValue> {
+        chain::ergo_state_context::ErgoStateContext::from_last_headers(headers.into())
+            .map(Self)
+            .map_err(to_js)
+    }
+
+    /// Create new context from the node REST API JSON: `headers_json` is the output of
+    /// `/blocks/lastHeaders/10`, `parameters_json` is the `parameters` object of `/info`.
+    pub fn from_block_headers_json(
+        headers_json: &str,
+        parameters_json: &str,
+    ) -> Result<ErgoStateContext, JsValue> {
+        let parameters: Parameters = serde_json::from_str(parameters_json).map_err(to_js)?;
+        let headers: Vec<Header> = serde_json::from_str(headers_json).map_err(to_js)?;
+        chain::ergo_state_context::ErgoStateContext::from_last_headers(headers)
+            .map(|ctx| Self(ctx.with_parameters(parameters)))
+            .map_err(to_js)
+    }
+
     /// empty (dummy) context (for signing P2PK tx only)
     pub fn dummy() -> ErgoStateContext {
         ErgoStateContext(chain::ergo_state_context::ErgoStateContext::dummy())
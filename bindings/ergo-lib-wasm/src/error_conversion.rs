@@ -3,6 +3,10 @@
 use std::num::ParseIntError;
 
 use base16::DecodeError;
+use bounded_vec::BoundedVecOutOfBounds;
+use ergo_lib::chain::ergo_state_context::ErgoStateContextError;
+use ergo_lib::ergotree_interpreter::sigma_protocol::prover::hint::HintsJsonError;
+use ergo_lib::ergotree_interpreter::sigma_protocol::verifier::VerifierError;
 use ergo_lib::ergotree_ir::chain::address::AddressEncoderError;
 use ergo_lib::ergotree_ir::chain::address::AddressError;
 use ergo_lib::ergotree_ir::chain::digest32::Digest32Error;
@@ -56,6 +60,10 @@ from_error_to_wrap!(TxBuilderError);
 from_error_to_wrap!(TxSigningError);
 from_error_to_wrap!(WalletError);
 from_error_to_wrap!(DecodeError);
+from_error_to_wrap!(ErgoStateContextError);
+from_error_to_wrap!(VerifierError);
+from_error_to_wrap!(BoundedVecOutOfBounds);
+from_error_to_wrap!(HintsJsonError);
 
 macro_rules! from_error_to_wrap_via_debug {
     ($t:ident) => {
@@ -1,7 +1,5 @@
 //! JSON serialization according to EIP-12 (using strings for BoxValue and TokenAmount)
 
-use std::convert::TryInto;
-
 use derive_more::FromStr;
 use ergo_lib::chain::transaction::unsigned::UnsignedTransaction;
 use ergo_lib::chain::transaction::DataInput;
@@ -39,10 +37,10 @@ impl From<Transaction> for TransactionJsonEip12 {
     fn from(t: Transaction) -> Self {
         TransactionJsonEip12 {
             tx_id: t.id(),
-            inputs: t.inputs.try_into().unwrap(),
+            inputs: t.inputs.as_vec().clone(),
             data_inputs: t
                 .data_inputs
-                .map(|di| di.try_into().unwrap())
+                .map(|di| di.as_vec().clone())
                 .unwrap_or_else(Vec::new),
             outputs: t.outputs.into_iter().map(|b| b.into()).collect(),
         }
@@ -67,10 +65,10 @@ pub(crate) struct UnsignedTransactionJsonEip12 {
 impl From<UnsignedTransaction> for UnsignedTransactionJsonEip12 {
     fn from(t: UnsignedTransaction) -> Self {
         UnsignedTransactionJsonEip12 {
-            inputs: t.inputs.try_into().unwrap(),
+            inputs: t.inputs.as_vec().clone(),
             data_inputs: t
                 .data_inputs
-                .map(|di| di.try_into().unwrap())
+                .map(|di| di.as_vec().clone())
                 .unwrap_or_else(Vec::new),
             outputs: t.output_candidates.into_iter().map(|b| b.into()).collect(),
         }
@@ -203,6 +201,7 @@ pub struct TokenAmountJsonEip12(
 );
 
 #[cfg(test)]
+#[allow(clippy::unwrap_used)]
 mod tests {
     use super::*;
 
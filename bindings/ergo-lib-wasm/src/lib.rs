@@ -14,6 +14,11 @@
 #![allow(clippy::new_without_default)]
 #![allow(clippy::len_without_is_empty)]
 #![deny(clippy::wildcard_enum_match_arm)]
+#![deny(clippy::unwrap_used)]
+#![deny(clippy::expect_used)]
+#![deny(clippy::todo)]
+#![deny(clippy::unimplemented)]
+#![deny(clippy::panic)]
 
 pub mod address;
 pub mod ast;
@@ -45,6 +45,22 @@ impl SecretKey {
     pub fn to_bytes(&self) -> Vec<u8> {
         self.0.to_bytes()
     }
+
+    /// Encode as Base58 with the key type tag and the checksum (to move the key between wallets)
+    pub fn to_base58check(&self) -> String {
+        self.0
+            .to_encoded(wallet::secret_key::SecretKeyEncoding::Base58Check)
+    }
+
+    /// Parse the key encoded with `to_base58check`
+    pub fn from_base58check(encoded: &str) -> Result<SecretKey, JsValue> {
+        wallet::secret_key::SecretKey::from_encoded(
+            encoded,
+            wallet::secret_key::SecretKeyEncoding::Base58Check,
+        )
+        .map(SecretKey)
+        .map_err(|e| JsValue::from_str(&format!("{}", e)))
+    }
 }
 
 /// SecretKey collection
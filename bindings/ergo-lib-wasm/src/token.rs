@@ -151,20 +151,27 @@ impl Tokens {
         self.0.as_ref().map(BoxTokens::len).unwrap_or(0)
     }
 
-    /// Returns the element of the collection with a given index
-    pub fn get(&self, index: usize) -> Token {
-        self.0.as_ref().unwrap().get(index).unwrap().clone()
-    }
-
-    /// Adds an elements to the collection
-    pub fn add(&mut self, elem: &Token) {
-        if self.0.is_some() {
-            let mut new_vec = self.0.as_ref().unwrap().as_vec().clone();
-            new_vec.push(elem.clone());
-            self.0 = Some(BoxTokens::from_vec(new_vec).unwrap()); // TODO: BoundedVec does not have any way to try to push elements. Right now the best way seems to be to just panic if someone tries to add > 255 tokens
-        } else {
-            self.0 = Some(BoxTokens::from([elem.clone()]));
+    /// Returns the element of the collection with a given index (fails if the index is out of
+    /// bounds)
+    pub fn get(&self, index: usize) -> Result<Token, JsValue> {
+        self.0
+            .as_ref()
+            .and_then(|tokens| tokens.get(index))
+            .cloned()
+            .ok_or_else(|| JsValue::from_str(&format!("index {} is out of bounds", index)))
+    }
+
+    /// Adds an elements to the collection (fails if there are 255 tokens already)
+    pub fn add(&mut self, elem: &Token) -> Result<(), JsValue> {
+        match self.0.as_ref() {
+            Some(tokens) => {
+                let mut new_vec = tokens.as_vec().clone();
+                new_vec.push(elem.clone());
+                self.0 = Some(BoxTokens::from_vec(new_vec).map_err(to_js)?);
+            }
+            None => self.0 = Some(BoxTokens::from([elem.clone()])),
         }
+        Ok(())
     }
 }
 
@@ -146,6 +146,12 @@ impl From<chain::transaction::Transaction> for Transaction {
     }
 }
 
+impl From<Transaction> for chain::transaction::Transaction {
+    fn from(t: Transaction) -> Self {
+        t.0
+    }
+}
+
 /// Unsigned (inputs without proofs) transaction
 #[wasm_bindgen]
 #[derive(PartialEq, Debug, Clone)]
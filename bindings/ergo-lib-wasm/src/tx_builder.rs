@@ -43,7 +43,7 @@ impl TxBuilder {
         TxBuilder(ergo_lib::wallet::tx_builder::TxBuilder::new(
             box_selection.clone().into(),
             output_candidates.clone().into(),
-            current_height,
+            current_height.into(),
             fee_amount.clone().into(),
             change_address.clone().into(),
             min_change_value.clone().into(),
@@ -81,7 +81,7 @@ impl TxBuilder {
 
     /// Get current height
     pub fn current_height(&self) -> u32 {
-        self.0.current_height()
+        self.0.current_height().into()
     }
 
     /// Get fee amount
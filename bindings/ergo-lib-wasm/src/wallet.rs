@@ -3,11 +3,15 @@ use ergo_lib::ergotree_ir::chain;
 use wasm_bindgen::prelude::*;
 
 use crate::{
-    box_coll::ErgoBoxes, ergo_state_ctx::ErgoStateContext, error_conversion::to_js,
-    secret_key::SecretKeys, transaction::reduced::ReducedTransaction, transaction::Transaction,
-    transaction::UnsignedTransaction,
+    address::Address, box_coll::ErgoBoxes, ergo_state_ctx::ErgoStateContext,
+    error_conversion::to_js, secret_key::SecretKeys, transaction::reduced::ReducedTransaction,
+    transaction::Transaction, transaction::UnsignedTransaction,
 };
 
+pub mod multi_sig;
+
+use multi_sig::TransactionHintsBag;
+
 /// A collection of secret keys. This simplified signing by matching the secret keys to the correct inputs automatically.
 #[wasm_bindgen]
 pub struct Wallet(ergo_lib::wallet::Wallet);
@@ -16,8 +20,10 @@ pub struct Wallet(ergo_lib::wallet::Wallet);
 impl Wallet {
     /// Create wallet instance loading secret key from mnemonic
     #[wasm_bindgen]
-    pub fn from_mnemonic(_mnemonic_phrase: &str, _mnemonic_pass: &str) -> Wallet {
-        todo!()
+    pub fn from_mnemonic(_mnemonic_phrase: &str, _mnemonic_pass: &str) -> Result<Wallet, JsValue> {
+        Err(JsValue::from_str(
+            "Wallet creation from mnemonic is not implemented yet",
+        ))
     }
 
     /// Create wallet using provided secret key
@@ -51,6 +57,65 @@ impl Wallet {
             .map(Transaction::from)
     }
 
+    /// Generate commitments for the transaction inputs which can be (partially) proven with the
+    /// wallet secrets, for distributed signing. The result holds the secret randomness, share only
+    /// its [`TransactionHintsBag::sanitize_for_sharing`] copy.
+    /// `tx` - transaction to sign
+    /// `boxes_to_spend` - boxes corresponding to [`UnsignedTransaction::inputs`]
+    /// `data_boxes` - boxes corresponding to [`UnsignedTransaction::data_inputs`]
+    #[wasm_bindgen]
+    pub fn generate_commitments(
+        &self,
+        state_context: &ErgoStateContext,
+        tx: &UnsignedTransaction,
+        boxes_to_spend: &ErgoBoxes,
+        data_boxes: &ErgoBoxes,
+    ) -> Result<TransactionHintsBag, JsValue> {
+        let boxes_to_spend: Vec<chain::ergo_box::ErgoBox> = boxes_to_spend.clone().into();
+        let data_boxes: Vec<chain::ergo_box::ErgoBox> = data_boxes.clone().into();
+        let tx_context = ergo_lib::wallet::signing::TransactionContext {
+            spending_tx: tx.clone().into(),
+            boxes_to_spend,
+            data_boxes,
+        };
+        self.0
+            .generate_commitments(tx_context, &state_context.clone().into())
+            .map_err(to_js)
+            .map(TransactionHintsBag::from)
+    }
+
+    /// Sign a transaction using the hints (commitments and proofs of other signers) for the inputs.
+    /// Inputs which cannot be fully proven without other signers get partial proofs.
+    /// `tx` - transaction to sign
+    /// `boxes_to_spend` - boxes corresponding to [`UnsignedTransaction::inputs`]
+    /// `data_boxes` - boxes corresponding to [`UnsignedTransaction::data_inputs`]
+    /// `tx_hints` - hints for the inputs
+    #[wasm_bindgen]
+    pub fn sign_transaction_with_hints(
+        &self,
+        state_context: &ErgoStateContext,
+        tx: &UnsignedTransaction,
+        boxes_to_spend: &ErgoBoxes,
+        data_boxes: &ErgoBoxes,
+        tx_hints: &TransactionHintsBag,
+    ) -> Result<Transaction, JsValue> {
+        let boxes_to_spend: Vec<chain::ergo_box::ErgoBox> = boxes_to_spend.clone().into();
+        let data_boxes: Vec<chain::ergo_box::ErgoBox> = data_boxes.clone().into();
+        let tx_context = ergo_lib::wallet::signing::TransactionContext {
+            spending_tx: tx.clone().into(),
+            boxes_to_spend,
+            data_boxes,
+        };
+        self.0
+            .sign_transaction_with_hints(
+                tx_context,
+                &state_context.clone().into(),
+                &tx_hints.clone().into(),
+            )
+            .map_err(to_js)
+            .map(Transaction::from)
+    }
+
     /// Sign a transaction:
     /// `reduced_tx` - reduced transaction, i.e. unsigned transaction where for each unsigned input
     /// added a script reduction result.
@@ -64,4 +129,29 @@ impl Wallet {
             .map_err(to_js)
             .map(Transaction::from)
     }
+
+    /// Sign an arbitrary message (e.g. a "sign-in" challenge of a dApp) for the address (P2PK or
+    /// P2S with a constant sigma proposition), verify it with [`verify_signature`]
+    #[wasm_bindgen]
+    pub fn sign_message_using_address(
+        &self,
+        address: &Address,
+        message: &[u8],
+    ) -> Result<Vec<u8>, JsValue> {
+        self.0
+            .sign_message_for_address(&address.clone().into(), message)
+            .map_err(to_js)
+    }
+}
+
+/// Verify the signature of an arbitrary message made by the owner of the address (see
+/// [`Wallet::sign_message_using_address`]), false if the signature is not valid
+#[wasm_bindgen]
+pub fn verify_signature(
+    address: &Address,
+    message: &[u8],
+    signature: &[u8],
+) -> Result<bool, JsValue> {
+    ergo_lib::wallet::verifier::verify_signature(&address.clone().into(), message, signature)
+        .map_err(to_js)
 }
@@ -0,0 +1,159 @@
+//! Distributed signing (multi-signature) of transactions
+use ergo_lib::ergotree_interpreter::sigma_protocol::prover::hint;
+use ergo_lib::ergotree_ir::chain;
+use ergo_lib::ergotree_ir::serialization::SigmaSerializable;
+use ergo_lib::ergotree_ir::sigma_protocol::sigma_boolean::SigmaBoolean;
+use ergo_lib::wallet::multi_sig;
+use wasm_bindgen::prelude::*;
+
+extern crate derive_more;
+use derive_more::{From, Into};
+
+use crate::box_coll::ErgoBoxes;
+use crate::ergo_state_ctx::ErgoStateContext;
+use crate::error_conversion::to_js;
+use crate::transaction::Transaction;
+
+/// Collection of sigma propositions (public images of the secrets) to extract hints for
+#[wasm_bindgen]
+#[derive(PartialEq, Debug, Clone)]
+pub struct Propositions(Vec<SigmaBoolean>);
+
+#[wasm_bindgen]
+impl Propositions {
+    /// Create empty collection
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Propositions {
+        Propositions(vec![])
+    }
+
+    /// Parse a serialized sigma proposition (e.g. the public key of a P2PK address) and add it
+    /// to the collection
+    pub fn add_proposition_from_bytes(&mut self, bytes: &[u8]) -> Result<(), JsValue> {
+        let sb = SigmaBoolean::sigma_parse_bytes(bytes).map_err(to_js)?;
+        self.0.push(sb);
+        Ok(())
+    }
+
+    /// Returns the number of elements in the collection
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns true if the collection is empty
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl Default for Propositions {
+    fn default() -> Self {
+        Propositions::new()
+    }
+}
+
+/// Collection of hints to be used by a prover
+#[wasm_bindgen]
+#[derive(PartialEq, Debug, Clone, From, Into)]
+pub struct HintsBag(hint::HintsBag);
+
+#[wasm_bindgen]
+impl HintsBag {
+    /// Bag without hints
+    pub fn empty() -> HintsBag {
+        HintsBag(hint::HintsBag::empty())
+    }
+
+    /// Returns true if the bag holds secret randomness of own commitments, i.e. should not be
+    /// shared (see [`HintsBag::sanitize_for_sharing`])
+    pub fn contains_secrets(&self) -> bool {
+        self.0.contains_secrets()
+    }
+
+    /// Copy of the bag which is safe to share with other signers (without secret randomness)
+    pub fn sanitize_for_sharing(&self) -> HintsBag {
+        HintsBag(self.0.sanitize_for_sharing())
+    }
+
+    /// JSON array of the hints in the Ergo node format (the secret randomness of own commitments
+    /// is included, see [`HintsBag::sanitize_for_sharing`])
+    pub fn to_json(&self) -> Result<String, JsValue> {
+        self.0.to_json().map(|json| json.to_string()).map_err(to_js)
+    }
+
+    /// Parse from the JSON array of the hints in the Ergo node format
+    pub fn from_json(json: &str) -> Result<HintsBag, JsValue> {
+        let json: serde_json::Value = serde_json::from_str(json).map_err(to_js)?;
+        hint::HintsBag::from_json(&json).map(Self).map_err(to_js)
+    }
+}
+
+/// Hints for the prover for every input of a transaction (by input index)
+#[wasm_bindgen]
+#[derive(PartialEq, Debug, Clone, From, Into)]
+pub struct TransactionHintsBag(multi_sig::TransactionHintsBag);
+
+#[wasm_bindgen]
+impl TransactionHintsBag {
+    /// Bag without hints
+    pub fn empty() -> TransactionHintsBag {
+        TransactionHintsBag(multi_sig::TransactionHintsBag::empty())
+    }
+
+    /// Add hints for the input with the given index
+    pub fn add_hints_for_input(&mut self, index: usize, hints_bag: &HintsBag) {
+        self.0.add_hints_for_input(index, hints_bag.0.clone());
+    }
+
+    /// All hints for the input with the given index
+    pub fn all_hints_for_input(&self, index: usize) -> HintsBag {
+        self.0.all_hints_for_input(index).into()
+    }
+
+    /// Copy of the bag which is safe to share with other signers (without secret randomness)
+    pub fn sanitize_for_sharing(&self) -> TransactionHintsBag {
+        TransactionHintsBag(self.0.sanitize_for_sharing())
+    }
+
+    /// JSON in the Ergo node format (`secretHints` and `publicHints` by input index)
+    pub fn to_json(&self) -> Result<String, JsValue> {
+        self.0.to_json().map(|json| json.to_string()).map_err(to_js)
+    }
+
+    /// Parse from the JSON in the Ergo node format
+    pub fn from_json(json: &str) -> Result<TransactionHintsBag, JsValue> {
+        let json: serde_json::Value = serde_json::from_str(json).map_err(to_js)?;
+        multi_sig::TransactionHintsBag::from_json(&json)
+            .map(Self)
+            .map_err(to_js)
+    }
+}
+
+/// Extract hints from a (partially) signed transaction:
+/// `signed_tx` - transaction signed by another signer
+/// `boxes_to_spend` - boxes corresponding to the transaction inputs
+/// `data_boxes` - boxes corresponding to the transaction data inputs
+/// `real_propositions` - public images of the secrets the signer of `signed_tx` proved
+/// `simulated_propositions` - public images the signer of `signed_tx` simulated
+#[wasm_bindgen]
+pub fn extract_hints(
+    signed_tx: &Transaction,
+    state_context: &ErgoStateContext,
+    boxes_to_spend: &ErgoBoxes,
+    data_boxes: &ErgoBoxes,
+    real_propositions: &Propositions,
+    simulated_propositions: &Propositions,
+) -> Result<TransactionHintsBag, JsValue> {
+    let boxes_to_spend: Vec<chain::ergo_box::ErgoBox> = boxes_to_spend.clone().into();
+    let data_boxes: Vec<chain::ergo_box::ErgoBox> = data_boxes.clone().into();
+    multi_sig::extract_hints(
+        &signed_tx.clone().into(),
+        &state_context.clone().into(),
+        boxes_to_spend,
+        data_boxes,
+        &real_propositions.0,
+        &simulated_propositions.0,
+    )
+    .map_err(to_js)
+    .map(TransactionHintsBag::from)
+}
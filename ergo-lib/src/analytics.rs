@@ -0,0 +1,393 @@
+//! Chain analytics: address clustering heuristics over the transaction streams
+//!
+//! [`AddressClustering`] groups the addresses (scripts) likely controlled by the same owner into
+//! clusters, applying the common heuristics to the transactions in the chain order:
+//! - common input ownership: the scripts of the boxes spent by a transaction are controlled by the
+//!   same owner (the owner signed all the inputs);
+//! - one-time change: if a transaction sends to exactly one script never seen before and to other
+//!   already seen scripts, the new script is the change of the owner of the inputs.
+//!
+//! The heuristics are not proofs of ownership (e.g. a transaction can be signed by several
+//! owners), the clusters are the estimates for the analysis.
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+
+use ergotree_ir::chain::address::Address;
+use ergotree_ir::chain::ergo_box::BoxId;
+use ergotree_ir::chain::ergo_box::ErgoBox;
+use ergotree_ir::chain::tx_id::TxId;
+use ergotree_ir::ergo_tree::ErgoTree;
+use ergotree_ir::serialization::SigmaSerializable;
+use ergotree_ir::serialization::SigmaSerializationError;
+use thiserror::Error;
+
+use crate::chain::network::Network;
+use crate::chain::transaction::graph::TxGraph;
+use crate::chain::transaction::graph::TxGraphError;
+use crate::chain::transaction::Transaction;
+
+/// Id of the address cluster: the index of the earliest seen script of the cluster (in the order
+/// the scripts are seen in the added transactions)
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Clone, Copy)]
+pub struct ClusterId(pub usize);
+
+/// Heuristics applied to the transactions
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct ClusteringHeuristics {
+    /// Scripts of the boxes spent by a transaction are in the same cluster
+    pub co_spend: bool,
+    /// The only output to a script never seen before (with other outputs to the seen scripts) is
+    /// the change, its script is in the cluster of the inputs
+    pub one_time_change: bool,
+    /// Apply the heuristics to the P2PK scripts only (contract boxes, e.g. DEX pools, are spent
+    /// along with the boxes of the different owners)
+    pub p2pk_only: bool,
+}
+
+impl Default for ClusteringHeuristics {
+    fn default() -> Self {
+        ClusteringHeuristics {
+            co_spend: true,
+            one_time_change: true,
+            p2pk_only: true,
+        }
+    }
+}
+
+/// Errors of the address clustering
+#[derive(Error, PartialEq, Eq, Debug, Clone)]
+pub enum ClusteringError {
+    /// Box spent by the transaction input is not found
+    #[error("Box {box_id:?} spent by transaction {tx_id:?} is not found")]
+    InputBoxNotFound {
+        /// Transaction id
+        tx_id: TxId,
+        /// Spent box id
+        box_id: BoxId,
+    },
+    /// Script serialization error
+    #[error("Serialization error: {0}")]
+    SerializationError(#[from] SigmaSerializationError),
+    /// Transaction graph error
+    #[error("Transaction graph error: {0}")]
+    TxGraphError(#[from] TxGraphError),
+}
+
+/// Address clusters of the added transactions (union-find over the seen scripts)
+#[derive(PartialEq, Debug, Clone)]
+pub struct AddressClustering {
+    heuristics: ClusteringHeuristics,
+    fee_proposition: ErgoTree,
+    script_indices: HashMap<Vec<u8>, usize>,
+    scripts: Vec<ErgoTree>,
+    parents: Vec<usize>,
+}
+
+impl AddressClustering {
+    /// Create the clustering with no scripts seen
+    pub fn new(heuristics: ClusteringHeuristics) -> Self {
+        AddressClustering {
+            heuristics,
+            fee_proposition: Network::mainnet().fee_proposition,
+            script_indices: HashMap::new(),
+            scripts: Vec::new(),
+            parents: Vec::new(),
+        }
+    }
+
+    /// Applies the heuristics to the transaction (the transactions should be added in the chain
+    /// order for the one-time change heuristic).
+    /// `input_boxes` - boxes spent by the transaction (can have other boxes as well)
+    pub fn add_transaction(
+        &mut self,
+        tx: &Transaction,
+        input_boxes: &[ErgoBox],
+    ) -> Result<(), ClusteringError> {
+        let inputs = tx
+            .inputs
+            .iter()
+            .map(|input| {
+                input_boxes
+                    .iter()
+                    .find(|b| b.box_id() == input.box_id)
+                    .ok_or_else(|| ClusteringError::InputBoxNotFound {
+                        tx_id: tx.id(),
+                        box_id: input.box_id.clone(),
+                    })
+            })
+            .collect::<Result<Vec<&ErgoBox>, ClusteringError>>()?;
+        let mut input_indices = Vec::with_capacity(inputs.len());
+        for b in inputs {
+            let (idx, _) = self.register(&b.ergo_tree)?;
+            if self.is_eligible(&b.ergo_tree) && !input_indices.contains(&idx) {
+                input_indices.push(idx);
+            }
+        }
+        if self.heuristics.co_spend {
+            for idx in input_indices.iter().skip(1) {
+                self.union(input_indices[0], *idx);
+            }
+        }
+        // outputs to the scripts seen for the first time (and if the script is eligible)
+        let mut new_outputs: Vec<(usize, bool)> = Vec::new();
+        let mut seen_outputs = 0;
+        for output in tx.outputs.iter() {
+            if output.ergo_tree == self.fee_proposition {
+                continue;
+            }
+            let (idx, is_new) = self.register(&output.ergo_tree)?;
+            if input_indices.contains(&idx) || new_outputs.iter().any(|(i, _)| *i == idx) {
+                continue;
+            }
+            if is_new {
+                new_outputs.push((idx, self.is_eligible(&output.ergo_tree)));
+            } else {
+                seen_outputs += 1;
+            }
+        }
+        if self.heuristics.one_time_change && seen_outputs > 0 {
+            if let ([(change_idx, true)], Some(input_idx)) =
+                (new_outputs.as_slice(), input_indices.first())
+            {
+                self.union(*input_idx, *change_idx);
+            }
+        }
+        Ok(())
+    }
+
+    /// Applies the heuristics to the transactions of the graph in the order of their dependencies
+    /// (see [`TxGraph::ordered_transactions`]).
+    /// `input_boxes` - boxes created outside of the graph spent by its transactions
+    pub fn add_graph(
+        &mut self,
+        graph: &TxGraph,
+        input_boxes: &[ErgoBox],
+    ) -> Result<(), ClusteringError> {
+        let mut boxes: Vec<ErgoBox> = input_boxes.to_vec();
+        graph
+            .transactions()
+            .iter()
+            .for_each(|tx| boxes.extend(tx.outputs.iter().cloned()));
+        for tx in graph.ordered_transactions()? {
+            self.add_transaction(tx, &boxes)?;
+        }
+        Ok(())
+    }
+
+    /// Cluster of the script, None if the script is not seen in the added transactions
+    pub fn cluster_id(&self, tree: &ErgoTree) -> Option<ClusterId> {
+        let bytes = tree.sigma_serialize_bytes().ok()?;
+        self.script_indices
+            .get(&bytes)
+            .map(|idx| ClusterId(self.find(*idx)))
+    }
+
+    /// Cluster of the address, None if the address script is not seen in the added transactions
+    pub fn cluster_id_of_address(&self, address: &Address) -> Option<ClusterId> {
+        self.cluster_id(&address.script().ok()?)
+    }
+
+    /// Scripts of the clusters (in the order they are seen)
+    pub fn clusters(&self) -> BTreeMap<ClusterId, Vec<ErgoTree>> {
+        let mut res: BTreeMap<ClusterId, Vec<ErgoTree>> = BTreeMap::new();
+        self.scripts.iter().enumerate().for_each(|(idx, tree)| {
+            res.entry(ClusterId(self.find(idx)))
+                .or_default()
+                .push(tree.clone())
+        });
+        res
+    }
+
+    /// Number of the seen scripts
+    pub fn scripts_count(&self) -> usize {
+        self.scripts.len()
+    }
+
+    /// Index of the script, true if the script is seen for the first time
+    fn register(&mut self, tree: &ErgoTree) -> Result<(usize, bool), SigmaSerializationError> {
+        let bytes = tree.sigma_serialize_bytes()?;
+        if let Some(idx) = self.script_indices.get(&bytes) {
+            return Ok((*idx, false));
+        }
+        let idx = self.scripts.len();
+        self.script_indices.insert(bytes, idx);
+        self.scripts.push(tree.clone());
+        self.parents.push(idx);
+        Ok((idx, true))
+    }
+
+    fn is_eligible(&self, tree: &ErgoTree) -> bool {
+        !self.heuristics.p2pk_only
+            || matches!(Address::recreate_from_ergo_tree(tree), Ok(Address::P2Pk(_)))
+    }
+
+    fn find(&self, idx: usize) -> usize {
+        let mut root = idx;
+        while self.parents[root] != root {
+            root = self.parents[root];
+        }
+        root
+    }
+
+    /// Merges the clusters, the earliest seen script is the root
+    fn union(&mut self, a: usize, b: usize) {
+        let (ra, rb) = (self.find(a), self.find(b));
+        let (root, child) = if ra <= rb { (ra, rb) } else { (rb, ra) };
+        self.parents[child] = root;
+        // path compression of the merged nodes
+        for idx in [a, b].iter() {
+            let mut node = *idx;
+            while self.parents[node] != root {
+                let next = self.parents[node];
+                self.parents[node] = root;
+                node = next;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::convert::TryFrom;
+
+    use ergotree_interpreter::sigma_protocol::private_input::DlogProverInput;
+    use ergotree_interpreter::sigma_protocol::prover::ContextExtension;
+    use ergotree_interpreter::sigma_protocol::prover::ProofBytes;
+    use ergotree_ir::chain::ergo_box::box_value::BoxValue;
+    use ergotree_ir::chain::ergo_box::ErgoBoxCandidate;
+    use ergotree_ir::chain::ergo_box::NonMandatoryRegisters;
+    use ergotree_ir::mir::expr::Expr;
+    use sigma_test_util::force_any_val;
+
+    use super::*;
+    use crate::chain::ergo_box::box_builder::ErgoBoxCandidateBuilder;
+    use crate::chain::transaction::input::prover_result::ProverResult;
+    use crate::chain::transaction::Input;
+    use crate::chain::transaction::TxIoVec;
+
+    fn p2pk() -> ErgoTree {
+        Address::P2Pk(DlogProverInput::random().public_image())
+            .script()
+            .unwrap()
+    }
+
+    fn contract() -> ErgoTree {
+        ErgoTree::try_from(Expr::Const(true.into())).unwrap()
+    }
+
+    fn input_box(tree: &ErgoTree) -> ErgoBox {
+        ErgoBox::new(
+            BoxValue::SAFE_USER_MIN,
+            tree.clone(),
+            None,
+            NonMandatoryRegisters::empty(),
+            0,
+            force_any_val::<TxId>(),
+            0,
+        )
+        .unwrap()
+    }
+
+    fn candidate(tree: &ErgoTree) -> ErgoBoxCandidate {
+        ErgoBoxCandidateBuilder::new(BoxValue::SAFE_USER_MIN, tree.clone(), 0)
+            .build()
+            .unwrap()
+    }
+
+    fn tx(inputs: &[&ErgoBox], outputs: &[&ErgoTree]) -> Transaction {
+        let inputs = inputs
+            .iter()
+            .map(|b| {
+                Input::new(
+                    b.box_id(),
+                    ProverResult {
+                        proof: ProofBytes::Empty,
+                        extension: ContextExtension::empty(),
+                    },
+                )
+            })
+            .collect();
+        let mut outputs: Vec<ErgoBoxCandidate> = outputs.iter().map(|t| candidate(t)).collect();
+        outputs.push(candidate(&Network::mainnet().fee_proposition));
+        Transaction::new(
+            TxIoVec::from_vec(inputs).unwrap(),
+            None,
+            TxIoVec::from_vec(outputs).unwrap(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn co_spend_and_change() {
+        let (a, b, c, d, e) = (p2pk(), p2pk(), p2pk(), p2pk(), p2pk());
+        let (box_a, box_b) = (input_box(&a), input_box(&b));
+        let mut clustering = AddressClustering::new(ClusteringHeuristics::default());
+        // a and b are co-spent, the only output (to c) is a payment
+        let tx1 = tx(&[&box_a, &box_b], &[&c]);
+        clustering
+            .add_transaction(&tx1, &[box_a.clone(), box_b.clone()])
+            .unwrap();
+        assert_eq!(clustering.cluster_id(&a), Some(ClusterId(0)));
+        assert_eq!(clustering.cluster_id(&b), Some(ClusterId(0)));
+        assert_eq!(clustering.cluster_id(&c), Some(ClusterId(2)));
+        assert_eq!(
+            clustering.cluster_id(&Network::mainnet().fee_proposition),
+            None
+        );
+        // c pays to the seen a and to the new d, d is the change of c
+        let box_c = tx1.outputs.first().clone();
+        let tx2 = tx(&[&box_c], &[&a, &d]);
+        clustering.add_transaction(&tx2, &[box_c]).unwrap();
+        assert_eq!(clustering.cluster_id(&d), Some(ClusterId(2)));
+        // two new outputs, the change is ambiguous
+        let box_d = tx2.outputs.get(1).unwrap().clone();
+        let tx3 = tx(&[&box_d], &[&e, &p2pk(), &a]);
+        clustering.add_transaction(&tx3, &[box_d]).unwrap();
+        assert_eq!(clustering.cluster_id(&e), Some(ClusterId(4)));
+        assert_eq!(clustering.clusters().len(), 4);
+        assert_eq!(
+            clustering.cluster_id_of_address(&Address::recreate_from_ergo_tree(&b).unwrap()),
+            Some(ClusterId(0))
+        );
+    }
+
+    #[test]
+    fn p2pk_only() {
+        let (a, b) = (p2pk(), contract());
+        let (box_a, box_b) = (input_box(&a), input_box(&b));
+        let transaction = tx(&[&box_a, &box_b], &[&p2pk()]);
+        let boxes = [box_a, box_b];
+        let mut clustering = AddressClustering::new(ClusteringHeuristics::default());
+        clustering.add_transaction(&transaction, &boxes).unwrap();
+        assert_ne!(clustering.cluster_id(&a), clustering.cluster_id(&b));
+        let mut clustering = AddressClustering::new(ClusteringHeuristics {
+            p2pk_only: false,
+            ..ClusteringHeuristics::default()
+        });
+        clustering.add_transaction(&transaction, &boxes).unwrap();
+        assert_eq!(clustering.cluster_id(&a), clustering.cluster_id(&b));
+    }
+
+    #[test]
+    fn graph() {
+        let (a, b, c) = (p2pk(), p2pk(), p2pk());
+        let box_a = input_box(&a);
+        let tx1 = tx(&[&box_a], &[&b]);
+        // spends the output of tx1 along with a box of c
+        let box_c = input_box(&c);
+        let tx2 = tx(&[tx1.outputs.first(), &box_c], &[&a]);
+        let graph = TxGraph::build(vec![tx2.clone(), tx1]).unwrap();
+        let mut clustering = AddressClustering::new(ClusteringHeuristics::default());
+        assert!(matches!(
+            clustering.add_graph(&graph, &[box_c.clone()]),
+            Err(ClusteringError::InputBoxNotFound { .. })
+        ));
+        let mut clustering = AddressClustering::new(ClusteringHeuristics::default());
+        clustering.add_graph(&graph, &[box_a, box_c]).unwrap();
+        assert_eq!(clustering.cluster_id(&b), clustering.cluster_id(&c));
+        assert_ne!(clustering.cluster_id(&a), clustering.cluster_id(&b));
+        assert_eq!(clustering.scripts_count(), 3);
+    }
+}
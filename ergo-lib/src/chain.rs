@@ -3,7 +3,12 @@
 #[cfg(feature = "json")]
 pub mod json;
 
+pub mod block;
 pub mod contract;
 pub mod ergo_box;
 pub mod ergo_state_context;
+pub mod index;
+pub mod network;
+pub mod parameters;
 pub mod transaction;
+pub mod utxo_snapshot;
@@ -0,0 +1,252 @@
+//! Full block: the header and the block sections (transactions, extension, AD proofs)
+
+mod ad_proofs;
+mod block_transactions;
+mod extension;
+
+pub use ad_proofs::*;
+pub use block_transactions::*;
+pub use extension::*;
+
+use ergotree_ir::chain::block_id::BlockId;
+use ergotree_ir::chain::digest32::Digest32;
+use ergotree_ir::chain::header::Header;
+#[cfg(feature = "json")]
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Block with all its sections, as returned by the node API (`/blocks/{headerId}`).
+/// Block sections have Scorex serialization, the header has JSON only.
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Debug, Clone)]
+pub struct FullBlock {
+    /// Block header
+    #[cfg_attr(feature = "json", serde(rename = "header"))]
+    pub header: Header,
+    /// Block transactions
+    #[cfg_attr(feature = "json", serde(rename = "blockTransactions"))]
+    pub block_transactions: BlockTransactions,
+    /// Block extension
+    #[cfg_attr(feature = "json", serde(rename = "extension"))]
+    pub extension: Extension,
+    /// AD proofs (might be missing, e.g. not kept by the node)
+    #[cfg_attr(feature = "json", serde(rename = "adProofs"))]
+    pub ad_proofs: Option<AdProofs>,
+}
+
+/// Errors on the block sections validation against the header
+#[derive(Error, PartialEq, Eq, Debug, Clone)]
+pub enum BlockValidationError {
+    /// Block section belongs to another block
+    #[error("{section} header id {actual:?} differs from the block header id {expected:?}")]
+    HeaderIdMismatch {
+        /// block section name
+        section: &'static str,
+        /// block header id
+        expected: BlockId,
+        /// header id in the block section
+        actual: BlockId,
+    },
+    /// Transactions root calculated from the block transactions differs from the header
+    #[error("transactions root {actual:?} differs from the header {expected:?}")]
+    TransactionsRootMismatch {
+        /// transactions root in the header
+        expected: Digest32,
+        /// transactions root of the block transactions
+        actual: Digest32,
+    },
+    /// Extension digest calculated from the extension fields differs from the header
+    #[error("extension digest {actual:?} differs from the header {expected:?}")]
+    ExtensionRootMismatch {
+        /// extension root in the header
+        expected: Digest32,
+        /// digest of the extension
+        actual: Digest32,
+    },
+    /// AD proofs digest differs from the header
+    #[error("AD proofs digest {actual:?} differs from the header {expected:?}")]
+    AdProofsRootMismatch {
+        /// AD proofs root in the header
+        expected: Digest32,
+        /// digest of the AD proofs
+        actual: Digest32,
+    },
+}
+
+/// Checks that the block sections belong to the block header and match the roots in the header:
+/// transactions root, extension digest and AD proofs digest (if AD proofs are present).
+/// Transactions themselves (scripts, balances, etc.) are not validated.
+pub fn validate_block_transactions(block: &FullBlock) -> Result<(), BlockValidationError> {
+    let header = &block.header;
+    check_header_id(
+        "block transactions",
+        header,
+        &block.block_transactions.header_id,
+    )?;
+    check_header_id("extension", header, &block.extension.header_id)?;
+    let transactions_root = block.block_transactions.transactions_root();
+    if transactions_root != header.transaction_root {
+        return Err(BlockValidationError::TransactionsRootMismatch {
+            expected: header.transaction_root.clone(),
+            actual: transactions_root,
+        });
+    }
+    let extension_root = block.extension.digest();
+    if extension_root != header.extension_root {
+        return Err(BlockValidationError::ExtensionRootMismatch {
+            expected: header.extension_root.clone(),
+            actual: extension_root,
+        });
+    }
+    if let Some(ad_proofs) = &block.ad_proofs {
+        check_header_id("AD proofs", header, &ad_proofs.header_id)?;
+        let ad_proofs_root = ad_proofs.digest();
+        if ad_proofs_root != header.ad_proofs_root {
+            return Err(BlockValidationError::AdProofsRootMismatch {
+                expected: header.ad_proofs_root.clone(),
+                actual: ad_proofs_root,
+            });
+        }
+    }
+    Ok(())
+}
+
+fn check_header_id(
+    section: &'static str,
+    header: &Header,
+    header_id: &BlockId,
+) -> Result<(), BlockValidationError> {
+    if *header_id == header.id {
+        Ok(())
+    } else {
+        Err(BlockValidationError::HeaderIdMismatch {
+            section,
+            expected: header.id.clone(),
+            actual: header_id.clone(),
+        })
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::chain::transaction::Transaction;
+    use sigma_test_util::force_any_val;
+
+    fn valid_block() -> FullBlock {
+        let header_id = BlockId(force_any_val::<Digest32>());
+        let block_transactions = BlockTransactions {
+            header_id: header_id.clone(),
+            block_version: 2,
+            transactions: vec![
+                force_any_val::<Transaction>(),
+                force_any_val::<Transaction>(),
+            ],
+        };
+        let extension = Extension {
+            header_id: header_id.clone(),
+            fields: vec![
+                ExtensionField::new([0, 1], vec![0, 0, 0, 10]).unwrap(),
+                ExtensionField::new([1, 0], vec![1; 33]).unwrap(),
+            ],
+        };
+        let ad_proofs = AdProofs {
+            header_id: header_id.clone(),
+            proof_bytes: vec![1, 2, 3],
+        };
+        let header = Header {
+            version: 2,
+            id: header_id,
+            transaction_root: block_transactions.transactions_root(),
+            extension_root: extension.digest(),
+            ad_proofs_root: ad_proofs.digest(),
+            ..Header::dummy()
+        };
+        FullBlock {
+            header,
+            block_transactions,
+            extension,
+            ad_proofs: Some(ad_proofs),
+        }
+    }
+
+    #[test]
+    fn validate_valid_block() {
+        let block = valid_block();
+        assert_eq!(validate_block_transactions(&block), Ok(()));
+        let without_ad_proofs = FullBlock {
+            ad_proofs: None,
+            ..block
+        };
+        assert_eq!(validate_block_transactions(&without_ad_proofs), Ok(()));
+    }
+
+    #[test]
+    fn validate_invalid_block() {
+        let block = valid_block();
+
+        let mut other_txs = block.clone();
+        other_txs.block_transactions.transactions.pop();
+        assert!(matches!(
+            validate_block_transactions(&other_txs),
+            Err(BlockValidationError::TransactionsRootMismatch { .. })
+        ));
+
+        // witness ids are included since block version 2
+        let mut other_version = block.clone();
+        other_version.block_transactions.block_version = 1;
+        assert!(matches!(
+            validate_block_transactions(&other_version),
+            Err(BlockValidationError::TransactionsRootMismatch { .. })
+        ));
+
+        let mut other_fields = block.clone();
+        other_fields.extension.fields.reverse();
+        assert_eq!(
+            validate_block_transactions(&other_fields),
+            Err(BlockValidationError::ExtensionRootMismatch {
+                expected: block.header.extension_root.clone(),
+                actual: other_fields.extension.digest(),
+            })
+        );
+
+        let mut other_proofs = block.clone();
+        other_proofs.ad_proofs = Some(AdProofs {
+            header_id: block.header.id.clone(),
+            proof_bytes: vec![],
+        });
+        assert!(matches!(
+            validate_block_transactions(&other_proofs),
+            Err(BlockValidationError::AdProofsRootMismatch { .. })
+        ));
+
+        let mut other_header_id = block.clone();
+        other_header_id.extension.header_id = BlockId(Digest32::zero());
+        assert!(matches!(
+            validate_block_transactions(&other_header_id),
+            Err(BlockValidationError::HeaderIdMismatch {
+                section: "extension",
+                ..
+            })
+        ));
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn json_roundtrip() {
+        let block = valid_block();
+        let json = serde_json::to_value(&block).unwrap();
+        assert_eq!(
+            json["extension"]["digest"],
+            serde_json::to_value(block.header.extension_root.clone()).unwrap()
+        );
+        let parsed: FullBlock = serde_json::from_value(json).unwrap();
+        // PoW solution is not in the header JSON
+        assert_eq!(parsed.header.id, block.header.id);
+        assert_eq!(parsed.block_transactions, block.block_transactions);
+        assert_eq!(parsed.extension, block.extension);
+        assert_eq!(parsed.ad_proofs, block.ad_proofs);
+        assert_eq!(validate_block_transactions(&parsed), Ok(()));
+    }
+}
@@ -0,0 +1,86 @@
+//! Block AD proofs section (proofs of the UTXO set AVL+ tree changes made by the block)
+
+use std::io::Read;
+
+use ergotree_ir::chain::block_id::BlockId;
+use ergotree_ir::chain::digest32::blake2b256_hash;
+use ergotree_ir::chain::digest32::Digest32;
+use ergotree_ir::serialization::sigma_byte_reader::SigmaByteRead;
+use ergotree_ir::serialization::sigma_byte_writer::SigmaByteWrite;
+use ergotree_ir::serialization::SigmaParsingError;
+use ergotree_ir::serialization::SigmaSerializable;
+use ergotree_ir::serialization::SigmaSerializeResult;
+#[cfg(feature = "json")]
+use serde::{Deserialize, Serialize};
+
+/// Block AD proofs
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "json",
+    serde(
+        into = "crate::chain::json::block::AdProofsJson",
+        from = "crate::chain::json::block::AdProofsJson"
+    )
+)]
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct AdProofs {
+    /// Id of the block header
+    pub header_id: BlockId,
+    /// Serialized proofs
+    pub proof_bytes: Vec<u8>,
+}
+
+impl AdProofs {
+    /// Hash of the proof bytes, the `adProofsRoot` in the header
+    pub fn digest(&self) -> Digest32 {
+        blake2b256_hash(&self.proof_bytes)
+    }
+}
+
+impl SigmaSerializable for AdProofs {
+    fn sigma_serialize<W: SigmaByteWrite>(&self, w: &mut W) -> SigmaSerializeResult {
+        self.header_id.0.sigma_serialize(w)?;
+        w.put_usize_as_u32_unwrapped(self.proof_bytes.len())?;
+        w.write_all(&self.proof_bytes)?;
+        Ok(())
+    }
+
+    fn sigma_parse<R: SigmaByteRead>(r: &mut R) -> Result<Self, SigmaParsingError> {
+        let header_id = BlockId(Digest32::sigma_parse(r)?);
+        let size = r.get_u32()? as usize;
+        let mut proof_bytes = Vec::new();
+        r.by_ref().take(size as u64).read_to_end(&mut proof_bytes)?;
+        if proof_bytes.len() != size {
+            return Err(SigmaParsingError::ValueOutOfBounds(format!(
+                "AD proofs size {} exceeds the remaining {} bytes",
+                size,
+                proof_bytes.len()
+            )));
+        }
+        Ok(AdProofs {
+            header_id,
+            proof_bytes,
+        })
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use ergotree_ir::serialization::sigma_serialize_roundtrip;
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    proptest! {
+
+        #[test]
+        fn ser_roundtrip(header_id in any::<Digest32>(), proof_bytes in vec(any::<u8>(), 0..100)) {
+            let v = AdProofs {
+                header_id: BlockId(header_id),
+                proof_bytes,
+            };
+            prop_assert_eq![sigma_serialize_roundtrip(&v), v];
+        }
+    }
+}
@@ -0,0 +1,199 @@
+//! Block transactions section
+
+use std::convert::TryFrom;
+
+use ergotree_ir::chain::block_id::BlockId;
+use ergotree_ir::chain::digest32::blake2b256_hash;
+use ergotree_ir::chain::digest32::Digest32;
+use ergotree_ir::chain::merkle::MerkleProof;
+use ergotree_ir::chain::merkle::MerkleTree;
+use ergotree_ir::chain::tx_id::TxId;
+use ergotree_ir::serialization::sigma_byte_reader::SigmaByteRead;
+use ergotree_ir::serialization::sigma_byte_writer::SigmaByteWrite;
+use ergotree_ir::serialization::SigmaParsingError;
+use ergotree_ir::serialization::SigmaSerializable;
+use ergotree_ir::serialization::SigmaSerializeResult;
+#[cfg(feature = "json")]
+use serde::{Deserialize, Serialize};
+
+use crate::chain::transaction::Transaction;
+
+/// Transactions of the block
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Debug, Clone)]
+pub struct BlockTransactions {
+    /// Id of the block header
+    #[cfg_attr(feature = "json", serde(rename = "headerId"))]
+    pub header_id: BlockId,
+    /// Block version (the same as in the header)
+    #[cfg_attr(feature = "json", serde(rename = "blockVersion"))]
+    pub block_version: u8,
+    /// Transactions of the block
+    #[cfg_attr(feature = "json", serde(rename = "transactions"))]
+    pub transactions: Vec<Transaction>,
+}
+
+impl BlockTransactions {
+    /// Maximum number of transactions in a block. For the block version > 1 the version is
+    /// serialized added to this value in place of the transactions count.
+    pub const MAX_TRANSACTIONS_IN_BLOCK: u32 = 10_000_000;
+
+    /// Root hash of the Merkle tree of the transaction ids (followed by the witness ids since the
+    /// block version 2), the `transactionsRoot` in the header
+    pub fn transactions_root(&self) -> Digest32 {
+        self.merkle_tree().root_hash()
+    }
+
+    /// Proof of the transaction membership in the block (valid against the `transactionsRoot` in
+    /// the header), `None` if there is no such transaction in the block
+    pub fn proof_for_tx(&self, tx_id: &TxId) -> Option<MerkleProof> {
+        self.merkle_tree().proof_by_element(tx_id.0.as_ref())
+    }
+
+    fn merkle_tree(&self) -> MerkleTree {
+        let mut leaves: Vec<Vec<u8>> = self
+            .transactions
+            .iter()
+            .map(|tx| tx.id().0.into())
+            .collect();
+        if self.block_version > 1 {
+            leaves.extend(self.transactions.iter().map(|tx| witness_id(tx).into()));
+        }
+        MerkleTree::new(leaves)
+    }
+}
+
+/// Hash of the concatenated proofs of the transaction inputs
+fn witness_id(tx: &Transaction) -> Digest32 {
+    let proofs: Vec<u8> = tx
+        .inputs
+        .iter()
+        .flat_map(|i| Vec::<u8>::from(i.spending_proof.proof.clone()))
+        .collect();
+    blake2b256_hash(&proofs)
+}
+
+impl SigmaSerializable for BlockTransactions {
+    fn sigma_serialize<W: SigmaByteWrite>(&self, w: &mut W) -> SigmaSerializeResult {
+        self.header_id.0.sigma_serialize(w)?;
+        if self.block_version > 1 {
+            w.put_u32(Self::MAX_TRANSACTIONS_IN_BLOCK + self.block_version as u32)?;
+        }
+        w.put_usize_as_u32_unwrapped(self.transactions.len())?;
+        self.transactions
+            .iter()
+            .try_for_each(|tx| tx.sigma_serialize(w))
+    }
+
+    fn sigma_parse<R: SigmaByteRead>(r: &mut R) -> Result<Self, SigmaParsingError> {
+        let header_id = BlockId(Digest32::sigma_parse(r)?);
+        let version_or_count = r.get_u32()?;
+        let (block_version, count) = if version_or_count > Self::MAX_TRANSACTIONS_IN_BLOCK {
+            let block_version = u8::try_from(version_or_count - Self::MAX_TRANSACTIONS_IN_BLOCK)
+                .map_err(|_| {
+                    SigmaParsingError::ValueOutOfBounds(format!(
+                        "invalid block version (serialized as {})",
+                        version_or_count
+                    ))
+                })?;
+            (block_version, r.get_u32()?)
+        } else {
+            (1, version_or_count)
+        };
+        if count > Self::MAX_TRANSACTIONS_IN_BLOCK {
+            return Err(SigmaParsingError::ValueOutOfBounds(format!(
+                "transactions count {} exceeds {}",
+                count,
+                Self::MAX_TRANSACTIONS_IN_BLOCK
+            )));
+        }
+        let mut transactions = Vec::new();
+        for _ in 0..count {
+            transactions.push(Transaction::sigma_parse(r)?);
+        }
+        Ok(BlockTransactions {
+            header_id,
+            block_version,
+            transactions,
+        })
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use ergotree_ir::chain::merkle::merkle_tree_root;
+    use ergotree_ir::serialization::sigma_serialize_roundtrip;
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    proptest! {
+
+        #![proptest_config(ProptestConfig::with_cases(16))]
+
+        #[test]
+        fn ser_roundtrip(
+            header_id in any::<Digest32>(),
+            block_version in 1u8..4,
+            transactions in vec(any::<Transaction>(), 0..3),
+        ) {
+            let v = BlockTransactions {
+                header_id: BlockId(header_id),
+                block_version,
+                transactions,
+            };
+            prop_assert_eq![sigma_serialize_roundtrip(&v), v];
+        }
+    }
+
+    #[test]
+    fn witness_ids_since_version_2() {
+        let v1 = BlockTransactions {
+            header_id: BlockId(Digest32::zero()),
+            block_version: 1,
+            transactions: vec![],
+        };
+        assert_eq!(v1.transactions_root(), Digest32::zero());
+        let tx = sigma_test_util::force_any_val::<Transaction>();
+        let v1 = BlockTransactions {
+            transactions: vec![tx.clone()],
+            ..v1
+        };
+        let v2 = BlockTransactions {
+            block_version: 2,
+            ..v1.clone()
+        };
+        assert_eq!(
+            v1.transactions_root(),
+            merkle_tree_root(&[tx.id().0.into()])
+        );
+        assert_eq!(
+            v2.transactions_root(),
+            merkle_tree_root(&[tx.id().0.into(), witness_id(&tx).into()])
+        );
+    }
+
+    #[test]
+    fn tx_proof() {
+        let block_transactions = BlockTransactions {
+            header_id: BlockId(Digest32::zero()),
+            block_version: 2,
+            transactions: vec![
+                sigma_test_util::force_any_val::<Transaction>(),
+                sigma_test_util::force_any_val::<Transaction>(),
+                sigma_test_util::force_any_val::<Transaction>(),
+            ],
+        };
+        let root = block_transactions.transactions_root();
+        for tx in &block_transactions.transactions {
+            let proof = block_transactions.proof_for_tx(&tx.id()).unwrap();
+            assert_eq!(proof.leaf_data, Vec::<u8>::from(tx.id().0));
+            assert!(proof.valid(&root));
+        }
+        assert_eq!(
+            block_transactions.proof_for_tx(&TxId(Digest32::zero())),
+            None
+        );
+    }
+}
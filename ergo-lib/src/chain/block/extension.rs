@@ -0,0 +1,229 @@
+//! Block extension section (key-value storage of the system parameters, interlinks, etc.)
+
+#[cfg(feature = "json")]
+use std::convert::TryFrom;
+#[cfg(feature = "json")]
+use std::convert::TryInto;
+
+#[cfg(feature = "json")]
+use ergotree_ir::chain::base16_bytes::Base16DecodedBytes;
+use ergotree_ir::chain::block_id::BlockId;
+use ergotree_ir::chain::digest32::Digest32;
+use ergotree_ir::chain::merkle::merkle_tree_root;
+use ergotree_ir::serialization::sigma_byte_reader::SigmaByteRead;
+use ergotree_ir::serialization::sigma_byte_writer::SigmaByteWrite;
+use ergotree_ir::serialization::SigmaParsingError;
+use ergotree_ir::serialization::SigmaSerializable;
+use ergotree_ir::serialization::SigmaSerializeResult;
+#[cfg(feature = "json")]
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Field of the block extension
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "json",
+    serde(
+        into = "(Base16DecodedBytes, Base16DecodedBytes)",
+        try_from = "(Base16DecodedBytes, Base16DecodedBytes)"
+    )
+)]
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct ExtensionField {
+    key: [u8; ExtensionField::KEY_SIZE],
+    value: Vec<u8>,
+}
+
+/// Errors on ExtensionField creation
+#[derive(Error, PartialEq, Eq, Debug, Clone)]
+pub enum ExtensionFieldError {
+    /// Key is not 2 bytes long
+    #[error("Extension field key size is {0} bytes, expected 2")]
+    InvalidKeySize(usize),
+    /// Value is longer than 64 bytes
+    #[error("Extension field value size {0} exceeds 64 bytes")]
+    ValueTooLong(usize),
+}
+
+impl ExtensionField {
+    /// Key size in bytes
+    pub const KEY_SIZE: usize = 2;
+    /// Maximum value size in bytes
+    pub const MAX_VALUE_SIZE: usize = 64;
+
+    /// Create new field, fails if the value is longer than [`ExtensionField::MAX_VALUE_SIZE`]
+    pub fn new(
+        key: [u8; ExtensionField::KEY_SIZE],
+        value: Vec<u8>,
+    ) -> Result<ExtensionField, ExtensionFieldError> {
+        if value.len() > Self::MAX_VALUE_SIZE {
+            return Err(ExtensionFieldError::ValueTooLong(value.len()));
+        }
+        Ok(ExtensionField { key, value })
+    }
+
+    /// Field key
+    pub fn key(&self) -> [u8; ExtensionField::KEY_SIZE] {
+        self.key
+    }
+
+    /// Field value
+    pub fn value(&self) -> &[u8] {
+        &self.value
+    }
+}
+
+#[cfg(feature = "json")]
+impl From<ExtensionField> for (Base16DecodedBytes, Base16DecodedBytes) {
+    fn from(f: ExtensionField) -> Self {
+        (
+            Base16DecodedBytes(f.key.to_vec()),
+            Base16DecodedBytes(f.value),
+        )
+    }
+}
+
+#[cfg(feature = "json")]
+impl TryFrom<(Base16DecodedBytes, Base16DecodedBytes)> for ExtensionField {
+    type Error = ExtensionFieldError;
+
+    fn try_from(
+        (key, value): (Base16DecodedBytes, Base16DecodedBytes),
+    ) -> Result<Self, Self::Error> {
+        let key_size = key.0.len();
+        let key = key
+            .0
+            .try_into()
+            .map_err(|_| ExtensionFieldError::InvalidKeySize(key_size))?;
+        ExtensionField::new(key, value.0)
+    }
+}
+
+/// Block extension
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "json",
+    serde(
+        into = "crate::chain::json::block::ExtensionJson",
+        from = "crate::chain::json::block::ExtensionJson"
+    )
+)]
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Extension {
+    /// Id of the block header
+    pub header_id: BlockId,
+    /// Fields of the extension
+    pub fields: Vec<ExtensionField>,
+}
+
+impl Extension {
+    /// Root hash of the Merkle tree of the fields (each field as `key size ++ key ++ value`), the
+    /// `extensionHash` in the header
+    pub fn digest(&self) -> Digest32 {
+        let leaves: Vec<Vec<u8>> = self
+            .fields
+            .iter()
+            .map(|f| {
+                let mut leaf = vec![ExtensionField::KEY_SIZE as u8];
+                leaf.extend_from_slice(&f.key);
+                leaf.extend_from_slice(&f.value);
+                leaf
+            })
+            .collect();
+        merkle_tree_root(&leaves)
+    }
+}
+
+impl SigmaSerializable for Extension {
+    fn sigma_serialize<W: SigmaByteWrite>(&self, w: &mut W) -> SigmaSerializeResult {
+        self.header_id.0.sigma_serialize(w)?;
+        w.put_usize_as_u16_unwrapped(self.fields.len())?;
+        self.fields.iter().try_for_each(|f| {
+            w.write_all(&f.key)?;
+            w.put_u8(f.value.len() as u8)?;
+            w.write_all(&f.value)?;
+            Ok(())
+        })
+    }
+
+    fn sigma_parse<R: SigmaByteRead>(r: &mut R) -> Result<Self, SigmaParsingError> {
+        let header_id = BlockId(Digest32::sigma_parse(r)?);
+        let count = r.get_u16()?;
+        let mut fields = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            let mut key = [0u8; ExtensionField::KEY_SIZE];
+            r.read_exact(&mut key)?;
+            let mut value = vec![0u8; r.get_u8()? as usize];
+            r.read_exact(&mut value)?;
+            fields.push(
+                ExtensionField::new(key, value)
+                    .map_err(|e| SigmaParsingError::ValueOutOfBounds(e.to_string()))?,
+            );
+        }
+        Ok(Extension { header_id, fields })
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use ergotree_ir::serialization::sigma_serialize_roundtrip;
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    fn field_strategy() -> impl Strategy<Value = ExtensionField> {
+        (
+            any::<[u8; 2]>(),
+            vec(any::<u8>(), 0..=ExtensionField::MAX_VALUE_SIZE),
+        )
+            .prop_map(|(key, value)| ExtensionField::new(key, value).unwrap())
+    }
+
+    proptest! {
+
+        #[test]
+        fn ser_roundtrip(header_id in any::<Digest32>(), fields in vec(field_strategy(), 0..10)) {
+            let v = Extension {
+                header_id: BlockId(header_id),
+                fields,
+            };
+            prop_assert_eq![sigma_serialize_roundtrip(&v), v];
+        }
+    }
+
+    #[test]
+    fn value_too_long() {
+        assert_eq!(
+            ExtensionField::new([0, 1], vec![0; 65]),
+            Err(ExtensionFieldError::ValueTooLong(65))
+        );
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn parse_json() {
+        let json = r#"{
+            "headerId": "4caa17e62fe66ba7bd69597afdc996ae35b1ff12e0ba90c22ff288a4de10e91b",
+            "digest": "3f91f3c680beb26615fdec251aee3f81aaf5a02740806c167c0f3c929471df44",
+            "fields": [
+                ["0100", "01b0244dfc267baca974a4caee06120321562784303a8a688976ae56170e4d175b"],
+                ["0101", "0000000a"]
+            ]
+        }"#;
+        let ext: Extension = serde_json::from_str(json).unwrap();
+        assert_eq!(ext.fields.len(), 2);
+        assert_eq!(ext.fields[1].key(), [1, 1]);
+        assert_eq!(ext.fields[1].value(), &[0, 0, 0, 10]);
+        let encoded = serde_json::to_value(&ext).unwrap();
+        assert_eq!(
+            encoded["digest"],
+            serde_json::to_value(ext.digest()).unwrap()
+        );
+        let decoded: Extension = serde_json::from_value(encoded).unwrap();
+        assert_eq!(decoded, ext);
+
+        let invalid_key = json.replace("\"0101\"", "\"01\"");
+        assert!(serde_json::from_str::<Extension>(&invalid_key).is_err());
+    }
+}
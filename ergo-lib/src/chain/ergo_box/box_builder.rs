@@ -4,11 +4,13 @@ use std::collections::HashMap;
 use std::convert::{TryFrom, TryInto};
 
 use ergotree_ir::chain::ergo_box::box_value::BoxValue;
+use ergotree_ir::chain::ergo_box::box_value::BoxValueError;
 use ergotree_ir::chain::ergo_box::BoxTokens;
 use ergotree_ir::chain::ergo_box::ErgoBoxCandidate;
 use ergotree_ir::chain::ergo_box::NonMandatoryRegisterId;
 use ergotree_ir::chain::ergo_box::NonMandatoryRegisters;
 use ergotree_ir::chain::ergo_box::NonMandatoryRegistersError;
+use ergotree_ir::chain::ergo_box::RegisterValue;
 use ergotree_ir::chain::token::Token;
 use ergotree_ir::ergo_tree::ErgoTree;
 use ergotree_ir::mir::constant::Constant;
@@ -45,6 +47,9 @@ pub enum ErgoBoxCandidateBuilderError {
     /// When creating a Box, it can either have no tokens, or 1-255 tokens
     #[error("Too many Tokens. The maximum number of Tokens in an Ergo Box is 255")]
     TooManyTokensError,
+    /// Box value (e.g. the minimal value for the box size) is out of bounds
+    #[error("Box value error: {0}")]
+    BoxValueError(#[from] BoxValueError),
 }
 
 /// Minted token info (id, amount, name, desc)
@@ -63,7 +68,7 @@ pub struct ErgoBoxCandidateBuilder {
     value: BoxValue,
     ergo_tree: ErgoTree,
     tokens: Vec<Token>,
-    additional_registers: HashMap<NonMandatoryRegisterId, Constant>,
+    additional_registers: HashMap<NonMandatoryRegisterId, RegisterValue>,
     creation_height: u32,
     minting_token: Option<MintingToken>,
 }
@@ -114,20 +119,28 @@ impl ErgoBoxCandidateBuilder {
     /// Calculate minimal box value for the current box serialized size(in bytes)
     pub fn calc_min_box_value(&self) -> Result<BoxValue, ErgoBoxCandidateBuilderError> {
         let box_size_bytes = self.calc_box_size_bytes()?;
-        Ok(
-            BoxValue::try_from(box_size_bytes as i64 * BoxValue::MIN_VALUE_PER_BOX_BYTE as i64)
-                .unwrap(),
-        )
+        Ok(BoxValue::try_from(
+            box_size_bytes as i64 * BoxValue::MIN_VALUE_PER_BOX_BYTE as i64,
+        )?)
     }
 
     /// Set register with a given id (R4-R9) to the given value
     pub fn set_register_value(&mut self, register_id: NonMandatoryRegisterId, value: Constant) {
+        self.additional_registers.insert(register_id, value.into());
+    }
+
+    /// Set register with a given id (R4-R9) to the given parsed or raw value (e.g. copied from
+    /// another box)
+    pub fn set_register(&mut self, register_id: NonMandatoryRegisterId, value: RegisterValue) {
         self.additional_registers.insert(register_id, value);
     }
 
     /// Returns register value for the given register id (R4-R9), or None if the register is empty
+    /// or holds a raw (unparsed) value
     pub fn register_value(&self, register_id: &NonMandatoryRegisterId) -> Option<&Constant> {
-        self.additional_registers.get(register_id)
+        self.additional_registers
+            .get(register_id)
+            .and_then(RegisterValue::as_constant)
     }
 
     /// Delete register value(make register empty) for the given register id (R4-R9)
@@ -202,23 +215,18 @@ impl ErgoBoxCandidateBuilder {
             tokens.push(minting_token.token);
             additional_registers.insert(
                 NonMandatoryRegisterId::R4,
-                minting_token.name.as_bytes().to_vec().into(),
+                Constant::from(minting_token.name.as_bytes().to_vec()).into(),
             );
             additional_registers.insert(
                 NonMandatoryRegisterId::R5,
-                minting_token.desc.as_bytes().to_vec().into(),
+                Constant::from(minting_token.desc.as_bytes().to_vec()).into(),
             );
             additional_registers.insert(
                 NonMandatoryRegisterId::R6,
-                minting_token
-                    .num_decimals
-                    .to_string()
-                    .as_bytes()
-                    .to_vec()
-                    .into(),
+                Constant::from(minting_token.num_decimals.to_string().as_bytes().to_vec()).into(),
             );
         }
-        let regs = NonMandatoryRegisters::new(additional_registers)?;
+        let regs = NonMandatoryRegisters::try_from(additional_registers)?;
         let tokens = if tokens.is_empty() {
             None
         } else {
@@ -235,9 +243,8 @@ impl ErgoBoxCandidateBuilder {
             creation_height: self.creation_height,
         };
         let box_size_bytes = b.sigma_serialize_bytes()?.len();
-        let min_box_value: BoxValue = (box_size_bytes as i64 * self.min_value_per_byte as i64)
-            .try_into()
-            .unwrap();
+        let min_box_value: BoxValue =
+            (box_size_bytes as i64 * self.min_value_per_byte as i64).try_into()?;
         if self.value >= min_box_value {
             Ok(b)
         } else {
@@ -254,7 +261,24 @@ impl ErgoBoxCandidateBuilder {
     }
 }
 
+/// Registers are copied by their ids, the raw (unparsed) values are kept as is
+impl From<ErgoBoxCandidate> for ErgoBoxCandidateBuilder {
+    fn from(b: ErgoBoxCandidate) -> Self {
+        let additional_registers = b.additional_registers.into();
+        ErgoBoxCandidateBuilder {
+            min_value_per_byte: BoxValue::MIN_VALUE_PER_BOX_BYTE,
+            value: b.value,
+            ergo_tree: b.ergo_tree,
+            tokens: b.tokens.map(|t| t.as_vec().clone()).unwrap_or_default(),
+            additional_registers,
+            creation_height: b.creation_height,
+            minting_token: None,
+        }
+    }
+}
+
 #[cfg(test)]
+#[allow(clippy::unwrap_used)]
 mod tests {
 
     use ergotree_ir::base16_str::Base16Str;
@@ -448,4 +472,18 @@ mod tests {
         let out_box = box_builder.build().unwrap();
         assert_eq!(out_box.tokens.unwrap().first(), &token);
     }
+
+    #[test]
+    fn test_from_candidate_keeps_raw_registers() {
+        let mut builder =
+            ErgoBoxCandidateBuilder::new(BoxValue::SAFE_USER_MIN, force_any_val::<ErgoTree>(), 1);
+        builder.set_register_value(R4, 1i32.into());
+        builder.set_register(R5, RegisterValue::Raw(vec![0x60, 0x01]));
+        builder.set_register_value(R6, 2i64.into());
+        let candidate = builder.build().unwrap();
+        let copy = ErgoBoxCandidateBuilder::from(candidate.clone());
+        assert_eq!(copy.register_value(&R5), None);
+        assert_eq!(copy.register_value(&R6), Some(&2i64.into()));
+        assert_eq!(copy.build().unwrap(), candidate);
+    }
 }
@@ -1,8 +1,12 @@
 //! Blockchain state
 use std::convert::TryInto;
 
+use ergo_units::Height;
 use ergotree_ir::chain::header::Header;
 use ergotree_ir::chain::preheader::PreHeader;
+use thiserror::Error;
+
+use super::parameters::Parameters;
 
 /// Blockchain state (last headers, etc.)
 #[derive(PartialEq, Eq, Debug, Clone)]
@@ -12,10 +16,56 @@ pub struct ErgoStateContext {
     pub pre_header: PreHeader,
     /// Fixed number of last block headers in descending order (first header is the newest one)
     pub headers: [Header; 10],
+    /// Blockchain parameters (the mainnet launch ones unless set)
+    pub parameters: Parameters,
+}
+
+/// Errors on ErgoStateContext construction
+#[derive(Error, PartialEq, Eq, Debug, Clone)]
+pub enum ErgoStateContextError {
+    /// Unexpected number of the last block headers
+    #[error("expected 10 last block headers, got {0}")]
+    InvalidHeadersCount(usize),
 }
 
 impl ErgoStateContext {
+    /// Create new instance from the pre-header and the last 10 block headers (newest first)
+    pub fn new(pre_header: PreHeader, headers: [Header; 10]) -> ErgoStateContext {
+        ErgoStateContext {
+            pre_header,
+            headers,
+            parameters: Parameters::default(),
+        }
+    }
+
+    /// Set the blockchain parameters
+    pub fn with_parameters(self, parameters: Parameters) -> ErgoStateContext {
+        ErgoStateContext { parameters, ..self }
+    }
+
+    /// Create new instance from the last 10 block headers in any order (e.g. node's
+    /// `/blocks/lastHeaders/10` returns them in ascending order). Headers are sorted newest
+    /// first and the pre-header is of the next block (the one the transactions are going to be
+    /// in, as the node's mempool validation sees it): its parent is the newest header and its
+    /// height is the next one, the other fields are taken from the newest header.
+    pub fn from_last_headers(
+        mut headers: Vec<Header>,
+    ) -> Result<ErgoStateContext, ErgoStateContextError> {
+        headers.sort_by(|a, b| b.height.cmp(&a.height));
+        let headers: [Header; 10] = headers
+            .try_into()
+            .map_err(|hs: Vec<Header>| ErgoStateContextError::InvalidHeadersCount(hs.len()))?;
+        let newest = &headers[0];
+        let pre_header = PreHeader {
+            parent_id: newest.id.clone(),
+            height: Height::new(newest.height.as_u32().saturating_add(1)),
+            ..PreHeader::from(newest.clone())
+        };
+        Ok(ErgoStateContext::new(pre_header, headers))
+    }
+
     /// Dummy instance intended for tests where actual values are not used
+    #[allow(clippy::expect_used)]
     pub fn dummy() -> ErgoStateContext {
         let headers = vec![Header::dummy(); 10]
             .try_into()
@@ -23,6 +73,44 @@ impl ErgoStateContext {
         ErgoStateContext {
             pre_header: PreHeader::dummy(),
             headers,
+            parameters: Parameters::default(),
         }
     }
 }
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use sigma_test_util::force_any_val;
+
+    #[test]
+    fn test_from_last_headers() {
+        let headers: Vec<Header> = (1..=10u32)
+            .map(|height| Header {
+                height: height.into(),
+                ..force_any_val::<Header>()
+            })
+            .collect();
+        let ctx = ErgoStateContext::from_last_headers(headers.clone()).unwrap();
+        assert_eq!(ctx.headers[0].height, 10u32.into());
+        assert_eq!(ctx.headers[9].height, 1u32.into());
+        assert_eq!(ctx.pre_header.parent_id, headers[9].id);
+        assert_eq!(ctx.pre_header.height, 11u32.into());
+        assert_eq!(ctx.pre_header.version, headers[9].version);
+        assert_eq!(ctx.pre_header.timestamp, headers[9].timestamp);
+        assert_eq!(ctx.pre_header.n_bits, headers[9].n_bits);
+        assert_eq!(ctx.pre_header.miner_pk, headers[9].miner_pk);
+        assert_eq!(ctx.pre_header.votes, headers[9].votes);
+        assert_eq!(
+            ErgoStateContext::from_last_headers(headers[1..].to_vec()),
+            Err(ErgoStateContextError::InvalidHeadersCount(9))
+        );
+        assert_eq!(ctx.parameters, Parameters::default());
+        let parameters = Parameters::new(vec![(1, 2)].into_iter().collect());
+        assert_eq!(
+            ctx.with_parameters(parameters.clone()).parameters,
+            parameters
+        );
+    }
+}
@@ -0,0 +1,139 @@
+//! Chain index: headers, transactions and boxes of the best chain, for wallets, explorers and
+//! other tooling written against the sigma-rust types.
+//!
+//! [`ChainIndex`] is the read access, [`ChainIndexWriter`] applies (and rolls back) the blocks.
+//! Storage backends implement both, [`InMemoryChainIndex`] is the reference implementation.
+
+mod in_memory;
+
+pub use in_memory::*;
+
+use ergo_units::Height;
+use ergotree_ir::chain::block_id::BlockId;
+use ergotree_ir::chain::digest32::blake2b256_hash;
+use ergotree_ir::chain::digest32::Digest32;
+use ergotree_ir::chain::ergo_box::BoxId;
+use ergotree_ir::chain::ergo_box::ErgoBox;
+use ergotree_ir::chain::header::Header;
+use ergotree_ir::chain::tx_id::TxId;
+use ergotree_ir::ergo_tree::ErgoTree;
+use ergotree_ir::serialization::SigmaSerializable;
+use ergotree_ir::serialization::SigmaSerializationError;
+use thiserror::Error;
+
+use crate::chain::block::FullBlock;
+use crate::chain::transaction::Transaction;
+
+/// Box with the block height it was created at and the transaction spending it (if any)
+#[derive(PartialEq, Debug, Clone)]
+pub struct IndexedBox {
+    /// Box
+    pub ergo_box: ErgoBox,
+    /// Height of the block with the transaction which created the box
+    pub inclusion_height: Height,
+    /// Transaction which spent the box, None if the box is unspent
+    pub spending_tx_id: Option<TxId>,
+}
+
+/// Transaction with the block it is included in
+#[derive(PartialEq, Debug, Clone)]
+pub struct IndexedTransaction {
+    /// Transaction
+    pub transaction: Transaction,
+    /// Id of the block with the transaction
+    pub header_id: BlockId,
+    /// Height of the block with the transaction
+    pub inclusion_height: Height,
+}
+
+/// Chain index errors
+#[derive(Error, PartialEq, Eq, Debug, Clone)]
+pub enum ChainIndexError {
+    /// Block is not the next block of the indexed chain
+    #[error("block height {actual} is not the next height {expected}")]
+    UnexpectedHeight {
+        /// height of the next block
+        expected: Height,
+        /// block height
+        actual: Height,
+    },
+    /// Block parent is not the best block of the indexed chain
+    #[error("block parent {actual:?} is not the best block {expected:?}")]
+    ParentMismatch {
+        /// id of the best block
+        expected: BlockId,
+        /// parent id of the block
+        actual: BlockId,
+    },
+    /// Box serialization error (ErgoTree hash calculation)
+    #[error("serialization error: {0}")]
+    SigmaSerializationError(#[from] SigmaSerializationError),
+    /// Storage backend error
+    #[error("storage error: {0}")]
+    Storage(String),
+}
+
+/// Read access to the indexed chain
+pub trait ChainIndex {
+    /// Box by id (spent or unspent), None if the box is not indexed
+    fn get_box(&self, box_id: &BoxId) -> Result<Option<IndexedBox>, ChainIndexError>;
+
+    /// Transaction by id, None if the transaction is not indexed
+    fn get_tx(&self, tx_id: &TxId) -> Result<Option<IndexedTransaction>, ChainIndexError>;
+
+    /// Headers of the blocks with heights in `from..=to` (in the order of heights), only the
+    /// indexed ones
+    fn get_headers_range(&self, from: Height, to: Height) -> Result<Vec<Header>, ChainIndexError>;
+
+    /// Unspent boxes guarded by the ErgoTree with the given hash (see [`ergo_tree_hash`]), the order
+    /// of the boxes is not specified
+    fn unspent_by_ergotree_hash(
+        &self,
+        ergo_tree_hash: &Digest32,
+    ) -> Result<Vec<ErgoBox>, ChainIndexError>;
+
+    /// Header of the last applied block, None if no blocks are indexed
+    fn best_header(&self) -> Result<Option<Header>, ChainIndexError>;
+}
+
+/// Updating of the index with the blocks of the best chain
+pub trait ChainIndexWriter {
+    /// Index the block transactions. The block must be the next one of the indexed chain (the
+    /// height and the parent id are checked), unless no blocks are indexed yet.
+    /// Inputs spending the boxes which are not indexed (created before the first indexed block)
+    /// are skipped.
+    fn apply_block(&mut self, block: &FullBlock) -> Result<(), ChainIndexError>;
+
+    /// Remove the last applied block (e.g. on a chain reorganization): the boxes it created are
+    /// removed and the boxes it spent are unspent again. Returns the header of the removed block,
+    /// None if no blocks are indexed.
+    fn rollback(&mut self) -> Result<Option<Header>, ChainIndexError>;
+}
+
+/// Hash of the serialized ErgoTree, the key of [`ChainIndex::unspent_by_ergotree_hash`]
+pub fn ergo_tree_hash(tree: &ErgoTree) -> Result<Digest32, SigmaSerializationError> {
+    Ok(blake2b256_hash(&tree.sigma_serialize_bytes()?))
+}
+
+/// Checks that the block is the next block of the chain with the given best block
+pub(crate) fn check_next_block(
+    best_header: Option<&Header>,
+    block: &FullBlock,
+) -> Result<(), ChainIndexError> {
+    if let Some(best) = best_header {
+        let expected = Height::new(best.height.as_u32().saturating_add(1));
+        if block.header.height != expected {
+            return Err(ChainIndexError::UnexpectedHeight {
+                expected,
+                actual: block.header.height,
+            });
+        }
+        if block.header.parent_id != best.id {
+            return Err(ChainIndexError::ParentMismatch {
+                expected: best.id.clone(),
+                actual: block.header.parent_id.clone(),
+            });
+        }
+    }
+    Ok(())
+}
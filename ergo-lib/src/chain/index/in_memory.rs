@@ -0,0 +1,375 @@
+//! In-memory chain index
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+
+use ergo_units::Height;
+use ergotree_ir::chain::digest32::Digest32;
+use ergotree_ir::chain::ergo_box::BoxId;
+use ergotree_ir::chain::ergo_box::ErgoBox;
+use ergotree_ir::chain::header::Header;
+use ergotree_ir::chain::tx_id::TxId;
+use indexmap::IndexSet;
+
+use crate::chain::block::FullBlock;
+
+use super::check_next_block;
+use super::ergo_tree_hash;
+use super::ChainIndex;
+use super::ChainIndexError;
+use super::ChainIndexWriter;
+use super::IndexedBox;
+use super::IndexedTransaction;
+
+/// Reference [`ChainIndex`] implementation keeping everything in memory (e.g. for tests or a
+/// short chain of a devnet)
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct InMemoryChainIndex {
+    headers: BTreeMap<Height, Header>,
+    block_txs: BTreeMap<Height, Vec<TxId>>,
+    txs: HashMap<TxId, IndexedTransaction>,
+    // boxes with their ErgoTree hashes
+    boxes: HashMap<BoxId, (IndexedBox, Digest32)>,
+    unspent_by_tree: HashMap<Digest32, IndexSet<BoxId>>,
+}
+
+impl InMemoryChainIndex {
+    /// Empty index
+    pub fn new() -> Self {
+        InMemoryChainIndex::default()
+    }
+
+    /// Index bootstrapped from the UTXO set snapshot made after the block with the given header
+    /// (see [`crate::chain::utxo_snapshot::UtxoSnapshot::verified_boxes`]), the next applied block
+    /// is the one after the header. The inclusion heights of the snapshot boxes are unknown, their
+    /// creation heights are used instead.
+    pub fn from_utxo_snapshot(
+        header: Header,
+        boxes: Vec<ErgoBox>,
+    ) -> Result<Self, ChainIndexError> {
+        let mut index = InMemoryChainIndex::new();
+        for b in boxes {
+            let tree_hash = ergo_tree_hash(&b.ergo_tree)?;
+            let box_id = b.box_id();
+            let indexed = IndexedBox {
+                inclusion_height: Height::new(b.creation_height),
+                ergo_box: b,
+                spending_tx_id: None,
+            };
+            index
+                .boxes
+                .insert(box_id.clone(), (indexed, tree_hash.clone()));
+            index.add_unspent(tree_hash, box_id);
+        }
+        index.headers.insert(header.height, header);
+        Ok(index)
+    }
+
+    fn add_unspent(&mut self, tree_hash: Digest32, box_id: BoxId) {
+        self.unspent_by_tree
+            .entry(tree_hash)
+            .or_insert_with(IndexSet::new)
+            .insert(box_id);
+    }
+
+    fn remove_unspent(&mut self, tree_hash: &Digest32, box_id: &BoxId) {
+        if let Some(unspent) = self.unspent_by_tree.get_mut(tree_hash) {
+            unspent.shift_remove(box_id);
+            if unspent.is_empty() {
+                self.unspent_by_tree.remove(tree_hash);
+            }
+        }
+    }
+}
+
+impl ChainIndex for InMemoryChainIndex {
+    fn get_box(&self, box_id: &BoxId) -> Result<Option<IndexedBox>, ChainIndexError> {
+        Ok(self.boxes.get(box_id).map(|(b, _)| b.clone()))
+    }
+
+    fn get_tx(&self, tx_id: &TxId) -> Result<Option<IndexedTransaction>, ChainIndexError> {
+        Ok(self.txs.get(tx_id).cloned())
+    }
+
+    fn get_headers_range(&self, from: Height, to: Height) -> Result<Vec<Header>, ChainIndexError> {
+        if from > to {
+            return Ok(vec![]);
+        }
+        Ok(self
+            .headers
+            .range(from..=to)
+            .map(|(_, h)| h.clone())
+            .collect())
+    }
+
+    fn unspent_by_ergotree_hash(
+        &self,
+        ergo_tree_hash: &Digest32,
+    ) -> Result<Vec<ErgoBox>, ChainIndexError> {
+        Ok(self
+            .unspent_by_tree
+            .get(ergo_tree_hash)
+            .map(|unspent| {
+                unspent
+                    .iter()
+                    .filter_map(|box_id| self.boxes.get(box_id))
+                    .map(|(b, _)| b.ergo_box.clone())
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    fn best_header(&self) -> Result<Option<Header>, ChainIndexError> {
+        Ok(self.headers.values().next_back().cloned())
+    }
+}
+
+impl ChainIndexWriter for InMemoryChainIndex {
+    fn apply_block(&mut self, block: &FullBlock) -> Result<(), ChainIndexError> {
+        check_next_block(self.headers.values().next_back(), block)?;
+        let height = block.header.height;
+        let transactions = &block.block_transactions.transactions;
+        // hash the trees first, so that the index is not changed on error
+        let tree_hashes = transactions
+            .iter()
+            .map(|tx| {
+                tx.outputs
+                    .iter()
+                    .map(|b| ergo_tree_hash(&b.ergo_tree))
+                    .collect::<Result<Vec<Digest32>, _>>()
+            })
+            .collect::<Result<Vec<Vec<Digest32>>, _>>()?;
+        let mut tx_ids = Vec::with_capacity(transactions.len());
+        for (tx, output_tree_hashes) in transactions.iter().zip(tree_hashes) {
+            let tx_id = tx.id();
+            for input in tx.inputs.iter() {
+                if let Some((spent, tree_hash)) = self.boxes.get_mut(&input.box_id) {
+                    spent.spending_tx_id = Some(tx_id.clone());
+                    let tree_hash = tree_hash.clone();
+                    self.remove_unspent(&tree_hash, &input.box_id);
+                }
+            }
+            for (output, tree_hash) in tx.outputs.iter().zip(output_tree_hashes) {
+                let indexed = IndexedBox {
+                    ergo_box: output.clone(),
+                    inclusion_height: height,
+                    spending_tx_id: None,
+                };
+                self.boxes
+                    .insert(output.box_id(), (indexed, tree_hash.clone()));
+                self.add_unspent(tree_hash, output.box_id());
+            }
+            self.txs.insert(
+                tx_id.clone(),
+                IndexedTransaction {
+                    transaction: tx.clone(),
+                    header_id: block.header.id.clone(),
+                    inclusion_height: height,
+                },
+            );
+            tx_ids.push(tx_id);
+        }
+        self.block_txs.insert(height, tx_ids);
+        self.headers.insert(height, block.header.clone());
+        Ok(())
+    }
+
+    fn rollback(&mut self) -> Result<Option<Header>, ChainIndexError> {
+        let height = match self.headers.keys().next_back() {
+            Some(height) => *height,
+            None => return Ok(None),
+        };
+        let tx_ids = self.block_txs.remove(&height).unwrap_or_default();
+        // in the reverse order, since a transaction might spend the outputs of the previous ones
+        for tx_id in tx_ids.iter().rev() {
+            if let Some(indexed) = self.txs.remove(tx_id) {
+                for output in &indexed.transaction.outputs {
+                    let box_id = output.box_id();
+                    if let Some((_, tree_hash)) = self.boxes.remove(&box_id) {
+                        self.remove_unspent(&tree_hash, &box_id);
+                    }
+                }
+                for input in indexed.transaction.inputs.iter() {
+                    if let Some((spent, tree_hash)) = self.boxes.get_mut(&input.box_id) {
+                        spent.spending_tx_id = None;
+                        let tree_hash = tree_hash.clone();
+                        self.add_unspent(tree_hash, input.box_id.clone());
+                    }
+                }
+            }
+        }
+        Ok(self.headers.remove(&height))
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::chain::block::AdProofs;
+    use crate::chain::block::BlockTransactions;
+    use crate::chain::block::Extension;
+    use crate::chain::transaction::input::prover_result::ProverResult;
+    use crate::chain::transaction::input::Input;
+    use crate::chain::transaction::Transaction;
+    use crate::chain::transaction::TxIoVec;
+    use ergotree_interpreter::sigma_protocol::prover::ContextExtension;
+    use ergotree_interpreter::sigma_protocol::prover::ProofBytes;
+    use ergotree_ir::chain::block_id::BlockId;
+    use ergotree_ir::chain::digest32::blake2b256_hash;
+    use sigma_test_util::force_any_val;
+
+    fn block(height: u32, parent_id: BlockId, transactions: Vec<Transaction>) -> FullBlock {
+        let id = BlockId(blake2b256_hash(&height.to_be_bytes()));
+        FullBlock {
+            header: Header {
+                id: id.clone(),
+                parent_id,
+                height: Height::new(height),
+                ..Header::dummy()
+            },
+            block_transactions: BlockTransactions {
+                header_id: id.clone(),
+                block_version: 2,
+                transactions,
+            },
+            extension: Extension {
+                header_id: id.clone(),
+                fields: vec![],
+            },
+            ad_proofs: Some(AdProofs {
+                header_id: id,
+                proof_bytes: vec![],
+            }),
+        }
+    }
+
+    // transaction spending the given box with the outputs of another transaction
+    fn spending_tx(box_id: BoxId) -> Transaction {
+        let input = Input::new(
+            box_id,
+            ProverResult {
+                proof: ProofBytes::Empty,
+                extension: ContextExtension::empty(),
+            },
+        );
+        Transaction::new(
+            TxIoVec::from_vec(vec![input]).unwrap(),
+            None,
+            force_any_val::<Transaction>().output_candidates,
+        )
+        .unwrap()
+    }
+
+    fn unspent_ids(index: &InMemoryChainIndex, b: &ErgoBox) -> Vec<BoxId> {
+        index
+            .unspent_by_ergotree_hash(&ergo_tree_hash(&b.ergo_tree).unwrap())
+            .unwrap()
+            .iter()
+            .map(ErgoBox::box_id)
+            .collect()
+    }
+
+    #[test]
+    fn apply_and_rollback() {
+        let tx1 = force_any_val::<Transaction>();
+        let spent = tx1.outputs[0].clone();
+        let tx2 = spending_tx(spent.box_id());
+        let block1 = block(1, BlockId(Digest32::zero()), vec![tx1.clone()]);
+        let block2 = block(2, block1.header.id.clone(), vec![tx2.clone()]);
+        let mut index = InMemoryChainIndex::new();
+        index.apply_block(&block1).unwrap();
+        assert!(unspent_ids(&index, &spent).contains(&spent.box_id()));
+        index.apply_block(&block2).unwrap();
+
+        assert_eq!(index.best_header().unwrap(), Some(block2.header.clone()));
+        let spent_indexed = index.get_box(&spent.box_id()).unwrap().unwrap();
+        assert_eq!(spent_indexed.inclusion_height, Height::new(1));
+        assert_eq!(spent_indexed.spending_tx_id, Some(tx2.id()));
+        assert!(!unspent_ids(&index, &spent).contains(&spent.box_id()));
+        let created = &tx2.outputs[0];
+        assert!(unspent_ids(&index, created).contains(&created.box_id()));
+        let tx2_indexed = index.get_tx(&tx2.id()).unwrap().unwrap();
+        assert_eq!(tx2_indexed.header_id, block2.header.id);
+        assert_eq!(tx2_indexed.inclusion_height, Height::new(2));
+        assert_eq!(
+            index
+                .get_headers_range(Height::new(0), Height::new(5))
+                .unwrap(),
+            vec![block1.header.clone(), block2.header.clone()]
+        );
+        assert_eq!(
+            index
+                .get_headers_range(Height::new(2), Height::new(2))
+                .unwrap(),
+            vec![block2.header.clone()]
+        );
+
+        assert_eq!(index.rollback().unwrap(), Some(block2.header));
+        assert_eq!(index.get_tx(&tx2.id()).unwrap(), None);
+        assert_eq!(index.get_box(&created.box_id()).unwrap(), None);
+        assert_eq!(
+            index
+                .get_box(&spent.box_id())
+                .unwrap()
+                .unwrap()
+                .spending_tx_id,
+            None
+        );
+        assert!(unspent_ids(&index, &spent).contains(&spent.box_id()));
+        assert_eq!(index.rollback().unwrap(), Some(block1.header));
+        assert_eq!(index, InMemoryChainIndex::new());
+        assert_eq!(index.rollback().unwrap(), None);
+    }
+
+    #[test]
+    fn apply_not_next_block() {
+        let block1 = block(1, BlockId(Digest32::zero()), vec![]);
+        let mut index = InMemoryChainIndex::new();
+        index.apply_block(&block1).unwrap();
+        assert_eq!(
+            index.apply_block(&block(3, block1.header.id.clone(), vec![])),
+            Err(ChainIndexError::UnexpectedHeight {
+                expected: Height::new(2),
+                actual: Height::new(3),
+            })
+        );
+        assert!(matches!(
+            index.apply_block(&block(2, BlockId(Digest32::zero()), vec![])),
+            Err(ChainIndexError::ParentMismatch { .. })
+        ));
+        assert_eq!(index.best_header().unwrap(), Some(block1.header));
+    }
+
+    #[test]
+    fn bootstrap_from_utxo_snapshot() {
+        let tx1 = force_any_val::<Transaction>();
+        let snapshot_block = block(10, BlockId(Digest32::zero()), vec![]);
+        let boxes: Vec<ErgoBox> = tx1.outputs.iter().cloned().collect();
+        let spent = boxes[0].clone();
+        let mut index =
+            InMemoryChainIndex::from_utxo_snapshot(snapshot_block.header.clone(), boxes).unwrap();
+        assert_eq!(
+            index.best_header().unwrap(),
+            Some(snapshot_block.header.clone())
+        );
+        assert!(unspent_ids(&index, &spent).contains(&spent.box_id()));
+        let tx2 = spending_tx(spent.box_id());
+        index
+            .apply_block(&block(
+                11,
+                snapshot_block.header.id.clone(),
+                vec![tx2.clone()],
+            ))
+            .unwrap();
+        assert_eq!(
+            index
+                .get_box(&spent.box_id())
+                .unwrap()
+                .unwrap()
+                .spending_tx_id,
+            Some(tx2.id())
+        );
+        assert!(!unspent_ids(&index, &spent).contains(&spent.box_id()));
+    }
+}
@@ -1,6 +1,9 @@
 //! JSON serialization
 
+pub(crate) mod block;
 pub(crate) mod context_extension;
+pub mod dapp;
+pub(crate) mod parameters;
 pub(crate) mod transaction;
 
 use ergotree_interpreter::sigma_protocol::prover::ProofBytes;
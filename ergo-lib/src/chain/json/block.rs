@@ -0,0 +1,65 @@
+use crate::chain::block::{AdProofs, Extension, ExtensionField};
+use ergotree_ir::chain::base16_bytes::Base16DecodedBytes;
+use ergotree_ir::chain::block_id::BlockId;
+use ergotree_ir::chain::digest32::Digest32;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct ExtensionJson {
+    #[serde(rename = "headerId")]
+    pub header_id: BlockId,
+    /// calculated from the fields on encoding, ignored on parsing
+    #[serde(rename = "digest", default, skip_serializing_if = "Option::is_none")]
+    pub digest: Option<Digest32>,
+    #[serde(rename = "fields")]
+    pub fields: Vec<ExtensionField>,
+}
+
+impl From<Extension> for ExtensionJson {
+    fn from(v: Extension) -> Self {
+        ExtensionJson {
+            digest: Some(v.digest()),
+            header_id: v.header_id,
+            fields: v.fields,
+        }
+    }
+}
+
+impl From<ExtensionJson> for Extension {
+    fn from(v: ExtensionJson) -> Self {
+        Extension {
+            header_id: v.header_id,
+            fields: v.fields,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct AdProofsJson {
+    #[serde(rename = "headerId")]
+    pub header_id: BlockId,
+    #[serde(rename = "proofBytes")]
+    pub proof_bytes: Base16DecodedBytes,
+    /// calculated from the proof bytes on encoding, ignored on parsing
+    #[serde(rename = "digest", default, skip_serializing_if = "Option::is_none")]
+    pub digest: Option<Digest32>,
+}
+
+impl From<AdProofs> for AdProofsJson {
+    fn from(v: AdProofs) -> Self {
+        AdProofsJson {
+            digest: Some(v.digest()),
+            header_id: v.header_id,
+            proof_bytes: Base16DecodedBytes(v.proof_bytes),
+        }
+    }
+}
+
+impl From<AdProofsJson> for AdProofs {
+    fn from(v: AdProofsJson) -> Self {
+        AdProofs {
+            header_id: v.header_id,
+            proof_bytes: v.proof_bytes.0,
+        }
+    }
+}
@@ -23,10 +23,13 @@ impl Serialize for ContextExtensionSerde {
     {
         use serde::ser::Error;
         let mut map = serializer.serialize_map(Some(self.values.len()))?;
-        for (k, v) in &self.values {
+        // same order as in the binary serialization (by id) to get the same JSON for the same values
+        let mut sorted_values: Vec<(&u8, &Constant)> = self.values.iter().collect();
+        sorted_values.sort_by_key(|(k, _)| *k);
+        for (k, v) in sorted_values {
             map.serialize_entry(
                 &format!("{}", k),
-                &base16::encode_lower(&v.sigma_serialize_bytes().map_err(Error::custom)?),
+                &sigma_util::hex::encode_lower(&v.sigma_serialize_bytes().map_err(Error::custom)?),
             )?;
         }
         map.end()
@@ -40,6 +43,7 @@ impl From<ContextExtension> for ContextExtensionSerde {
 }
 
 #[cfg(test)]
+#[allow(clippy::unwrap_used)]
 mod tests {
     use super::*;
 
@@ -61,4 +65,18 @@ mod tests {
         assert!(c.values.get(&1u8).is_some());
         assert!(c.values.get(&3u8).is_some());
     }
+
+    #[test]
+    fn context_extension_json_order() {
+        let json = r#"{"3":"048ce5d4e505","1":"05b0b5cad8e6dbaef44a","2":"0402"}"#;
+        let expected = r#"{"1":"05b0b5cad8e6dbaef44a","2":"0402","3":"048ce5d4e505"}"#;
+        // every parsed HashMap has its own random iteration order
+        for _ in 0..20 {
+            let mut de = serde_json::Deserializer::from_str(json);
+            let c: ContextExtension = ContextExtensionSerde::deserialize(&mut de).unwrap();
+            assert_eq!(c.values.keys().collect::<Vec<_>>(), vec![&1u8, &2u8, &3u8]);
+            let ser = serde_json::to_string(&ContextExtensionSerde::from(c)).unwrap();
+            assert_eq!(ser, expected);
+        }
+    }
 }
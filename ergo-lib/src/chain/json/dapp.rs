@@ -0,0 +1,455 @@
+//! EIP-12 dApp connector (wallet bridge) JSON types
+//! (see <https://github.com/ergoplatform/eips/blob/master/eip-0012.md>)
+//!
+//! Unlike the node API format, box values and token amounts are encoded as strings and boxes are
+//! included in full for the transaction inputs and data inputs.
+
+use std::convert::TryFrom;
+use std::convert::TryInto;
+
+use ergotree_interpreter::sigma_protocol::prover::ContextExtension;
+use ergotree_ir::chain::ergo_box::box_value::BoxValue;
+use ergotree_ir::chain::ergo_box::box_value::BoxValueError;
+use ergotree_ir::chain::ergo_box::BoxId;
+use ergotree_ir::chain::ergo_box::BoxTokens;
+use ergotree_ir::chain::ergo_box::ErgoBox;
+use ergotree_ir::chain::ergo_box::ErgoBoxCandidate;
+use ergotree_ir::chain::ergo_box::NonMandatoryRegisters;
+use ergotree_ir::chain::token::Token;
+use ergotree_ir::chain::token::TokenAmount;
+use ergotree_ir::chain::token::TokenAmountError;
+use ergotree_ir::chain::token::TokenId;
+use ergotree_ir::chain::tx_id::TxId;
+use ergotree_ir::ergo_tree::ErgoTree;
+use ergotree_ir::serialization::SigmaSerializable;
+use ergotree_ir::serialization::SigmaSerializationError;
+use serde::{Deserialize, Serialize, Serializer};
+use thiserror::Error;
+
+use crate::chain::json::context_extension::ContextExtensionSerde;
+use crate::chain::transaction::unsigned::UnsignedTransaction;
+use crate::chain::transaction::DataInput;
+use crate::chain::transaction::UnsignedInput;
+
+/// Errors on conversion between EIP-12 JSON types and the internal types
+#[derive(Error, PartialEq, Eq, Debug, Clone)]
+pub enum DAppJsonError {
+    /// Box value or token amount is not a valid unsigned integer
+    #[error("Invalid amount: {0}")]
+    InvalidAmount(String),
+    /// Invalid ErgoTree (base16 or serialization)
+    #[error("Invalid ErgoTree: {0}")]
+    InvalidErgoTree(String),
+    /// Box id differs from the id calculated from the box content
+    #[error("Box id {0:?} differs from the one calculated from the box content")]
+    InvalidBoxId(BoxId),
+    /// Box for the transaction input (or data input) is not found
+    #[error("Box with id {0:?} is not found")]
+    BoxNotFound(BoxId),
+    /// Invalid transaction (e.g. no inputs or outputs)
+    #[error("Invalid transaction: {0}")]
+    InvalidTransaction(String),
+    /// Box value error
+    #[error("Box value error: {0}")]
+    BoxValueError(#[from] BoxValueError),
+    /// Token amount error
+    #[error("Token amount error: {0}")]
+    TokenAmountError(#[from] TokenAmountError),
+    /// Serialization error
+    #[error("Serialization error: {0}")]
+    SerializationError(#[from] SigmaSerializationError),
+    /// Box has more tokens than allowed (255)
+    #[error("Too many tokens in a box: {0}")]
+    TooManyTokens(usize),
+}
+
+/// Token with the amount encoded as a string
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+pub struct DAppToken {
+    /// token id
+    #[serde(rename = "tokenId")]
+    pub token_id: TokenId,
+    /// token amount
+    #[serde(rename = "amount")]
+    pub amount: String,
+}
+
+impl From<Token> for DAppToken {
+    fn from(t: Token) -> Self {
+        DAppToken {
+            token_id: t.token_id,
+            amount: t.amount.as_u64().to_string(),
+        }
+    }
+}
+
+impl TryFrom<DAppToken> for Token {
+    type Error = DAppJsonError;
+
+    fn try_from(t: DAppToken) -> Result<Self, Self::Error> {
+        Ok(Token {
+            token_id: t.token_id,
+            amount: TokenAmount::try_from(parse_amount(&t.amount)?)?,
+        })
+    }
+}
+
+/// Box candidate (transaction output)
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+pub struct DAppBoxCandidate {
+    /// amount of nanoERGs
+    #[serde(rename = "value")]
+    pub value: String,
+    /// serialized ErgoTree (base16)
+    #[serde(rename = "ergoTree")]
+    pub ergo_tree: String,
+    /// tokens
+    #[serde(rename = "assets")]
+    pub assets: Vec<DAppToken>,
+    /// additional registers (R4-R9)
+    #[serde(rename = "additionalRegisters")]
+    pub additional_registers: NonMandatoryRegisters,
+    /// height when a transaction containing the box was created
+    #[serde(rename = "creationHeight")]
+    pub creation_height: u32,
+}
+
+impl TryFrom<ErgoBoxCandidate> for DAppBoxCandidate {
+    type Error = DAppJsonError;
+
+    fn try_from(b: ErgoBoxCandidate) -> Result<Self, Self::Error> {
+        Ok(DAppBoxCandidate {
+            value: b.value.as_u64().to_string(),
+            ergo_tree: sigma_util::hex::encode_lower(&b.ergo_tree.sigma_serialize_bytes()?),
+            assets: b
+                .tokens
+                .into_iter()
+                .flatten()
+                .map(DAppToken::from)
+                .collect(),
+            additional_registers: b.additional_registers,
+            creation_height: b.creation_height,
+        })
+    }
+}
+
+impl TryFrom<DAppBoxCandidate> for ErgoBoxCandidate {
+    type Error = DAppJsonError;
+
+    fn try_from(b: DAppBoxCandidate) -> Result<Self, Self::Error> {
+        let tokens = b
+            .assets
+            .into_iter()
+            .map(Token::try_from)
+            .collect::<Result<Vec<Token>, DAppJsonError>>()?;
+        let tokens = if tokens.is_empty() {
+            None
+        } else {
+            let tokens_count = tokens.len();
+            Some(
+                BoxTokens::from_vec(tokens)
+                    .map_err(|_| DAppJsonError::TooManyTokens(tokens_count))?,
+            )
+        };
+        Ok(ErgoBoxCandidate {
+            value: BoxValue::try_from(parse_amount(&b.value)?)?,
+            ergo_tree: parse_ergo_tree(&b.ergo_tree)?,
+            tokens,
+            additional_registers: b.additional_registers,
+            creation_height: b.creation_height,
+        })
+    }
+}
+
+/// Box (transaction input or data input)
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+pub struct DAppBox {
+    /// box id
+    #[serde(rename = "boxId")]
+    pub box_id: BoxId,
+    /// id of transaction which created the box
+    #[serde(rename = "transactionId")]
+    pub transaction_id: TxId,
+    /// index of the box in the transaction outputs
+    #[serde(rename = "index")]
+    pub index: u16,
+    /// box content
+    #[serde(flatten)]
+    pub candidate: DAppBoxCandidate,
+}
+
+impl TryFrom<ErgoBox> for DAppBox {
+    type Error = DAppJsonError;
+
+    fn try_from(b: ErgoBox) -> Result<Self, Self::Error> {
+        Ok(DAppBox {
+            box_id: b.box_id(),
+            transaction_id: b.transaction_id.clone(),
+            index: b.index,
+            candidate: ErgoBoxCandidate::from(b).try_into()?,
+        })
+    }
+}
+
+impl TryFrom<DAppBox> for ErgoBox {
+    type Error = DAppJsonError;
+
+    fn try_from(b: DAppBox) -> Result<Self, Self::Error> {
+        let candidate = ErgoBoxCandidate::try_from(b.candidate)?;
+        let ergo_box = ErgoBox::from_box_candidate(&candidate, b.transaction_id, b.index)?;
+        if ergo_box.box_id() != b.box_id {
+            return Err(DAppJsonError::InvalidBoxId(b.box_id));
+        }
+        Ok(ergo_box)
+    }
+}
+
+/// Unsigned transaction input (box with the context extension)
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+pub struct DAppUnsignedInput {
+    /// box to spend
+    #[serde(flatten)]
+    pub ergo_box: DAppBox,
+    /// user-defined variables to be put into context
+    #[serde(
+        rename = "extension",
+        serialize_with = "serialize_context_extension",
+        deserialize_with = "ContextExtensionSerde::deserialize"
+    )]
+    pub extension: ContextExtension,
+}
+
+fn serialize_context_extension<S>(
+    extension: &ContextExtension,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    ContextExtensionSerde::from(extension.clone()).serialize(serializer)
+}
+
+/// Unsigned transaction
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+pub struct DAppUnsignedTx {
+    /// inputs (boxes to spend)
+    #[serde(rename = "inputs")]
+    pub inputs: Vec<DAppUnsignedInput>,
+    /// data inputs (boxes)
+    #[serde(rename = "dataInputs")]
+    pub data_inputs: Vec<DAppBox>,
+    /// outputs
+    #[serde(rename = "outputs")]
+    pub outputs: Vec<DAppBoxCandidate>,
+}
+
+impl DAppUnsignedTx {
+    /// Create from the unsigned transaction and the boxes for it's inputs and data inputs
+    pub fn new(
+        tx: &UnsignedTransaction,
+        boxes_to_spend: &[ErgoBox],
+        data_boxes: &[ErgoBox],
+    ) -> Result<DAppUnsignedTx, DAppJsonError> {
+        let inputs = tx
+            .inputs
+            .iter()
+            .map(|i| {
+                Ok(DAppUnsignedInput {
+                    ergo_box: find_box(boxes_to_spend, &i.box_id)?.try_into()?,
+                    extension: i.extension.clone(),
+                })
+            })
+            .collect::<Result<Vec<DAppUnsignedInput>, DAppJsonError>>()?;
+        let data_inputs = tx
+            .data_inputs
+            .iter()
+            .flatten()
+            .map(|di| find_box(data_boxes, &di.box_id)?.try_into())
+            .collect::<Result<Vec<DAppBox>, DAppJsonError>>()?;
+        let outputs = tx
+            .output_candidates
+            .iter()
+            .cloned()
+            .map(DAppBoxCandidate::try_from)
+            .collect::<Result<Vec<DAppBoxCandidate>, DAppJsonError>>()?;
+        Ok(DAppUnsignedTx {
+            inputs,
+            data_inputs,
+            outputs,
+        })
+    }
+
+    /// Boxes to spend (transaction inputs)
+    pub fn boxes_to_spend(&self) -> Result<Vec<ErgoBox>, DAppJsonError> {
+        self.inputs
+            .iter()
+            .map(|i| ErgoBox::try_from(i.ergo_box.clone()))
+            .collect()
+    }
+
+    /// Data input boxes
+    pub fn data_boxes(&self) -> Result<Vec<ErgoBox>, DAppJsonError> {
+        self.data_inputs
+            .iter()
+            .cloned()
+            .map(ErgoBox::try_from)
+            .collect()
+    }
+}
+
+impl TryFrom<DAppUnsignedTx> for UnsignedTransaction {
+    type Error = DAppJsonError;
+
+    fn try_from(tx: DAppUnsignedTx) -> Result<Self, Self::Error> {
+        let inputs = tx
+            .inputs
+            .into_iter()
+            .map(|i| UnsignedInput {
+                box_id: i.ergo_box.box_id,
+                extension: i.extension,
+            })
+            .collect::<Vec<UnsignedInput>>()
+            .try_into()
+            .map_err(|e| DAppJsonError::InvalidTransaction(format!("inputs: {:?}", e)))?;
+        let data_inputs = tx
+            .data_inputs
+            .into_iter()
+            .map(|b| DataInput::from(b.box_id))
+            .collect::<Vec<DataInput>>()
+            .try_into()
+            .ok();
+        let outputs = tx
+            .outputs
+            .into_iter()
+            .map(ErgoBoxCandidate::try_from)
+            .collect::<Result<Vec<ErgoBoxCandidate>, DAppJsonError>>()?
+            .try_into()
+            .map_err(|e| DAppJsonError::InvalidTransaction(format!("outputs: {:?}", e)))?;
+        Ok(UnsignedTransaction::new(inputs, data_inputs, outputs)?)
+    }
+}
+
+fn parse_amount(s: &str) -> Result<u64, DAppJsonError> {
+    s.parse::<u64>()
+        .map_err(|e| DAppJsonError::InvalidAmount(format!("{}: {}", s, e)))
+}
+
+fn parse_ergo_tree(s: &str) -> Result<ErgoTree, DAppJsonError> {
+    let bytes =
+        sigma_util::hex::decode(s).map_err(|e| DAppJsonError::InvalidErgoTree(e.to_string()))?;
+    ErgoTree::sigma_parse_bytes(&bytes).map_err(|e| DAppJsonError::InvalidErgoTree(e.to_string()))
+}
+
+fn find_box(boxes: &[ErgoBox], box_id: &BoxId) -> Result<ErgoBox, DAppJsonError> {
+    boxes
+        .iter()
+        .find(|b| b.box_id() == *box_id)
+        .cloned()
+        .ok_or_else(|| DAppJsonError::BoxNotFound(box_id.clone()))
+}
+
+#[allow(clippy::unwrap_used)]
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+    use sigma_test_util::force_any_val;
+
+    proptest! {
+
+        #![proptest_config(ProptestConfig::with_cases(16))]
+
+        #[test]
+        fn dapp_box_roundtrip(b in any::<ErgoBox>()) {
+            let j = serde_json::to_string(&DAppBox::try_from(b.clone())?)?;
+            let b_parsed: DAppBox = serde_json::from_str(&j)?;
+            prop_assert_eq![ErgoBox::try_from(b_parsed)?, b];
+        }
+
+        #[test]
+        fn dapp_unsigned_tx_roundtrip(
+            boxes_to_spend in vec(any::<ErgoBox>(), 1..3),
+            data_boxes in vec(any::<ErgoBox>(), 0..3),
+            outputs in vec(any::<ErgoBoxCandidate>(), 1..3),
+        ) {
+            let inputs: Vec<UnsignedInput> = boxes_to_spend
+                .iter()
+                .map(|b| UnsignedInput {
+                    box_id: b.box_id(),
+                    extension: ContextExtension::empty(),
+                })
+                .collect();
+            let data_inputs: Vec<DataInput> =
+                data_boxes.iter().map(|b| DataInput::from(b.box_id())).collect();
+            let tx = UnsignedTransaction::new(
+                inputs.try_into().unwrap(),
+                data_inputs.try_into().ok(),
+                outputs.try_into().unwrap(),
+            )?;
+            let dapp_tx = DAppUnsignedTx::new(&tx, &boxes_to_spend, &data_boxes)?;
+            let j = serde_json::to_string(&dapp_tx)?;
+            let dapp_tx_parsed: DAppUnsignedTx = serde_json::from_str(&j)?;
+            prop_assert_eq![dapp_tx_parsed.boxes_to_spend()?, boxes_to_spend.clone()];
+            prop_assert_eq![dapp_tx_parsed.data_boxes()?, data_boxes];
+            prop_assert_eq![UnsignedTransaction::try_from(dapp_tx_parsed)?, tx.clone()];
+            // boxes for the inputs are required
+            prop_assert_eq!(
+                DAppUnsignedTx::new(&tx, &[], &[]),
+                Err(DAppJsonError::BoxNotFound(boxes_to_spend[0].box_id()))
+            );
+        }
+    }
+
+    #[test]
+    fn parse_dapp_box() {
+        let json = r#"{
+          "boxId": "e56847ed19b3dc6b72828fcfb992fdf7310828cf291221269b7ffc72fd66706e",
+          "value": "67500000000",
+          "ergoTree": "100204a00b08cd021dde34603426402615658f1d970cfa7c7bd92ac81a8b16eeebff264d59ce4604ea02d192a39a8cc7a70173007301",
+          "assets": [],
+          "creationHeight": 284761,
+          "additionalRegisters": {},
+          "transactionId": "9148408c04c2e38a6402a7950d6157730fa7d49e9ab3b9cadec481d7769918e9",
+          "index": 1
+        }"#;
+        let b: DAppBox = serde_json::from_str(json).unwrap();
+        let ergo_box = ErgoBox::try_from(b.clone()).unwrap();
+        assert_eq!(*ergo_box.value.as_u64(), 67500000000);
+        assert_eq!(DAppBox::try_from(ergo_box).unwrap(), b);
+        let b_invalid_value = DAppBox {
+            candidate: DAppBoxCandidate {
+                value: "67500000000.0".to_string(),
+                ..b.candidate.clone()
+            },
+            ..b
+        };
+        assert!(matches!(
+            ErgoBox::try_from(b_invalid_value),
+            Err(DAppJsonError::InvalidAmount(_))
+        ));
+    }
+
+    #[test]
+    fn parse_dapp_box_tokens() {
+        let candidate = DAppBoxCandidate::try_from(force_any_val::<ErgoBoxCandidate>()).unwrap();
+        let token = DAppToken {
+            token_id: force_any_val::<TokenId>(),
+            amount: "1".to_string(),
+        };
+        let no_tokens = DAppBoxCandidate {
+            assets: vec![],
+            ..candidate.clone()
+        };
+        assert_eq!(ErgoBoxCandidate::try_from(no_tokens).unwrap().tokens, None);
+        let too_many_tokens = DAppBoxCandidate {
+            assets: vec![token; 256],
+            ..candidate
+        };
+        assert_eq!(
+            ErgoBoxCandidate::try_from(too_many_tokens),
+            Err(DAppJsonError::TooManyTokens(256))
+        );
+    }
+}
@@ -0,0 +1,67 @@
+use serde::Deserialize;
+
+use crate::chain::parameters::*;
+
+/// Blockchain parameters in the node REST API format (`parameters` of `/info`)
+#[derive(Deserialize, PartialEq, Eq, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ParametersJson {
+    storage_fee_factor: i32,
+    min_value_per_byte: i32,
+    max_block_size: i32,
+    max_block_cost: i32,
+    token_access_cost: i32,
+    input_cost: i32,
+    data_input_cost: i32,
+    output_cost: i32,
+    block_version: i32,
+}
+
+impl From<ParametersJson> for Parameters {
+    fn from(p: ParametersJson) -> Self {
+        Parameters::new(
+            vec![
+                (STORAGE_FEE_FACTOR_ID, p.storage_fee_factor),
+                (MIN_VALUE_PER_BYTE_ID, p.min_value_per_byte),
+                (MAX_BLOCK_SIZE_ID, p.max_block_size),
+                (MAX_BLOCK_COST_ID, p.max_block_cost),
+                (TOKEN_ACCESS_COST_ID, p.token_access_cost),
+                (INPUT_COST_ID, p.input_cost),
+                (DATA_INPUT_COST_ID, p.data_input_cost),
+                (OUTPUT_COST_ID, p.output_cost),
+                (BLOCK_VERSION_ID, p.block_version),
+            ]
+            .into_iter()
+            .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_node_parameters() {
+        // `parameters` of the node's /info
+        let json = r#"{
+            "height": 471744,
+            "storageFeeFactor": 1250000,
+            "minValuePerByte": 360,
+            "maxBlockSize": 1271009,
+            "maxBlockCost": 2000000,
+            "blockVersion": 2,
+            "tokenAccessCost": 100,
+            "inputCost": 2000,
+            "dataInputCost": 100,
+            "outputCost": 100
+        }"#;
+        let parameters: Parameters = serde_json::from_str(json).unwrap();
+        assert_eq!(parameters.max_block_size(), Some(1271009));
+        assert_eq!(parameters.max_block_cost(), Some(2000000));
+        assert_eq!(parameters.block_version(), Some(2));
+        assert_eq!(parameters.table().len(), 9);
+        assert!(serde_json::from_str::<Parameters>("[]").is_err());
+    }
+}
@@ -0,0 +1,183 @@
+//! Network (mainnet, testnet, devnet or a custom chain) configuration
+
+use ergotree_ir::chain::address::Address;
+use ergotree_ir::chain::address::AddressEncoder;
+use ergotree_ir::chain::address::AddressEncoderError;
+use ergotree_ir::chain::address::NetworkPrefix;
+use ergotree_ir::chain::ergo_box::box_value::BoxValue;
+use ergotree_ir::chain::ergo_box::ErgoBoxCandidate;
+use ergotree_ir::ergo_tree::ErgoTree;
+use ergotree_ir::serialization::SigmaSerializable;
+
+use crate::chain::ergo_box::box_builder::ErgoBoxCandidateBuilder;
+use crate::chain::ergo_box::box_builder::ErgoBoxCandidateBuilderError;
+use crate::chain::parameters::Parameters;
+
+/// Network configuration: address prefix, blockchain parameters at the launch, miner's fee
+/// proposition and the default API URLs (for the applications' explorer and node clients, this
+/// library has no REST client).
+/// Use [`Network::mainnet`], [`Network::testnet`] or [`Network::devnet`], for a custom chain
+/// override the fields of one of them.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Network {
+    /// Prefix (network type) of the addresses
+    pub address_prefix: NetworkPrefix,
+    /// Blockchain parameters at the launch (before any update by the miners voting)
+    pub launch_parameters: Parameters,
+    /// Proposition (script) of the miner's fee box
+    pub fee_proposition: ErgoTree,
+    /// Default explorer API URL
+    pub explorer_url: Option<String>,
+    /// Default node REST API URL
+    pub node_url: Option<String>,
+}
+
+impl Network {
+    /// Mainnet
+    pub fn mainnet() -> Network {
+        Network {
+            address_prefix: NetworkPrefix::Mainnet,
+            launch_parameters: Parameters::default(),
+            fee_proposition: miner_fee_tree(),
+            explorer_url: Some("https://api.ergoplatform.com".to_string()),
+            node_url: Some("http://127.0.0.1:9053".to_string()),
+        }
+    }
+
+    /// Testnet
+    pub fn testnet() -> Network {
+        Network {
+            address_prefix: NetworkPrefix::Testnet,
+            launch_parameters: Parameters::default(),
+            fee_proposition: miner_fee_tree(),
+            explorer_url: Some("https://api-testnet.ergoplatform.com".to_string()),
+            node_url: Some("http://127.0.0.1:9052".to_string()),
+        }
+    }
+
+    /// Devnet (local development network, no public explorer)
+    pub fn devnet() -> Network {
+        Network {
+            address_prefix: NetworkPrefix::Devnet,
+            launch_parameters: Parameters::default(),
+            fee_proposition: miner_fee_tree(),
+            explorer_url: None,
+            node_url: Some("http://127.0.0.1:9053".to_string()),
+        }
+    }
+
+    /// Address encoder for the addresses of this network
+    pub fn address_encoder(&self) -> AddressEncoder {
+        AddressEncoder::new(self.address_prefix)
+    }
+
+    /// Parse Base58 encoded address, fails if the address is of another network
+    pub fn parse_address(&self, str: &str) -> Result<Address, AddressEncoderError> {
+        self.address_encoder().parse_address_from_str(str)
+    }
+
+    /// Encode address as Base58 string with the prefix of this network
+    pub fn address_to_str(&self, address: &Address) -> String {
+        self.address_encoder().address_to_str(address)
+    }
+
+    /// Create a miner's fee box (with [`Network::fee_proposition`]) with the given value
+    pub fn miner_fee_box(
+        &self,
+        fee_amount: BoxValue,
+        creation_height: u32,
+    ) -> Result<ErgoBoxCandidate, ErgoBoxCandidateBuilderError> {
+        ErgoBoxCandidateBuilder::new(fee_amount, self.fee_proposition.clone(), creation_height)
+            .build()
+    }
+}
+
+/// Serialized miner's fee script, the same for all networks (only the address prefix of
+/// [`crate::constants::MINERS_FEE_MAINNET_ADDRESS`] and
+/// [`crate::constants::MINERS_FEE_TESTNET_ADDRESS`] differs)
+const MINERS_FEE_TREE_BYTES: [u8; 105] = [
+    0x10, 0x05, 0x04, 0x00, 0x04, 0x00, 0x0e, 0x36, 0x10, 0x02, 0x04, 0xa0, 0x0b, 0x08, 0xcd, 0x02,
+    0x79, 0xbe, 0x66, 0x7e, 0xf9, 0xdc, 0xbb, 0xac, 0x55, 0xa0, 0x62, 0x95, 0xce, 0x87, 0x0b, 0x07,
+    0x02, 0x9b, 0xfc, 0xdb, 0x2d, 0xce, 0x28, 0xd9, 0x59, 0xf2, 0x81, 0x5b, 0x16, 0xf8, 0x17, 0x98,
+    0xea, 0x02, 0xd1, 0x92, 0xa3, 0x9a, 0x8c, 0xc7, 0xa7, 0x01, 0x73, 0x00, 0x73, 0x01, 0x10, 0x01,
+    0x02, 0x04, 0x02, 0xd1, 0x96, 0x83, 0x03, 0x01, 0x93, 0xa3, 0x8c, 0xc7, 0xb2, 0xa5, 0x73, 0x00,
+    0x00, 0x01, 0x93, 0xc2, 0xb2, 0xa5, 0x73, 0x01, 0x00, 0x74, 0x73, 0x02, 0x73, 0x03, 0x83, 0x01,
+    0x08, 0xcd, 0xee, 0xac, 0x93, 0xb1, 0xa5, 0x73, 0x04,
+];
+
+// the bytes are constant, the `miner_fee_tree_bytes` test checks that they parse in full and
+// match the fee addresses
+#[allow(clippy::unwrap_used)]
+fn miner_fee_tree() -> ErgoTree {
+    ErgoTree::sigma_parse_bytes(&MINERS_FEE_TREE_BYTES).unwrap()
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::constants::MINERS_FEE_MAINNET_ADDRESS;
+    use crate::constants::MINERS_FEE_TESTNET_ADDRESS;
+
+    #[test]
+    fn miner_fee_tree_bytes() {
+        let tree = miner_fee_tree();
+        assert!(tree.proposition().is_ok());
+        assert_eq!(
+            tree.sigma_serialize_bytes().unwrap(),
+            MINERS_FEE_TREE_BYTES.to_vec()
+        );
+        assert_eq!(
+            Network::mainnet()
+                .parse_address(MINERS_FEE_MAINNET_ADDRESS)
+                .unwrap()
+                .script()
+                .unwrap(),
+            tree
+        );
+    }
+
+    #[test]
+    fn fee_proposition() {
+        // the testnet fee address encodes the same script
+        let testnet = Network::testnet();
+        assert_eq!(
+            testnet
+                .parse_address(MINERS_FEE_TESTNET_ADDRESS)
+                .unwrap()
+                .script()
+                .unwrap(),
+            testnet.fee_proposition
+        );
+    }
+
+    #[test]
+    fn address_of_another_network() {
+        let devnet = Network::devnet();
+        let address = Network::mainnet()
+            .parse_address(MINERS_FEE_MAINNET_ADDRESS)
+            .unwrap();
+        let encoded = devnet.address_to_str(&address);
+        assert_eq!(devnet.parse_address(&encoded).unwrap(), address);
+        assert!(matches!(
+            Network::testnet().parse_address(&encoded),
+            Err(AddressEncoderError::InvalidNetwork(_))
+        ));
+        assert!(Network::mainnet().parse_address(&encoded).is_err());
+    }
+
+    #[test]
+    fn custom_chain() {
+        let mut launch_parameters = Parameters::default().table().clone();
+        launch_parameters.insert(crate::chain::parameters::MIN_VALUE_PER_BYTE_ID, 1);
+        let custom = Network {
+            launch_parameters: Parameters::new(launch_parameters),
+            node_url: Some("http://10.0.0.1:9053".to_string()),
+            ..Network::devnet()
+        };
+        assert_eq!(custom.launch_parameters.min_value_per_byte(), Some(1));
+        assert_eq!(custom.address_prefix, NetworkPrefix::Devnet);
+        let fee_box = custom.miner_fee_box(BoxValue::SAFE_USER_MIN, 1).unwrap();
+        assert_eq!(fee_box.ergo_tree, custom.fee_proposition);
+    }
+}
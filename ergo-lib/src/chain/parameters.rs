@@ -0,0 +1,417 @@
+//! Blockchain parameters (max block cost/size, storage fee factor, etc.) and their updates via
+//! miners voting
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::convert::TryInto;
+
+use ergotree_ir::chain::votes::Votes;
+use thiserror::Error;
+
+/// Storage fee factor (per byte per storage period)
+pub const STORAGE_FEE_FACTOR_ID: i8 = 1;
+/// Minimum monetary value of a box per byte
+pub const MIN_VALUE_PER_BYTE_ID: i8 = 2;
+/// Max block size
+pub const MAX_BLOCK_SIZE_ID: i8 = 3;
+/// Max block cost
+pub const MAX_BLOCK_COST_ID: i8 = 4;
+/// Token access cost
+pub const TOKEN_ACCESS_COST_ID: i8 = 5;
+/// Cost per one transaction input
+pub const INPUT_COST_ID: i8 = 6;
+/// Cost per one data input
+pub const DATA_INPUT_COST_ID: i8 = 7;
+/// Cost per one transaction output
+pub const OUTPUT_COST_ID: i8 = 8;
+/// Soft-fork vote (ids starting from this one are not voted on by the step rules)
+pub const SOFT_FORK_ID: i8 = 120;
+/// Block version
+pub const BLOCK_VERSION_ID: i8 = 123;
+/// Rules disabled by a soft-fork (variable length value, not a parameter)
+const SOFT_FORK_DISABLING_RULES_ID: i8 = 124;
+
+/// Extension section key prefix for the system parameters
+const SYSTEM_PARAMETERS_PREFIX: u8 = 0x00;
+
+/// Voting epoch length on the mainnet (in blocks)
+pub const MAINNET_VOTING_EPOCH_LENGTH: u32 = 1024;
+
+/// Block extension section field (2-byte key, value)
+pub type ExtensionField = ([u8; 2], Vec<u8>);
+
+/// Errors on parameters parsing and updates
+#[derive(Error, PartialEq, Eq, Debug, Clone)]
+pub enum ParametersError {
+    /// Parameter value in the extension section is not a 4-byte integer
+    #[error("invalid value for parameter {id} in the extension section: {value:?}")]
+    InvalidValue {
+        /// parameter id
+        id: i8,
+        /// value bytes
+        value: Vec<u8>,
+    },
+    /// Parameters can only be updated at the voting epoch start
+    #[error("height {height} is not a voting epoch start (epoch length {epoch_length})")]
+    NotEpochStart {
+        /// height of the update
+        height: u32,
+        /// voting epoch length
+        epoch_length: u32,
+    },
+}
+
+/// Blockchain parameters (parameter id -> value)
+#[cfg_attr(
+    feature = "json",
+    derive(serde::Deserialize),
+    serde(from = "crate::chain::json::parameters::ParametersJson")
+)]
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Parameters {
+    table: BTreeMap<i8, i32>,
+}
+
+impl Default for Parameters {
+    /// Parameters at the mainnet launch
+    fn default() -> Self {
+        Parameters::new(
+            vec![
+                (STORAGE_FEE_FACTOR_ID, 1250000),
+                (MIN_VALUE_PER_BYTE_ID, 360),
+                (MAX_BLOCK_SIZE_ID, 512 * 1024),
+                (MAX_BLOCK_COST_ID, 1000000),
+                (TOKEN_ACCESS_COST_ID, 100),
+                (INPUT_COST_ID, 2000),
+                (DATA_INPUT_COST_ID, 100),
+                (OUTPUT_COST_ID, 100),
+                (BLOCK_VERSION_ID, 1),
+            ]
+            .into_iter()
+            .collect(),
+        )
+    }
+}
+
+impl Parameters {
+    /// Create new instance from the parameters table
+    pub fn new(table: BTreeMap<i8, i32>) -> Parameters {
+        Parameters { table }
+    }
+
+    /// Parameters table (parameter id -> value)
+    pub fn table(&self) -> &BTreeMap<i8, i32> {
+        &self.table
+    }
+
+    /// Value of the parameter with the given id
+    pub fn get(&self, id: i8) -> Option<i32> {
+        self.table.get(&id).copied()
+    }
+
+    /// Storage fee factor (per byte per storage period)
+    pub fn storage_fee_factor(&self) -> Option<i32> {
+        self.get(STORAGE_FEE_FACTOR_ID)
+    }
+
+    /// Minimum monetary value of a box per byte
+    pub fn min_value_per_byte(&self) -> Option<i32> {
+        self.get(MIN_VALUE_PER_BYTE_ID)
+    }
+
+    /// Max block size
+    pub fn max_block_size(&self) -> Option<i32> {
+        self.get(MAX_BLOCK_SIZE_ID)
+    }
+
+    /// Max block cost
+    pub fn max_block_cost(&self) -> Option<i32> {
+        self.get(MAX_BLOCK_COST_ID)
+    }
+
+    /// Token access cost
+    pub fn token_access_cost(&self) -> Option<i32> {
+        self.get(TOKEN_ACCESS_COST_ID)
+    }
+
+    /// Cost per one transaction input
+    pub fn input_cost(&self) -> Option<i32> {
+        self.get(INPUT_COST_ID)
+    }
+
+    /// Cost per one data input
+    pub fn data_input_cost(&self) -> Option<i32> {
+        self.get(DATA_INPUT_COST_ID)
+    }
+
+    /// Cost per one transaction output
+    pub fn output_cost(&self) -> Option<i32> {
+        self.get(OUTPUT_COST_ID)
+    }
+
+    /// Block version
+    pub fn block_version(&self) -> Option<i32> {
+        self.get(BLOCK_VERSION_ID)
+    }
+
+    /// Parse the parameters written in the block extension section (keys with the system
+    /// parameters prefix, 4-byte big-endian values). Other fields are ignored.
+    pub fn parse_extension(fields: &[ExtensionField]) -> Result<Parameters, ParametersError> {
+        let mut table = BTreeMap::new();
+        for (key, value) in fields {
+            let id = key[1] as i8;
+            if key[0] != SYSTEM_PARAMETERS_PREFIX || id == SOFT_FORK_DISABLING_RULES_ID {
+                continue;
+            }
+            let bytes: [u8; 4] =
+                value
+                    .as_slice()
+                    .try_into()
+                    .map_err(|_| ParametersError::InvalidValue {
+                        id,
+                        value: value.clone(),
+                    })?;
+            table.insert(id, i32::from_be_bytes(bytes));
+        }
+        Ok(Parameters::new(table))
+    }
+
+    /// Apply the votes collected during the voting epoch. A parameter is changed by one step
+    /// (positive id - increase, negative id - decrease) if more than a half of the epoch blocks
+    /// voted for it, within the parameter's limits.
+    pub fn update(&self, epoch_votes: &HashMap<i8, u32>, voting_epoch_length: u32) -> Parameters {
+        let mut table = self.table.clone();
+        for (signed_id, count) in epoch_votes {
+            let id = signed_id.wrapping_abs();
+            if id >= SOFT_FORK_ID || *count <= voting_epoch_length / 2 {
+                continue;
+            }
+            if let Some(current) = self.get(id) {
+                let step = step(id, current);
+                let new_value = if *signed_id > 0 && current < max_value(id) {
+                    current + step
+                } else if *signed_id < 0 && current > min_value(id) {
+                    current - step
+                } else {
+                    current
+                };
+                table.insert(id, new_value);
+            }
+        }
+        Parameters::new(table)
+    }
+}
+
+fn step(id: i8, current: i32) -> i32 {
+    match id {
+        STORAGE_FEE_FACTOR_ID => 25000,
+        MIN_VALUE_PER_BYTE_ID => 10,
+        _ => std::cmp::max(1, current / 100),
+    }
+}
+
+fn min_value(id: i8) -> i32 {
+    match id {
+        MAX_BLOCK_SIZE_ID | MAX_BLOCK_COST_ID => 16 * 1024,
+        _ => 0,
+    }
+}
+
+fn max_value(id: i8) -> i32 {
+    match id {
+        STORAGE_FEE_FACTOR_ID => 2500000,
+        MIN_VALUE_PER_BYTE_ID => 10000,
+        _ => i32::MAX / 2,
+    }
+}
+
+/// Count the parameter votes (non-zero vote bytes) of the epoch block headers
+pub fn count_votes<'a, I: IntoIterator<Item = &'a Votes>>(votes: I) -> HashMap<i8, u32> {
+    let mut counts = HashMap::new();
+    votes
+        .into_iter()
+        .flat_map(|v| v.0.iter())
+        .filter(|b| **b != 0)
+        .for_each(|b| *counts.entry(*b as i8).or_insert(0) += 1);
+    counts
+}
+
+/// Active parameters by voting epochs, to get the parameters at any height
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct ParametersHistory {
+    voting_epoch_length: u32,
+    genesis_parameters: Parameters,
+    /// epoch start height -> parameters active since this height
+    epochs: BTreeMap<u32, Parameters>,
+}
+
+impl ParametersHistory {
+    /// Create new history with the parameters active from the genesis block
+    pub fn new(voting_epoch_length: u32, genesis_parameters: Parameters) -> ParametersHistory {
+        ParametersHistory {
+            voting_epoch_length,
+            genesis_parameters,
+            epochs: BTreeMap::new(),
+        }
+    }
+
+    /// Mainnet history, starting with the launch parameters
+    pub fn mainnet() -> ParametersHistory {
+        ParametersHistory::new(MAINNET_VOTING_EPOCH_LENGTH, Parameters::default())
+    }
+
+    /// Voting epoch length (in blocks)
+    pub fn voting_epoch_length(&self) -> u32 {
+        self.voting_epoch_length
+    }
+
+    /// Parameters active at the given height
+    pub fn parameters_at(&self, height: u32) -> &Parameters {
+        self.epochs
+            .range(..=height)
+            .next_back()
+            .map(|(_, p)| p)
+            .unwrap_or(&self.genesis_parameters)
+    }
+
+    /// Apply the parameters from the extension section of the block at the voting epoch start.
+    /// Parameters missing in the extension keep their previous values.
+    pub fn apply_extension(
+        &mut self,
+        height: u32,
+        fields: &[ExtensionField],
+    ) -> Result<&Parameters, ParametersError> {
+        self.check_epoch_start(height)?;
+        let parsed = Parameters::parse_extension(fields)?;
+        let mut table = self.parameters_before(height).table.clone();
+        table.extend(parsed.table);
+        Ok(self.insert(height, Parameters::new(table)))
+    }
+
+    /// Apply the votes of the previous epoch block headers at the voting epoch start
+    /// (see [`count_votes`])
+    pub fn apply_votes(
+        &mut self,
+        height: u32,
+        epoch_votes: &HashMap<i8, u32>,
+    ) -> Result<&Parameters, ParametersError> {
+        self.check_epoch_start(height)?;
+        let parameters = self
+            .parameters_before(height)
+            .update(epoch_votes, self.voting_epoch_length);
+        Ok(self.insert(height, parameters))
+    }
+
+    fn check_epoch_start(&self, height: u32) -> Result<(), ParametersError> {
+        if height == 0 || height % self.voting_epoch_length != 0 {
+            return Err(ParametersError::NotEpochStart {
+                height,
+                epoch_length: self.voting_epoch_length,
+            });
+        }
+        Ok(())
+    }
+
+    fn parameters_before(&self, height: u32) -> &Parameters {
+        self.parameters_at(height.saturating_sub(1))
+    }
+
+    fn insert(&mut self, height: u32, parameters: Parameters) -> &Parameters {
+        self.epochs.insert(height, parameters);
+        self.parameters_at(height)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_extension() {
+        let fields = vec![
+            (
+                [0x00, MAX_BLOCK_COST_ID as u8],
+                2000000i32.to_be_bytes().to_vec(),
+            ),
+            ([0x00, BLOCK_VERSION_ID as u8], 2i32.to_be_bytes().to_vec()),
+            ([0x00, SOFT_FORK_DISABLING_RULES_ID as u8], vec![0]),
+            // interlinks
+            ([0x01, 0x00], vec![1, 2, 3]),
+        ];
+        let parameters = Parameters::parse_extension(&fields).unwrap();
+        assert_eq!(parameters.table().len(), 2);
+        assert_eq!(parameters.max_block_cost(), Some(2000000));
+        assert_eq!(parameters.block_version(), Some(2));
+        assert_eq!(
+            Parameters::parse_extension(&[([0x00, 1], vec![1, 2])]),
+            Err(ParametersError::InvalidValue {
+                id: 1,
+                value: vec![1, 2]
+            })
+        );
+    }
+
+    #[test]
+    fn test_update() {
+        let params = Parameters::default();
+        let mut votes = HashMap::new();
+        votes.insert(MAX_BLOCK_COST_ID, 513);
+        votes.insert(-MIN_VALUE_PER_BYTE_ID, 513);
+        // not enough votes
+        votes.insert(MAX_BLOCK_SIZE_ID, 512);
+        let updated = params.update(&votes, 1024);
+        assert_eq!(updated.max_block_cost(), Some(1010000));
+        assert_eq!(updated.min_value_per_byte(), Some(350));
+        assert_eq!(updated.max_block_size(), params.max_block_size());
+        assert_eq!(updated.storage_fee_factor(), params.storage_fee_factor());
+    }
+
+    #[test]
+    fn test_update_limits() {
+        let params = Parameters::new(vec![(STORAGE_FEE_FACTOR_ID, 2500000)].into_iter().collect());
+        let mut votes = HashMap::new();
+        votes.insert(STORAGE_FEE_FACTOR_ID, 1024);
+        assert_eq!(params.update(&votes, 1024), params);
+    }
+
+    #[test]
+    fn test_count_votes() {
+        let votes = vec![
+            Votes([MAX_BLOCK_COST_ID as u8, 0, 0]),
+            Votes([MAX_BLOCK_COST_ID as u8, -MIN_VALUE_PER_BYTE_ID as u8, 0]),
+            Votes([0, 0, 0]),
+        ];
+        let counts = count_votes(&votes);
+        assert_eq!(counts.len(), 2);
+        assert_eq!(counts[&MAX_BLOCK_COST_ID], 2);
+        assert_eq!(counts[&-MIN_VALUE_PER_BYTE_ID], 1);
+    }
+
+    #[test]
+    fn test_history() {
+        let mut history = ParametersHistory::new(10, Parameters::default());
+        let mut votes = HashMap::new();
+        votes.insert(MAX_BLOCK_COST_ID, 6);
+        history.apply_votes(10, &votes).unwrap();
+        let fields = vec![(
+            [0x00, MAX_BLOCK_SIZE_ID as u8],
+            1000000i32.to_be_bytes().to_vec(),
+        )];
+        history.apply_extension(30, &fields).unwrap();
+        assert_eq!(history.parameters_at(0), &Parameters::default());
+        assert_eq!(history.parameters_at(9), &Parameters::default());
+        assert_eq!(history.parameters_at(10).max_block_cost(), Some(1010000));
+        assert_eq!(history.parameters_at(29).max_block_cost(), Some(1010000));
+        assert_eq!(history.parameters_at(29).max_block_size(), Some(512 * 1024));
+        assert_eq!(history.parameters_at(30).max_block_cost(), Some(1010000));
+        assert_eq!(history.parameters_at(100).max_block_size(), Some(1000000));
+        assert_eq!(
+            history.apply_votes(15, &votes),
+            Err(ParametersError::NotEpochStart {
+                height: 15,
+                epoch_length: 10
+            })
+        );
+    }
+}
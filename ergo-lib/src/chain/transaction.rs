@@ -1,6 +1,7 @@
 //! Ergo transaction
 
 mod data_input;
+pub mod graph;
 pub mod input;
 pub mod reduced;
 pub mod unsigned;
@@ -187,7 +188,7 @@ impl SigmaSerializable for Transaction {
         // This optimization is crucial to allow up to MaxTokens (== 255) in a box.
         // Without it total size of all token ids 255 * 32 = 8160, way beyond MaxBoxSize (== 4K)
         let distinct_token_ids = distinct_token_ids(self.output_candidates.clone());
-        w.put_u32(u32::try_from(distinct_token_ids.len()).unwrap())?;
+        w.put_usize_as_u32_unwrapped(distinct_token_ids.len())?;
         distinct_token_ids
             .iter()
             .try_for_each(|t_id| t_id.sigma_serialize(w))?;
@@ -321,24 +322,48 @@ impl TryFrom<json::transaction::TransactionJson> for Transaction {
     }
 }
 
-#[cfg(test)]
-pub mod tests {
-    use std::convert::TryInto;
-
+/// Arbitrary impl
+#[cfg(feature = "arbitrary")]
+#[allow(clippy::unwrap_used)]
+pub mod arbitrary {
     use super::*;
 
-    use ergotree_ir::serialization::sigma_serialize_roundtrip;
     use proptest::prelude::*;
     use proptest::{arbitrary::Arbitrary, collection::vec};
+    use std::ops::Range;
+
+    /// Parameters for arbitrary (unsigned) transaction generation.
+    /// Failing cases are shrunk by dropping inputs, data inputs and outputs down to the lower
+    /// bound of their ranges, then by shrinking the inputs and outputs themselves (e.g. output
+    /// boxes shrink to P2PK trees without tokens and with the minimal value).
+    #[derive(PartialEq, Eq, Debug, Clone)]
+    pub struct ArbTransactionParams {
+        /// Number of inputs
+        pub inputs: Range<usize>,
+        /// Number of data inputs
+        pub data_inputs: Range<usize>,
+        /// Number of outputs
+        pub outputs: Range<usize>,
+    }
+
+    impl Default for ArbTransactionParams {
+        fn default() -> Self {
+            ArbTransactionParams {
+                inputs: 1..10,
+                data_inputs: 0..10,
+                outputs: 1..10,
+            }
+        }
+    }
 
     impl Arbitrary for Transaction {
-        type Parameters = ();
+        type Parameters = ArbTransactionParams;
 
-        fn arbitrary_with(_args: Self::Parameters) -> Self::Strategy {
+        fn arbitrary_with(args: Self::Parameters) -> Self::Strategy {
             (
-                vec(any::<Input>(), 1..10),
-                vec(any::<DataInput>(), 0..10),
-                vec(any::<ErgoBoxCandidate>(), 1..10),
+                vec(any::<Input>(), args.inputs),
+                vec(any::<DataInput>(), args.data_inputs),
+                vec(any::<ErgoBoxCandidate>(), args.outputs),
             )
                 .prop_map(|(inputs, data_inputs, outputs)| {
                     Self::new(
@@ -352,6 +377,19 @@ pub mod tests {
         }
         type Strategy = BoxedStrategy<Self>;
     }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+#[allow(clippy::panic)]
+pub mod tests {
+    use super::arbitrary::ArbTransactionParams;
+    use super::*;
+
+    use ergotree_ir::serialization::sigma_serialize_roundtrip;
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+    use proptest::test_runner::{TestError, TestRunner};
 
     proptest! {
 
@@ -362,12 +400,60 @@ pub mod tests {
             prop_assert_eq![sigma_serialize_roundtrip(&v), v];
         }
 
+        #[test]
+        fn tx_parse_corrupted(v in any::<Transaction>(), pos in any::<usize>(), byte in any::<u8>()) {
+            // fails with an error (no panics) on the corrupted and truncated bytes
+            let bytes = v.sigma_serialize_bytes().unwrap();
+            let pos = pos % bytes.len();
+            let mut corrupted = bytes.clone();
+            corrupted[pos] = byte;
+            let _ = Transaction::sigma_parse_bytes(&corrupted);
+            prop_assert!(Transaction::sigma_parse_bytes(&bytes[..pos]).is_err());
+        }
+
+        #[test]
+        fn tx_parse_arbitrary_bytes(bytes in vec(any::<u8>(), 0..512)) {
+            let _ = Transaction::sigma_parse_bytes(&bytes);
+        }
+
 
         #[test]
         fn tx_id_ser_roundtrip(v in any::<TxId>()) {
             prop_assert_eq![sigma_serialize_roundtrip(&v), v];
         }
 
+        #[test]
+        fn tx_arbitrary_params(v in any_with::<Transaction>(ArbTransactionParams {
+            inputs: 1..2,
+            data_inputs: 0..1,
+            outputs: 1..3,
+        })) {
+            prop_assert_eq!(v.inputs.len(), 1);
+            prop_assert!(v.data_inputs.is_none());
+            prop_assert!(v.output_candidates.len() <= 2);
+        }
+
+    }
+
+    #[test]
+    fn tx_arbitrary_shrinking() {
+        // failing case is shrunk to the minimal transaction that still fails
+        let mut runner = TestRunner::new(ProptestConfig {
+            failure_persistence: None,
+            ..ProptestConfig::default()
+        });
+        let result = runner.run(&any::<Transaction>(), |tx| {
+            prop_assert!(tx.output_candidates.len() < 3);
+            Ok(())
+        });
+        match result {
+            Err(TestError::Fail(_, tx)) => {
+                assert_eq!(tx.inputs.len(), 1);
+                assert!(tx.data_inputs.is_none());
+                assert_eq!(tx.output_candidates.len(), 3);
+            }
+            _ => panic!("expected a failing case, got {:?}", result),
+        }
     }
 
     #[test]
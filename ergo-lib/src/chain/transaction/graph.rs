@@ -0,0 +1,280 @@
+//! Graph of the box-spending relationships across a set of transactions (e.g. a chain of
+//! unconfirmed transactions), to order them for the submission
+
+use std::collections::HashMap;
+
+use ergotree_ir::chain::ergo_box::BoxId;
+use thiserror::Error;
+
+use super::Transaction;
+use super::TxId;
+
+/// Dependency of a transaction on an output of another transaction in the set
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct TxGraphEdge {
+    /// Index of the transaction which created the box
+    pub creator: usize,
+    /// Index of the transaction which spends (or reads) the box
+    pub spender: usize,
+    /// Box id
+    pub box_id: BoxId,
+    /// true if the box is a data input of the spender (read, but not spent)
+    pub is_data_input: bool,
+}
+
+/// Errors of the transaction graph building and ordering
+#[derive(Error, PartialEq, Eq, Debug, Clone)]
+pub enum TxGraphError {
+    /// Transaction is in the set more than once
+    #[error("Transaction {0:?} is in the set more than once")]
+    DuplicateTx(TxId),
+    /// Transactions depend on each other (directly or through other transactions), there is no
+    /// order to submit them in
+    #[error("Transactions {0:?} depend on each other")]
+    Cycle(Vec<TxId>),
+}
+
+/// Directed graph of the transactions in the set, an edge goes from the transaction which created
+/// a box to the transaction which spends it (or reads it as a data input). Boxes created outside
+/// of the set are not in the graph.
+#[derive(PartialEq, Debug, Clone)]
+pub struct TxGraph {
+    txs: Vec<Transaction>,
+    edges: Vec<TxGraphEdge>,
+}
+
+impl TxGraph {
+    /// Build the graph, the transactions keep their indices in the given set
+    pub fn build(txs: Vec<Transaction>) -> Result<TxGraph, TxGraphError> {
+        let mut tx_ids: HashMap<TxId, usize> = HashMap::new();
+        let mut creators: HashMap<BoxId, usize> = HashMap::new();
+        for (idx, tx) in txs.iter().enumerate() {
+            if tx_ids.insert(tx.id(), idx).is_some() {
+                return Err(TxGraphError::DuplicateTx(tx.id()));
+            }
+            tx.outputs.iter().for_each(|b| {
+                creators.insert(b.box_id(), idx);
+            });
+        }
+        let mut edges = Vec::new();
+        for (spender, tx) in txs.iter().enumerate() {
+            let spent = tx.inputs.iter().map(|i| (&i.box_id, false));
+            let read = tx
+                .data_inputs
+                .iter()
+                .flat_map(|dis| dis.iter())
+                .map(|di| (&di.box_id, true));
+            for (box_id, is_data_input) in spent.chain(read) {
+                if let Some(creator) = creators.get(box_id) {
+                    edges.push(TxGraphEdge {
+                        creator: *creator,
+                        spender,
+                        box_id: box_id.clone(),
+                        is_data_input,
+                    });
+                }
+            }
+        }
+        Ok(TxGraph { txs, edges })
+    }
+
+    /// Transactions (in the order of the set the graph is built from)
+    pub fn transactions(&self) -> &[Transaction] {
+        self.txs.as_slice()
+    }
+
+    /// Dependencies between the transactions
+    pub fn edges(&self) -> &[TxGraphEdge] {
+        self.edges.as_slice()
+    }
+
+    /// Indices of the transactions which created the boxes spent (or read) by the given
+    /// transaction, without duplicates
+    pub fn parents(&self, tx_idx: usize) -> Vec<usize> {
+        let mut res: Vec<usize> = Vec::new();
+        self.edges
+            .iter()
+            .filter(|e| e.spender == tx_idx && !res.contains(&e.creator))
+            .for_each(|e| res.push(e.creator));
+        res
+    }
+
+    /// Indices of the transactions which spend (or read) the boxes created by the given
+    /// transaction, without duplicates
+    pub fn children(&self, tx_idx: usize) -> Vec<usize> {
+        let mut res: Vec<usize> = Vec::new();
+        self.edges
+            .iter()
+            .filter(|e| e.creator == tx_idx && !res.contains(&e.spender))
+            .for_each(|e| res.push(e.spender));
+        res
+    }
+
+    /// Indices of the transactions which depend only on the boxes created outside of the set
+    pub fn roots(&self) -> Vec<usize> {
+        (0..self.txs.len())
+            .filter(|idx| !self.edges.iter().any(|e| e.spender == *idx))
+            .collect()
+    }
+
+    /// Indices of the transactions in the order to submit them in (every transaction goes after
+    /// the transactions it depends on, otherwise in the order of the set).
+    /// Returns [`TxGraphError::Cycle`] with the transactions which depend on each other (and the
+    /// transactions depending on them) if there is no such order.
+    pub fn topological_order(&self) -> Result<Vec<usize>, TxGraphError> {
+        let mut in_degree: Vec<usize> = (0..self.txs.len())
+            .map(|idx| self.parents(idx).len())
+            .collect();
+        let mut ready: Vec<usize> = (0..self.txs.len())
+            .filter(|idx| in_degree[*idx] == 0)
+            .rev()
+            .collect();
+        let mut res = Vec::with_capacity(self.txs.len());
+        while let Some(idx) = ready.pop() {
+            res.push(idx);
+            let mut unblocked: Vec<usize> = Vec::new();
+            for child in self.children(idx) {
+                in_degree[child] -= 1;
+                if in_degree[child] == 0 {
+                    unblocked.push(child);
+                }
+            }
+            // keep the lowest index on top (the order of the set for the independent txs)
+            ready.extend(unblocked);
+            ready.sort_unstable_by(|a, b| b.cmp(a));
+        }
+        if res.len() == self.txs.len() {
+            Ok(res)
+        } else {
+            Err(TxGraphError::Cycle(
+                (0..self.txs.len())
+                    .filter(|idx| in_degree[*idx] > 0)
+                    .map(|idx| self.txs[idx].id())
+                    .collect(),
+            ))
+        }
+    }
+
+    /// Transactions in the order to submit them in (see [`TxGraph::topological_order`])
+    pub fn ordered_transactions(&self) -> Result<Vec<&Transaction>, TxGraphError> {
+        Ok(self
+            .topological_order()?
+            .into_iter()
+            .map(|idx| &self.txs[idx])
+            .collect())
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use ergotree_interpreter::sigma_protocol::prover::ContextExtension;
+    use ergotree_interpreter::sigma_protocol::prover::ProofBytes;
+    use ergotree_ir::chain::ergo_box::ErgoBoxCandidate;
+    use sigma_test_util::force_any_val;
+
+    use super::*;
+    use crate::chain::transaction::input::prover_result::ProverResult;
+    use crate::chain::transaction::DataInput;
+    use crate::chain::transaction::Input;
+    use crate::chain::transaction::TxIoVec;
+
+    fn tx(inputs: Vec<BoxId>, data_inputs: Vec<BoxId>) -> Transaction {
+        let inputs: Vec<Input> = inputs
+            .into_iter()
+            .map(|box_id| {
+                Input::new(
+                    box_id,
+                    ProverResult {
+                        proof: ProofBytes::Empty,
+                        extension: ContextExtension::empty(),
+                    },
+                )
+            })
+            .collect();
+        let data_inputs = if data_inputs.is_empty() {
+            None
+        } else {
+            Some(TxIoVec::from_vec(data_inputs.into_iter().map(DataInput::from).collect()).unwrap())
+        };
+        Transaction::new(
+            TxIoVec::from_vec(inputs).unwrap(),
+            data_inputs,
+            TxIoVec::from_vec(vec![
+                force_any_val::<ErgoBoxCandidate>(),
+                force_any_val::<ErgoBoxCandidate>(),
+            ])
+            .unwrap(),
+        )
+        .unwrap()
+    }
+
+    fn output(tx: &Transaction, idx: usize) -> BoxId {
+        tx.outputs[idx].box_id()
+    }
+
+    #[test]
+    fn chained_txs() {
+        // tx0 <- tx1 <- tx2, tx1 <- tx3 (data input), independent tx4
+        let tx0 = tx(vec![force_any_val::<BoxId>()], vec![]);
+        let tx1 = tx(vec![output(&tx0, 0), output(&tx0, 1)], vec![]);
+        let tx2 = tx(vec![output(&tx1, 0)], vec![]);
+        let tx3 = tx(vec![force_any_val::<BoxId>()], vec![output(&tx1, 1)]);
+        let tx4 = tx(vec![force_any_val::<BoxId>()], vec![]);
+        let set = vec![
+            tx3.clone(),
+            tx2.clone(),
+            tx4.clone(),
+            tx1.clone(),
+            tx0.clone(),
+        ];
+        let graph = TxGraph::build(set).unwrap();
+        assert_eq!(graph.edges().len(), 4);
+        assert!(graph.edges().contains(&TxGraphEdge {
+            creator: 3,
+            spender: 0,
+            box_id: output(&tx1, 1),
+            is_data_input: true,
+        }));
+        assert_eq!(graph.parents(3), vec![4]);
+        assert_eq!(graph.children(3), vec![0, 1]);
+        assert_eq!(graph.roots(), vec![2, 4]);
+        assert_eq!(graph.topological_order().unwrap(), vec![2, 4, 3, 0, 1]);
+        assert_eq!(
+            graph.ordered_transactions().unwrap(),
+            vec![&tx4, &tx0, &tx1, &tx3, &tx2]
+        );
+    }
+
+    #[test]
+    fn duplicate_tx() {
+        let tx0 = tx(vec![force_any_val::<BoxId>()], vec![]);
+        assert_eq!(
+            TxGraph::build(vec![tx0.clone(), tx0.clone()]),
+            Err(TxGraphError::DuplicateTx(tx0.id()))
+        );
+    }
+
+    #[test]
+    fn cycle() {
+        let mut tx0 = tx(vec![force_any_val::<BoxId>()], vec![]);
+        let tx1 = tx(vec![output(&tx0, 0)], vec![]);
+        let tx2 = tx(vec![output(&tx1, 0)], vec![]);
+        let tx3 = tx(vec![force_any_val::<BoxId>()], vec![]);
+        // inconsistent set (the tx id and the outputs are not updated), tx0 spends an output of
+        // tx1 which spends an output of tx0
+        tx0.inputs = TxIoVec::from_vec(vec![Input::new(
+            output(&tx1, 1),
+            ProverResult {
+                proof: ProofBytes::Empty,
+                extension: ContextExtension::empty(),
+            },
+        )])
+        .unwrap();
+        let graph = TxGraph::build(vec![tx0.clone(), tx1.clone(), tx2.clone(), tx3]).unwrap();
+        assert_eq!(
+            graph.topological_order(),
+            Err(TxGraphError::Cycle(vec![tx0.id(), tx1.id(), tx2.id()]))
+        );
+    }
+}
@@ -73,10 +73,10 @@ impl SigmaSerializable for ProverResult {
     }
 }
 
-#[cfg(test)]
-mod tests {
+/// Arbitrary impl
+#[cfg(feature = "arbitrary")]
+mod arbitrary {
     use super::*;
-    use ergotree_ir::serialization::sigma_serialize_roundtrip;
     use proptest::prelude::*;
 
     impl Arbitrary for ProverResult {
@@ -89,6 +89,14 @@ mod tests {
                 .boxed()
         }
     }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use ergotree_ir::serialization::sigma_serialize_roundtrip;
+    use proptest::prelude::*;
 
     proptest! {
 
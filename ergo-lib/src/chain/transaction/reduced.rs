@@ -138,17 +138,19 @@ impl SigmaSerializable for ReducedTransaction {
 }
 
 #[cfg(test)]
+#[allow(clippy::unwrap_used)]
 mod tests {
     use super::*;
 
+    use crate::chain::transaction::arbitrary::ArbTransactionParams;
     use ergotree_ir::serialization::sigma_serialize_roundtrip;
     use proptest::prelude::*;
 
     impl Arbitrary for ReducedTransaction {
-        type Parameters = ();
+        type Parameters = ArbTransactionParams;
 
-        fn arbitrary_with(_args: Self::Parameters) -> Self::Strategy {
-            (any::<UnsignedTransaction>(), any::<SigmaBoolean>())
+        fn arbitrary_with(args: Self::Parameters) -> Self::Strategy {
+            (any_with::<UnsignedTransaction>(args), any::<SigmaBoolean>())
                 .prop_map(|(unsigned_tx, sb)| Self {
                     unsigned_tx: unsigned_tx.clone(),
                     reduced_inputs: unsigned_tx.inputs.mapped(|unsigned_input| ReducedInput {
@@ -138,20 +138,22 @@ impl TryFrom<json::transaction::UnsignedTransactionJson> for UnsignedTransaction
 }
 
 #[cfg(test)]
+#[allow(clippy::unwrap_used)]
 pub mod tests {
     use super::*;
 
+    use crate::chain::transaction::arbitrary::ArbTransactionParams;
     use proptest::prelude::*;
     use proptest::{arbitrary::Arbitrary, collection::vec};
 
     impl Arbitrary for UnsignedTransaction {
-        type Parameters = ();
+        type Parameters = ArbTransactionParams;
 
-        fn arbitrary_with(_args: Self::Parameters) -> Self::Strategy {
+        fn arbitrary_with(args: Self::Parameters) -> Self::Strategy {
             (
-                vec(any::<UnsignedInput>(), 1..10),
-                vec(any::<DataInput>(), 0..10),
-                vec(any::<ErgoBoxCandidate>(), 1..10),
+                vec(any::<UnsignedInput>(), args.inputs),
+                vec(any::<DataInput>(), args.data_inputs),
+                vec(any::<ErgoBoxCandidate>(), args.outputs),
             )
                 .prop_map(|(inputs, data_inputs, outputs)| {
                     Self::new(
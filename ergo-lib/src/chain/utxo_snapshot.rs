@@ -0,0 +1,630 @@
+//! UTXO set snapshot (bootstrapping) chunks in the format of the node
+//!
+//! The node serves the UTXO set AVL+ tree (boxes by their ids) split into a manifest (the top
+//! levels of the tree, `manifestDepth` of them) and the subtrees below it, each chunk is
+//! identified by the label (hash) of its root node. The labels of all nodes are checked on
+//! parsing, so with the manifest digest matching the `stateRoot` of a header the boxes of the
+//! snapshot are the UTXO set after the block, without replaying the chain.
+//!
+//! Chunk bytes:
+//! ```text
+//! manifest: <root height: byte> <manifest depth: byte> <nodes, pre-order, down to the depth>
+//! subtree:  <nodes, pre-order>
+//! internal node: 0x00 <balance: byte> <key: 32> <left label: 32> <right label: 32>
+//! leaf node:     0x01 <key: 32> <value length: int32 BE> <value> <next leaf key: 32>
+//! ```
+
+use std::collections::HashMap;
+
+use ergotree_ir::chain::digest32::blake2b256_hash;
+use ergotree_ir::chain::digest32::ADDigest;
+use ergotree_ir::chain::digest32::Digest32;
+use ergotree_ir::chain::ergo_box::BoxId;
+use ergotree_ir::chain::ergo_box::ErgoBox;
+use ergotree_ir::chain::header::Header;
+use ergotree_ir::serialization::SigmaParsingError;
+use ergotree_ir::serialization::SigmaSerializable;
+use thiserror::Error;
+
+const INTERNAL_NODE_PREFIX: u8 = 0;
+const LEAF_NODE_PREFIX: u8 = 1;
+/// Prefixes of the node bytes hashed for the node labels
+const INTERNAL_NODE_LABEL_PREFIX: u8 = 1;
+const LEAF_NODE_LABEL_PREFIX: u8 = 0;
+/// AVL+ tree height limit (the tree of 2^64 leaves is lower), so that the parsing recursion is
+/// bounded on the malformed chunks
+const MAX_TREE_HEIGHT: usize = 128;
+
+/// UTXO snapshot errors
+#[derive(Error, PartialEq, Eq, Debug, Clone)]
+pub enum UtxoSnapshotError {
+    /// Chunk bytes end before the node
+    #[error("Unexpected end of the chunk bytes")]
+    UnexpectedEnd,
+    /// Unknown node prefix
+    #[error("Invalid node prefix {0}")]
+    InvalidNodePrefix(u8),
+    /// Bytes left after the nodes of the chunk
+    #[error("{0} bytes left after the chunk nodes")]
+    TrailingBytes(usize),
+    /// Tree is higher than the limit
+    #[error("Tree height exceeds {0}")]
+    TreeTooHigh(usize),
+    /// Label of the child node is not the one stored in its parent
+    #[error("Label mismatch of the child node: expected {expected:?}, got {actual:?}")]
+    LabelMismatch {
+        /// label stored in the parent node
+        expected: Digest32,
+        /// label of the child node
+        actual: Digest32,
+    },
+    /// Manifest digest is not the expected state root
+    #[error("Manifest digest {actual:?} is not the state root {expected:?}")]
+    DigestMismatch {
+        /// state root
+        expected: ADDigest,
+        /// manifest digest
+        actual: ADDigest,
+    },
+    /// Subtree is not referenced by the manifest
+    #[error("Subtree {0:?} is not in the manifest")]
+    UnknownSubtree(Digest32),
+    /// Subtree referenced by the manifest is not added
+    #[error("Subtree {0:?} is missing")]
+    MissingSubtree(Digest32),
+    /// Box parsing error (leaf value)
+    #[error("Box parsing error: {0}")]
+    BoxParsingError(#[from] SigmaParsingError),
+    /// Id of the box is not the leaf key
+    #[error("Box id {actual:?} is not the leaf key {expected:?}")]
+    BoxIdMismatch {
+        /// leaf key
+        expected: BoxId,
+        /// id of the box in the leaf value
+        actual: BoxId,
+    },
+}
+
+/// Internal node of the UTXO set tree
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct InternalNode {
+    /// Balance of the node (height of the right subtree minus height of the left one)
+    pub balance: i8,
+    /// Key (the smallest key of the right subtree)
+    pub key: Digest32,
+    /// Label of the left child
+    pub left_label: Digest32,
+    /// Label of the right child
+    pub right_label: Digest32,
+    /// Children (left, right), None if they are in the subtree chunks (the nodes of the last
+    /// level of the manifest)
+    pub children: Option<Box<(AvlNode, AvlNode)>>,
+}
+
+/// Leaf node of the UTXO set tree
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct LeafNode {
+    /// Key (box id, all zeros for the leftmost sentinel leaf)
+    pub key: Digest32,
+    /// Value (serialized box, empty for the sentinel leaf)
+    pub value: Vec<u8>,
+    /// Key of the next leaf (all 0xFF for the rightmost leaf)
+    pub next_leaf_key: Digest32,
+}
+
+/// Node of the UTXO set tree
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum AvlNode {
+    /// Internal node
+    Internal(InternalNode),
+    /// Leaf node
+    Leaf(LeafNode),
+}
+
+impl AvlNode {
+    /// Label (hash) of the node
+    pub fn label(&self) -> Digest32 {
+        let mut bytes = Vec::new();
+        match self {
+            AvlNode::Internal(n) => {
+                bytes.push(INTERNAL_NODE_LABEL_PREFIX);
+                bytes.push(n.balance as u8);
+                bytes.extend_from_slice(n.left_label.0.as_ref());
+                bytes.extend_from_slice(n.right_label.0.as_ref());
+            }
+            AvlNode::Leaf(n) => {
+                bytes.push(LEAF_NODE_LABEL_PREFIX);
+                bytes.extend_from_slice(n.key.0.as_ref());
+                bytes.extend_from_slice(&n.value);
+                bytes.extend_from_slice(n.next_leaf_key.0.as_ref());
+            }
+        }
+        blake2b256_hash(&bytes)
+    }
+
+    /// Leaves of the node subtree (from left to right)
+    pub fn leaves(&self) -> Vec<&LeafNode> {
+        let mut res = Vec::new();
+        let mut stack = vec![self];
+        while let Some(node) = stack.pop() {
+            match node {
+                AvlNode::Internal(n) => {
+                    if let Some(children) = &n.children {
+                        stack.push(&children.1);
+                        stack.push(&children.0);
+                    }
+                }
+                AvlNode::Leaf(n) => res.push(n),
+            }
+        }
+        res
+    }
+
+    /// Labels of the children in the subtree chunks (from left to right)
+    fn subtree_labels(&self) -> Vec<Digest32> {
+        match self {
+            AvlNode::Internal(n) => match &n.children {
+                Some(children) => {
+                    let mut res = children.0.subtree_labels();
+                    res.extend(children.1.subtree_labels());
+                    res
+                }
+                None => vec![n.left_label.clone(), n.right_label.clone()],
+            },
+            AvlNode::Leaf(_) => vec![],
+        }
+    }
+
+    fn parse(r: &mut ChunkReader, level: usize, depth: usize) -> Result<Self, UtxoSnapshotError> {
+        if level > MAX_TREE_HEIGHT {
+            return Err(UtxoSnapshotError::TreeTooHigh(MAX_TREE_HEIGHT));
+        }
+        match r.get_u8()? {
+            INTERNAL_NODE_PREFIX => {
+                let balance = r.get_u8()? as i8;
+                let key = r.get_digest()?;
+                let left_label = r.get_digest()?;
+                let right_label = r.get_digest()?;
+                let children = if level < depth {
+                    let left = AvlNode::parse(r, level + 1, depth)?;
+                    check_label(&left, &left_label)?;
+                    let right = AvlNode::parse(r, level + 1, depth)?;
+                    check_label(&right, &right_label)?;
+                    Some(Box::new((left, right)))
+                } else {
+                    None
+                };
+                Ok(AvlNode::Internal(InternalNode {
+                    balance,
+                    key,
+                    left_label,
+                    right_label,
+                    children,
+                }))
+            }
+            LEAF_NODE_PREFIX => {
+                let key = r.get_digest()?;
+                let value_len = u32::from_be_bytes(r.get_array()?) as usize;
+                let value = r.get_bytes(value_len)?.to_vec();
+                let next_leaf_key = r.get_digest()?;
+                Ok(AvlNode::Leaf(LeafNode {
+                    key,
+                    value,
+                    next_leaf_key,
+                }))
+            }
+            prefix => Err(UtxoSnapshotError::InvalidNodePrefix(prefix)),
+        }
+    }
+
+    fn write_bytes(&self, w: &mut Vec<u8>) {
+        match self {
+            AvlNode::Internal(n) => {
+                w.push(INTERNAL_NODE_PREFIX);
+                w.push(n.balance as u8);
+                w.extend_from_slice(n.key.0.as_ref());
+                w.extend_from_slice(n.left_label.0.as_ref());
+                w.extend_from_slice(n.right_label.0.as_ref());
+                if let Some(children) = &n.children {
+                    children.0.write_bytes(w);
+                    children.1.write_bytes(w);
+                }
+            }
+            AvlNode::Leaf(n) => {
+                w.push(LEAF_NODE_PREFIX);
+                w.extend_from_slice(n.key.0.as_ref());
+                w.extend_from_slice(&(n.value.len() as u32).to_be_bytes());
+                w.extend_from_slice(&n.value);
+                w.extend_from_slice(n.next_leaf_key.0.as_ref());
+            }
+        }
+    }
+}
+
+/// Manifest of the snapshot (the top levels of the UTXO set tree)
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct UtxoSnapshotManifest {
+    /// Height of the tree
+    pub root_height: u8,
+    /// Number of the tree levels in the manifest
+    pub manifest_depth: u8,
+    /// Root node of the tree
+    pub root: AvlNode,
+}
+
+impl UtxoSnapshotManifest {
+    /// Parse the manifest chunk (the labels of the nodes are checked)
+    pub fn parse(bytes: &[u8]) -> Result<Self, UtxoSnapshotError> {
+        let mut r = ChunkReader { bytes, pos: 0 };
+        let root_height = r.get_u8()?;
+        let manifest_depth = r.get_u8()?;
+        let root = AvlNode::parse(&mut r, 1, manifest_depth as usize)?;
+        r.check_end()?;
+        Ok(UtxoSnapshotManifest {
+            root_height,
+            manifest_depth,
+            root,
+        })
+    }
+
+    /// Serialize the manifest chunk
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut w = vec![self.root_height, self.manifest_depth];
+        self.root.write_bytes(&mut w);
+        w
+    }
+
+    /// Manifest id (label of the root node)
+    pub fn id(&self) -> Digest32 {
+        self.root.label()
+    }
+
+    /// Digest of the tree (root label and the tree height), the `stateRoot` of the header of the
+    /// block the snapshot is made after
+    pub fn digest(&self) -> ADDigest {
+        let mut bytes = [0u8; 33];
+        bytes[..32].copy_from_slice(self.id().0.as_ref());
+        bytes[32] = self.root_height;
+        bytes.into()
+    }
+
+    /// Check the manifest digest is the given state root
+    pub fn verify(&self, state_root: &ADDigest) -> Result<(), UtxoSnapshotError> {
+        let digest = self.digest();
+        if &digest != state_root {
+            return Err(UtxoSnapshotError::DigestMismatch {
+                expected: state_root.clone(),
+                actual: digest,
+            });
+        }
+        Ok(())
+    }
+
+    /// Ids of the subtree chunks of the snapshot (from left to right)
+    pub fn subtree_ids(&self) -> Vec<Digest32> {
+        self.root.subtree_labels()
+    }
+}
+
+/// Subtree chunk of the snapshot (below the manifest levels)
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct UtxoSnapshotSubtree {
+    /// Root node of the subtree
+    pub root: AvlNode,
+}
+
+impl UtxoSnapshotSubtree {
+    /// Parse the subtree chunk (the labels of the nodes are checked)
+    pub fn parse(bytes: &[u8]) -> Result<Self, UtxoSnapshotError> {
+        let mut r = ChunkReader { bytes, pos: 0 };
+        // all levels, deeper nodes fail with `TreeTooHigh`
+        let root = AvlNode::parse(&mut r, 1, MAX_TREE_HEIGHT + 1)?;
+        r.check_end()?;
+        Ok(UtxoSnapshotSubtree { root })
+    }
+
+    /// Serialize the subtree chunk
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut w = Vec::new();
+        self.root.write_bytes(&mut w);
+        w
+    }
+
+    /// Subtree id (label of the root node)
+    pub fn id(&self) -> Digest32 {
+        self.root.label()
+    }
+}
+
+/// Snapshot being downloaded: the manifest and the subtrees referenced by it
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct UtxoSnapshot {
+    manifest: UtxoSnapshotManifest,
+    subtrees: HashMap<Digest32, UtxoSnapshotSubtree>,
+}
+
+impl UtxoSnapshot {
+    /// Snapshot with no subtrees downloaded yet
+    pub fn new(manifest: UtxoSnapshotManifest) -> Self {
+        UtxoSnapshot {
+            manifest,
+            subtrees: HashMap::new(),
+        }
+    }
+
+    /// Manifest of the snapshot
+    pub fn manifest(&self) -> &UtxoSnapshotManifest {
+        &self.manifest
+    }
+
+    /// Add the subtree (it should be referenced by the manifest)
+    pub fn add_subtree(&mut self, subtree: UtxoSnapshotSubtree) -> Result<(), UtxoSnapshotError> {
+        let id = subtree.id();
+        if !self.manifest.subtree_ids().contains(&id) {
+            return Err(UtxoSnapshotError::UnknownSubtree(id));
+        }
+        self.subtrees.insert(id, subtree);
+        Ok(())
+    }
+
+    /// Ids of the subtrees not added yet (to download)
+    pub fn missing_subtrees(&self) -> Vec<Digest32> {
+        self.manifest
+            .subtree_ids()
+            .into_iter()
+            .filter(|id| !self.subtrees.contains_key(id))
+            .collect()
+    }
+
+    /// True if all subtrees of the manifest are added
+    pub fn is_complete(&self) -> bool {
+        self.missing_subtrees().is_empty()
+    }
+
+    /// Boxes of the snapshot (ordered by id), all subtrees should be added
+    pub fn boxes(&self) -> Result<Vec<ErgoBox>, UtxoSnapshotError> {
+        let mut leaves = self.manifest.root.leaves();
+        for id in self.manifest.subtree_ids() {
+            let subtree = self
+                .subtrees
+                .get(&id)
+                .ok_or(UtxoSnapshotError::MissingSubtree(id))?;
+            leaves.extend(subtree.root.leaves());
+        }
+        leaves
+            .into_iter()
+            .filter(|leaf| leaf.key != Digest32::zero())
+            .map(|leaf| {
+                let b = ErgoBox::sigma_parse_bytes(&leaf.value)?;
+                let expected = BoxId::from(leaf.key.clone());
+                if b.box_id() != expected {
+                    return Err(UtxoSnapshotError::BoxIdMismatch {
+                        expected,
+                        actual: b.box_id(),
+                    });
+                }
+                Ok(b)
+            })
+            .collect()
+    }
+
+    /// Boxes of the snapshot made after the block with the given header (the manifest digest is
+    /// checked against the header state root)
+    pub fn verified_boxes(&self, header: &Header) -> Result<Vec<ErgoBox>, UtxoSnapshotError> {
+        self.manifest.verify(&header.state_root)?;
+        self.boxes()
+    }
+}
+
+fn check_label(node: &AvlNode, expected: &Digest32) -> Result<(), UtxoSnapshotError> {
+    let actual = node.label();
+    if &actual != expected {
+        return Err(UtxoSnapshotError::LabelMismatch {
+            expected: expected.clone(),
+            actual,
+        });
+    }
+    Ok(())
+}
+
+struct ChunkReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> ChunkReader<'a> {
+    fn get_bytes(&mut self, len: usize) -> Result<&'a [u8], UtxoSnapshotError> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or(UtxoSnapshotError::UnexpectedEnd)?;
+        let res = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(res)
+    }
+
+    fn get_array<const N: usize>(&mut self) -> Result<[u8; N], UtxoSnapshotError> {
+        let mut res = [0u8; N];
+        res.copy_from_slice(self.get_bytes(N)?);
+        Ok(res)
+    }
+
+    fn get_u8(&mut self) -> Result<u8, UtxoSnapshotError> {
+        Ok(self.get_array::<1>()?[0])
+    }
+
+    fn get_digest(&mut self) -> Result<Digest32, UtxoSnapshotError> {
+        Ok(self.get_array::<32>()?.into())
+    }
+
+    fn check_end(&self) -> Result<(), UtxoSnapshotError> {
+        match self.bytes.len() - self.pos {
+            0 => Ok(()),
+            left => Err(UtxoSnapshotError::TrailingBytes(left)),
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use ergotree_ir::chain::ergo_box::box_value::BoxValue;
+    use ergotree_ir::chain::ergo_box::NonMandatoryRegisters;
+    use ergotree_ir::chain::tx_id::TxId;
+    use ergotree_ir::ergo_tree::ErgoTree;
+    use sigma_test_util::force_any_val;
+
+    fn ergo_box(index: u16) -> ErgoBox {
+        ErgoBox::new(
+            BoxValue::SAFE_USER_MIN,
+            force_any_val::<ErgoTree>(),
+            None,
+            NonMandatoryRegisters::empty(),
+            0,
+            TxId::zero(),
+            index,
+        )
+        .unwrap()
+    }
+
+    fn leaf(key: Digest32, value: Vec<u8>, next_leaf_key: Digest32) -> AvlNode {
+        AvlNode::Leaf(LeafNode {
+            key,
+            value,
+            next_leaf_key,
+        })
+    }
+
+    fn internal(balance: i8, key: Digest32, left: AvlNode, right: AvlNode) -> AvlNode {
+        AvlNode::Internal(InternalNode {
+            balance,
+            key,
+            left_label: left.label(),
+            right_label: right.label(),
+            children: Some(Box::new((left, right))),
+        })
+    }
+
+    fn without_children(node: &AvlNode) -> AvlNode {
+        match node {
+            AvlNode::Internal(n) => AvlNode::Internal(InternalNode {
+                children: None,
+                ..n.clone()
+            }),
+            AvlNode::Leaf(_) => node.clone(),
+        }
+    }
+
+    /// Tree of the sentinel leaf and two boxes, the manifest of depth 1 and two subtrees
+    fn snapshot_chunks() -> (Vec<ErgoBox>, Vec<u8>, Vec<Vec<u8>>) {
+        let mut boxes = vec![ergo_box(0), ergo_box(1)];
+        boxes.sort_by_key(|b| Digest32::from(b.box_id()).0);
+        let keys: Vec<Digest32> = boxes.iter().map(|b| b.box_id().into()).collect();
+        let left = leaf(Digest32::zero(), vec![], keys[0].clone());
+        let right = internal(
+            0,
+            keys[1].clone(),
+            leaf(
+                keys[0].clone(),
+                boxes[0].sigma_serialize_bytes().unwrap(),
+                keys[1].clone(),
+            ),
+            leaf(
+                keys[1].clone(),
+                boxes[1].sigma_serialize_bytes().unwrap(),
+                [0xFFu8; 32].into(),
+            ),
+        );
+        let manifest = UtxoSnapshotManifest {
+            root_height: 2,
+            manifest_depth: 1,
+            root: without_children(&internal(1, keys[0].clone(), left.clone(), right.clone())),
+        };
+        let subtrees = vec![
+            UtxoSnapshotSubtree { root: left }.to_bytes(),
+            UtxoSnapshotSubtree { root: right }.to_bytes(),
+        ];
+        (boxes, manifest.to_bytes(), subtrees)
+    }
+
+    #[test]
+    fn parse_snapshot() {
+        let (boxes, manifest_bytes, subtrees) = snapshot_chunks();
+        let manifest = UtxoSnapshotManifest::parse(&manifest_bytes).unwrap();
+        assert_eq!(manifest.to_bytes(), manifest_bytes);
+        assert_eq!(manifest.subtree_ids().len(), 2);
+        let mut header = force_any_val::<Header>();
+        header.state_root = manifest.digest();
+        let mut snapshot = UtxoSnapshot::new(manifest.clone());
+        for bytes in subtrees.iter() {
+            assert!(!snapshot.is_complete());
+            let subtree = UtxoSnapshotSubtree::parse(bytes).unwrap();
+            assert_eq!(&subtree.to_bytes(), bytes);
+            snapshot.add_subtree(subtree).unwrap();
+        }
+        assert!(snapshot.is_complete());
+        assert_eq!(snapshot.verified_boxes(&header).unwrap(), boxes);
+
+        header.state_root = ADDigest::zero();
+        assert!(matches!(
+            snapshot.verified_boxes(&header),
+            Err(UtxoSnapshotError::DigestMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn missing_and_unknown_subtrees() {
+        let (_, manifest_bytes, subtrees) = snapshot_chunks();
+        let manifest = UtxoSnapshotManifest::parse(&manifest_bytes).unwrap();
+        let mut snapshot = UtxoSnapshot::new(manifest.clone());
+        snapshot
+            .add_subtree(UtxoSnapshotSubtree::parse(&subtrees[0]).unwrap())
+            .unwrap();
+        assert_eq!(
+            snapshot.missing_subtrees(),
+            vec![manifest.subtree_ids()[1].clone()]
+        );
+        assert_eq!(
+            snapshot.boxes(),
+            Err(UtxoSnapshotError::MissingSubtree(
+                manifest.subtree_ids()[1].clone()
+            ))
+        );
+        let unknown = UtxoSnapshotSubtree {
+            root: leaf(Digest32::zero(), vec![1], Digest32::zero()),
+        };
+        assert!(matches!(
+            snapshot.add_subtree(unknown),
+            Err(UtxoSnapshotError::UnknownSubtree(_))
+        ));
+    }
+
+    #[test]
+    fn malformed_chunks() {
+        let (_, manifest_bytes, subtrees) = snapshot_chunks();
+        assert_eq!(
+            UtxoSnapshotManifest::parse(&manifest_bytes[..manifest_bytes.len() - 1]),
+            Err(UtxoSnapshotError::UnexpectedEnd)
+        );
+        let mut trailing = manifest_bytes.clone();
+        trailing.push(0);
+        assert_eq!(
+            UtxoSnapshotManifest::parse(&trailing),
+            Err(UtxoSnapshotError::TrailingBytes(1))
+        );
+        assert_eq!(
+            UtxoSnapshotSubtree::parse(&[2]),
+            Err(UtxoSnapshotError::InvalidNodePrefix(2))
+        );
+        // the changed box bytes of the left child leaf (after the root node) change its label
+        let mut tampered = subtrees[1].clone();
+        let leaf_value_start = (1 + 1 + 32 + 32 + 32) + (1 + 32 + 4);
+        tampered[leaf_value_start] ^= 1;
+        assert!(matches!(
+            UtxoSnapshotSubtree::parse(&tampered),
+            Err(UtxoSnapshotError::LabelMismatch { .. })
+        ));
+    }
+}
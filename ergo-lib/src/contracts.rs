@@ -0,0 +1,6 @@
+//! High-level helpers (box parsers and transaction templates) for the widely used mainnet contracts
+
+pub mod auction;
+pub mod spectrum;
+#[cfg(feature = "json")]
+pub mod template_bundle;
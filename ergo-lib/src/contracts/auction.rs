@@ -0,0 +1,605 @@
+//! Auction (NFT marketplace) boxes: auction box parser and transaction templates for placing a
+//! bid and settling the auction
+//!
+//! Auction box layout:
+//! - value: current bid (initially the starting value locked by the seller);
+//! - tokens: auctioned tokens;
+//! - R4: seller (`SigmaProp`, P2PK);
+//! - R5: current bidder (`SigmaProp`, P2PK), the seller until the first bid is placed;
+//! - R6: deadline height (`Int`);
+//! - R7: minimal bid step in nanoERGs (`Long`);
+//! - R8 (optional): auto-extension window (`Int`), see [`AuctionBox::deadline_after_bid`].
+//!
+//! The auction box is always the first input of the transaction (and the first output on bid).
+
+use std::convert::TryFrom;
+
+use ergotree_ir::chain::address::Address;
+use ergotree_ir::chain::ergo_box::box_value::BoxValue;
+use ergotree_ir::chain::ergo_box::box_value::BoxValueError;
+use ergotree_ir::chain::ergo_box::ErgoBox;
+use ergotree_ir::chain::ergo_box::NonMandatoryRegisterId;
+use ergotree_ir::chain::token::Token;
+use ergotree_ir::mir::constant::Constant;
+use ergotree_ir::mir::constant::TryExtractInto;
+use ergotree_ir::serialization::SigmaParsingError;
+use ergotree_ir::sigma_protocol::sigma_boolean::ProveDlog;
+use thiserror::Error;
+
+use crate::chain::contract::Contract;
+use crate::chain::ergo_box::box_builder::ErgoBoxCandidateBuilder;
+use crate::chain::ergo_box::box_builder::ErgoBoxCandidateBuilderError;
+use crate::wallet::box_selector::BoxSelection;
+use crate::wallet::box_selector::BoxSelector;
+use crate::wallet::box_selector::BoxSelectorError;
+use crate::wallet::box_selector::SimpleBoxSelector;
+use crate::wallet::tx_builder::TxBuilder;
+
+/// Errors of the auction box parsing and transaction templates
+#[derive(Error, PartialEq, Eq, Debug, Clone)]
+pub enum AuctionError {
+    /// Box does not match the auction box layout
+    #[error("Invalid auction box: {0}")]
+    InvalidAuctionBox(String),
+    /// Invalid arguments
+    #[error("Invalid arguments: {0}")]
+    InvalidArgs(String),
+    /// Bid is placed at or after the auction deadline
+    #[error("Auction ended at height {deadline}")]
+    AuctionEnded {
+        /// auction deadline height
+        deadline: u32,
+    },
+    /// Settlement is attempted before the auction deadline
+    #[error("Auction is not ended yet (deadline is {deadline})")]
+    AuctionNotEnded {
+        /// auction deadline height
+        deadline: u32,
+    },
+    /// Bid is less than the current bid plus the minimal bid step
+    #[error("Bid {bid} is less than the minimal bid {min_bid}")]
+    BidTooLow {
+        /// placed bid
+        bid: u64,
+        /// minimal bid accepted by the auction
+        min_bid: u64,
+    },
+    /// Box value error
+    #[error("Box value error: {0}")]
+    BoxValueError(#[from] BoxValueError),
+    /// Box selection error
+    #[error("Box selector error: {0}")]
+    BoxSelectorError(#[from] BoxSelectorError),
+    /// ErgoBoxCandidate error
+    #[error("ErgoBoxCandidateBuilder error: {0}")]
+    ErgoBoxCandidateBuilderError(#[from] ErgoBoxCandidateBuilderError),
+    /// Parsing error
+    #[error("Parsing error: {0}")]
+    ParsingError(#[from] SigmaParsingError),
+}
+
+/// Auction parsed from the auction box
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct AuctionBox {
+    /// Auction box
+    pub auction_box: ErgoBox,
+    /// Seller (R4)
+    pub seller: ProveDlog,
+    /// Current bidder (R5), the seller if there are no bids yet
+    pub bidder: ProveDlog,
+    /// Current bid (box value)
+    pub current_bid: u64,
+    /// Deadline height (R6)
+    pub deadline: u32,
+    /// Minimal bid step (R7)
+    pub min_step: u64,
+    /// Auto-extension window (R8)
+    pub extension_window: Option<u32>,
+}
+
+impl TryFrom<ErgoBox> for AuctionBox {
+    type Error = AuctionError;
+
+    fn try_from(auction_box: ErgoBox) -> Result<Self, Self::Error> {
+        let seller = prove_dlog_register(&auction_box, NonMandatoryRegisterId::R4, "seller")?;
+        let bidder = prove_dlog_register(&auction_box, NonMandatoryRegisterId::R5, "bidder")?;
+        let deadline = register(&auction_box, NonMandatoryRegisterId::R6, "deadline")?
+            .try_extract_into::<i32>()
+            .map_err(|e| AuctionError::InvalidAuctionBox(format!("R6 is not Int: {}", e)))?;
+        let min_step = register(&auction_box, NonMandatoryRegisterId::R7, "min. bid step")?
+            .try_extract_into::<i64>()
+            .map_err(|e| AuctionError::InvalidAuctionBox(format!("R7 is not Long: {}", e)))?;
+        let extension_window = auction_box
+            .additional_registers
+            .get(NonMandatoryRegisterId::R8)
+            .cloned()
+            .map(|c| c.try_extract_into::<i32>())
+            .transpose()
+            .map_err(|e| AuctionError::InvalidAuctionBox(format!("R8 is not Int: {}", e)))?;
+        if deadline < 0 || min_step < 0 || extension_window.map_or(false, |w| w < 0) {
+            return Err(AuctionError::InvalidAuctionBox(format!(
+                "negative deadline ({}), min. bid step ({}) or extension window ({:?})",
+                deadline, min_step, extension_window
+            )));
+        }
+        if auction_box.tokens.is_none() {
+            return Err(AuctionError::InvalidAuctionBox(
+                "no auctioned tokens".into(),
+            ));
+        }
+        Ok(AuctionBox {
+            seller,
+            bidder,
+            current_bid: *auction_box.value.as_u64(),
+            deadline: deadline as u32,
+            min_step: min_step as u64,
+            extension_window: extension_window.map(|w| w as u32),
+            auction_box,
+        })
+    }
+}
+
+impl AuctionBox {
+    /// Returns true if at least one bid was placed
+    pub fn has_bids(&self) -> bool {
+        self.bidder != self.seller
+    }
+
+    /// Auctioned tokens
+    pub fn tokens(&self) -> Vec<Token> {
+        self.auction_box
+            .tokens
+            .clone()
+            .map(|t| t.as_vec().clone())
+            .unwrap_or_default()
+    }
+
+    /// Minimal bid accepted by the auction (current bid plus the minimal bid step)
+    pub fn min_next_bid(&self) -> Result<u64, AuctionError> {
+        self.current_bid
+            .checked_add(self.min_step)
+            .ok_or_else(|| AuctionError::InvalidArgs("bid amount overflow".into()))
+    }
+
+    /// Returns true if the auction is ended at the given height
+    pub fn is_ended(&self, current_height: u32) -> bool {
+        current_height >= self.deadline
+    }
+
+    /// Deadline after the bid placed at `current_height`. If the auction has an auto-extension
+    /// window and the bid is placed within it before the deadline, the deadline is moved to the
+    /// end of the window counting from `current_height` (protects from the last-block sniping).
+    pub fn deadline_after_bid(&self, current_height: u32) -> u32 {
+        match self.extension_window {
+            Some(window) => self.deadline.max(current_height.saturating_add(window)),
+            None => self.deadline,
+        }
+    }
+}
+
+/// Common parameters of the auction transactions
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct AuctionTxParams {
+    /// Change address
+    pub change_address: Address,
+    /// Miner's fee
+    pub fee_amount: BoxValue,
+    /// Current height
+    pub current_height: u32,
+}
+
+/// Place a bid of `bid_amount` nanoERGs from `bidder`.
+/// Outputs are the auction box with the new bid, bidder and deadline (see
+/// [`AuctionBox::deadline_after_bid`]) and the refund of the current bid to the previous bidder
+/// (the seller's starting value if there were no bids yet).
+/// `user_boxes` - boxes to cover the bid and the miner's fee.
+pub fn bid(
+    auction: &AuctionBox,
+    bid_amount: u64,
+    bidder: &ProveDlog,
+    user_boxes: Vec<ErgoBox>,
+    params: &AuctionTxParams,
+) -> Result<TxBuilder<ErgoBox>, AuctionError> {
+    if auction.is_ended(params.current_height) {
+        return Err(AuctionError::AuctionEnded {
+            deadline: auction.deadline,
+        });
+    }
+    let min_bid = auction.min_next_bid()?;
+    if bid_amount < min_bid {
+        return Err(AuctionError::BidTooLow {
+            bid: bid_amount,
+            min_bid,
+        });
+    }
+    let deadline = auction.deadline_after_bid(params.current_height);
+    let mut successor = ErgoBoxCandidateBuilder::new(
+        BoxValue::try_from(bid_amount)?,
+        auction.auction_box.ergo_tree.clone(),
+        params.current_height,
+    );
+    // copied by the register ids, the raw (unparsed) values are kept as is
+    for (register_id, value) in NonMandatoryRegisterId::REG_IDS.iter().zip(
+        auction
+            .auction_box
+            .additional_registers
+            .get_ordered_values()
+            .iter(),
+    ) {
+        successor.set_register(*register_id, value.clone());
+    }
+    successor.set_register_value(NonMandatoryRegisterId::R5, bidder.clone().into());
+    successor.set_register_value(NonMandatoryRegisterId::R6, (deadline as i32).into());
+    auction
+        .tokens()
+        .into_iter()
+        .for_each(|t| successor.add_token(t));
+    let refund = ErgoBoxCandidateBuilder::new(
+        auction.auction_box.value,
+        Contract::pay_to_address(&Address::P2Pk(auction.bidder.clone()))?.ergo_tree(),
+        params.current_height,
+    )
+    .build()?;
+
+    let target_balance = BoxValue::try_from(
+        bid_amount
+            .checked_add(*params.fee_amount.as_u64())
+            .ok_or_else(|| AuctionError::InvalidArgs("bid amount overflow".into()))?,
+    )?;
+    let user_selection = SimpleBoxSelector::new().select(user_boxes, target_balance, &[])?;
+    let mut boxes = vec![auction.auction_box.clone()];
+    boxes.extend(user_selection.boxes);
+    Ok(TxBuilder::new(
+        BoxSelection {
+            boxes,
+            change_boxes: user_selection.change_boxes,
+        },
+        vec![successor.build()?, refund],
+        params.current_height.into(),
+        params.fee_amount,
+        params.change_address.clone(),
+        BoxValue::SAFE_USER_MIN,
+    ))
+}
+
+/// Settle the ended auction. The auctioned tokens go to the winner (in a box with
+/// [`BoxValue::SAFE_USER_MIN`]) and the rest of the bid (minus the miner's fee) goes to the seller.
+/// If there were no bids, the tokens and the value (minus the miner's fee) are returned to the
+/// seller. The miner's fee is paid from the auction box.
+pub fn settle(
+    auction: &AuctionBox,
+    params: &AuctionTxParams,
+) -> Result<TxBuilder<ErgoBox>, AuctionError> {
+    if !auction.is_ended(params.current_height) {
+        return Err(AuctionError::AuctionNotEnded {
+            deadline: auction.deadline,
+        });
+    }
+    let winner_box_value = if auction.has_bids() {
+        *BoxValue::SAFE_USER_MIN.as_u64()
+    } else {
+        0
+    };
+    let seller_value = auction
+        .current_bid
+        .checked_sub(*params.fee_amount.as_u64())
+        .and_then(|v| v.checked_sub(winner_box_value))
+        .ok_or_else(|| {
+            AuctionError::InvalidArgs(format!(
+                "auction value {} is too small to cover the miner's fee",
+                auction.current_bid
+            ))
+        })?;
+    let mut seller_output = ErgoBoxCandidateBuilder::new(
+        BoxValue::try_from(seller_value)?,
+        Contract::pay_to_address(&Address::P2Pk(auction.seller.clone()))?.ergo_tree(),
+        params.current_height,
+    );
+    let output_candidates = if auction.has_bids() {
+        let mut winner_output = ErgoBoxCandidateBuilder::new(
+            BoxValue::SAFE_USER_MIN,
+            Contract::pay_to_address(&Address::P2Pk(auction.bidder.clone()))?.ergo_tree(),
+            params.current_height,
+        );
+        auction
+            .tokens()
+            .into_iter()
+            .for_each(|t| winner_output.add_token(t));
+        vec![winner_output.build()?, seller_output.build()?]
+    } else {
+        auction
+            .tokens()
+            .into_iter()
+            .for_each(|t| seller_output.add_token(t));
+        vec![seller_output.build()?]
+    };
+    Ok(TxBuilder::new(
+        BoxSelection {
+            boxes: vec![auction.auction_box.clone()],
+            change_boxes: vec![],
+        },
+        output_candidates,
+        params.current_height.into(),
+        params.fee_amount,
+        params.change_address.clone(),
+        BoxValue::SAFE_USER_MIN,
+    ))
+}
+
+fn register(
+    auction_box: &ErgoBox,
+    register_id: NonMandatoryRegisterId,
+    name: &str,
+) -> Result<Constant, AuctionError> {
+    auction_box
+        .additional_registers
+        .get(register_id)
+        .cloned()
+        .ok_or_else(|| {
+            AuctionError::InvalidAuctionBox(format!("{:?} ({}) is empty", register_id, name))
+        })
+}
+
+fn prove_dlog_register(
+    auction_box: &ErgoBox,
+    register_id: NonMandatoryRegisterId,
+    name: &str,
+) -> Result<ProveDlog, AuctionError> {
+    ProveDlog::try_from(register(auction_box, register_id, name)?.v).map_err(|e| {
+        AuctionError::InvalidAuctionBox(format!(
+            "{:?} ({}) is not a P2PK SigmaProp: {}",
+            register_id, name, e
+        ))
+    })
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use ergotree_ir::chain::ergo_box::NonMandatoryRegisters;
+    use ergotree_ir::chain::ergo_box::RegisterValue;
+    use ergotree_ir::chain::token::TokenId;
+    use ergotree_ir::chain::tx_id::TxId;
+    use ergotree_ir::ergo_tree::ErgoTree;
+    use sigma_test_util::force_any_val;
+    use std::convert::TryInto;
+
+    const STARTING_VALUE: u64 = 10_000_000;
+    const MIN_STEP: u64 = 1_000_000;
+    const DEADLINE: u32 = 1000;
+
+    fn auction_box(
+        seller: &ProveDlog,
+        bidder: &ProveDlog,
+        extension_window: Option<u32>,
+    ) -> ErgoBox {
+        let mut registers: Vec<Constant> = vec![
+            seller.clone().into(),
+            bidder.clone().into(),
+            (DEADLINE as i32).into(),
+            (MIN_STEP as i64).into(),
+        ];
+        if let Some(w) = extension_window {
+            registers.push((w as i32).into());
+        }
+        ErgoBox::new(
+            STARTING_VALUE.try_into().unwrap(),
+            force_any_val::<ErgoTree>(),
+            Some(
+                vec![Token {
+                    token_id: force_any_val::<TokenId>(),
+                    amount: 1.try_into().unwrap(),
+                }]
+                .try_into()
+                .unwrap(),
+            ),
+            NonMandatoryRegisters::try_from(registers).unwrap(),
+            1,
+            force_any_val::<TxId>(),
+            0,
+        )
+        .unwrap()
+    }
+
+    fn user_box(value: u64) -> ErgoBox {
+        ErgoBox::new(
+            value.try_into().unwrap(),
+            force_any_val::<ErgoTree>(),
+            None,
+            NonMandatoryRegisters::empty(),
+            1,
+            force_any_val::<TxId>(),
+            0,
+        )
+        .unwrap()
+    }
+
+    fn tx_params(current_height: u32) -> AuctionTxParams {
+        AuctionTxParams {
+            change_address: force_any_val::<Address>(),
+            fee_amount: BoxValue::SAFE_USER_MIN,
+            current_height,
+        }
+    }
+
+    fn p2pk_tree(pk: &ProveDlog) -> ErgoTree {
+        Address::P2Pk(pk.clone()).script().unwrap()
+    }
+
+    #[test]
+    fn test_parse_auction_box() {
+        let seller = force_any_val::<ProveDlog>();
+        let auction = AuctionBox::try_from(auction_box(&seller, &seller, None)).unwrap();
+        assert_eq!(auction.seller, seller);
+        assert!(!auction.has_bids());
+        assert_eq!(auction.current_bid, STARTING_VALUE);
+        assert_eq!(auction.deadline, DEADLINE);
+        assert_eq!(auction.min_step, MIN_STEP);
+        assert_eq!(auction.extension_window, None);
+        assert_eq!(auction.min_next_bid().unwrap(), STARTING_VALUE + MIN_STEP);
+        assert_eq!(auction.tokens().len(), 1);
+
+        // no registers
+        assert!(AuctionBox::try_from(user_box(STARTING_VALUE)).is_err());
+    }
+
+    #[test]
+    fn test_deadline_after_bid() {
+        let seller = force_any_val::<ProveDlog>();
+        let auction = AuctionBox::try_from(auction_box(&seller, &seller, Some(10))).unwrap();
+        assert_eq!(auction.deadline_after_bid(DEADLINE - 20), DEADLINE);
+        assert_eq!(auction.deadline_after_bid(DEADLINE - 5), DEADLINE + 5);
+        let auction = AuctionBox::try_from(auction_box(&seller, &seller, None)).unwrap();
+        assert_eq!(auction.deadline_after_bid(DEADLINE - 5), DEADLINE);
+    }
+
+    #[test]
+    fn test_bid() {
+        let seller = force_any_val::<ProveDlog>();
+        let prev_bidder = force_any_val::<ProveDlog>();
+        let bidder = force_any_val::<ProveDlog>();
+        let auction = AuctionBox::try_from(auction_box(&seller, &prev_bidder, Some(10))).unwrap();
+        assert!(auction.has_bids());
+        let bid_amount = STARTING_VALUE + MIN_STEP;
+        let user_boxes = vec![user_box(100_000_000)];
+        assert_eq!(
+            bid(
+                &auction,
+                bid_amount - 1,
+                &bidder,
+                user_boxes.clone(),
+                &tx_params(DEADLINE - 5)
+            )
+            .err(),
+            Some(AuctionError::BidTooLow {
+                bid: bid_amount - 1,
+                min_bid: bid_amount
+            })
+        );
+        assert_eq!(
+            bid(
+                &auction,
+                bid_amount,
+                &bidder,
+                user_boxes.clone(),
+                &tx_params(DEADLINE)
+            )
+            .err(),
+            Some(AuctionError::AuctionEnded { deadline: DEADLINE })
+        );
+        let tx = bid(
+            &auction,
+            bid_amount,
+            &bidder,
+            user_boxes,
+            &tx_params(DEADLINE - 5),
+        )
+        .unwrap()
+        .build()
+        .unwrap();
+        assert_eq!(tx.inputs.first().box_id, auction.auction_box.box_id());
+        let successor = tx.output_candidates.get(0).unwrap();
+        assert_eq!(successor.ergo_tree, auction.auction_box.ergo_tree);
+        assert_eq!(*successor.value.as_u64(), bid_amount);
+        assert_eq!(successor.tokens, auction.auction_box.tokens);
+        let successor_auction = AuctionBox::try_from(
+            ErgoBox::from_box_candidate(successor, force_any_val::<TxId>(), 0).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(successor_auction.seller, seller);
+        assert_eq!(successor_auction.bidder, bidder);
+        assert_eq!(successor_auction.deadline, DEADLINE + 5);
+        let refund = tx.output_candidates.get(1).unwrap();
+        assert_eq!(*refund.value.as_u64(), STARTING_VALUE);
+        assert_eq!(refund.ergo_tree, p2pk_tree(&prev_bidder));
+    }
+
+    #[test]
+    fn test_bid_keeps_raw_registers() {
+        let seller = force_any_val::<ProveDlog>();
+        let bidder = force_any_val::<ProveDlog>();
+        let base = auction_box(&seller, &seller, Some(10));
+        let mut registers = base.additional_registers.get_ordered_values().clone();
+        // R9 is not parsed
+        registers.push(RegisterValue::Raw(vec![0x60, 0x01]));
+        let auction = AuctionBox::try_from(
+            ErgoBox::new(
+                base.value,
+                base.ergo_tree,
+                base.tokens,
+                NonMandatoryRegisters::try_from(registers).unwrap(),
+                1,
+                force_any_val::<TxId>(),
+                0,
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let bid_amount = auction.min_next_bid().unwrap();
+        let tx = bid(
+            &auction,
+            bid_amount,
+            &bidder,
+            vec![user_box(100_000_000)],
+            &tx_params(DEADLINE - 5),
+        )
+        .unwrap()
+        .build()
+        .unwrap();
+        let successor = tx.output_candidates.get(0).unwrap();
+        assert_eq!(
+            successor
+                .additional_registers
+                .get(NonMandatoryRegisterId::R8),
+            Some(&10i32.into())
+        );
+        assert_eq!(
+            successor
+                .additional_registers
+                .get_value(NonMandatoryRegisterId::R9),
+            Some(&RegisterValue::Raw(vec![0x60, 0x01]))
+        );
+    }
+
+    #[test]
+    fn test_settle() {
+        let seller = force_any_val::<ProveDlog>();
+        let bidder = force_any_val::<ProveDlog>();
+        let auction = AuctionBox::try_from(auction_box(&seller, &bidder, None)).unwrap();
+        assert_eq!(
+            settle(&auction, &tx_params(DEADLINE - 1)).err(),
+            Some(AuctionError::AuctionNotEnded { deadline: DEADLINE })
+        );
+        let tx = settle(&auction, &tx_params(DEADLINE))
+            .unwrap()
+            .build()
+            .unwrap();
+        let winner_output = tx.output_candidates.get(0).unwrap();
+        assert_eq!(winner_output.ergo_tree, p2pk_tree(&bidder));
+        assert_eq!(winner_output.tokens, auction.auction_box.tokens);
+        let seller_output = tx.output_candidates.get(1).unwrap();
+        assert_eq!(seller_output.ergo_tree, p2pk_tree(&seller));
+        assert_eq!(
+            *seller_output.value.as_u64(),
+            STARTING_VALUE - 2 * BoxValue::SAFE_USER_MIN.as_u64()
+        );
+    }
+
+    #[test]
+    fn test_settle_without_bids() {
+        let seller = force_any_val::<ProveDlog>();
+        let auction = AuctionBox::try_from(auction_box(&seller, &seller, None)).unwrap();
+        let tx = settle(&auction, &tx_params(DEADLINE))
+            .unwrap()
+            .build()
+            .unwrap();
+        let seller_output = tx.output_candidates.get(0).unwrap();
+        assert_eq!(seller_output.ergo_tree, p2pk_tree(&seller));
+        assert_eq!(seller_output.tokens, auction.auction_box.tokens);
+        assert_eq!(
+            *seller_output.value.as_u64(),
+            STARTING_VALUE - BoxValue::SAFE_USER_MIN.as_u64()
+        );
+    }
+}
@@ -0,0 +1,742 @@
+//! Spectrum (ErgoDEX) AMM pools: pool box parser and transaction templates for swap, deposit
+//! and redeem operations against the pool (see <https://github.com/spectrum-finance/ergo-dex>)
+//!
+//! Pool box layout:
+//! - N2T (ERG/token) pool: tokens `[pool NFT, LP, Y]`, X reserves are the box value;
+//! - T2T (token/token) pool: tokens `[pool NFT, LP, X, Y]`;
+//! - R4: fee numerator (`Int`), fee denominator is [`FEE_DENOM`].
+//!
+//! The pool box is always the first input and the updated pool box is the first output of the
+//! transaction.
+
+use std::convert::TryFrom;
+use std::convert::TryInto;
+
+use ergotree_ir::chain::address::Address;
+use ergotree_ir::chain::ergo_box::box_value::BoxValue;
+use ergotree_ir::chain::ergo_box::box_value::BoxValueError;
+use ergotree_ir::chain::ergo_box::ErgoBox;
+use ergotree_ir::chain::ergo_box::ErgoBoxCandidate;
+use ergotree_ir::chain::ergo_box::NonMandatoryRegisterId;
+use ergotree_ir::chain::token::Token;
+use ergotree_ir::chain::token::TokenAmountError;
+use ergotree_ir::chain::token::TokenId;
+use ergotree_ir::mir::constant::TryExtractInto;
+use ergotree_ir::serialization::SigmaParsingError;
+use thiserror::Error;
+
+use crate::chain::contract::Contract;
+use crate::chain::ergo_box::box_builder::ErgoBoxCandidateBuilder;
+use crate::chain::ergo_box::box_builder::ErgoBoxCandidateBuilderError;
+use crate::wallet::box_selector::BoxSelection;
+use crate::wallet::box_selector::BoxSelector;
+use crate::wallet::box_selector::BoxSelectorError;
+use crate::wallet::box_selector::SimpleBoxSelector;
+use crate::wallet::tx_builder::TxBuilder;
+
+/// Denominator of the pool fee (fee numerator is stored in R4 of the pool box)
+pub const FEE_DENOM: u64 = 1000;
+
+/// Total emission of the pool LP token. LP supply is the emission minus LP tokens locked in the pool
+pub const TOTAL_LP_EMISSION: u64 = i64::MAX as u64;
+
+/// Errors of the Spectrum pool parsing and transaction templates
+#[derive(Error, PartialEq, Eq, Debug, Clone)]
+pub enum SpectrumError {
+    /// Box does not match the pool box layout
+    #[error("Invalid pool box: {0}")]
+    InvalidPoolBox(String),
+    /// Invalid arguments
+    #[error("Invalid arguments: {0}")]
+    InvalidArgs(String),
+    /// Output amount is less than the requested minimum (slippage)
+    #[error("Output amount {output} is less than the requested minimum {min_output}")]
+    MinOutputNotReached {
+        /// output amount calculated from the pool reserves
+        output: u64,
+        /// requested minimal output amount
+        min_output: u64,
+    },
+    /// Box value error
+    #[error("Box value error: {0}")]
+    BoxValueError(#[from] BoxValueError),
+    /// Token amount error
+    #[error("Token amount error: {0}")]
+    TokenAmountError(#[from] TokenAmountError),
+    /// Box selection error
+    #[error("Box selector error: {0}")]
+    BoxSelectorError(#[from] BoxSelectorError),
+    /// ErgoBoxCandidate error
+    #[error("ErgoBoxCandidateBuilder error: {0}")]
+    ErgoBoxCandidateBuilderError(#[from] ErgoBoxCandidateBuilderError),
+    /// Parsing error
+    #[error("Parsing error: {0}")]
+    ParsingError(#[from] SigmaParsingError),
+}
+
+/// Asset held in the pool
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum PoolAsset {
+    /// ERG (X asset of N2T pool), amounts are in nanoERGs
+    Erg,
+    /// Token with the given id
+    Token(TokenId),
+}
+
+/// AMM pool parsed from the pool box
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct AmmPool {
+    /// Pool box
+    pub pool_box: ErgoBox,
+    /// Pool identifier (NFT)
+    pub pool_nft: TokenId,
+    /// LP token id
+    pub lp_id: TokenId,
+    /// Amount of LP tokens locked in the pool
+    pub lp_locked: u64,
+    /// X asset (ERG for N2T pool)
+    pub x: PoolAsset,
+    /// X asset reserves
+    pub reserves_x: u64,
+    /// Y asset (token) id
+    pub y: TokenId,
+    /// Y asset reserves
+    pub reserves_y: u64,
+    /// Pool fee numerator (R4), pool fee is `1 - fee_num / FEE_DENOM`
+    pub fee_num: u64,
+}
+
+impl TryFrom<ErgoBox> for AmmPool {
+    type Error = SpectrumError;
+
+    fn try_from(pool_box: ErgoBox) -> Result<Self, Self::Error> {
+        let fee_num = pool_box
+            .additional_registers
+            .get(NonMandatoryRegisterId::R4)
+            .cloned()
+            .ok_or_else(|| SpectrumError::InvalidPoolBox("R4 (fee numerator) is empty".into()))?
+            .try_extract_into::<i32>()
+            .map_err(|e| {
+                SpectrumError::InvalidPoolBox(format!("R4 (fee numerator) is not Int: {}", e))
+            })?;
+        if fee_num <= 0 || fee_num as u64 > FEE_DENOM {
+            return Err(SpectrumError::InvalidPoolBox(format!(
+                "fee numerator {} is out of bounds (1..={})",
+                fee_num, FEE_DENOM
+            )));
+        }
+        let tokens: Vec<Token> = pool_box
+            .tokens
+            .clone()
+            .map(|t| t.as_vec().clone())
+            .unwrap_or_default();
+        let (nft, lp, x, reserves_x, y) = match tokens.as_slice() {
+            [nft, lp, y] => (nft, lp, PoolAsset::Erg, *pool_box.value.as_u64(), y),
+            [nft, lp, x, y] => (
+                nft,
+                lp,
+                PoolAsset::Token(x.token_id.clone()),
+                *x.amount.as_u64(),
+                y,
+            ),
+            _ => {
+                return Err(SpectrumError::InvalidPoolBox(format!(
+                    "expected 3 (N2T) or 4 (T2T) tokens, found {}",
+                    tokens.len()
+                )))
+            }
+        };
+        if *nft.amount.as_u64() != 1 {
+            return Err(SpectrumError::InvalidPoolBox(format!(
+                "pool NFT amount is expected to be 1, found {}",
+                nft.amount.as_u64()
+            )));
+        }
+        Ok(AmmPool {
+            pool_nft: nft.token_id.clone(),
+            lp_id: lp.token_id.clone(),
+            lp_locked: *lp.amount.as_u64(),
+            x,
+            reserves_x,
+            y: y.token_id.clone(),
+            reserves_y: *y.amount.as_u64(),
+            fee_num: fee_num as u64,
+            pool_box,
+        })
+    }
+}
+
+impl AmmPool {
+    /// Y asset of the pool
+    pub fn y_asset(&self) -> PoolAsset {
+        PoolAsset::Token(self.y.clone())
+    }
+
+    /// Circulating supply of the LP token
+    pub fn supply_lp(&self) -> u64 {
+        TOTAL_LP_EMISSION - self.lp_locked
+    }
+
+    /// Amount of the other pool asset received for `input_amount` of `input` asset (pool fee is
+    /// taken from the input)
+    pub fn output_amount(
+        &self,
+        input: &PoolAsset,
+        input_amount: u64,
+    ) -> Result<u64, SpectrumError> {
+        let (reserves_in, reserves_out) = if *input == self.x {
+            (self.reserves_x, self.reserves_y)
+        } else if *input == self.y_asset() {
+            (self.reserves_y, self.reserves_x)
+        } else {
+            return Err(SpectrumError::InvalidArgs(format!(
+                "asset {:?} is not in the pool",
+                input
+            )));
+        };
+        let input_amount = input_amount as u128;
+        let fee_num = self.fee_num as u128;
+        let output = reserves_out as u128 * input_amount * fee_num
+            / (reserves_in as u128 * FEE_DENOM as u128 + input_amount * fee_num);
+        // output is always less than reserves_out
+        Ok(output as u64)
+    }
+
+    /// Amount of LP tokens received for the deposit of `amount_x` and `amount_y`. If the amounts
+    /// are not in the pool ratio, the reward is calculated from the smaller share (excess is left in
+    /// the pool).
+    pub fn deposit_reward(&self, amount_x: u64, amount_y: u64) -> Result<u64, SpectrumError> {
+        let supply_lp = self.checked_supply_lp()? as u128;
+        let reward_x = amount_x as u128 * supply_lp / self.reserves_x as u128;
+        let reward_y = amount_y as u128 * supply_lp / self.reserves_y as u128;
+        let reward = reward_x.min(reward_y);
+        if reward >= self.lp_locked as u128 {
+            return Err(SpectrumError::InvalidArgs(format!(
+                "not enough LP tokens in the pool ({}) for the deposit",
+                self.lp_locked
+            )));
+        }
+        Ok(reward as u64)
+    }
+
+    /// Amounts of X and Y assets received for redeeming `lp_amount` of LP tokens
+    pub fn redeem_shares(&self, lp_amount: u64) -> Result<(u64, u64), SpectrumError> {
+        let supply_lp = self.checked_supply_lp()?;
+        if lp_amount > supply_lp {
+            return Err(SpectrumError::InvalidArgs(format!(
+                "LP amount {} exceeds LP supply {}",
+                lp_amount, supply_lp
+            )));
+        }
+        let share_x = lp_amount as u128 * self.reserves_x as u128 / supply_lp as u128;
+        let share_y = lp_amount as u128 * self.reserves_y as u128 / supply_lp as u128;
+        // shares are never greater than reserves
+        Ok((share_x as u64, share_y as u64))
+    }
+
+    fn checked_supply_lp(&self) -> Result<u64, SpectrumError> {
+        match self.supply_lp() {
+            0 => Err(SpectrumError::InvalidPoolBox(
+                "LP supply is zero (pool is not initialized)".into(),
+            )),
+            supply_lp => Ok(supply_lp),
+        }
+    }
+
+    /// Pool box with the updated reserves and the same script, tokens order and registers
+    fn successor(
+        &self,
+        reserves_x: u64,
+        reserves_y: u64,
+        lp_locked: u64,
+        current_height: u32,
+    ) -> Result<ErgoBoxCandidate, SpectrumError> {
+        let value = match self.x {
+            PoolAsset::Erg => BoxValue::try_from(reserves_x)?,
+            PoolAsset::Token(_) => self.pool_box.value,
+        };
+        let mut builder =
+            ErgoBoxCandidateBuilder::new(value, self.pool_box.ergo_tree.clone(), current_height);
+        // copied by the register ids, the raw (unparsed) values are kept as is
+        for (register_id, value) in NonMandatoryRegisterId::REG_IDS.iter().zip(
+            self.pool_box
+                .additional_registers
+                .get_ordered_values()
+                .iter(),
+        ) {
+            builder.set_register(*register_id, value.clone());
+        }
+        builder.add_token(Token {
+            token_id: self.pool_nft.clone(),
+            amount: 1u64.try_into()?,
+        });
+        builder.add_token(Token {
+            token_id: self.lp_id.clone(),
+            amount: lp_locked.try_into()?,
+        });
+        if let PoolAsset::Token(x_id) = &self.x {
+            builder.add_token(Token {
+                token_id: x_id.clone(),
+                amount: reserves_x.try_into()?,
+            });
+        }
+        builder.add_token(Token {
+            token_id: self.y.clone(),
+            amount: reserves_y.try_into()?,
+        });
+        Ok(builder.build()?)
+    }
+}
+
+/// Common parameters of the pool transactions
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct PoolTxParams {
+    /// Address to receive the output assets (swapped or redeemed assets, LP tokens on deposit)
+    pub recipient: Address,
+    /// Change address
+    pub change_address: Address,
+    /// Miner's fee
+    pub fee_amount: BoxValue,
+    /// Current height
+    pub current_height: u32,
+}
+
+/// Swap `input_amount` of `input` pool asset for the other pool asset.
+/// Fails if the output amount is less than `min_output` (slippage protection).
+/// `user_boxes` - boxes to cover the input amount and the miner's fee.
+pub fn swap(
+    pool: &AmmPool,
+    input: &PoolAsset,
+    input_amount: u64,
+    min_output: u64,
+    user_boxes: Vec<ErgoBox>,
+    params: &PoolTxParams,
+) -> Result<TxBuilder<ErgoBox>, SpectrumError> {
+    if input_amount == 0 {
+        return Err(SpectrumError::InvalidArgs("input amount is zero".into()));
+    }
+    let output = pool.output_amount(input, input_amount)?;
+    if output == 0 || output < min_output {
+        return Err(SpectrumError::MinOutputNotReached { output, min_output });
+    }
+    let (reserves_x, reserves_y, output_asset) = if *input == pool.x {
+        (
+            checked_add(pool.reserves_x, input_amount)?,
+            pool.reserves_y - output,
+            pool.y_asset(),
+        )
+    } else {
+        (
+            pool.reserves_x - output,
+            checked_add(pool.reserves_y, input_amount)?,
+            pool.x.clone(),
+        )
+    };
+    let successor = pool.successor(
+        reserves_x,
+        reserves_y,
+        pool.lp_locked,
+        params.current_height,
+    )?;
+    pool_tx_builder(
+        pool,
+        successor,
+        &[(output_asset, output)],
+        &[(input.clone(), input_amount)],
+        user_boxes,
+        params,
+    )
+}
+
+/// Deposit `amount_x` and `amount_y` to the pool in exchange for LP tokens (see
+/// [`AmmPool::deposit_reward`]).
+/// `user_boxes` - boxes to cover the deposited amounts and the miner's fee.
+pub fn deposit(
+    pool: &AmmPool,
+    amount_x: u64,
+    amount_y: u64,
+    user_boxes: Vec<ErgoBox>,
+    params: &PoolTxParams,
+) -> Result<TxBuilder<ErgoBox>, SpectrumError> {
+    let reward = pool.deposit_reward(amount_x, amount_y)?;
+    if reward == 0 {
+        return Err(SpectrumError::InvalidArgs(
+            "deposit is too small to get any LP tokens".into(),
+        ));
+    }
+    let successor = pool.successor(
+        checked_add(pool.reserves_x, amount_x)?,
+        checked_add(pool.reserves_y, amount_y)?,
+        pool.lp_locked - reward,
+        params.current_height,
+    )?;
+    pool_tx_builder(
+        pool,
+        successor,
+        &[(PoolAsset::Token(pool.lp_id.clone()), reward)],
+        &[(pool.x.clone(), amount_x), (pool.y_asset(), amount_y)],
+        user_boxes,
+        params,
+    )
+}
+
+/// Redeem `lp_amount` of LP tokens for the pool assets (see [`AmmPool::redeem_shares`]).
+/// `user_boxes` - boxes to cover the LP tokens and the miner's fee.
+pub fn redeem(
+    pool: &AmmPool,
+    lp_amount: u64,
+    user_boxes: Vec<ErgoBox>,
+    params: &PoolTxParams,
+) -> Result<TxBuilder<ErgoBox>, SpectrumError> {
+    let (share_x, share_y) = pool.redeem_shares(lp_amount)?;
+    if share_x == 0 || share_y == 0 {
+        return Err(SpectrumError::InvalidArgs(
+            "LP amount is too small to redeem".into(),
+        ));
+    }
+    let successor = pool.successor(
+        pool.reserves_x - share_x,
+        pool.reserves_y - share_y,
+        checked_add(pool.lp_locked, lp_amount)?,
+        params.current_height,
+    )?;
+    pool_tx_builder(
+        pool,
+        successor,
+        &[(pool.x.clone(), share_x), (pool.y_asset(), share_y)],
+        &[(PoolAsset::Token(pool.lp_id.clone()), lp_amount)],
+        user_boxes,
+        params,
+    )
+}
+
+/// Transaction with the pool box as the first input and it's successor as the first output,
+/// user output (`output_assets` sent to the recipient) as the second output.
+/// User boxes are selected to cover `input_assets`, the user output box value and the miner's fee.
+fn pool_tx_builder(
+    pool: &AmmPool,
+    successor: ErgoBoxCandidate,
+    output_assets: &[(PoolAsset, u64)],
+    input_assets: &[(PoolAsset, u64)],
+    user_boxes: Vec<ErgoBox>,
+    params: &PoolTxParams,
+) -> Result<TxBuilder<ErgoBox>, SpectrumError> {
+    let (output_erg, output_tokens) = split_assets(output_assets)?;
+    // user output box holds the min. box value in addition to the received ERGs
+    let mut user_output = ErgoBoxCandidateBuilder::new(
+        BoxValue::try_from(checked_add(*BoxValue::SAFE_USER_MIN.as_u64(), output_erg)?)?,
+        Contract::pay_to_address(&params.recipient)?.ergo_tree(),
+        params.current_height,
+    );
+    output_tokens
+        .into_iter()
+        .for_each(|t| user_output.add_token(t));
+    let user_output = user_output.build()?;
+
+    let (input_erg, input_tokens) = split_assets(input_assets)?;
+    let target_balance = BoxValue::try_from(checked_add(
+        checked_add(input_erg, *BoxValue::SAFE_USER_MIN.as_u64())?,
+        *params.fee_amount.as_u64(),
+    )?)?;
+    let user_selection =
+        SimpleBoxSelector::new().select(user_boxes, target_balance, input_tokens.as_slice())?;
+    let mut boxes = vec![pool.pool_box.clone()];
+    boxes.extend(user_selection.boxes);
+    Ok(TxBuilder::new(
+        BoxSelection {
+            boxes,
+            change_boxes: user_selection.change_boxes,
+        },
+        vec![successor, user_output],
+        params.current_height.into(),
+        params.fee_amount,
+        params.change_address.clone(),
+        BoxValue::SAFE_USER_MIN,
+    ))
+}
+
+/// Sum of ERGs and the list of tokens (zero amounts are skipped)
+fn split_assets(assets: &[(PoolAsset, u64)]) -> Result<(u64, Vec<Token>), SpectrumError> {
+    let mut erg = 0u64;
+    let mut tokens = Vec::new();
+    for (asset, amount) in assets.iter().filter(|(_, amount)| *amount > 0) {
+        match asset {
+            PoolAsset::Erg => erg = checked_add(erg, *amount)?,
+            PoolAsset::Token(token_id) => tokens.push(Token {
+                token_id: token_id.clone(),
+                amount: (*amount).try_into()?,
+            }),
+        }
+    }
+    Ok((erg, tokens))
+}
+
+fn checked_add(a: u64, b: u64) -> Result<u64, SpectrumError> {
+    a.checked_add(b)
+        .ok_or_else(|| SpectrumError::InvalidArgs("amount overflow".into()))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use ergotree_ir::chain::ergo_box::NonMandatoryRegisters;
+    use ergotree_ir::chain::ergo_box::RegisterValue;
+    use ergotree_ir::chain::tx_id::TxId;
+    use ergotree_ir::ergo_tree::ErgoTree;
+    use ergotree_ir::mir::constant::Constant;
+    use sigma_test_util::force_any_val;
+
+    const RESERVES_X: u64 = 1_000_000_000;
+    const RESERVES_Y: u64 = 1_000_000;
+    const SUPPLY_LP: u64 = 1_000_000;
+
+    fn n2t_pool_box(registers: NonMandatoryRegisters) -> ErgoBox {
+        let tokens = vec![
+            Token {
+                token_id: force_any_val::<TokenId>(),
+                amount: 1.try_into().unwrap(),
+            },
+            Token {
+                token_id: force_any_val::<TokenId>(),
+                amount: (TOTAL_LP_EMISSION - SUPPLY_LP).try_into().unwrap(),
+            },
+            Token {
+                token_id: force_any_val::<TokenId>(),
+                amount: RESERVES_Y.try_into().unwrap(),
+            },
+        ];
+        ErgoBox::new(
+            RESERVES_X.try_into().unwrap(),
+            force_any_val::<ErgoTree>(),
+            Some(tokens.try_into().unwrap()),
+            registers,
+            1,
+            force_any_val::<TxId>(),
+            0,
+        )
+        .unwrap()
+    }
+
+    fn n2t_pool() -> AmmPool {
+        let registers = NonMandatoryRegisters::try_from(vec![Constant::from(997i32)]).unwrap();
+        AmmPool::try_from(n2t_pool_box(registers)).unwrap()
+    }
+
+    #[test]
+    fn test_successor_keeps_registers() {
+        let registers = NonMandatoryRegisters::try_from(vec![
+            RegisterValue::from(Constant::from(997i32)),
+            RegisterValue::Raw(vec![0x60, 0x01]),
+            RegisterValue::from(Constant::from(5i64)),
+        ])
+        .unwrap();
+        let pool = AmmPool::try_from(n2t_pool_box(registers)).unwrap();
+        let successor = pool
+            .successor(RESERVES_X, RESERVES_Y, pool.lp_locked, 2)
+            .unwrap();
+        assert_eq!(
+            successor.additional_registers,
+            pool.pool_box.additional_registers
+        );
+    }
+
+    fn user_box(value: u64, tokens: Vec<Token>) -> ErgoBox {
+        ErgoBox::new(
+            value.try_into().unwrap(),
+            force_any_val::<ErgoTree>(),
+            tokens.try_into().ok(),
+            NonMandatoryRegisters::empty(),
+            1,
+            force_any_val::<TxId>(),
+            0,
+        )
+        .unwrap()
+    }
+
+    fn tx_params() -> PoolTxParams {
+        PoolTxParams {
+            recipient: force_any_val::<Address>(),
+            change_address: force_any_val::<Address>(),
+            fee_amount: BoxValue::SAFE_USER_MIN,
+            current_height: 100,
+        }
+    }
+
+    fn token_amount(b: &ErgoBoxCandidate, token_id: &TokenId) -> u64 {
+        b.tokens
+            .iter()
+            .flat_map(|tokens| tokens.iter())
+            .find(|t| t.token_id == *token_id)
+            .map(|t| *t.amount.as_u64())
+            .unwrap_or(0)
+    }
+
+    #[test]
+    fn test_parse_n2t_pool() {
+        let pool = n2t_pool();
+        assert_eq!(pool.x, PoolAsset::Erg);
+        assert_eq!(pool.reserves_x, RESERVES_X);
+        assert_eq!(pool.reserves_y, RESERVES_Y);
+        assert_eq!(pool.fee_num, 997);
+        assert_eq!(pool.supply_lp(), SUPPLY_LP);
+
+        // no fee in R4
+        assert!(AmmPool::try_from(n2t_pool_box(NonMandatoryRegisters::empty())).is_err());
+        // no tokens
+        assert!(AmmPool::try_from(user_box(RESERVES_X, vec![])).is_err());
+    }
+
+    #[test]
+    fn test_pool_math() {
+        let pool = n2t_pool();
+        assert_eq!(
+            pool.output_amount(&PoolAsset::Erg, 10_000_000).unwrap(),
+            9871
+        );
+        assert!(pool
+            .output_amount(&PoolAsset::Token(force_any_val::<TokenId>()), 1)
+            .is_err());
+        // 10% of the reserves
+        assert_eq!(pool.deposit_reward(100_000_000, 100_000).unwrap(), 100_000);
+        // excess of Y is not rewarded
+        assert_eq!(pool.deposit_reward(100_000_000, 200_000).unwrap(), 100_000);
+        assert_eq!(pool.redeem_shares(100_000).unwrap(), (100_000_000, 100_000));
+        assert!(pool.redeem_shares(SUPPLY_LP + 1).is_err());
+    }
+
+    #[test]
+    fn test_swap_erg_to_token() {
+        let pool = n2t_pool();
+        let params = tx_params();
+        let input_amount = 10_000_000;
+        let user_boxes = vec![user_box(100_000_000, vec![])];
+        assert_eq!(
+            swap(
+                &pool,
+                &PoolAsset::Erg,
+                input_amount,
+                10_000,
+                user_boxes.clone(),
+                &params
+            )
+            .err(),
+            Some(SpectrumError::MinOutputNotReached {
+                output: 9871,
+                min_output: 10_000
+            })
+        );
+        let tx = swap(
+            &pool,
+            &PoolAsset::Erg,
+            input_amount,
+            9871,
+            user_boxes,
+            &params,
+        )
+        .unwrap()
+        .build()
+        .unwrap();
+        assert_eq!(tx.inputs.first().box_id, pool.pool_box.box_id());
+        let successor = tx.output_candidates.get(0).unwrap();
+        assert_eq!(successor.ergo_tree, pool.pool_box.ergo_tree);
+        assert_eq!(*successor.value.as_u64(), RESERVES_X + input_amount);
+        assert_eq!(token_amount(successor, &pool.y), RESERVES_Y - 9871);
+        assert_eq!(token_amount(successor, &pool.lp_id), pool.lp_locked);
+        assert_eq!(token_amount(successor, &pool.pool_nft), 1);
+        assert_eq!(
+            successor.additional_registers,
+            pool.pool_box.additional_registers
+        );
+        let user_output = tx.output_candidates.get(1).unwrap();
+        assert_eq!(token_amount(user_output, &pool.y), 9871);
+        assert_eq!(user_output.ergo_tree, params.recipient.script().unwrap());
+    }
+
+    #[test]
+    fn test_swap_token_to_erg() {
+        let pool = n2t_pool();
+        let input_amount = 10_000;
+        let output = pool.output_amount(&pool.y_asset(), input_amount).unwrap();
+        let user_boxes = vec![user_box(
+            100_000_000,
+            vec![Token {
+                token_id: pool.y.clone(),
+                amount: input_amount.try_into().unwrap(),
+            }],
+        )];
+        let tx = swap(
+            &pool,
+            &pool.y_asset(),
+            input_amount,
+            output,
+            user_boxes,
+            &tx_params(),
+        )
+        .unwrap()
+        .build()
+        .unwrap();
+        let successor = tx.output_candidates.get(0).unwrap();
+        assert_eq!(*successor.value.as_u64(), RESERVES_X - output);
+        assert_eq!(token_amount(successor, &pool.y), RESERVES_Y + input_amount);
+        let user_output = tx.output_candidates.get(1).unwrap();
+        assert_eq!(
+            *user_output.value.as_u64(),
+            BoxValue::SAFE_USER_MIN.as_u64() + output
+        );
+    }
+
+    #[test]
+    fn test_deposit() {
+        let pool = n2t_pool();
+        let user_boxes = vec![user_box(
+            1_000_000_000,
+            vec![Token {
+                token_id: pool.y.clone(),
+                amount: 100_000.try_into().unwrap(),
+            }],
+        )];
+        let tx = deposit(&pool, 100_000_000, 100_000, user_boxes, &tx_params())
+            .unwrap()
+            .build()
+            .unwrap();
+        let successor = tx.output_candidates.get(0).unwrap();
+        assert_eq!(*successor.value.as_u64(), RESERVES_X + 100_000_000);
+        assert_eq!(token_amount(successor, &pool.y), RESERVES_Y + 100_000);
+        assert_eq!(
+            token_amount(successor, &pool.lp_id),
+            pool.lp_locked - 100_000
+        );
+        let user_output = tx.output_candidates.get(1).unwrap();
+        assert_eq!(token_amount(user_output, &pool.lp_id), 100_000);
+    }
+
+    #[test]
+    fn test_redeem() {
+        let pool = n2t_pool();
+        let user_boxes = vec![user_box(
+            100_000_000,
+            vec![Token {
+                token_id: pool.lp_id.clone(),
+                amount: 100_000.try_into().unwrap(),
+            }],
+        )];
+        let tx = redeem(&pool, 100_000, user_boxes, &tx_params())
+            .unwrap()
+            .build()
+            .unwrap();
+        let successor = tx.output_candidates.get(0).unwrap();
+        assert_eq!(*successor.value.as_u64(), RESERVES_X - 100_000_000);
+        assert_eq!(token_amount(successor, &pool.y), RESERVES_Y - 100_000);
+        assert_eq!(
+            token_amount(successor, &pool.lp_id),
+            pool.lp_locked + 100_000
+        );
+        let user_output = tx.output_candidates.get(1).unwrap();
+        assert_eq!(
+            *user_output.value.as_u64(),
+            BoxValue::SAFE_USER_MIN.as_u64() + 100_000_000
+        );
+        assert_eq!(token_amount(user_output, &pool.y), 100_000);
+    }
+}
@@ -0,0 +1,491 @@
+//! Contract template bundle: a shareable, machine-checkable contract template with metadata.
+//!
+//! Bundle is a JSON document (format version 1):
+//! - `formatVersion`: bundle format version (`1`);
+//! - `name`, `description`, `version`, `author`: contract metadata (strings);
+//! - `source` (optional): ErgoScript source of the contract (.es);
+//! - `ergoTree`: serialized ErgoTree (Base16) with constant segregation and the default values
+//!   of the template parameters (segregated constants);
+//! - `template`: template bytes (Base16) of the ErgoTree (see [`ErgoTree::template_bytes`]),
+//!   checked against `ergoTree` on loading;
+//! - `constants`: descriptor of every segregated constant (in the order of the ErgoTree
+//!   constants): `name` (unique), `description` and `type` (Base16 of the serialized type, checked
+//!   against the ErgoTree constant on loading);
+//! - `tests`: template instantiation tests, each is `name`, `parameters` (constant name ->
+//!   Base16 of the serialized constant) and `expectedErgoTree` (Base16), see
+//!   [`TemplateBundle::run_tests`].
+
+use std::io::Read;
+use std::io::Write;
+
+use ergotree_ir::chain::base16_bytes::Base16DecodedBytes;
+use ergotree_ir::chain::digest32::Digest32;
+use ergotree_ir::ergo_tree::ErgoTree;
+use ergotree_ir::ergo_tree::ErgoTreeError;
+use ergotree_ir::mir::constant::Constant;
+use ergotree_ir::serialization::SigmaParsingError;
+use ergotree_ir::serialization::SigmaSerializable;
+use ergotree_ir::serialization::SigmaSerializationError;
+use ergotree_ir::types::stype::SType;
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Current bundle format version
+pub const TEMPLATE_BUNDLE_FORMAT_VERSION: u32 = 1;
+
+/// Errors on the template bundle creation, loading and instantiation
+#[derive(Error, Debug)]
+pub enum TemplateBundleError {
+    /// JSON encoding/decoding or IO error
+    #[error("JSON error: {0}")]
+    JsonError(#[from] serde_json::Error),
+    /// Bundle format version is not supported
+    #[error("Unsupported bundle format version {0}")]
+    UnsupportedFormatVersion(u32),
+    /// ErgoTree parsing error
+    #[error("Parsing error: {0}")]
+    ParsingError(#[from] SigmaParsingError),
+    /// Serialization error
+    #[error("Serialization error: {0}")]
+    SerializationError(#[from] SigmaSerializationError),
+    /// ErgoTree error
+    #[error("ErgoTree error: {0}")]
+    ErgoTreeError(#[from] ErgoTreeError),
+    /// ErgoTree has no segregated constants (template parameters)
+    #[error("ErgoTree should have constant segregation enabled")]
+    NoConstantSegregation,
+    /// Template bytes do not match the ErgoTree
+    #[error("Template bytes do not match the ErgoTree")]
+    TemplateMismatch,
+    /// Number of the constant descriptors differs from the number of the ErgoTree constants
+    #[error("ErgoTree has {tree_constants} constants, {descriptors} are described")]
+    ConstantsCountMismatch {
+        /// number of the ErgoTree constants
+        tree_constants: usize,
+        /// number of the constant descriptors
+        descriptors: usize,
+    },
+    /// Constant name is used more than once
+    #[error("Duplicate constant name: {0}")]
+    DuplicateConstantName(String),
+    /// Constant with the given name is not described in the bundle
+    #[error("Unknown constant: {0}")]
+    UnknownConstant(String),
+    /// Constant type differs from the type of the ErgoTree constant
+    #[error("Constant {name} has type {actual:?}, expected {expected:?}")]
+    ConstantTypeMismatch {
+        /// constant name
+        name: String,
+        /// type of the ErgoTree constant
+        expected: SType,
+        /// provided type
+        actual: SType,
+    },
+    /// Failed to set the constant value in the ErgoTree
+    #[error("Failed to set constant {0}: {1}")]
+    ConstantError(String, String),
+    /// Template instantiation test failed
+    #[error("Test {0} failed: ErgoTree differs from the expected one")]
+    TestFailed(String),
+}
+
+/// Descriptor of the template parameter (ErgoTree segregated constant)
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct ConstantDescriptor {
+    /// Name (unique in the bundle)
+    pub name: String,
+    /// Description
+    pub description: String,
+    /// Default value (the constant in the bundle ErgoTree)
+    pub default_value: Constant,
+}
+
+/// Template instantiation test: the template with the given parameters has to be the expected tree
+/// (e.g. the tree of the deployed contract)
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct TemplateTest {
+    /// Test name
+    pub name: String,
+    /// Values of the template parameters (by constant name), the rest have default values
+    pub parameters: IndexMap<String, Constant>,
+    /// Expected tree
+    pub expected_tree: ErgoTree,
+}
+
+/// Contract template with metadata, constant descriptors and tests (see the module docs for the
+/// bundle format)
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct TemplateBundle {
+    /// Contract name
+    pub name: String,
+    /// Contract description
+    pub description: String,
+    /// Contract version
+    pub version: String,
+    /// Contract author
+    pub author: String,
+    /// ErgoScript source of the contract
+    pub source: Option<String>,
+    /// Template instantiation tests
+    pub tests: Vec<TemplateTest>,
+    tree: ErgoTree,
+    constants: Vec<ConstantDescriptor>,
+}
+
+impl TemplateBundle {
+    /// Create a bundle (with empty metadata and no tests) from the tree with constant
+    /// segregation. `constants` are names and descriptions of all the tree constants (in the
+    /// order of the tree constants), the tree constants are the default values.
+    pub fn new(
+        name: String,
+        tree: ErgoTree,
+        constants: Vec<(String, String)>,
+    ) -> Result<TemplateBundle, TemplateBundleError> {
+        if !tree.header().is_constant_segregation() {
+            return Err(TemplateBundleError::NoConstantSegregation);
+        }
+        let tree_constants = tree_constants(&tree)?;
+        if tree_constants.len() != constants.len() {
+            return Err(TemplateBundleError::ConstantsCountMismatch {
+                tree_constants: tree_constants.len(),
+                descriptors: constants.len(),
+            });
+        }
+        let constants: Vec<ConstantDescriptor> = constants
+            .into_iter()
+            .zip(tree_constants)
+            .map(|((name, description), default_value)| ConstantDescriptor {
+                name,
+                description,
+                default_value,
+            })
+            .collect();
+        for (i, c) in constants.iter().enumerate() {
+            if constants[..i].iter().any(|other| other.name == c.name) {
+                return Err(TemplateBundleError::DuplicateConstantName(c.name.clone()));
+            }
+        }
+        Ok(TemplateBundle {
+            name,
+            description: String::new(),
+            version: String::new(),
+            author: String::new(),
+            source: None,
+            tests: Vec::new(),
+            tree,
+            constants,
+        })
+    }
+
+    /// Tree with the default values of the parameters
+    pub fn ergo_tree(&self) -> &ErgoTree {
+        &self.tree
+    }
+
+    /// Descriptors of the template parameters
+    pub fn constants(&self) -> &[ConstantDescriptor] {
+        &self.constants
+    }
+
+    /// Template hash (see [`ErgoTree::template_hash`])
+    pub fn template_hash(&self) -> Result<Digest32, TemplateBundleError> {
+        Ok(self.tree.template_hash()?)
+    }
+
+    /// Tree with the given values of the parameters (by constant name), the rest of the
+    /// parameters have default values
+    pub fn instantiate(
+        &self,
+        parameters: &IndexMap<String, Constant>,
+    ) -> Result<ErgoTree, TemplateBundleError> {
+        let mut tree = self.tree.clone();
+        for (name, value) in parameters {
+            let (index, descriptor) = self
+                .constants
+                .iter()
+                .enumerate()
+                .find(|(_, c)| c.name == *name)
+                .ok_or_else(|| TemplateBundleError::UnknownConstant(name.clone()))?;
+            if value.tpe != descriptor.default_value.tpe {
+                return Err(TemplateBundleError::ConstantTypeMismatch {
+                    name: name.clone(),
+                    expected: descriptor.default_value.tpe.clone(),
+                    actual: value.tpe.clone(),
+                });
+            }
+            tree = tree.with_constant(index, value.clone()).map_err(|e| {
+                TemplateBundleError::ConstantError(name.clone(), format!("{:?}", e))
+            })?;
+        }
+        Ok(tree)
+    }
+
+    /// Checks that every test instantiates the template to the expected tree
+    pub fn run_tests(&self) -> Result<(), TemplateBundleError> {
+        self.tests.iter().try_for_each(|test| {
+            let tree = self.instantiate(&test.parameters)?;
+            if tree.sigma_serialize_bytes()? == test.expected_tree.sigma_serialize_bytes()? {
+                Ok(())
+            } else {
+                Err(TemplateBundleError::TestFailed(test.name.clone()))
+            }
+        })
+    }
+
+    /// Load the bundle from JSON (see the module docs for the format). The template bytes and
+    /// the constant types are checked against the ErgoTree (tests are not run, see
+    /// [`TemplateBundle::run_tests`]).
+    pub fn load<R: Read>(reader: R) -> Result<TemplateBundle, TemplateBundleError> {
+        let json: TemplateBundleJson = serde_json::from_reader(reader)?;
+        if json.format_version != TEMPLATE_BUNDLE_FORMAT_VERSION {
+            return Err(TemplateBundleError::UnsupportedFormatVersion(
+                json.format_version,
+            ));
+        }
+        let tree = ErgoTree::sigma_parse_bytes(&json.ergo_tree.0)?;
+        if tree.template_bytes()? != json.template.0 {
+            return Err(TemplateBundleError::TemplateMismatch);
+        }
+        let constants = json
+            .constants
+            .iter()
+            .map(|c| (c.name.clone(), c.description.clone()))
+            .collect();
+        let mut bundle = TemplateBundle::new(json.name, tree, constants)?;
+        for (c, c_json) in bundle.constants.iter().zip(&json.constants) {
+            let tpe = SType::sigma_parse_bytes(&c_json.tpe.0)?;
+            if tpe != c.default_value.tpe {
+                return Err(TemplateBundleError::ConstantTypeMismatch {
+                    name: c.name.clone(),
+                    expected: c.default_value.tpe.clone(),
+                    actual: tpe,
+                });
+            }
+        }
+        bundle.description = json.description;
+        bundle.version = json.version;
+        bundle.author = json.author;
+        bundle.source = json.source;
+        bundle.tests = json
+            .tests
+            .into_iter()
+            .map(|t| {
+                let parameters = t
+                    .parameters
+                    .into_iter()
+                    .map(|(name, bytes)| Ok((name, Constant::sigma_parse_bytes(&bytes.0)?)))
+                    .collect::<Result<IndexMap<String, Constant>, TemplateBundleError>>()?;
+                Ok(TemplateTest {
+                    name: t.name,
+                    parameters,
+                    expected_tree: ErgoTree::sigma_parse_bytes(&t.expected_ergo_tree.0)?,
+                })
+            })
+            .collect::<Result<Vec<TemplateTest>, TemplateBundleError>>()?;
+        Ok(bundle)
+    }
+
+    /// Save the bundle as JSON (see the module docs for the format)
+    pub fn save<W: Write>(&self, writer: W) -> Result<(), TemplateBundleError> {
+        let json = TemplateBundleJson {
+            format_version: TEMPLATE_BUNDLE_FORMAT_VERSION,
+            name: self.name.clone(),
+            description: self.description.clone(),
+            version: self.version.clone(),
+            author: self.author.clone(),
+            source: self.source.clone(),
+            ergo_tree: Base16DecodedBytes(self.tree.sigma_serialize_bytes()?),
+            template: Base16DecodedBytes(self.tree.template_bytes()?),
+            constants: self
+                .constants
+                .iter()
+                .map(|c| {
+                    Ok(ConstantDescriptorJson {
+                        name: c.name.clone(),
+                        description: c.description.clone(),
+                        tpe: Base16DecodedBytes(c.default_value.tpe.sigma_serialize_bytes()?),
+                    })
+                })
+                .collect::<Result<Vec<_>, TemplateBundleError>>()?,
+            tests: self
+                .tests
+                .iter()
+                .map(|t| {
+                    Ok(TemplateTestJson {
+                        name: t.name.clone(),
+                        parameters: t
+                            .parameters
+                            .iter()
+                            .map(|(name, c)| {
+                                Ok((name.clone(), Base16DecodedBytes(c.sigma_serialize_bytes()?)))
+                            })
+                            .collect::<Result<IndexMap<_, _>, TemplateBundleError>>()?,
+                        expected_ergo_tree: Base16DecodedBytes(
+                            t.expected_tree.sigma_serialize_bytes()?,
+                        ),
+                    })
+                })
+                .collect::<Result<Vec<_>, TemplateBundleError>>()?,
+        };
+        Ok(serde_json::to_writer_pretty(writer, &json)?)
+    }
+}
+
+fn tree_constants(tree: &ErgoTree) -> Result<Vec<Constant>, TemplateBundleError> {
+    let len = tree.constants_len().map_err(ErgoTreeError::from)?;
+    (0..len)
+        .map(|i| {
+            tree.get_constant(i)
+                .map_err(ErgoTreeError::from)?
+                .ok_or_else(|| SigmaParsingError::Misc(format!("no constant {}", i)).into())
+        })
+        .collect()
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+struct ConstantDescriptorJson {
+    #[serde(rename = "name")]
+    name: String,
+    #[serde(rename = "description")]
+    description: String,
+    #[serde(rename = "type")]
+    tpe: Base16DecodedBytes,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+struct TemplateTestJson {
+    #[serde(rename = "name")]
+    name: String,
+    #[serde(rename = "parameters")]
+    parameters: IndexMap<String, Base16DecodedBytes>,
+    #[serde(rename = "expectedErgoTree")]
+    expected_ergo_tree: Base16DecodedBytes,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+struct TemplateBundleJson {
+    #[serde(rename = "formatVersion")]
+    format_version: u32,
+    #[serde(rename = "name")]
+    name: String,
+    #[serde(rename = "description")]
+    description: String,
+    #[serde(rename = "version")]
+    version: String,
+    #[serde(rename = "author")]
+    author: String,
+    #[serde(rename = "source", default, skip_serializing_if = "Option::is_none")]
+    source: Option<String>,
+    #[serde(rename = "ergoTree")]
+    ergo_tree: Base16DecodedBytes,
+    #[serde(rename = "template")]
+    template: Base16DecodedBytes,
+    #[serde(rename = "constants")]
+    constants: Vec<ConstantDescriptorJson>,
+    #[serde(rename = "tests", default)]
+    tests: Vec<TemplateTestJson>,
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::constants::MINERS_FEE_MAINNET_ADDRESS;
+    use ergotree_ir::chain::address::AddressEncoder;
+
+    /// Miner fee contract with the delay (`Int`, blocks) as the first constant
+    fn fee_bundle() -> TemplateBundle {
+        let tree = AddressEncoder::unchecked_parse_address_from_str(MINERS_FEE_MAINNET_ADDRESS)
+            .unwrap()
+            .script()
+            .unwrap();
+        let mut constants = vec![("delay".to_string(), "miner reward delay".to_string())];
+        for i in 1..tree.constants_len().unwrap() {
+            constants.push((format!("c{}", i), String::new()));
+        }
+        let mut bundle =
+            TemplateBundle::new("Miner fee".to_string(), tree.clone(), constants).unwrap();
+        bundle.version = "1.0".to_string();
+        bundle.author = "Ergo".to_string();
+        bundle.tests = vec![
+            TemplateTest {
+                name: "default".to_string(),
+                parameters: IndexMap::new(),
+                expected_tree: tree.clone(),
+            },
+            TemplateTest {
+                name: "delay 100".to_string(),
+                parameters: vec![("delay".to_string(), Constant::from(100i32))]
+                    .into_iter()
+                    .collect(),
+                expected_tree: tree.with_constant(0, 100i32.into()).unwrap(),
+            },
+        ];
+        bundle
+    }
+
+    #[test]
+    fn save_load_roundtrip() {
+        let bundle = fee_bundle();
+        bundle.run_tests().unwrap();
+        let mut bytes = Vec::new();
+        bundle.save(&mut bytes).unwrap();
+        let loaded = TemplateBundle::load(bytes.as_slice()).unwrap();
+        assert_eq!(loaded, bundle);
+        loaded.run_tests().unwrap();
+        assert_eq!(
+            loaded.template_hash().unwrap(),
+            bundle.ergo_tree().template_hash().unwrap()
+        );
+    }
+
+    #[test]
+    fn instantiate_errors() {
+        let bundle = fee_bundle();
+        let params = |name: &str, c: Constant| vec![(name.to_string(), c)].into_iter().collect();
+        assert!(matches!(
+            bundle.instantiate(&params("unknown", 1i32.into())),
+            Err(TemplateBundleError::UnknownConstant(_))
+        ));
+        assert!(matches!(
+            bundle.instantiate(&params("delay", 1i64.into())),
+            Err(TemplateBundleError::ConstantTypeMismatch { .. })
+        ));
+        let mut failing = bundle.clone();
+        failing.tests[1].parameters = params("delay", 101i32.into());
+        assert!(matches!(
+            failing.run_tests(),
+            Err(TemplateBundleError::TestFailed(name)) if name == "delay 100"
+        ));
+    }
+
+    #[test]
+    fn load_checks() {
+        let bundle = fee_bundle();
+        let mut bytes = Vec::new();
+        bundle.save(&mut bytes).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+
+        let mut wrong_template = json.clone();
+        wrong_template["template"] = "00".into();
+        assert!(matches!(
+            TemplateBundle::load(wrong_template.to_string().as_bytes()),
+            Err(TemplateBundleError::TemplateMismatch)
+        ));
+
+        let mut wrong_type = json.clone();
+        wrong_type["constants"][0]["type"] = "05".into();
+        assert!(matches!(
+            TemplateBundle::load(wrong_type.to_string().as_bytes()),
+            Err(TemplateBundleError::ConstantTypeMismatch { .. })
+        ));
+
+        let mut wrong_version = json;
+        wrong_version["formatVersion"] = 2.into();
+        assert!(matches!(
+            TemplateBundle::load(wrong_version.to_string().as_bytes()),
+            Err(TemplateBundleError::UnsupportedFormatVersion(2))
+        ));
+    }
+}
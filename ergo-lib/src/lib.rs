@@ -13,12 +13,20 @@
 #![allow(clippy::unit_arg)]
 #![deny(rustdoc::broken_intra_doc_links)]
 #![deny(clippy::wildcard_enum_match_arm)]
+#![deny(clippy::unwrap_used)]
+#![deny(clippy::expect_used)]
+#![deny(clippy::todo)]
+#![deny(clippy::unimplemented)]
+#![deny(clippy::panic)]
 
+pub mod analytics;
 pub mod chain;
 pub mod constants;
+pub mod contracts;
 pub mod wallet;
 
 /// Re-exported types from dependencies
+pub extern crate ergo_units;
 #[cfg(feature = "compiler")]
 pub extern crate ergoscript_compiler;
 pub extern crate ergotree_interpreter;
@@ -1,23 +1,46 @@
 //! Wallet-related features for Ergo
 
 pub mod box_selector;
+#[cfg(feature = "json")]
+pub mod cold_signing;
+pub mod fee;
+pub mod fee_estimator;
+pub mod mnemonic_generator;
+pub mod multi_sig;
 pub mod secret_key;
+#[cfg(feature = "secret-storage")]
+pub mod secret_storage;
 pub mod signing;
+pub mod signing_policy;
+#[cfg(feature = "arbitrary")]
+pub mod testing;
+pub mod token_verification;
 pub mod tx_builder;
+pub mod verifier;
 
 use ergotree_interpreter::sigma_protocol::private_input::PrivateInput;
 use ergotree_interpreter::sigma_protocol::prover::Prover;
+use ergotree_interpreter::sigma_protocol::prover::ProverError;
+use ergotree_interpreter::sigma_protocol::prover::SecretProviderProver;
 use ergotree_interpreter::sigma_protocol::prover::TestProver;
+use ergotree_interpreter::sigma_protocol::secret_provider::SecretProvider;
+use ergotree_ir::chain::address::Address;
+use ergotree_ir::sigma_protocol::sigma_boolean::SigmaBoolean;
+use multi_sig::{generate_commitments, TransactionHintsBag};
 use secret_key::SecretKey;
-use signing::{sign_transaction, TxSigningError};
+use signing::{sign_transaction, sign_transaction_with_hints, TxSigningError};
 use thiserror::Error;
 
 use crate::chain::ergo_state_context::ErgoStateContext;
+use crate::chain::transaction::reduced::reduce_tx;
 use crate::chain::transaction::reduced::ReducedTransaction;
 use crate::chain::transaction::Transaction;
 
+use self::signing::sign_message;
 use self::signing::sign_reduced_transaction;
 use self::signing::TransactionContext;
+use self::signing_policy::SigningPolicy;
+use self::signing_policy::SigningPolicyError;
 
 /// Wallet
 pub struct Wallet {
@@ -30,6 +53,15 @@ pub enum WalletError {
     /// Error on tx signing
     #[error("Transaction signing error: {0}")]
     TxSigningError(TxSigningError),
+    /// Error on message signing
+    #[error("Prover error: {0}")]
+    ProverError(#[from] ProverError),
+    /// Address has no sigma proposition to sign the message for
+    #[error("Unsupported address: {0}")]
+    UnsupportedAddress(String),
+    /// Transaction violates the signing policy
+    #[error("Signing policy error: {0}")]
+    SigningPolicyError(#[from] SigningPolicyError),
 }
 
 impl From<TxSigningError> for WalletError {
@@ -49,6 +81,14 @@ impl Wallet {
         }
     }
 
+    /// Create Wallet with the secrets in the given storage (e.g. an HSM or a keystore), the
+    /// private keys are not loaded into memory
+    pub fn from_secret_provider(secret_provider: Box<dyn SecretProvider>) -> Wallet {
+        Wallet {
+            prover: Box::new(SecretProviderProver::new(secret_provider)),
+        }
+    }
+
     /// Signs a transaction
     pub fn sign_transaction(
         &self,
@@ -58,6 +98,29 @@ impl Wallet {
         sign_transaction(self.prover.as_ref(), tx_context, state_context).map_err(WalletError::from)
     }
 
+    /// Generates commitments for the transaction inputs which can be (partially) proven with the
+    /// wallet secrets, for distributed signing (see [`multi_sig`])
+    pub fn generate_commitments(
+        &self,
+        tx_context: TransactionContext,
+        state_context: &ErgoStateContext,
+    ) -> Result<TransactionHintsBag, WalletError> {
+        let public_images = self.prover.secrets().public_images();
+        generate_commitments(tx_context, state_context, &public_images).map_err(WalletError::from)
+    }
+
+    /// Signs a transaction using the hints (e.g. commitments and proofs of other signers) for the
+    /// inputs
+    pub fn sign_transaction_with_hints(
+        &self,
+        tx_context: TransactionContext,
+        state_context: &ErgoStateContext,
+        tx_hints: &TransactionHintsBag,
+    ) -> Result<Transaction, WalletError> {
+        sign_transaction_with_hints(self.prover.as_ref(), tx_context, state_context, tx_hints)
+            .map_err(WalletError::from)
+    }
+
     /// Signs a reduced transaction (generating proofs for inputs)
     pub fn sign_reduced_transaction(
         &self,
@@ -65,4 +128,43 @@ impl Wallet {
     ) -> Result<Transaction, WalletError> {
         sign_reduced_transaction(self.prover.as_ref(), reduced_tx).map_err(WalletError::from)
     }
+
+    /// Signs a transaction if it satisfies the signing policy (see [`signing_policy`]), the value
+    /// it sends is recorded in the policy
+    pub fn sign_transaction_with_policy(
+        &self,
+        tx_context: TransactionContext,
+        state_context: &ErgoStateContext,
+        policy: &mut SigningPolicy,
+    ) -> Result<Transaction, WalletError> {
+        let height = state_context.pre_header.height.as_u32();
+        let boxes_to_spend = tx_context.boxes_to_spend.clone();
+        let reduced_tx = reduce_tx(tx_context, state_context)?;
+        let sent = policy.check(&reduced_tx, &boxes_to_spend, height)?;
+        let tx = self.sign_reduced_transaction(reduced_tx)?;
+        policy.record(sent, height);
+        Ok(tx)
+    }
+
+    /// Signs an arbitrary message for the sigma proposition (e.g. a public key), see
+    /// [`verifier::verify_signature`]
+    pub fn sign_message(
+        &self,
+        sigma_tree: SigmaBoolean,
+        msg: &[u8],
+    ) -> Result<Vec<u8>, WalletError> {
+        sign_message(self.prover.as_ref(), sigma_tree, msg).map_err(WalletError::from)
+    }
+
+    /// Signs an arbitrary message (e.g. a "sign-in" challenge of a dApp) for the address (see
+    /// [`verifier::address_sigma_prop`] for the supported addresses)
+    pub fn sign_message_for_address(
+        &self,
+        address: &Address,
+        msg: &[u8],
+    ) -> Result<Vec<u8>, WalletError> {
+        let sigma_tree = verifier::address_sigma_prop(address)
+            .ok_or_else(|| WalletError::UnsupportedAddress(format!("{:?}", address)))?;
+        self.sign_message(sigma_tree, msg)
+    }
 }
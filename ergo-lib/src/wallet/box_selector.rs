@@ -1,7 +1,10 @@
 //! Box selection for transaction inputs
 
+#[cfg(not(target_arch = "wasm32"))]
+mod reservation;
 mod simple;
 use std::collections::HashMap;
+use std::convert::TryInto;
 
 use ergotree_ir::chain::ergo_box::box_value::BoxValue;
 use ergotree_ir::chain::ergo_box::box_value::BoxValueError;
@@ -12,6 +15,8 @@ use ergotree_ir::chain::ergo_box::ErgoBoxCandidate;
 use ergotree_ir::chain::token::Token;
 use ergotree_ir::chain::token::TokenAmount;
 use ergotree_ir::chain::token::TokenId;
+#[cfg(not(target_arch = "wasm32"))]
+pub use reservation::*;
 pub use simple::*;
 
 use thiserror::Error;
@@ -53,6 +58,18 @@ pub enum BoxSelectorError {
     /// BoxValue out of bounds
     #[error("BoxValue out of bounds")]
     BoxValueError(BoxValueError),
+
+    /// Total amount of a token overflows
+    #[error("Token amounts error: {0}")]
+    TokenAmountsOverflow(TokenAmountsOverflow),
+
+    /// Selected index is out of the inputs bounds (see [`select_by_indices`])
+    #[error("Selected index {0} is out of bounds")]
+    InvalidIndex(usize),
+
+    /// Box is selected more than once (see [`select_by_indices`])
+    #[error("Index {0} is selected more than once")]
+    DuplicateIndex(usize),
 }
 
 impl From<BoxValueError> for BoxSelectorError {
@@ -61,6 +78,12 @@ impl From<BoxValueError> for BoxSelectorError {
     }
 }
 
+impl From<TokenAmountsOverflow> for BoxSelectorError {
+    fn from(e: TokenAmountsOverflow) -> Self {
+        BoxSelectorError::TokenAmountsOverflow(e)
+    }
+}
+
 /// Assets that ErgoBox holds
 pub trait ErgoBoxAssets {
     /// Box value
@@ -125,42 +148,232 @@ pub fn sum_value<T: ErgoBoxAssets>(bs: &[T]) -> u64 {
     bs.iter().map(|b| *b.value().as_u64()).sum()
 }
 
-/// Returns the total token amounts (all tokens combined)
-pub fn sum_tokens(ts: Option<&[Token]>) -> HashMap<TokenId, TokenAmount> {
-    let mut res: HashMap<TokenId, TokenAmount> = HashMap::new();
-    ts.into_iter().flatten().for_each(|t| {
-        res.entry(t.token_id.clone())
-            .and_modify(|amt| *amt = amt.checked_add(&t.amount).unwrap())
-            .or_insert(t.amount);
-    });
-    res
+/// Returns the total token amounts (all tokens combined), fails if the total amount of a token
+/// exceeds [`TokenAmount::MAX_RAW`]
+pub fn sum_tokens(
+    ts: Option<&[Token]>,
+) -> Result<HashMap<TokenId, TokenAmount>, TokenAmountsOverflow> {
+    let mut res = TokenAmounts::default();
+    ts.into_iter()
+        .flatten()
+        .try_for_each(|t| res.add_token(t))?;
+    Ok(res.0)
+}
+
+/// Returns the total token amounts (all tokens combined) of the given boxes, fails if the total
+/// amount of a token exceeds [`TokenAmount::MAX_RAW`]
+pub fn sum_tokens_from_boxes<T: ErgoBoxAssets>(
+    bs: &[T],
+) -> Result<HashMap<TokenId, TokenAmount>, TokenAmountsOverflow> {
+    checked_sum_tokens(bs).map(|t| t.0)
+}
+
+/// Total amounts of the tokens (by token id)
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct TokenAmounts(HashMap<TokenId, TokenAmount>);
+
+/// Total amount of the token exceeds [`TokenAmount::MAX_RAW`]
+#[derive(Error, PartialEq, Eq, Debug, Clone)]
+#[error("Total amount of token {0:?} overflows")]
+pub struct TokenAmountsOverflow(pub TokenId);
+
+impl TokenAmounts {
+    /// Total amount of the token (if any)
+    pub fn get(&self, token_id: &TokenId) -> Option<TokenAmount> {
+        self.0.get(token_id).cloned()
+    }
+
+    /// Number of distinct tokens
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns true if there are no tokens
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Token ids with their total amounts
+    pub fn iter(&self) -> impl Iterator<Item = (&TokenId, &TokenAmount)> {
+        self.0.iter()
+    }
+
+    /// Add the token amount to the total amount of the token
+    pub fn add_token(&mut self, token: &Token) -> Result<(), TokenAmountsOverflow> {
+        let total = match self.0.get(&token.token_id) {
+            Some(amount) => amount
+                .checked_add(&token.amount)
+                .map_err(|_| TokenAmountsOverflow(token.token_id.clone()))?,
+            None => token.amount,
+        };
+        self.0.insert(token.token_id.clone(), total);
+        Ok(())
+    }
+
+    /// Tokens with their total amounts
+    pub fn to_tokens(&self) -> Vec<Token> {
+        self.iter()
+            .map(|(token_id, amount)| Token {
+                token_id: token_id.clone(),
+                amount: *amount,
+            })
+            .collect()
+    }
+}
+
+/// Tokens minted and burned by the transaction (difference between the tokens in the outputs
+/// and inputs)
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct TokensDiff {
+    /// Tokens (amounts) in the outputs that are not in the inputs
+    pub minted: TokenAmounts,
+    /// Tokens (amounts) in the inputs that are not in the outputs
+    pub burned: TokenAmounts,
+}
+
+/// Returns the total token amounts of the given boxes, fails if the total amount of a token
+/// exceeds [`TokenAmount::MAX_RAW`]
+pub fn checked_sum_tokens<T: ErgoBoxAssets>(
+    bs: &[T],
+) -> Result<TokenAmounts, TokenAmountsOverflow> {
+    let mut res = TokenAmounts::default();
+    bs.iter().try_for_each(|b| {
+        b.tokens()
+            .into_iter()
+            .flatten()
+            .try_for_each(|t| res.add_token(&t))
+    })?;
+    Ok(res)
 }
 
-/// Returns the total token amounts (all tokens combined) of the given boxes
-pub fn sum_tokens_from_boxes<T: ErgoBoxAssets>(bs: &[T]) -> HashMap<TokenId, TokenAmount> {
-    let mut res: HashMap<TokenId, TokenAmount> = HashMap::new();
-    bs.iter().for_each(|b| {
-        b.tokens().into_iter().flatten().for_each(|t| {
-            res.entry(t.token_id.clone())
-                .and_modify(|amt| *amt = amt.checked_add(&t.amount).unwrap())
-                .or_insert(t.amount);
-        });
-    });
-    res
+/// Returns the tokens minted and burned in a transaction with the given outputs and inputs
+pub fn tokens_diff<O: ErgoBoxAssets, I: ErgoBoxAssets>(
+    outputs: &[O],
+    inputs: &[I],
+) -> Result<TokensDiff, TokenAmountsOverflow> {
+    let output_tokens = checked_sum_tokens(outputs)?;
+    let input_tokens = checked_sum_tokens(inputs)?;
+    Ok(TokensDiff {
+        minted: excess(&output_tokens, &input_tokens),
+        burned: excess(&input_tokens, &output_tokens),
+    })
+}
+
+/// Token amounts in `a` exceeding the amounts in `b`
+fn excess(a: &TokenAmounts, b: &TokenAmounts) -> TokenAmounts {
+    TokenAmounts(
+        a.iter()
+            .filter_map(|(token_id, a_amount)| match b.get(token_id) {
+                // no excess if the difference is not a valid (positive) amount
+                Some(b_amount) => a_amount
+                    .checked_sub(&b_amount)
+                    .ok()
+                    .map(|diff| (token_id.clone(), diff)),
+                None => Some((token_id.clone(), *a_amount)),
+            })
+            .collect(),
+    )
+}
+
+/// Returns the change of spending the given boxes for the target balance and tokens (the value
+/// and the tokens left over), `None` if the boxes hold exactly the targets. Fails if the boxes do
+/// not cover the targets.
+pub fn selection_change<T: ErgoBoxAssets>(
+    boxes: &[T],
+    target_balance: BoxValue,
+    target_tokens: &[Token],
+) -> Result<Option<ErgoBoxAssetsData>, BoxSelectorError> {
+    let selected_value = sum_value(boxes);
+    let target_value = *target_balance.as_u64();
+    if selected_value < target_value {
+        return Err(BoxSelectorError::NotEnoughCoins(
+            target_value - selected_value,
+        ));
+    }
+    let selected_tokens = checked_sum_tokens(boxes)?;
+    let mut targets = TokenAmounts::default();
+    target_tokens
+        .iter()
+        .try_for_each(|t| targets.add_token(t))?;
+    let missing = excess(&targets, &selected_tokens);
+    if !missing.is_empty() {
+        return Err(BoxSelectorError::NotEnoughTokens(missing.to_tokens()));
+    }
+    let change_value = selected_value - target_value;
+    let change_tokens = excess(&selected_tokens, &targets);
+    if change_value == 0 && change_tokens.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(ErgoBoxAssetsData {
+        value: change_value.try_into()?,
+        tokens: BoxTokens::from_vec(change_tokens.to_tokens()).ok(),
+    }))
+}
+
+/// Selects the boxes with the given indices in `inputs` (e.g. chosen by the user with the coin
+/// control of the wallet app), all of them are spent. Fails if an index is out of bounds or
+/// repeated, if the boxes do not cover the targets or if the change value is below
+/// [`BoxValue::SAFE_USER_MIN`].
+pub fn select_by_indices<T: ErgoBoxAssets>(
+    inputs: Vec<T>,
+    indices: &[usize],
+    target_balance: BoxValue,
+    target_tokens: &[Token],
+) -> Result<BoxSelection<T>, BoxSelectorError> {
+    let mut inputs: Vec<Option<T>> = inputs.into_iter().map(Some).collect();
+    let mut boxes: Vec<T> = Vec::with_capacity(indices.len());
+    for idx in indices {
+        let b = inputs
+            .get_mut(*idx)
+            .ok_or(BoxSelectorError::InvalidIndex(*idx))?
+            .take()
+            .ok_or(BoxSelectorError::DuplicateIndex(*idx))?;
+        boxes.push(b);
+    }
+    let change_value = sum_value(boxes.as_slice()).saturating_sub(*target_balance.as_u64());
+    let min_change_value = *BoxValue::SAFE_USER_MIN.as_u64();
+    let change_boxes: Vec<ErgoBoxAssetsData> =
+        match selection_change(boxes.as_slice(), target_balance, target_tokens) {
+            // including the change value below the box value minimum
+            Ok(Some(_)) | Err(BoxSelectorError::BoxValueError(_))
+                if change_value < min_change_value =>
+            {
+                return Err(BoxSelectorError::NotEnoughCoins(
+                    min_change_value - change_value,
+                ))
+            }
+            change => change?.into_iter().collect(),
+        };
+    Ok(BoxSelection {
+        boxes,
+        change_boxes,
+    })
 }
 
 #[cfg(test)]
+#[allow(clippy::unwrap_used)]
 mod tests {
 
     use ergotree_ir::chain::ergo_box::box_value::arbitrary::ArbBoxValueRange;
     use ergotree_ir::chain::ergo_box::box_value::BoxValue;
     use ergotree_ir::chain::ergo_box::BoxTokens;
+    use ergotree_ir::chain::token::arbitrary::ArbTokenIdParam;
     use ergotree_ir::chain::token::Token;
+    use ergotree_ir::chain::token::TokenAmount;
+    use ergotree_ir::chain::token::TokenId;
     use proptest::{arbitrary::Arbitrary, collection::vec, option::of, prelude::*};
     use sigma_test_util::force_any_val;
+    use sigma_test_util::force_any_val_with;
+    use std::convert::TryInto;
 
+    use crate::wallet::box_selector::checked_sum_tokens;
+    use crate::wallet::box_selector::select_by_indices;
+    use crate::wallet::box_selector::selection_change;
     use crate::wallet::box_selector::sum_tokens;
     use crate::wallet::box_selector::sum_tokens_from_boxes;
+    use crate::wallet::box_selector::tokens_diff;
+    use crate::wallet::box_selector::BoxSelectorError;
+    use crate::wallet::box_selector::TokenAmountsOverflow;
 
     use super::ErgoBoxAssetsData;
 
@@ -193,6 +406,7 @@ mod tests {
         assert_eq!(
             u64::from(
                 *sum_tokens_from_boxes(vec![b.clone(), b].as_slice())
+                    .unwrap()
                     .get(&token.token_id)
                     .unwrap()
             ),
@@ -200,11 +414,183 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_tokens_diff() {
+        let token = |amount: u64| Token {
+            token_id: force_any_val_with::<TokenId>(ArbTokenIdParam::Arbitrary),
+            amount: amount.try_into().unwrap(),
+        };
+        let assets = |tokens: Vec<Token>| ErgoBoxAssetsData {
+            value: BoxValue::SAFE_USER_MIN,
+            tokens: BoxTokens::from_vec(tokens).ok(),
+        };
+        let (kept, reduced, burned, minted) = (token(10), token(10), token(5), token(7));
+        let inputs = vec![
+            assets(vec![kept.clone(), reduced.clone()]),
+            assets(vec![burned.clone()]),
+        ];
+        let outputs = vec![
+            assets(vec![kept.clone(), minted.clone()]),
+            assets(vec![Token {
+                amount: 4.try_into().unwrap(),
+                ..reduced.clone()
+            }]),
+        ];
+        let diff = tokens_diff(outputs.as_slice(), inputs.as_slice()).unwrap();
+        assert_eq!(diff.minted.len(), 1);
+        assert_eq!(diff.minted.get(&minted.token_id), Some(minted.amount));
+        assert_eq!(diff.burned.len(), 2);
+        assert_eq!(diff.burned.get(&burned.token_id), Some(burned.amount));
+        assert_eq!(
+            diff.burned.get(&reduced.token_id),
+            Some(6.try_into().unwrap())
+        );
+        let reverse = tokens_diff(inputs.as_slice(), outputs.as_slice()).unwrap();
+        assert_eq!(reverse.minted, diff.burned);
+        assert_eq!(reverse.burned, diff.minted);
+    }
+
+    #[test]
+    fn test_checked_sum_tokens_overflow() {
+        let token = Token {
+            amount: TokenAmount::MAX_RAW.try_into().unwrap(),
+            ..force_any_val::<Token>()
+        };
+        let b = ErgoBoxAssetsData {
+            value: BoxValue::SAFE_USER_MIN,
+            tokens: BoxTokens::from_vec(vec![token.clone()]).ok(),
+        };
+        assert_eq!(
+            checked_sum_tokens(vec![b.clone()].as_slice())
+                .unwrap()
+                .get(&token.token_id),
+            Some(token.amount)
+        );
+        assert_eq!(
+            checked_sum_tokens(vec![b.clone(), b].as_slice()),
+            Err(TokenAmountsOverflow(token.token_id))
+        );
+    }
+
+    #[test]
+    fn test_selection_change() {
+        let token = Token {
+            amount: 10.try_into().unwrap(),
+            ..force_any_val::<Token>()
+        };
+        let b = ErgoBoxAssetsData {
+            value: BoxValue::SAFE_USER_MIN,
+            tokens: BoxTokens::from_vec(vec![token.clone()]).ok(),
+        };
+        let boxes = vec![b.clone(), b];
+        let double_value = BoxValue::SAFE_USER_MIN
+            .checked_add(&BoxValue::SAFE_USER_MIN)
+            .unwrap();
+        let double_token = Token {
+            amount: token.amount.checked_add(&token.amount).unwrap(),
+            ..token.clone()
+        };
+        // exact targets
+        assert_eq!(
+            selection_change(
+                boxes.as_slice(),
+                double_value,
+                vec![token.clone(), token.clone()].as_slice()
+            ),
+            Ok(None)
+        );
+        // the tokens left over are the change
+        assert_eq!(
+            selection_change(boxes.as_slice(), BoxValue::SAFE_USER_MIN, &[]),
+            Ok(Some(ErgoBoxAssetsData {
+                value: BoxValue::SAFE_USER_MIN,
+                tokens: BoxTokens::from_vec(vec![double_token.clone()]).ok(),
+            }))
+        );
+        assert_eq!(
+            selection_change(
+                boxes.as_slice(),
+                double_value.checked_add(&BoxValue::SAFE_USER_MIN).unwrap(),
+                &[]
+            ),
+            Err(BoxSelectorError::NotEnoughCoins(
+                *BoxValue::SAFE_USER_MIN.as_u64()
+            ))
+        );
+        assert_eq!(
+            selection_change(
+                boxes.as_slice(),
+                double_value,
+                vec![double_token, token.clone()].as_slice()
+            ),
+            Err(BoxSelectorError::NotEnoughTokens(vec![token]))
+        );
+    }
+
+    #[test]
+    fn test_select_by_indices() {
+        let assets = |value: u64| ErgoBoxAssetsData {
+            value: value.try_into().unwrap(),
+            tokens: None,
+        };
+        let safe_min = *BoxValue::SAFE_USER_MIN.as_u64();
+        let inputs = vec![assets(safe_min), assets(safe_min * 2), assets(safe_min * 3)];
+        let selection =
+            select_by_indices(inputs.clone(), &[2, 0], BoxValue::SAFE_USER_MIN, &[]).unwrap();
+        assert_eq!(
+            selection.boxes,
+            vec![assets(safe_min * 3), assets(safe_min)]
+        );
+        assert_eq!(selection.change_boxes, vec![assets(safe_min * 3)]);
+        // no change
+        assert_eq!(
+            select_by_indices(
+                inputs.clone(),
+                &[1],
+                (safe_min * 2).try_into().unwrap(),
+                &[]
+            )
+            .unwrap()
+            .change_boxes,
+            vec![]
+        );
+        // change below the minimal value
+        assert_eq!(
+            select_by_indices(
+                inputs.clone(),
+                &[1],
+                (safe_min * 2 - 1).try_into().unwrap(),
+                &[]
+            ),
+            Err(BoxSelectorError::NotEnoughCoins(safe_min - 1))
+        );
+        assert_eq!(
+            select_by_indices(inputs.clone(), &[0, 3], BoxValue::SAFE_USER_MIN, &[]),
+            Err(BoxSelectorError::InvalidIndex(3))
+        );
+        assert_eq!(
+            select_by_indices(inputs.clone(), &[0, 1, 0], BoxValue::SAFE_USER_MIN, &[]),
+            Err(BoxSelectorError::DuplicateIndex(0))
+        );
+        assert_eq!(
+            select_by_indices(inputs, &[0], (safe_min * 2).try_into().unwrap(), &[]),
+            Err(BoxSelectorError::NotEnoughCoins(safe_min))
+        );
+    }
+
     proptest! {
 
+        #[test]
+        fn checked_sum_tokens_eq(bs in vec(any::<ErgoBoxAssetsData>(), 0..5)) {
+            prop_assert_eq!(
+                checked_sum_tokens(bs.as_slice()).unwrap().to_tokens().len(),
+                sum_tokens_from_boxes(bs.as_slice()).unwrap().len()
+            );
+        }
+
         #[test]
         fn sum_tokens_eq(b in any::<ErgoBoxAssetsData>()) {
-            prop_assert_eq!(sum_tokens(b.tokens.as_ref().map(BoxTokens::as_ref)), sum_tokens_from_boxes(vec![b].as_slice()))
+            prop_assert_eq!(sum_tokens(b.tokens.as_ref().map(BoxTokens::as_ref)).unwrap(), sum_tokens_from_boxes(vec![b].as_slice()).unwrap())
         }
     }
 }
@@ -0,0 +1,337 @@
+//! Reservation of the boxes selected for the pending transactions
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::sync::MutexGuard;
+use std::time::Duration;
+use std::time::Instant;
+
+use ergotree_ir::chain::ergo_box::box_value::BoxValue;
+use ergotree_ir::chain::ergo_box::BoxId;
+use ergotree_ir::chain::token::Token;
+use thiserror::Error;
+
+use super::BoxSelection;
+use super::BoxSelector;
+use super::BoxSelectorError;
+use super::ErgoBoxAssets;
+use super::ErgoBoxId;
+
+/// Id of the reservation (boxes of a pending transaction)
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
+pub struct ReservationId(u64);
+
+/// Errors of BoxReservation
+#[derive(Error, PartialEq, Eq, Debug, Clone)]
+pub enum BoxReservationError {
+    /// Box is reserved for another pending transaction
+    #[error("box {0:?} is already reserved")]
+    AlreadyReserved(BoxId),
+
+    /// Expiration time (now + time-to-live) overflows
+    #[error("reservation time-to-live {0:?} is too long")]
+    TtlOverflow(Duration),
+
+    /// Box selection failed
+    #[error("Box selector error: {0}")]
+    BoxSelectorError(#[from] BoxSelectorError),
+}
+
+fn expiration(now: Instant, ttl: Duration) -> Result<Instant, BoxReservationError> {
+    now.checked_add(ttl)
+        .ok_or(BoxReservationError::TtlOverflow(ttl))
+}
+
+#[derive(Debug)]
+struct Reservation {
+    box_ids: Vec<BoxId>,
+    expires_at: Instant,
+}
+
+#[derive(Debug, Default)]
+struct Reservations {
+    next_id: u64,
+    reservations: HashMap<ReservationId, Reservation>,
+    reserved_boxes: HashMap<BoxId, ReservationId>,
+}
+
+impl Reservations {
+    fn remove_expired(&mut self, now: Instant) {
+        let expired: Vec<ReservationId> = self
+            .reservations
+            .iter()
+            .filter(|(_, r)| r.expires_at <= now)
+            .map(|(id, _)| *id)
+            .collect();
+        for id in expired {
+            self.remove(id);
+        }
+    }
+
+    fn remove(&mut self, id: ReservationId) -> Vec<BoxId> {
+        let box_ids = self
+            .reservations
+            .remove(&id)
+            .map(|r| r.box_ids)
+            .unwrap_or_default();
+        for box_id in &box_ids {
+            self.reserved_boxes.remove(box_id);
+        }
+        box_ids
+    }
+
+    fn insert(&mut self, box_ids: Vec<BoxId>, expires_at: Instant) -> ReservationId {
+        let id = ReservationId(self.next_id);
+        self.next_id += 1;
+        for box_id in &box_ids {
+            self.reserved_boxes.insert(box_id.clone(), id);
+        }
+        self.reservations.insert(
+            id,
+            Reservation {
+                box_ids,
+                expires_at,
+            },
+        );
+        id
+    }
+}
+
+/// Boxes reserved for the pending transactions, to be shared by the services building
+/// transactions concurrently so that the same box is not selected for two transactions.
+/// A reservation lasts until it is released (when the transaction is confirmed or abandoned) or
+/// its time-to-live expires (e.g. the transaction was never submitted).
+///
+/// The box selectors know nothing about the reservations, only the selection made with
+/// [`BoxReservation::select_and_reserve`] skips the reserved boxes. Calling
+/// [`BoxSelector::select`] directly might select the reserved boxes.
+///
+/// Uses [`Instant`] for the expiration, which is not available on wasm32-unknown-unknown, so
+/// the reservation is not built for wasm32.
+#[derive(Debug, Default)]
+pub struct BoxReservation {
+    state: Mutex<Reservations>,
+}
+
+impl BoxReservation {
+    /// No boxes reserved
+    pub fn new() -> Self {
+        BoxReservation::default()
+    }
+
+    // the state is consistent after every operation, so a panic in another thread does not
+    // invalidate it
+    fn lock(&self) -> MutexGuard<Reservations> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Reserve the boxes for `ttl`, fails if any of them is already reserved
+    pub fn reserve(
+        &self,
+        box_ids: &[BoxId],
+        ttl: Duration,
+    ) -> Result<ReservationId, BoxReservationError> {
+        self.reserve_at(box_ids, ttl, Instant::now())
+    }
+
+    fn reserve_at(
+        &self,
+        box_ids: &[BoxId],
+        ttl: Duration,
+        now: Instant,
+    ) -> Result<ReservationId, BoxReservationError> {
+        let expires_at = expiration(now, ttl)?;
+        let mut state = self.lock();
+        state.remove_expired(now);
+        if let Some(reserved) = box_ids
+            .iter()
+            .find(|id| state.reserved_boxes.contains_key(*id))
+        {
+            return Err(BoxReservationError::AlreadyReserved(reserved.clone()));
+        }
+        Ok(state.insert(box_ids.to_vec(), expires_at))
+    }
+
+    /// Release the reserved boxes (the transaction is confirmed or abandoned), returns the boxes
+    /// of the reservation (none if it's expired or released already)
+    pub fn release(&self, id: ReservationId) -> Vec<BoxId> {
+        self.lock().remove(id)
+    }
+
+    /// Returns true if the box is reserved (and the reservation is not expired)
+    pub fn is_reserved(&self, box_id: &BoxId) -> bool {
+        self.is_reserved_at(box_id, Instant::now())
+    }
+
+    fn is_reserved_at(&self, box_id: &BoxId, now: Instant) -> bool {
+        let mut state = self.lock();
+        state.remove_expired(now);
+        state.reserved_boxes.contains_key(box_id)
+    }
+
+    /// Select the boxes with `selector` out of the `inputs` which are not reserved, and reserve the
+    /// selected boxes for `ttl`. The reservation is atomic, concurrent calls never select the same
+    /// box.
+    pub fn select_and_reserve<T, S>(
+        &self,
+        selector: &S,
+        inputs: Vec<T>,
+        target_balance: BoxValue,
+        target_tokens: &[Token],
+        ttl: Duration,
+    ) -> Result<(BoxSelection<T>, ReservationId), BoxReservationError>
+    where
+        T: ErgoBoxAssets + ErgoBoxId,
+        S: BoxSelector<T>,
+    {
+        self.select_and_reserve_at(
+            selector,
+            inputs,
+            target_balance,
+            target_tokens,
+            ttl,
+            Instant::now(),
+        )
+    }
+
+    fn select_and_reserve_at<T, S>(
+        &self,
+        selector: &S,
+        inputs: Vec<T>,
+        target_balance: BoxValue,
+        target_tokens: &[Token],
+        ttl: Duration,
+        now: Instant,
+    ) -> Result<(BoxSelection<T>, ReservationId), BoxReservationError>
+    where
+        T: ErgoBoxAssets + ErgoBoxId,
+        S: BoxSelector<T>,
+    {
+        let expires_at = expiration(now, ttl)?;
+        // the lock is held during the selection, so that the selected boxes are reserved before
+        // any other selection
+        let mut state = self.lock();
+        state.remove_expired(now);
+        let unreserved: Vec<T> = inputs
+            .into_iter()
+            .filter(|b| !state.reserved_boxes.contains_key(&b.box_id()))
+            .collect();
+        let selection = selector.select(unreserved, target_balance, target_tokens)?;
+        let box_ids = selection.boxes.iter().map(|b| b.box_id()).collect();
+        let id = state.insert(box_ids, expires_at);
+        Ok((selection, id))
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::wallet::box_selector::SimpleBoxSelector;
+    use ergotree_ir::chain::ergo_box::ErgoBox;
+    use sigma_test_util::force_any_val;
+
+    const TTL: Duration = Duration::from_secs(60);
+
+    fn boxes(count: usize) -> Vec<ErgoBox> {
+        (0..count)
+            .map(|_| {
+                let mut b = force_any_val::<ErgoBox>();
+                b.value = BoxValue::SAFE_USER_MIN;
+                b.tokens = None;
+                b
+            })
+            .collect()
+    }
+
+    #[test]
+    fn concurrent_selections_do_not_overlap() {
+        let reservation = BoxReservation::new();
+        let inputs = boxes(2);
+        let selector = SimpleBoxSelector::new();
+        let now = Instant::now();
+        let (first, first_id) = reservation
+            .select_and_reserve_at(
+                &selector,
+                inputs.clone(),
+                BoxValue::SAFE_USER_MIN,
+                &[],
+                TTL,
+                now,
+            )
+            .unwrap();
+        let (second, _) = reservation
+            .select_and_reserve_at(
+                &selector,
+                inputs.clone(),
+                BoxValue::SAFE_USER_MIN,
+                &[],
+                TTL,
+                now,
+            )
+            .unwrap();
+        assert_eq!(first.boxes.len(), 1);
+        assert_eq!(second.boxes.len(), 1);
+        assert_ne!(first.boxes[0].box_id(), second.boxes[0].box_id());
+        // all the boxes are reserved
+        assert!(matches!(
+            reservation.select_and_reserve_at(
+                &selector,
+                inputs.clone(),
+                BoxValue::SAFE_USER_MIN,
+                &[],
+                TTL,
+                now
+            ),
+            Err(BoxReservationError::BoxSelectorError(
+                BoxSelectorError::NotEnoughCoins(_)
+            ))
+        ));
+        // the first transaction is abandoned
+        assert_eq!(reservation.release(first_id), vec![first.boxes[0].box_id()]);
+        assert!(!reservation.is_reserved_at(&first.boxes[0].box_id(), now));
+        let (third, _) = reservation
+            .select_and_reserve_at(&selector, inputs, BoxValue::SAFE_USER_MIN, &[], TTL, now)
+            .unwrap();
+        assert_eq!(third.boxes, first.boxes);
+    }
+
+    #[test]
+    fn reservation_expires() {
+        let reservation = BoxReservation::new();
+        let box_ids: Vec<BoxId> = boxes(2).iter().map(ErgoBox::box_id).collect();
+        let now = Instant::now();
+        let id = reservation.reserve_at(&box_ids, TTL, now).unwrap();
+        assert_eq!(
+            reservation.reserve_at(&box_ids[1..], TTL, now),
+            Err(BoxReservationError::AlreadyReserved(box_ids[1].clone()))
+        );
+        assert!(reservation.is_reserved_at(&box_ids[0], now + TTL / 2));
+        assert!(!reservation.is_reserved_at(&box_ids[0], now + TTL));
+        assert!(reservation.reserve_at(&box_ids, TTL, now + TTL).is_ok());
+        // the expired reservation is gone
+        assert_eq!(reservation.release(id), vec![]);
+    }
+
+    #[test]
+    fn ttl_overflow() {
+        let reservation = BoxReservation::new();
+        let box_ids: Vec<BoxId> = boxes(1).iter().map(ErgoBox::box_id).collect();
+        let ttl = Duration::MAX;
+        assert_eq!(
+            reservation.reserve(&box_ids, ttl),
+            Err(BoxReservationError::TtlOverflow(ttl))
+        );
+        assert!(matches!(
+            reservation.select_and_reserve(
+                &SimpleBoxSelector::new(),
+                boxes(1),
+                BoxValue::SAFE_USER_MIN,
+                &[],
+                ttl
+            ),
+            Err(BoxReservationError::TtlOverflow(_))
+        ));
+        assert!(!reservation.is_reserved(&box_ids[0]));
+    }
+}
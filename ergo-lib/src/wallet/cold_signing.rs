@@ -0,0 +1,396 @@
+//! Messages of the cold (offline) wallet signing, see EIP-19 -
+//! <https://github.com/ergoplatform/eips/blob/f280890a4163f2f2e988a0091c078e36912fc531/eip-0019.md>
+//!
+//! The hot wallet sends a [`ColdSigningRequest`] (reduced transaction, the sender's address and
+//! the boxes it spends) to the cold wallet, which signs the transaction and sends back a
+//! [`ColdSigningResponse`].
+//! The messages are JSON objects (`{"reducedTx": .., "sender": .., "inputs": [..]}` and
+//! `{"signedTx": ..}`) with the transactions and the boxes in the Sigma serialization encoded in
+//! Base64 (URL-safe alphabet). For the QR codes the JSON is split into the pages
+//! `{"CSR": <part>, "n": <pages>, "p": <page>}` (`CSTX` for the response) numbered from 1.
+
+use ergotree_ir::chain::address::AddressEncoder;
+use ergotree_ir::chain::address::NetworkAddress;
+use ergotree_ir::chain::ergo_box::ErgoBox;
+use ergotree_ir::serialization::SigmaParsingError;
+use ergotree_ir::serialization::SigmaSerializable;
+use ergotree_ir::serialization::SigmaSerializationError;
+use serde::Deserialize;
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::chain::transaction::reduced::ReducedTransaction;
+use crate::chain::transaction::Transaction;
+
+/// Errors of the cold signing messages encoding
+#[derive(Error, PartialEq, Eq, Debug, Clone)]
+pub enum ColdSigningError {
+    /// Invalid JSON encoding
+    #[error("JSON error: {0}")]
+    Json(String),
+    /// Invalid Base64 encoding
+    #[error("Base64 error: {0}")]
+    Base64(String),
+    /// Transaction or box serialization error
+    #[error("Serialization error: {0}")]
+    SigmaSerializationError(SigmaSerializationError),
+    /// Transaction or box parsing error
+    #[error("Parsing error: {0}")]
+    SigmaParsingError(SigmaParsingError),
+    /// Unexpected message content (e.g. invalid sender address)
+    #[error("Invalid message: {0}")]
+    InvalidMessage(String),
+    /// Invalid page (prefix, page number or total count)
+    #[error("Invalid chunk: {0}")]
+    InvalidChunk(String),
+    /// Page with the given number (starting with 1) is missing
+    #[error("Chunk {0} is missing")]
+    MissingChunk(usize),
+}
+
+impl From<SigmaSerializationError> for ColdSigningError {
+    fn from(e: SigmaSerializationError) -> Self {
+        ColdSigningError::SigmaSerializationError(e)
+    }
+}
+
+impl From<SigmaParsingError> for ColdSigningError {
+    fn from(e: SigmaParsingError) -> Self {
+        ColdSigningError::SigmaParsingError(e)
+    }
+}
+
+/// Request to sign the reduced transaction, sent to the cold wallet
+#[derive(PartialEq, Debug, Clone)]
+pub struct ColdSigningRequest {
+    /// Transaction to sign
+    pub reduced_tx: ReducedTransaction,
+    /// Address of the sender, so that the cold wallet can pick the key to sign with (optional)
+    pub sender: Option<NetworkAddress>,
+    /// Boxes spent by the transaction, so that the cold wallet can show the amounts and the fee
+    /// (optional, empty if not sent)
+    pub inputs: Vec<ErgoBox>,
+}
+
+/// Signed transaction, sent back by the cold wallet
+#[derive(PartialEq, Debug, Clone)]
+pub struct ColdSigningResponse {
+    /// Signed transaction
+    pub signed_tx: Transaction,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ColdSigningRequestJson {
+    #[serde(rename = "reducedTx")]
+    reduced_tx: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    sender: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    inputs: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ColdSigningResponseJson {
+    #[serde(rename = "signedTx")]
+    signed_tx: String,
+}
+
+impl ColdSigningRequest {
+    /// Page prefix of the request
+    pub const CHUNK_PREFIX: &'static str = "CSR";
+
+    /// Create a request
+    pub fn new(
+        reduced_tx: ReducedTransaction,
+        sender: Option<NetworkAddress>,
+        inputs: Vec<ErgoBox>,
+    ) -> Self {
+        ColdSigningRequest {
+            reduced_tx,
+            sender,
+            inputs,
+        }
+    }
+
+    /// JSON encoding (`{"reducedTx": .., "sender": .., "inputs": [..]}`)
+    pub fn to_json(&self) -> Result<String, ColdSigningError> {
+        let json = ColdSigningRequestJson {
+            reduced_tx: encode_base64(&self.reduced_tx.sigma_serialize_bytes()?),
+            sender: self.sender.as_ref().map(NetworkAddress::to_base58),
+            inputs: self
+                .inputs
+                .iter()
+                .map(|b| b.sigma_serialize_bytes().map(|bytes| encode_base64(&bytes)))
+                .collect::<Result<Vec<String>, _>>()?,
+        };
+        serde_json::to_string(&json).map_err(|e| ColdSigningError::Json(e.to_string()))
+    }
+
+    /// Parse from JSON encoding
+    pub fn from_json(json: &str) -> Result<Self, ColdSigningError> {
+        let json: ColdSigningRequestJson =
+            serde_json::from_str(json).map_err(|e| ColdSigningError::Json(e.to_string()))?;
+        let reduced_tx = ReducedTransaction::sigma_parse_bytes(&decode_base64(&json.reduced_tx)?)?;
+        let sender = json
+            .sender
+            .map(|s| {
+                AddressEncoder::unchecked_parse_network_address_from_str(&s).map_err(|e| {
+                    ColdSigningError::InvalidMessage(format!("invalid sender {}: {}", s, e))
+                })
+            })
+            .transpose()?;
+        let inputs = json
+            .inputs
+            .iter()
+            .map(|s| Ok(ErgoBox::sigma_parse_bytes(&decode_base64(s)?)?))
+            .collect::<Result<Vec<ErgoBox>, ColdSigningError>>()?;
+        Ok(ColdSigningRequest {
+            reduced_tx,
+            sender,
+            inputs,
+        })
+    }
+
+    /// Split the JSON encoding into the pages (`{"CSR": <part>, "n": <pages>, "p": <page>}`)
+    /// with at most `max_data_len` characters of the JSON in each
+    pub fn to_chunks(&self, max_data_len: usize) -> Result<Vec<String>, ColdSigningError> {
+        to_chunks(
+            ColdSigningRequest::CHUNK_PREFIX,
+            &self.to_json()?,
+            max_data_len,
+        )
+    }
+
+    /// Parse from the pages (in any order)
+    pub fn from_chunks<S: AsRef<str>>(chunks: &[S]) -> Result<Self, ColdSigningError> {
+        ColdSigningRequest::from_json(&join_chunks(ColdSigningRequest::CHUNK_PREFIX, chunks)?)
+    }
+}
+
+impl ColdSigningResponse {
+    /// Page prefix of the response
+    pub const CHUNK_PREFIX: &'static str = "CSTX";
+
+    /// Create a response
+    pub fn new(signed_tx: Transaction) -> Self {
+        ColdSigningResponse { signed_tx }
+    }
+
+    /// JSON encoding (`{"signedTx": ..}`)
+    pub fn to_json(&self) -> Result<String, ColdSigningError> {
+        let json = ColdSigningResponseJson {
+            signed_tx: encode_base64(&self.signed_tx.sigma_serialize_bytes()?),
+        };
+        serde_json::to_string(&json).map_err(|e| ColdSigningError::Json(e.to_string()))
+    }
+
+    /// Parse from JSON encoding
+    pub fn from_json(json: &str) -> Result<Self, ColdSigningError> {
+        let json: ColdSigningResponseJson =
+            serde_json::from_str(json).map_err(|e| ColdSigningError::Json(e.to_string()))?;
+        let signed_tx = Transaction::sigma_parse_bytes(&decode_base64(&json.signed_tx)?)?;
+        Ok(ColdSigningResponse { signed_tx })
+    }
+
+    /// Split the JSON encoding into the pages (`{"CSTX": <part>, "n": <pages>, "p": <page>}`)
+    /// with at most `max_data_len` characters of the JSON in each
+    pub fn to_chunks(&self, max_data_len: usize) -> Result<Vec<String>, ColdSigningError> {
+        to_chunks(
+            ColdSigningResponse::CHUNK_PREFIX,
+            &self.to_json()?,
+            max_data_len,
+        )
+    }
+
+    /// Parse from the pages (in any order)
+    pub fn from_chunks<S: AsRef<str>>(chunks: &[S]) -> Result<Self, ColdSigningError> {
+        ColdSigningResponse::from_json(&join_chunks(ColdSigningResponse::CHUNK_PREFIX, chunks)?)
+    }
+}
+
+fn encode_base64(bytes: &[u8]) -> String {
+    base64::encode_config(bytes, base64::URL_SAFE)
+}
+
+fn decode_base64(s: &str) -> Result<Vec<u8>, ColdSigningError> {
+    // the padding is optional
+    base64::decode_config(s.trim_end_matches('='), base64::URL_SAFE_NO_PAD)
+        .map_err(|e| ColdSigningError::Base64(e.to_string()))
+}
+
+fn to_chunks(
+    prefix: &str,
+    data: &str,
+    max_data_len: usize,
+) -> Result<Vec<String>, ColdSigningError> {
+    let chars: Vec<char> = data.chars().collect();
+    let parts: Vec<String> = chars
+        .chunks(max_data_len.max(1))
+        .map(|part| part.iter().collect())
+        .collect();
+    let total = parts.len();
+    parts
+        .into_iter()
+        .enumerate()
+        .map(|(idx, part)| {
+            let mut page = serde_json::Map::new();
+            page.insert(prefix.to_string(), part.into());
+            page.insert("n".to_string(), total.into());
+            page.insert("p".to_string(), (idx + 1).into());
+            serde_json::to_string(&page).map_err(|e| ColdSigningError::Json(e.to_string()))
+        })
+        .collect()
+}
+
+fn join_chunks<S: AsRef<str>>(prefix: &str, chunks: &[S]) -> Result<String, ColdSigningError> {
+    let mut parts: Vec<Option<String>> = Vec::new();
+    for chunk in chunks {
+        let chunk = chunk.as_ref();
+        let invalid = || ColdSigningError::InvalidChunk(chunk.to_string());
+        let page: serde_json::Value = serde_json::from_str(chunk).map_err(|_| invalid())?;
+        let data = page
+            .get(prefix)
+            .and_then(serde_json::Value::as_str)
+            .ok_or_else(invalid)?;
+        let total = page
+            .get("n")
+            .and_then(serde_json::Value::as_u64)
+            .ok_or_else(invalid)? as usize;
+        let number = page
+            .get("p")
+            .and_then(serde_json::Value::as_u64)
+            .ok_or_else(invalid)? as usize;
+        if parts.is_empty() {
+            parts = vec![None; total];
+        }
+        if number == 0 || number > total || total != parts.len() {
+            return Err(invalid());
+        }
+        parts[number - 1] = Some(data.to_string());
+    }
+    if parts.is_empty() {
+        return Err(ColdSigningError::MissingChunk(1));
+    }
+    parts
+        .into_iter()
+        .enumerate()
+        .map(|(idx, part)| part.ok_or(ColdSigningError::MissingChunk(idx + 1)))
+        .collect()
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use ergotree_ir::chain::address::Address;
+    use ergotree_ir::chain::address::NetworkPrefix;
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    proptest! {
+
+        #![proptest_config(ProptestConfig::with_cases(16))]
+
+        #[test]
+        fn request_roundtrip(
+            reduced_tx in any::<ReducedTransaction>(),
+            sender in any::<Option<Address>>(),
+            inputs in vec(any::<ErgoBox>(), 0..5),
+            max_data_len in 10usize..500,
+        ) {
+            let sender = sender.map(|a| NetworkAddress::new(NetworkPrefix::Mainnet, &a));
+            let req = ColdSigningRequest::new(reduced_tx, sender, inputs);
+            let json = req.to_json().unwrap();
+            prop_assert_eq![ColdSigningRequest::from_json(&json).unwrap(), req.clone()];
+            let mut chunks = req.to_chunks(max_data_len).unwrap();
+            prop_assert!(chunks.iter().all(|c| c.starts_with("{\"CSR\":")));
+            chunks.reverse();
+            prop_assert_eq![ColdSigningRequest::from_chunks(&chunks).unwrap(), req];
+        }
+
+        #[test]
+        fn response_roundtrip(signed_tx in any::<Transaction>(), max_data_len in 10usize..500) {
+            let resp = ColdSigningResponse::new(signed_tx);
+            prop_assert_eq![ColdSigningResponse::from_json(&resp.to_json().unwrap()).unwrap(), resp.clone()];
+            let chunks = resp.to_chunks(max_data_len).unwrap();
+            prop_assert_eq![ColdSigningResponse::from_chunks(&chunks).unwrap(), resp];
+        }
+    }
+
+    #[test]
+    fn request_json_keys() {
+        let req = ColdSigningRequest::new(
+            sigma_test_util::force_any_val::<ReducedTransaction>(),
+            None,
+            vec![],
+        );
+        let json: serde_json::Value = serde_json::from_str(&req.to_json().unwrap()).unwrap();
+        let keys: Vec<&String> = json.as_object().unwrap().keys().collect();
+        assert_eq!(keys, vec!["reducedTx"]);
+        // the padding is optional
+        let padded = json.get("reducedTx").unwrap().as_str().unwrap().to_string();
+        let unpadded = format!("{{\"reducedTx\":\"{}\"}}", padded.trim_end_matches('='));
+        assert_eq!(ColdSigningRequest::from_json(&unpadded).unwrap(), req);
+    }
+
+    #[test]
+    fn chunks() {
+        let data = "abcdefghij";
+        let chunks = to_chunks("CSR", data, 4).unwrap();
+        assert_eq!(
+            chunks,
+            vec![
+                r#"{"CSR":"abcd","n":3,"p":1}"#,
+                r#"{"CSR":"efgh","n":3,"p":2}"#,
+                r#"{"CSR":"ij","n":3,"p":3}"#
+            ]
+        );
+        assert_eq!(join_chunks("CSR", &chunks).unwrap(), data);
+        assert_eq!(
+            join_chunks("CSR", &[&chunks[0], &chunks[2]]),
+            Err(ColdSigningError::MissingChunk(2))
+        );
+        assert!(matches!(
+            join_chunks("CSTX", &chunks),
+            Err(ColdSigningError::InvalidChunk(_))
+        ));
+        assert!(matches!(
+            join_chunks(
+                "CSR",
+                &[
+                    r#"{"CSR":"abcd","n":3,"p":1}"#,
+                    r#"{"CSR":"efgh","n":2,"p":2}"#
+                ]
+            ),
+            Err(ColdSigningError::InvalidChunk(_))
+        ));
+        assert!(matches!(
+            join_chunks("CSR", &[r#"{"CSR":"abcd","n":3,"p":4}"#]),
+            Err(ColdSigningError::InvalidChunk(_))
+        ));
+        assert!(matches!(
+            join_chunks("CSR", &["CSR/1/1/abcd"]),
+            Err(ColdSigningError::InvalidChunk(_))
+        ));
+        assert_eq!(
+            join_chunks::<&str>("CSR", &[]),
+            Err(ColdSigningError::MissingChunk(1))
+        );
+    }
+
+    #[test]
+    fn invalid_message() {
+        assert!(matches!(
+            ColdSigningResponse::from_json(r#"{"signedTx":1}"#),
+            Err(ColdSigningError::Json(_))
+        ));
+        assert!(matches!(
+            ColdSigningRequest::from_json(r#"{"signedTx":"AA"}"#),
+            Err(ColdSigningError::Json(_))
+        ));
+        assert!(matches!(
+            ColdSigningRequest::from_json(r#"{"reducedTx":"not base64!"}"#),
+            Err(ColdSigningError::Base64(_))
+        ));
+    }
+}
@@ -0,0 +1,274 @@
+//! Minimal transaction fee estimation based on the transaction size and blockchain parameters
+
+use ergotree_interpreter::sigma_protocol;
+use ergotree_interpreter::sigma_protocol::prover::ProofBytes;
+use ergotree_ir::chain::ergo_box::box_value::BoxValue;
+use ergotree_ir::chain::ergo_box::box_value::BoxValueError;
+use ergotree_ir::chain::ergo_box::BoxId;
+use ergotree_ir::chain::ergo_box::ErgoBox;
+use ergotree_ir::ergo_tree::ErgoTree;
+use ergotree_ir::mir::constant::Constant;
+use ergotree_ir::mir::constant::Literal;
+use ergotree_ir::mir::expr::Expr;
+use ergotree_ir::serialization::SigmaSerializable;
+use ergotree_ir::serialization::SigmaSerializationError;
+use ergotree_ir::sigma_protocol::sigma_boolean::SigmaBoolean;
+use ergotree_ir::sigma_protocol::sigma_boolean::SigmaConjecture;
+use thiserror::Error;
+
+use crate::chain::ergo_box::box_builder::ErgoBoxCandidateBuilder;
+use crate::chain::ergo_box::box_builder::ErgoBoxCandidateBuilderError;
+use crate::chain::parameters;
+use crate::chain::parameters::Parameters;
+use crate::chain::transaction::input::prover_result::ProverResult;
+use crate::chain::transaction::unsigned::UnsignedTransaction;
+use crate::chain::transaction::Input;
+use crate::chain::transaction::Transaction;
+
+use super::tx_builder::new_miner_fee_box;
+
+/// Size of the group element (response `z` in the proof of a leaf proposition)
+const GROUP_SIZE: usize = 32;
+
+/// Fee estimation errors
+#[derive(Error, PartialEq, Eq, Debug, Clone)]
+pub enum FeeEstimationError {
+    /// Parameter is not found in the blockchain parameters
+    #[error("parameter with id {0} not found")]
+    MissingParameter(i8),
+    /// Box spent by the input is not found
+    #[error("input box not found: {0:?}")]
+    InputBoxNotFound(BoxId),
+    /// Transaction serialization error
+    #[error("serialization error: {0}")]
+    SigmaSerializationError(#[from] SigmaSerializationError),
+    /// Invalid fee value
+    #[error("invalid fee value: {0}")]
+    BoxValueError(#[from] BoxValueError),
+    /// Miner's fee box building error
+    #[error("miner's fee box building error: {0}")]
+    ErgoBoxCandidateBuilderError(#[from] ErgoBoxCandidateBuilderError),
+}
+
+/// Estimates the minimal miner fee for the transaction as
+/// `tx_size * min_value_per_byte + inputs * input_cost + data_inputs * data_input_cost + outputs * output_cost`
+/// (one nanoERG per cost unit), where `tx_size` is the size of the signed transaction.
+/// Since the transaction is not signed yet, the proofs are mocked with dummy ones of the size
+/// of the proofs for the spent boxes propositions (see [`proof_size`]).
+/// `boxes_to_spend` should contain the boxes spent by the transaction inputs (in any order).
+/// The result is never lower than the minimal value of the miner's fee box (by `min_value_per_byte`).
+pub fn estimate_fee(
+    unsigned_tx: &UnsignedTransaction,
+    boxes_to_spend: &[ErgoBox],
+    parameters: &Parameters,
+) -> Result<BoxValue, FeeEstimationError> {
+    let param = |id: i8| {
+        parameters
+            .get(id)
+            .map(|v| v.max(0) as u64)
+            .ok_or(FeeEstimationError::MissingParameter(id))
+    };
+    let min_value_per_byte = param(parameters::MIN_VALUE_PER_BYTE_ID)?;
+    let input_cost = param(parameters::INPUT_COST_ID)?;
+    let data_input_cost = param(parameters::DATA_INPUT_COST_ID)?;
+    let output_cost = param(parameters::OUTPUT_COST_ID)?;
+
+    let inputs = unsigned_tx.inputs.clone().try_mapped(|ui| {
+        let input_box = boxes_to_spend
+            .iter()
+            .find(|b| b.box_id() == ui.box_id)
+            .ok_or_else(|| FeeEstimationError::InputBoxNotFound(ui.box_id.clone()))?;
+        let proof = match proof_size(&input_box.ergo_tree) {
+            0 => ProofBytes::Empty,
+            size => ProofBytes::Some(vec![0u8; size]),
+        };
+        Ok(Input::new(
+            ui.box_id,
+            ProverResult {
+                proof,
+                extension: ui.extension,
+            },
+        ))
+    })?;
+    let signed_tx_mock = Transaction::new(
+        inputs,
+        unsigned_tx.data_inputs.clone(),
+        unsigned_tx.output_candidates.clone(),
+    )?;
+    let tx_size = signed_tx_mock.sigma_serialize_bytes()?.len() as u64;
+    let data_inputs_count = unsigned_tx
+        .data_inputs
+        .as_ref()
+        .map(|d| d.len())
+        .unwrap_or(0) as u64;
+    let fee = tx_size
+        .saturating_mul(min_value_per_byte)
+        .saturating_add(unsigned_tx.inputs.len() as u64 * input_cost)
+        .saturating_add(data_inputs_count * data_input_cost)
+        .saturating_add(unsigned_tx.output_candidates.len() as u64 * output_cost)
+        .min(BoxValue::MAX_RAW);
+    let fee = BoxValue::new(fee.max(BoxValue::MIN_RAW))?;
+    let fee_box = new_miner_fee_box(fee, unsigned_tx.output_candidates.first().creation_height)?;
+    let min_fee = (ErgoBoxCandidateBuilder::from(fee_box).calc_box_size_bytes()? as u64)
+        .saturating_mul(min_value_per_byte);
+    if *fee.as_u64() < min_fee {
+        Ok(BoxValue::new(min_fee)?)
+    } else {
+        Ok(fee)
+    }
+}
+
+/// Size of the proof for spending a box with the given proposition.
+/// If the proposition is not a constant sigma proposition (i.e. a script), the proof is assumed
+/// to be for a single ProveDlog as the most common case.
+pub fn proof_size(ergo_tree: &ErgoTree) -> usize {
+    match ergo_tree.proposition().as_deref() {
+        Ok(Expr::Const(Constant {
+            v: Literal::SigmaProp(sp),
+            ..
+        })) => sigma_boolean_proof_size(sp.value()),
+        _ => sigma_protocol::SOUNDNESS_BYTES + GROUP_SIZE,
+    }
+}
+
+/// Size of the serialized proof for the given sigma proposition (see SigSerializer in Scala)
+fn sigma_boolean_proof_size(sb: &SigmaBoolean) -> usize {
+    match sb {
+        // no proof needed for `true`, and `false` cannot be proven
+        SigmaBoolean::TrivialProp(_) => 0,
+        // root challenge
+        _ => sigma_protocol::SOUNDNESS_BYTES + node_proof_size(sb, false),
+    }
+}
+
+fn node_proof_size(sb: &SigmaBoolean, with_challenge: bool) -> usize {
+    let challenge_size = if with_challenge {
+        sigma_protocol::SOUNDNESS_BYTES
+    } else {
+        0
+    };
+    let node_size = match sb {
+        SigmaBoolean::TrivialProp(_) => 0,
+        SigmaBoolean::ProofOfKnowledge(_) => GROUP_SIZE,
+        SigmaBoolean::SigmaConjecture(SigmaConjecture::Cand(cand)) => {
+            cand.items.iter().map(|it| node_proof_size(it, false)).sum()
+        }
+        SigmaBoolean::SigmaConjecture(SigmaConjecture::Cor(cor)) => {
+            // challenges are written for all children except the last one
+            let last = cor.items.len() - 1;
+            cor.items
+                .iter()
+                .enumerate()
+                .map(|(i, it)| node_proof_size(it, i != last))
+                .sum()
+        }
+        SigmaBoolean::SigmaConjecture(SigmaConjecture::Cthreshold(ct)) => {
+            // polynomial coefficients (n - k) and children without challenges
+            let coeffs = ct.items.len().saturating_sub(ct.n.max(0) as usize);
+            coeffs * sigma_protocol::SOUNDNESS_BYTES
+                + ct.items
+                    .iter()
+                    .map(|it| node_proof_size(it, false))
+                    .sum::<usize>()
+        }
+    };
+    challenge_size + node_size
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::chain::transaction::UnsignedInput;
+    use ergotree_ir::chain::address::Address;
+    use ergotree_ir::chain::ergo_box::NonMandatoryRegisters;
+    use ergotree_ir::chain::tx_id::TxId;
+    use ergotree_ir::sigma_protocol::sigma_boolean::cand::Cand;
+    use ergotree_ir::sigma_protocol::sigma_boolean::cor::Cor;
+    use ergotree_ir::sigma_protocol::sigma_boolean::cthreshold::Cthreshold;
+    use ergotree_ir::sigma_protocol::sigma_boolean::ProveDlog;
+    use ergotree_ir::sigma_protocol::sigma_boolean::SigmaProp;
+    use sigma_test_util::force_any_val;
+    use std::convert::TryInto;
+
+    fn pk() -> SigmaBoolean {
+        force_any_val::<ProveDlog>().into()
+    }
+
+    fn tree(sb: SigmaBoolean) -> ErgoTree {
+        let e: Expr = Constant::from(SigmaProp::new(sb)).into();
+        e.try_into().unwrap()
+    }
+
+    #[test]
+    fn test_proof_size() {
+        assert_eq!(proof_size(&tree(pk())), 24 + 32);
+        assert_eq!(proof_size(&tree(true.into())), 0);
+        let cand = Cand {
+            items: vec![pk(), pk()].try_into().unwrap(),
+        };
+        assert_eq!(proof_size(&tree(cand.into())), 24 + 32 + 32);
+        let cor = Cor {
+            items: vec![pk(), pk()].try_into().unwrap(),
+        };
+        assert_eq!(proof_size(&tree(cor.into())), 24 + (24 + 32) + 32);
+        let ct = Cthreshold {
+            n: 2,
+            items: vec![pk(), pk(), pk()].try_into().unwrap(),
+        };
+        assert_eq!(proof_size(&tree(ct.into())), 24 + 24 + 3 * 32);
+    }
+
+    #[test]
+    fn test_estimate_fee() {
+        let input_box = ErgoBox::new(
+            BoxValue::new(10000000000).unwrap(),
+            tree(pk()),
+            None,
+            NonMandatoryRegisters::empty(),
+            1,
+            TxId::zero(),
+            0,
+        )
+        .unwrap();
+        let out_box = ErgoBoxCandidateBuilder::new(
+            BoxValue::SAFE_USER_MIN,
+            Address::P2Pk(force_any_val::<ProveDlog>())
+                .script()
+                .unwrap(),
+            1,
+        )
+        .build()
+        .unwrap();
+        let unsigned_tx = UnsignedTransaction::new(
+            vec![UnsignedInput::from(input_box.clone())]
+                .try_into()
+                .unwrap(),
+            None,
+            vec![out_box.clone(), out_box].try_into().unwrap(),
+        )
+        .unwrap();
+        let params = Parameters::default();
+        let fee = estimate_fee(&unsigned_tx, &[input_box.clone()], &params).unwrap();
+        let tx_size = unsigned_tx.bytes_to_sign().unwrap().len() as u64 + 56;
+        let expected = tx_size * params.min_value_per_byte().unwrap() as u64
+            + params.input_cost().unwrap() as u64
+            + 2 * params.output_cost().unwrap() as u64;
+        assert_eq!(*fee.as_u64(), expected);
+
+        assert_eq!(
+            estimate_fee(&unsigned_tx, &[], &params),
+            Err(FeeEstimationError::InputBoxNotFound(input_box.box_id()))
+        );
+        assert_eq!(
+            estimate_fee(
+                &unsigned_tx,
+                &[input_box],
+                &Parameters::new(Default::default())
+            ),
+            Err(FeeEstimationError::MissingParameter(
+                parameters::MIN_VALUE_PER_BYTE_ID
+            ))
+        );
+    }
+}
@@ -0,0 +1,164 @@
+//! Transaction fee estimation from the fees paid in recent blocks
+
+use ergo_units::NanoErg;
+use ergotree_ir::chain::ergo_box::box_value::BoxValue;
+use ergotree_ir::ergo_tree::ErgoTree;
+use ergotree_ir::serialization::SigmaSerializable;
+use ergotree_ir::serialization::SigmaSerializationError;
+
+use crate::chain::network::Network;
+use crate::chain::transaction::Transaction;
+
+/// Collects fee-per-byte rates of the transactions from recent blocks (e.g. fetched from a node
+/// or an explorer) and recommends a fee for a new transaction at the given percentile of the
+/// collected rates
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct FeeEstimator {
+    miner_fee_tree: ErgoTree,
+    /// fee per byte (nanoERGs) of the collected transactions, in ascending order
+    fee_per_byte_rates: Vec<u64>,
+}
+
+impl FeeEstimator {
+    /// Create an estimator without any data
+    pub fn new() -> FeeEstimator {
+        let miner_fee_tree = Network::mainnet().fee_proposition;
+        FeeEstimator {
+            miner_fee_tree,
+            fee_per_byte_rates: Vec::new(),
+        }
+    }
+
+    /// Create an estimator from the transactions of recent blocks
+    pub fn from_transactions(txs: &[Transaction]) -> Result<FeeEstimator, SigmaSerializationError> {
+        let mut estimator = FeeEstimator::new();
+        estimator.add_transactions(txs)?;
+        Ok(estimator)
+    }
+
+    /// Add the fee rate of the transaction (sum of the outputs to the miner's fee contract divided
+    /// by the serialized transaction size). Transactions without a fee (e.g. emission) are ignored.
+    pub fn add_transaction(&mut self, tx: &Transaction) -> Result<(), SigmaSerializationError> {
+        let fee: u64 = tx
+            .output_candidates
+            .iter()
+            .filter(|b| b.ergo_tree == self.miner_fee_tree)
+            .map(|b| *b.value.as_u64())
+            .sum();
+        if fee > 0 {
+            let size = tx.sigma_serialize_bytes()?.len() as u64;
+            let rate = fee / size.max(1);
+            let pos = self
+                .fee_per_byte_rates
+                .binary_search(&rate)
+                .unwrap_or_else(|pos| pos);
+            self.fee_per_byte_rates.insert(pos, rate);
+        }
+        Ok(())
+    }
+
+    /// Add the fee rates of the transactions (see [`FeeEstimator::add_transaction`])
+    pub fn add_transactions(&mut self, txs: &[Transaction]) -> Result<(), SigmaSerializationError> {
+        txs.iter().try_for_each(|tx| self.add_transaction(tx))
+    }
+
+    /// Number of collected fee rates
+    pub fn len(&self) -> usize {
+        self.fee_per_byte_rates.len()
+    }
+
+    /// Returns true if no fee rates were collected
+    pub fn is_empty(&self) -> bool {
+        self.fee_per_byte_rates.is_empty()
+    }
+
+    /// Fee per byte at the given percentile (0-100, nearest-rank) of the collected rates, or None
+    /// if no rates were collected
+    pub fn fee_per_byte(&self, percentile: u8) -> Option<NanoErg> {
+        let len = self.fee_per_byte_rates.len();
+        if len == 0 {
+            return None;
+        }
+        let percentile = percentile.min(100) as usize;
+        let rank = (percentile * len + 99) / 100;
+        self.fee_per_byte_rates
+            .get(rank.max(1) - 1)
+            .copied()
+            .map(NanoErg::new)
+    }
+
+    /// Recommended fee for the transaction of the given size at the given percentile of the
+    /// collected fee rates, or None if no rates were collected
+    pub fn recommended_fee(&self, tx_size_bytes: usize, percentile: u8) -> Option<BoxValue> {
+        self.fee_per_byte(percentile).and_then(|rate| {
+            let fee = rate
+                .as_u64()
+                .saturating_mul(tx_size_bytes as u64)
+                .max(BoxValue::MIN_RAW)
+                .min(BoxValue::MAX_RAW);
+            BoxValue::new(fee).ok()
+        })
+    }
+}
+
+impl Default for FeeEstimator {
+    fn default() -> Self {
+        FeeEstimator::new()
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::chain::transaction::Input;
+    use crate::wallet::tx_builder::new_miner_fee_box;
+    use ergotree_ir::chain::ergo_box::ErgoBoxCandidate;
+    use sigma_test_util::force_any_val;
+    use std::convert::TryInto;
+
+    fn tx_with_fee(fee: u64) -> Transaction {
+        let fee_box = new_miner_fee_box(BoxValue::new(fee).unwrap(), 0).unwrap();
+        let other_box = ErgoBoxCandidate {
+            ergo_tree: force_any_val::<ErgoTree>(),
+            ..fee_box.clone()
+        };
+        Transaction::new(
+            vec![force_any_val::<Input>()].try_into().unwrap(),
+            None,
+            vec![other_box, fee_box].try_into().unwrap(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_fee_per_byte() {
+        let txs: Vec<Transaction> = (1..=10u64).map(|i| tx_with_fee(i * 1000000)).collect();
+        let mut rates: Vec<u64> = txs
+            .iter()
+            .zip(1..=10u64)
+            .map(|(tx, i)| i * 1000000 / tx.sigma_serialize_bytes().unwrap().len() as u64)
+            .collect();
+        rates.sort_unstable();
+        let estimator = FeeEstimator::from_transactions(&txs).unwrap();
+        assert_eq!(estimator.len(), 10);
+        let rate_at = |p| estimator.fee_per_byte(p).map(u64::from);
+        assert_eq!(rate_at(0), Some(rates[0]));
+        assert_eq!(rate_at(50), Some(rates[4]));
+        assert_eq!(rate_at(51), Some(rates[5]));
+        assert_eq!(rate_at(100), Some(rates[9]));
+        assert_eq!(rate_at(200), Some(rates[9]));
+        assert_eq!(
+            estimator.recommended_fee(1000, 100),
+            Some(BoxValue::new(rates[9] * 1000).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_no_data() {
+        let estimator = FeeEstimator::new();
+        assert!(estimator.is_empty());
+        assert_eq!(estimator.fee_per_byte(50), None);
+        assert_eq!(estimator.recommended_fee(1000, 50), None);
+    }
+}
@@ -0,0 +1,365 @@
+//! BIP-39 mnemonic phrase generation and validation
+
+use std::str::FromStr;
+
+use rand::rngs::OsRng;
+use rand::RngCore;
+use sigma_util::hash::sha256_hash;
+use thiserror::Error;
+
+/// Language of the mnemonic wordlist (BIP-39 wordlists)
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Language {
+    /// English
+    English,
+    /// Chinese (simplified)
+    ChineseSimplified,
+    /// Chinese (traditional)
+    ChineseTraditional,
+    /// Czech
+    Czech,
+    /// French
+    French,
+    /// Italian
+    Italian,
+    /// Japanese (words are separated with the ideographic space)
+    Japanese,
+    /// Korean
+    Korean,
+    /// Portuguese
+    Portuguese,
+    /// Spanish
+    Spanish,
+}
+
+impl Language {
+    /// All the supported languages
+    pub const ALL: [Language; 10] = [
+        Language::English,
+        Language::ChineseSimplified,
+        Language::ChineseTraditional,
+        Language::Czech,
+        Language::French,
+        Language::Italian,
+        Language::Japanese,
+        Language::Korean,
+        Language::Portuguese,
+        Language::Spanish,
+    ];
+
+    /// Wordlist of the language (2048 words, NFKD normalized)
+    pub fn word_list(&self) -> Vec<&'static str> {
+        let words = match self {
+            Language::English => include_str!("mnemonic_generator/english.txt"),
+            Language::ChineseSimplified => {
+                include_str!("mnemonic_generator/chinese_simplified.txt")
+            }
+            Language::ChineseTraditional => {
+                include_str!("mnemonic_generator/chinese_traditional.txt")
+            }
+            Language::Czech => include_str!("mnemonic_generator/czech.txt"),
+            Language::French => include_str!("mnemonic_generator/french.txt"),
+            Language::Italian => include_str!("mnemonic_generator/italian.txt"),
+            Language::Japanese => include_str!("mnemonic_generator/japanese.txt"),
+            Language::Korean => include_str!("mnemonic_generator/korean.txt"),
+            Language::Portuguese => include_str!("mnemonic_generator/portuguese.txt"),
+            Language::Spanish => include_str!("mnemonic_generator/spanish.txt"),
+        };
+        words.lines().collect()
+    }
+
+    /// Separator of the words in the mnemonic phrase
+    pub fn separator(&self) -> &'static str {
+        match self {
+            Language::Japanese => "\u{3000}",
+            _ => " ",
+        }
+    }
+
+    /// Name of the language (e.g. "english"), as parsed with `str::parse`
+    pub fn name(&self) -> &'static str {
+        match self {
+            Language::English => "english",
+            Language::ChineseSimplified => "chinese_simplified",
+            Language::ChineseTraditional => "chinese_traditional",
+            Language::Czech => "czech",
+            Language::French => "french",
+            Language::Italian => "italian",
+            Language::Japanese => "japanese",
+            Language::Korean => "korean",
+            Language::Portuguese => "portuguese",
+            Language::Spanish => "spanish",
+        }
+    }
+}
+
+impl FromStr for Language {
+    type Err = MnemonicGeneratorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Language::ALL
+            .iter()
+            .find(|l| l.name() == s)
+            .copied()
+            .ok_or_else(|| MnemonicGeneratorError::UnknownLanguage(s.to_string()))
+    }
+}
+
+/// Mnemonic generation and validation errors
+#[derive(Error, PartialEq, Eq, Debug, Clone)]
+pub enum MnemonicGeneratorError {
+    /// Strength is not one of 128, 160, 192, 224 or 256 bits
+    #[error("invalid strength {0} bits, expected 128, 160, 192, 224 or 256")]
+    InvalidStrength(u32),
+    /// Entropy length differs from the strength of the generator
+    #[error("invalid entropy length {actual} bytes, expected {expected}")]
+    InvalidEntropyLength {
+        /// entropy length for the generator strength
+        expected: usize,
+        /// length of the given entropy
+        actual: usize,
+    },
+    /// Number of words is not one of 12, 15, 18, 21 or 24
+    #[error("invalid number of words {0}, expected 12, 15, 18, 21 or 24")]
+    InvalidWordCount(usize),
+    /// Word is not in the wordlist of the language
+    #[error("word {0} is not in the wordlist")]
+    UnknownWord(String),
+    /// Checksum (the last bits of the last word) does not match the entropy
+    #[error("invalid checksum")]
+    InvalidChecksum,
+    /// Unknown language name
+    #[error("unknown language {0}")]
+    UnknownLanguage(String),
+}
+
+/// BIP-39 mnemonic phrase generator
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct MnemonicGenerator {
+    language: Language,
+    strength: u32,
+}
+
+impl MnemonicGenerator {
+    /// Create a generator of the phrases in the given language with the given strength (entropy
+    /// size in bits: 128, 160, 192, 224 or 256 for 12, 15, 18, 21 or 24 words)
+    pub fn new(language: Language, strength: u32) -> Result<Self, MnemonicGeneratorError> {
+        if !(128..=256).contains(&strength) || strength % 32 != 0 {
+            return Err(MnemonicGeneratorError::InvalidStrength(strength));
+        }
+        Ok(MnemonicGenerator { language, strength })
+    }
+
+    /// Generate a phrase from the random entropy (OS random number generator)
+    pub fn generate(&self) -> String {
+        let mut entropy = vec![0u8; self.strength as usize / 8];
+        OsRng.fill_bytes(&mut entropy);
+        self.phrase(&entropy)
+    }
+
+    /// Phrase encoding the given entropy (its length must match the strength)
+    pub fn from_entropy(&self, entropy: &[u8]) -> Result<String, MnemonicGeneratorError> {
+        let expected = self.strength as usize / 8;
+        if entropy.len() != expected {
+            return Err(MnemonicGeneratorError::InvalidEntropyLength {
+                expected,
+                actual: entropy.len(),
+            });
+        }
+        Ok(self.phrase(entropy))
+    }
+
+    fn phrase(&self, entropy: &[u8]) -> String {
+        let checksum = sha256_hash(entropy)[0];
+        let checksum_bits = entropy.len() / 4;
+        let mut bits: Vec<bool> = entropy
+            .iter()
+            .flat_map(|b| (0..8).rev().map(move |i| (b >> i) & 1 == 1))
+            .collect();
+        bits.extend((0..checksum_bits).map(|i| (checksum >> (7 - i)) & 1 == 1));
+        let word_list = self.language.word_list();
+        bits.chunks(11)
+            .map(|chunk| {
+                let index = chunk
+                    .iter()
+                    .fold(0usize, |acc, bit| (acc << 1) | *bit as usize);
+                word_list[index]
+            })
+            .collect::<Vec<&str>>()
+            .join(self.language.separator())
+    }
+}
+
+/// Check that the phrase consists of the words of the given language and its checksum is valid.
+/// The phrase is expected to be NFKD normalized (as the wordlists are), the words are separated
+/// with any whitespace.
+pub fn validate_mnemonic(phrase: &str, language: Language) -> Result<(), MnemonicGeneratorError> {
+    let words: Vec<&str> = phrase.split_whitespace().collect();
+    if !(12..=24).contains(&words.len()) || words.len() % 3 != 0 {
+        return Err(MnemonicGeneratorError::InvalidWordCount(words.len()));
+    }
+    let word_list = language.word_list();
+    let mut bits: Vec<bool> = Vec::with_capacity(words.len() * 11);
+    for word in words {
+        let index = word_list
+            .iter()
+            .position(|w| *w == word)
+            .ok_or_else(|| MnemonicGeneratorError::UnknownWord(word.to_string()))?;
+        bits.extend((0..11).rev().map(|i| (index >> i) & 1 == 1));
+    }
+    let checksum_bits = bits.len() / 33;
+    let (entropy_bits, checksum) = bits.split_at(bits.len() - checksum_bits);
+    let entropy: Vec<u8> = entropy_bits
+        .chunks(8)
+        .map(|byte| byte.iter().fold(0u8, |acc, bit| (acc << 1) | *bit as u8))
+        .collect();
+    let expected_checksum = sha256_hash(&entropy)[0];
+    let checksum_valid = checksum
+        .iter()
+        .enumerate()
+        .all(|(i, bit)| ((expected_checksum >> (7 - i)) & 1 == 1) == *bit);
+    if checksum_valid {
+        Ok(())
+    } else {
+        Err(MnemonicGeneratorError::InvalidChecksum)
+    }
+}
+
+/// Language of the valid phrase (see [`validate_mnemonic`]), trying all the supported languages.
+/// If the phrase is not valid in any language the error is the one of English, or
+/// [`MnemonicGeneratorError::InvalidChecksum`] if all the words are in some wordlist.
+pub fn detect_mnemonic_language(phrase: &str) -> Result<Language, MnemonicGeneratorError> {
+    let mut error = MnemonicGeneratorError::InvalidChecksum;
+    for (i, language) in Language::ALL.iter().enumerate() {
+        match validate_mnemonic(phrase, *language) {
+            Ok(()) => return Ok(*language),
+            Err(MnemonicGeneratorError::InvalidChecksum) => {
+                error = MnemonicGeneratorError::InvalidChecksum
+            }
+            Err(e) if i == 0 => error = e,
+            Err(_) => (),
+        }
+    }
+    Err(error)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn english(strength: u32) -> MnemonicGenerator {
+        MnemonicGenerator::new(Language::English, strength).unwrap()
+    }
+
+    #[test]
+    fn bip39_vectors() {
+        // test vectors from https://github.com/trezor/python-mnemonic/blob/master/vectors.json
+        let vectors = [
+            (
+                "00000000000000000000000000000000",
+                "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about",
+            ),
+            (
+                "7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f",
+                "legal winner thank year wave sausage worth useful legal winner thank yellow",
+            ),
+            (
+                "ffffffffffffffffffffffffffffffff",
+                "zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo wrong",
+            ),
+            (
+                "9e885d952ad362caeb4efe34a8e91bd2",
+                "ozone drill grab fiber curtain grace pudding thank cruise elder eight picnic",
+            ),
+            (
+                "808080808080808080808080808080808080808080808080",
+                "letter advice cage absurd amount doctor acoustic avoid letter advice cage absurd amount doctor acoustic avoid letter always",
+            ),
+            (
+                "7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f",
+                "legal winner thank year wave sausage worth useful legal winner thank year wave sausage worth useful legal winner thank year wave sausage worth title",
+            ),
+        ];
+        for (entropy, phrase) in vectors.iter() {
+            let entropy = base16::decode(entropy).unwrap();
+            let generator = english(entropy.len() as u32 * 8);
+            assert_eq!(generator.from_entropy(&entropy).unwrap(), *phrase);
+            assert_eq!(validate_mnemonic(phrase, Language::English), Ok(()));
+            assert_eq!(detect_mnemonic_language(phrase), Ok(Language::English));
+        }
+    }
+
+    #[test]
+    fn word_lists() {
+        for language in Language::ALL.iter() {
+            assert_eq!(language.word_list().len(), 2048);
+            assert_eq!(language.name().parse::<Language>(), Ok(*language));
+        }
+        assert_eq!(
+            "klingon".parse::<Language>(),
+            Err(MnemonicGeneratorError::UnknownLanguage(
+                "klingon".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn japanese_separator() {
+        let generator = MnemonicGenerator::new(Language::Japanese, 128).unwrap();
+        let phrase = generator.from_entropy(&[0; 16]).unwrap();
+        let words = Language::Japanese.word_list();
+        let mut expected = vec![words[0]; 11];
+        expected.push(words[3]);
+        assert_eq!(phrase, expected.join("\u{3000}"));
+        assert_eq!(detect_mnemonic_language(&phrase), Ok(Language::Japanese));
+    }
+
+    #[test]
+    fn generate_all_languages() {
+        for language in Language::ALL.iter() {
+            for strength in (128..=256).step_by(32) {
+                let phrase = MnemonicGenerator::new(*language, strength)
+                    .unwrap()
+                    .generate();
+                assert_eq!(
+                    phrase.split_whitespace().count(),
+                    strength as usize * 33 / 32 / 11
+                );
+                assert_eq!(validate_mnemonic(&phrase, *language), Ok(()));
+            }
+        }
+    }
+
+    #[test]
+    fn invalid() {
+        assert_eq!(
+            MnemonicGenerator::new(Language::English, 100),
+            Err(MnemonicGeneratorError::InvalidStrength(100))
+        );
+        assert_eq!(
+            english(128).from_entropy(&[0; 20]),
+            Err(MnemonicGeneratorError::InvalidEntropyLength {
+                expected: 16,
+                actual: 20
+            })
+        );
+        // the last word encodes the checksum
+        assert_eq!(
+            detect_mnemonic_language(
+                "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon"
+            ),
+            Err(MnemonicGeneratorError::InvalidChecksum)
+        );
+        assert_eq!(
+            detect_mnemonic_language(
+                "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon sigma"
+            ),
+            Err(MnemonicGeneratorError::UnknownWord("sigma".to_string()))
+        );
+        assert_eq!(
+            detect_mnemonic_language("zoo zoo zoo"),
+            Err(MnemonicGeneratorError::InvalidWordCount(3))
+        );
+    }
+}
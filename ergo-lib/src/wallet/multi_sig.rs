@@ -0,0 +1,317 @@
+//! Distributed signing of transactions (inputs protected by multi-signature scripts)
+//!
+//! The signers exchange commitments ([`generate_commitments`]) and partially signed transactions
+//! (see [`extract_hints`]) as described in
+//! [`ergotree_interpreter::sigma_protocol::multi_sig`], with the hints kept per transaction input
+//! in [`TransactionHintsBag`].
+
+use std::collections::HashMap;
+
+use ergotree_interpreter::sigma_protocol::multi_sig::bag_for_multi_sig;
+use ergotree_interpreter::sigma_protocol::multi_sig::generate_commitments_for;
+use ergotree_interpreter::sigma_protocol::prover::hint::HintsBag;
+#[cfg(feature = "json")]
+use ergotree_interpreter::sigma_protocol::prover::hint::HintsJsonError;
+use ergotree_ir::chain::ergo_box::ErgoBox;
+use ergotree_ir::sigma_protocol::sigma_boolean::SigmaBoolean;
+
+use crate::chain::ergo_state_context::ErgoStateContext;
+use crate::chain::transaction::reduced::reduce_tx;
+use crate::chain::transaction::unsigned::UnsignedTransaction;
+use crate::chain::transaction::Transaction;
+use crate::chain::transaction::UnsignedInput;
+
+use super::signing::TransactionContext;
+use super::signing::TxSigningError;
+
+/// Hints for the prover for every input of a transaction (by input index)
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct TransactionHintsBag {
+    hints: HashMap<usize, HintsBag>,
+}
+
+impl TransactionHintsBag {
+    /// Bag without hints
+    pub fn empty() -> Self {
+        TransactionHintsBag {
+            hints: HashMap::new(),
+        }
+    }
+
+    /// Add hints for the input with the given index
+    pub fn add_hints_for_input(&mut self, index: usize, hints_bag: HintsBag) {
+        self.hints
+            .entry(index)
+            .or_insert_with(HintsBag::empty)
+            .add_hints_from(hints_bag);
+    }
+
+    /// All hints for the input with the given index
+    pub fn all_hints_for_input(&self, index: usize) -> HintsBag {
+        self.hints
+            .get(&index)
+            .cloned()
+            .unwrap_or_else(HintsBag::empty)
+    }
+
+    /// Copy of the bag which is safe to share with other signers, i.e. without the secret
+    /// randomness of own commitments (see [`HintsBag::sanitize_for_sharing`])
+    pub fn sanitize_for_sharing(&self) -> TransactionHintsBag {
+        TransactionHintsBag {
+            hints: self
+                .hints
+                .iter()
+                .map(|(idx, bag)| (*idx, bag.sanitize_for_sharing()))
+                .collect(),
+        }
+    }
+
+    /// Encode in the Ergo node format: the hints with the secret randomness (own commitments) in
+    /// `secretHints` and the other hints in `publicHints`, by input index
+    #[cfg(feature = "json")]
+    pub fn to_json(&self) -> Result<serde_json::Value, HintsJsonError> {
+        let mut secret_hints = serde_json::Map::new();
+        let mut public_hints = serde_json::Map::new();
+        for (idx, bag) in &self.hints {
+            let (secret, public): (Vec<serde_json::Value>, Vec<serde_json::Value>) = bag
+                .to_json()?
+                .as_array()
+                .cloned()
+                .unwrap_or_default()
+                .into_iter()
+                .partition(|hint| hint["hint"] == "cmtWithSecret");
+            if !secret.is_empty() {
+                secret_hints.insert(idx.to_string(), secret.into());
+            }
+            if !public.is_empty() {
+                public_hints.insert(idx.to_string(), public.into());
+            }
+        }
+        Ok(serde_json::json!({
+            "secretHints": secret_hints,
+            "publicHints": public_hints,
+        }))
+    }
+
+    /// Decode from the Ergo node format (the hints from `secretHints` and `publicHints` are merged)
+    #[cfg(feature = "json")]
+    pub fn from_json(json: &serde_json::Value) -> Result<TransactionHintsBag, HintsJsonError> {
+        let mut tx_hints = TransactionHintsBag::empty();
+        for &name in &["secretHints", "publicHints"] {
+            let by_input = match json.get(name) {
+                Some(v) => v.as_object().ok_or_else(|| {
+                    HintsJsonError::InvalidField(name, "expected object".to_string())
+                })?,
+                None => continue,
+            };
+            for (idx, hints) in by_input {
+                let idx = idx
+                    .parse::<usize>()
+                    .map_err(|e| HintsJsonError::InvalidField(name, e.to_string()))?;
+                tx_hints.add_hints_for_input(idx, HintsBag::from_json(hints)?);
+            }
+        }
+        Ok(tx_hints)
+    }
+}
+
+/// Generates commitments for the inputs of the transaction which scripts (reduced to sigma
+/// propositions) include the given public images (of the signer's secrets).
+/// The returned bag holds the secret randomness, share only its
+/// [`TransactionHintsBag::sanitize_for_sharing`] copy.
+pub fn generate_commitments(
+    tx_context: TransactionContext,
+    state_context: &ErgoStateContext,
+    public_images: &[SigmaBoolean],
+) -> Result<TransactionHintsBag, TxSigningError> {
+    let reduced_tx = reduce_tx(tx_context, state_context)?;
+    let mut tx_hints = TransactionHintsBag::empty();
+    for (idx, reduced_input) in reduced_tx.reduced_inputs().iter().enumerate() {
+        tx_hints.add_hints_for_input(
+            idx,
+            generate_commitments_for(&reduced_input.reduction_result.sigma_prop, public_images),
+        );
+    }
+    Ok(tx_hints)
+}
+
+/// Extracts hints from the proofs of a (partially) signed transaction: commitments and proofs for
+/// the given public images which were proven (`real_propositions`) or simulated
+/// (`simulated_propositions`) by the signer of `tx`.
+/// `boxes_to_spend` and `data_boxes` are the boxes corresponding to the inputs and data inputs.
+pub fn extract_hints(
+    tx: &Transaction,
+    state_context: &ErgoStateContext,
+    boxes_to_spend: Vec<ErgoBox>,
+    data_boxes: Vec<ErgoBox>,
+    real_propositions: &[SigmaBoolean],
+    simulated_propositions: &[SigmaBoolean],
+) -> Result<TransactionHintsBag, TxSigningError> {
+    let unsigned_inputs = tx
+        .inputs
+        .mapped_ref(|i| UnsignedInput::new(i.box_id.clone(), i.spending_proof.extension.clone()));
+    let tx_context = TransactionContext {
+        spending_tx: UnsignedTransaction::new(
+            unsigned_inputs,
+            tx.data_inputs.clone(),
+            tx.output_candidates.clone(),
+        )?,
+        boxes_to_spend,
+        data_boxes,
+    };
+    let reduced_tx = reduce_tx(tx_context, state_context)?;
+    let mut tx_hints = TransactionHintsBag::empty();
+    for (idx, reduced_input) in reduced_tx.reduced_inputs().iter().enumerate() {
+        let input = tx
+            .inputs
+            .get(idx)
+            .ok_or(TxSigningError::InputIndexOutOfBounds(idx))?;
+        let hints = bag_for_multi_sig(
+            &reduced_input.reduction_result.sigma_prop,
+            &input.spending_proof.proof,
+            real_propositions,
+            simulated_propositions,
+        )
+        .map_err(|e| TxSigningError::HintsExtractionError(e, idx))?;
+        tx_hints.add_hints_for_input(idx, hints);
+    }
+    Ok(tx_hints)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::chain::ergo_box::box_builder::ErgoBoxCandidateBuilder;
+    use crate::wallet::secret_key::SecretKey;
+    use crate::wallet::Wallet;
+    use ergotree_interpreter::eval::context::Context;
+    use ergotree_interpreter::eval::env::Env;
+    use ergotree_interpreter::sigma_protocol::private_input::PrivateInput;
+    use ergotree_interpreter::sigma_protocol::verifier::TestVerifier;
+    use ergotree_interpreter::sigma_protocol::verifier::Verifier;
+    use ergotree_ir::chain::ergo_box::box_value::BoxValue;
+    use ergotree_ir::chain::ergo_box::NonMandatoryRegisters;
+    use ergotree_ir::chain::tx_id::TxId;
+    use ergotree_ir::ergo_tree::ErgoTree;
+    use ergotree_ir::mir::expr::Expr;
+    use ergotree_ir::mir::sigma_and::SigmaAnd;
+    use sigma_test_util::force_any_val;
+    use std::convert::TryFrom;
+    use std::convert::TryInto;
+    use std::rc::Rc;
+
+    fn verify_tx_proof(tx: &Transaction, input_box: &ErgoBox) -> bool {
+        TestVerifier
+            .verify(
+                &input_box.ergo_tree,
+                &Env::empty(),
+                Rc::new(force_any_val::<Context>()),
+                tx.inputs.first().spending_proof.proof.clone(),
+                &tx.bytes_to_sign().unwrap(),
+            )
+            .unwrap()
+            .result
+    }
+
+    #[test]
+    fn two_signers() {
+        let secret1 = SecretKey::random_dlog();
+        let secret2 = SecretKey::random_dlog();
+        let pk1 = PrivateInput::from(secret1.clone()).public_image();
+        let pk2 = PrivateInput::from(secret2.clone()).public_image();
+        let expr: Expr = SigmaAnd::new(vec![
+            Expr::Const(pk1.clone().into()),
+            Expr::Const(pk2.clone().into()),
+        ])
+        .unwrap()
+        .into();
+        let input_box = ErgoBox::new(
+            BoxValue::SAFE_USER_MIN,
+            ErgoTree::try_from(expr).unwrap(),
+            None,
+            NonMandatoryRegisters::empty(),
+            0,
+            TxId::zero(),
+            0,
+        )
+        .unwrap();
+        let candidate =
+            ErgoBoxCandidateBuilder::new(BoxValue::SAFE_USER_MIN, force_any_val::<ErgoTree>(), 0)
+                .build()
+                .unwrap();
+        let unsigned_tx = UnsignedTransaction::new(
+            vec![UnsignedInput::from(input_box.clone())]
+                .try_into()
+                .unwrap(),
+            None,
+            vec![candidate].try_into().unwrap(),
+        )
+        .unwrap();
+        let tx_context = TransactionContext {
+            spending_tx: unsigned_tx,
+            boxes_to_spend: vec![input_box.clone()],
+            data_boxes: vec![],
+        };
+        let state_context = ErgoStateContext::dummy();
+        let wallet1 = Wallet::from_secrets(vec![secret1]);
+        let wallet2 = Wallet::from_secrets(vec![secret2]);
+
+        // the second signer generates a commitment and shares its public part
+        let own_hints2 = wallet2
+            .generate_commitments(tx_context.clone(), &state_context)
+            .unwrap();
+        assert!(own_hints2.all_hints_for_input(0).contains_secrets());
+        let shared_hints2 = own_hints2.sanitize_for_sharing();
+        assert!(!shared_hints2.all_hints_for_input(0).contains_secrets());
+        #[cfg(feature = "json")]
+        {
+            let own_json = own_hints2.to_json().unwrap();
+            assert_eq!(own_json["secretHints"]["0"][0]["hint"], "cmtWithSecret");
+            assert_eq!(own_json["publicHints"], serde_json::json!({}));
+            assert_eq!(
+                TransactionHintsBag::from_json(&own_json).unwrap(),
+                own_hints2
+            );
+            let shared_json = shared_hints2.to_json().unwrap();
+            assert_eq!(shared_json["secretHints"], serde_json::json!({}));
+            assert_eq!(shared_json["publicHints"]["0"][0]["hint"], "cmtReal");
+            assert_eq!(
+                TransactionHintsBag::from_json(&shared_json).unwrap(),
+                shared_hints2
+            );
+        }
+        assert!(wallet1
+            .sign_transaction(tx_context.clone(), &state_context)
+            .is_err());
+
+        // the first signer makes a partial proof
+        let partial_tx = wallet1
+            .sign_transaction_with_hints(tx_context.clone(), &state_context, &shared_hints2)
+            .unwrap();
+        assert!(!verify_tx_proof(&partial_tx, &input_box));
+
+        // the second signer extracts the first signer proof and completes the proof
+        let mut hints = extract_hints(
+            &partial_tx,
+            &state_context,
+            vec![input_box.clone()],
+            vec![],
+            &[pk1],
+            &[],
+        )
+        .unwrap();
+        assert_eq!(hints.all_hints_for_input(0).real_proofs().len(), 1);
+        #[cfg(feature = "json")]
+        assert_eq!(
+            TransactionHintsBag::from_json(&hints.to_json().unwrap()).unwrap(),
+            hints
+        );
+        hints.add_hints_for_input(0, own_hints2.all_hints_for_input(0));
+        let tx = wallet2
+            .sign_transaction_with_hints(tx_context, &state_context, &hints)
+            .unwrap();
+        assert_eq!(tx.id(), partial_tx.id());
+        assert!(verify_tx_proof(&tx, &input_box));
+    }
+}
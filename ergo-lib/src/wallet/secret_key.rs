@@ -1,30 +1,100 @@
 //! Secret types
+use std::convert::TryFrom;
+use std::convert::TryInto;
+
+use ergotree_interpreter::sigma_protocol::private_input::DhTupleProverInput;
 use ergotree_interpreter::sigma_protocol::private_input::DlogProverInput;
 use ergotree_interpreter::sigma_protocol::private_input::PrivateInput;
 use ergotree_ir::chain::address::Address;
+use ergotree_ir::ergo_tree::ErgoTree;
+use ergotree_ir::mir::expr::Expr;
+use ergotree_ir::sigma_protocol::sigma_boolean::SigmaProp;
+use sigma_util::hash::blake2b256_hash;
+use thiserror::Error;
 
 /// Types of secrets
 #[derive(PartialEq, Debug, Clone)]
 pub enum SecretKey {
     /// Secret exponent of a group element, i.e. secret w such as h = g^^w, where g is group generator, h is a public key.
     DlogSecretKey(DlogProverInput),
+    /// Secret of a Diffie-Hellman tuple, i.e. secret w such as u = g^^w and v = h^^w for the
+    /// group elements g, h, u, v of the tuple
+    DhtSecretKey(DhTupleProverInput),
+}
+
+/// Text encoding of a secret key (see [`SecretKey::to_encoded`])
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum SecretKeyEncoding {
+    /// Base58 of the key type tag byte ([`SecretKey::DLOG_TAG`] or [`SecretKey::DHT_TAG`]), the
+    /// key bytes and the checksum (first 4 bytes of Blake2b256 hash of the tag and the key bytes)
+    Base58Check,
+    /// Base16 of the key bytes (the key type is told by the size)
+    Hex,
+}
+
+/// Errors of the secret key decoding
+#[derive(Error, PartialEq, Eq, Debug, Clone)]
+pub enum SecretKeyParsingError {
+    /// Invalid Base58 or Base16 string
+    #[error("Invalid encoding: {0}")]
+    InvalidEncoding(String),
+    /// Checksum does not match the encoded bytes
+    #[error("Invalid checksum")]
+    InvalidChecksum,
+    /// Unknown key type tag
+    #[error("Unknown key type tag {0}")]
+    UnknownTag(u8),
+    /// Unexpected key size
+    #[error("Unexpected key size {0}")]
+    InvalidSize(usize),
+    /// Key bytes are not a valid secret (e.g. the scalar is out of range)
+    #[error("Invalid key bytes")]
+    InvalidKey,
 }
 
 impl SecretKey {
+    /// Tag byte of DlogSecretKey in [`SecretKeyEncoding::Base58Check`]
+    pub const DLOG_TAG: u8 = 0;
+    /// Tag byte of DhtSecretKey in [`SecretKeyEncoding::Base58Check`]
+    pub const DHT_TAG: u8 = 1;
+    /// Checksum size in [`SecretKeyEncoding::Base58Check`]
+    pub const CHECKSUM_LENGTH: usize = 4;
+
     /// Generates random DlogProverInput
     pub fn random_dlog() -> SecretKey {
         SecretKey::DlogSecretKey(DlogProverInput::random())
     }
 
+    /// Generates random DhTupleProverInput
+    pub fn random_dht() -> SecretKey {
+        SecretKey::DhtSecretKey(DhTupleProverInput::random())
+    }
+
     /// Parse DlogSecretKey from bytes (SEC-1-encoded scalar)
     pub fn dlog_from_bytes(bytes: &[u8; DlogProverInput::SIZE_BYTES]) -> Option<SecretKey> {
         DlogProverInput::from_bytes(bytes).map(SecretKey::DlogSecretKey)
     }
 
-    /// Address (encoded public image)
+    /// Parse DhtSecretKey from bytes (SEC-1-encoded scalar followed by the compressed points of
+    /// the tuple, see [`DhTupleProverInput::from_bytes`])
+    pub fn dht_from_bytes(bytes: &[u8; DhTupleProverInput::SIZE_BYTES]) -> Option<SecretKey> {
+        DhTupleProverInput::from_bytes(bytes).map(SecretKey::DhtSecretKey)
+    }
+
+    /// Address (encoded public image), P2PK for DlogSecretKey and P2S (of the tuple proposition)
+    /// for DhtSecretKey
+    // the tree of a DHT proposition is always serializable and recreates a P2S address
+    #[allow(clippy::expect_used)]
     pub fn get_address_from_public_image(&self) -> Address {
         match self {
             SecretKey::DlogSecretKey(dpi) => Address::P2Pk(dpi.public_image()),
+            SecretKey::DhtSecretKey(dht) => {
+                let prop: Expr = SigmaProp::from(dht.public_image().clone()).into();
+                ErgoTree::try_from(prop)
+                    .ok()
+                    .and_then(|tree| Address::recreate_from_ergo_tree(&tree).ok())
+                    .expect("internal error: failed to make an address of the DHT proposition")
+            }
         }
     }
 
@@ -32,6 +102,76 @@ impl SecretKey {
     pub fn to_bytes(&self) -> Vec<u8> {
         match self {
             SecretKey::DlogSecretKey(key) => key.to_bytes().to_vec(),
+            SecretKey::DhtSecretKey(key) => key.to_bytes().to_vec(),
+        }
+    }
+
+    /// Parse the key from bytes, the key type is told by the size ([`DlogProverInput::SIZE_BYTES`]
+    /// or [`DhTupleProverInput::SIZE_BYTES`])
+    pub fn from_bytes(bytes: &[u8]) -> Result<SecretKey, SecretKeyParsingError> {
+        if let Ok(dlog_bytes) = bytes.try_into() {
+            SecretKey::dlog_from_bytes(dlog_bytes).ok_or(SecretKeyParsingError::InvalidKey)
+        } else if let Ok(dht_bytes) = bytes.try_into() {
+            SecretKey::dht_from_bytes(dht_bytes).ok_or(SecretKeyParsingError::InvalidKey)
+        } else {
+            Err(SecretKeyParsingError::InvalidSize(bytes.len()))
+        }
+    }
+
+    /// Encode the key as text (to move it between the wallets)
+    pub fn to_encoded(&self, encoding: SecretKeyEncoding) -> String {
+        match encoding {
+            SecretKeyEncoding::Base58Check => {
+                let tag = match self {
+                    SecretKey::DlogSecretKey(_) => SecretKey::DLOG_TAG,
+                    SecretKey::DhtSecretKey(_) => SecretKey::DHT_TAG,
+                };
+                let mut bytes = vec![tag];
+                bytes.extend(self.to_bytes());
+                let checksum = blake2b256_hash(&bytes);
+                bytes.extend_from_slice(&checksum[..SecretKey::CHECKSUM_LENGTH]);
+                bs58::encode(bytes).into_string()
+            }
+            SecretKeyEncoding::Hex => base16::encode_lower(&self.to_bytes()),
+        }
+    }
+
+    /// Parse the key encoded with [`SecretKey::to_encoded`]
+    pub fn from_encoded(
+        encoded: &str,
+        encoding: SecretKeyEncoding,
+    ) -> Result<SecretKey, SecretKeyParsingError> {
+        match encoding {
+            SecretKeyEncoding::Base58Check => {
+                let bytes = bs58::decode(encoded)
+                    .into_vec()
+                    .map_err(|e| SecretKeyParsingError::InvalidEncoding(e.to_string()))?;
+                if bytes.len() < 1 + SecretKey::CHECKSUM_LENGTH {
+                    return Err(SecretKeyParsingError::InvalidSize(bytes.len()));
+                }
+                let (tagged, checksum) = bytes.split_at(bytes.len() - SecretKey::CHECKSUM_LENGTH);
+                if checksum != &blake2b256_hash(tagged)[..SecretKey::CHECKSUM_LENGTH] {
+                    return Err(SecretKeyParsingError::InvalidChecksum);
+                }
+                let key_bytes = &tagged[1..];
+                let size_err = || SecretKeyParsingError::InvalidSize(key_bytes.len());
+                match tagged[0] {
+                    SecretKey::DLOG_TAG => {
+                        SecretKey::dlog_from_bytes(key_bytes.try_into().map_err(|_| size_err())?)
+                            .ok_or(SecretKeyParsingError::InvalidKey)
+                    }
+                    SecretKey::DHT_TAG => {
+                        SecretKey::dht_from_bytes(key_bytes.try_into().map_err(|_| size_err())?)
+                            .ok_or(SecretKeyParsingError::InvalidKey)
+                    }
+                    tag => Err(SecretKeyParsingError::UnknownTag(tag)),
+                }
+            }
+            SecretKeyEncoding::Hex => {
+                let bytes = base16::decode(encoded)
+                    .map_err(|e| SecretKeyParsingError::InvalidEncoding(e.to_string()))?;
+                SecretKey::from_bytes(&bytes)
+            }
         }
     }
 }
@@ -40,6 +180,7 @@ impl From<SecretKey> for PrivateInput {
     fn from(s: SecretKey) -> Self {
         match s {
             SecretKey::DlogSecretKey(dpi) => PrivateInput::DlogProverInput(dpi),
+            SecretKey::DhtSecretKey(dht) => PrivateInput::DhTupleProverInput(dht),
         }
     }
 }
@@ -50,7 +191,14 @@ impl From<DlogProverInput> for SecretKey {
     }
 }
 
+impl From<DhTupleProverInput> for SecretKey {
+    fn from(pi: DhTupleProverInput) -> Self {
+        SecretKey::DhtSecretKey(pi)
+    }
+}
+
 #[cfg(test)]
+#[allow(clippy::unwrap_used)]
 mod tests {
     use super::*;
     use std::convert::TryInto;
@@ -62,4 +210,82 @@ mod tests {
             SecretKey::dlog_from_bytes(&sk.to_bytes().as_slice().try_into().unwrap()).unwrap();
         assert_eq!(sk, sk_copy);
     }
+
+    #[test]
+    fn dht_roundtrip() {
+        let sk = SecretKey::random_dht();
+        let sk_copy =
+            SecretKey::dht_from_bytes(&sk.to_bytes().as_slice().try_into().unwrap()).unwrap();
+        assert_eq!(sk, sk_copy);
+        assert_eq!(SecretKey::from_bytes(&sk.to_bytes()), Ok(sk));
+    }
+
+    #[test]
+    fn encoded_roundtrip() {
+        for sk in vec![SecretKey::random_dlog(), SecretKey::random_dht()] {
+            for encoding in vec![SecretKeyEncoding::Base58Check, SecretKeyEncoding::Hex] {
+                let encoded = sk.to_encoded(encoding);
+                assert_eq!(SecretKey::from_encoded(&encoded, encoding), Ok(sk.clone()));
+            }
+        }
+    }
+
+    #[test]
+    fn encoded_known_key() {
+        // scalar 1
+        let mut bytes = [0u8; DlogProverInput::SIZE_BYTES];
+        bytes[31] = 1;
+        let sk = SecretKey::dlog_from_bytes(&bytes).unwrap();
+        let hex = sk.to_encoded(SecretKeyEncoding::Hex);
+        assert_eq!(
+            hex,
+            "0000000000000000000000000000000000000000000000000000000000000001"
+        );
+        let decoded = bs58::decode(sk.to_encoded(SecretKeyEncoding::Base58Check))
+            .into_vec()
+            .unwrap();
+        assert_eq!(decoded.len(), 1 + 32 + 4);
+        assert_eq!(decoded[0], SecretKey::DLOG_TAG);
+        assert_eq!(&decoded[1..33], &bytes[..]);
+    }
+
+    #[test]
+    fn encoded_errors() {
+        let sk = SecretKey::random_dlog();
+        let mut bytes = bs58::decode(sk.to_encoded(SecretKeyEncoding::Base58Check))
+            .into_vec()
+            .unwrap();
+        bytes[5] ^= 1;
+        assert_eq!(
+            SecretKey::from_encoded(
+                &bs58::encode(&bytes).into_string(),
+                SecretKeyEncoding::Base58Check
+            ),
+            Err(SecretKeyParsingError::InvalidChecksum)
+        );
+        let mut unknown_tag = vec![7u8];
+        unknown_tag.extend(sk.to_bytes());
+        let checksum = blake2b256_hash(&unknown_tag);
+        unknown_tag.extend_from_slice(&checksum[..SecretKey::CHECKSUM_LENGTH]);
+        assert_eq!(
+            SecretKey::from_encoded(
+                &bs58::encode(&unknown_tag).into_string(),
+                SecretKeyEncoding::Base58Check
+            ),
+            Err(SecretKeyParsingError::UnknownTag(7))
+        );
+        assert_eq!(
+            SecretKey::from_encoded("00ff", SecretKeyEncoding::Hex),
+            Err(SecretKeyParsingError::InvalidSize(2))
+        );
+        // out of the scalar range
+        assert_eq!(
+            SecretKey::from_encoded(&"ff".repeat(32), SecretKeyEncoding::Hex),
+            Err(SecretKeyParsingError::InvalidKey)
+        );
+        assert!(matches!(
+            SecretKey::from_encoded("0OIl", SecretKeyEncoding::Base58Check),
+            Err(SecretKeyParsingError::InvalidEncoding(_))
+        ));
+    }
 }
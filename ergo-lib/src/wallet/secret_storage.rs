@@ -0,0 +1,287 @@
+//! Encrypted secret storage of the Ergo node wallet (the JSON file in the node's `keystore` dir)
+//!
+//! The node keeps the wallet seed encrypted with AES-GCM under the key derived from the wallet
+//! password with PBKDF2:
+//! ```json
+//! {
+//!   "cipherText": "<Base16>",
+//!   "salt": "<Base16>",
+//!   "iv": "<Base16>",
+//!   "authTag": "<Base16>",
+//!   "cipherParams": { "prf": "HmacSHA256", "c": 128000, "dkLen": 256 },
+//!   "usePre1627KeyDerivation": false
+//! }
+//! ```
+//! The decrypted secret is the seed of the wallet mnemonic (with the mnemonic password), the
+//! root of the BIP-32 key derivation.
+
+use aes_gcm::aead::consts::U16;
+use aes_gcm::aead::generic_array::GenericArray;
+use aes_gcm::aead::Aead;
+use aes_gcm::aead::NewAead;
+use aes_gcm::aes::Aes256;
+use aes_gcm::AesGcm;
+use ergotree_ir::chain::base16_bytes::Base16DecodedBytes;
+use hmac::Hmac;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use thiserror::Error;
+
+/// AES-GCM with the 128-bit IV used by the node
+type Aes256Gcm16 = AesGcm<Aes256, U16>;
+
+/// Length of the IV (in bytes)
+pub const IV_LENGTH: usize = 16;
+/// Length of the PBKDF2 salt (in bytes) of the newly encrypted secrets
+pub const SALT_LENGTH: usize = 32;
+/// Length of the GCM authentication tag (in bytes)
+pub const AUTH_TAG_LENGTH: usize = 16;
+
+/// The only pseudo-random function of the key derivation supported by the node
+pub const PRF_HMAC_SHA256: &str = "HmacSHA256";
+
+/// Key derivation parameters
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+pub struct EncryptionSettings {
+    /// Pseudo-random function of PBKDF2
+    pub prf: String,
+    /// Number of PBKDF2 iterations
+    pub c: u32,
+    /// Derived key length (in bits)
+    #[serde(rename = "dkLen")]
+    pub dk_len: u32,
+}
+
+impl Default for EncryptionSettings {
+    /// Node defaults (`ergo.wallet.secretStorage.encryption`)
+    fn default() -> Self {
+        EncryptionSettings {
+            prf: PRF_HMAC_SHA256.to_string(),
+            c: 128000,
+            dk_len: 256,
+        }
+    }
+}
+
+/// Errors of the secret storage
+#[derive(Error, PartialEq, Eq, Debug, Clone)]
+pub enum SecretStorageError {
+    /// Pseudo-random function of the key derivation is not supported
+    #[error("Unsupported PRF: {0}")]
+    UnsupportedPrf(String),
+    /// Derived key length is not supported (only AES-256 keys are)
+    #[error("Unsupported derived key length: {0} bits")]
+    UnsupportedKeyLength(u32),
+    /// IV has unexpected length
+    #[error("Invalid IV length: {0}")]
+    InvalidIvLength(usize),
+    /// Seed is too long for the cipher
+    #[error("Failed to encrypt the secret")]
+    EncryptionFailed,
+    /// Cipher text fails the authentication (wrong password or corrupted storage)
+    #[error("Failed to decrypt the secret (wrong password?)")]
+    WrongPassword,
+    /// JSON (de)serialization error
+    #[error("JSON error: {0}")]
+    Json(String),
+}
+
+/// Encrypted secret (the node wallet storage file contents)
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+pub struct EncryptedSecret {
+    /// Cipher text (Base16)
+    #[serde(rename = "cipherText")]
+    pub cipher_text: Base16DecodedBytes,
+    /// PBKDF2 salt (Base16)
+    pub salt: Base16DecodedBytes,
+    /// AES-GCM IV (Base16)
+    pub iv: Base16DecodedBytes,
+    /// AES-GCM authentication tag (Base16)
+    #[serde(rename = "authTag")]
+    pub auth_tag: Base16DecodedBytes,
+    /// Key derivation parameters
+    #[serde(rename = "cipherParams")]
+    pub cipher_params: EncryptionSettings,
+    /// The keys are derived with the BIP-32 derivation of the node versions before the fix of the
+    /// node issue 1627, None in the files of the older node versions
+    #[serde(
+        rename = "usePre1627KeyDerivation",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub use_pre_1627_key_derivation: Option<bool>,
+}
+
+impl EncryptedSecret {
+    /// Encrypts the seed with the password (random salt and IV)
+    pub fn encrypt(
+        seed: &[u8],
+        password: &str,
+        settings: EncryptionSettings,
+    ) -> Result<EncryptedSecret, SecretStorageError> {
+        let mut salt = [0u8; SALT_LENGTH];
+        let mut iv = [0u8; IV_LENGTH];
+        OsRng.fill_bytes(&mut salt);
+        OsRng.fill_bytes(&mut iv);
+        let cipher = cipher(password, &salt, &settings)?;
+        let encrypted = cipher
+            .encrypt(GenericArray::from_slice(&iv), seed)
+            .map_err(|_| SecretStorageError::EncryptionFailed)?;
+        // the node splits the cipher output at the tag length (`AES.encrypt` of the node) and puts
+        // the parts back in the same order on decryption
+        let (auth_tag, cipher_text) = encrypted.split_at(AUTH_TAG_LENGTH);
+        Ok(EncryptedSecret {
+            cipher_text: Base16DecodedBytes(cipher_text.to_vec()),
+            salt: Base16DecodedBytes(salt.to_vec()),
+            iv: Base16DecodedBytes(iv.to_vec()),
+            auth_tag: Base16DecodedBytes(auth_tag.to_vec()),
+            cipher_params: settings,
+            use_pre_1627_key_derivation: Some(false),
+        })
+    }
+
+    /// Decrypts the seed with the password
+    pub fn decrypt(&self, password: &str) -> Result<Vec<u8>, SecretStorageError> {
+        if self.iv.0.len() != IV_LENGTH {
+            return Err(SecretStorageError::InvalidIvLength(self.iv.0.len()));
+        }
+        let cipher = cipher(password, &self.salt.0, &self.cipher_params)?;
+        let nonce = GenericArray::from_slice(&self.iv.0);
+        let node_order = [self.auth_tag.0.as_slice(), self.cipher_text.0.as_slice()].concat();
+        cipher
+            .decrypt(nonce, node_order.as_slice())
+            .map_err(|_| SecretStorageError::WrongPassword)
+    }
+
+    /// Parse from the storage file JSON
+    pub fn from_json(json: &str) -> Result<EncryptedSecret, SecretStorageError> {
+        serde_json::from_str(json).map_err(|e| SecretStorageError::Json(e.to_string()))
+    }
+
+    /// Storage file JSON
+    pub fn to_json(&self) -> Result<String, SecretStorageError> {
+        serde_json::to_string_pretty(self).map_err(|e| SecretStorageError::Json(e.to_string()))
+    }
+}
+
+fn cipher(
+    password: &str,
+    salt: &[u8],
+    settings: &EncryptionSettings,
+) -> Result<Aes256Gcm16, SecretStorageError> {
+    if settings.prf != PRF_HMAC_SHA256 {
+        return Err(SecretStorageError::UnsupportedPrf(settings.prf.clone()));
+    }
+    if settings.dk_len != 256 {
+        return Err(SecretStorageError::UnsupportedKeyLength(settings.dk_len));
+    }
+    let mut key = [0u8; 32];
+    pbkdf2::pbkdf2::<Hmac<Sha256>>(password.as_bytes(), salt, settings.c, &mut key);
+    Ok(Aes256Gcm16::new(GenericArray::from_slice(&key)))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn fast_settings() -> EncryptionSettings {
+        EncryptionSettings {
+            c: 1000,
+            ..EncryptionSettings::default()
+        }
+    }
+
+    #[test]
+    fn encrypt_decrypt_roundtrip() {
+        let seed = (0..64u8).collect::<Vec<u8>>();
+        let secret = EncryptedSecret::encrypt(&seed, "password", fast_settings()).unwrap();
+        assert_eq!(secret.auth_tag.0.len(), AUTH_TAG_LENGTH);
+        assert_eq!(secret.cipher_text.0.len(), seed.len());
+        assert_eq!(secret.decrypt("password").unwrap(), seed);
+        let parsed = EncryptedSecret::from_json(&secret.to_json().unwrap()).unwrap();
+        assert_eq!(parsed, secret);
+        assert_eq!(parsed.decrypt("password").unwrap(), seed);
+    }
+
+    #[test]
+    fn decrypt_jce_encrypted() {
+        // generated with the JCE the way the node's `AES.encrypt` does it (PBKDF2WithHmacSHA256
+        // key, AES/GCM/NoPadding with the 128-bit tag, the `doFinal` output split at the tag
+        // length into the `authTag` and `cipherText`), not taken from a node keystore file
+        let json = r#"{
+            "cipherText": "885c4bb9546b2e7de9f64f73afe417fed485e11168d10c7fe28a96e02064f3105294bcfbeeb58212d915378f64bb1f53d1210b807f32a5032d772c97b3c09083",
+            "salt": "a0a1a2a3a4a5a6a7a8a9aaabacadaeafb0b1b2b3b4b5b6b7b8b9babbbcbdbebf",
+            "iv": "101112131415161718191a1b1c1d1e1f",
+            "authTag": "43fcf014acd5121ceb19f858ec2bcad1",
+            "cipherParams": { "prf": "HmacSHA256", "c": 1000, "dkLen": 256 }
+        }"#;
+        let secret = EncryptedSecret::from_json(json).unwrap();
+        assert_eq!(
+            secret.decrypt("test password").unwrap(),
+            (0..64u8).collect::<Vec<u8>>()
+        );
+        // the same cipher output split with the tag last
+        let mut tag_last = secret.clone();
+        tag_last.cipher_text.0 = [secret.auth_tag.0.clone(), secret.cipher_text.0.clone()].concat();
+        tag_last.auth_tag.0 = tag_last.cipher_text.0.split_off(secret.cipher_text.0.len());
+        assert_eq!(
+            tag_last.decrypt("test password"),
+            Err(SecretStorageError::WrongPassword)
+        );
+    }
+
+    #[test]
+    fn wrong_password() {
+        let secret = EncryptedSecret::encrypt(&[1u8; 64], "password", fast_settings()).unwrap();
+        assert_eq!(
+            secret.decrypt("passw0rd"),
+            Err(SecretStorageError::WrongPassword)
+        );
+        let mut tampered = secret;
+        tampered.cipher_text.0[0] ^= 1;
+        assert_eq!(
+            tampered.decrypt("password"),
+            Err(SecretStorageError::WrongPassword)
+        );
+    }
+
+    #[test]
+    fn unsupported_params() {
+        let settings = EncryptionSettings {
+            prf: "HmacSHA512".to_string(),
+            ..fast_settings()
+        };
+        assert_eq!(
+            EncryptedSecret::encrypt(&[1u8; 64], "password", settings),
+            Err(SecretStorageError::UnsupportedPrf("HmacSHA512".to_string()))
+        );
+        let mut secret = EncryptedSecret::encrypt(&[1u8; 64], "password", fast_settings()).unwrap();
+        secret.iv.0.truncate(12);
+        assert_eq!(
+            secret.decrypt("password"),
+            Err(SecretStorageError::InvalidIvLength(12))
+        );
+    }
+
+    #[test]
+    fn json_fields() {
+        let json = r#"{
+            "cipherText": "00",
+            "salt": "01",
+            "iv": "02",
+            "authTag": "03",
+            "cipherParams": { "prf": "HmacSHA256", "c": 128000, "dkLen": 256 }
+        }"#;
+        let secret = EncryptedSecret::from_json(json).unwrap();
+        assert_eq!(secret.cipher_text.0, vec![0]);
+        assert_eq!(secret.auth_tag.0, vec![3]);
+        assert_eq!(secret.cipher_params, EncryptionSettings::default());
+        assert_eq!(secret.use_pre_1627_key_derivation, None);
+        let value: serde_json::Value = serde_json::from_str(&secret.to_json().unwrap()).unwrap();
+        assert_eq!(value["cipherParams"]["dkLen"], 256);
+        assert!(value.get("usePre1627KeyDerivation").is_none());
+    }
+}
@@ -11,11 +11,14 @@ use crate::chain::{
     ergo_state_context::ErgoStateContext,
     transaction::{unsigned::UnsignedTransaction, Transaction},
 };
+use crate::wallet::multi_sig::TransactionHintsBag;
 
 use ergotree_interpreter::eval::context::Context;
 use ergotree_interpreter::eval::env::Env;
 use ergotree_interpreter::sigma_protocol::prover::ProverError;
 use ergotree_interpreter::sigma_protocol::prover::{ContextExtension, Prover};
+use ergotree_interpreter::sigma_protocol::verifier::VerifierError;
+use ergotree_ir::sigma_protocol::sigma_boolean::SigmaBoolean;
 use thiserror::Error;
 
 /// Errors on transaction signing
@@ -27,9 +30,15 @@ pub enum TxSigningError {
     /// failed to find an input in boxes_to_spend
     #[error("Input box not found (index {0})")]
     InputBoxNotFound(usize),
+    /// input index is out of bounds of the transaction inputs
+    #[error("Input index {0} is out of bounds")]
+    InputIndexOutOfBounds(usize),
     /// Context creation error
     #[error("Context error: {0}")]
     ContextError(String),
+    /// Failed to extract hints from the input proof
+    #[error("Hints extraction error (tx input index {1}): {0}")]
+    HintsExtractionError(VerifierError, usize),
     /// Tx serialization failed (id calculation)
     #[error("Transaction serialization failed: {0}")]
     SerializationError(#[from] SigmaSerializationError),
@@ -46,6 +55,18 @@ pub struct TransactionContext {
     pub data_boxes: Vec<ErgoBox>,
 }
 
+/// Sign an arbitrary message for the sigma proposition (e.g. the public key of a P2PK address),
+/// the signature is verified with [`super::verifier::verify_signature`]
+pub fn sign_message(
+    prover: &dyn Prover,
+    sigma_tree: SigmaBoolean,
+    msg: &[u8],
+) -> Result<Vec<u8>, ProverError> {
+    prover
+        .generate_proof(sigma_tree, msg, &HintsBag::empty())
+        .map(|proof| proof.proof.into())
+}
+
 /// `self_index` - index of the SELF box in the tx_ctx.boxes_to_spend
 pub fn make_context(
     state_ctx: &ErgoStateContext,
@@ -87,11 +108,48 @@ pub fn make_context(
     })
 }
 
+/// Returns the message (bytes) the prover signs for the input with the given index, so that it
+/// can be verified independently (e.g. by auditors or hardware wallet firmware).
+/// The message is the transaction serialized with empty proofs (including the distinct token ids
+/// of the outputs) and is the same for all inputs. Fails if the input index is out of bounds or
+/// the box for the input is not found in `boxes_to_spend`.
+pub fn signing_message(
+    unsigned_tx: &UnsignedTransaction,
+    boxes_to_spend: &[ErgoBox],
+    input_index: usize,
+) -> Result<Vec<u8>, TxSigningError> {
+    let input = unsigned_tx
+        .inputs
+        .get(input_index)
+        .ok_or(TxSigningError::InputIndexOutOfBounds(input_index))?;
+    if !boxes_to_spend.iter().any(|b| b.box_id() == input.box_id) {
+        return Err(TxSigningError::InputBoxNotFound(input_index));
+    }
+    Ok(unsigned_tx.bytes_to_sign()?)
+}
+
 /// Signs a transaction (generating proofs for inputs)
 pub fn sign_transaction(
     prover: &dyn Prover,
     tx_context: TransactionContext,
     state_context: &ErgoStateContext,
+) -> Result<Transaction, TxSigningError> {
+    sign_transaction_with_hints(
+        prover,
+        tx_context,
+        state_context,
+        &TransactionHintsBag::empty(),
+    )
+}
+
+/// Signs a transaction (generating proofs for inputs) using the hints for every input, e.g.
+/// commitments and proofs of other signers (see [`crate::wallet::multi_sig`]).
+/// Inputs which cannot be fully proven without other signers get partial proofs.
+pub fn sign_transaction_with_hints(
+    prover: &dyn Prover,
+    tx_context: TransactionContext,
+    state_context: &ErgoStateContext,
+    tx_hints: &TransactionHintsBag,
 ) -> Result<Transaction, TxSigningError> {
     let tx = tx_context.spending_tx.clone();
     let message_to_sign = tx.bytes_to_sign()?;
@@ -108,7 +166,7 @@ pub fn sign_transaction(
                     &Env::empty(),
                     ctx,
                     message_to_sign.as_slice(),
-                    &HintsBag::empty(),
+                    &tx_hints.all_hints_for_input(idx),
                 )
                 .map(|proof| Input::new(input.box_id.clone(), proof.into()))
                 .map_err(|e| TxSigningError::ProverError(e, idx))
@@ -136,7 +194,7 @@ pub fn sign_reduced_transaction(
                 reduced_tx
                     .reduced_inputs()
                     .get(idx)
-                    .unwrap()
+                    .ok_or(TxSigningError::InputIndexOutOfBounds(idx))?
                     .reduction_result
                     .sigma_prop
                     .clone(),
@@ -154,6 +212,7 @@ pub fn sign_reduced_transaction(
 }
 
 #[cfg(test)]
+#[allow(clippy::unwrap_used)]
 mod tests {
     use super::*;
     use ergotree_interpreter::sigma_protocol::private_input::DlogProverInput;
@@ -240,6 +299,14 @@ mod tests {
             let res = sign_transaction(prover.as_ref(), tx_context.clone(), &ErgoStateContext::dummy());
             let signed_tx = res.unwrap();
             prop_assert!(verify_tx_proofs(&signed_tx, &boxes_to_spend).unwrap());
+            for idx in 0..secrets.len() {
+                prop_assert_eq!(signing_message(&tx_context.spending_tx, &boxes_to_spend, idx).unwrap(),
+                    signed_tx.bytes_to_sign().unwrap());
+            }
+            prop_assert_eq!(signing_message(&tx_context.spending_tx, &boxes_to_spend, secrets.len()),
+                Err(TxSigningError::InputIndexOutOfBounds(secrets.len())));
+            prop_assert_eq!(signing_message(&tx_context.spending_tx, &[], 0),
+                Err(TxSigningError::InputBoxNotFound(0)));
             let reduced_tx = reduce_tx(tx_context, &ErgoStateContext::dummy()).unwrap();
             let signed_reduced_tx = sign_reduced_transaction(prover.as_ref(), reduced_tx).unwrap();
             prop_assert!(verify_tx_proofs(&signed_reduced_tx, &boxes_to_spend).unwrap());
@@ -0,0 +1,556 @@
+//! Signing policy of the custodial signers: declarative rules (allowed destinations, value limit
+//! per epoch, allowed tokens, required co-signers) checked against a transaction before it is
+//! signed (see [`super::Wallet::sign_transaction_with_policy`])
+//!
+//! The rules are loaded from JSON, e.g.:
+//! ```json
+//! {
+//!   "allowedDestinationTemplates": ["<template hash (Base16)>"],
+//!   "valueLimit": { "epochLength": 720, "maxValue": 1000000000000 },
+//!   "allowedTokens": ["<token id (Base16)>"],
+//!   "requiredCosigners": ["<public key (Base16)>"]
+//! }
+//! ```
+//! Omitted rules are not enforced.
+
+use ergotree_ir::chain::digest32::Digest32;
+use ergotree_ir::chain::ergo_box::BoxTokens;
+use ergotree_ir::chain::ergo_box::ErgoBox;
+use ergotree_ir::chain::token::TokenId;
+use ergotree_ir::ergo_tree::ErgoTree;
+use ergotree_ir::ergo_tree::ErgoTreeError;
+use ergotree_ir::sigma_protocol::sigma_boolean::ProveDlog;
+use ergotree_ir::sigma_protocol::sigma_boolean::SigmaBoolean;
+use ergotree_ir::sigma_protocol::sigma_boolean::SigmaConjecture;
+use ergotree_ir::sigma_protocol::sigma_boolean::SigmaProofOfKnowledgeTree;
+#[cfg(feature = "json")]
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::chain::transaction::reduced::ReducedTransaction;
+
+/// Limit of the value sent by the transactions signed in an epoch
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct ValueLimit {
+    /// Epoch length in blocks (the epoch of the height is `height / epoch_length`, 0 for a
+    /// single epoch of the whole chain)
+    #[cfg_attr(feature = "json", serde(rename = "epochLength"))]
+    pub epoch_length: u32,
+    /// Maximum value (in nanoERGs) sent in an epoch
+    #[cfg_attr(feature = "json", serde(rename = "maxValue"))]
+    pub max_value: u64,
+}
+
+/// Rules the transaction is checked against before signing.
+/// The outputs guarded by the scripts of the spent boxes are the change, the other outputs
+/// (including the miner fee) are the destinations.
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct SigningPolicyRules {
+    /// Template hashes (see [`ErgoTree::template_hash`]) of the scripts the destinations can be
+    /// guarded by (the miner fee script should be in the list), any script if None
+    #[cfg_attr(
+        feature = "json",
+        serde(rename = "allowedDestinationTemplates", default)
+    )]
+    pub allowed_destination_templates: Option<Vec<Digest32>>,
+    /// Limit of the value sent to the destinations, no limit if None
+    #[cfg_attr(feature = "json", serde(rename = "valueLimit", default))]
+    pub value_limit: Option<ValueLimit>,
+    /// Tokens the destinations can hold, any tokens if None
+    #[cfg_attr(feature = "json", serde(rename = "allowedTokens", default))]
+    pub allowed_tokens: Option<Vec<TokenId>>,
+    /// Keys which must sign every input along with the signer (the reduced proposition of every
+    /// input cannot be proven without them)
+    #[cfg_attr(
+        feature = "json",
+        serde(rename = "requiredCosigners", default, with = "cosigners_json")
+    )]
+    pub required_cosigners: Vec<ProveDlog>,
+}
+
+/// Errors of the signing policy check
+#[derive(Error, PartialEq, Eq, Debug, Clone)]
+pub enum SigningPolicyError {
+    /// Destination is guarded by a script with the template not in the allowed list
+    #[error("Output {output_index} script template {template_hash:?} is not allowed")]
+    DestinationNotAllowed {
+        /// Index of the output
+        output_index: usize,
+        /// Template hash of the output script
+        template_hash: Digest32,
+    },
+    /// Value sent in the epoch (with the transaction) is over the limit
+    #[error(
+        "Value limit {max_value} is exceeded: {sent} sent after {spent_in_epoch} in the epoch"
+    )]
+    ValueLimitExceeded {
+        /// Value sent by the transaction
+        sent: u64,
+        /// Value sent by the transactions signed earlier in the epoch
+        spent_in_epoch: u64,
+        /// Maximum value sent in an epoch
+        max_value: u64,
+    },
+    /// Destination holds a token not in the allowed list
+    #[error("Output {output_index} token {token_id:?} is not allowed")]
+    TokenNotAllowed {
+        /// Index of the output
+        output_index: usize,
+        /// Token id
+        token_id: TokenId,
+    },
+    /// Input proposition can be proven without the required co-signer
+    #[error("Input {input_index} does not require co-signer {cosigner:?}")]
+    CosignerNotRequired {
+        /// Index of the input
+        input_index: usize,
+        /// Public key of the co-signer
+        cosigner: ProveDlog,
+    },
+    /// Box spent by the input is not found
+    #[error("Input box not found (index {0})")]
+    InputBoxNotFound(usize),
+    /// Output script template could not be computed
+    #[error("ErgoTree error: {0:?}")]
+    ErgoTreeError(ErgoTreeError),
+}
+
+impl From<ErgoTreeError> for SigningPolicyError {
+    fn from(e: ErgoTreeError) -> Self {
+        SigningPolicyError::ErgoTreeError(e)
+    }
+}
+
+/// Signing policy: the rules and the value sent by the transactions signed in the current epoch
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct SigningPolicy {
+    rules: SigningPolicyRules,
+    epoch: u32,
+    spent_in_epoch: u64,
+}
+
+impl SigningPolicy {
+    /// Create the policy with nothing sent yet
+    pub fn new(rules: SigningPolicyRules) -> Self {
+        SigningPolicy {
+            rules,
+            epoch: 0,
+            spent_in_epoch: 0,
+        }
+    }
+
+    /// Rules of the policy
+    pub fn rules(&self) -> &SigningPolicyRules {
+        &self.rules
+    }
+
+    /// Value sent by the transactions signed in the epoch of the height
+    pub fn spent_in_epoch(&self, height: u32) -> u64 {
+        match self.epoch_of(height) {
+            Some(epoch) if epoch == self.epoch => self.spent_in_epoch,
+            _ => 0,
+        }
+    }
+
+    /// Checks the reduced transaction against the rules, returns the value it sends to the
+    /// destinations.
+    /// `boxes_to_spend` - boxes spent by the transaction inputs,
+    /// `height` - height of the block the transaction is signed for (the value limit epoch)
+    pub fn check(
+        &self,
+        reduced_tx: &ReducedTransaction,
+        boxes_to_spend: &[ErgoBox],
+        height: u32,
+    ) -> Result<u64, SigningPolicyError> {
+        let tx = &reduced_tx.unsigned_tx;
+        let spent_trees = tx
+            .inputs
+            .iter()
+            .enumerate()
+            .map(|(idx, input)| {
+                boxes_to_spend
+                    .iter()
+                    .find(|b| b.box_id() == input.box_id)
+                    .map(|b| &b.ergo_tree)
+                    .ok_or(SigningPolicyError::InputBoxNotFound(idx))
+            })
+            .collect::<Result<Vec<&ErgoTree>, SigningPolicyError>>()?;
+        let mut sent = 0u64;
+        for (output_index, output) in tx.output_candidates.iter().enumerate() {
+            if spent_trees.contains(&&output.ergo_tree) {
+                continue;
+            }
+            if let Some(templates) = &self.rules.allowed_destination_templates {
+                let template_hash = output.ergo_tree.template_hash()?;
+                if !templates.contains(&template_hash) {
+                    return Err(SigningPolicyError::DestinationNotAllowed {
+                        output_index,
+                        template_hash,
+                    });
+                }
+            }
+            if let Some(tokens) = &self.rules.allowed_tokens {
+                let output_tokens = output.tokens.as_ref().map(BoxTokens::as_ref).unwrap_or(&[]);
+                if let Some(token) = output_tokens.iter().find(|t| !tokens.contains(&t.token_id)) {
+                    return Err(SigningPolicyError::TokenNotAllowed {
+                        output_index,
+                        token_id: token.token_id.clone(),
+                    });
+                }
+            }
+            sent = sent.saturating_add(*output.value.as_u64());
+        }
+        if let Some(limit) = self.rules.value_limit {
+            let spent_in_epoch = self.spent_in_epoch(height);
+            if spent_in_epoch.saturating_add(sent) > limit.max_value {
+                return Err(SigningPolicyError::ValueLimitExceeded {
+                    sent,
+                    spent_in_epoch,
+                    max_value: limit.max_value,
+                });
+            }
+        }
+        for (input_index, input) in reduced_tx.reduced_inputs().iter().enumerate() {
+            let sigma_prop = &input.reduction_result.sigma_prop;
+            if let Some(cosigner) = self
+                .rules
+                .required_cosigners
+                .iter()
+                .find(|c| provable_without(sigma_prop, c))
+            {
+                return Err(SigningPolicyError::CosignerNotRequired {
+                    input_index,
+                    cosigner: cosigner.clone(),
+                });
+            }
+        }
+        Ok(sent)
+    }
+
+    /// Records the value sent by the signed transaction (see [`SigningPolicy::check`]) in the
+    /// epoch of the height
+    pub fn record(&mut self, sent: u64, height: u32) {
+        if let Some(epoch) = self.epoch_of(height) {
+            self.spent_in_epoch = self.spent_in_epoch(height).saturating_add(sent);
+            self.epoch = epoch;
+        }
+    }
+
+    fn epoch_of(&self, height: u32) -> Option<u32> {
+        self.rules
+            .value_limit
+            .map(|l| height.checked_div(l.epoch_length).unwrap_or(0))
+    }
+}
+
+/// Returns true if the proposition can be proven without the key (with all the other keys)
+fn provable_without(sb: &SigmaBoolean, key: &ProveDlog) -> bool {
+    match sb {
+        SigmaBoolean::TrivialProp(b) => *b,
+        SigmaBoolean::ProofOfKnowledge(SigmaProofOfKnowledgeTree::ProveDlog(pk)) => pk != key,
+        SigmaBoolean::ProofOfKnowledge(SigmaProofOfKnowledgeTree::ProveDhTuple(_)) => true,
+        SigmaBoolean::SigmaConjecture(SigmaConjecture::Cand(cand)) => {
+            cand.items.iter().all(|i| provable_without(i, key))
+        }
+        SigmaBoolean::SigmaConjecture(SigmaConjecture::Cor(cor)) => {
+            cor.items.iter().any(|i| provable_without(i, key))
+        }
+        SigmaBoolean::SigmaConjecture(SigmaConjecture::Cthreshold(ct)) => {
+            let provable = ct.items.iter().filter(|i| provable_without(i, key)).count();
+            provable as i64 >= i64::from(ct.n)
+        }
+    }
+}
+
+/// Co-signer public keys as Base16-encoded (compressed) points
+#[cfg(feature = "json")]
+mod cosigners_json {
+    use ergotree_ir::chain::base16_bytes::Base16DecodedBytes;
+    use ergotree_ir::serialization::SigmaSerializable;
+    use ergotree_ir::sigma_protocol::dlog_group::EcPoint;
+    use ergotree_ir::sigma_protocol::sigma_boolean::ProveDlog;
+    use serde::Deserialize;
+    use serde::Deserializer;
+    use serde::Serialize;
+    use serde::Serializer;
+
+    pub(super) fn serialize<S: Serializer>(keys: &[ProveDlog], s: S) -> Result<S::Ok, S::Error> {
+        keys.iter()
+            .map(|k| k.h.sigma_serialize_bytes().map(Base16DecodedBytes))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(serde::ser::Error::custom)?
+            .serialize(s)
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Vec<ProveDlog>, D::Error> {
+        Vec::<Base16DecodedBytes>::deserialize(d)?
+            .into_iter()
+            .map(|bytes| {
+                EcPoint::sigma_parse_bytes(&bytes.0)
+                    .map(ProveDlog::from)
+                    .map_err(serde::de::Error::custom)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::convert::TryFrom;
+    use std::convert::TryInto;
+
+    use super::*;
+    use crate::chain::ergo_box::box_builder::ErgoBoxCandidateBuilder;
+    use crate::chain::ergo_state_context::ErgoStateContext;
+    use crate::chain::transaction::reduced::reduce_tx;
+    use crate::chain::transaction::unsigned::UnsignedTransaction;
+    use crate::chain::transaction::UnsignedInput;
+    use crate::wallet::secret_key::SecretKey;
+    use crate::wallet::signing::TransactionContext;
+    use crate::wallet::Wallet;
+    use crate::wallet::WalletError;
+    use ergotree_interpreter::sigma_protocol::private_input::DlogProverInput;
+    use ergotree_ir::chain::ergo_box::box_value::BoxValue;
+    use ergotree_ir::chain::ergo_box::NonMandatoryRegisters;
+    use ergotree_ir::chain::token::Token;
+    use ergotree_ir::chain::tx_id::TxId;
+    use ergotree_ir::mir::constant::Constant;
+    use ergotree_ir::mir::expr::Expr;
+    use ergotree_ir::sigma_protocol::sigma_boolean::cand::Cand;
+    use ergotree_ir::sigma_protocol::sigma_boolean::cor::Cor;
+    use ergotree_ir::sigma_protocol::sigma_boolean::SigmaConjectureItems;
+    use sigma_test_util::force_any_val;
+
+    fn tree(sb: SigmaBoolean) -> ErgoTree {
+        ErgoTree::try_from(Expr::Const(Constant::from(sb))).unwrap()
+    }
+
+    fn value(v: u64) -> BoxValue {
+        BoxValue::try_from(v).unwrap()
+    }
+
+    /// Spends a box guarded by `input_tree`, sends `sent` to `destination` (with the tokens) and
+    /// the change back
+    fn tx_context(
+        input_tree: ErgoTree,
+        destination: ErgoTree,
+        sent: u64,
+        tokens: Vec<Token>,
+    ) -> TransactionContext {
+        let input_box = ErgoBox::new(
+            value(sent * 2),
+            input_tree.clone(),
+            None,
+            NonMandatoryRegisters::empty(),
+            0,
+            TxId::zero(),
+            0,
+        )
+        .unwrap();
+        let mut dest_builder = ErgoBoxCandidateBuilder::new(value(sent), destination, 0);
+        tokens.into_iter().for_each(|t| dest_builder.add_token(t));
+        let change = ErgoBoxCandidateBuilder::new(value(sent), input_tree, 0)
+            .build()
+            .unwrap();
+        let spending_tx = UnsignedTransaction::new(
+            vec![UnsignedInput::from(input_box.clone())]
+                .try_into()
+                .unwrap(),
+            None,
+            vec![dest_builder.build().unwrap(), change]
+                .try_into()
+                .unwrap(),
+        )
+        .unwrap();
+        TransactionContext {
+            spending_tx,
+            boxes_to_spend: vec![input_box],
+            data_boxes: vec![],
+        }
+    }
+
+    fn check(
+        policy: &SigningPolicy,
+        tx_context: TransactionContext,
+    ) -> Result<u64, SigningPolicyError> {
+        let boxes_to_spend = tx_context.boxes_to_spend.clone();
+        let reduced_tx = reduce_tx(tx_context, &ErgoStateContext::dummy()).unwrap();
+        policy.check(&reduced_tx, &boxes_to_spend, 0)
+    }
+
+    #[test]
+    fn destination_templates() {
+        let signer = tree(DlogProverInput::random().public_image().into());
+        let destination = tree(DlogProverInput::random().public_image().into());
+        let template_hash = destination.template_hash().unwrap();
+        let mut rules = SigningPolicyRules {
+            allowed_destination_templates: Some(vec![template_hash.clone()]),
+            ..SigningPolicyRules::default()
+        };
+        let ctx = tx_context(signer, destination, 1_000_000, vec![]);
+        assert_eq!(
+            check(&SigningPolicy::new(rules.clone()), ctx.clone()),
+            Ok(1_000_000)
+        );
+        rules.allowed_destination_templates = Some(vec![force_any_val::<Digest32>()]);
+        assert_eq!(
+            check(&SigningPolicy::new(rules), ctx),
+            Err(SigningPolicyError::DestinationNotAllowed {
+                output_index: 0,
+                template_hash,
+            })
+        );
+    }
+
+    #[test]
+    fn value_limit_per_epoch() {
+        let rules = SigningPolicyRules {
+            value_limit: Some(ValueLimit {
+                epoch_length: 100,
+                max_value: 2_000_000,
+            }),
+            ..SigningPolicyRules::default()
+        };
+        let mut policy = SigningPolicy::new(rules);
+        policy.record(1_500_000, 150);
+        assert_eq!(policy.spent_in_epoch(199), 1_500_000);
+        assert_eq!(policy.spent_in_epoch(200), 0);
+        let signer = tree(DlogProverInput::random().public_image().into());
+        let destination = tree(DlogProverInput::random().public_image().into());
+        let ctx = tx_context(signer, destination, 1_000_000, vec![]);
+        let boxes_to_spend = ctx.boxes_to_spend.clone();
+        let reduced_tx = reduce_tx(ctx, &ErgoStateContext::dummy()).unwrap();
+        assert_eq!(
+            policy.check(&reduced_tx, &boxes_to_spend, 199),
+            Err(SigningPolicyError::ValueLimitExceeded {
+                sent: 1_000_000,
+                spent_in_epoch: 1_500_000,
+                max_value: 2_000_000,
+            })
+        );
+        assert_eq!(
+            policy.check(&reduced_tx, &boxes_to_spend, 200),
+            Ok(1_000_000)
+        );
+        policy.record(1_000_000, 200);
+        assert_eq!(policy.spent_in_epoch(250), 1_000_000);
+    }
+
+    #[test]
+    fn allowed_tokens() {
+        let allowed = force_any_val::<Token>();
+        let other = force_any_val::<Token>();
+        let rules = SigningPolicyRules {
+            allowed_tokens: Some(vec![allowed.token_id.clone()]),
+            ..SigningPolicyRules::default()
+        };
+        let policy = SigningPolicy::new(rules);
+        let signer = tree(DlogProverInput::random().public_image().into());
+        let destination = tree(DlogProverInput::random().public_image().into());
+        assert!(check(
+            &policy,
+            tx_context(
+                signer.clone(),
+                destination.clone(),
+                1_000_000,
+                vec![allowed.clone()]
+            )
+        )
+        .is_ok());
+        assert_eq!(
+            check(
+                &policy,
+                tx_context(signer, destination, 1_000_000, vec![allowed, other.clone()])
+            ),
+            Err(SigningPolicyError::TokenNotAllowed {
+                output_index: 0,
+                token_id: other.token_id,
+            })
+        );
+    }
+
+    #[test]
+    fn required_cosigners() {
+        let signer = DlogProverInput::random().public_image();
+        let cosigner = DlogProverInput::random().public_image();
+        let rules = SigningPolicyRules {
+            required_cosigners: vec![cosigner.clone()],
+            ..SigningPolicyRules::default()
+        };
+        let policy = SigningPolicy::new(rules);
+        let destination = tree(DlogProverInput::random().public_image().into());
+        let items = || {
+            SigmaConjectureItems::from_vec(vec![signer.clone().into(), cosigner.clone().into()])
+                .unwrap()
+        };
+        let both = tree(Cand::normalized(items()));
+        assert!(check(
+            &policy,
+            tx_context(both, destination.clone(), 1_000_000, vec![])
+        )
+        .is_ok());
+        let either = tree(Cor::normalized(items()));
+        assert_eq!(
+            check(&policy, tx_context(either, destination, 1_000_000, vec![])),
+            Err(SigningPolicyError::CosignerNotRequired {
+                input_index: 0,
+                cosigner,
+            })
+        );
+    }
+
+    #[test]
+    fn sign_with_policy() {
+        let secret = DlogProverInput::random();
+        let signer = tree(secret.public_image().into());
+        let destination = tree(DlogProverInput::random().public_image().into());
+        let wallet = Wallet::from_secrets(vec![SecretKey::DlogSecretKey(secret)]);
+        let mut policy = SigningPolicy::new(SigningPolicyRules {
+            value_limit: Some(ValueLimit {
+                epoch_length: 0,
+                max_value: 1_500_000,
+            }),
+            ..SigningPolicyRules::default()
+        });
+        let ctx = tx_context(signer, destination, 1_000_000, vec![]);
+        let state_context = ErgoStateContext::dummy();
+        assert!(wallet
+            .sign_transaction_with_policy(ctx.clone(), &state_context, &mut policy)
+            .is_ok());
+        assert_eq!(policy.spent_in_epoch(0), 1_000_000);
+        assert!(matches!(
+            wallet.sign_transaction_with_policy(ctx, &state_context, &mut policy),
+            Err(WalletError::SigningPolicyError(
+                SigningPolicyError::ValueLimitExceeded { .. }
+            ))
+        ));
+        assert_eq!(policy.spent_in_epoch(0), 1_000_000);
+    }
+
+    #[test]
+    fn rules_json() {
+        let cosigner = DlogProverInput::random().public_image();
+        let rules = SigningPolicyRules {
+            allowed_destination_templates: Some(vec![force_any_val::<Digest32>()]),
+            value_limit: Some(ValueLimit {
+                epoch_length: 720,
+                max_value: 1_000_000_000_000,
+            }),
+            allowed_tokens: None,
+            required_cosigners: vec![cosigner],
+        };
+        let json = serde_json::to_string(&rules).unwrap();
+        assert!(json.contains("\"epochLength\":720"));
+        assert_eq!(
+            serde_json::from_str::<SigningPolicyRules>(&json).unwrap(),
+            rules
+        );
+        assert_eq!(
+            serde_json::from_str::<SigningPolicyRules>("{}").unwrap(),
+            SigningPolicyRules::default()
+        );
+    }
+}
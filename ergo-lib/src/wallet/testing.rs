@@ -0,0 +1,439 @@
+//! Property-based testing of the transaction building (box selection, fee and change)
+//!
+//! [`WalletScenario`] generates wallets, their UTXO sets and payment requests, and
+//! [`check_tx_invariants`] builds, signs and checks the transaction for a scenario with the given
+//! [`BoxSelector`], so that a custom box selector can be tested against the same invariants:
+//! ```ignore
+//! proptest! {
+//!     #[test]
+//!     fn my_selector_invariants(scenario in any::<WalletScenario>()) {
+//!         check_tx_invariants(&MySelector::new(), &scenario).unwrap();
+//!     }
+//! }
+//! ```
+
+use std::convert::TryFrom;
+use std::convert::TryInto;
+
+use ergo_units::Height;
+use ergotree_interpreter::sigma_protocol::private_input::DlogProverInput;
+use ergotree_ir::chain::address::Address;
+use ergotree_ir::chain::ergo_box::box_value::BoxValue;
+use ergotree_ir::chain::ergo_box::box_value::BoxValueError;
+use ergotree_ir::chain::ergo_box::BoxId;
+use ergotree_ir::chain::ergo_box::BoxTokens;
+use ergotree_ir::chain::ergo_box::ErgoBox;
+use ergotree_ir::chain::ergo_box::ErgoBoxCandidate;
+use ergotree_ir::chain::ergo_box::NonMandatoryRegisters;
+use ergotree_ir::chain::token::Token;
+use ergotree_ir::chain::token::TokenAmount;
+use ergotree_ir::chain::tx_id::TxId;
+use ergotree_ir::ergo_tree::ErgoTree;
+use ergotree_ir::mir::expr::Expr;
+use ergotree_ir::serialization::SigmaSerializable;
+use ergotree_ir::serialization::SigmaSerializationError;
+use proptest::collection::vec;
+use proptest::option;
+use proptest::prelude::*;
+use thiserror::Error;
+
+use crate::chain::ergo_box::box_builder::ErgoBoxCandidateBuilder;
+use crate::chain::ergo_box::box_builder::ErgoBoxCandidateBuilderError;
+use crate::chain::ergo_state_context::ErgoStateContext;
+use crate::chain::transaction::Transaction;
+
+use super::box_selector::checked_sum_tokens;
+use super::box_selector::sum_value;
+use super::box_selector::tokens_diff;
+use super::box_selector::BoxSelector;
+use super::box_selector::BoxSelectorError;
+use super::box_selector::TokenAmountsOverflow;
+use super::box_selector::TokensDiff;
+use super::secret_key::SecretKey;
+use super::signing::TransactionContext;
+use super::tx_builder::TxBuilder;
+use super::tx_builder::TxBuilderError;
+use super::verifier::verify_tx_proofs;
+use super::verifier::TxVerifyError;
+use super::Wallet;
+use super::WalletError;
+
+/// Wallet with its UTXO set and a payment request
+#[derive(PartialEq, Debug, Clone)]
+pub struct WalletScenario {
+    /// Wallet secrets
+    pub secrets: Vec<SecretKey>,
+    /// Unspent boxes of the wallet (protected by the wallet's keys)
+    pub utxos: Vec<ErgoBox>,
+    /// Payment outputs (satisfiable with the UTXO set)
+    pub payments: Vec<ErgoBoxCandidate>,
+    /// Miner's fee
+    pub fee: BoxValue,
+    /// Height of the transaction
+    pub current_height: Height,
+}
+
+impl WalletScenario {
+    /// Change address (the address of the first wallet key)
+    pub fn change_address(&self) -> Option<Address> {
+        self.secrets
+            .first()
+            .map(SecretKey::get_address_from_public_image)
+    }
+
+    /// Value to select from the UTXO set (payments and the fee)
+    pub fn target_balance(&self) -> Result<BoxValue, BoxValueError> {
+        BoxValue::try_from(sum_value(self.payments.as_slice()) + *self.fee.as_u64())
+    }
+
+    /// Tokens to select from the UTXO set (transferred in the payments)
+    pub fn target_tokens(&self) -> Result<Vec<Token>, TokenAmountsOverflow> {
+        Ok(checked_sum_tokens(self.payments.as_slice())?.to_tokens())
+    }
+}
+
+/// Minimal value of the change box passed to [`TxBuilder`] in [`check_tx_invariants`]
+pub const MIN_CHANGE_VALUE: BoxValue = BoxValue::SAFE_USER_MIN;
+
+/// Violations of the transaction building invariants
+#[derive(Error, PartialEq, Debug, Clone)]
+pub enum TxInvariantViolation {
+    /// Scenario has no secrets
+    #[error("Scenario has no secrets (no change address)")]
+    NoSecrets,
+    /// Box value error
+    #[error("Box value error: {0}")]
+    BoxValueError(#[from] BoxValueError),
+    /// Total amount of a token overflows
+    #[error("Token amounts overflow: {0}")]
+    TokenAmountsOverflow(#[from] TokenAmountsOverflow),
+    /// Box selector failed on a satisfiable request
+    #[error("Box selector error: {0}")]
+    BoxSelectorError(#[from] BoxSelectorError),
+    /// Transaction building failed
+    #[error("TxBuilder error: {0}")]
+    TxBuilderError(#[from] TxBuilderError),
+    /// Box serialization failed
+    #[error("Serialization error: {0}")]
+    SerializationError(#[from] SigmaSerializationError),
+    /// Transaction input is not in the UTXO set
+    #[error("Input {0:?} is not in the UTXO set")]
+    UnknownInput(BoxId),
+    /// Total value of the outputs differs from the total value of the inputs
+    #[error("Value is not conserved: inputs {inputs} nanoERGs, outputs {outputs} nanoERGs")]
+    ValueNotConserved {
+        /// Total value of the inputs
+        inputs: u64,
+        /// Total value of the outputs
+        outputs: u64,
+    },
+    /// Tokens are minted or burned
+    #[error("Tokens are not conserved: {0:?}")]
+    TokensNotConserved(TokensDiff),
+    /// Payment output is not in the transaction outputs
+    #[error("Payment output #{0} is missing")]
+    MissingPayment(usize),
+    /// Miner's fee box is not in the transaction outputs
+    #[error("Miner's fee box is missing")]
+    MissingFee,
+    /// Output value is below the minimal value for the box size
+    #[error("Output #{index} value {value} is below the minimum {min_value}")]
+    BelowMinValue {
+        /// Index of the output
+        index: usize,
+        /// Value of the output
+        value: u64,
+        /// Minimal value for the box size
+        min_value: u64,
+    },
+    /// Transaction signing failed
+    #[error("Wallet error: {0}")]
+    WalletError(#[from] WalletError),
+    /// Proof verification failed
+    #[error("Tx verification error: {0}")]
+    TxVerifyError(#[from] TxVerifyError),
+    /// Signed transaction has invalid proofs
+    #[error("Signed transaction has invalid proofs")]
+    InvalidProofs,
+}
+
+/// Builds the transaction for the scenario with the given box selector, signs it with the
+/// scenario's wallet and checks that:
+/// - box selection and transaction building succeed;
+/// - inputs are from the UTXO set;
+/// - value and tokens are conserved (change is returned, nothing is minted or burned);
+/// - payments and the miner's fee are in the outputs;
+/// - every output's value covers the minimal value for its size;
+/// - the transaction is signed by the wallet and its proofs are valid.
+///
+/// Returns the signed transaction.
+pub fn check_tx_invariants<S: BoxSelector<ErgoBox> + ?Sized>(
+    selector: &S,
+    scenario: &WalletScenario,
+) -> Result<Transaction, TxInvariantViolation> {
+    let change_address = scenario
+        .change_address()
+        .ok_or(TxInvariantViolation::NoSecrets)?;
+    let box_selection = selector.select(
+        scenario.utxos.clone(),
+        scenario.target_balance()?,
+        scenario.target_tokens()?.as_slice(),
+    )?;
+    let tx_builder = TxBuilder::new(
+        box_selection,
+        scenario.payments.clone(),
+        scenario.current_height,
+        scenario.fee,
+        change_address,
+        MIN_CHANGE_VALUE,
+    );
+    let fee_proposition = tx_builder.fee_proposition();
+    let unsigned_tx = tx_builder.build()?;
+
+    let boxes_to_spend = unsigned_tx
+        .inputs
+        .iter()
+        .map(|i| {
+            scenario
+                .utxos
+                .iter()
+                .find(|b| b.box_id() == i.box_id)
+                .cloned()
+                .ok_or_else(|| TxInvariantViolation::UnknownInput(i.box_id.clone()))
+        })
+        .collect::<Result<Vec<ErgoBox>, _>>()?;
+    let outputs = unsigned_tx.output_candidates.as_vec();
+
+    let inputs_value = sum_value(boxes_to_spend.as_slice());
+    let outputs_value = sum_value(outputs.as_slice());
+    if inputs_value != outputs_value {
+        return Err(TxInvariantViolation::ValueNotConserved {
+            inputs: inputs_value,
+            outputs: outputs_value,
+        });
+    }
+    let diff = tokens_diff(outputs.as_slice(), boxes_to_spend.as_slice())?;
+    if !diff.minted.is_empty() || !diff.burned.is_empty() {
+        return Err(TxInvariantViolation::TokensNotConserved(diff));
+    }
+
+    if let Some(idx) = scenario.payments.iter().position(|p| !outputs.contains(p)) {
+        return Err(TxInvariantViolation::MissingPayment(idx));
+    }
+    if !outputs
+        .iter()
+        .any(|b| b.ergo_tree == fee_proposition && b.value == scenario.fee)
+    {
+        return Err(TxInvariantViolation::MissingFee);
+    }
+    for (index, candidate) in outputs.iter().enumerate() {
+        let output = ErgoBox::from_box_candidate(candidate, unsigned_tx.id(), index as u16)?;
+        let min_value =
+            output.sigma_serialize_bytes()?.len() as u64 * BoxValue::MIN_VALUE_PER_BOX_BYTE as u64;
+        if *candidate.value.as_u64() < min_value {
+            return Err(TxInvariantViolation::BelowMinValue {
+                index,
+                value: *candidate.value.as_u64(),
+                min_value,
+            });
+        }
+    }
+
+    let state_context = ErgoStateContext::dummy();
+    let wallet = Wallet::from_secrets(scenario.secrets.clone());
+    let tx = wallet.sign_transaction(
+        TransactionContext {
+            spending_tx: unsigned_tx,
+            boxes_to_spend: boxes_to_spend.clone(),
+            data_boxes: vec![],
+        },
+        &state_context,
+    )?;
+    if !verify_tx_proofs(&tx, &state_context, &boxes_to_spend, &[])?.is_valid() {
+        return Err(TxInvariantViolation::InvalidProofs);
+    }
+    Ok(tx)
+}
+
+#[allow(clippy::unwrap_used)]
+fn p2pk_tree(secret: &DlogProverInput) -> ErgoTree {
+    // P2PK tree is always valid
+    ErgoTree::try_from(Expr::Const(secret.public_image().into())).unwrap()
+}
+
+fn utxo_strategy(
+    secrets: Vec<DlogProverInput>,
+) -> impl Strategy<Value = Result<ErgoBox, SigmaSerializationError>> {
+    (
+        0..secrets.len(),
+        any_with::<BoxValue>(
+            (*BoxValue::SAFE_USER_MIN.as_u64() * 10..*BoxValue::SAFE_USER_MIN.as_u64() * 100)
+                .into(),
+        ),
+        option::of(vec(any::<Token>(), 1..3)),
+        0u32..1000,
+        any::<TxId>(),
+        any::<u16>(),
+    )
+        .prop_map(
+            move |(owner, value, tokens, creation_height, tx_id, index)| {
+                ErgoBox::new(
+                    value,
+                    p2pk_tree(&secrets[owner]),
+                    tokens.and_then(|ts| BoxTokens::from_vec(ts).ok()),
+                    NonMandatoryRegisters::empty(),
+                    creation_height,
+                    tx_id,
+                    index,
+                )
+            },
+        )
+}
+
+fn payment(
+    value: BoxValue,
+    recipient: &DlogProverInput,
+    token: Option<Token>,
+    creation_height: u32,
+) -> Result<ErgoBoxCandidate, ErgoBoxCandidateBuilderError> {
+    let mut builder = ErgoBoxCandidateBuilder::new(value, p2pk_tree(recipient), creation_height);
+    if let Some(token) = token {
+        builder.add_token(token);
+    }
+    builder.build()
+}
+
+impl Arbitrary for WalletScenario {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    #[allow(clippy::unwrap_used)]
+    fn arbitrary_with(_args: Self::Parameters) -> Self::Strategy {
+        vec(any::<DlogProverInput>(), 1..4)
+            .prop_flat_map(|secrets| {
+                (
+                    Just(secrets.clone()),
+                    vec(utxo_strategy(secrets), 1..8),
+                    1000u32..1_000_000,
+                )
+            })
+            .prop_flat_map(|(secrets, utxos, current_height)| {
+                let utxos: Vec<ErgoBox> = utxos.into_iter().map(Result::unwrap).collect();
+                // payments (with the fee) take less than a half of the wallet's coins, so that
+                // the change is always above the minimal change value
+                let max_payment = sum_value(utxos.as_slice()) / 8;
+                let tokens = checked_sum_tokens(utxos.as_slice()).unwrap().to_tokens();
+                let token_transfer = if tokens.is_empty() {
+                    Just(None).boxed()
+                } else {
+                    option::of((0..tokens.len(), 1u64..=100)).boxed()
+                };
+                (
+                    Just(secrets),
+                    Just(utxos),
+                    vec(
+                        (
+                            *BoxValue::SAFE_USER_MIN.as_u64()..=max_payment,
+                            any::<DlogProverInput>(),
+                        ),
+                        1..4,
+                    ),
+                    token_transfer.prop_map(move |t| {
+                        t.map(|(idx, percent)| {
+                            let token = tokens[idx].clone();
+                            let amount = (u64::from(token.amount) * percent / 100).max(1);
+                            Token {
+                                token_id: token.token_id,
+                                amount: TokenAmount::try_from(amount).unwrap(),
+                            }
+                        })
+                    }),
+                    BoxValue::MIN_RAW..=*BoxValue::SAFE_USER_MIN.as_u64(),
+                    Just(current_height),
+                )
+            })
+            .prop_map(
+                |(secrets, utxos, payments, token_transfer, fee, current_height)| {
+                    let payments = payments
+                        .into_iter()
+                        .enumerate()
+                        .map(|(idx, (value, recipient))| {
+                            let token = if idx == 0 {
+                                token_transfer.clone()
+                            } else {
+                                None
+                            };
+                            payment(value.try_into().unwrap(), &recipient, token, current_height)
+                                .unwrap()
+                        })
+                        .collect();
+                    WalletScenario {
+                        secrets: secrets.into_iter().map(SecretKey::DlogSecretKey).collect(),
+                        utxos,
+                        payments,
+                        fee: fee.try_into().unwrap(),
+                        current_height: Height::new(current_height),
+                    }
+                },
+            )
+            .boxed()
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::wallet::box_selector::BoxSelection;
+    use crate::wallet::box_selector::SimpleBoxSelector;
+
+    /// Selector that "forgets" the change
+    struct NoChangeSelector {}
+
+    impl BoxSelector<ErgoBox> for NoChangeSelector {
+        fn select(
+            &self,
+            inputs: Vec<ErgoBox>,
+            target_balance: BoxValue,
+            target_tokens: &[Token],
+        ) -> Result<BoxSelection<ErgoBox>, BoxSelectorError> {
+            let selection =
+                SimpleBoxSelector::new().select(inputs, target_balance, target_tokens)?;
+            Ok(BoxSelection {
+                boxes: selection.boxes,
+                change_boxes: vec![],
+            })
+        }
+    }
+
+    proptest! {
+
+        #![proptest_config(ProptestConfig::with_cases(16))]
+
+        #[test]
+        fn simple_box_selector(scenario in any::<WalletScenario>()) {
+            let tx = check_tx_invariants(&SimpleBoxSelector::new(), &scenario);
+            prop_assert!(tx.is_ok(), "{:?}", tx);
+        }
+
+        #[test]
+        fn lost_change(scenario in any::<WalletScenario>()) {
+            let res = check_tx_invariants(&NoChangeSelector {}, &scenario);
+            prop_assert!(
+                matches!(res, Err(TxInvariantViolation::ValueNotConserved { .. })),
+                "{:?}",
+                res
+            );
+        }
+
+        #[test]
+        fn unknown_key(scenario in any::<WalletScenario>(), other_key in any::<DlogProverInput>()) {
+            let scenario = WalletScenario {
+                secrets: vec![SecretKey::DlogSecretKey(other_key)],
+                ..scenario
+            };
+            let res = check_tx_invariants(&SimpleBoxSelector::new(), &scenario);
+            prop_assert!(matches!(res, Err(TxInvariantViolation::WalletError(_))), "{:?}", res);
+        }
+    }
+}
@@ -0,0 +1,260 @@
+//! Token verification lists (published by the asset registries and used by the wallets to flag
+//! the verified and the scam tokens)
+//!
+//! The list is a UTF-8 text, one token per line:
+//! ```text
+//! # comments and empty lines are ignored
+//! <token id (Base16)> <status: verified | scam> [name]
+//! ```
+//! The list is signed by the registry key (a signature of the list bytes as is, see
+//! [`sign_token_list`]) and the signature is distributed alongside the list.
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+use ergotree_interpreter::sigma_protocol::verifier::verify_signature;
+use ergotree_interpreter::sigma_protocol::verifier::VerifierError;
+use ergotree_ir::chain::digest32::Digest32;
+use ergotree_ir::chain::token::TokenId;
+use ergotree_ir::sigma_protocol::sigma_boolean::ProveDlog;
+use thiserror::Error;
+
+use super::Wallet;
+use super::WalletError;
+
+/// Verification status of a token
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum TokenStatus {
+    /// Token is verified by the registry
+    Verified,
+    /// Token is flagged as a scam (e.g. impersonates a verified token)
+    Scam,
+    /// Token is not in the list
+    Unknown,
+}
+
+/// Token verification list entry
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct TokenVerificationEntry {
+    /// Verification status
+    pub status: TokenStatus,
+    /// Token name (as verified by the registry)
+    pub name: Option<String>,
+}
+
+/// Errors of the token verification list loading
+#[derive(Error, PartialEq, Eq, Debug, Clone)]
+pub enum TokenVerificationError {
+    /// List is not a valid UTF-8 text
+    #[error("Token list is not a valid UTF-8 text")]
+    InvalidUtf8,
+    /// Invalid line in the list
+    #[error("Invalid token list line {line}: {reason}")]
+    InvalidLine {
+        /// Line number (starting from 1)
+        line: usize,
+        /// Error description
+        reason: String,
+    },
+    /// Token is listed more than once
+    #[error("Token {0:?} is listed more than once")]
+    DuplicateToken(TokenId),
+    /// Signature could not be verified
+    #[error("Signature verification error: {0}")]
+    VerifierError(#[from] VerifierError),
+    /// Signature is not valid for the list and the registry key
+    #[error("Invalid token list signature")]
+    InvalidSignature,
+}
+
+/// Token verification list
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct TokenVerification {
+    entries: HashMap<TokenId, TokenVerificationEntry>,
+}
+
+impl TokenVerification {
+    /// Parse the list (without the signature check, e.g. for a local list)
+    pub fn parse(list: &str) -> Result<Self, TokenVerificationError> {
+        let mut entries = HashMap::new();
+        for (idx, line) in list.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid_line = |reason: String| TokenVerificationError::InvalidLine {
+                line: idx + 1,
+                reason,
+            };
+            let (id, rest) = split_word(line);
+            let (status, name) = split_word(rest);
+            let token_id: TokenId = Digest32::try_from(id.to_string())
+                .map_err(|e| invalid_line(format!("invalid token id: {}", e)))?
+                .into();
+            let status = match status {
+                "verified" => TokenStatus::Verified,
+                "scam" => TokenStatus::Scam,
+                "" => return Err(invalid_line("missing status".to_string())),
+                s => return Err(invalid_line(format!("unknown status {:?}", s))),
+            };
+            let name = if name.is_empty() {
+                None
+            } else {
+                Some(name.to_string())
+            };
+            if entries
+                .insert(token_id.clone(), TokenVerificationEntry { status, name })
+                .is_some()
+            {
+                return Err(TokenVerificationError::DuplicateToken(token_id));
+            }
+        }
+        Ok(TokenVerification { entries })
+    }
+
+    /// Verify the signature of the list bytes with the registry key and parse the list
+    pub fn from_signed(
+        list: &[u8],
+        signature: &[u8],
+        registry_key: &ProveDlog,
+    ) -> Result<Self, TokenVerificationError> {
+        if !verify_signature(registry_key.clone().into(), list, signature)? {
+            return Err(TokenVerificationError::InvalidSignature);
+        }
+        let list = std::str::from_utf8(list).map_err(|_| TokenVerificationError::InvalidUtf8)?;
+        TokenVerification::parse(list)
+    }
+
+    /// Verification status of the token ([`TokenStatus::Unknown`] if it's not in the list)
+    pub fn status(&self, token_id: &TokenId) -> TokenStatus {
+        self.entries
+            .get(token_id)
+            .map(|e| e.status)
+            .unwrap_or(TokenStatus::Unknown)
+    }
+
+    /// List entry of the token
+    pub fn entry(&self, token_id: &TokenId) -> Option<&TokenVerificationEntry> {
+        self.entries.get(token_id)
+    }
+
+    /// Number of the listed tokens
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if the list has no tokens
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Splits off the first word, returns the word and the rest (trimmed)
+fn split_word(s: &str) -> (&str, &str) {
+    let s = s.trim_start();
+    match s.find(char::is_whitespace) {
+        Some(idx) => (&s[..idx], s[idx..].trim()),
+        None => (s, ""),
+    }
+}
+
+/// Sign the list bytes with the registry key (for the list publishers), the secret of the
+/// registry key is kept by the wallet (e.g. in an HSM, see [`Wallet::from_secret_provider`])
+pub fn sign_token_list(
+    list: &[u8],
+    wallet: &Wallet,
+    registry_key: &ProveDlog,
+) -> Result<Vec<u8>, WalletError> {
+    wallet.sign_message(registry_key.clone().into(), list)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::wallet::secret_key::SecretKey;
+    use ergotree_interpreter::sigma_protocol::private_input::DlogProverInput;
+
+    const VERIFIED_ID: &str = "3130a82e45842aebb888742868e055e2f554ab7d92f233f2c828ed4a43793710";
+    const SCAM_ID: &str = "e7321ffb4ec5d71deb3110eb1ac09612b9cf57445acab1e0e3b1222d5b5a6c60";
+    const UNKNOWN_ID: &str = "ad62f6dd92e7dc850bc406770dfac9a943dd221a7fb440b7b2bcc7d3149c1792";
+
+    fn token_id(id: &str) -> TokenId {
+        Digest32::try_from(id.to_string()).unwrap().into()
+    }
+
+    fn list() -> String {
+        format!(
+            "# test list\n\n{} verified Test Token\n{}  scam\n",
+            VERIFIED_ID, SCAM_ID
+        )
+    }
+
+    #[test]
+    fn parse() {
+        let verification = TokenVerification::parse(&list()).unwrap();
+        assert_eq!(verification.len(), 2);
+        assert_eq!(
+            verification.status(&token_id(VERIFIED_ID)),
+            TokenStatus::Verified
+        );
+        assert_eq!(
+            verification.entry(&token_id(VERIFIED_ID)).unwrap().name,
+            Some("Test Token".to_string())
+        );
+        assert_eq!(verification.status(&token_id(SCAM_ID)), TokenStatus::Scam);
+        assert_eq!(verification.entry(&token_id(SCAM_ID)).unwrap().name, None);
+        assert_eq!(
+            verification.status(&token_id(UNKNOWN_ID)),
+            TokenStatus::Unknown
+        );
+    }
+
+    #[test]
+    fn parse_invalid() {
+        assert!(matches!(
+            TokenVerification::parse("abc verified"),
+            Err(TokenVerificationError::InvalidLine { line: 1, .. })
+        ));
+        assert!(matches!(
+            TokenVerification::parse(&format!("# header\n{}", VERIFIED_ID)),
+            Err(TokenVerificationError::InvalidLine { line: 2, .. })
+        ));
+        assert!(matches!(
+            TokenVerification::parse(&format!("{} trusted", VERIFIED_ID)),
+            Err(TokenVerificationError::InvalidLine { line: 1, .. })
+        ));
+        assert_eq!(
+            TokenVerification::parse(&format!("{} verified\n{} scam", SCAM_ID, SCAM_ID)),
+            Err(TokenVerificationError::DuplicateToken(token_id(SCAM_ID)))
+        );
+    }
+
+    #[test]
+    fn signed_list() {
+        let registry_dpi = DlogProverInput::random();
+        let public_key = registry_dpi.public_image();
+        let wallet = Wallet::from_secrets(vec![SecretKey::from(registry_dpi)]);
+        let list = list();
+        let signature = sign_token_list(list.as_bytes(), &wallet, &public_key).unwrap();
+        assert_eq!(
+            TokenVerification::from_signed(list.as_bytes(), &signature, &public_key).unwrap(),
+            TokenVerification::parse(&list).unwrap()
+        );
+
+        let tampered = list.replace("scam", "verified");
+        assert_eq!(
+            TokenVerification::from_signed(tampered.as_bytes(), &signature, &public_key),
+            Err(TokenVerificationError::InvalidSignature)
+        );
+        let other_key = DlogProverInput::random().public_image();
+        assert_eq!(
+            TokenVerification::from_signed(list.as_bytes(), &signature, &other_key),
+            Err(TokenVerificationError::InvalidSignature)
+        );
+        assert_eq!(
+            TokenVerification::from_signed(list.as_bytes(), &[], &public_key),
+            Err(TokenVerificationError::InvalidSignature)
+        );
+    }
+}
@@ -1,34 +1,54 @@
 //! Builder for an UnsignedTransaction
 
 use ergotree_ir::chain::ergo_box::box_value::BoxValueError;
+use std::collections::HashMap;
 use std::collections::HashSet;
 use std::convert::TryInto;
+use std::sync::Arc;
 
 use bounded_vec::BoundedVecOutOfBounds;
+use ergo_units::Height;
 use ergotree_interpreter::sigma_protocol;
+use ergotree_interpreter::sigma_protocol::prover::ContextExtension;
 use ergotree_interpreter::sigma_protocol::prover::ProofBytes;
 use ergotree_ir::chain::address::Address;
-use ergotree_ir::chain::address::AddressEncoder;
-use ergotree_ir::chain::address::NetworkPrefix;
 use ergotree_ir::chain::ergo_box::box_value::BoxValue;
 use ergotree_ir::chain::ergo_box::BoxId;
+use ergotree_ir::chain::ergo_box::ErgoBox;
 use ergotree_ir::chain::ergo_box::ErgoBoxCandidate;
+use ergotree_ir::chain::ergo_box::NonMandatoryRegisterId;
 use ergotree_ir::chain::token::Token;
+use ergotree_ir::chain::token::TokenAmount;
 use ergotree_ir::chain::token::TokenId;
+use ergotree_ir::ergo_tree::ErgoTree;
+use ergotree_ir::ergo_tree::ErgoTreeError;
+use ergotree_ir::mir::context_vars::context_vars;
 use ergotree_ir::serialization::{SigmaParsingError, SigmaSerializable, SigmaSerializationError};
+use ergotree_ir::types::stype::SType;
 use thiserror::Error;
 
 use crate::chain::contract::Contract;
 use crate::chain::ergo_box::box_builder::{ErgoBoxCandidateBuilder, ErgoBoxCandidateBuilderError};
+use crate::chain::network::Network;
 use crate::chain::transaction::unsigned::UnsignedTransaction;
 use crate::chain::transaction::{DataInput, Input, Transaction, UnsignedInput};
-use crate::constants::MINERS_FEE_MAINNET_ADDRESS;
 
-use super::box_selector::sum_tokens_from_boxes;
 use super::box_selector::sum_value;
+use super::box_selector::tokens_diff;
 use super::box_selector::ErgoBoxAssets;
 use super::box_selector::ErgoBoxId;
+use super::box_selector::TokenAmountsOverflow;
 use super::box_selector::{BoxSelection, BoxSelectorError};
+use super::fee_estimator::FeeEstimator;
+
+mod plugin;
+
+pub use plugin::PluginClaim;
+pub use plugin::TxBuilderPlugin;
+pub use plugin::TxBuilderPluginError;
+pub use plugin::TxDraft;
+
+use plugin::PluginStage;
 
 /// Unsigned transaction builder
 #[derive(Clone)]
@@ -36,10 +56,27 @@ pub struct TxBuilder<S: ErgoBoxAssets> {
     box_selection: BoxSelection<S>,
     data_inputs: Vec<DataInput>,
     output_candidates: Vec<ErgoBoxCandidate>,
-    current_height: u32,
+    current_height: Height,
     fee_amount: BoxValue,
     change_address: Address,
     min_change_value: BoxValue,
+    minting_token: Option<TokenSpec>,
+    fee_proposition: ErgoTree,
+    input_extensions: HashMap<usize, ContextExtension>,
+    plugins: Vec<Arc<dyn TxBuilderPlugin<S> + Send + Sync>>,
+}
+
+/// Token to mint in the transaction, as defined in <https://github.com/ergoplatform/eips/blob/master/eip-0004.md>
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct TokenSpec {
+    /// Token name (will be encoded in R4)
+    pub name: String,
+    /// Token description (will be encoded in R5)
+    pub description: String,
+    /// Number of decimals (will be encoded in R6)
+    pub decimals: usize,
+    /// Amount of the token to mint
+    pub amount: TokenAmount,
 }
 
 impl<S: ErgoBoxAssets + ErgoBoxId + Clone> TxBuilder<S> {
@@ -54,7 +91,7 @@ impl<S: ErgoBoxAssets + ErgoBoxId + Clone> TxBuilder<S> {
     pub fn new(
         box_selection: BoxSelection<S>,
         output_candidates: Vec<ErgoBoxCandidate>,
-        current_height: u32,
+        current_height: Height,
         fee_amount: BoxValue,
         change_address: Address,
         min_change_value: BoxValue,
@@ -67,6 +104,10 @@ impl<S: ErgoBoxAssets + ErgoBoxId + Clone> TxBuilder<S> {
             fee_amount,
             change_address,
             min_change_value,
+            minting_token: None,
+            fee_proposition: Network::mainnet().fee_proposition,
+            input_extensions: HashMap::new(),
+            plugins: vec![],
         }
     }
 
@@ -86,7 +127,7 @@ impl<S: ErgoBoxAssets + ErgoBoxId + Clone> TxBuilder<S> {
     }
 
     /// Get current height
-    pub fn current_height(&self) -> u32 {
+    pub fn current_height(&self) -> Height {
         self.current_height
     }
 
@@ -105,11 +146,96 @@ impl<S: ErgoBoxAssets + ErgoBoxId + Clone> TxBuilder<S> {
         self.min_change_value
     }
 
+    /// Get the proposition of the miner's fee box
+    pub fn fee_proposition(&self) -> ErgoTree {
+        self.fee_proposition.clone()
+    }
+
+    /// Use the miner's fee proposition of the given network (mainnet by default)
+    pub fn set_network(&mut self, network: &Network) {
+        self.fee_proposition = network.fee_proposition.clone();
+    }
+
     /// Set transaction's data inputs
     pub fn set_data_inputs(&mut self, data_inputs: Vec<DataInput>) {
         self.data_inputs = data_inputs;
     }
 
+    /// Get the context extension of the input with the given index (in
+    /// [`BoxSelection::boxes`]), if set
+    pub fn input_extension(&self, input_idx: usize) -> Option<ContextExtension> {
+        self.input_extensions.get(&input_idx).cloned()
+    }
+
+    /// Get the token to be minted
+    pub fn minting_token(&self) -> Option<TokenSpec> {
+        self.minting_token.clone()
+    }
+
+    /// Mint a token in the first output candidate (issuance box) according to EIP-4.
+    /// The token id is the id of the first input box, the token name, description and number of
+    /// decimals are placed in R4-R6 of the issuance box (these registers must be empty and the box
+    /// must have no other tokens, otherwise [`TxBuilder::build`] fails). Only one token can be
+    /// minted per transaction.
+    pub fn mint_token(&mut self, token_spec: TokenSpec) {
+        self.minting_token = Some(token_spec);
+    }
+
+    /// Register the plugin (see [`TxBuilderPlugin`]), fails if a plugin with the same name is
+    /// already registered or the plugin claims the part of the transaction claimed by a
+    /// registered plugin.
+    /// The context extensions set by the plugin's hooks ([`TxDraft::input_extensions`]) are put
+    /// into the transaction as is, they are not checked against the input scripts as in
+    /// [`TxBuilder::set_input_extension`] (the inputs' scripts are not known to the builder for
+    /// any box type), the plugin is trusted to set the variables the scripts expect.
+    pub fn add_plugin(
+        &mut self,
+        plugin: Arc<dyn TxBuilderPlugin<S> + Send + Sync>,
+    ) -> Result<(), TxBuilderError> {
+        plugin::check_registration(&self.plugins, plugin.as_ref())?;
+        self.plugins.push(plugin);
+        Ok(())
+    }
+
+    /// Get the names of the registered plugins (in the order of registration)
+    pub fn plugins(&self) -> Vec<String> {
+        self.plugins.iter().map(|p| p.name().to_string()).collect()
+    }
+
+    /// Set the miner's fee recommended by the fee estimator for the given percentile (0-100) of
+    /// the recent fee rates and the estimated transaction size (but not less than the minimal
+    /// value of the miner's fee box). The difference with the current fee is taken from (or
+    /// returned to) the first change box. Returns the new fee.
+    pub fn set_fee_auto(
+        &mut self,
+        fee_estimator: &FeeEstimator,
+        percentile: u8,
+    ) -> Result<BoxValue, TxBuilderError> {
+        let tx_size = self.estimate_tx_size_bytes()?;
+        let estimated_fee = fee_estimator
+            .recommended_fee(tx_size, percentile)
+            .ok_or_else(|| TxBuilderError::InvalidArgs("fee estimator has no data".to_string()))?;
+        let min_fee = ErgoBoxCandidateBuilder::from(self.miner_fee_box(estimated_fee)?)
+            .calc_min_box_value()?;
+        let fee = if estimated_fee > min_fee {
+            estimated_fee
+        } else {
+            min_fee
+        };
+        let change_box = self.box_selection.change_boxes.first_mut().ok_or_else(|| {
+            TxBuilderError::InvalidArgs("no change box to adjust for the new fee".to_string())
+        })?;
+        let available = change_box.value.checked_add(&self.fee_amount)?;
+        if fee > available {
+            return Err(TxBuilderError::NotEnoughCoins(
+                *fee.as_u64() - *available.as_u64(),
+            ));
+        }
+        change_box.value = available.checked_sub(&fee)?;
+        self.fee_amount = fee;
+        Ok(fee)
+    }
+
     /// Estimated serialized transaction size in bytes after signing (assuming P2PK box spending)
     pub fn estimate_tx_size_bytes(&self) -> Result<usize, TxBuilderError> {
         let tx = self.build_tx()?;
@@ -150,16 +276,78 @@ impl<S: ErgoBoxAssets + ErgoBoxId + Clone> TxBuilder<S> {
                 "duplicate inputs found".to_string(),
             ));
         }
-        if self.data_inputs.len() > u16::MAX as usize {
+        let plugins = plugin::ordered(&self.plugins)?;
+        let mut draft = TxDraft {
+            inputs: self.box_selection.boxes.as_slice(),
+            data_inputs: self.data_inputs.clone(),
+            output_candidates: self.output_candidates.clone(),
+            change_boxes: self.box_selection.change_boxes.clone(),
+            input_extensions: self.input_extensions.clone(),
+            current_height: self.current_height,
+        };
+        plugin::run_stage(&plugins, PluginStage::AfterSelection, &mut draft)?;
+        if draft.data_inputs.len() > u16::MAX as usize {
             return Err(TxBuilderError::InvalidArgs(
                 "too many data inputs".to_string(),
             ));
         }
 
-        let mut output_candidates = self.output_candidates.clone();
-        let change_address_ergo_tree = Contract::pay_to_address(&self.change_address)?.ergo_tree();
-        let change_boxes: Result<Vec<ErgoBoxCandidate>, ErgoBoxCandidateBuilderError> = self
+        let first_input_box_id: TokenId = self
             .box_selection
+            .boxes
+            .first()
+            .ok_or_else(|| TxBuilderError::InvalidArgs("inputs is empty".to_string()))?
+            .box_id()
+            .into();
+        if let Some(token_spec) = &self.minting_token {
+            if draft.output_candidates.iter().any(|b| {
+                b.tokens
+                    .iter()
+                    .flat_map(|tokens| tokens.iter())
+                    .any(|t| t.token_id == first_input_box_id)
+            }) {
+                return Err(TxBuilderError::InvalidArgs(
+                    "cannot mint more than one token (already minted in output candidates)"
+                        .to_string(),
+                ));
+            }
+            let issuance_box = draft.output_candidates.first_mut().ok_or_else(|| {
+                TxBuilderError::InvalidArgs(
+                    "output_candidates is empty (no issuance box for the minted token)".to_string(),
+                )
+            })?;
+            if issuance_box.tokens.is_some() {
+                return Err(TxBuilderError::InvalidArgs(
+                    "issuance box of the minted token must have no other tokens".to_string(),
+                ));
+            }
+            for reg_id in &[
+                NonMandatoryRegisterId::R4,
+                NonMandatoryRegisterId::R5,
+                NonMandatoryRegisterId::R6,
+            ] {
+                if issuance_box.additional_registers.get(*reg_id).is_some() {
+                    return Err(TxBuilderError::InvalidArgs(format!(
+                        "register {} of the issuance box is reserved for the minted token info",
+                        String::from(*reg_id)
+                    )));
+                }
+            }
+            let mut box_builder = ErgoBoxCandidateBuilder::from(issuance_box.clone());
+            box_builder.mint_token(
+                Token {
+                    token_id: first_input_box_id.clone(),
+                    amount: token_spec.amount,
+                },
+                token_spec.name.clone(),
+                token_spec.description.clone(),
+                token_spec.decimals,
+            );
+            *issuance_box = box_builder.build()?;
+        }
+        plugin::run_stage(&plugins, PluginStage::BeforeChange, &mut draft)?;
+        let change_address_ergo_tree = Contract::pay_to_address(&self.change_address)?.ergo_tree();
+        let change_boxes: Result<Vec<ErgoBoxCandidate>, ErgoBoxCandidateBuilderError> = draft
             .change_boxes
             .iter()
             .filter(|b| b.value >= self.min_change_value)
@@ -167,7 +355,7 @@ impl<S: ErgoBoxAssets + ErgoBoxId + Clone> TxBuilder<S> {
                 let mut candidate = ErgoBoxCandidateBuilder::new(
                     b.value,
                     change_address_ergo_tree.clone(),
-                    self.current_height,
+                    self.current_height.as_u32(),
                 );
                 for token in b.tokens().into_iter().flatten() {
                     candidate.add_token(token.clone());
@@ -175,18 +363,25 @@ impl<S: ErgoBoxAssets + ErgoBoxId + Clone> TxBuilder<S> {
                 candidate.build()
             })
             .collect();
-        output_candidates.append(&mut change_boxes?);
+        draft.output_candidates.append(&mut change_boxes?);
 
         // Ergo transactions need at least one output
         // but that single output could just be the change (ex: if you want to send all ERG to a single address)
-        if output_candidates.is_empty() {
+        if draft.output_candidates.is_empty() {
             return Err(TxBuilderError::InvalidArgs(
                 "output_candidates is empty".to_string(),
             ));
         }
         // add miner's fee
-        let miner_fee_box = new_miner_fee_box(self.fee_amount, self.current_height)?;
-        output_candidates.push(miner_fee_box);
+        let miner_fee_box = self.miner_fee_box(self.fee_amount)?;
+        draft.output_candidates.push(miner_fee_box);
+        plugin::run_stage(&plugins, PluginStage::AfterAssembly, &mut draft)?;
+        let TxDraft {
+            data_inputs,
+            output_candidates,
+            input_extensions,
+            ..
+        } = draft;
         if output_candidates.len() > Transaction::MAX_OUTPUTS_COUNT {
             return Err(TxBuilderError::InvalidArgs("too many outputs".to_string()));
         }
@@ -198,66 +393,125 @@ impl<S: ErgoBoxAssets + ErgoBoxId + Clone> TxBuilder<S> {
                 total_output_value - total_input_value,
             ));
         }
-        // check that inputs have enough tokens
-        let input_tokens = sum_tokens_from_boxes(self.box_selection.boxes.as_slice());
-        let output_tokens = sum_tokens_from_boxes(output_candidates.as_slice());
-        let first_input_box_id: TokenId = self.box_selection.boxes.first().unwrap().box_id().into();
-        let output_tokens_len = output_tokens.len();
-        let output_tokens_without_minted: Vec<Token> = output_tokens
+        // check that inputs have enough tokens (only the token with the id of the first input can
+        // be minted, burning is allowed)
+        let diff = tokens_diff(
+            output_candidates.as_slice(),
+            self.box_selection.boxes.as_slice(),
+        )?;
+        let not_enough_tokens: Vec<Token> = diff
+            .minted
+            .to_tokens()
             .into_iter()
-            .map(Token::from)
             .filter(|t| t.token_id != first_input_box_id)
             .collect();
-        if output_tokens_len - output_tokens_without_minted.len() > 1 {
-            return Err(TxBuilderError::InvalidArgs(
-                "cannot mint more than one token".to_string(),
-            ));
+        if !not_enough_tokens.is_empty() {
+            return Err(TxBuilderError::NotEnoughTokens(not_enough_tokens));
         }
-        output_tokens_without_minted
-            .iter()
-            .try_for_each(|output_token| {
-                match input_tokens.get(&output_token.token_id).cloned() {
-                    Some(input_token_amount) if input_token_amount >= output_token.amount => Ok(()),
-                    _ => Err(TxBuilderError::NotEnoughTokens(vec![output_token.clone()])),
-                }
-            })?;
 
         Ok(UnsignedTransaction::new(
             self.box_selection
                 .boxes
-                .clone()
-                .into_iter()
-                .map(UnsignedInput::from)
+                .iter()
+                .enumerate()
+                .map(|(idx, b)| {
+                    UnsignedInput::new(
+                        b.box_id(),
+                        input_extensions
+                            .get(&idx)
+                            .cloned()
+                            .unwrap_or_else(ContextExtension::empty),
+                    )
+                })
                 .collect::<Vec<UnsignedInput>>()
                 .try_into()?,
-            self.data_inputs.clone().try_into().ok(),
+            data_inputs.try_into().ok(),
             output_candidates.try_into()?,
         )?)
     }
 
+    fn miner_fee_box(&self, fee_amount: BoxValue) -> Result<ErgoBoxCandidate, TxBuilderError> {
+        Ok(ErgoBoxCandidateBuilder::new(
+            fee_amount,
+            self.fee_proposition.clone(),
+            self.current_height.as_u32(),
+        )
+        .build()?)
+    }
+
     /// Build the unsigned transaction
     pub fn build(self) -> Result<UnsignedTransaction, TxBuilderError> {
         self.build_tx()
     }
 }
 
+impl TxBuilder<ErgoBox> {
+    /// Set the context extension (variables for `getVar`) of the input with the given index (in
+    /// [`BoxSelection::boxes`]), which is put into the unsigned transaction.
+    /// Fails if any variable is not read by the input's script or its type differs from the type
+    /// expected by the script (see [`context_vars`]).
+    /// If the script has `DeserializeContext` or `DeserializeRegister` nodes (see
+    /// [`ErgoTree::has_deserialize`]), the deserialized script may read any variable, so only the
+    /// types of the variables read by the input's script itself are checked.
+    pub fn set_input_extension(
+        &mut self,
+        input_idx: usize,
+        extension: ContextExtension,
+    ) -> Result<(), TxBuilderError> {
+        let input = self
+            .box_selection
+            .boxes
+            .get(input_idx)
+            .ok_or(InputExtensionError::InputIndexOutOfBounds(input_idx))?;
+        let expr = input
+            .ergo_tree
+            .proposition()
+            .map_err(|e| InputExtensionError::ScriptParsingError(input_idx, e))?;
+        let has_deserialize = input
+            .ergo_tree
+            .has_deserialize()
+            .map_err(|e| InputExtensionError::ScriptParsingError(input_idx, e))?;
+        let script_vars = context_vars(expr.as_ref());
+        for (var_id, value) in extension.values.iter() {
+            let var_id = *var_id;
+            let mut var_types = script_vars
+                .iter()
+                .filter(|v| v.var_id == var_id)
+                .map(|v| &v.var_tpe)
+                .peekable();
+            if var_types.peek().is_none() && !has_deserialize {
+                return Err(InputExtensionError::UnusedVar { input_idx, var_id }.into());
+            }
+            if let Some(expected) = var_types.find(|tpe| **tpe != value.tpe) {
+                return Err(InputExtensionError::TypeMismatch {
+                    input_idx,
+                    var_id,
+                    expected: expected.clone(),
+                    actual: value.tpe.clone(),
+                }
+                .into());
+            }
+        }
+        self.input_extensions.insert(input_idx, extension);
+        Ok(())
+    }
+}
+
 /// Suggested transaction fee (1100000 nanoERGs, semi-default value used across wallets and dApps as of Oct 2020)
 #[allow(non_snake_case)]
+#[allow(clippy::unwrap_used)]
 pub fn SUGGESTED_TX_FEE() -> BoxValue {
+    // the constant is in the box value bounds
     BoxValue::new(1100000u64).unwrap()
 }
 
-/// Create a box with miner's contract and a given value
+/// Create a box with miner's contract and a given value (the miner's fee script is the same on
+/// all networks, see [`Network::miner_fee_box`] for a custom chain)
 pub fn new_miner_fee_box(
     fee_amount: BoxValue,
     creation_height: u32,
 ) -> Result<ErgoBoxCandidate, ErgoBoxCandidateBuilderError> {
-    let address_encoder = AddressEncoder::new(NetworkPrefix::Mainnet);
-    let miner_fee_address = address_encoder
-        .parse_address_from_str(MINERS_FEE_MAINNET_ADDRESS)
-        .unwrap();
-    let ergo_tree = miner_fee_address.script().unwrap();
-    ErgoBoxCandidateBuilder::new(fee_amount, ergo_tree, creation_height).build()
+    Network::mainnet().miner_fee_box(fee_amount, creation_height)
 }
 
 /// Errors of TxBuilder
@@ -290,9 +544,52 @@ pub enum TxBuilderError {
     /// Invalid Tx input count
     #[error("Invalid tx inputs count: {0}")]
     InvalidInputsCount(#[from] BoundedVecOutOfBounds),
+    /// Total amount of a token in the inputs or outputs overflows
+    #[error("Token amounts overflow: {0}")]
+    TokenAmountsOverflow(#[from] TokenAmountsOverflow),
+    /// Context extension does not match the input script
+    #[error("Input context extension error: {0}")]
+    InputExtensionError(#[from] InputExtensionError),
+    /// Plugin registration or hook error
+    #[error("Plugin error: {0}")]
+    PluginError(#[from] TxBuilderPluginError),
+}
+
+/// Errors of the input context extension validation (see [`TxBuilder::set_input_extension`])
+#[derive(Error, PartialEq, Eq, Debug, Clone)]
+pub enum InputExtensionError {
+    /// Input index is out of bounds of the selected boxes
+    #[error("Input index {0} is out of bounds")]
+    InputIndexOutOfBounds(usize),
+    /// Failed to parse the input script
+    #[error("Failed to parse the script of input {0}: {1}")]
+    ScriptParsingError(usize, ErgoTreeError),
+    /// Variable is not read by the input script
+    #[error("Variable {var_id} is not used by the script of input {input_idx}")]
+    UnusedVar {
+        /// Input index
+        input_idx: usize,
+        /// Variable id
+        var_id: u8,
+    },
+    /// Variable type differs from the type expected by the input script
+    #[error(
+        "Variable {var_id} of input {input_idx} is expected to be {expected:?}, got {actual:?}"
+    )]
+    TypeMismatch {
+        /// Input index
+        input_idx: usize,
+        /// Variable id
+        var_id: u8,
+        /// Type expected by the script
+        expected: SType,
+        /// Type of the variable value
+        actual: SType,
+    },
 }
 
 #[cfg(test)]
+#[allow(clippy::unwrap_used)]
 mod tests {
 
     use std::convert::TryInto;
@@ -308,6 +605,8 @@ mod tests {
     use sigma_test_util::force_any_val;
     use sigma_test_util::force_any_val_with;
 
+    use crate::wallet::box_selector::sum_tokens_from_boxes;
+    use crate::wallet::box_selector::ErgoBoxAssetsData;
     use crate::wallet::box_selector::{BoxSelector, SimpleBoxSelector};
 
     use super::*;
@@ -321,7 +620,7 @@ mod tests {
         let r = TxBuilder::new(
             box_selection,
             vec![force_any_val::<ErgoBoxCandidate>()],
-            1,
+            Height::new(1),
             force_any_val::<BoxValue>(),
             force_any_val::<Address>(),
             BoxValue::SAFE_USER_MIN,
@@ -339,7 +638,7 @@ mod tests {
         let r = TxBuilder::new(
             box_selection,
             vec![force_any_val::<ErgoBoxCandidate>()],
-            1,
+            Height::new(1),
             force_any_val::<BoxValue>(),
             force_any_val::<Address>(),
             BoxValue::SAFE_USER_MIN,
@@ -356,7 +655,7 @@ mod tests {
                 .select(inputs, BoxValue::MIN, &[])
                 .unwrap(),
             outputs,
-            1,
+            Height::new(1),
             force_any_val::<BoxValue>(),
             force_any_val::<Address>(),
             BoxValue::SAFE_USER_MIN,
@@ -398,7 +697,7 @@ mod tests {
         let tx_builder = TxBuilder::new(
             box_selection,
             outputs,
-            0,
+            Height::new(0),
             tx_fee,
             force_any_val::<Address>(),
             BoxValue::SAFE_USER_MIN,
@@ -410,6 +709,216 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_input_extension() {
+        use ergotree_ir::mir::bool_to_sigma::BoolToSigmaProp;
+        use ergotree_ir::mir::constant::Constant;
+        use ergotree_ir::mir::expr::Expr;
+        use ergotree_ir::mir::get_var::GetVar;
+        use ergotree_ir::mir::option_get_or_else::OptionGetOrElse;
+        use std::convert::TryFrom;
+
+        // sigmaProp(getVar[Boolean](1).getOrElse(false))
+        let script: Expr = BoolToSigmaProp {
+            input: Box::new(
+                OptionGetOrElse::new(
+                    GetVar {
+                        var_id: 1,
+                        var_tpe: SType::SBoolean,
+                    }
+                    .into(),
+                    false.into(),
+                )
+                .unwrap()
+                .into(),
+            ),
+        }
+        .into();
+        let input_box = ErgoBox::new(
+            10000000i64.try_into().unwrap(),
+            ErgoTree::try_from(script).unwrap(),
+            None,
+            NonMandatoryRegisters::empty(),
+            1,
+            force_any_val::<TxId>(),
+            0,
+        )
+        .unwrap();
+        let box_selection = SimpleBoxSelector::new()
+            .select(vec![input_box], BoxValue::SAFE_USER_MIN, &[])
+            .unwrap();
+        let mut tx_builder = TxBuilder::new(
+            box_selection,
+            vec![],
+            Height::new(0),
+            BoxValue::SAFE_USER_MIN,
+            force_any_val::<Address>(),
+            BoxValue::SAFE_USER_MIN,
+        );
+        let extension = |var_id: u8, value: Constant| {
+            let mut ext = ContextExtension::empty();
+            ext.values.insert(var_id, value);
+            ext
+        };
+
+        assert_eq!(
+            tx_builder.set_input_extension(1, extension(1, true.into())),
+            Err(TxBuilderError::InputExtensionError(
+                InputExtensionError::InputIndexOutOfBounds(1)
+            ))
+        );
+        assert_eq!(
+            tx_builder.set_input_extension(0, extension(2, true.into())),
+            Err(TxBuilderError::InputExtensionError(
+                InputExtensionError::UnusedVar {
+                    input_idx: 0,
+                    var_id: 2
+                }
+            ))
+        );
+        assert_eq!(
+            tx_builder.set_input_extension(0, extension(1, 1i32.into())),
+            Err(TxBuilderError::InputExtensionError(
+                InputExtensionError::TypeMismatch {
+                    input_idx: 0,
+                    var_id: 1,
+                    expected: SType::SBoolean,
+                    actual: SType::SInt,
+                }
+            ))
+        );
+        assert_eq!(tx_builder.input_extension(0), None);
+        let tx = tx_builder.clone().build().unwrap();
+        assert_eq!(
+            tx.inputs.get(0).unwrap().extension,
+            ContextExtension::empty()
+        );
+
+        tx_builder
+            .set_input_extension(0, extension(1, true.into()))
+            .unwrap();
+        assert_eq!(
+            tx_builder.input_extension(0),
+            Some(extension(1, true.into()))
+        );
+        let tx = tx_builder.build().unwrap();
+        assert_eq!(
+            tx.inputs.get(0).unwrap().extension,
+            extension(1, true.into())
+        );
+    }
+
+    #[test]
+    fn test_input_extension_with_deserialize() {
+        use ergotree_ir::mir::bool_to_sigma::BoolToSigmaProp;
+        use ergotree_ir::mir::constant::Constant;
+        use ergotree_ir::mir::deserialize_context::DeserializeContext;
+        use ergotree_ir::mir::expr::Expr;
+        use std::convert::TryFrom;
+
+        // sigmaProp(deserializeContext[Boolean](1)), the deserialized script may read any variable
+        let script: Expr = BoolToSigmaProp {
+            input: Box::new(
+                DeserializeContext {
+                    tpe: SType::SBoolean,
+                    id: 1,
+                }
+                .into(),
+            ),
+        }
+        .into();
+        let input_box = ErgoBox::new(
+            10000000i64.try_into().unwrap(),
+            ErgoTree::try_from(script).unwrap(),
+            None,
+            NonMandatoryRegisters::empty(),
+            1,
+            force_any_val::<TxId>(),
+            0,
+        )
+        .unwrap();
+        let box_selection = SimpleBoxSelector::new()
+            .select(vec![input_box], BoxValue::SAFE_USER_MIN, &[])
+            .unwrap();
+        let mut tx_builder = TxBuilder::new(
+            box_selection,
+            vec![],
+            Height::new(0),
+            BoxValue::SAFE_USER_MIN,
+            force_any_val::<Address>(),
+            BoxValue::SAFE_USER_MIN,
+        );
+        let extension = |var_id: u8, value: Constant| {
+            let mut ext = ContextExtension::empty();
+            ext.values.insert(var_id, value);
+            ext
+        };
+
+        assert_eq!(
+            tx_builder.set_input_extension(0, extension(1, true.into())),
+            Err(TxBuilderError::InputExtensionError(
+                InputExtensionError::TypeMismatch {
+                    input_idx: 0,
+                    var_id: 1,
+                    expected: SType::SColl(SType::SByte.into()),
+                    actual: SType::SBoolean,
+                }
+            ))
+        );
+        tx_builder
+            .set_input_extension(0, extension(2, 1i32.into()))
+            .unwrap();
+        assert_eq!(
+            tx_builder.input_extension(0),
+            Some(extension(2, 1i32.into()))
+        );
+    }
+
+    #[test]
+    fn test_custom_fee_proposition() {
+        let input_box = ErgoBox::new(
+            100000000i64.try_into().unwrap(),
+            force_any_val::<ErgoTree>(),
+            None,
+            NonMandatoryRegisters::empty(),
+            1,
+            force_any_val::<TxId>(),
+            0,
+        )
+        .unwrap();
+        let tx_fee = BoxValue::SAFE_USER_MIN;
+        let out_box_value = BoxValue::SAFE_USER_MIN;
+        let target_balance = out_box_value.checked_add(&tx_fee).unwrap();
+        let box_selection = SimpleBoxSelector::new()
+            .select(vec![input_box], target_balance, &[])
+            .unwrap();
+        let out_box = ErgoBoxCandidateBuilder::new(out_box_value, force_any_val::<ErgoTree>(), 0)
+            .build()
+            .unwrap();
+        let mut tx_builder = TxBuilder::new(
+            box_selection,
+            vec![out_box],
+            Height::new(0),
+            tx_fee,
+            force_any_val::<Address>(),
+            BoxValue::SAFE_USER_MIN,
+        );
+        assert_eq!(
+            tx_builder.fee_proposition(),
+            Network::mainnet().fee_proposition
+        );
+        let network = Network {
+            fee_proposition: force_any_val::<ErgoTree>(),
+            ..Network::devnet()
+        };
+        tx_builder.set_network(&network);
+        let tx = tx_builder.build().unwrap();
+        assert!(tx
+            .output_candidates
+            .iter()
+            .any(|b| b.ergo_tree == network.fee_proposition && b.value == tx_fee));
+    }
+
     #[test]
     fn test_mint_token() {
         let input_box = ErgoBox::new(
@@ -445,7 +954,7 @@ mod tests {
         let tx_builder = TxBuilder::new(
             box_selection,
             outputs,
-            0,
+            Height::new(0),
             tx_fee,
             force_any_val::<Address>(),
             BoxValue::SAFE_USER_MIN,
@@ -464,6 +973,125 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_tx_builder_mint_token() {
+        let input_box = ErgoBox::new(
+            100000000i64.try_into().unwrap(),
+            force_any_val::<ErgoTree>(),
+            None,
+            NonMandatoryRegisters::empty(),
+            1,
+            force_any_val::<TxId>(),
+            0,
+        )
+        .unwrap();
+        let token_id = TokenId::from(input_box.box_id());
+        let out_box_value = BoxValue::SAFE_USER_MIN;
+        let out_box = ErgoBoxCandidateBuilder::new(out_box_value, force_any_val::<ErgoTree>(), 0)
+            .build()
+            .unwrap();
+        let tx_fee = BoxValue::SAFE_USER_MIN;
+        let target_balance = out_box_value.checked_add(&tx_fee).unwrap();
+        let box_selection = SimpleBoxSelector::new()
+            .select(vec![input_box], target_balance, vec![].as_slice())
+            .unwrap();
+        let mut tx_builder = TxBuilder::new(
+            box_selection,
+            vec![out_box],
+            Height::new(0),
+            tx_fee,
+            force_any_val::<Address>(),
+            BoxValue::SAFE_USER_MIN,
+        );
+        let token_spec = TokenSpec {
+            name: "TKN".to_string(),
+            description: "token desc".to_string(),
+            decimals: 2,
+            amount: 100.try_into().unwrap(),
+        };
+        tx_builder.mint_token(token_spec.clone());
+        let tx = tx_builder.clone().build().unwrap();
+        let issuance_box = tx.output_candidates.get(0).unwrap();
+        let minted_token = issuance_box.tokens().unwrap().first().clone();
+        assert_eq!(minted_token.token_id, token_id);
+        assert_eq!(minted_token.amount, token_spec.amount);
+        let regs = &issuance_box.additional_registers;
+        assert_eq!(
+            regs.get(NonMandatoryRegisterId::R4).cloned(),
+            Some("TKN".as_bytes().to_vec().into())
+        );
+        assert_eq!(
+            regs.get(NonMandatoryRegisterId::R5).cloned(),
+            Some("token desc".as_bytes().to_vec().into())
+        );
+        assert_eq!(
+            regs.get(NonMandatoryRegisterId::R6).cloned(),
+            Some("2".as_bytes().to_vec().into())
+        );
+
+        // the token is minted in the output candidates already
+        let mut box_builder =
+            ErgoBoxCandidateBuilder::new(out_box_value, force_any_val::<ErgoTree>(), 0);
+        box_builder.add_token(Token {
+            token_id,
+            amount: 1.try_into().unwrap(),
+        });
+        let mut output_candidates = tx_builder.output_candidates();
+        output_candidates.push(box_builder.build().unwrap());
+        let mut tx_builder = TxBuilder::new(
+            tx_builder.box_selection(),
+            output_candidates,
+            Height::new(0),
+            tx_fee,
+            force_any_val::<Address>(),
+            BoxValue::SAFE_USER_MIN,
+        );
+        tx_builder.mint_token(token_spec.clone());
+        assert!(
+            tx_builder.build().is_err(),
+            "expected error on minting more than one token"
+        );
+
+        // the issuance box has a register reserved for the token info
+        let mut box_builder =
+            ErgoBoxCandidateBuilder::new(out_box_value, force_any_val::<ErgoTree>(), 0);
+        box_builder.set_register_value(NonMandatoryRegisterId::R5, 1i32.into());
+        let mut tx_builder = TxBuilder::new(
+            tx_builder.box_selection(),
+            vec![box_builder.build().unwrap()],
+            Height::new(0),
+            tx_fee,
+            force_any_val::<Address>(),
+            BoxValue::SAFE_USER_MIN,
+        );
+        tx_builder.mint_token(token_spec.clone());
+        assert!(
+            tx_builder.build().is_err(),
+            "expected error on minting in a box with R5 set"
+        );
+
+        // the issuance box has other tokens
+        let mut box_builder =
+            ErgoBoxCandidateBuilder::new(out_box_value, force_any_val::<ErgoTree>(), 0);
+        box_builder.add_token(Token {
+            token_id: force_any_val_with::<TokenId>(ArbTokenIdParam::Arbitrary),
+            amount: 1.try_into().unwrap(),
+        });
+        let mut tx_builder = TxBuilder::new(
+            tx_builder.box_selection(),
+            vec![box_builder.build().unwrap()],
+            Height::new(0),
+            tx_fee,
+            force_any_val::<Address>(),
+            BoxValue::SAFE_USER_MIN,
+        );
+        tx_builder.mint_token(token_spec);
+        assert!(
+            tx_builder.build().is_err(),
+            "expected error on minting in a box with other tokens"
+        );
+    }
+
     #[test]
     fn test_tokens_balance_error() {
         let input_box = force_any_val_with::<ErgoBox>(
@@ -489,7 +1117,7 @@ mod tests {
         let tx_builder = TxBuilder::new(
             box_selection,
             outputs,
-            0,
+            Height::new(0),
             tx_fee,
             force_any_val::<Address>(),
             BoxValue::SAFE_USER_MIN,
@@ -522,7 +1150,7 @@ mod tests {
         let tx_builder = TxBuilder::new(
             box_selection,
             outputs,
-            0,
+            Height::new(0),
             tx_fee,
             force_any_val::<Address>(),
             BoxValue::SAFE_USER_MIN,
@@ -557,7 +1185,7 @@ mod tests {
                 change_boxes: vec![],
             },
             outputs,
-            0,
+            Height::new(0),
             tx_fee,
             force_any_val::<Address>(),
             BoxValue::SAFE_USER_MIN,
@@ -565,6 +1193,66 @@ mod tests {
         assert!(tx_builder.estimate_tx_size_bytes().unwrap() > 0);
     }
 
+    #[test]
+    fn test_set_fee_auto() {
+        let input = ErgoBox::new(
+            1000000000i64.try_into().unwrap(),
+            force_any_val::<ErgoTree>(),
+            None,
+            NonMandatoryRegisters::empty(),
+            1,
+            force_any_val::<TxId>(),
+            0,
+        )
+        .unwrap();
+        let tx_fee = super::SUGGESTED_TX_FEE();
+        let out_box_value = BoxValue::new(100000000).unwrap();
+        let out_box = ErgoBoxCandidateBuilder::new(out_box_value, force_any_val::<ErgoTree>(), 0)
+            .build()
+            .unwrap();
+        let change_value = input
+            .value
+            .checked_sub(&out_box_value)
+            .unwrap()
+            .checked_sub(&tx_fee)
+            .unwrap();
+        let mut tx_builder = TxBuilder::new(
+            BoxSelection {
+                boxes: vec![input.clone()],
+                change_boxes: vec![ErgoBoxAssetsData {
+                    value: change_value,
+                    tokens: None,
+                }],
+            },
+            vec![out_box],
+            Height::new(0),
+            tx_fee,
+            force_any_val::<Address>(),
+            BoxValue::SAFE_USER_MIN,
+        );
+        assert!(tx_builder.set_fee_auto(&FeeEstimator::new(), 50).is_err());
+
+        let recent_tx = Transaction::new(
+            vec![force_any_val::<Input>()].try_into().unwrap(),
+            None,
+            vec![new_miner_fee_box(BoxValue::new(5000000).unwrap(), 0).unwrap()]
+                .try_into()
+                .unwrap(),
+        )
+        .unwrap();
+        let fee_estimator = FeeEstimator::from_transactions(&[recent_tx]).unwrap();
+        let tx_size = tx_builder.estimate_tx_size_bytes().unwrap();
+        let fee = tx_builder.set_fee_auto(&fee_estimator, 50).unwrap();
+        assert_eq!(
+            *fee.as_u64(),
+            fee_estimator.fee_per_byte(50).unwrap().as_u64() * tx_size as u64
+        );
+        assert_eq!(tx_builder.fee_amount(), fee);
+        let tx = tx_builder.build().unwrap();
+        let outputs_sum = checked_sum(tx.output_candidates.iter().map(|b| b.value)).unwrap();
+        assert_eq!(outputs_sum, input.value);
+    }
+
     proptest! {
 
         #![proptest_config(ProptestConfig::with_cases(16))]
@@ -575,7 +1263,7 @@ mod tests {
                          change_address in any::<Address>(),
                          miners_fee in any_with::<BoxValue>((BoxValue::MIN_RAW * 100..BoxValue::MIN_RAW * 200).into()),
                          data_inputs in vec(any::<DataInput>(), 0..2)) {
-            prop_assume!(sum_tokens_from_boxes(outputs.as_slice()).is_empty());
+            prop_assume!(sum_tokens_from_boxes(outputs.as_slice()).unwrap().is_empty());
             let min_change_value = BoxValue::SAFE_USER_MIN;
             let all_outputs = checked_sum(outputs.iter().map(|b| b.value)).unwrap()
                                                                              .checked_add(&miners_fee)
@@ -587,7 +1275,7 @@ mod tests {
             let mut tx_builder = TxBuilder::new(
                 SimpleBoxSelector::new().select(inputs.clone(), total_output_value, &[]).unwrap(),
                 outputs.clone(),
-                1,
+                Height::new(1),
                 miners_fee,
                 change_address.clone(),
                 min_change_value,
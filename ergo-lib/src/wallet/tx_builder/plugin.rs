@@ -0,0 +1,566 @@
+//! Plugins of the transaction builder: hooks for the protocol SDKs (oracle pools, DEX, babel
+//! fees, etc.) to add their outputs, data inputs and context extensions to the transaction built
+//! by [`super::TxBuilder`] instead of assembling the transaction themselves
+//!
+//! The hooks are called on the [`TxDraft`] in the stages of the build:
+//! - [`TxBuilderPlugin::after_selection`] - the outputs and data inputs given to the builder
+//! (before the token minting);
+//! - [`TxBuilderPlugin::before_change`] - the outputs after the token minting and the change of
+//! the box selection, before the change boxes are made;
+//! - [`TxBuilderPlugin::after_assembly`] - all the outputs (including the change boxes and the
+//! miner's fee box), before the balance checks.
+//!
+//! In each stage the plugins are called in the order of registration, except a plugin is called
+//! after the plugins it depends on (see [`TxBuilderPlugin::run_after`]). A plugin can claim parts
+//! of the transaction (see [`PluginClaim`]): plugins with the same claims cannot be registered in
+//! one builder and a hook changing a part claimed by another plugin fails the build.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use ergo_units::Height;
+use ergotree_interpreter::sigma_protocol::prover::ContextExtension;
+use ergotree_ir::chain::ergo_box::ErgoBoxCandidate;
+use ergotree_ir::chain::token::TokenId;
+use thiserror::Error;
+
+use crate::chain::transaction::DataInput;
+use crate::wallet::box_selector::ErgoBoxAssets;
+use crate::wallet::box_selector::ErgoBoxAssetsData;
+
+/// Transaction being built, passed to the plugin hooks
+pub struct TxDraft<'a, S: ErgoBoxAssets> {
+    /// Selected boxes to spend (in the order of the transaction inputs)
+    pub inputs: &'a [S],
+    /// Data inputs
+    pub data_inputs: Vec<DataInput>,
+    /// Outputs (see the stages in the module docs)
+    pub output_candidates: Vec<ErgoBoxCandidate>,
+    /// Change of the box selection, the change boxes are made from it after the
+    /// [`TxBuilderPlugin::before_change`] stage (changes in the later stage are ignored)
+    pub change_boxes: Vec<ErgoBoxAssetsData>,
+    /// Context extensions of the inputs (by input index), the extensions set by the plugins are
+    /// not checked against the input scripts
+    pub input_extensions: HashMap<usize, ContextExtension>,
+    /// Height of the boxes created by the builder
+    pub current_height: Height,
+}
+
+/// Part of the transaction owned by a plugin, other plugins' hooks cannot change it
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum PluginClaim {
+    /// Output with the given index (e.g. the pool box of a DEX swap)
+    OutputIndex(usize),
+    /// Total amount of the token in the outputs and the change
+    Token(TokenId),
+    /// Context extension of the input with the given index
+    InputExtension(usize),
+    /// Change of the box selection
+    Change,
+}
+
+/// Errors of the transaction builder plugins
+#[derive(Error, PartialEq, Eq, Debug, Clone)]
+pub enum TxBuilderPluginError {
+    /// Plugin's hook rejected the transaction
+    #[error("Plugin {plugin} rejected the transaction: {reason}")]
+    Rejected {
+        /// Plugin name
+        plugin: String,
+        /// Reason given by the plugin
+        reason: String,
+    },
+    /// Plugin with the same name is already registered
+    #[error("Plugin {0} is already registered")]
+    DuplicateName(String),
+    /// Plugins claim the same part of the transaction
+    #[error("Plugins {first} and {second} both claim {claim:?}")]
+    ConflictingClaims {
+        /// Claimed part
+        claim: PluginClaim,
+        /// Registered plugin
+        first: String,
+        /// Plugin being registered
+        second: String,
+    },
+    /// Plugin's hook changed the part claimed by another plugin
+    #[error("Plugin {plugin} changed {claim:?} claimed by plugin {owner}")]
+    ClaimViolation {
+        /// Claimed part
+        claim: PluginClaim,
+        /// Plugin claiming the part
+        owner: String,
+        /// Plugin which changed the part
+        plugin: String,
+    },
+    /// Plugin depends on the plugin which is not registered
+    #[error("Plugin {plugin} runs after plugin {dependency} which is not registered")]
+    UnknownDependency {
+        /// Plugin name
+        plugin: String,
+        /// Name of the missing plugin
+        dependency: String,
+    },
+    /// Plugins depend on each other (names of the plugins which could not be ordered)
+    #[error("Plugins dependency cycle: {0:?}")]
+    DependencyCycle(Vec<String>),
+}
+
+/// Transaction builder plugin
+pub trait TxBuilderPlugin<S: ErgoBoxAssets> {
+    /// Unique name of the plugin (referred to in [`TxBuilderPlugin::run_after`])
+    fn name(&self) -> &str;
+
+    /// Names of the plugins whose hooks must be called before the hooks of this plugin
+    fn run_after(&self) -> Vec<String> {
+        vec![]
+    }
+
+    /// Parts of the transaction owned by the plugin
+    fn claims(&self) -> Vec<PluginClaim> {
+        vec![]
+    }
+
+    /// Called with the outputs and data inputs given to the builder, returns the reason on
+    /// rejection
+    fn after_selection(&self, _tx: &mut TxDraft<S>) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Called before the change boxes are made, returns the reason on rejection
+    fn before_change(&self, _tx: &mut TxDraft<S>) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Called with all the outputs (including change and miner's fee) before the balance checks,
+    /// returns the reason on rejection
+    fn after_assembly(&self, _tx: &mut TxDraft<S>) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+/// Stage of the build to call the plugin hooks in
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub(crate) enum PluginStage {
+    AfterSelection,
+    BeforeChange,
+    AfterAssembly,
+}
+
+/// Checks the plugin can be registered along with the given plugins
+pub(crate) fn check_registration<S: ErgoBoxAssets>(
+    registered: &[Arc<dyn TxBuilderPlugin<S> + Send + Sync>],
+    plugin: &dyn TxBuilderPlugin<S>,
+) -> Result<(), TxBuilderPluginError> {
+    let claims = plugin.claims();
+    for other in registered {
+        if other.name() == plugin.name() {
+            return Err(TxBuilderPluginError::DuplicateName(
+                plugin.name().to_string(),
+            ));
+        }
+        if let Some(claim) = other.claims().into_iter().find(|c| claims.contains(c)) {
+            return Err(TxBuilderPluginError::ConflictingClaims {
+                claim,
+                first: other.name().to_string(),
+                second: plugin.name().to_string(),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Plugins in the order of the hook calls: in the order of registration, each plugin after its
+/// dependencies
+pub(crate) fn ordered<S: ErgoBoxAssets>(
+    registered: &[Arc<dyn TxBuilderPlugin<S> + Send + Sync>],
+) -> Result<Vec<&dyn TxBuilderPlugin<S>>, TxBuilderPluginError> {
+    let plugins: Vec<&dyn TxBuilderPlugin<S>> = registered
+        .iter()
+        .map(|p| p.as_ref() as &dyn TxBuilderPlugin<S>)
+        .collect();
+    for plugin in &plugins {
+        if let Some(dependency) = plugin
+            .run_after()
+            .into_iter()
+            .find(|d| !plugins.iter().any(|p| p.name() == d))
+        {
+            return Err(TxBuilderPluginError::UnknownDependency {
+                plugin: plugin.name().to_string(),
+                dependency,
+            });
+        }
+    }
+    let mut res: Vec<&dyn TxBuilderPlugin<S>> = Vec::with_capacity(plugins.len());
+    let mut pending = plugins;
+    while !pending.is_empty() {
+        let ready = pending.iter().position(|p| {
+            p.run_after()
+                .iter()
+                .all(|d| res.iter().any(|r| r.name() == d))
+        });
+        match ready {
+            Some(idx) => res.push(pending.remove(idx)),
+            None => {
+                return Err(TxBuilderPluginError::DependencyCycle(
+                    pending.iter().map(|p| p.name().to_string()).collect(),
+                ))
+            }
+        }
+    }
+    Ok(res)
+}
+
+/// Calls the hooks of the stage (plugins are expected to be [`ordered`]), checking the parts
+/// claimed by each plugin are not changed by the other plugins
+pub(crate) fn run_stage<S: ErgoBoxAssets>(
+    plugins: &[&dyn TxBuilderPlugin<S>],
+    stage: PluginStage,
+    tx: &mut TxDraft<S>,
+) -> Result<(), TxBuilderPluginError> {
+    for plugin in plugins {
+        let others_claims: Vec<(String, PluginClaim)> = plugins
+            .iter()
+            .filter(|p| p.name() != plugin.name())
+            .flat_map(|p| {
+                p.claims()
+                    .into_iter()
+                    .map(move |c| (p.name().to_string(), c))
+            })
+            .collect();
+        let claimed_before: Vec<ClaimedPart> = others_claims
+            .iter()
+            .map(|(_, claim)| ClaimedPart::of(claim, tx))
+            .collect();
+        let res = match stage {
+            PluginStage::AfterSelection => plugin.after_selection(tx),
+            PluginStage::BeforeChange => plugin.before_change(tx),
+            PluginStage::AfterAssembly => plugin.after_assembly(tx),
+        };
+        res.map_err(|reason| TxBuilderPluginError::Rejected {
+            plugin: plugin.name().to_string(),
+            reason,
+        })?;
+        for ((owner, claim), before) in others_claims.into_iter().zip(claimed_before) {
+            if ClaimedPart::of(&claim, tx) != before {
+                return Err(TxBuilderPluginError::ClaimViolation {
+                    claim,
+                    owner,
+                    plugin: plugin.name().to_string(),
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Value of the claimed part of the transaction
+#[derive(PartialEq, Eq, Debug)]
+enum ClaimedPart {
+    Output(Option<ErgoBoxCandidate>),
+    // u128 to sum the token amounts without overflow
+    TokenAmount(u128),
+    InputExtension(Option<ContextExtension>),
+    Change(Vec<ErgoBoxAssetsData>),
+}
+
+impl ClaimedPart {
+    fn of<S: ErgoBoxAssets>(claim: &PluginClaim, tx: &TxDraft<S>) -> ClaimedPart {
+        match claim {
+            PluginClaim::OutputIndex(idx) => {
+                ClaimedPart::Output(tx.output_candidates.get(*idx).cloned())
+            }
+            PluginClaim::Token(token_id) => ClaimedPart::TokenAmount(
+                tx.output_candidates
+                    .iter()
+                    .flat_map(|b| b.tokens())
+                    .chain(tx.change_boxes.iter().flat_map(|b| b.tokens()))
+                    .flat_map(|tokens| tokens.as_vec().clone())
+                    .filter(|t| t.token_id == *token_id)
+                    .map(|t| u64::from(t.amount) as u128)
+                    .sum(),
+            ),
+            PluginClaim::InputExtension(idx) => {
+                ClaimedPart::InputExtension(tx.input_extensions.get(idx).cloned())
+            }
+            PluginClaim::Change => ClaimedPart::Change(tx.change_boxes.clone()),
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::convert::TryInto;
+
+    use ergotree_ir::chain::address::Address;
+    use ergotree_ir::chain::ergo_box::box_value::BoxValue;
+    use ergotree_ir::chain::ergo_box::ErgoBox;
+    use ergotree_ir::chain::ergo_box::NonMandatoryRegisters;
+    use ergotree_ir::chain::tx_id::TxId;
+    use ergotree_ir::ergo_tree::ErgoTree;
+    use sigma_test_util::force_any_val;
+
+    use crate::chain::ergo_box::box_builder::ErgoBoxCandidateBuilder;
+    use crate::wallet::box_selector::{BoxSelector, SimpleBoxSelector};
+    use crate::wallet::tx_builder::TxBuilder;
+    use crate::wallet::tx_builder::TxBuilderError;
+
+    use super::*;
+
+    /// Adds an output with the value taken from the change in `after_selection`
+    struct AddOutput {
+        name: String,
+        value: u64,
+        index: usize,
+        run_after: Vec<String>,
+        claims: Vec<PluginClaim>,
+    }
+
+    impl AddOutput {
+        fn new(name: &str, value: u64) -> AddOutput {
+            AddOutput {
+                name: name.to_string(),
+                value,
+                index: 0,
+                run_after: vec![],
+                claims: vec![],
+            }
+        }
+    }
+
+    impl TxBuilderPlugin<ErgoBox> for AddOutput {
+        fn name(&self) -> &str {
+            &self.name
+        }
+
+        fn run_after(&self) -> Vec<String> {
+            self.run_after.clone()
+        }
+
+        fn claims(&self) -> Vec<PluginClaim> {
+            self.claims.clone()
+        }
+
+        fn after_selection(&self, tx: &mut TxDraft<ErgoBox>) -> Result<(), String> {
+            let value: BoxValue = self.value.try_into().map_err(|_| "invalid value")?;
+            let change = tx.change_boxes.first_mut().ok_or("no change")?;
+            change.value = change
+                .value
+                .checked_sub(&value)
+                .map_err(|_| "not enough change")?;
+            let out_box = ErgoBoxCandidateBuilder::new(
+                value,
+                force_any_val::<ErgoTree>(),
+                tx.current_height.as_u32(),
+            )
+            .build()
+            .map_err(|e| e.to_string())?;
+            tx.output_candidates.insert(self.index, out_box);
+            Ok(())
+        }
+    }
+
+    /// Checks the outputs and change in `before_change` and `after_assembly`
+    struct Inspect;
+
+    impl TxBuilderPlugin<ErgoBox> for Inspect {
+        fn name(&self) -> &str {
+            "inspect"
+        }
+
+        fn before_change(&self, tx: &mut TxDraft<ErgoBox>) -> Result<(), String> {
+            if tx.change_boxes.is_empty() {
+                return Err("no change".to_string());
+            }
+            Ok(())
+        }
+
+        fn after_assembly(&self, tx: &mut TxDraft<ErgoBox>) -> Result<(), String> {
+            // plugin output, builder output, change and miner's fee
+            if tx.output_candidates.len() != 4 {
+                return Err(format!("{} outputs", tx.output_candidates.len()));
+            }
+            Ok(())
+        }
+    }
+
+    fn tx_builder() -> TxBuilder<ErgoBox> {
+        let input_box = ErgoBox::new(
+            100000000i64.try_into().unwrap(),
+            force_any_val::<ErgoTree>(),
+            None,
+            NonMandatoryRegisters::empty(),
+            1,
+            force_any_val::<TxId>(),
+            0,
+        )
+        .unwrap();
+        let out_box =
+            ErgoBoxCandidateBuilder::new(BoxValue::SAFE_USER_MIN, force_any_val::<ErgoTree>(), 0)
+                .build()
+                .unwrap();
+        let tx_fee = BoxValue::SAFE_USER_MIN;
+        let target_balance = BoxValue::SAFE_USER_MIN.checked_add(&tx_fee).unwrap();
+        let box_selection = SimpleBoxSelector::new()
+            .select(vec![input_box], target_balance, vec![].as_slice())
+            .unwrap();
+        TxBuilder::new(
+            box_selection,
+            vec![out_box],
+            Height::new(0),
+            tx_fee,
+            force_any_val::<Address>(),
+            BoxValue::SAFE_USER_MIN,
+        )
+    }
+
+    #[test]
+    fn test_plugin_outputs() {
+        let mut tx_builder = tx_builder();
+        tx_builder
+            .add_plugin(Arc::new(AddOutput::new("pool", 2000000)))
+            .unwrap();
+        let inspect: Arc<dyn TxBuilderPlugin<ErgoBox> + Send + Sync> = Arc::new(Inspect);
+        tx_builder.add_plugin(inspect.clone()).unwrap();
+        // the plugins can be shared between threads
+        let name = std::thread::spawn(move || inspect.name().to_string())
+            .join()
+            .unwrap();
+        assert_eq!(name, "inspect");
+        let tx = tx_builder.build().unwrap();
+        assert_eq!(tx.output_candidates.len(), 4);
+        assert_eq!(
+            *tx.output_candidates.first().value.as_u64(),
+            2000000,
+            "expected the plugin output first"
+        );
+    }
+
+    #[test]
+    fn test_plugins_order() {
+        let mut tx_builder = tx_builder();
+        // both insert at index 0, the output of the plugin called last comes first
+        let mut first = AddOutput::new("first", 3000000);
+        first.run_after = vec!["second".to_string()];
+        tx_builder.add_plugin(Arc::new(first)).unwrap();
+        tx_builder
+            .add_plugin(Arc::new(AddOutput::new("second", 2000000)))
+            .unwrap();
+        let tx = tx_builder.build().unwrap();
+        assert_eq!(*tx.output_candidates.first().value.as_u64(), 3000000);
+        assert_eq!(
+            *tx.output_candidates.get(1).unwrap().value.as_u64(),
+            2000000
+        );
+    }
+
+    #[test]
+    fn test_plugin_registration() {
+        let mut tx_builder = tx_builder();
+        let mut pool = AddOutput::new("pool", 2000000);
+        pool.claims = vec![PluginClaim::OutputIndex(0), PluginClaim::Change];
+        tx_builder.add_plugin(Arc::new(pool)).unwrap();
+        assert_eq!(
+            tx_builder.add_plugin(Arc::new(AddOutput::new("pool", 2000000))),
+            Err(TxBuilderError::PluginError(
+                TxBuilderPluginError::DuplicateName("pool".to_string())
+            ))
+        );
+        let mut fee = AddOutput::new("fee", 2000000);
+        fee.claims = vec![PluginClaim::Change];
+        assert_eq!(
+            tx_builder.add_plugin(Arc::new(fee)),
+            Err(TxBuilderError::PluginError(
+                TxBuilderPluginError::ConflictingClaims {
+                    claim: PluginClaim::Change,
+                    first: "pool".to_string(),
+                    second: "fee".to_string(),
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn test_claim_violation() {
+        let mut tx_builder = tx_builder();
+        let mut pool = AddOutput::new("pool", 2000000);
+        pool.claims = vec![PluginClaim::OutputIndex(0)];
+        tx_builder.add_plugin(Arc::new(pool)).unwrap();
+        // shifts the pool output
+        let mut other = AddOutput::new("other", 3000000);
+        other.run_after = vec!["pool".to_string()];
+        tx_builder.add_plugin(Arc::new(other)).unwrap();
+        assert_eq!(
+            tx_builder.clone().build(),
+            Err(TxBuilderError::PluginError(
+                TxBuilderPluginError::ClaimViolation {
+                    claim: PluginClaim::OutputIndex(0),
+                    owner: "pool".to_string(),
+                    plugin: "other".to_string(),
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn test_plugin_rejected() {
+        let mut tx_builder = tx_builder();
+        let mut outputs = tx_builder.output_candidates();
+        outputs.push(outputs.first().unwrap().clone());
+        let mut tx_builder_more_outputs = tx_builder.clone();
+        tx_builder_more_outputs.output_candidates = outputs;
+        tx_builder_more_outputs
+            .add_plugin(Arc::new(AddOutput::new("pool", 2000000)))
+            .unwrap();
+        tx_builder_more_outputs
+            .add_plugin(Arc::new(Inspect))
+            .unwrap();
+        assert_eq!(
+            tx_builder_more_outputs.build(),
+            Err(TxBuilderError::PluginError(
+                TxBuilderPluginError::Rejected {
+                    plugin: "inspect".to_string(),
+                    reason: "5 outputs".to_string(),
+                }
+            ))
+        );
+        tx_builder.box_selection.change_boxes.clear();
+        tx_builder.add_plugin(Arc::new(Inspect)).unwrap();
+        assert_eq!(
+            tx_builder.build(),
+            Err(TxBuilderError::PluginError(
+                TxBuilderPluginError::Rejected {
+                    plugin: "inspect".to_string(),
+                    reason: "no change".to_string(),
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn test_plugin_dependencies() {
+        let mut tx_builder = tx_builder();
+        let mut a = AddOutput::new("a", 2000000);
+        a.run_after = vec!["b".to_string()];
+        tx_builder.add_plugin(Arc::new(a)).unwrap();
+        assert_eq!(
+            tx_builder.clone().build(),
+            Err(TxBuilderError::PluginError(
+                TxBuilderPluginError::UnknownDependency {
+                    plugin: "a".to_string(),
+                    dependency: "b".to_string(),
+                }
+            ))
+        );
+        let mut b = AddOutput::new("b", 2000000);
+        b.run_after = vec!["a".to_string()];
+        tx_builder.add_plugin(Arc::new(b)).unwrap();
+        assert_eq!(
+            tx_builder.build(),
+            Err(TxBuilderError::PluginError(
+                TxBuilderPluginError::DependencyCycle(vec!["a".to_string(), "b".to_string()])
+            ))
+        );
+    }
+}
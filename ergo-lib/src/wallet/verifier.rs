@@ -0,0 +1,487 @@
+//! Transaction input proof verification
+
+use std::rc::Rc;
+
+use ergotree_interpreter::eval::env::Env;
+use ergotree_interpreter::eval::settings::EvalSettings;
+use ergotree_interpreter::sigma_protocol::verifier::TestVerifier;
+use ergotree_interpreter::sigma_protocol::verifier::VerificationResult;
+use ergotree_interpreter::sigma_protocol::verifier::Verifier;
+use ergotree_interpreter::sigma_protocol::verifier::VerifierError;
+use ergotree_ir::chain::address::Address;
+use ergotree_ir::chain::ergo_box::BoxId;
+use ergotree_ir::chain::ergo_box::ErgoBox;
+use ergotree_ir::mir::constant::Constant;
+use ergotree_ir::mir::constant::Literal;
+use ergotree_ir::mir::expr::Expr;
+use ergotree_ir::serialization::SigmaSerializationError;
+use ergotree_ir::sigma_protocol::sigma_boolean::SigmaBoolean;
+#[cfg(feature = "json")]
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::chain::ergo_state_context::ErgoStateContext;
+use crate::chain::transaction::unsigned::UnsignedTransaction;
+use crate::chain::transaction::Transaction;
+use crate::chain::transaction::UnsignedInput;
+
+use super::signing::make_context;
+use super::signing::TransactionContext;
+use super::signing::TxSigningError;
+
+/// Errors on transaction input proof verification
+#[derive(Error, PartialEq, Eq, Debug, Clone)]
+pub enum TxVerifyError {
+    /// input index is out of bounds of the transaction inputs
+    #[error("Input index {0} is out of bounds")]
+    InputIndexOutOfBounds(usize),
+    /// failed to find an input in boxes_to_spend
+    #[error("Input box not found (index {0})")]
+    InputBoxNotFound(usize),
+    /// Context creation error
+    #[error("Context error: {0}")]
+    ContextError(TxSigningError),
+    /// Proof verification error
+    #[error("Verifier error: {0}")]
+    VerifierError(#[from] VerifierError),
+    /// Tx serialization failed (id calculation)
+    #[error("Transaction serialization failed: {0}")]
+    SerializationError(#[from] SigmaSerializationError),
+}
+
+/// Verifies the proof of the transaction input with the given index (evaluates the spent box
+/// script in the context of the transaction and checks the proof against the reduced
+/// proposition). The result is false if the proof does not satisfy the script.
+/// The activated script version is taken from the block version of the pre-header as the node does
+/// (see [`EvalSettings::from_block_version`]).
+/// `boxes_to_spend` - boxes spent by the transaction inputs (in the order of the inputs),
+/// `data_boxes` - boxes corresponding to the transaction data inputs (in the order of the data inputs)
+pub fn verify_tx_input_proof(
+    tx: &Transaction,
+    input_idx: usize,
+    state_context: &ErgoStateContext,
+    boxes_to_spend: &[ErgoBox],
+    data_boxes: &[ErgoBox],
+) -> Result<VerificationResult, TxVerifyError> {
+    let settings = EvalSettings::from_block_version(state_context.pre_header.version);
+    verify_tx_input_proof_with_settings(
+        tx,
+        input_idx,
+        state_context,
+        boxes_to_spend,
+        data_boxes,
+        &settings,
+    )
+}
+
+/// Same as [`verify_tx_input_proof`], with the given evaluation settings
+pub fn verify_tx_input_proof_with_settings(
+    tx: &Transaction,
+    input_idx: usize,
+    state_context: &ErgoStateContext,
+    boxes_to_spend: &[ErgoBox],
+    data_boxes: &[ErgoBox],
+    settings: &EvalSettings,
+) -> Result<VerificationResult, TxVerifyError> {
+    let input = tx
+        .inputs
+        .get(input_idx)
+        .ok_or(TxVerifyError::InputIndexOutOfBounds(input_idx))?;
+    let self_index = boxes_to_spend
+        .iter()
+        .position(|b| b.box_id() == input.box_id)
+        .ok_or(TxVerifyError::InputBoxNotFound(input_idx))?;
+    let spending_tx = UnsignedTransaction::new(
+        tx.inputs.mapped_ref(|i| UnsignedInput {
+            box_id: i.box_id.clone(),
+            extension: i.spending_proof.extension.clone(),
+        }),
+        tx.data_inputs.clone(),
+        tx.output_candidates.clone(),
+    )?;
+    let tx_context = TransactionContext {
+        spending_tx,
+        boxes_to_spend: boxes_to_spend.to_vec(),
+        data_boxes: data_boxes.to_vec(),
+    };
+    let ctx = make_context(state_context, &tx_context, self_index)
+        .map_err(TxVerifyError::ContextError)?
+        .with_extension(input.spending_proof.extension.clone());
+    let message = tx.bytes_to_sign()?;
+    Ok(TestVerifier.verify_with_settings(
+        &boxes_to_spend[self_index].ergo_tree,
+        &Env::empty(),
+        Rc::new(ctx),
+        input.spending_proof.proof.clone(),
+        &message,
+        settings,
+    )?)
+}
+
+/// Verification result of a transaction input
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct InputVerificationReport {
+    /// Index of the input in the transaction
+    #[cfg_attr(feature = "json", serde(rename = "index"))]
+    pub index: usize,
+    /// Id of the spent box
+    #[cfg_attr(feature = "json", serde(rename = "boxId"))]
+    pub box_id: BoxId,
+    /// True if the proof satisfies the spent box script
+    #[cfg_attr(feature = "json", serde(rename = "result"))]
+    pub result: bool,
+    /// Estimated cost of the script evaluation
+    #[cfg_attr(feature = "json", serde(rename = "cost"))]
+    pub cost: u64,
+    /// Verification time in microseconds (not measured on WASM, where the system clock is not
+    /// available)
+    #[cfg_attr(feature = "json", serde(rename = "timeMicros"))]
+    pub time_micros: Option<u64>,
+}
+
+/// Verification results of all inputs of a transaction
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct TxVerificationReport {
+    /// Results of the inputs (in the order of the inputs)
+    #[cfg_attr(feature = "json", serde(rename = "inputs"))]
+    pub inputs: Vec<InputVerificationReport>,
+}
+
+impl TxVerificationReport {
+    /// True if the proofs of all inputs are valid
+    pub fn is_valid(&self) -> bool {
+        self.inputs.iter().all(|i| i.result)
+    }
+
+    /// Total estimated cost of the scripts of all inputs
+    pub fn total_cost(&self) -> u64 {
+        self.inputs
+            .iter()
+            .fold(0u64, |acc, i| acc.saturating_add(i.cost))
+    }
+}
+
+/// Verifies the proofs of all transaction inputs (see [`verify_tx_input_proof`]) and reports the
+/// result, estimated script cost and verification time of every input.
+/// Fails on the first input which cannot be verified (e.g. its box is missing in
+/// `boxes_to_spend`), invalid proofs are reported as `result: false`.
+pub fn verify_tx_proofs(
+    tx: &Transaction,
+    state_context: &ErgoStateContext,
+    boxes_to_spend: &[ErgoBox],
+    data_boxes: &[ErgoBox],
+) -> Result<TxVerificationReport, TxVerifyError> {
+    let settings = EvalSettings::from_block_version(state_context.pre_header.version);
+    let inputs = tx
+        .inputs
+        .iter()
+        .enumerate()
+        .map(|(index, input)| {
+            let timer = Timer::start();
+            let res = verify_tx_input_proof_with_settings(
+                tx,
+                index,
+                state_context,
+                boxes_to_spend,
+                data_boxes,
+                &settings,
+            )?;
+            Ok(InputVerificationReport {
+                index,
+                box_id: input.box_id.clone(),
+                result: res.result,
+                cost: res.cost,
+                time_micros: timer.elapsed_micros(),
+            })
+        })
+        .collect::<Result<Vec<_>, TxVerifyError>>()?;
+    Ok(TxVerificationReport { inputs })
+}
+
+/// Sigma proposition the messages are signed for by the owner of the address (see
+/// [`super::signing::sign_message`]): the public key of a P2PK address or the proposition of a P2S
+/// address with a constant sigma proposition script (e.g. a Diffie-Hellman tuple).
+/// None for the other addresses (the script of a P2SH address is unknown).
+pub fn address_sigma_prop(address: &Address) -> Option<SigmaBoolean> {
+    match address {
+        Address::P2Pk(pk) => Some(pk.clone().into()),
+        Address::P2S(_) => {
+            let expr = address.script().ok()?.proposition().ok()?;
+            match expr.as_ref() {
+                Expr::Const(Constant {
+                    v: Literal::SigmaProp(sp),
+                    ..
+                }) => Some(sp.value().clone()),
+                _ => None,
+            }
+        }
+        Address::P2SH(_) => None,
+    }
+}
+
+/// Verifies the signature of an arbitrary message (e.g. a "sign-in" challenge) made by the owner
+/// of the address (see [`address_sigma_prop`] for the supported addresses).
+/// The result is false if the signature is not valid for the message and the address.
+pub fn verify_signature(
+    address: &Address,
+    message: &[u8],
+    signature: &[u8],
+) -> Result<bool, VerifierError> {
+    let sigma_tree = address_sigma_prop(address).ok_or_else(|| {
+        VerifierError::Unexpected(format!(
+            "address {:?} has no sigma proposition to verify the signature for",
+            address
+        ))
+    })?;
+    ergotree_interpreter::sigma_protocol::verifier::verify_signature(sigma_tree, message, signature)
+}
+
+/// Measures the elapsed time where the system clock is available
+struct Timer {
+    #[cfg(not(target_arch = "wasm32"))]
+    start: std::time::Instant,
+}
+
+impl Timer {
+    fn start() -> Self {
+        Timer {
+            #[cfg(not(target_arch = "wasm32"))]
+            start: std::time::Instant::now(),
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn elapsed_micros(&self) -> Option<u64> {
+        Some(self.start.elapsed().as_micros() as u64)
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn elapsed_micros(&self) -> Option<u64> {
+        None
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::chain::ergo_box::box_builder::ErgoBoxCandidateBuilder;
+    use crate::wallet::secret_key::SecretKey;
+    use crate::wallet::Wallet;
+    use ergotree_interpreter::sigma_protocol::private_input::DlogProverInput;
+    use ergotree_ir::chain::ergo_box::box_value::BoxValue;
+    use ergotree_ir::chain::ergo_box::NonMandatoryRegisters;
+    use ergotree_ir::chain::tx_id::TxId;
+    use ergotree_ir::ergo_tree::ErgoTree;
+    use ergotree_ir::serialization::SigmaSerializable;
+    use sigma_test_util::force_any_val;
+    use std::convert::TryFrom;
+    use std::convert::TryInto;
+
+    #[test]
+    fn test_verify_signed_input() {
+        let secret = DlogProverInput::random();
+        let tree = ErgoTree::try_from(Expr::Const(secret.public_image().into())).unwrap();
+        let input_box = ErgoBox::new(
+            BoxValue::SAFE_USER_MIN,
+            tree.clone(),
+            None,
+            NonMandatoryRegisters::empty(),
+            0,
+            TxId::zero(),
+            0,
+        )
+        .unwrap();
+        let output = ErgoBoxCandidateBuilder::new(BoxValue::SAFE_USER_MIN, tree, 0)
+            .build()
+            .unwrap();
+        let spending_tx = UnsignedTransaction::new(
+            vec![UnsignedInput::from(input_box.clone())]
+                .try_into()
+                .unwrap(),
+            None,
+            vec![output].try_into().unwrap(),
+        )
+        .unwrap();
+        let boxes_to_spend = vec![input_box];
+        let tx_context = TransactionContext {
+            spending_tx,
+            boxes_to_spend: boxes_to_spend.clone(),
+            data_boxes: vec![],
+        };
+        let state_context = ErgoStateContext::dummy();
+        let wallet = Wallet::from_secrets(vec![SecretKey::DlogSecretKey(secret)]);
+        let tx = wallet.sign_transaction(tx_context, &state_context).unwrap();
+
+        let res = verify_tx_input_proof(&tx, 0, &state_context, &boxes_to_spend, &[]).unwrap();
+        assert!(res.result);
+        // the cost accumulated on the reduction is passed on (the C bindings report it as is)
+        assert!(res.cost > 0);
+
+        assert_eq!(
+            verify_tx_input_proof(&tx, 1, &state_context, &boxes_to_spend, &[]),
+            Err(TxVerifyError::InputIndexOutOfBounds(1))
+        );
+        assert_eq!(
+            verify_tx_input_proof(&tx, 0, &state_context, &[], &[]),
+            Err(TxVerifyError::InputBoxNotFound(0))
+        );
+
+        // the proof does not match the changed transaction
+        let changed_output =
+            ErgoBoxCandidateBuilder::new(BoxValue::SAFE_USER_MIN, force_any_val::<ErgoTree>(), 0)
+                .build()
+                .unwrap();
+        let changed_tx = Transaction::new(
+            tx.inputs.clone(),
+            None,
+            vec![changed_output].try_into().unwrap(),
+        )
+        .unwrap();
+        let res =
+            verify_tx_input_proof(&changed_tx, 0, &state_context, &boxes_to_spend, &[]).unwrap();
+        assert!(!res.result);
+
+        // the script of a not yet activated version is accepted (soft-fork), unless strict
+        let future_version = EvalSettings {
+            tree_version_override: Some(1),
+            ..EvalSettings::new(0)
+        };
+        let res = verify_tx_input_proof_with_settings(
+            &changed_tx,
+            0,
+            &state_context,
+            &boxes_to_spend,
+            &[],
+            &future_version,
+        )
+        .unwrap();
+        assert!(res.result);
+        let strict = EvalSettings {
+            strict: true,
+            ..future_version
+        };
+        assert!(matches!(
+            verify_tx_input_proof_with_settings(
+                &changed_tx,
+                0,
+                &state_context,
+                &boxes_to_spend,
+                &[],
+                &strict,
+            ),
+            Err(TxVerifyError::VerifierError(_))
+        ));
+    }
+
+    #[test]
+    fn test_verify_tx_proofs_report() {
+        let secret = DlogProverInput::random();
+        let tree = ErgoTree::try_from(Expr::Const(secret.public_image().into())).unwrap();
+        let input_boxes: Vec<ErgoBox> = (0..2)
+            .map(|index| {
+                ErgoBox::new(
+                    BoxValue::SAFE_USER_MIN,
+                    tree.clone(),
+                    None,
+                    NonMandatoryRegisters::empty(),
+                    0,
+                    TxId::zero(),
+                    index,
+                )
+                .unwrap()
+            })
+            .collect();
+        let output = ErgoBoxCandidateBuilder::new(BoxValue::SAFE_USER_MIN, tree, 0)
+            .build()
+            .unwrap();
+        let spending_tx = UnsignedTransaction::new(
+            input_boxes
+                .iter()
+                .cloned()
+                .map(UnsignedInput::from)
+                .collect::<Vec<_>>()
+                .try_into()
+                .unwrap(),
+            None,
+            vec![output].try_into().unwrap(),
+        )
+        .unwrap();
+        let tx_context = TransactionContext {
+            spending_tx,
+            boxes_to_spend: input_boxes.clone(),
+            data_boxes: vec![],
+        };
+        let state_context = ErgoStateContext::dummy();
+        let wallet = Wallet::from_secrets(vec![SecretKey::DlogSecretKey(secret)]);
+        let tx = wallet.sign_transaction(tx_context, &state_context).unwrap();
+
+        let report = verify_tx_proofs(&tx, &state_context, &input_boxes, &[]).unwrap();
+        assert!(report.is_valid());
+        assert_eq!(report.inputs.len(), 2);
+        for (idx, input) in report.inputs.iter().enumerate() {
+            assert_eq!(input.index, idx);
+            assert_eq!(input.box_id, input_boxes[idx].box_id());
+            assert!(input.cost > 0);
+            assert!(input.time_micros.is_some());
+        }
+        assert_eq!(
+            report.total_cost(),
+            report.inputs[0].cost + report.inputs[1].cost
+        );
+        let json = serde_json::to_string(&report).unwrap();
+        assert!(json.contains("\"timeMicros\""));
+        assert_eq!(
+            serde_json::from_str::<TxVerificationReport>(&json).unwrap(),
+            report
+        );
+
+        assert_eq!(
+            verify_tx_proofs(&tx, &state_context, &input_boxes[..1], &[]),
+            Err(TxVerifyError::InputBoxNotFound(1))
+        );
+    }
+
+    #[test]
+    fn test_sign_and_verify_message() {
+        let secret = DlogProverInput::random();
+        let address = Address::P2Pk(secret.public_image());
+        let wallet = Wallet::from_secrets(vec![SecretKey::DlogSecretKey(secret)]);
+        let message = b"sign-in challenge".to_vec();
+        let signature = wallet.sign_message_for_address(&address, &message).unwrap();
+        assert!(verify_signature(&address, &message, &signature).unwrap());
+        assert!(!verify_signature(&address, b"other challenge", &signature).unwrap());
+        let other_address = Address::P2Pk(DlogProverInput::random().public_image());
+        assert!(!verify_signature(&other_address, &message, &signature).unwrap());
+    }
+
+    #[test]
+    fn test_sign_and_verify_message_dht_address() {
+        let secret = SecretKey::random_dht();
+        let address = secret.get_address_from_public_image();
+        assert!(matches!(address, Address::P2S(_)));
+        let wallet = Wallet::from_secrets(vec![secret]);
+        let message = b"sign-in challenge".to_vec();
+        let signature = wallet.sign_message_for_address(&address, &message).unwrap();
+        assert!(verify_signature(&address, &message, &signature).unwrap());
+    }
+
+    #[test]
+    fn test_verify_message_unsupported_address() {
+        let p2sh = Address::P2SH([0u8; 24]);
+        assert_eq!(address_sigma_prop(&p2sh), None);
+        assert!(matches!(
+            verify_signature(&p2sh, b"msg", &[]),
+            Err(VerifierError::Unexpected(_))
+        ));
+        let script = Address::P2S(
+            ErgoTree::try_from(Expr::Const(true.into()))
+                .unwrap()
+                .sigma_serialize_bytes()
+                .unwrap(),
+        );
+        assert_eq!(address_sigma_prop(&script), None);
+    }
+}
@@ -0,0 +1,113 @@
+//! Serialization golden tests against the mainnet corpus in `tests/corpus`.
+//!
+//! Each fixture file holds real-world encodings (as returned by the node/explorer API). Every entry
+//! must be parsed and serialized back byte-identically. Box and transaction ids are checked on
+//! JSON parsing and block ids on binary parsing (they are hashes of the serialized bytes), so a
+//! mismatch there also points to a serializer regression.
+//!
+//! Run with `cargo test -p ergo-lib --features corpus --test corpus`.
+#![cfg(feature = "corpus")]
+
+use ergo_lib::chain::transaction::Transaction;
+use ergo_lib::ergotree_interpreter::sigma_protocol::prover::ProofBytes;
+use ergo_lib::ergotree_ir::chain::ergo_box::ErgoBox;
+use ergo_lib::ergotree_ir::chain::header::Header;
+use ergo_lib::ergotree_ir::ergo_tree::ErgoTree;
+use ergo_lib::ergotree_ir::serialization::SigmaSerializable;
+use pretty_assertions::assert_eq;
+
+const ERGO_TREES: &str = include_str!("corpus/ergo_trees.json");
+const BOXES: &str = include_str!("corpus/boxes.json");
+const TRANSACTIONS: &str = include_str!("corpus/transactions.json");
+const HEADERS: &str = include_str!("corpus/headers.json");
+
+fn load_entries(fixture: &str) -> Vec<serde_json::Value> {
+    serde_json::from_str(fixture).expect("corpus fixture should be a JSON array")
+}
+
+#[test]
+fn corpus_ergo_trees_roundtrip() {
+    let entries: Vec<String> = serde_json::from_str(ERGO_TREES).unwrap();
+    assert!(!entries.is_empty());
+    for hex in entries {
+        let bytes = base16::decode(hex.as_bytes()).unwrap();
+        let tree = ErgoTree::sigma_parse_bytes(&bytes).unwrap();
+        // the tree must be fully parsed, otherwise serialization just echoes the original bytes
+        assert!(tree.proposition().is_ok(), "cannot parse tree {}", hex);
+        assert_eq!(tree.to_base16_bytes().unwrap(), hex);
+    }
+}
+
+#[test]
+fn corpus_boxes_roundtrip() {
+    let entries = load_entries(BOXES);
+    assert!(!entries.is_empty());
+    for json in entries {
+        // box id is checked against the calculated one on parsing
+        let b: ErgoBox = serde_json::from_value(json.clone())
+            .unwrap_or_else(|e| panic!("cannot parse box {}: {}", json, e));
+        let bytes = b.sigma_serialize_bytes().unwrap();
+        let parsed = ErgoBox::sigma_parse_bytes(&bytes).unwrap();
+        assert_eq!(parsed.sigma_serialize_bytes().unwrap(), bytes);
+        assert_eq!(parsed, b);
+    }
+}
+
+#[test]
+fn corpus_transactions_roundtrip() {
+    let entries = load_entries(TRANSACTIONS);
+    assert!(!entries.is_empty());
+    for json in entries {
+        // tx id is checked against the calculated one on parsing
+        let tx: Transaction = serde_json::from_value(json.clone())
+            .unwrap_or_else(|e| panic!("cannot parse tx {}: {}", json, e));
+        let bytes = tx.sigma_serialize_bytes().unwrap();
+        let parsed = Transaction::sigma_parse_bytes(&bytes).unwrap();
+        assert_eq!(parsed.sigma_serialize_bytes().unwrap(), bytes);
+        assert_eq!(parsed, tx);
+        // outputs are the boxes as they are stored in the UTXO set
+        for output in tx.outputs.iter() {
+            let box_bytes = output.sigma_serialize_bytes().unwrap();
+            assert_eq!(ErgoBox::sigma_parse_bytes(&box_bytes).unwrap(), *output);
+        }
+    }
+}
+
+#[test]
+fn corpus_transactions_have_signed_inputs() {
+    let signed_inputs: usize = load_entries(TRANSACTIONS)
+        .into_iter()
+        .map(|json| serde_json::from_value::<Transaction>(json).unwrap())
+        .map(|tx| {
+            tx.inputs
+                .iter()
+                .filter(|input| input.spending_proof.proof != ProofBytes::Empty)
+                .count()
+        })
+        .sum();
+    assert!(signed_inputs > 0, "corpus should have inputs with proofs");
+}
+
+#[test]
+fn corpus_headers_roundtrip() {
+    let entries = load_entries(HEADERS);
+    assert!(!entries.is_empty());
+    for json in entries {
+        let header: Header = serde_json::from_value(json.clone())
+            .unwrap_or_else(|e| panic!("cannot parse header {}: {}", json, e));
+        let encoded = serde_json::to_value(&header).unwrap();
+        let decoded: Header = serde_json::from_value(encoded).unwrap();
+        assert_eq!(decoded, header);
+        assert!(
+            !header.nonce.is_empty(),
+            "header {:?} has no PoW solution",
+            header.id
+        );
+        // block id is calculated on parsing as the hash of the serialized header (with the PoW
+        // solution), so it must be the same as the id reported by the node
+        let bytes = header.sigma_serialize_bytes().unwrap();
+        let parsed = Header::sigma_parse_bytes(&bytes).unwrap();
+        assert_eq!(parsed.sigma_serialize_bytes().unwrap(), bytes);
+        assert_eq!(parsed, header);
+    }
+}
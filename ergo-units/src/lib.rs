@@ -0,0 +1,226 @@
+//! Typed units for Ergo: block height, timestamp and amount of nanoERGs
+
+// Coding conventions
+#![forbid(unsafe_code)]
+#![deny(non_upper_case_globals)]
+#![deny(non_camel_case_types)]
+#![deny(non_snake_case)]
+#![deny(unused_mut)]
+#![deny(dead_code)]
+#![deny(unused_imports)]
+#![deny(missing_docs)]
+// Clippy exclusions
+#![allow(clippy::unit_arg)]
+#![deny(rustdoc::broken_intra_doc_links)]
+#![deny(clippy::unwrap_used)]
+#![deny(clippy::expect_used)]
+#![deny(clippy::todo)]
+#![deny(clippy::unimplemented)]
+#![deny(clippy::panic)]
+
+use std::fmt;
+
+/// Block height
+#[cfg_attr(feature = "json", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "json", serde(transparent))]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Clone, Copy, Default)]
+pub struct Height(u32);
+
+impl Height {
+    /// Create a new height
+    pub const fn new(height: u32) -> Height {
+        Height(height)
+    }
+
+    /// Height as u32
+    pub const fn as_u32(&self) -> u32 {
+        self.0
+    }
+
+    /// Height `blocks` later, or None on overflow
+    pub fn checked_add(&self, blocks: u32) -> Option<Height> {
+        self.0.checked_add(blocks).map(Height)
+    }
+
+    /// Height `blocks` earlier, or None if it's below zero
+    pub fn checked_sub(&self, blocks: u32) -> Option<Height> {
+        self.0.checked_sub(blocks).map(Height)
+    }
+
+    /// Number of blocks since the `earlier` height, or None if `earlier` is above this height
+    pub fn blocks_since(&self, earlier: Height) -> Option<u32> {
+        self.0.checked_sub(earlier.0)
+    }
+}
+
+impl From<u32> for Height {
+    fn from(v: u32) -> Self {
+        Height(v)
+    }
+}
+
+impl From<Height> for u32 {
+    fn from(v: Height) -> Self {
+        v.0
+    }
+}
+
+impl fmt::Display for Height {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// Timestamp in milliseconds from UNIX epoch
+#[cfg_attr(feature = "json", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "json", serde(transparent))]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Clone, Copy, Default)]
+pub struct TimestampMillis(u64);
+
+impl TimestampMillis {
+    /// Create a new timestamp
+    pub const fn new(millis: u64) -> TimestampMillis {
+        TimestampMillis(millis)
+    }
+
+    /// Milliseconds from UNIX epoch
+    pub const fn as_u64(&self) -> u64 {
+        self.0
+    }
+
+    /// Timestamp `millis` later, or None on overflow
+    pub fn checked_add(&self, millis: u64) -> Option<TimestampMillis> {
+        self.0.checked_add(millis).map(TimestampMillis)
+    }
+
+    /// Timestamp `millis` earlier, or None if it's before UNIX epoch
+    pub fn checked_sub(&self, millis: u64) -> Option<TimestampMillis> {
+        self.0.checked_sub(millis).map(TimestampMillis)
+    }
+
+    /// Milliseconds since the `earlier` timestamp, or None if `earlier` is after this timestamp
+    pub fn millis_since(&self, earlier: TimestampMillis) -> Option<u64> {
+        self.0.checked_sub(earlier.0)
+    }
+}
+
+impl From<u64> for TimestampMillis {
+    fn from(v: u64) -> Self {
+        TimestampMillis(v)
+    }
+}
+
+impl From<TimestampMillis> for u64 {
+    fn from(v: TimestampMillis) -> Self {
+        v.0
+    }
+}
+
+impl fmt::Display for TimestampMillis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// Amount of nanoERGs (1 ERG = 10^9 nanoERGs)
+#[cfg_attr(feature = "json", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "json", serde(transparent))]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Clone, Copy, Default)]
+pub struct NanoErg(u64);
+
+impl NanoErg {
+    /// Number of nanoERGs in one ERG
+    pub const PER_ERG: u64 = 1_000_000_000;
+
+    /// Create a new amount
+    pub const fn new(nano_ergs: u64) -> NanoErg {
+        NanoErg(nano_ergs)
+    }
+
+    /// Amount of nanoERGs as u64
+    pub const fn as_u64(&self) -> u64 {
+        self.0
+    }
+
+    /// Sum of the amounts, or None on overflow
+    pub fn checked_add(&self, rhs: NanoErg) -> Option<NanoErg> {
+        self.0.checked_add(rhs.0).map(NanoErg)
+    }
+
+    /// Difference of the amounts, or None if `rhs` is greater
+    pub fn checked_sub(&self, rhs: NanoErg) -> Option<NanoErg> {
+        self.0.checked_sub(rhs.0).map(NanoErg)
+    }
+
+    /// Amount multiplied by `rhs` (e.g. fee per byte multiplied by size), or None on overflow
+    pub fn checked_mul(&self, rhs: u64) -> Option<NanoErg> {
+        self.0.checked_mul(rhs).map(NanoErg)
+    }
+}
+
+impl From<u64> for NanoErg {
+    fn from(v: u64) -> Self {
+        NanoErg(v)
+    }
+}
+
+impl From<NanoErg> for u64 {
+    fn from(v: NanoErg) -> Self {
+        v.0
+    }
+}
+
+impl fmt::Display for NanoErg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn height_arithmetic() {
+        let h = Height::new(100);
+        assert_eq!(h.checked_add(10), Some(Height::new(110)));
+        assert_eq!(Height::new(u32::MAX).checked_add(1), None);
+        assert_eq!(h.checked_sub(100), Some(Height::new(0)));
+        assert_eq!(h.checked_sub(101), None);
+        assert_eq!(h.blocks_since(Height::new(40)), Some(60));
+        assert_eq!(Height::new(40).blocks_since(h), None);
+    }
+
+    #[test]
+    fn timestamp_arithmetic() {
+        let t = TimestampMillis::new(1000);
+        assert_eq!(t.checked_add(500), Some(TimestampMillis::new(1500)));
+        assert_eq!(TimestampMillis::new(u64::MAX).checked_add(1), None);
+        assert_eq!(t.checked_sub(1001), None);
+        assert_eq!(t.millis_since(TimestampMillis::new(1)), Some(999));
+    }
+
+    #[test]
+    fn nano_erg_arithmetic() {
+        let a = NanoErg::new(NanoErg::PER_ERG);
+        assert_eq!(
+            a.checked_add(NanoErg::new(1)),
+            Some(NanoErg::new(1_000_000_001))
+        );
+        assert_eq!(NanoErg::new(u64::MAX).checked_add(NanoErg::new(1)), None);
+        assert_eq!(a.checked_sub(NanoErg::new(NanoErg::PER_ERG + 1)), None);
+        assert_eq!(a.checked_mul(3), Some(NanoErg::new(3 * NanoErg::PER_ERG)));
+        assert_eq!(a.checked_mul(u64::MAX), None);
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn json_is_transparent() {
+        assert_eq!(serde_json::to_string(&Height::new(42)).unwrap(), "42");
+        let t: TimestampMillis = serde_json::from_str("1634000000000").unwrap();
+        assert_eq!(t, TimestampMillis::new(1634000000000));
+        let n: NanoErg = serde_json::from_str("1000000").unwrap();
+        assert_eq!(u64::from(n), 1000000);
+    }
+}
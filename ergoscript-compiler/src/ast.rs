@@ -63,6 +63,7 @@ pub enum Expr {
     Ident(Ident),
     BinaryExpr(BinaryExpr),
     Literal(Literal),
+    CallExpr(CallExpr),
     // ParenExpr(ParenExpr),
     // UnaryExpr(UnaryExpr),
 }
@@ -74,6 +75,7 @@ impl Expr {
             SyntaxKind::InfixExpr => Self::BinaryExpr(BinaryExpr(node)),
             SyntaxKind::IntNumber => Self::Literal(Literal(node)),
             SyntaxKind::LongNumber => Self::Literal(Literal(node)),
+            SyntaxKind::CallExpr => Self::CallExpr(CallExpr(node)),
             // SyntaxKind::ParenExpr => Self::ParenExpr(ParenExpr(node)),
             // SyntaxKind::PrefixExpr => Self::UnaryExpr(UnaryExpr(node)),
             _ => return None,
@@ -143,6 +145,78 @@ impl BinaryExpr {
     }
 }
 
+#[derive(Debug)]
+pub struct CallExpr(SyntaxNode);
+
+impl CallExpr {
+    pub fn name(&self) -> Result<SyntaxToken, AstError> {
+        self.0
+            .children()
+            .find(|node| node.kind() == SyntaxKind::Ident)
+            .map(Ident)
+            .ok_or_else(|| {
+                AstError::new(
+                    format!("Cannot find callee in {:?}", self.0),
+                    self.0.text_range(),
+                )
+            })?
+            .name()
+    }
+
+    pub fn type_args(&self) -> Vec<TypeRef> {
+        type_args(&self.0)
+    }
+
+    pub fn args(&self) -> Vec<Expr> {
+        self.0
+            .children()
+            .find(|node| node.kind() == SyntaxKind::ArgList)
+            .map(|args| args.children().filter_map(Expr::cast).collect())
+            .unwrap_or_default()
+    }
+
+    pub fn span(&self) -> TextRange {
+        self.0.text_range()
+    }
+}
+
+#[derive(Debug)]
+pub struct TypeRef(SyntaxNode);
+
+impl TypeRef {
+    pub fn name(&self) -> Result<SyntaxToken, AstError> {
+        self.0
+            .children_with_tokens()
+            .filter_map(SyntaxElement::into_token)
+            .find(|token| token.kind() == SyntaxKind::Ident)
+            .ok_or_else(|| AstError::new(format!("Empty type name in: {:?}", self.0), self.span()))
+    }
+
+    pub fn type_args(&self) -> Vec<TypeRef> {
+        type_args(&self.0)
+    }
+
+    pub fn text(&self) -> String {
+        self.0.text().to_string().trim().to_string()
+    }
+
+    pub fn span(&self) -> TextRange {
+        self.0.text_range()
+    }
+}
+
+fn type_args(node: &SyntaxNode) -> Vec<TypeRef> {
+    node.children()
+        .find(|node| node.kind() == SyntaxKind::TypeArgs)
+        .map(|args| {
+            args.children()
+                .filter(|node| node.kind() == SyntaxKind::TypeRef)
+                .map(TypeRef)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
 #[derive(Debug)]
 pub enum LiteralValue {
     Int(i32),
@@ -1,5 +1,9 @@
+use ergotree_ir::types::stype::SType;
 use rowan::TextRange;
 
+use crate::diagnostic::source_span;
+use crate::diagnostic::Diagnostic;
+use crate::diagnostic::ErrorCode;
 use crate::error::pretty_error_desc;
 use crate::hir;
 use crate::hir::Expr;
@@ -21,6 +25,14 @@ impl BinderError {
     pub fn pretty_desc(&self, source: &str) -> String {
         pretty_error_desc(source, self.span, &self.msg)
     }
+
+    pub fn diagnostic(&self) -> Diagnostic {
+        Diagnostic::new(
+            ErrorCode::BinderError,
+            source_span(self.span),
+            self.msg.clone(),
+        )
+    }
 }
 
 pub struct Binder {
@@ -55,6 +67,40 @@ fn rewrite(expr: Expr, env: &ScriptEnv) -> Result<Expr, BinderError> {
                     _ => None,
                 },
             },
+            ExprKind::GetVar(get_var) => {
+                let declared_tpe = env.context_var_type(get_var.var_id);
+                let var_tpe = match (&get_var.var_tpe, declared_tpe) {
+                    (Some(tpe), Some(declared)) if tpe != declared => {
+                        return Err(BinderError::new(
+                            format!(
+                                "context variable {} is declared as {:?}, but used as {:?}",
+                                get_var.var_id, declared, tpe
+                            ),
+                            e.span,
+                        ))
+                    }
+                    (Some(_), _) => return Ok(None),
+                    (None, Some(declared)) => declared.clone(),
+                    (None, None) => {
+                        return Err(BinderError::new(
+                            format!(
+                                "type of context variable {} is not declared, use getVar[T]({})",
+                                get_var.var_id, get_var.var_id
+                            ),
+                            e.span,
+                        ))
+                    }
+                };
+                Some(Expr {
+                    kind: hir::GetVar {
+                        var_id: get_var.var_id,
+                        var_tpe: Some(var_tpe.clone()),
+                    }
+                    .into(),
+                    span: e.span,
+                    tpe: Some(SType::SOption(var_tpe.into())),
+                })
+            }
             _ => None,
         })
     })
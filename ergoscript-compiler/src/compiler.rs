@@ -4,6 +4,8 @@ use super::binder::BinderError;
 use super::hir::HirLoweringError;
 use crate::ast;
 use crate::binder::Binder;
+use crate::diagnostic::Diagnostic;
+use crate::diagnostic::ErrorCode;
 use crate::hir;
 use crate::mir;
 use crate::parser::parse_error::ParseError;
@@ -16,6 +18,7 @@ extern crate derive_more;
 use derive_more::From;
 use ergotree_ir::ergo_tree::ErgoTree;
 use ergotree_ir::ergo_tree::ErgoTreeError;
+use ergotree_ir::source_span::SourceSpan;
 use ergotree_ir::type_check::TypeCheckError;
 use mir::lower::MirLoweringError;
 
@@ -53,6 +56,28 @@ impl CompileError {
             CompileError::ErgoTreeError(e) => format!("{:?}", e),
         }
     }
+
+    /// Structured diagnostics (error code, position in the source code, message) for all errors.
+    /// Parser recovers from syntax errors, so all of them are reported at once.
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        match self {
+            CompileError::ParseError(errors) => errors.iter().map(|e| e.diagnostic()).collect(),
+            CompileError::HirLoweringError(e) => vec![e.diagnostic()],
+            CompileError::BinderError(e) => vec![e.diagnostic()],
+            CompileError::TypeInferenceError(e) => vec![e.diagnostic()],
+            CompileError::MirLoweringError(e) => vec![e.diagnostic()],
+            CompileError::TypeCheckError(e) => vec![Diagnostic::new(
+                ErrorCode::TypeCheckError,
+                SourceSpan::empty(),
+                e.pretty_desc(),
+            )],
+            CompileError::ErgoTreeError(e) => vec![Diagnostic::new(
+                ErrorCode::ErgoTreeError,
+                SourceSpan::empty(),
+                format!("{:?}", e),
+            )],
+        }
+    }
 }
 
 /// Compiles given source code to [`ergotree_ir::mir::expr::Expr`], or returns an error
@@ -99,6 +124,8 @@ fn check(input: &str, expected_tree: expect_test::Expect) {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use ergotree_ir::mir::get_var::GetVar;
+    use ergotree_ir::types::stype::SType;
     use expect_test::expect;
 
     #[test]
@@ -117,10 +144,80 @@ mod tests {
         check(
             "HSB.HEIGHT",
             expect![[r#"
-                error: expected ‘+’, ‘-’, ‘*’, ‘/’, ‘val’, number, number, identifier, ‘-’ or ‘(’, but found an unrecognized token
+                error: expected ‘[’, ‘(’, ‘+’, ‘-’, ‘*’, ‘/’, ‘val’, number, number, identifier, ‘-’ or ‘(’, but found an unrecognized token
                 line: 1
                 HSB.HEIGHT
                   ^^"#]],
         );
     }
+
+    #[test]
+    fn test_diagnostics_multiple_syntax_errors() {
+        let source = "val a = )\nval b = val c = 1";
+        let diagnostics = compile_expr(source, ScriptEnv::new())
+            .unwrap_err()
+            .diagnostics();
+        assert_eq!(diagnostics.len(), 2);
+        assert!(diagnostics
+            .iter()
+            .all(|d| d.code == ErrorCode::SyntaxError && d.code.code() == "E0001"));
+        assert_eq!(diagnostics[0].found, Some("‘)’".to_string()));
+        assert_eq!(diagnostics[0].line_col(source), (1, 9));
+        // recovered on `val` keyword without consuming it
+        assert_eq!(
+            diagnostics[1].source_span,
+            SourceSpan {
+                offset: 18,
+                length: 3
+            }
+        );
+        assert_eq!(diagnostics[1].found, Some("‘val’".to_string()));
+        assert_eq!(diagnostics[1].line_col(source), (2, 9));
+    }
+
+    #[test]
+    fn test_get_var() {
+        check(
+            "getVar[Coll[Byte]](1)",
+            expect![[r#"
+                GetVar(
+                    GetVar {
+                        var_id: 1,
+                        var_tpe: SColl(
+                            SByte,
+                        ),
+                    },
+                )"#]],
+        );
+    }
+
+    #[test]
+    fn test_get_var_declared_in_env() {
+        let env = || ScriptEnv::new().with_context_var(2, SType::SLong);
+        let expected: ergotree_ir::mir::expr::Expr = GetVar {
+            var_id: 2,
+            var_tpe: SType::SLong,
+        }
+        .into();
+        assert_eq!(compile_expr("getVar(2)", env()).unwrap(), expected);
+        assert_eq!(compile_expr("getVar[Long](2)", env()).unwrap(), expected);
+        let error_code =
+            |source: &str| compile_expr(source, env()).unwrap_err().diagnostics()[0].code;
+        // declared with another type
+        assert_eq!(error_code("getVar[Int](2)"), ErrorCode::BinderError);
+        // type is neither declared nor given
+        assert_eq!(error_code("getVar(3)"), ErrorCode::BinderError);
+        assert_eq!(error_code("getVar[Int](256)"), ErrorCode::HirLoweringError);
+        assert_eq!(
+            error_code("getVar[Int](HEIGHT)"),
+            ErrorCode::HirLoweringError
+        );
+        assert_eq!(
+            error_code("getVar[Int, Long](1)"),
+            ErrorCode::HirLoweringError
+        );
+        assert_eq!(error_code("getVar[Foo](1)"), ErrorCode::HirLoweringError);
+        assert_eq!(error_code("getVar[Coll](1)"), ErrorCode::HirLoweringError);
+        assert_eq!(error_code("foo(1)"), ErrorCode::HirLoweringError);
+    }
 }
@@ -0,0 +1,100 @@
+//! Structured compiler diagnostics
+
+use ergotree_ir::source_span::SourceSpan;
+use line_col::LineColLookup;
+use text_size::TextRange;
+
+/// Kind of the diagnostic
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ErrorCode {
+    /// Unexpected token or malformed syntax
+    SyntaxError,
+    /// Error on AST to HIR lowering
+    HirLoweringError,
+    /// Error on binder pass (e.g. unresolved name)
+    BinderError,
+    /// Error on type inference pass
+    TypeInferenceError,
+    /// Error on HIR to MIR lowering
+    MirLoweringError,
+    /// Error on type checking
+    TypeCheckError,
+    /// Error on ErgoTree construction
+    ErgoTreeError,
+}
+
+impl ErrorCode {
+    /// Stable code to refer the diagnostic kind (e.g. in docs)
+    pub fn code(&self) -> &'static str {
+        match self {
+            ErrorCode::SyntaxError => "E0001",
+            ErrorCode::HirLoweringError => "E0002",
+            ErrorCode::BinderError => "E0003",
+            ErrorCode::TypeInferenceError => "E0004",
+            ErrorCode::MirLoweringError => "E0005",
+            ErrorCode::TypeCheckError => "E0006",
+            ErrorCode::ErgoTreeError => "E0007",
+        }
+    }
+}
+
+/// Compiler diagnostic (error)
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Diagnostic {
+    /// Kind of the diagnostic
+    pub code: ErrorCode,
+    /// Position in the source code (empty if not known)
+    pub source_span: SourceSpan,
+    /// Error message
+    pub message: String,
+    /// Tokens the parser expected (syntax errors only)
+    pub expected: Vec<String>,
+    /// Token the parser found instead (syntax errors only)
+    pub found: Option<String>,
+}
+
+impl Diagnostic {
+    pub(crate) fn new(code: ErrorCode, source_span: SourceSpan, message: String) -> Self {
+        Diagnostic {
+            code,
+            source_span,
+            message,
+            expected: Vec::new(),
+            found: None,
+        }
+    }
+
+    /// One-based line and column of the diagnostic position in the source code
+    pub fn line_col(&self, source: &str) -> (usize, usize) {
+        line_col(source, self.source_span)
+    }
+}
+
+/// One-based line and column of the span start in the source code.
+/// Can be used to map evaluation errors (see `EvalError::Spanned`) back to the source code.
+pub fn line_col(source: &str, source_span: SourceSpan) -> (usize, usize) {
+    LineColLookup::new(source).get(source_span.offset.min(source.len()))
+}
+
+pub(crate) fn source_span(range: TextRange) -> SourceSpan {
+    SourceSpan {
+        offset: usize::from(range.start()),
+        length: usize::from(range.len()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_col() {
+        let source = "val a = 1\nval b = HEIGHT";
+        let span = SourceSpan {
+            offset: 18,
+            length: 6,
+        };
+        assert_eq!(line_col(source, span), (2, 9));
+        assert_eq!(line_col(source, SourceSpan::empty()), (1, 1));
+    }
+}
@@ -8,8 +8,12 @@ pub use rewrite::rewrite;
 
 use super::ast;
 use crate::ast::AstError;
+use crate::diagnostic::source_span;
+use crate::diagnostic::Diagnostic;
+use crate::diagnostic::ErrorCode;
 use crate::error::pretty_error_desc;
 use crate::syntax::SyntaxKind;
+use std::convert::TryFrom;
 use text_size::TextRange;
 
 extern crate derive_more;
@@ -50,6 +54,14 @@ impl HirLoweringError {
     pub fn pretty_desc(&self, source: &str) -> String {
         pretty_error_desc(source, self.span, &self.msg)
     }
+
+    pub fn diagnostic(&self) -> Diagnostic {
+        Diagnostic::new(
+            ErrorCode::HirLoweringError,
+            source_span(self.span),
+            self.msg.clone(),
+        )
+    }
 }
 
 impl From<AstError> for HirLoweringError {
@@ -90,6 +102,24 @@ impl Expr {
                 };
                 Ok(expr)
             }
+            ast::Expr::CallExpr(ast) => {
+                let name = ast.name()?;
+                match name.text() {
+                    "getVar" => {
+                        let get_var = GetVar::lower(ast)?;
+                        let tpe = get_var.var_tpe.clone().map(|t| SType::SOption(t.into()));
+                        Ok(Expr {
+                            kind: get_var.into(),
+                            span: ast.span(),
+                            tpe,
+                        })
+                    }
+                    _ => Err(HirLoweringError::new(
+                        format!("unknown function: {}", name.text()),
+                        name.text_range(),
+                    )),
+                }
+            }
         }
     }
 
@@ -143,12 +173,92 @@ impl Binary {
     }
 }
 
+/// Context variable, `getVar[T](id)`
+#[derive(Debug, PartialEq, Clone)]
+pub struct GetVar {
+    pub var_id: u8,
+    /// Type from the type argument, if omitted it's the type declared in the script env
+    pub var_tpe: Option<SType>,
+}
+
+impl GetVar {
+    fn lower(ast: &ast::CallExpr) -> Result<GetVar, HirLoweringError> {
+        let var_tpe = match ast.type_args().as_slice() {
+            [] => None,
+            [tpe] => Some(lower_type(tpe)?),
+            _ => {
+                return Err(HirLoweringError::new(
+                    "getVar expects one type argument".to_string(),
+                    ast.span(),
+                ))
+            }
+        };
+        let args = ast.args();
+        let var_id = match args.as_slice() {
+            [arg] => match Expr::lower(arg)? {
+                Expr {
+                    kind: ExprKind::Literal(Literal::Int(id)),
+                    span,
+                    ..
+                } => u8::try_from(id).map_err(|_| {
+                    HirLoweringError::new(
+                        format!("context variable id is out of range [0, 255]: {}", id),
+                        span,
+                    )
+                })?,
+                e => {
+                    return Err(HirLoweringError::new(
+                        "context variable id should be an Int literal".to_string(),
+                        e.span,
+                    ))
+                }
+            },
+            _ => {
+                return Err(HirLoweringError::new(
+                    "getVar expects one argument (context variable id)".to_string(),
+                    ast.span(),
+                ))
+            }
+        };
+        Ok(GetVar { var_id, var_tpe })
+    }
+}
+
+fn lower_type(ast: &ast::TypeRef) -> Result<SType, HirLoweringError> {
+    let name = ast.name()?;
+    let args = ast.type_args();
+    let tpe = match (name.text(), args.as_slice()) {
+        ("Boolean", []) => SType::SBoolean,
+        ("Byte", []) => SType::SByte,
+        ("Short", []) => SType::SShort,
+        ("Int", []) => SType::SInt,
+        ("Long", []) => SType::SLong,
+        ("BigInt", []) => SType::SBigInt,
+        ("GroupElement", []) => SType::SGroupElement,
+        ("SigmaProp", []) => SType::SSigmaProp,
+        ("Box", []) => SType::SBox,
+        ("AvlTree", []) => SType::SAvlTree,
+        ("Header", []) => SType::SHeader,
+        ("PreHeader", []) => SType::SPreHeader,
+        ("Coll", [elem]) => SType::SColl(lower_type(elem)?.into()),
+        ("Option", [elem]) => SType::SOption(lower_type(elem)?.into()),
+        _ => {
+            return Err(HirLoweringError::new(
+                format!("unknown type: {}", ast.text()),
+                ast.span(),
+            ))
+        }
+    };
+    Ok(tpe)
+}
+
 #[derive(Debug, PartialEq, From, Clone)]
 pub enum ExprKind {
     Ident(String),
     Binary(Binary),
     GlobalVars(GlobalVars),
     Literal(Literal),
+    GetVar(GetVar),
     // ...
     // Block
     // ValNode
@@ -25,5 +25,6 @@ pub fn rewrite<E, F: Fn(&Expr) -> Result<Option<Expr>, E>>(e: Expr, f: F) -> Res
         ExprKind::Ident(_) => f(&e)?.unwrap_or(e), // TODO: duplicate call to f?
         ExprKind::GlobalVars(_) => f(&e)?.unwrap_or(e),
         ExprKind::Literal(_) => f(&e)?.unwrap_or(e),
+        ExprKind::GetVar(_) => f(&e)?.unwrap_or(e),
     })
 }
@@ -53,6 +53,15 @@ pub enum TokenKind {
     #[token("}")]
     RBrace,
 
+    #[token("[")]
+    LBracket,
+
+    #[token("]")]
+    RBracket,
+
+    #[token(",")]
+    Comma,
+
     #[regex("//.*")]
     Comment,
 
@@ -85,6 +94,9 @@ impl fmt::Display for TokenKind {
             Self::RParen => "‘)’",
             Self::LBrace => "‘{’",
             Self::RBrace => "‘}’",
+            Self::LBracket => "‘[’",
+            Self::RBracket => "‘]’",
+            Self::Comma => "‘,’",
             Self::Comment => "comment",
             Self::Error => "an unrecognized token",
         })
@@ -189,6 +201,21 @@ mod tests {
         check("}", TokenKind::RBrace);
     }
 
+    #[test]
+    fn lex_left_bracket() {
+        check("[", TokenKind::LBracket);
+    }
+
+    #[test]
+    fn lex_right_bracket() {
+        check("]", TokenKind::RBracket);
+    }
+
+    #[test]
+    fn lex_comma() {
+        check(",", TokenKind::Comma);
+    }
+
     #[test]
     fn lex_comment() {
         check("// foo", TokenKind::Comment);
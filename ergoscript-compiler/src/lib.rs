@@ -24,4 +24,5 @@ pub(crate) mod syntax;
 pub(crate) mod type_infer;
 
 pub mod compiler;
+pub mod diagnostic;
 pub mod script_env;
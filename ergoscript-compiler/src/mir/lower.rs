@@ -3,10 +3,15 @@ use ergotree_ir::mir::bin_op::BinOp;
 use ergotree_ir::mir::bin_op::BinOpKind;
 use ergotree_ir::mir::constant::Constant;
 use ergotree_ir::mir::expr::Expr;
+use ergotree_ir::mir::get_var::GetVar;
 use ergotree_ir::mir::global_vars::GlobalVars;
+use ergotree_ir::source_span::Spanned;
 use hir::BinaryOp;
 use rowan::TextRange;
 
+use crate::diagnostic::source_span;
+use crate::diagnostic::Diagnostic;
+use crate::diagnostic::ErrorCode;
 use crate::error::pretty_error_desc;
 use crate::hir;
 
@@ -24,6 +29,14 @@ impl MirLoweringError {
     pub fn pretty_desc(&self, source: &str) -> String {
         pretty_error_desc(source, self.span, &self.msg)
     }
+
+    pub fn diagnostic(&self) -> Diagnostic {
+        Diagnostic::new(
+            ErrorCode::MirLoweringError,
+            source_span(self.span),
+            self.msg.clone(),
+        )
+    }
 }
 
 pub fn lower(hir_expr: hir::Expr) -> Result<Expr, MirLoweringError> {
@@ -40,12 +53,14 @@ pub fn lower(hir_expr: hir::Expr) -> Result<Expr, MirLoweringError> {
         hir::ExprKind::Binary(hir) => {
             let l = lower(*hir.lhs.clone())?;
             let r = lower(*hir.rhs.clone())?;
-            BinOp {
-                kind: hir.op.node.clone().into(),
-                left: l.into(),
-                right: r.into(),
-            }
-            .into()
+            Expr::BinOp(Spanned::new(
+                BinOp {
+                    kind: hir.op.node.clone().into(),
+                    left: l.into(),
+                    right: r.into(),
+                },
+                source_span(hir_expr.span),
+            ))
         }
         hir::ExprKind::Literal(hir) => {
             let constant: Constant = match *hir {
@@ -54,6 +69,19 @@ pub fn lower(hir_expr: hir::Expr) -> Result<Expr, MirLoweringError> {
             };
             constant.into()
         }
+        hir::ExprKind::GetVar(hir) => match &hir.var_tpe {
+            Some(var_tpe) => GetVar {
+                var_id: hir.var_id,
+                var_tpe: var_tpe.clone(),
+            }
+            .into(),
+            None => {
+                return Err(MirLoweringError::new(
+                    format!("MIR error: unresolved type of context variable {0:?}", hir),
+                    hir_expr.span,
+                ))
+            }
+        },
     };
     let hir_tpe = hir_expr.tpe.clone().ok_or_else(|| {
         MirLoweringError::new(
@@ -111,16 +139,22 @@ mod tests {
             "HEIGHT + HEIGHT",
             expect![[r#"
             BinOp(
-                BinOp {
-                    kind: Arith(
-                        Plus,
-                    ),
-                    left: GlobalVars(
-                        Height,
-                    ),
-                    right: GlobalVars(
-                        Height,
-                    ),
+                Spanned {
+                    source_span: SourceSpan {
+                        offset: 0,
+                        length: 15,
+                    },
+                    expr: BinOp {
+                        kind: Arith(
+                            Plus,
+                        ),
+                        left: GlobalVars(
+                            Height,
+                        ),
+                        right: GlobalVars(
+                            Height,
+                        ),
+                    },
                 },
             )"#]],
         )
@@ -164,26 +198,32 @@ mod tests {
             "4+2",
             expect![[r#"
             BinOp(
-                BinOp {
-                    kind: Arith(
-                        Plus,
-                    ),
-                    left: Const(
-                        Constant {
-                            tpe: SInt,
-                            v: Int(
-                                4,
-                            ),
-                        },
-                    ),
-                    right: Const(
-                        Constant {
-                            tpe: SInt,
-                            v: Int(
-                                2,
-                            ),
-                        },
-                    ),
+                Spanned {
+                    source_span: SourceSpan {
+                        offset: 0,
+                        length: 3,
+                    },
+                    expr: BinOp {
+                        kind: Arith(
+                            Plus,
+                        ),
+                        left: Const(
+                            Constant {
+                                tpe: SInt,
+                                v: Int(
+                                    4,
+                                ),
+                            },
+                        ),
+                        right: Const(
+                            Constant {
+                                tpe: SInt,
+                                v: Int(
+                                    2,
+                                ),
+                            },
+                        ),
+                    },
                 },
             )"#]],
         );
@@ -195,26 +235,32 @@ mod tests {
             "4L+2L",
             expect![[r#"
             BinOp(
-                BinOp {
-                    kind: Arith(
-                        Plus,
-                    ),
-                    left: Const(
-                        Constant {
-                            tpe: SLong,
-                            v: Long(
-                                4,
-                            ),
-                        },
-                    ),
-                    right: Const(
-                        Constant {
-                            tpe: SLong,
-                            v: Long(
-                                2,
-                            ),
-                        },
-                    ),
+                Spanned {
+                    source_span: SourceSpan {
+                        offset: 0,
+                        length: 5,
+                    },
+                    expr: BinOp {
+                        kind: Arith(
+                            Plus,
+                        ),
+                        left: Const(
+                            Constant {
+                                tpe: SLong,
+                                v: Long(
+                                    4,
+                                ),
+                            },
+                        ),
+                        right: Const(
+                            Constant {
+                                tpe: SLong,
+                                v: Long(
+                                    2,
+                                ),
+                            },
+                        ),
+                    },
                 },
             )"#]],
         );
@@ -21,7 +21,7 @@ use self::marker::Marker;
 use self::parse_error::ParseError;
 use self::source::Source;
 
-// const RECOVERY_SET: [TokenKind; 1] = [TokenKind::ValKw];
+const RECOVERY_SET: [TokenKind; 1] = [TokenKind::ValKw];
 
 pub struct Parser<'t, 'input> {
     pub source: Source<'t, 'input>,
@@ -75,8 +75,7 @@ impl<'t, 'input> Parser<'t, 'input> {
             span: range,
         }));
 
-        // if !self.at_set(&RECOVERY_SET) && !self.at_end() {
-        if !self.at_end() {
+        if !self.at_set(&RECOVERY_SET) && !self.at_end() {
             let m = self.start();
             self.bump();
             m.complete(self, SyntaxKind::Error);
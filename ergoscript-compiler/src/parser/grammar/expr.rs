@@ -180,7 +180,61 @@ fn ident(p: &mut Parser) -> CompletedMarker {
 
     let m = p.start();
     p.bump();
-    m.complete(p, SyntaxKind::Ident)
+    let ident = m.complete(p, SyntaxKind::Ident);
+
+    if p.at(TokenKind::LBracket) || p.at(TokenKind::LParen) {
+        call_expr(p, ident)
+    } else {
+        ident
+    }
+}
+
+// f[T1, T2](arg1, arg2), type arguments are optional
+fn call_expr(p: &mut Parser, callee: CompletedMarker) -> CompletedMarker {
+    let m = callee.precede(p);
+    if p.at(TokenKind::LBracket) {
+        type_args(p);
+    }
+    arg_list(p);
+    m.complete(p, SyntaxKind::CallExpr)
+}
+
+fn type_args(p: &mut Parser) -> CompletedMarker {
+    assert!(p.at(TokenKind::LBracket));
+
+    let m = p.start();
+    p.bump();
+    type_ref(p);
+    while p.at(TokenKind::Comma) {
+        p.bump();
+        type_ref(p);
+    }
+    p.expect(TokenKind::RBracket);
+    m.complete(p, SyntaxKind::TypeArgs)
+}
+
+// type name with optional type arguments (e.g. `Coll[Byte]`)
+fn type_ref(p: &mut Parser) -> CompletedMarker {
+    let m = p.start();
+    p.expect(TokenKind::Ident);
+    if p.at(TokenKind::LBracket) {
+        type_args(p);
+    }
+    m.complete(p, SyntaxKind::TypeRef)
+}
+
+fn arg_list(p: &mut Parser) -> CompletedMarker {
+    let m = p.start();
+    p.expect(TokenKind::LParen);
+    if !p.at(TokenKind::RParen) {
+        expr_binding_power(p, 0);
+        while p.at(TokenKind::Comma) {
+            p.bump();
+            expr_binding_power(p, 0);
+        }
+    }
+    p.expect(TokenKind::RParen);
+    m.complete(p, SyntaxKind::ArgList)
 }
 
 fn prefix_expr(p: &mut Parser) -> CompletedMarker {
@@ -472,4 +526,73 @@ mod tests {
                       RParen@6..7 ")""#]],
         );
     }
+
+    #[test]
+    fn parse_call_with_type_args() {
+        check(
+            "getVar[Int](1)",
+            expect![[r#"
+                Root@0..14
+                  CallExpr@0..14
+                    Ident@0..6
+                      Ident@0..6 "getVar"
+                    TypeArgs@6..11
+                      LBracket@6..7 "["
+                      TypeRef@7..10
+                        Ident@7..10 "Int"
+                      RBracket@10..11 "]"
+                    ArgList@11..14
+                      LParen@11..12 "("
+                      IntNumber@12..13
+                        IntNumber@12..13 "1"
+                      RParen@13..14 ")""#]],
+        );
+    }
+
+    #[test]
+    fn parse_call_with_nested_type_args() {
+        check(
+            "getVar[Coll[Byte]](2)",
+            expect![[r#"
+                Root@0..21
+                  CallExpr@0..21
+                    Ident@0..6
+                      Ident@0..6 "getVar"
+                    TypeArgs@6..18
+                      LBracket@6..7 "["
+                      TypeRef@7..17
+                        Ident@7..11 "Coll"
+                        TypeArgs@11..17
+                          LBracket@11..12 "["
+                          TypeRef@12..16
+                            Ident@12..16 "Byte"
+                          RBracket@16..17 "]"
+                      RBracket@17..18 "]"
+                    ArgList@18..21
+                      LParen@18..19 "("
+                      IntNumber@19..20
+                        IntNumber@19..20 "2"
+                      RParen@20..21 ")""#]],
+        );
+    }
+
+    #[test]
+    fn parse_call_without_type_args() {
+        check(
+            "f(1,x)",
+            expect![[r#"
+                Root@0..6
+                  CallExpr@0..6
+                    Ident@0..1
+                      Ident@0..1 "f"
+                    ArgList@1..6
+                      LParen@1..2 "("
+                      IntNumber@2..3
+                        IntNumber@2..3 "1"
+                      Comma@3..4 ","
+                      Ident@4..5
+                        Ident@4..5 "x"
+                      RParen@5..6 ")""#]],
+        );
+    }
 }
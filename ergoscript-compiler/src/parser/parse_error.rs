@@ -1,6 +1,9 @@
 use std::fmt;
 use text_size::TextRange;
 
+use crate::diagnostic::source_span;
+use crate::diagnostic::Diagnostic;
+use crate::diagnostic::ErrorCode;
 use crate::error::pretty_error_desc;
 use crate::lexer::TokenKind;
 
@@ -15,6 +18,16 @@ impl ParseError {
     pub fn pretty_desc(&self, source: &str) -> String {
         pretty_error_desc(source, self.span, &self.to_string())
     }
+
+    pub fn diagnostic(&self) -> Diagnostic {
+        Diagnostic {
+            code: ErrorCode::SyntaxError,
+            source_span: source_span(self.span),
+            message: self.to_string(),
+            expected: self.expected.iter().map(|kind| kind.to_string()).collect(),
+            found: self.found.map(|kind| kind.to_string()),
+        }
+    }
 }
 
 impl fmt::Display for ParseError {
@@ -3,9 +3,14 @@
 use std::collections::HashMap;
 
 use ergotree_ir::mir::constant::Constant;
+use ergotree_ir::types::stype::SType;
 
-/// Environment with values substituted for identifiers during compilation
-pub struct ScriptEnv(HashMap<String, Constant>);
+/// Environment with values substituted for identifiers during compilation, and the types of the
+/// context variables (`getVar`) expected in the context extension of the spending input
+pub struct ScriptEnv {
+    values: HashMap<String, Constant>,
+    context_vars: HashMap<u8, SType>,
+}
 
 impl Default for ScriptEnv {
     fn default() -> Self {
@@ -16,11 +21,26 @@ impl Default for ScriptEnv {
 impl ScriptEnv {
     /// Empty environment
     pub fn new() -> Self {
-        ScriptEnv(HashMap::new())
+        ScriptEnv {
+            values: HashMap::new(),
+            context_vars: HashMap::new(),
+        }
     }
 
     /// Returns the value([`Constant`]) for the given identifier (if any)
     pub fn get(&self, ident: &str) -> Option<&Constant> {
-        self.0.get(ident)
+        self.values.get(ident)
+    }
+
+    /// Declare the type of the context variable with the given id. `getVar(id)` without the type
+    /// argument is compiled with this type, and `getVar[T](id)` with another type fails to compile.
+    pub fn with_context_var(mut self, id: u8, tpe: SType) -> Self {
+        self.context_vars.insert(id, tpe);
+        self
+    }
+
+    /// Returns the declared type of the context variable with the given id (if any)
+    pub fn context_var_type(&self, id: u8) -> Option<&SType> {
+        self.context_vars.get(&id)
     }
 }
@@ -22,6 +22,9 @@ pub enum SyntaxKind {
     RParen,
     LBrace,
     RBrace,
+    LBracket,
+    RBracket,
+    Comma,
     Comment,
     Error,
     Root,
@@ -29,6 +32,10 @@ pub enum SyntaxKind {
     ParenExpr,
     PrefixExpr,
     VariableDef,
+    CallExpr,
+    TypeArgs,
+    TypeRef,
+    ArgList,
 }
 
 impl From<TokenKind> for SyntaxKind {
@@ -49,6 +56,9 @@ impl From<TokenKind> for SyntaxKind {
             TokenKind::RParen => Self::RParen,
             TokenKind::LBrace => Self::LBrace,
             TokenKind::RBrace => Self::RBrace,
+            TokenKind::LBracket => Self::LBracket,
+            TokenKind::RBracket => Self::RBracket,
+            TokenKind::Comma => Self::Comma,
             TokenKind::Comment => Self::Comment,
             TokenKind::Error => Self::Error,
             TokenKind::And => Self::And,
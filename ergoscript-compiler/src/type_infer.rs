@@ -1,5 +1,8 @@
 use rowan::TextRange;
 
+use crate::diagnostic::source_span;
+use crate::diagnostic::Diagnostic;
+use crate::diagnostic::ErrorCode;
 use crate::error::pretty_error_desc;
 use crate::hir;
 use crate::hir::Binary;
@@ -20,6 +23,14 @@ impl TypeInferenceError {
     pub fn pretty_desc(&self, source: &str) -> String {
         pretty_error_desc(source, self.span, &self.msg)
     }
+
+    pub fn diagnostic(&self) -> Diagnostic {
+        Diagnostic::new(
+            ErrorCode::TypeInferenceError,
+            source_span(self.span),
+            self.msg.clone(),
+        )
+    }
 }
 
 pub fn assign_type(expr: Expr) -> Result<Expr, TypeInferenceError> {
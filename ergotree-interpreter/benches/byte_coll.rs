@@ -0,0 +1,112 @@
+//! Coll[Byte] heavy evaluation (xor, hashing)
+//!
+//! Besides the timings, prints the number of heap allocations per evaluation.
+
+use std::alloc::GlobalAlloc;
+use std::alloc::Layout;
+use std::alloc::System;
+use std::rc::Rc;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+
+use criterion::criterion_group;
+use criterion::criterion_main;
+use criterion::Criterion;
+use ergotree_interpreter::eval::context::Context;
+use ergotree_interpreter::eval::env::Env;
+use ergotree_interpreter::eval::reduce_to_crypto;
+use ergotree_ir::mir::bin_op::BinOp;
+use ergotree_ir::mir::bin_op::RelationOp;
+use ergotree_ir::mir::calc_blake2b256::CalcBlake2b256;
+use ergotree_ir::mir::calc_sha256::CalcSha256;
+use ergotree_ir::mir::expr::Expr;
+use ergotree_ir::mir::xor::Xor;
+use sigma_test_util::force_any_val;
+
+struct CountingAlloc;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+const COLL_SIZE: usize = 4096;
+
+fn bytes(seed: u8) -> Vec<u8> {
+    (0..COLL_SIZE)
+        .map(|i| (i as u8).wrapping_mul(seed))
+        .collect()
+}
+
+fn eq(left: Expr, right: Expr) -> Expr {
+    BinOp {
+        kind: RelationOp::Eq.into(),
+        left: Box::new(left),
+        right: Box::new(right),
+    }
+    .into()
+}
+
+fn xor_expr() -> Expr {
+    let left = bytes(3);
+    let right = bytes(7);
+    let expected: Vec<u8> = left.iter().zip(right.iter()).map(|(l, r)| l ^ r).collect();
+    let xor: Expr = Xor {
+        left: Box::new(left.into()),
+        right: Box::new(right.into()),
+    }
+    .into();
+    eq(xor, expected.into())
+}
+
+fn blake2b256_expr() -> Expr {
+    let input = bytes(5);
+    let expected = sigma_util::hash::blake2b256_hash(&input).to_vec();
+    let hash: Expr = CalcBlake2b256 {
+        input: Box::new(input.into()),
+    }
+    .into();
+    eq(hash, expected.into())
+}
+
+fn sha256_expr() -> Expr {
+    let input = bytes(11);
+    let expected = sigma_util::hash::sha256_hash(&input).to_vec();
+    let hash: Expr = CalcSha256 {
+        input: Box::new(input.into()),
+    }
+    .into();
+    eq(hash, expected.into())
+}
+
+fn bench_expr(c: &mut Criterion, name: &str, expr: Expr) {
+    let ctx = Rc::new(force_any_val::<Context>());
+    let env = Env::empty();
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    reduce_to_crypto(&expr, &env, ctx.clone()).unwrap();
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - before;
+    println!("{}: {} allocations per evaluation", name, allocations);
+    c.bench_function(name, |b| {
+        b.iter(|| reduce_to_crypto(&expr, &env, ctx.clone()).unwrap())
+    });
+}
+
+fn byte_coll_benchmark(c: &mut Criterion) {
+    bench_expr(c, "xor", xor_expr());
+    bench_expr(c, "blake2b256", blake2b256_expr());
+    bench_expr(c, "sha256", sha256_expr());
+}
+
+criterion_group!(benches, byte_coll_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,103 @@
+//! Collection fold in a large environment (many vals in scope), the lambda environment is
+//! extended on every fold step
+
+use std::convert::TryInto;
+use std::rc::Rc;
+
+use criterion::criterion_group;
+use criterion::criterion_main;
+use criterion::Criterion;
+use ergotree_interpreter::eval::context::Context;
+use ergotree_interpreter::eval::env::Env;
+use ergotree_interpreter::eval::reduce_to_crypto;
+use ergotree_ir::mir::bin_op::ArithOp;
+use ergotree_ir::mir::bin_op::BinOp;
+use ergotree_ir::mir::bin_op::RelationOp;
+use ergotree_ir::mir::block::BlockValue;
+use ergotree_ir::mir::coll_fold::Fold;
+use ergotree_ir::mir::expr::Expr;
+use ergotree_ir::mir::func_value::FuncArg;
+use ergotree_ir::mir::func_value::FuncValue;
+use ergotree_ir::mir::select_field::SelectField;
+use ergotree_ir::mir::val_def::ValDef;
+use ergotree_ir::mir::val_use::ValUse;
+use ergotree_ir::types::stuple::STuple;
+use ergotree_ir::types::stype::SType;
+use sigma_test_util::force_any_val;
+
+const COLL_SIZE: i64 = 10_000;
+
+fn fold_sum_expr(vals_in_scope: u32) -> Expr {
+    let items: Vec<Expr> = (1..=vals_in_scope)
+        .map(|id| {
+            ValDef {
+                id: id.into(),
+                rhs: Box::new((id as i64).into()),
+            }
+            .into()
+        })
+        .collect();
+    let arg_id = vals_in_scope + 1;
+    let tuple_tpe = SType::STuple(STuple {
+        items: [SType::SLong, SType::SLong].into(),
+    });
+    let tuple: Expr = ValUse {
+        val_id: arg_id.into(),
+        tpe: tuple_tpe.clone(),
+    }
+    .into();
+    let fold_op_body: Expr = BinOp {
+        kind: ArithOp::Plus.into(),
+        left: Box::new(
+            SelectField::new(tuple.clone(), 1.try_into().unwrap())
+                .unwrap()
+                .into(),
+        ),
+        right: Box::new(
+            SelectField::new(tuple, 2.try_into().unwrap())
+                .unwrap()
+                .into(),
+        ),
+    }
+    .into();
+    let input: Vec<i64> = (0..COLL_SIZE).collect();
+    let fold: Expr = Fold::new(
+        input.into(),
+        0i64.into(),
+        FuncValue::new(
+            vec![FuncArg {
+                idx: arg_id.into(),
+                tpe: tuple_tpe,
+            }],
+            fold_op_body,
+        )
+        .into(),
+    )
+    .unwrap()
+    .into();
+    let result: Expr = BinOp {
+        kind: RelationOp::Eq.into(),
+        left: Box::new(fold),
+        right: Box::new((COLL_SIZE * (COLL_SIZE - 1) / 2).into()),
+    }
+    .into();
+    BlockValue {
+        items,
+        result: Box::new(result),
+    }
+    .into()
+}
+
+fn env_fold_benchmark(c: &mut Criterion) {
+    let ctx = Rc::new(force_any_val::<Context>());
+    let env = Env::empty();
+    for vals_in_scope in &[1u32, 100, 1000] {
+        let expr = fold_sum_expr(*vals_in_scope);
+        c.bench_function(&format!("fold with {} vals in scope", vals_in_scope), |b| {
+            b.iter(|| reduce_to_crypto(&expr, &env, ctx.clone()).unwrap())
+        });
+    }
+}
+
+criterion_group!(benches, env_fold_benchmark);
+criterion_main!(benches);
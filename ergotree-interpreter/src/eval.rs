@@ -1,26 +1,51 @@
 //! Interpreter
 use std::rc::Rc;
 
+#[cfg(feature = "json")]
+use ergotree_ir::chain::ergo_box::RenderedRegister;
+use ergotree_ir::chain::ergo_box::UnparsedRegisterError;
+use ergotree_ir::ergo_tree::ErgoTree;
 use ergotree_ir::ergo_tree::ErgoTreeError;
+#[cfg(feature = "json")]
+use ergotree_ir::mir::constant::Constant;
 use ergotree_ir::mir::constant::TryExtractFromError;
+use ergotree_ir::mir::constant::ValueToConstantError;
 use ergotree_ir::mir::expr::Expr;
+use ergotree_ir::mir::val_def::ValId;
 use ergotree_ir::mir::value::Value;
+use ergotree_ir::serialization::sigma_byte_reader;
+use ergotree_ir::serialization::sigma_byte_reader::DeserializePolicy;
 use ergotree_ir::serialization::SigmaParsingError;
+use ergotree_ir::serialization::SigmaSerializable;
 use ergotree_ir::serialization::SigmaSerializationError;
 use ergotree_ir::sigma_protocol::sigma_boolean::SigmaBoolean;
+use ergotree_ir::source_span::SourceSpan;
+#[cfg(feature = "json")]
+use std::convert::TryFrom;
 
 use cost_accum::CostAccumulator;
 use ergotree_ir::types::smethod::SMethod;
+use ergotree_ir::types::stype::SType;
 use thiserror::Error;
 
 use self::context::Context;
 use self::cost_accum::CostError;
 use self::env::Env;
+use self::method_registry::EvalMethodRegistry;
+use self::settings::EvalSettings;
 
+/// Off-chain (non-consensus) expression evaluation with relaxed limits
+pub mod analysis;
 /// Context(blockchain) for the interpreter
 pub mod context;
+/// Step-by-step evaluation with breakpoints for the contract developers
+pub mod debugger;
 /// Environment for
 pub mod env;
+/// Evaluation with a custom method registry
+pub mod method_registry;
+/// Evaluation settings (activated script version, soft-fork behavior)
+pub mod settings;
 
 pub(crate) mod and;
 pub(crate) mod apply;
@@ -78,6 +103,7 @@ pub(crate) mod sbox;
 pub(crate) mod scoll;
 pub(crate) mod scontext;
 pub(crate) mod select_field;
+pub(crate) mod sglobal;
 pub(crate) mod sgroup_elem;
 pub(crate) mod sheader;
 pub(crate) mod sigma_and;
@@ -110,12 +136,18 @@ pub enum EvalError {
     /// Unexpected value type
     #[error("Unexpected value type: {0:?}")]
     TryExtractFrom(#[from] TryExtractFromError),
+    /// Value cannot be converted into a constant (e.g. a function value)
+    #[error("Value to constant conversion error: {0}")]
+    ValueToConstant(#[from] ValueToConstantError),
     /// Not found (missing value, argument, etc.)
     #[error("Not found: {0}")]
     NotFound(String),
     /// Register id out of bounds
     #[error("{0}")]
     RegisterIdOutOfBounds(String),
+    /// Register value could not be parsed as a constant
+    #[error("{0}")]
+    UnparsedRegister(#[from] UnparsedRegisterError),
     /// Unexpected value
     #[error("Unexpected value: {0}")]
     UnexpectedValue(String),
@@ -134,9 +166,150 @@ pub enum EvalError {
     /// ErgoTree error
     #[error("ErgoTree error: {0}")]
     ErgoTreeError(#[from] ErgoTreeError),
+    /// Nested script deserialization (`DeserializeContext`, `DeserializeRegister`) is too deep
+    #[error("Maximum depth of nested script deserialization ({0}) is exceeded")]
+    DeserializeDepthExceeded(usize),
     /// Not yet implemented
     #[error("evaluation is not yet implemented: {0}")]
     NotImplementedYet(&'static str),
+    /// Error with the position of the failed expression in the source code
+    #[error("{0}")]
+    Spanned(SpannedEvalError),
+    /// ErgoTree version is higher than the activated script version (in strict mode)
+    #[error("ErgoTree version {tree_version} is higher than the activated script version {activated_version}")]
+    ScriptVersionNotActivated {
+        /// version of the tree
+        tree_version: u8,
+        /// activated script version
+        activated_version: u8,
+    },
+    /// Method is not available in the activated script version
+    #[error("Method {0} is not available in the activated script version {1}")]
+    MethodNotActivated(&'static str, u8),
+    /// Register value has a type different from the expected one
+    #[error("Register R{register_id} value has type {actual:?}, expected {expected:?}")]
+    UnexpectedRegisterType {
+        /// register index
+        register_id: i8,
+        /// expected type
+        expected: SType,
+        /// type of the register value
+        actual: SType,
+    },
+    /// Context variable value has a type different from the one expected by `getVar`
+    #[error("Context variable {var_id} has type {actual:?}, expected {expected:?}")]
+    UnexpectedContextVarType {
+        /// variable id
+        var_id: u8,
+        /// expected type
+        expected: SType,
+        /// type of the variable value
+        actual: SType,
+    },
+    /// Evaluation is aborted by [`debugger::Debugger`]
+    #[error("Evaluation is aborted by the debugger at step {0}")]
+    DebuggerAborted(usize),
+    /// `SubstConstants` new value has a type different from the constant it replaces
+    #[error("SubstConstants: new value for the constant {position} has type {actual:?}, expected {expected:?}")]
+    SubstConstantsTypeMismatch {
+        /// position of the replaced constant
+        position: usize,
+        /// type of the replaced constant
+        expected: SType,
+        /// type of the new value
+        actual: SType,
+    },
+}
+
+/// Evaluation error with the position of the failed expression in the source code
+#[derive(Error, PartialEq, Eq, Debug, Clone)]
+#[error("{error} (at offset {}, length {})", source_span.offset, source_span.length)]
+pub struct SpannedEvalError {
+    /// Evaluation error
+    pub error: Box<EvalError>,
+    /// Position of the failed expression in the source code
+    pub source_span: SourceSpan,
+    /// Values of the variables (`ValUse`) referenced in the failed expression, in the order of
+    /// their first reference (the variables bound inside the failed expression are not included)
+    pub bindings: Vec<(ValId, Value)>,
+}
+
+#[cfg(feature = "json")]
+impl SpannedEvalError {
+    /// Error description for the wallet UIs:
+    /// `{"error": "...", "sourceSpan": {"offset": 4, "length": 5}, "bindings": [{"id": 1,
+    /// "sigmaType": "SInt", "renderedValue": "5"}]}`.
+    /// The values which cannot be rendered as constants (e.g. boxes, lambdas) have `null` type and
+    /// the debug representation as the rendered value.
+    pub fn to_diagnostic_json(&self) -> serde_json::Value {
+        let bindings: Vec<serde_json::Value> = self
+            .bindings
+            .iter()
+            .map(|(id, value)| {
+                let rendered = Constant::try_from(value.clone())
+                    .ok()
+                    .and_then(|c| RenderedRegister::new(&c).ok());
+                let (sigma_type, rendered_value) = match rendered {
+                    Some(r) => (serde_json::Value::from(r.sigma_type), r.rendered_value),
+                    None => (serde_json::Value::Null, format!("{:?}", value)),
+                };
+                serde_json::json!({
+                    "id": id.0,
+                    "sigmaType": sigma_type,
+                    "renderedValue": rendered_value,
+                })
+            })
+            .collect();
+        serde_json::json!({
+            "error": self.error.to_string(),
+            "sourceSpan": {
+                "offset": self.source_span.offset,
+                "length": self.source_span.length,
+            },
+            "bindings": bindings,
+        })
+    }
+}
+
+impl EvalError {
+    /// Attach the source position of the failed expression.
+    /// The error is returned as is if the span is empty or the error already has a (more precise)
+    /// position of the nested expression.
+    pub fn wrap_spanned(self, source_span: SourceSpan) -> Self {
+        self.wrap_spanned_in_env(source_span, None, &Env::empty())
+    }
+
+    /// Same as [`EvalError::wrap_spanned`], also captures the values of the variables referenced
+    /// in the failed expression from the environment it was evaluated in (see
+    /// [`SpannedEvalError::bindings`])
+    pub fn wrap_spanned_in_env(
+        self,
+        source_span: SourceSpan,
+        expr: Option<&Expr>,
+        env: &Env,
+    ) -> Self {
+        if source_span.is_empty() || matches!(self, EvalError::Spanned(_)) {
+            self
+        } else {
+            let mut bindings: Vec<(ValId, Value)> = Vec::new();
+            let mut stack: Vec<&Expr> = expr.into_iter().collect();
+            while let Some(e) = stack.pop() {
+                if let Expr::ValUse(vu) = e {
+                    if !bindings.iter().any(|(id, _)| *id == vu.val_id) {
+                        if let Some(v) = env.get(vu.val_id) {
+                            bindings.push((vu.val_id, v.clone()));
+                        }
+                    }
+                }
+                stack.extend(e.children().into_iter().rev());
+            }
+            EvalError::Spanned(SpannedEvalError {
+                error: Box::new(self),
+                source_span,
+                bindings,
+            })
+        }
+    }
 }
 
 /// Result of expression reduction procedure (see `reduce_to_crypto`).
@@ -149,39 +322,177 @@ pub struct ReductionResult {
 }
 
 /// Evaluate the given expression by reducing it to SigmaBoolean value.
+/// The expression is evaluated with the default settings (see [`EvalSettings::default`]).
 pub fn reduce_to_crypto(
     expr: &Expr,
     env: &Env,
     ctx: Rc<Context>,
+) -> Result<ReductionResult, EvalError> {
+    reduce_expr_to_crypto(expr, env, ctx, EvalSettings::default(), None)
+}
+
+/// Evaluate the given tree by reducing it to SigmaBoolean value with the given settings.
+/// If the tree version is higher than the activated script version the tree is reduced to `true`
+/// without evaluation (soft-fork condition), or an error is returned in strict mode.
+pub fn reduce_tree_to_crypto(
+    tree: &ErgoTree,
+    env: &Env,
+    ctx: Rc<Context>,
+    settings: &EvalSettings,
+) -> Result<ReductionResult, EvalError> {
+    reduce_tree_with_registry(tree, env, ctx, settings, None)
+}
+
+/// Evaluate the given tree by reducing it to SigmaBoolean value with the given settings, the tree
+/// is parsed and evaluated with the methods of the given registry (see
+/// [`reduce_tree_to_crypto`] for the rest).
+pub fn reduce_tree_to_crypto_with_methods(
+    tree: &ErgoTree,
+    env: &Env,
+    ctx: Rc<Context>,
+    settings: &EvalSettings,
+    method_registry: &EvalMethodRegistry,
+) -> Result<ReductionResult, EvalError> {
+    reduce_tree_with_registry(tree, env, ctx, settings, Some(method_registry))
+}
+
+fn reduce_tree_with_registry(
+    tree: &ErgoTree,
+    env: &Env,
+    ctx: Rc<Context>,
+    settings: &EvalSettings,
+    method_registry: Option<&EvalMethodRegistry>,
+) -> Result<ReductionResult, EvalError> {
+    let tree_version = settings.tree_version(tree);
+    if tree_version > settings.activated_version {
+        return if settings.strict {
+            Err(EvalError::ScriptVersionNotActivated {
+                tree_version,
+                activated_version: settings.activated_version,
+            })
+        } else {
+            Ok(ReductionResult {
+                sigma_prop: SigmaBoolean::TrivialProp(true),
+                cost: 0,
+            })
+        };
+    }
+    let expr = match method_registry {
+        Some(registry) => tree.proposition_with_methods(registry.methods())?,
+        None => tree.proposition()?,
+    };
+    reduce_expr_to_crypto(expr.as_ref(), env, ctx, *settings, method_registry.cloned())
+}
+
+fn reduce_expr_to_crypto(
+    expr: &Expr,
+    env: &Env,
+    ctx: Rc<Context>,
+    settings: EvalSettings,
+    method_registry: Option<EvalMethodRegistry>,
 ) -> Result<ReductionResult, EvalError> {
     let cost_accum = CostAccumulator::new(0, None);
-    let mut ectx = EvalContext::new(ctx, cost_accum);
-    expr.eval(env, &mut ectx)
+    let mut ectx = EvalContext::new(ctx, cost_accum).with_settings(settings);
+    ectx.method_registry = method_registry;
+    let res = expr
+        .eval(env, &mut ectx)
         .and_then(|v| -> Result<ReductionResult, EvalError> {
+            let cost = ectx.cost_accum.total();
             match v {
                 Value::Boolean(b) => Ok(ReductionResult {
                     sigma_prop: SigmaBoolean::TrivialProp(b),
-                    cost: 0,
+                    cost,
                 }),
                 Value::SigmaProp(sp) => Ok(ReductionResult {
-                    sigma_prop: sp.value().clone(),
-                    cost: 0,
+                    // constants (e.g. from registers) might have non-folded trivial props
+                    sigma_prop: SigmaBoolean::from(*sp).reduce_trivial(),
+                    cost,
                 }),
                 _ => Err(EvalError::InvalidResultType),
             }
-        })
+        });
+    #[cfg(feature = "log")]
+    log::debug!("reduce_to_crypto: {:?}, {:?}", res, ectx.cost_accum);
+    res
 }
 
+/// Maximum depth of nested script deserialization (`DeserializeContext`, `DeserializeRegister`).
+/// Scripts deserialized from the context extension or registers may not deserialize further.
+pub const MAX_DESERIALIZE_DEPTH: usize = 1;
+
 #[derive(Debug)]
 pub(crate) struct EvalContext {
     pub(crate) ctx: Rc<Context>,
     pub(crate) cost_accum: CostAccumulator,
+    /// Depth of the currently evaluated deserialized scripts
+    pub(crate) deserialize_depth: usize,
+    pub(crate) settings: EvalSettings,
+    /// Methods to parse the deserialized scripts with and the evaluation of the added and replaced
+    /// methods (built-in methods if not set)
+    pub(crate) method_registry: Option<EvalMethodRegistry>,
+    /// Non-consensus evaluation of [`analysis::AnalysisEvaluator`] (BigInt without the 256-bit
+    /// limit)
+    pub(crate) analysis_mode: bool,
+    /// State of [`debugger::Debugger`] if the evaluation is debugged
+    pub(crate) debug_session: Option<Box<debugger::DebugSession>>,
 }
 
 impl EvalContext {
     pub fn new(ctx: Rc<Context>, cost_accum: CostAccumulator) -> Self {
-        EvalContext { ctx, cost_accum }
+        EvalContext {
+            ctx,
+            cost_accum,
+            deserialize_depth: 0,
+            settings: EvalSettings::default(),
+            method_registry: None,
+            analysis_mode: false,
+            debug_session: None,
+        }
+    }
+
+    pub fn with_settings(self, settings: EvalSettings) -> Self {
+        EvalContext { settings, ..self }
+    }
+
+    pub fn with_analysis_mode(self) -> Self {
+        EvalContext {
+            analysis_mode: true,
+            ..self
+        }
+    }
+}
+
+/// Parse the script bytes (from a context variable or a register), check its type and evaluate
+/// it. Deserialize* nodes are rejected in the parsed script and the nesting depth is limited by
+/// [`MAX_DESERIALIZE_DEPTH`].
+pub(crate) fn eval_deserialized(
+    node_name: &str,
+    bytes: &[u8],
+    expected_tpe: &SType,
+    env: &Env,
+    ctx: &mut EvalContext,
+) -> Result<Value, EvalError> {
+    if ctx.deserialize_depth >= MAX_DESERIALIZE_DEPTH {
+        return Err(EvalError::DeserializeDepthExceeded(MAX_DESERIALIZE_DEPTH));
+    }
+    let mut r =
+        sigma_byte_reader::from_bytes(bytes).with_deserialize_policy(DeserializePolicy::Reject);
+    if let Some(registry) = &ctx.method_registry {
+        r = r.with_method_registry(registry.methods().clone());
+    }
+    let expr = Expr::sigma_parse(&mut r)?;
+    if expr.tpe() != *expected_tpe {
+        return Err(EvalError::UnexpectedExpr(format!(
+            "{}: expected deserialized expr to have type {:?}, got {:?}",
+            node_name,
+            expected_tpe,
+            expr.tpe()
+        )));
     }
+    ctx.deserialize_depth += 1;
+    let res = expr.eval(env, ctx);
+    ctx.deserialize_depth -= 1;
+    res
 }
 
 /// Expression evaluation.
@@ -206,6 +517,7 @@ fn smethod_eval_fn(method: &SMethod) -> Result<EvalFn, EvalError> {
             savltree::IS_UPDATE_ALLOWED_METHOD_ID => self::savltree::IS_UPDATE_ALLOWED_EVAL_FN,
             savltree::IS_REMOVE_ALLOWED_METHOD_ID => self::savltree::IS_REMOVE_ALLOWED_EVAL_FN,
             savltree::UPDATE_OPERATIONS_METHOD_ID => self::savltree::UPDATE_OPERATIONS_EVAL_FN,
+            savltree::CONTAINS_METHOD_ID => self::savltree::CONTAINS_EVAL_FN,
             savltree::GET_METHOD_ID => self::savltree::GET_EVAL_FN,
             savltree::GET_MANY_METHOD_ID => self::savltree::GET_MANY_EVAL_FN,
             savltree::INSERT_METHOD_ID => self::savltree::INSERT_EVAL_FN,
@@ -223,6 +535,14 @@ fn smethod_eval_fn(method: &SMethod) -> Result<EvalFn, EvalError> {
             scontext::SELF_BOX_INDEX_PROPERTY_METHOD_ID => self::scontext::SELF_BOX_INDEX_EVAL_FN,
             scontext::HEADERS_PROPERTY_METHOD_ID => self::scontext::HEADERS_EVAL_FN,
             scontext::PRE_HEADER_PROPERTY_METHOD_ID => self::scontext::PRE_HEADER_EVAL_FN,
+            scontext::INPUTS_PROPERTY_METHOD_ID => self::scontext::INPUTS_EVAL_FN,
+            scontext::OUTPUTS_PROPERTY_METHOD_ID => self::scontext::OUTPUTS_EVAL_FN,
+            scontext::HEIGHT_PROPERTY_METHOD_ID => self::scontext::HEIGHT_EVAL_FN,
+            scontext::SELF_PROPERTY_METHOD_ID => self::scontext::SELF_EVAL_FN,
+            scontext::LAST_BLOCK_UTXO_ROOT_HASH_PROPERTY_METHOD_ID => {
+                self::scontext::LAST_BLOCK_UTXO_ROOT_HASH_EVAL_FN
+            }
+            scontext::MINER_PUBKEY_PROPERTY_METHOD_ID => self::scontext::MINER_PUBKEY_EVAL_FN,
             method_id => {
                 return Err(EvalError::NotFound(format!(
                     "Eval fn: unknown method id in SContext: {:?}",
@@ -249,6 +569,9 @@ fn smethod_eval_fn(method: &SMethod) -> Result<EvalFn, EvalError> {
             scoll::PATCH_METHOD_ID => self::scoll::PATCH_EVAL_FN,
             scoll::UPDATED_METHOD_ID => self::scoll::UPDATED_EVAL_FN,
             scoll::UPDATE_MANY_METHOD_ID => self::scoll::UPDATE_MANY_EVAL_FN,
+            scoll::DISTINCT_METHOD_ID => self::scoll::DISTINCT_EVAL_FN,
+            scoll::STARTS_WITH_METHOD_ID => self::scoll::STARTS_WITH_EVAL_FN,
+            scoll::ENDS_WITH_METHOD_ID => self::scoll::ENDS_WITH_EVAL_FN,
             method_id => {
                 return Err(EvalError::NotFound(format!(
                     "Eval fn: unknown method id in SCollection: {:?}",
@@ -256,6 +579,16 @@ fn smethod_eval_fn(method: &SMethod) -> Result<EvalFn, EvalError> {
                 )))
             }
         },
+        sglobal::TYPE_CODE => match method.method_id() {
+            sglobal::SERIALIZE_METHOD_ID => self::sglobal::SERIALIZE_EVAL_FN,
+            sglobal::DESERIALIZE_TO_METHOD_ID => self::sglobal::DESERIALIZE_TO_EVAL_FN,
+            method_id => {
+                return Err(EvalError::NotFound(format!(
+                    "Eval fn: unknown method id in SGlobal: {:?}",
+                    method_id
+                )))
+            }
+        },
         sgroup_elem::TYPE_CODE => match method.method_id() {
             sgroup_elem::GET_ENCODED_METHOD_ID => self::sgroup_elem::GET_ENCODED_EVAL_FN,
             sgroup_elem::NEGATE_METHOD_ID => self::sgroup_elem::NEGATE_EVAL_FN,
@@ -332,14 +665,26 @@ pub(crate) mod tests {
 
     #![allow(dead_code)]
 
+    use super::context::canned::seeded_context;
+    use super::context::canned::SEED;
     use super::env::Env;
     use super::*;
+    use ergotree_ir::ergo_tree::ErgoTreeHeader;
+    use ergotree_ir::mir::bin_op::ArithOp;
+    use ergotree_ir::mir::bin_op::BinOp;
+    use ergotree_ir::mir::bin_op::RelationOp;
+    use ergotree_ir::mir::constant::Constant;
     use ergotree_ir::mir::constant::TryExtractFrom;
     use ergotree_ir::mir::constant::TryExtractInto;
+    use ergotree_ir::sigma_protocol::sigma_boolean::cand::Cand;
+    use ergotree_ir::sigma_protocol::sigma_boolean::cor::Cor;
+    use ergotree_ir::sigma_protocol::sigma_boolean::ProveDlog;
+    use ergotree_ir::sigma_protocol::sigma_boolean::SigmaProp;
     use sigma_test_util::force_any_val;
+    use std::convert::TryInto;
 
     pub fn eval_out_wo_ctx<T: TryExtractFrom<Value>>(expr: &Expr) -> T {
-        let ctx = Rc::new(force_any_val::<Context>());
+        let ctx = Rc::new(seeded_context(SEED));
         eval_out(expr, ctx)
     }
 
@@ -363,7 +708,81 @@ pub(crate) mod tests {
     }
 
     pub fn try_eval_out_wo_ctx<T: TryExtractFrom<Value>>(expr: &Expr) -> Result<T, EvalError> {
-        let ctx = Rc::new(force_any_val::<Context>());
+        let ctx = Rc::new(seeded_context(SEED));
         try_eval_out(expr, ctx)
     }
+
+    /// Evaluates with the given settings (e.g. activated script version)
+    pub fn try_eval_out_with_settings<T: TryExtractFrom<Value>>(
+        expr: &Expr,
+        ctx: Rc<Context>,
+        settings: EvalSettings,
+    ) -> Result<T, EvalError> {
+        let cost_accum = CostAccumulator::new(0, None);
+        let mut ectx = EvalContext::new(ctx, cost_accum).with_settings(settings);
+        expr.eval(&Env::empty(), &mut ectx)
+            .and_then(|v| v.try_extract_into::<T>().map_err(EvalError::TryExtractFrom))
+    }
+
+    /// `1 / 0 == 1`, fails on evaluation
+    pub fn failing_bool_expr() -> Expr {
+        BinOp {
+            kind: RelationOp::Eq.into(),
+            left: Box::new(
+                BinOp {
+                    kind: ArithOp::Divide.into(),
+                    left: Box::new(Expr::Const(1i32.into())),
+                    right: Box::new(Expr::Const(0i32.into())),
+                }
+                .into(),
+            ),
+            right: Box::new(Expr::Const(1i32.into())),
+        }
+        .into()
+    }
+
+    #[test]
+    fn reduce_to_crypto_folds_trivial_props() {
+        let pk = force_any_val::<ProveDlog>();
+        let cor = Cor {
+            items: vec![false.into(), true.into()].try_into().unwrap(),
+        };
+        let cand = Cand {
+            items: vec![pk.clone().into(), cor.into()].try_into().unwrap(),
+        };
+        let expr: Expr = Constant::from(SigmaProp::new(cand.into())).into();
+        let ctx = Rc::new(force_any_val::<Context>());
+        let res = reduce_to_crypto(&expr, &Env::empty(), ctx).unwrap();
+        assert_eq!(res.sigma_prop, pk.into());
+    }
+
+    #[test]
+    fn reduce_tree_version_not_activated() {
+        let tree = ErgoTree::new(ErgoTreeHeader::v1(false), &false.into()).unwrap();
+        let ctx = Rc::new(force_any_val::<Context>());
+        let reduce = |settings: EvalSettings| {
+            reduce_tree_to_crypto(&tree, &Env::empty(), ctx.clone(), &settings)
+                .map(|res| res.sigma_prop)
+        };
+        // soft-fork condition
+        assert_eq!(reduce(EvalSettings::new(0)), Ok(true.into()));
+        assert_eq!(
+            reduce(EvalSettings {
+                strict: true,
+                ..EvalSettings::new(0)
+            }),
+            Err(EvalError::ScriptVersionNotActivated {
+                tree_version: 1,
+                activated_version: 0
+            })
+        );
+        assert_eq!(reduce(EvalSettings::new(1)), Ok(false.into()));
+        assert_eq!(
+            reduce(EvalSettings {
+                tree_version_override: Some(0),
+                ..EvalSettings::new(0)
+            }),
+            Ok(false.into())
+        );
+    }
 }
@@ -0,0 +1,276 @@
+use std::convert::TryFrom;
+use std::rc::Rc;
+
+use ergotree_ir::bigint256::BigInt256;
+use ergotree_ir::ergo_tree::ErgoTree;
+use ergotree_ir::mir::bin_op::ArithOp;
+use ergotree_ir::mir::bin_op::BinOpKind;
+use ergotree_ir::mir::bin_op::RelationOp;
+use ergotree_ir::mir::constant::TryExtractInto;
+use ergotree_ir::mir::expr::Expr;
+use ergotree_ir::mir::value::Value;
+use ergotree_ir::types::stype::SType;
+use num_bigint::BigInt;
+use num_traits::Zero;
+
+use super::context::Context;
+use super::cost_accum::CostAccumulator;
+use super::env::Env;
+use super::settings::EvalSettings;
+use super::EvalContext;
+use super::EvalError;
+use super::Evaluable;
+
+/// Result of the evaluation in analysis mode
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct AnalysisResult {
+    /// Value of the expression (of any type)
+    pub value: Value,
+    /// Estimated cost of the evaluation
+    pub cost: u64,
+}
+
+/// Evaluator of the expressions over the chain data for the tooling (indexers, explorers,
+/// analytics), with the relaxed consensus limits:
+/// - no cost limit;
+/// - the methods proposed for the future script versions are available
+///   (see [`EvalSettings::extended`]);
+/// - BigInt values are not limited to 256 bits, the arithmetic, negation and comparison of BigInt
+///   values out of the 256-bit range result in [`Value::UnboundedBigInt`];
+/// - the tree is evaluated regardless of its version and the result is of any type.
+///
+/// The results might differ from the node's, so it must never be used for the transaction
+/// validation or signing (see [`super::reduce_tree_to_crypto`] for the consensus evaluation).
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub struct AnalysisEvaluator {}
+
+impl AnalysisEvaluator {
+    /// Create the evaluator
+    pub fn new() -> Self {
+        AnalysisEvaluator {}
+    }
+
+    /// Evaluate the expression
+    pub fn eval(
+        &self,
+        expr: &Expr,
+        env: &Env,
+        ctx: Rc<Context>,
+    ) -> Result<AnalysisResult, EvalError> {
+        let mut ectx = EvalContext::new(ctx, CostAccumulator::new(0, None))
+            .with_settings(EvalSettings::extended())
+            .with_analysis_mode();
+        let value = expr.eval(env, &mut ectx)?;
+        Ok(AnalysisResult {
+            value,
+            cost: ectx.cost_accum.total(),
+        })
+    }
+
+    /// Evaluate the proposition of the tree (of any version)
+    pub fn eval_tree(
+        &self,
+        tree: &ErgoTree,
+        env: &Env,
+        ctx: Rc<Context>,
+    ) -> Result<AnalysisResult, EvalError> {
+        let expr = tree.proposition()?;
+        self.eval(expr.as_ref(), env, ctx)
+    }
+}
+
+/// Evaluates the BigInt operations without the 256-bit limit (the nodes of BigInt type only),
+/// returns `None` for the rest of the nodes to be evaluated as usual
+pub(crate) fn eval_unbounded_bigint(
+    expr: &Expr,
+    env: &Env,
+    ctx: &mut EvalContext,
+) -> Result<Option<Value>, EvalError> {
+    match expr {
+        Expr::BinOp(op) if op.expr().left.tpe() == SType::SBigInt => {
+            let op = op.expr();
+            match op.kind {
+                BinOpKind::Arith(arith_op) => {
+                    let l = unbounded(op.left.eval(env, ctx)?)?;
+                    let r = unbounded(op.right.eval(env, ctx)?)?;
+                    eval_arith(arith_op, l, r).map(Some)
+                }
+                BinOpKind::Relation(relation_op) => {
+                    let l = unbounded(op.left.eval(env, ctx)?)?;
+                    let r = unbounded(op.right.eval(env, ctx)?)?;
+                    Ok(Some(Value::Boolean(match relation_op {
+                        RelationOp::Eq => l == r,
+                        RelationOp::NEq => l != r,
+                        RelationOp::Gt => l > r,
+                        RelationOp::Lt => l < r,
+                        RelationOp::Ge => l >= r,
+                        RelationOp::Le => l <= r,
+                    })))
+                }
+                _ => Ok(None),
+            }
+        }
+        Expr::Negation(op) if op.input.tpe() == SType::SBigInt => {
+            let v = unbounded(op.input.eval(env, ctx)?)?;
+            Ok(Some(bigint_value(-v)))
+        }
+        Expr::ByteArrayToBigInt(op) => {
+            let input = op.input.eval(env, ctx)?.try_extract_into::<Vec<u8>>()?;
+            if input.is_empty() {
+                return Err(EvalError::UnexpectedValue(
+                    "ByteArrayToBigInt: byte array is empty".into(),
+                ));
+            }
+            Ok(Some(bigint_value(BigInt::from_signed_bytes_be(&input))))
+        }
+        _ => Ok(None),
+    }
+}
+
+fn eval_arith(op: ArithOp, l: BigInt, r: BigInt) -> Result<Value, EvalError> {
+    Ok(bigint_value(match op {
+        ArithOp::Plus => l + r,
+        ArithOp::Minus => l - r,
+        ArithOp::Multiply => l * r,
+        ArithOp::Divide => {
+            if r.is_zero() {
+                return Err(EvalError::ArithmeticException(format!(
+                    "({}) / ({}) resulted in division by zero",
+                    l, r
+                )));
+            }
+            l / r
+        }
+        ArithOp::Max => l.max(r),
+        ArithOp::Min => l.min(r),
+    }))
+}
+
+fn unbounded(v: Value) -> Result<BigInt, EvalError> {
+    match v {
+        Value::BigInt(n) => Ok(n.into()),
+        Value::UnboundedBigInt(n) => Ok(n),
+        _ => Err(EvalError::UnexpectedValue(format!(
+            "expected BigInt value, got {:?}",
+            v
+        ))),
+    }
+}
+
+/// BigInt value, [`Value::UnboundedBigInt`] only if out of the 256-bit range (so that the
+/// equal values are always equal)
+fn bigint_value(n: BigInt) -> Value {
+    match BigInt256::try_from(n.clone()) {
+        Ok(v) => Value::BigInt(v),
+        Err(_) => Value::UnboundedBigInt(n),
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::eval::tests::try_eval_out_wo_ctx;
+    use ergotree_ir::mir::bin_op::BinOp;
+    use ergotree_ir::mir::byte_array_to_bigint::ByteArrayToBigInt;
+    use ergotree_ir::mir::constant::Constant;
+    use ergotree_ir::mir::method_call::MethodCall;
+    use ergotree_ir::mir::negation::Negation;
+    use ergotree_ir::types::scoll;
+    use ergotree_ir::types::stype_param::STypeVar;
+    use num_traits::Bounded;
+    use sigma_test_util::force_any_val;
+
+    fn analyze(expr: &Expr) -> Result<Value, EvalError> {
+        let ctx = Rc::new(force_any_val::<Context>());
+        AnalysisEvaluator::new()
+            .eval(expr, &Env::empty(), ctx)
+            .map(|res| res.value)
+    }
+
+    fn bigint(n: BigInt) -> Value {
+        Value::UnboundedBigInt(n)
+    }
+
+    #[test]
+    fn bigint_beyond_256_bits() {
+        let max = BigInt::from(BigInt256::max_value());
+        let plus: Expr = BinOp {
+            kind: ArithOp::Plus.into(),
+            left: Box::new(Constant::from(BigInt256::max_value()).into()),
+            right: Box::new(Constant::from(BigInt256::from(1i64)).into()),
+        }
+        .into();
+        assert_eq!(analyze(&plus), Ok(bigint(max.clone() + 1)));
+        // consensus evaluation fails on overflow
+        assert!(try_eval_out_wo_ctx::<BigInt256>(&plus).is_err());
+
+        // back in the 256-bit range
+        let minus: Expr = BinOp {
+            kind: ArithOp::Minus.into(),
+            left: Box::new(plus.clone()),
+            right: Box::new(Constant::from(BigInt256::from(1i64)).into()),
+        }
+        .into();
+        assert_eq!(analyze(&minus), Ok(Value::BigInt(BigInt256::max_value())));
+        let gt: Expr = BinOp {
+            kind: RelationOp::Gt.into(),
+            left: Box::new(plus.clone()),
+            right: Box::new(Constant::from(BigInt256::max_value()).into()),
+        }
+        .into();
+        assert_eq!(analyze(&gt), Ok(Value::Boolean(true)));
+
+        let mul: Expr = BinOp {
+            kind: ArithOp::Multiply.into(),
+            left: Box::new(plus.clone()),
+            right: Box::new(plus),
+        }
+        .into();
+        assert_eq!(
+            analyze(&mul),
+            Ok(bigint((max.clone() + 1) * (max.clone() + 1)))
+        );
+
+        let neg: Expr = Negation {
+            input: Box::new(Constant::from(BigInt256::min_value()).into()),
+        }
+        .into();
+        assert_eq!(analyze(&neg), Ok(bigint(max + 1)));
+
+        let bytes = vec![0x7fu8; 40];
+        let from_bytes: Expr = ByteArrayToBigInt {
+            input: Box::new(Constant::from(bytes.clone()).into()),
+        }
+        .into();
+        assert_eq!(
+            analyze(&from_bytes),
+            Ok(bigint(BigInt::from_signed_bytes_be(&bytes)))
+        );
+        assert!(try_eval_out_wo_ctx::<BigInt256>(&from_bytes).is_err());
+
+        let div_by_zero: Expr = BinOp {
+            kind: ArithOp::Divide.into(),
+            left: Box::new(Constant::from(BigInt256::from(1i64)).into()),
+            right: Box::new(Constant::from(BigInt256::from(0i64)).into()),
+        }
+        .into();
+        assert!(analyze(&div_by_zero).is_err());
+    }
+
+    #[test]
+    fn extended_methods() {
+        let expr: Expr = MethodCall::new(
+            Constant::from(vec![1i32, 1, 2]).into(),
+            scoll::DISTINCT_METHOD
+                .clone()
+                .with_concrete_types(&[(STypeVar::t(), SType::SInt)].iter().cloned().collect()),
+            vec![],
+        )
+        .unwrap()
+        .into();
+        assert_eq!(analyze(&expr), Ok(Value::from(vec![1i32, 2])));
+        assert!(try_eval_out_wo_ctx::<Vec<i32>>(&expr).is_err());
+    }
+}
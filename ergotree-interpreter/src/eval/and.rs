@@ -15,6 +15,7 @@ impl Evaluable for And {
     }
 }
 
+#[allow(clippy::unwrap_used)]
 #[allow(clippy::panic)]
 #[cfg(test)]
 mod tests {
@@ -22,6 +23,10 @@ mod tests {
 
     use crate::eval::context::Context;
     use crate::eval::tests::eval_out;
+    use crate::eval::tests::failing_bool_expr;
+    use crate::eval::tests::try_eval_out;
+    use ergotree_ir::mir::collection::Collection;
+    use ergotree_ir::types::stype::SType;
 
     use super::*;
 
@@ -40,4 +45,17 @@ mod tests {
             prop_assert_eq!(res, bools.iter().all(|b| *b));
         }
     }
+
+    #[test]
+    fn eval_strictness() {
+        // all items are evaluated even if the result is known after the first one (as in the
+        // reference implementation)
+        let items = vec![Expr::Const(false.into()), failing_bool_expr()];
+        let expr: Expr = And {
+            input: Box::new(Collection::new(SType::SBoolean, items).unwrap().into()),
+        }
+        .into();
+        let ctx = Rc::new(force_any_val::<Context>());
+        assert!(try_eval_out::<bool>(&expr, ctx).is_err());
+    }
 }
@@ -16,7 +16,7 @@ impl Evaluable for Apply {
         match func_v {
             Value::Lambda(fv) => {
                 let arg_ids: Vec<ValId> = fv.args.iter().map(|a| a.idx).collect();
-                let mut cur_env = env.clone();
+                let mut cur_env = Env::closure(&fv);
                 arg_ids.iter().zip(args_v).for_each(|(idx, arg_v)| {
                     cur_env.insert(*idx, arg_v);
                 });
@@ -54,7 +54,7 @@ mod tests {
     #[test]
     fn eval_user_defined_func_call() {
         let arg = Expr::Const(1i32.into());
-        let bin_op = Expr::BinOp(BinOp {
+        let bin_op: Expr = BinOp {
             kind: RelationOp::Eq.into(),
             left: Box::new(
                 ValUse {
@@ -70,7 +70,8 @@ mod tests {
                 }
                 .into(),
             ),
-        });
+        }
+        .into();
         let body = Expr::BlockValue(BlockValue {
             items: vec![ValDef {
                 id: 2.into(),
@@ -71,9 +71,13 @@ where
     T: Num + CheckedDiv + TryExtractFrom<Value> + Into<Value> + std::fmt::Display,
 {
     let rv_raw = rv.try_extract_into::<T>()?;
+    if rv_raw.is_zero() {
+        return Err(arithmetic_err("/", lv_raw, rv_raw, "division by zero"));
+    }
+    // the only other case is MIN / -1 which does not fit into T
     lv_raw
         .checked_div(&rv_raw)
-        .ok_or_else(|| arithmetic_err("/", lv_raw, rv_raw, "exception"))
+        .ok_or_else(|| arithmetic_err("/", lv_raw, rv_raw, "overflow"))
         .map(|t| t.into()) // convert T to Value
 }
 
@@ -306,9 +310,14 @@ mod tests {
     use crate::eval::tests::try_eval_out;
     use ergotree_ir::mir::constant::Constant;
     use ergotree_ir::mir::expr::Expr;
+    use ergotree_ir::source_span::SourceSpan;
+    use ergotree_ir::source_span::Spanned;
+    use num_bigint::BigInt;
     use num_traits::Bounded;
+    use num_traits::Zero;
     use proptest::prelude::*;
     use sigma_test_util::force_any_val;
+    use std::convert::TryFrom;
     use std::rc::Rc;
 
     fn check_eq_neq(left: Constant, right: Constant) -> bool {
@@ -433,6 +442,119 @@ mod tests {
         try_eval_out::<T>(&expr, ctx)
     }
 
+    /// Evaluates the arithmetic op and checks the result against the one computed with unbounded
+    /// BigInt. Results that do not fit into T (including division by zero) must be an
+    /// ArithmeticException, as in JVM's `Math.*Exact` methods
+    fn check_arith_op_against_bigint<T>(op: ArithOp, l: T, r: T) -> Result<(), TestCaseError>
+    where
+        T: TryExtractFrom<Value> + Into<Constant> + Into<BigInt> + TryFrom<BigInt>,
+        T: Copy + PartialEq + std::fmt::Debug,
+    {
+        let (lb, rb): (BigInt, BigInt) = (l.into(), r.into());
+        let expected: Option<T> = match op {
+            ArithOp::Plus => T::try_from(lb + rb).ok(),
+            ArithOp::Minus => T::try_from(lb - rb).ok(),
+            ArithOp::Multiply => T::try_from(lb * rb).ok(),
+            // BigInt division truncates toward zero, same as JVM
+            ArithOp::Divide if rb.is_zero() => None,
+            ArithOp::Divide => T::try_from(lb / rb).ok(),
+            ArithOp::Max => T::try_from(lb.max(rb)).ok(),
+            ArithOp::Min => T::try_from(lb.min(rb)).ok(),
+        };
+        match (eval_arith_op(op, l, r), expected) {
+            (Ok(res), Some(exp)) => prop_assert_eq!(res, exp),
+            (Err(EvalError::ArithmeticException(_)), None) => (),
+            (res, exp) => prop_assert!(
+                false,
+                "{:?} {:?} {:?}: expected {:?}, got {:?}",
+                l,
+                op,
+                r,
+                exp,
+                res
+            ),
+        }
+        Ok(())
+    }
+
+    fn check_all_arith_ops_against_bigint<T>(l: T, r: T) -> Result<(), TestCaseError>
+    where
+        T: TryExtractFrom<Value> + Into<Constant> + Into<BigInt> + TryFrom<BigInt>,
+        T: Copy + PartialEq + std::fmt::Debug,
+    {
+        for op in [
+            ArithOp::Plus,
+            ArithOp::Minus,
+            ArithOp::Multiply,
+            ArithOp::Divide,
+            ArithOp::Max,
+            ArithOp::Min,
+        ]
+        .iter()
+        {
+            check_arith_op_against_bigint(*op, l, r)?;
+        }
+        Ok(())
+    }
+
+    /// Values around the type bounds and zero, where overflows are expected
+    fn edge_values<T: Bounded + TryFrom<i64> + Copy>() -> Vec<T> {
+        let mut vals = vec![T::min_value(), T::max_value()];
+        vals.extend(
+            [-2i64, -1, 0, 1, 2]
+                .iter()
+                .filter_map(|v| T::try_from(*v).ok()),
+        );
+        vals
+    }
+
+    fn check_edge_values_against_bigint<T>() -> Result<(), TestCaseError>
+    where
+        T: TryExtractFrom<Value> + Into<Constant> + Into<BigInt> + TryFrom<BigInt>,
+        T: Bounded + TryFrom<i64> + Copy + PartialEq + std::fmt::Debug,
+    {
+        let vals = edge_values::<T>();
+        for l in vals.iter() {
+            for r in vals.iter() {
+                check_all_arith_ops_against_bigint(*l, *r)?;
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_arith_edge_values_against_bigint() {
+        check_edge_values_against_bigint::<i8>().unwrap();
+        check_edge_values_against_bigint::<i16>().unwrap();
+        check_edge_values_against_bigint::<i32>().unwrap();
+        check_edge_values_against_bigint::<i64>().unwrap();
+    }
+
+    #[test]
+    fn test_div_errors() {
+        fn is_arith_err<T: std::fmt::Debug>(res: Result<T, EvalError>, msg: &str) -> bool {
+            matches!(res, Err(EvalError::ArithmeticException(m)) if m.ends_with(msg))
+        }
+        assert!(is_arith_err(
+            eval_arith_op(ArithOp::Divide, i64::MIN, -1),
+            "overflow"
+        ));
+        assert!(is_arith_err(
+            eval_arith_op(ArithOp::Divide, i32::MIN, -1),
+            "overflow"
+        ));
+        assert!(is_arith_err(
+            eval_arith_op(ArithOp::Divide, 1i64, 0),
+            "division by zero"
+        ));
+        assert!(is_arith_err(
+            eval_arith_op(ArithOp::Divide, 0i8, 0),
+            "division by zero"
+        ));
+        assert_eq!(eval_arith_op(ArithOp::Divide, -7i32, 2), Ok(-3));
+        assert_eq!(eval_arith_op(ArithOp::Divide, i64::MIN, 1), Ok(i64::MIN));
+    }
+
     fn eval_bit_op<T: TryExtractFrom<Value> + Into<Constant>>(
         op: BitOp,
         left: T,
@@ -470,6 +592,99 @@ mod tests {
         eval_out::<bool>(&expr, ctx)
     }
 
+    #[test]
+    fn eval_error_source_span() {
+        let source_span = SourceSpan {
+            offset: 4,
+            length: 5,
+        };
+        let expr = Expr::BinOp(Spanned::new(
+            BinOp {
+                kind: BinOpKind::Arith(ArithOp::Divide),
+                left: Box::new(1i32.into()),
+                right: Box::new(0i32.into()),
+            },
+            source_span,
+        ));
+        let ctx = Rc::new(force_any_val::<Context>());
+        match try_eval_out::<i32>(&expr, ctx) {
+            Err(EvalError::Spanned(e)) => {
+                assert_eq!(e.source_span, source_span);
+                assert!(matches!(*e.error, EvalError::ArithmeticException(_)));
+            }
+            res => panic!("expected spanned error, got {:?}", res),
+        }
+    }
+
+    #[test]
+    fn eval_error_bindings() {
+        use ergotree_ir::mir::block::BlockValue;
+        use ergotree_ir::mir::val_def::ValDef;
+        use ergotree_ir::mir::val_def::ValId;
+        use ergotree_ir::mir::val_use::ValUse;
+        use ergotree_ir::mir::value::Value;
+        use ergotree_ir::types::stype::SType;
+
+        let val_use = |id: u32| -> Expr {
+            ValUse {
+                val_id: ValId(id),
+                tpe: SType::SInt,
+            }
+            .into()
+        };
+        let val_def = |id: u32, v: i32| -> Expr {
+            ValDef {
+                id: ValId(id),
+                rhs: Box::new(v.into()),
+            }
+            .into()
+        };
+        let source_span = SourceSpan {
+            offset: 20,
+            length: 5,
+        };
+        // { val a = 1; val b = 0; val c = 2; c + a / b }
+        let div = Expr::BinOp(Spanned::new(
+            BinOp {
+                kind: BinOpKind::Arith(ArithOp::Divide),
+                left: Box::new(val_use(1)),
+                right: Box::new(val_use(2)),
+            },
+            source_span,
+        ));
+        let expr: Expr = BlockValue {
+            items: vec![val_def(1, 1), val_def(2, 0), val_def(3, 2)],
+            result: Box::new(
+                BinOp {
+                    kind: BinOpKind::Arith(ArithOp::Plus),
+                    left: Box::new(val_use(3)),
+                    right: Box::new(div),
+                }
+                .into(),
+            ),
+        }
+        .into();
+        let ctx = Rc::new(force_any_val::<Context>());
+        match try_eval_out::<i32>(&expr, ctx) {
+            Err(EvalError::Spanned(e)) => {
+                assert_eq!(e.source_span, source_span);
+                assert_eq!(
+                    e.bindings,
+                    vec![(ValId(1), Value::Int(1)), (ValId(2), Value::Int(0))]
+                );
+                #[cfg(feature = "json")]
+                {
+                    let json = e.to_diagnostic_json();
+                    assert_eq!(json["sourceSpan"]["offset"], 20);
+                    assert_eq!(json["bindings"][1]["id"], 2);
+                    assert_eq!(json["bindings"][1]["sigmaType"], "SInt");
+                    assert_eq!(json["bindings"][1]["renderedValue"], "0");
+                }
+            }
+            res => panic!("expected spanned error, got {:?}", res),
+        }
+    }
+
     #[test]
     fn test_bigint_extremes() {
         let b = |n| BigInt256::from(n);
@@ -613,6 +828,26 @@ mod tests {
             prop_assert_eq!(eval_relation_op(RelationOp::Le, l.clone(), r.clone()), l <= r);
         }
 
+        #[test]
+        fn test_arith_against_bigint_sbyte(l in any::<i8>(), r in any::<i8>()) {
+            check_all_arith_ops_against_bigint(l, r)?;
+        }
+
+        #[test]
+        fn test_arith_against_bigint_sshort(l in any::<i16>(), r in any::<i16>()) {
+            check_all_arith_ops_against_bigint(l, r)?;
+        }
+
+        #[test]
+        fn test_arith_against_bigint_sint(l in any::<i32>(), r in any::<i32>()) {
+            check_all_arith_ops_against_bigint(l, r)?;
+        }
+
+        #[test]
+        fn test_arith_against_bigint_slong(l in any::<i64>(), r in any::<i64>()) {
+            check_all_arith_ops_against_bigint(l, r)?;
+        }
+
         #[test]
         fn test_and_or_xor(l in any::<bool>(), r in any::<bool>()) {
             prop_assert_eq!(eval_logical_op(LogicalOp::And, l, r), l && r);
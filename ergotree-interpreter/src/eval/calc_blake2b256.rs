@@ -1,9 +1,13 @@
+use std::io::Write;
+
 use ergotree_ir::mir::calc_blake2b256::CalcBlake2b256;
+use ergotree_ir::mir::expr::Expr;
 use ergotree_ir::mir::value::CollKind;
 use ergotree_ir::mir::value::NativeColl;
 use ergotree_ir::mir::value::Value;
-use ergotree_ir::util::AsVecU8;
-use sigma_util::hash::blake2b256_hash;
+use ergotree_ir::util::AsU8Slice;
+use ergotree_ir::util::IntoVecI8;
+use sigma_util::hash::Blake2b256Writer;
 
 use crate::eval::env::Env;
 use crate::eval::EvalContext;
@@ -12,30 +16,53 @@ use crate::eval::Evaluable;
 
 impl Evaluable for CalcBlake2b256 {
     fn eval(&self, env: &Env, ctx: &mut EvalContext) -> Result<Value, EvalError> {
-        let input_v = self.input.eval(env, ctx)?;
-        match input_v.clone() {
-            Value::Coll(CollKind::NativeColl(NativeColl::CollByte(coll_byte))) => {
-                let expected_hash: Vec<u8> =
-                    blake2b256_hash(coll_byte.as_vec_u8().as_slice()).to_vec();
-                Ok(expected_hash.into())
-            }
-            _ => Err(EvalError::UnexpectedValue(format!(
-                "expected CalcBlake2b256 input to be byte array, got: {0:?}",
-                input_v
-            ))),
+        let mut hasher = Blake2b256Writer::new();
+        write_byte_coll(&self.input, env, ctx, &mut hasher, "CalcBlake2b256")?;
+        Ok(hasher.finalize().to_vec().into_vec_i8().into())
+    }
+}
+
+/// Evaluates the byte array expression and writes its bytes into the hasher (no copies of the
+/// collection). The operands of the `Append` nodes are written one after another without building
+/// the concatenated collection (the `Append` nodes are costed as if evaluated).
+pub(crate) fn write_byte_coll<W: Write>(
+    expr: &Expr,
+    env: &Env,
+    ctx: &mut EvalContext,
+    hasher: &mut W,
+    op_name: &str,
+) -> Result<(), EvalError> {
+    match expr {
+        // evaluate the nodes one by one in a debug session
+        Expr::Append(append) if ctx.debug_session.is_none() => {
+            ctx.cost_accum.add_cost_of(expr)?;
+            write_byte_coll(&append.input, env, ctx, hasher, op_name)?;
+            write_byte_coll(&append.col_2, env, ctx, hasher, op_name)
         }
+        _ => match expr.eval(env, ctx)? {
+            Value::Coll(CollKind::NativeColl(NativeColl::CollByte(coll_byte))) => hasher
+                .write_all(coll_byte.as_u8_slice())
+                .map_err(|e| EvalError::Misc(format!("{}: hashing error {}", op_name, e))),
+            input_v => Err(EvalError::UnexpectedValue(format!(
+                "expected {0} input to be byte array, got: {1:?}",
+                op_name, input_v
+            ))),
+        },
     }
 }
 
 #[cfg(test)]
 #[allow(clippy::panic)]
+#[allow(clippy::unwrap_used)]
 mod tests {
     use super::*;
     use crate::eval::context::Context;
+    use crate::eval::cost_accum::CostAccumulator;
     use crate::eval::tests::eval_out;
-    use ergotree_ir::mir::expr::Expr;
+    use ergotree_ir::mir::coll_append::Append;
     use proptest::prelude::*;
     use sigma_test_util::force_any_val;
+    use sigma_util::hash::blake2b256_hash;
     use std::rc::Rc;
 
     proptest! {
@@ -48,7 +75,35 @@ mod tests {
             }
             .into();
             let ctx = Rc::new(force_any_val::<Context>());
-            assert_eq!(eval_out::<Vec<i8>>(&expr, ctx).as_vec_u8(), expected_hash);
+            assert_eq!(eval_out::<Vec<i8>>(&expr, ctx).as_u8_slice(), expected_hash);
+        }
+
+        #[test]
+        fn eval_append(left in any::<Vec<u8>>(), mid in any::<Vec<u8>>(), right in any::<Vec<u8>>()) {
+            let expected_hash = blake2b256_hash(&[left.clone(), mid.clone(), right.clone()].concat()).to_vec();
+            let append = |a: Expr, b: Expr| -> Expr { Append::new(a, b).unwrap().into() };
+            let input = append(
+                Expr::Const(left.into()),
+                append(Expr::Const(mid.into()), Expr::Const(right.into())),
+            );
+            let expr: Expr = CalcBlake2b256 {
+                input: Box::new(input.clone()),
+            }
+            .into();
+            let ctx = Rc::new(force_any_val::<Context>());
+            assert_eq!(eval_out::<Vec<i8>>(&expr, ctx.clone()).as_u8_slice(), expected_hash);
+            // streamed operands are costed as the evaluated concatenation
+            let cost = |e: &Expr| {
+                let mut ectx = EvalContext::new(ctx.clone(), CostAccumulator::new(0, None));
+                e.eval(&Env::empty(), &mut ectx).unwrap();
+                ectx.cost_accum.total()
+            };
+            let concat = Expr::Const(eval_out::<Vec<i8>>(&input, ctx.clone()).into());
+            let streamed_cost = cost(&expr);
+            let input_cost = cost(&input);
+            let const_cost = cost(&concat);
+            let concat_cost = cost(&CalcBlake2b256 { input: Box::new(concat) }.into());
+            prop_assert_eq!(streamed_cost, concat_cost - const_cost + input_cost);
         }
 
     }
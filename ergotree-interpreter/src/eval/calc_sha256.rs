@@ -1,10 +1,9 @@
 use ergotree_ir::mir::calc_sha256::CalcSha256;
-use ergotree_ir::mir::value::CollKind;
-use ergotree_ir::mir::value::NativeColl;
 use ergotree_ir::mir::value::Value;
-use ergotree_ir::util::AsVecU8;
-use sigma_util::hash::sha256_hash;
+use ergotree_ir::util::IntoVecI8;
+use sigma_util::hash::Sha256Writer;
 
+use crate::eval::calc_blake2b256::write_byte_coll;
 use crate::eval::env::Env;
 use crate::eval::EvalContext;
 use crate::eval::EvalError;
@@ -12,29 +11,25 @@ use crate::eval::Evaluable;
 
 impl Evaluable for CalcSha256 {
     fn eval(&self, env: &Env, ctx: &mut EvalContext) -> Result<Value, EvalError> {
-        let input_v = self.input.eval(env, ctx)?;
-        match input_v.clone() {
-            Value::Coll(CollKind::NativeColl(NativeColl::CollByte(coll_byte))) => {
-                let expected_hash: Vec<u8> = sha256_hash(coll_byte.as_vec_u8().as_slice()).to_vec();
-                Ok(expected_hash.into())
-            }
-            _ => Err(EvalError::UnexpectedValue(format!(
-                "expected Sha256 input to be byte array, got: {0:?}",
-                input_v
-            ))),
-        }
+        let mut hasher = Sha256Writer::new();
+        write_byte_coll(&self.input, env, ctx, &mut hasher, "Sha256")?;
+        Ok(hasher.finalize().to_vec().into_vec_i8().into())
     }
 }
 
 #[cfg(test)]
 #[allow(clippy::panic)]
+#[allow(clippy::unwrap_used)]
 mod tests {
     use super::*;
     use crate::eval::context::Context;
     use crate::eval::tests::eval_out;
+    use ergotree_ir::mir::coll_append::Append;
     use ergotree_ir::mir::expr::Expr;
+    use ergotree_ir::util::AsU8Slice;
     use proptest::prelude::*;
     use sigma_test_util::force_any_val;
+    use sigma_util::hash::sha256_hash;
     use std::rc::Rc;
 
     proptest! {
@@ -47,7 +42,21 @@ mod tests {
             }
             .into();
             let ctx = Rc::new(force_any_val::<Context>());
-            assert_eq!(eval_out::<Vec<i8>>(&expr, ctx).as_vec_u8(), expected_hash);
+            assert_eq!(eval_out::<Vec<i8>>(&expr, ctx).as_u8_slice(), expected_hash);
+        }
+
+        #[test]
+        fn eval_append(left in any::<Vec<u8>>(), right in any::<Vec<u8>>()) {
+            let expected_hash = sha256_hash(&[left.clone(), right.clone()].concat()).to_vec();
+            let input: Expr = Append::new(Expr::Const(left.into()), Expr::Const(right.into()))
+                .unwrap()
+                .into();
+            let expr: Expr = CalcSha256 {
+                input: Box::new(input),
+            }
+            .into();
+            let ctx = Rc::new(force_any_val::<Context>());
+            assert_eq!(eval_out::<Vec<i8>>(&expr, ctx).as_u8_slice(), expected_hash);
         }
 
     }
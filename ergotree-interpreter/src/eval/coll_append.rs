@@ -1,6 +1,7 @@
 use ergotree_ir::mir::coll_append::Append;
 // use ergotree_ir::mir::constant::TryExtractInto;
 use ergotree_ir::mir::value::CollKind;
+use ergotree_ir::mir::value::NativeColl;
 use ergotree_ir::mir::value::Value;
 
 use crate::eval::env::Env;
@@ -25,8 +26,20 @@ fn extract_vecval(inp: Value) -> Result<Vec<Value>, EvalError> {
 
 impl Evaluable for Append {
     fn eval(&self, env: &Env, ctx: &mut EvalContext) -> Result<Value, EvalError> {
-        let input_vecval: Vec<Value> = extract_vecval(self.input.eval(env, ctx)?)?;
-        let col_2_vecval: Vec<Value> = extract_vecval(self.col_2.eval(env, ctx)?)?;
+        let input_v = self.input.eval(env, ctx)?;
+        let col_2_v = self.col_2.eval(env, ctx)?;
+        if let (
+            Value::Coll(CollKind::NativeColl(NativeColl::CollByte(bytes))),
+            Value::Coll(CollKind::NativeColl(NativeColl::CollByte(bytes_2))),
+        ) = (&input_v, &col_2_v)
+        {
+            // byte arrays are concatenated without the per-byte values
+            return Ok(Value::Coll(CollKind::NativeColl(NativeColl::CollByte(
+                [bytes.as_slice(), bytes_2.as_slice()].concat(),
+            ))));
+        }
+        let input_vecval: Vec<Value> = extract_vecval(input_v)?;
+        let col_2_vecval: Vec<Value> = extract_vecval(col_2_v)?;
         let concat_vecval: Vec<Value> = concat(input_vecval, col_2_vecval);
         Ok(Value::Coll(CollKind::from_vec(self.tpe(), concat_vecval)?))
     }
@@ -74,4 +87,17 @@ mod tests {
             assert_eq!(append_eval, *expected_output);
         }
     }
+
+    #[test]
+    fn test_append_bytes() {
+        let in_expr = Expr::from(vec![1i8, 2]);
+        let col2_expr = Expr::from(vec![3i8]);
+        let append_expr = Expr::from(Append::new(in_expr, col2_expr.clone()).unwrap());
+        let append_eval: Vec<i8> = eval_out_wo_ctx(&append_expr);
+        assert_eq!(append_eval, vec![1, 2, 3]);
+        let empty_expr = Expr::from(Vec::<i8>::new());
+        let append_expr = Expr::from(Append::new(empty_expr, col2_expr).unwrap());
+        let append_eval: Vec<i8> = eval_out_wo_ctx(&append_expr);
+        assert_eq!(append_eval, vec![3]);
+    }
 }
@@ -14,13 +14,17 @@ impl Evaluable for Fold {
         let zero_v = self.zero.eval(env, ctx)?;
         let fold_op_v = self.fold_op.eval(env, ctx)?;
         let input_v_clone = input_v.clone();
+        let closure_env = match &fold_op_v {
+            Value::Lambda(func_value) => Env::closure(func_value),
+            _ => Env::empty(),
+        };
         let mut fold_op_call = |arg: Value| match &fold_op_v {
             Value::Lambda(func_value) => {
                 let func_arg = func_value
                     .args
                     .first()
                     .ok_or_else(|| EvalError::NotFound("empty argument for fold op".to_string()))?;
-                let env1 = env.clone().extend(func_arg.idx, arg);
+                let env1 = closure_env.extend(func_arg.idx, arg);
                 func_value.body.eval(&env1, ctx)
             }
             _ => Err(EvalError::UnexpectedValue(format!(
@@ -12,6 +12,10 @@ impl Evaluable for ForAll {
         let input_v = self.input.eval(env, ctx)?;
         let condition_v = self.condition.eval(env, ctx)?;
         let input_v_clone = input_v.clone();
+        let closure_env = match &condition_v {
+            Value::Lambda(func_value) => Env::closure(func_value),
+            _ => Env::empty(),
+        };
         let mut condition_call = |arg: Value| match &condition_v {
             Value::Lambda(func_value) => {
                 let func_arg = func_value.args.first().ok_or_else(|| {
@@ -19,7 +23,7 @@ impl Evaluable for ForAll {
                         "ForAll: evaluated condition has empty arguments list".to_string(),
                     )
                 })?;
-                let env1 = env.clone().extend(func_arg.idx, arg);
+                let env1 = closure_env.extend(func_arg.idx, arg);
                 func_value.body.eval(&env1, ctx)
             }
             _ => Err(EvalError::UnexpectedValue(format!(
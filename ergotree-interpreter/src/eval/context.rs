@@ -1,15 +1,20 @@
 use std::rc::Rc;
 
 use crate::sigma_protocol::prover::ContextExtension;
+use ergo_units::Height;
 use ergotree_ir::chain::ergo_box::ErgoBox;
 use ergotree_ir::chain::header::Header;
 use ergotree_ir::chain::preheader::PreHeader;
 
+#[cfg(test)]
+#[cfg(feature = "arbitrary")]
+pub(crate) mod canned;
+
 /// Interpreter's context (blockchain state)
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Context {
     /// Current height
-    pub height: u32,
+    pub height: Height,
     /// Box that contains the script we're evaluating (from spending transaction inputs)
     pub self_box: Rc<ErgoBox>,
     /// Spending transaction outputs
@@ -48,7 +53,7 @@ mod arbitrary {
 
         fn arbitrary_with(_args: Self::Parameters) -> Self::Strategy {
             (
-                0..i32::MAX as u32,
+                (0..i32::MAX as u32).prop_map(Height::new),
                 any::<ErgoBox>(),
                 vec(any::<ErgoBox>(), 1..3),
                 vec(any::<ErgoBox>(), 1..3),
@@ -66,8 +71,23 @@ mod arbitrary {
                         data_inputs,
                         pre_header,
                         extension,
-                        headers,
+                        mut headers,
                     )| {
+                        // link the headers in a chain (the newest first) ending with the
+                        // pre-header of the current block
+                        let ids: Vec<_> = headers.iter().map(|h| h.id.clone()).collect();
+                        for (i, header) in headers.iter_mut().enumerate() {
+                            header.height =
+                                Height::new(height.as_u32().saturating_sub(i as u32 + 1));
+                            if let Some(parent_id) = ids.get(i + 1) {
+                                header.parent_id = parent_id.clone();
+                            }
+                        }
+                        let pre_header = PreHeader {
+                            parent_id: headers[0].id.clone(),
+                            height,
+                            ..pre_header
+                        };
                         Self {
                             height,
                             self_box: Rc::new(self_box),
@@ -88,4 +108,23 @@ mod arbitrary {
 }
 
 #[cfg(test)]
-mod tests {}
+#[cfg(feature = "arbitrary")]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    proptest! {
+
+        #![proptest_config(ProptestConfig::with_cases(16))]
+
+        #[test]
+        fn headers_linkage(ctx in any::<Context>()) {
+            prop_assert_eq!(&ctx.pre_header.parent_id, &ctx.headers[0].id);
+            prop_assert_eq!(ctx.pre_header.height, ctx.height);
+            for pair in ctx.headers.windows(2) {
+                prop_assert_eq!(&pair[0].parent_id, &pair[1].id);
+                prop_assert!(pair[0].height >= pair[1].height);
+            }
+        }
+    }
+}
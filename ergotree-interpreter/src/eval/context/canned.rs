@@ -0,0 +1,170 @@
+//! Canned contexts for the reproducible interpreter tests. A context is generated from a seed
+//! (the same seed gives the same context on every run), so a failure can be reproduced.
+
+use std::convert::TryFrom;
+use std::rc::Rc;
+
+use ergo_units::Height;
+use ergo_units::TimestampMillis;
+use ergotree_ir::chain::block_id::BlockId;
+use ergotree_ir::chain::digest32::blake2b256_hash;
+use ergotree_ir::chain::ergo_box::box_value::BoxValue;
+use ergotree_ir::chain::ergo_box::ErgoBox;
+use ergotree_ir::chain::ergo_box::NonMandatoryRegisters;
+use ergotree_ir::chain::header::Header;
+use ergotree_ir::chain::preheader::PreHeader;
+use ergotree_ir::chain::tx_id::TxId;
+use ergotree_ir::ergo_tree::ErgoTree;
+use ergotree_ir::mir::expr::Expr;
+use ergotree_ir::serialization::SigmaSerializable;
+use ergotree_ir::sigma_protocol::dlog_group;
+use ergotree_ir::sigma_protocol::sigma_boolean::ProveDlog;
+use ergotree_ir::sigma_protocol::sigma_boolean::SigmaProp;
+use sigma_test_util::force_any_val_seeded;
+
+use super::Context;
+use crate::sigma_protocol::private_input::DlogProverInput;
+use crate::sigma_protocol::prover::ContextExtension;
+
+/// Seed of the canned contexts used across the test suite
+pub(crate) const SEED: u64 = 1;
+
+/// Height of the block of [`mainnet_like_context`]
+pub(crate) const HEIGHT: u32 = 600_000;
+
+/// Timestamp of the block of [`mainnet_like_context`] (2021-10-12)
+const TIMESTAMP: u64 = 1_634_000_000_000;
+
+/// Target block interval (2 minutes)
+const BLOCK_INTERVAL_MILLIS: u64 = 120_000;
+
+/// Context generated from the seed (same as `force_any_val::<Context>()`, but reproducible)
+pub(crate) fn seeded_context(seed: u64) -> Context {
+    force_any_val_seeded::<Context>(seed)
+}
+
+/// Context of a P2PK payment transaction at the mainnet-like height: the chain of version 2
+/// headers two minutes apart (the ids are the hashes of the headers), the pre-header of the next
+/// block, the spent P2PK box of 2 ERG, the payment (1 ERG) and the change outputs
+pub(crate) fn mainnet_like_context(seed: u64) -> Context {
+    let ctx = seeded_context(seed);
+    let headers = header_chain(ctx.headers, seed);
+    let pre_header = PreHeader {
+        version: 2,
+        parent_id: headers[0].id.clone(),
+        timestamp: TimestampMillis::new(TIMESTAMP),
+        height: Height::new(HEIGHT),
+        miner_pk: headers[0].miner_pk.clone(),
+        ..ctx.pre_header
+    };
+    let owner = secret(seed, 0).public_image();
+    let recipient = secret(seed, 1).public_image();
+    let self_box = Rc::new(p2pk_box(
+        owner.clone(),
+        2_000_000_000,
+        HEIGHT - 100,
+        force_any_val_seeded::<TxId>(seed),
+        0,
+    ));
+    let tx_id = force_any_val_seeded::<TxId>(seed.wrapping_add(1));
+    let outputs = vec![
+        Rc::new(p2pk_box(recipient, 1_000_000_000, HEIGHT, tx_id.clone(), 0)),
+        Rc::new(p2pk_box(owner, 998_900_000, HEIGHT, tx_id, 1)),
+    ];
+    Context {
+        height: Height::new(HEIGHT),
+        self_box: self_box.clone(),
+        outputs,
+        data_inputs: vec![],
+        inputs: vec![self_box],
+        pre_header,
+        headers,
+        extension: ContextExtension::empty(),
+    }
+}
+
+/// Secret key derived from the seed
+#[allow(clippy::expect_used)]
+pub(crate) fn secret(seed: u64, idx: u8) -> DlogProverInput {
+    let mut bytes = seed.to_be_bytes().to_vec();
+    bytes.push(idx);
+    let digest = blake2b256_hash(&bytes);
+    DlogProverInput::from_bytes(&digest.0)
+        .expect("internal error: seeded secret is out of the scalar range")
+}
+
+#[allow(clippy::expect_used)]
+fn p2pk_box(pk: ProveDlog, value: u64, creation_height: u32, tx_id: TxId, index: u16) -> ErgoBox {
+    let tree = ErgoTree::try_from(Expr::from(SigmaProp::from(pk)))
+        .expect("internal error: failed to build a P2PK tree");
+    ErgoBox::new(
+        BoxValue::try_from(value).expect("internal error: invalid box value"),
+        tree,
+        None,
+        NonMandatoryRegisters::empty(),
+        creation_height,
+        tx_id,
+        index,
+    )
+    .expect("internal error: failed to build a box")
+}
+
+/// Headers (the newest first) before the block at [`HEIGHT`], linked by the ids calculated from
+/// the header bytes
+#[allow(clippy::expect_used)]
+fn header_chain(mut headers: [Header; 10], seed: u64) -> [Header; 10] {
+    for i in (0..headers.len()).rev() {
+        let parent_id = headers
+            .get(i + 1)
+            .map(|parent| parent.id.clone())
+            .unwrap_or_else(|| headers[i].parent_id.clone());
+        let header = &mut headers[i];
+        let blocks_before = i as u64 + 1;
+        header.version = 2;
+        header.parent_id = parent_id;
+        header.height = Height::new(HEIGHT - blocks_before as u32);
+        header.timestamp = TimestampMillis::new(TIMESTAMP - blocks_before * BLOCK_INTERVAL_MILLIS);
+        header.nonce = seed.wrapping_add(blocks_before).to_be_bytes().to_vec();
+        header.pow_onetime_pk = dlog_group::generator().into();
+        header.pow_distance = Default::default();
+        let bytes = header
+            .sigma_serialize_bytes()
+            .expect("internal error: failed to serialize a header");
+        header.id = BlockId(blake2b256_hash(&bytes));
+    }
+    headers
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reproducible() {
+        let ctx1 = mainnet_like_context(SEED);
+        let ctx2 = mainnet_like_context(SEED);
+        assert_eq!(ctx1.headers, ctx2.headers);
+        assert_eq!(ctx1.self_box, ctx2.self_box);
+        assert_eq!(ctx1.outputs, ctx2.outputs);
+        assert_eq!(seeded_context(SEED).self_box, seeded_context(SEED).self_box);
+    }
+
+    #[test]
+    fn mainnet_like_chain() {
+        let ctx = mainnet_like_context(SEED);
+        assert_eq!(ctx.pre_header.parent_id, ctx.headers[0].id);
+        assert_eq!(ctx.pre_header.height, ctx.height);
+        for pair in ctx.headers.windows(2) {
+            assert_eq!(pair[0].parent_id, pair[1].id);
+            assert_eq!(pair[0].height.as_u32(), pair[1].height.as_u32() + 1);
+        }
+        // the ids are the hashes of the headers
+        let header_bytes = ctx.headers[3].sigma_serialize_bytes().unwrap();
+        assert_eq!(
+            Header::sigma_parse_bytes(&header_bytes).unwrap(),
+            ctx.headers[3]
+        );
+        assert_eq!(ctx.inputs[0], ctx.self_box);
+    }
+}
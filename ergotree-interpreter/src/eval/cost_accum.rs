@@ -29,6 +29,11 @@ impl CostAccumulator {
         self.add(cost)
     }
 
+    /// Accumulated cost
+    pub fn total(&self) -> u64 {
+        self.accum
+    }
+
     pub fn add(&mut self, cost: Cost) -> Result<(), CostError> {
         self.accum += u32::from(cost) as u64;
         if let Some(limit) = self.limit {
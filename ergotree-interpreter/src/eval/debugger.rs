@@ -0,0 +1,412 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+
+use ergotree_ir::ergo_tree::ErgoTree;
+use ergotree_ir::mir::expr::Expr;
+use ergotree_ir::mir::value::Value;
+
+use super::context::Context;
+use super::cost_accum::CostAccumulator;
+use super::env::Env;
+use super::settings::EvalSettings;
+use super::EvalContext;
+use super::EvalError;
+use super::Evaluable;
+use crate::sigma_protocol::prover::ContextExtension;
+
+/// Evaluation event of a node
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum DebugEvent<'a> {
+    /// Node is about to be evaluated
+    Enter,
+    /// Node is evaluated to the value
+    Exit(&'a Value),
+}
+
+/// Step of the evaluation the debugger stopped at
+#[derive(Debug, Clone, Copy)]
+pub struct DebugStep<'a> {
+    /// Sequence number of the step (every node has two steps, entering and exiting it)
+    pub index: usize,
+    /// Index of the node in the evaluated expression (see [`Debugger::nodes`]), None for the
+    /// nodes not in the expression (the bodies of the lambda values and the deserialized scripts)
+    pub node: Option<usize>,
+    /// Nesting depth of the node in the evaluation (0 for the root)
+    pub depth: usize,
+    /// Evaluated node
+    pub expr: &'a Expr,
+    /// Event of the step
+    pub event: DebugEvent<'a>,
+    /// Environment the node is evaluated in
+    pub env: &'a Env,
+    /// Context of the evaluation
+    pub context: &'a Context,
+}
+
+/// What the debugger does after the step
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum DebugCommand {
+    /// Stop at the next step
+    Step,
+    /// Run to the next breakpoint
+    Continue,
+    /// Stop the evaluation, it fails with [`EvalError::DebuggerAborted`]
+    Abort,
+}
+
+/// Handler of the steps the debugger stops at
+pub trait DebugHandler {
+    /// Called at the step the debugger stopped at. The context variables can be changed in
+    /// `extension`, the evaluation continues with the changed context.
+    fn on_step(&mut self, step: &DebugStep, extension: &mut ContextExtension) -> DebugCommand;
+}
+
+impl<F: FnMut(&DebugStep, &mut ContextExtension) -> DebugCommand> DebugHandler for F {
+    fn on_step(&mut self, step: &DebugStep, extension: &mut ContextExtension) -> DebugCommand {
+        self(step, extension)
+    }
+}
+
+/// Where the debugger stops (on entering and on exiting the node)
+#[derive(Clone, Copy)]
+pub enum Breakpoint {
+    /// Node of the evaluated expression by its index (see [`Debugger::nodes`])
+    Node(usize),
+    /// Any node matching the predicate (e.g. `|e| matches!(e, Expr::CalcBlake2b256(_))`), the
+    /// nodes of the lambda bodies and the deserialized scripts included
+    Matching(fn(&Expr) -> bool),
+}
+
+impl fmt::Debug for Breakpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Breakpoint::Node(idx) => f.debug_tuple("Node").field(idx).finish(),
+            Breakpoint::Matching(_) => f.debug_tuple("Matching").finish(),
+        }
+    }
+}
+
+impl Breakpoint {
+    fn matches(&self, expr: &Expr, node: Option<usize>) -> bool {
+        match self {
+            Breakpoint::Node(idx) => node == Some(*idx),
+            Breakpoint::Matching(predicate) => predicate(expr),
+        }
+    }
+}
+
+/// Step-by-step evaluator for the contract developers: stops at the breakpoints (or at every
+/// step), passes the evaluated node, its value, the environment and the context to the
+/// [`DebugHandler`], which can change the context variables between the steps.
+/// The evaluation is the same as the consensus one (see [`super::reduce_to_crypto`]), with the
+/// given settings and without the cost limit.
+pub struct Debugger {
+    handler: Box<dyn DebugHandler>,
+    breakpoints: Vec<Breakpoint>,
+    step_from_start: bool,
+    settings: EvalSettings,
+}
+
+impl Debugger {
+    /// Create the debugger running to the first breakpoint
+    pub fn new<H: DebugHandler + 'static>(handler: H) -> Self {
+        Debugger {
+            handler: Box::new(handler),
+            breakpoints: Vec::new(),
+            step_from_start: false,
+            settings: EvalSettings::default(),
+        }
+    }
+
+    /// Stop at the breakpoint
+    pub fn with_breakpoint(mut self, breakpoint: Breakpoint) -> Self {
+        self.breakpoints.push(breakpoint);
+        self
+    }
+
+    /// Stop at the first step (entering the root node)
+    pub fn step_from_start(self) -> Self {
+        Debugger {
+            step_from_start: true,
+            ..self
+        }
+    }
+
+    /// Evaluate with the settings (activated script version, etc.)
+    pub fn with_settings(self, settings: EvalSettings) -> Self {
+        Debugger { settings, ..self }
+    }
+
+    /// Nodes of the expression in the order of [`Breakpoint::Node`] indices (pre-order, the
+    /// node goes before its children)
+    pub fn nodes(expr: &Expr) -> Vec<&Expr> {
+        let mut res = Vec::new();
+        let mut stack = vec![expr];
+        while let Some(e) = stack.pop() {
+            res.push(e);
+            stack.extend(e.children().into_iter().rev());
+        }
+        res
+    }
+
+    /// Evaluate the expression
+    pub fn eval(&mut self, expr: &Expr, env: &Env, ctx: Rc<Context>) -> Result<Value, EvalError> {
+        let nodes = Debugger::nodes(expr)
+            .into_iter()
+            .enumerate()
+            .map(|(idx, e)| (e as *const Expr, idx))
+            .collect();
+        let noop: Box<dyn DebugHandler> =
+            Box::new(|_: &DebugStep, _: &mut ContextExtension| DebugCommand::Continue);
+        let session = DebugSession {
+            handler: std::mem::replace(&mut self.handler, noop),
+            breakpoints: self.breakpoints.clone(),
+            nodes,
+            stepping: self.step_from_start,
+            step: 0,
+            depth: 0,
+        };
+        let mut ectx =
+            EvalContext::new(ctx, CostAccumulator::new(0, None)).with_settings(self.settings);
+        ectx.debug_session = Some(Box::new(session));
+        let res = expr.eval(env, &mut ectx);
+        if let Some(session) = ectx.debug_session.take() {
+            self.handler = session.handler;
+        }
+        res
+    }
+
+    /// Evaluate the proposition of the tree
+    pub fn eval_tree(
+        &mut self,
+        tree: &ErgoTree,
+        env: &Env,
+        ctx: Rc<Context>,
+    ) -> Result<Value, EvalError> {
+        let expr = tree.proposition()?;
+        self.eval(expr.as_ref(), env, ctx)
+    }
+}
+
+impl fmt::Debug for Debugger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Debugger")
+            .field("breakpoints", &self.breakpoints)
+            .field("step_from_start", &self.step_from_start)
+            .field("settings", &self.settings)
+            .finish()
+    }
+}
+
+/// Debugger state during the evaluation
+pub(crate) struct DebugSession {
+    handler: Box<dyn DebugHandler>,
+    breakpoints: Vec<Breakpoint>,
+    /// Node indices by the node address in the evaluated expression
+    nodes: HashMap<*const Expr, usize>,
+    stepping: bool,
+    step: usize,
+    depth: usize,
+}
+
+impl fmt::Debug for DebugSession {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DebugSession")
+            .field("breakpoints", &self.breakpoints)
+            .field("stepping", &self.stepping)
+            .field("step", &self.step)
+            .field("depth", &self.depth)
+            .finish()
+    }
+}
+
+impl DebugSession {
+    fn on_event(
+        &mut self,
+        expr: &Expr,
+        env: &Env,
+        ctx: &mut Rc<Context>,
+        event: DebugEvent,
+    ) -> Result<(), EvalError> {
+        let index = self.step;
+        self.step += 1;
+        let node = self.nodes.get(&(expr as *const Expr)).copied();
+        if !self.stepping && !self.breakpoints.iter().any(|b| b.matches(expr, node)) {
+            return Ok(());
+        }
+        let step = DebugStep {
+            index,
+            node,
+            depth: self.depth,
+            expr,
+            event,
+            env,
+            context: ctx.as_ref(),
+        };
+        let mut extension = ctx.extension.clone();
+        let command = self.handler.on_step(&step, &mut extension);
+        if extension != ctx.extension {
+            *ctx = Rc::new(ctx.as_ref().clone().with_extension(extension));
+        }
+        match command {
+            DebugCommand::Step => self.stepping = true,
+            DebugCommand::Continue => self.stepping = false,
+            DebugCommand::Abort => return Err(EvalError::DebuggerAborted(index)),
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn on_enter(expr: &Expr, env: &Env, ctx: &mut EvalContext) -> Result<(), EvalError> {
+    with_session(ctx, |session, context| {
+        let res = session.on_event(expr, env, context, DebugEvent::Enter);
+        session.depth += 1;
+        res
+    })
+}
+
+pub(crate) fn on_exit(
+    expr: &Expr,
+    env: &Env,
+    ctx: &mut EvalContext,
+    value: &Value,
+) -> Result<(), EvalError> {
+    with_session(ctx, |session, context| {
+        session.depth = session.depth.saturating_sub(1);
+        session.on_event(expr, env, context, DebugEvent::Exit(value))
+    })
+}
+
+fn with_session<F: FnOnce(&mut DebugSession, &mut Rc<Context>) -> Result<(), EvalError>>(
+    ctx: &mut EvalContext,
+    f: F,
+) -> Result<(), EvalError> {
+    match ctx.debug_session.as_mut() {
+        Some(session) => f(session, &mut ctx.ctx),
+        None => Ok(()),
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::cell::RefCell;
+
+    use super::*;
+    use crate::eval::context::canned::seeded_context;
+    use crate::eval::context::canned::SEED;
+    use ergotree_ir::mir::bin_op::ArithOp;
+    use ergotree_ir::mir::bin_op::BinOp;
+    use ergotree_ir::mir::constant::Constant;
+    use ergotree_ir::mir::get_var::GetVar;
+    use ergotree_ir::mir::option_get::OptionGet;
+    use ergotree_ir::mir::unary_op::OneArgOpTryBuild;
+    use ergotree_ir::types::stype::SType;
+
+    fn sum(l: Expr, r: Expr) -> Expr {
+        BinOp {
+            kind: ArithOp::Plus.into(),
+            left: Box::new(l),
+            right: Box::new(r),
+        }
+        .into()
+    }
+
+    fn get_var(id: u8) -> Expr {
+        OptionGet::try_build(
+            GetVar {
+                var_id: id,
+                var_tpe: SType::SInt,
+            }
+            .into(),
+        )
+        .unwrap()
+        .into()
+    }
+
+    fn ctx() -> Rc<Context> {
+        Rc::new(seeded_context(SEED).with_extension(ContextExtension::empty()))
+    }
+
+    #[test]
+    fn step_through() {
+        // 1 + (2 + 3)
+        let expr = sum(1i32.into(), sum(2i32.into(), 3i32.into()));
+        let steps = Rc::new(RefCell::new(Vec::new()));
+        let steps_log = steps.clone();
+        let mut debugger = Debugger::new(move |step: &DebugStep, _: &mut ContextExtension| {
+            let value = match step.event {
+                DebugEvent::Enter => None,
+                DebugEvent::Exit(v) => Some(v.clone()),
+            };
+            steps_log
+                .borrow_mut()
+                .push((step.index, step.node, step.depth, value));
+            DebugCommand::Step
+        })
+        .step_from_start();
+        let res = debugger.eval(&expr, &Env::empty(), ctx()).unwrap();
+        assert_eq!(res, Value::Int(6));
+        let steps = steps.borrow();
+        assert_eq!(steps.len(), 10);
+        assert_eq!(steps[0], (0, Some(0), 0, None));
+        assert_eq!(steps[1], (1, Some(1), 1, None));
+        assert_eq!(steps[2], (2, Some(1), 1, Some(Value::Int(1))));
+        assert_eq!(steps[8], (8, Some(2), 1, Some(Value::Int(5))));
+        assert_eq!(steps[9], (9, Some(0), 0, Some(Value::Int(6))));
+    }
+
+    #[test]
+    fn breakpoints() {
+        let expr = sum(1i32.into(), sum(2i32.into(), 3i32.into()));
+        let nodes = Debugger::nodes(&expr);
+        assert_eq!(nodes.len(), 5);
+        assert_eq!(nodes[4], &Expr::from(3i32));
+        let stops = Rc::new(RefCell::new(Vec::new()));
+        let stops_log = stops.clone();
+        let mut debugger = Debugger::new(move |step: &DebugStep, _: &mut ContextExtension| {
+            stops_log.borrow_mut().push(step.node);
+            DebugCommand::Continue
+        })
+        .with_breakpoint(Breakpoint::Node(4))
+        .with_breakpoint(Breakpoint::Matching(|e| {
+            e == &Expr::Const(Constant::from(1i32))
+        }));
+        debugger.eval(&expr, &Env::empty(), ctx()).unwrap();
+        assert_eq!(*stops.borrow(), vec![Some(1), Some(1), Some(4), Some(4)]);
+    }
+
+    #[test]
+    fn change_context_var() {
+        // getVar[Int](1).get + getVar[Int](1).get
+        let expr = sum(get_var(1), get_var(1));
+        let mut debugger = Debugger::new(|step: &DebugStep, ext: &mut ContextExtension| {
+            if step.index == 0 {
+                ext.values.insert(1, 20i32.into());
+            }
+            DebugCommand::Continue
+        })
+        .step_from_start();
+        let res = debugger.eval(&expr, &Env::empty(), ctx()).unwrap();
+        assert_eq!(res, Value::Int(40));
+    }
+
+    #[test]
+    fn abort() {
+        let expr = sum(1i32.into(), 2i32.into());
+        let mut debugger = Debugger::new(|step: &DebugStep, _: &mut ContextExtension| {
+            if step.index == 3 {
+                DebugCommand::Abort
+            } else {
+                DebugCommand::Step
+            }
+        })
+        .step_from_start();
+        assert_eq!(
+            debugger.eval(&expr, &Env::empty(), ctx()),
+            Err(EvalError::DebuggerAborted(3))
+        );
+    }
+}
@@ -1,11 +1,10 @@
 use ergotree_ir::mir::constant::TryExtractInto;
 use ergotree_ir::mir::deserialize_context::DeserializeContext;
-use ergotree_ir::mir::expr::Expr;
 use ergotree_ir::mir::value::Value;
-use ergotree_ir::serialization::SigmaSerializable;
 use ergotree_ir::types::stype::SType;
 
 use crate::eval::env::Env;
+use crate::eval::eval_deserialized;
 use crate::eval::EvalContext;
 use crate::eval::EvalError;
 use crate::eval::Evaluable;
@@ -18,11 +17,7 @@ impl Evaluable for DeserializeContext {
                     Err(EvalError::UnexpectedExpr(format!("DeserializeContext: expected extension value to have type SColl(SByte), got {:?}", c.tpe)))
                 } else {
                     let bytes = c.v.clone().try_extract_into::<Vec<u8>>()?;
-                    let expr = Expr::sigma_parse_bytes(bytes.as_slice())?;
-                    if expr.tpe() != self.tpe {
-                        return Err(EvalError::UnexpectedExpr(format!("DeserializeContext: expected deserialized expr to have type {:?}, got {:?}", self.tpe, expr.tpe())));
-                    }
-                    expr.eval(env, ctx)
+                    eval_deserialized("DeserializeContext", &bytes, &self.tpe, env, ctx)
                 }
             }
             None => Err(EvalError::NotFound(format!(
@@ -39,11 +34,16 @@ mod tests {
     use std::rc::Rc;
 
     use ergotree_ir::mir::constant::Constant;
+    use ergotree_ir::mir::expr::Expr;
     use ergotree_ir::mir::global_vars::GlobalVars;
+    use ergotree_ir::serialization::SigmaParsingError;
+    use ergotree_ir::serialization::SigmaSerializable;
     use sigma_test_util::force_any_val;
 
     use crate::eval::context::Context;
+    use crate::eval::cost_accum::CostAccumulator;
     use crate::eval::tests::try_eval_out;
+    use crate::eval::MAX_DESERIALIZE_DEPTH;
     use crate::sigma_protocol::prover::ContextExtension;
 
     use super::*;
@@ -111,4 +111,74 @@ mod tests {
         let ctx = force_any_val::<Context>().with_extension(ctx_ext);
         assert!(try_eval_out::<Value>(&expr, Rc::new(ctx)).is_err());
     }
+
+    #[test]
+    fn nested_deserialize_rejected() {
+        let expr: Expr = DeserializeContext {
+            tpe: SType::SBoolean,
+            id: 1,
+        }
+        .into();
+        // deserialized script tries to deserialize another context variable
+        let inner_expr: Expr = DeserializeContext {
+            tpe: SType::SBoolean,
+            id: 2,
+        }
+        .into();
+        let ctx_ext = ContextExtension {
+            values: [
+                (1u8, inner_expr.sigma_serialize_bytes().unwrap().into()),
+                (
+                    2u8,
+                    Expr::from(true).sigma_serialize_bytes().unwrap().into(),
+                ),
+            ]
+            .iter()
+            .cloned()
+            .collect(),
+        };
+        let ctx = force_any_val::<Context>().with_extension(ctx_ext);
+        assert_eq!(
+            try_eval_out::<bool>(&expr, Rc::new(ctx)),
+            Err(EvalError::SigmaParsingError(
+                SigmaParsingError::AtPosition {
+                    error: Box::new(SigmaParsingError::DeserializeNotAllowed(
+                        "DeserializeContext"
+                    )),
+                    offset: 1,
+                    path: vec!["DeserializeContext"],
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn deserialize_depth_exceeded() {
+        let expr = DeserializeContext {
+            tpe: SType::SBoolean,
+            id: 1,
+        };
+        let ctx_ext = ContextExtension {
+            values: [(
+                1u8,
+                Expr::from(true).sigma_serialize_bytes().unwrap().into(),
+            )]
+            .iter()
+            .cloned()
+            .collect(),
+        };
+        let ctx = force_any_val::<Context>().with_extension(ctx_ext);
+        let mut ectx = EvalContext::new(Rc::new(ctx), CostAccumulator::new(0, None));
+        assert!(expr
+            .eval(&Env::empty(), &mut ectx)
+            .unwrap()
+            .try_extract_into::<bool>()
+            .unwrap());
+        assert_eq!(ectx.deserialize_depth, 0);
+        ectx.deserialize_depth = MAX_DESERIALIZE_DEPTH;
+        assert_eq!(
+            expr.eval(&Env::empty(), &mut ectx),
+            Err(EvalError::DeserializeDepthExceeded(MAX_DESERIALIZE_DEPTH))
+        );
+    }
 }
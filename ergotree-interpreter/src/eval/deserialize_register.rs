@@ -2,12 +2,11 @@ use std::convert::TryInto;
 
 use ergotree_ir::mir::constant::TryExtractInto;
 use ergotree_ir::mir::deserialize_register::DeserializeRegister;
-use ergotree_ir::mir::expr::Expr;
 use ergotree_ir::mir::value::Value;
-use ergotree_ir::serialization::SigmaSerializable;
 use ergotree_ir::types::stype::SType;
 
 use crate::eval::env::Env;
+use crate::eval::eval_deserialized;
 use crate::eval::EvalContext;
 use crate::eval::EvalError;
 use crate::eval::Evaluable;
@@ -17,12 +16,12 @@ impl Evaluable for DeserializeRegister {
         match ctx
             .ctx
             .self_box
-            .get_register(self.reg.try_into().map_err(|e| {
+            .try_get_register(self.reg.try_into().map_err(|e| {
                 EvalError::RegisterIdOutOfBounds(format!(
                     "register index is out of bounds: {:?} ",
                     e
                 ))
-            })?) {
+            })?)? {
             Some(c) => {
                 if c.tpe != SType::SColl(SType::SByte.into()) {
                     Err(EvalError::UnexpectedExpr(format!(
@@ -31,12 +30,7 @@ impl Evaluable for DeserializeRegister {
                     )))
                 } else {
                     let bytes = c.v.try_extract_into::<Vec<u8>>()?;
-                    let expr = Expr::sigma_parse_bytes(bytes.as_slice())?;
-                    if expr.tpe() != self.tpe {
-                        Err(EvalError::UnexpectedExpr(format!("DeserializeRegister: expected deserialized expr to have type {:?}, got {:?}", self.tpe, expr.tpe())))
-                    } else {
-                        expr.eval(env, ctx)
-                    }
+                    eval_deserialized("DeserializeRegister", &bytes, &self.tpe, env, ctx)
                 }
             }
             None => match &self.default {
@@ -70,6 +64,7 @@ mod tests {
     use ergotree_ir::mir::constant::Constant;
     use ergotree_ir::mir::expr::Expr;
     use ergotree_ir::mir::global_vars::GlobalVars;
+    use ergotree_ir::serialization::SigmaParsingError;
     use ergotree_ir::serialization::SigmaSerializable;
     use ergotree_ir::types::stype::SType;
     use sigma_test_util::force_any_val;
@@ -82,7 +77,7 @@ mod tests {
     fn make_ctx_with_self_box(self_box: ErgoBox) -> Context {
         let ctx = force_any_val::<Context>();
         Context {
-            height: 0u32,
+            height: 0u32.into(),
             self_box: Rc::new(self_box),
             ..ctx
         }
@@ -179,4 +174,37 @@ mod tests {
         let ctx = make_ctx_with_self_box(b);
         assert!(try_eval_out::<bool>(&expr, Rc::new(ctx)).is_err());
     }
+
+    #[test]
+    fn nested_deserialize_rejected() {
+        // deserialized script tries to deserialize another register
+        let inner_expr: Expr = DeserializeRegister {
+            reg: 4,
+            tpe: SType::SBoolean,
+            default: None,
+        }
+        .into();
+        let reg_value: Constant = inner_expr.sigma_serialize_bytes().unwrap().into();
+        let b = force_any_val::<ErgoBox>()
+            .with_additional_registers(vec![reg_value].try_into().unwrap());
+        let expr: Expr = DeserializeRegister {
+            reg: 4,
+            tpe: SType::SBoolean,
+            default: None,
+        }
+        .into();
+        let ctx = make_ctx_with_self_box(b);
+        assert_eq!(
+            try_eval_out::<bool>(&expr, Rc::new(ctx)),
+            Err(EvalError::SigmaParsingError(
+                SigmaParsingError::AtPosition {
+                    error: Box::new(SigmaParsingError::DeserializeNotAllowed(
+                        "DeserializeRegister"
+                    )),
+                    offset: 1,
+                    path: vec!["DeserializeRegister"],
+                }
+            ))
+        );
+    }
 }
@@ -1,36 +1,101 @@
 use std::collections::HashMap;
+use std::rc::Rc;
 
 use ergotree_ir::mir::val_def::ValId;
+use ergotree_ir::mir::value::Lambda;
 use ergotree_ir::mir::value::Value;
 
-/// Environment for the interpreter
-#[derive(PartialEq, Eq, Debug, Clone)]
-pub struct Env {
-    store: HashMap<ValId, Value>,
+/// Environment for the interpreter.
+/// Persistent (immutable) list of bindings, so extending (e.g. on every lambda call in a
+/// collection fold) is O(1) and shares the bindings of the parent environment instead of copying
+/// them. Lookup is linear in the number of bindings in scope, which is small in practice.
+#[derive(Debug, Clone)]
+pub struct Env(Option<Rc<Binding>>);
+
+#[derive(Debug)]
+struct Binding {
+    idx: ValId,
+    value: Value,
+    parent: Option<Rc<Binding>>,
 }
 
 impl Env {
     /// Empty environment
     pub fn empty() -> Env {
-        Env {
-            store: HashMap::new(),
-        }
+        Env(None)
+    }
+
+    /// Environment of the lambda body, the values captured when the lambda was created (the
+    /// lambda is evaluated in the environment it was created in, wherever it is called)
+    pub fn closure(lambda: &Lambda) -> Env {
+        lambda
+            .captured
+            .iter()
+            .fold(Env::empty(), |env, (idx, v)| env.extend(*idx, v.clone()))
     }
 
     /// Extend this environment (create new) with added element
     pub fn extend(&self, idx: ValId, v: Value) -> Env {
-        let mut new_store = self.store.clone();
-        new_store.insert(idx, v);
-        Env { store: new_store }
+        Env(Some(Rc::new(Binding {
+            idx,
+            value: v,
+            parent: self.0.clone(),
+        })))
     }
 
     /// Insert a Value for the given ValId
     pub fn insert(&mut self, idx: ValId, v: Value) {
-        self.store.insert(idx, v);
+        *self = self.extend(idx, v);
     }
 
     /// Get an element
     pub fn get(&self, idx: ValId) -> Option<&Value> {
-        self.store.get(&idx)
+        self.bindings().find(|b| b.idx == idx).map(|b| &b.value)
+    }
+
+    /// Bindings from the most recent one, shadowed bindings included
+    fn bindings(&self) -> impl Iterator<Item = &Binding> {
+        std::iter::successors(self.0.as_deref(), |b| b.parent.as_deref())
+    }
+
+    /// Visible (not shadowed) bindings
+    fn to_map(&self) -> HashMap<ValId, &Value> {
+        let mut map = HashMap::new();
+        self.bindings().for_each(|b| {
+            map.entry(b.idx).or_insert(&b.value);
+        });
+        map
+    }
+}
+
+impl PartialEq for Env {
+    fn eq(&self, other: &Self) -> bool {
+        self.to_map() == other.to_map()
+    }
+}
+
+impl Eq for Env {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extend_and_shadow() {
+        let env = Env::empty().extend(ValId(1), 1i32.into());
+        let inner = env.extend(ValId(2), 2i32.into());
+        let shadowed = inner.extend(ValId(1), 3i32.into());
+        assert_eq!(env.get(ValId(2)), None);
+        assert_eq!(inner.get(ValId(1)), Some(&1i32.into()));
+        assert_eq!(shadowed.get(ValId(1)), Some(&3i32.into()));
+        assert_eq!(shadowed.get(ValId(2)), Some(&2i32.into()));
+        // the parent environment is not changed
+        assert_eq!(env.get(ValId(1)), Some(&1i32.into()));
+
+        let mut same = Env::empty();
+        same.insert(ValId(2), 2i32.into());
+        same.insert(ValId(1), 3i32.into());
+        assert_eq!(same, shadowed);
+        assert_ne!(same, inner);
     }
 }
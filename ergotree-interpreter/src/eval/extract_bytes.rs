@@ -28,7 +28,7 @@ mod tests {
     use crate::eval::tests::eval_out;
     use ergotree_ir::mir::expr::Expr;
     use ergotree_ir::mir::global_vars::GlobalVars;
-    use ergotree_ir::util::AsVecI8;
+    use ergotree_ir::util::IntoVecI8;
     use sigma_test_util::force_any_val;
     use std::rc::Rc;
 
@@ -41,7 +41,7 @@ mod tests {
         let ctx = Rc::new(force_any_val::<Context>());
         assert_eq!(
             eval_out::<Vec<i8>>(&e, ctx.clone()),
-            ctx.self_box.sigma_serialize_bytes().unwrap().as_vec_i8()
+            ctx.self_box.sigma_serialize_bytes().unwrap().into_vec_i8()
         );
     }
 }
@@ -5,6 +5,7 @@ use ergotree_ir::chain::ergo_box::ErgoBox;
 use ergotree_ir::mir::constant::TryExtractInto;
 use ergotree_ir::mir::extract_reg_as::ExtractRegisterAs;
 use ergotree_ir::mir::value::Value;
+use ergotree_ir::types::stype::SType;
 
 use crate::eval::env::Env;
 use crate::eval::EvalContext;
@@ -17,31 +18,52 @@ impl Evaluable for ExtractRegisterAs {
             .input
             .eval(env, ctx)?
             .try_extract_into::<Rc<ErgoBox>>()?;
-        Ok(Value::Opt(Box::new(
-            ir_box
-                .get_register(self.register_id.try_into().map_err(|e| {
-                    EvalError::RegisterIdOutOfBounds(format!(
-                        "register index is out of bounds: {:?} ",
-                        e
-                    ))
-                })?)
-                .map(|c| Value::from(c.v)),
-        )))
+        get_register_value(&ir_box, self.register_id, &self.elem_tpe)
+    }
+}
+
+/// Register value as `Option` (None if the register is empty). A register holding an `Option`
+/// value is returned as `Some` of it, so `Some(None)` for a register with `None` in it.
+/// Fails if the register value type is not `elem_tpe` (not checked if it's a type variable).
+pub(crate) fn get_register_value(
+    ir_box: &ErgoBox,
+    register_id: i8,
+    elem_tpe: &SType,
+) -> Result<Value, EvalError> {
+    let reg_value = ir_box.try_get_register(register_id.try_into().map_err(|e| {
+        EvalError::RegisterIdOutOfBounds(format!("register index is out of bounds: {:?} ", e))
+    })?)?;
+    match reg_value {
+        Some(c) if !matches!(elem_tpe, SType::STypeVar(_)) && c.tpe != *elem_tpe => {
+            Err(EvalError::UnexpectedRegisterType {
+                register_id,
+                expected: elem_tpe.clone(),
+                actual: c.tpe,
+            })
+        }
+        reg_value => Ok(Value::Opt(Box::new(reg_value.map(|c| Value::from(c.v))))),
     }
 }
 
 #[allow(clippy::unwrap_used)]
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
     use crate::eval::context::Context;
     use crate::eval::tests::eval_out;
+    use crate::eval::tests::try_eval_out;
+    use ergotree_ir::chain::ergo_box::NonMandatoryRegisterId;
+    use ergotree_ir::chain::ergo_box::NonMandatoryRegisters;
+    use ergotree_ir::chain::ergo_box::RegisterValue;
+    use ergotree_ir::chain::ergo_box::UnparsedRegisterError;
+    use ergotree_ir::chain::tx_id::TxId;
+    use ergotree_ir::mir::constant::Constant;
     use ergotree_ir::mir::expr::Expr;
     use ergotree_ir::mir::global_vars::GlobalVars;
     use ergotree_ir::mir::option_get::OptionGet;
     use ergotree_ir::mir::unary_op::OneArgOpTryBuild;
-    use ergotree_ir::types::stype::SType;
     use sigma_test_util::force_any_val;
+    use std::convert::TryFrom;
     use std::rc::Rc;
 
     #[test]
@@ -58,4 +80,104 @@ mod tests {
         let v = eval_out::<i64>(&option_get_expr, ctx.clone());
         assert_eq!(v, ctx.self_box.value.as_i64());
     }
+
+    /// Context with SELF box holding `None` (of `Option[Long]`) in R4 and a `Long` in R5
+    pub(crate) fn ctx_with_self_registers() -> Rc<Context> {
+        let ctx = force_any_val::<Context>();
+        let regs = NonMandatoryRegisters::try_from(vec![
+            Constant::from(None::<i64>),
+            Constant::from(1i64),
+        ])
+        .unwrap();
+        let self_box = ErgoBox::new(
+            ctx.self_box.value,
+            ctx.self_box.ergo_tree.clone(),
+            None,
+            regs,
+            0,
+            TxId::zero(),
+            0,
+        )
+        .unwrap();
+        Rc::new(Context {
+            self_box: Rc::new(self_box),
+            ..ctx
+        })
+    }
+
+    fn get_reg_expr(register_id: i8, elem_tpe: SType) -> Expr {
+        ExtractRegisterAs::new(
+            GlobalVars::SelfBox.into(),
+            register_id,
+            SType::SOption(elem_tpe.into()),
+        )
+        .unwrap()
+        .into()
+    }
+
+    #[test]
+    fn eval_box_get_reg_none_vs_empty() {
+        let ctx = ctx_with_self_registers();
+        let opt_long = SType::SOption(SType::SLong.into());
+        assert_eq!(
+            eval_out::<Option<Option<i64>>>(&get_reg_expr(4, opt_long.clone()), ctx.clone()),
+            Some(None)
+        );
+        assert_eq!(
+            eval_out::<Option<Option<i64>>>(&get_reg_expr(6, opt_long), ctx.clone()),
+            None
+        );
+        assert_eq!(
+            eval_out::<Option<i64>>(&get_reg_expr(5, SType::SLong), ctx),
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn eval_box_get_reg_type_mismatch() {
+        let ctx = ctx_with_self_registers();
+        assert_eq!(
+            try_eval_out::<Value>(&get_reg_expr(5, SType::SInt), ctx.clone()),
+            Err(EvalError::UnexpectedRegisterType {
+                register_id: 5,
+                expected: SType::SInt,
+                actual: SType::SLong,
+            })
+        );
+        assert_eq!(
+            try_eval_out::<Value>(&get_reg_expr(4, SType::SLong), ctx),
+            Err(EvalError::UnexpectedRegisterType {
+                register_id: 4,
+                expected: SType::SLong,
+                actual: SType::SOption(SType::SLong.into()),
+            })
+        );
+    }
+
+    #[test]
+    fn eval_box_get_reg_unparsed() {
+        let ctx = force_any_val::<Context>();
+        let regs =
+            NonMandatoryRegisters::try_from(vec![RegisterValue::Raw(vec![0, 0xff])]).unwrap();
+        let self_box = ErgoBox::new(
+            ctx.self_box.value,
+            ctx.self_box.ergo_tree.clone(),
+            None,
+            regs,
+            0,
+            TxId::zero(),
+            0,
+        )
+        .unwrap();
+        let ctx = Rc::new(Context {
+            self_box: Rc::new(self_box),
+            ..ctx
+        });
+        assert_eq!(
+            try_eval_out::<Value>(&get_reg_expr(4, SType::SLong), ctx),
+            Err(EvalError::UnparsedRegister(UnparsedRegisterError(
+                NonMandatoryRegisterId::R4
+            )))
+        );
+    }
 }
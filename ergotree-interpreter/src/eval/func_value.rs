@@ -1,4 +1,6 @@
+use ergotree_ir::mir::expr::Expr;
 use ergotree_ir::mir::func_value::FuncValue;
+use ergotree_ir::mir::val_def::ValId;
 use ergotree_ir::mir::value::Lambda;
 use ergotree_ir::mir::value::Value;
 
@@ -8,10 +10,167 @@ use crate::eval::EvalError;
 use crate::eval::Evaluable;
 
 impl Evaluable for FuncValue {
-    fn eval(&self, _env: &Env, _ctx: &mut EvalContext) -> Result<Value, EvalError> {
+    fn eval(&self, env: &Env, _ctx: &mut EvalContext) -> Result<Value, EvalError> {
         Ok(Value::Lambda(Lambda {
             args: self.args().to_vec(),
             body: self.body().clone().into(),
+            captured: captured_vals(self, env),
         }))
     }
 }
+
+/// Values of the ValDefs (and the arguments of the enclosing lambdas) in scope which are used in
+/// the lambda body (including the nested lambdas)
+fn captured_vals(func: &FuncValue, env: &Env) -> Vec<(ValId, Value)> {
+    let mut res: Vec<(ValId, Value)> = Vec::new();
+    let mut stack: Vec<&Expr> = vec![func.body()];
+    while let Some(e) = stack.pop() {
+        if let Expr::ValUse(v) = e {
+            let is_arg = func.args().iter().any(|a| a.idx == v.val_id);
+            if !is_arg && !res.iter().any(|(idx, _)| *idx == v.val_id) {
+                if let Some(value) = env.get(v.val_id) {
+                    res.push((v.val_id, value.clone()));
+                }
+            }
+        }
+        stack.extend(e.children());
+    }
+    res
+}
+
+#[cfg(test)]
+#[cfg(feature = "arbitrary")]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use ergotree_ir::mir::apply::Apply;
+    use ergotree_ir::mir::bin_op::ArithOp;
+    use ergotree_ir::mir::bin_op::BinOp;
+    use ergotree_ir::mir::bin_op::RelationOp;
+    use ergotree_ir::mir::block::BlockValue;
+    use ergotree_ir::mir::coll_exists::Exists;
+    use ergotree_ir::mir::coll_forall::ForAll;
+    use ergotree_ir::mir::constant::Constant;
+    use ergotree_ir::mir::func_value::FuncArg;
+    use ergotree_ir::mir::val_def::ValDef;
+    use ergotree_ir::mir::val_use::ValUse;
+    use ergotree_ir::types::stype::SType;
+
+    use crate::eval::tests::eval_out_wo_ctx;
+
+    use super::*;
+
+    fn val_use(id: u32, tpe: SType) -> Expr {
+        ValUse {
+            val_id: id.into(),
+            tpe,
+        }
+        .into()
+    }
+
+    fn val_def(id: u32, rhs: Expr) -> Expr {
+        ValDef {
+            id: id.into(),
+            rhs: Box::new(rhs),
+        }
+        .into()
+    }
+
+    fn func(arg_id: u32, body: Expr) -> Expr {
+        FuncValue::new(
+            vec![FuncArg {
+                idx: arg_id.into(),
+                tpe: SType::SInt,
+            }],
+            body,
+        )
+        .into()
+    }
+
+    fn plus(left: Expr, right: Expr) -> Expr {
+        BinOp {
+            kind: ArithOp::Plus.into(),
+            left: Box::new(left),
+            right: Box::new(right),
+        }
+        .into()
+    }
+
+    #[test]
+    fn eval_returned_closure() {
+        // val f = { (a: Int) => { (b: Int) => a + b } }
+        // val g = f(1)
+        // g(2)
+        let f = func(
+            2,
+            func(3, plus(val_use(2, SType::SInt), val_use(3, SType::SInt))),
+        );
+        let f_tpe = f.tpe();
+        let g: Expr = Apply::new(val_use(1, f_tpe), vec![1i32.into()])
+            .unwrap()
+            .into();
+        let g_tpe = g.tpe();
+        let expr: Expr = BlockValue {
+            items: vec![val_def(1, f), val_def(4, g)],
+            result: Box::new(
+                Apply::new(val_use(4, g_tpe), vec![2i32.into()])
+                    .unwrap()
+                    .into(),
+            ),
+        }
+        .into();
+        assert_eq!(eval_out_wo_ctx::<i32>(&expr), 3);
+    }
+
+    #[test]
+    fn eval_closure_shadowed_val_id() {
+        // val x = 1
+        // val f = { (a: Int) => a + x }
+        // { val x = 10 (the same ValId); f(0) }
+        let f = func(3, plus(val_use(3, SType::SInt), val_use(1, SType::SInt)));
+        let f_tpe = f.tpe();
+        let call: Expr = BlockValue {
+            items: vec![val_def(1, 10i32.into())],
+            result: Box::new(
+                Apply::new(val_use(2, f_tpe), vec![0i32.into()])
+                    .unwrap()
+                    .into(),
+            ),
+        }
+        .into();
+        let expr: Expr = BlockValue {
+            items: vec![val_def(1, 1i32.into()), val_def(2, f)],
+            result: Box::new(call),
+        }
+        .into();
+        // the lambda sees the value it was created with (as the node does)
+        assert_eq!(eval_out_wo_ctx::<i32>(&expr), 1);
+    }
+
+    #[test]
+    fn eval_nested_coll_closures() {
+        // val x = 1
+        // Coll(1, 2).forall { (a: Int) => Coll(2, 3).exists { (b: Int) => b == a + x } }
+        let outer: Constant = vec![1i32, 2].into();
+        let inner: Constant = vec![2i32, 3].into();
+        let cond: Expr = BinOp {
+            kind: RelationOp::Eq.into(),
+            left: Box::new(val_use(3, SType::SInt)),
+            right: Box::new(plus(val_use(2, SType::SInt), val_use(1, SType::SInt))),
+        }
+        .into();
+        let exists: Expr = Exists::new(inner.into(), func(3, cond)).unwrap().into();
+        let forall: Expr = ForAll::new(outer.into(), func(2, exists)).unwrap().into();
+        let expr: Expr = BlockValue {
+            items: vec![val_def(1, 1i32.into())],
+            result: Box::new(forall.clone()),
+        }
+        .into();
+        assert!(eval_out_wo_ctx::<bool>(&expr));
+        let expr: Expr = BlockValue {
+            items: vec![val_def(1, 2i32.into())],
+            result: Box::new(forall),
+        }
+        .into();
+        assert!(!eval_out_wo_ctx::<bool>(&expr));
+    }
+}
@@ -1,5 +1,3 @@
-// use ergotree_ir::mir::constant::TryExtractInto;
-use ergotree_ir::mir::constant::TryExtractFromError;
 use ergotree_ir::mir::get_var::GetVar;
 use ergotree_ir::mir::value::Value;
 
@@ -8,27 +6,40 @@ use crate::eval::EvalContext;
 use crate::eval::EvalError;
 use crate::eval::Evaluable;
 
+/// Returns `Some(value)` of the variable from the context extension, or `None` if there is no
+/// variable with this id. Fails only if the value has a type different from the expected one.
+/// The semantics are the same in all script versions (including v6.0).
 impl Evaluable for GetVar {
     fn eval(&self, _env: &Env, ctx: &mut EvalContext) -> Result<Value, EvalError> {
         match ctx.ctx.extension.values.get(&self.var_id) {
             None => Ok(Value::Opt(None.into())),
             Some(v) if v.tpe == self.var_tpe => Ok((Some(v.v.clone())).into()),
-            Some(v) => Err(TryExtractFromError(format!(
-                "GetVar: expected {:?}, found {:?}",
-                self.var_tpe, v.tpe
-            ))
-            .into()),
+            Some(v) => Err(EvalError::UnexpectedContextVarType {
+                var_id: self.var_id,
+                expected: self.var_tpe.clone(),
+                actual: v.tpe.clone(),
+            }),
         }
     }
 }
 
 #[cfg(test)]
+#[cfg(feature = "arbitrary")]
+#[allow(clippy::unwrap_used)]
 mod tests {
     use super::*;
     use crate::eval::context::Context;
-    use crate::eval::tests::{eval_out, try_eval_out};
+    use crate::eval::settings::EvalSettings;
+    use crate::eval::settings::V6_SCRIPT_VERSION;
+    use crate::eval::tests::{eval_out, try_eval_out, try_eval_out_with_settings};
+    use ergotree_ir::mir::constant::Constant;
     use ergotree_ir::mir::expr::Expr;
+    use ergotree_ir::mir::option_get::OptionGet;
+    use ergotree_ir::mir::option_get_or_else::OptionGetOrElse;
+    use ergotree_ir::mir::option_is_defined::OptionIsDefined;
+    use ergotree_ir::mir::unary_op::OneArgOpTryBuild;
     use ergotree_ir::types::stype::SType;
+    use proptest::prelude::*;
     use sigma_test_util::force_any_val;
     use std::rc::Rc;
 
@@ -43,15 +54,15 @@ mod tests {
         Rc::new(ctx)
     }
 
+    fn get_var(var_id: u8, var_tpe: SType) -> Expr {
+        GetVar { var_id, var_tpe }.into()
+    }
+
     /// Normal evaluation
     #[test]
     fn eval_success() {
         let ctx = prepare_context();
-        let expr: Expr = GetVar {
-            var_id: VAR_IDX,
-            var_tpe: SType::SInt,
-        }
-        .into();
+        let expr = get_var(VAR_IDX, SType::SInt);
         let res = eval_out::<Option<i32>>(&expr, ctx);
         assert_eq!(res, Some(VAR_VAL));
     }
@@ -60,11 +71,7 @@ mod tests {
     #[test]
     fn eval_fail() {
         let ctx = prepare_context();
-        let expr: Expr = GetVar {
-            var_id: VAR_IDX + 1,
-            var_tpe: SType::SInt,
-        }
-        .into();
+        let expr = get_var(VAR_IDX + 1, SType::SInt);
         let res = eval_out::<Option<i32>>(&expr, ctx);
         assert_eq!(res, None);
     }
@@ -73,12 +80,83 @@ mod tests {
     #[test]
     fn eval_wrong_type() {
         let ctx = prepare_context();
-        let expr: Expr = GetVar {
-            var_id: VAR_IDX,
-            var_tpe: SType::SBoolean,
-        }
-        .into();
+        let expr = get_var(VAR_IDX, SType::SBoolean);
         let res = try_eval_out::<Value>(&expr, ctx);
-        assert!(res.is_err());
+        assert_eq!(
+            res,
+            Err(EvalError::UnexpectedContextVarType {
+                var_id: VAR_IDX,
+                expected: SType::SBoolean,
+                actual: SType::SInt,
+            })
+        );
+    }
+
+    #[test]
+    fn eval_option_chaining() {
+        let is_defined = |var_id: u8| -> Expr {
+            OptionIsDefined {
+                input: Box::new(get_var(var_id, SType::SInt)),
+            }
+            .into()
+        };
+        let get_or_else = |var_id: u8| -> Expr {
+            OptionGetOrElse::new(get_var(var_id, SType::SInt), 7i32.into())
+                .unwrap()
+                .into()
+        };
+        let get = |var_id: u8| -> Expr {
+            OptionGet::try_build(get_var(var_id, SType::SInt))
+                .unwrap()
+                .into()
+        };
+        for version in 0..=V6_SCRIPT_VERSION {
+            let eval = |expr: Expr| {
+                try_eval_out_with_settings::<Value>(
+                    &expr,
+                    prepare_context(),
+                    EvalSettings::new(version),
+                )
+            };
+            assert_eq!(eval(is_defined(VAR_IDX)), Ok(true.into()));
+            assert_eq!(eval(is_defined(VAR_IDX + 1)), Ok(false.into()));
+            assert_eq!(eval(get_or_else(VAR_IDX)), Ok(VAR_VAL.into()));
+            assert_eq!(eval(get_or_else(VAR_IDX + 1)), Ok(7i32.into()));
+            assert_eq!(eval(get(VAR_IDX)), Ok(VAR_VAL.into()));
+            assert!(eval(get(VAR_IDX + 1)).is_err());
+            // type mismatch fails even if the value is not used
+            let expr: Expr = OptionIsDefined {
+                input: Box::new(get_var(VAR_IDX, SType::SLong)),
+            }
+            .into();
+            assert!(matches!(
+                eval(expr),
+                Err(EvalError::UnexpectedContextVarType { .. })
+            ));
+        }
+    }
+
+    proptest! {
+
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn eval_any_type(c in any::<Constant>(), var_id in any::<u8>()) {
+            let mut ctx = force_any_val::<Context>();
+            ctx.extension.values.clear();
+            ctx.extension.values.insert(var_id, c.clone());
+            let ctx = Rc::new(ctx);
+            let expr = get_var(var_id, c.tpe.clone());
+            for version in 0..=V6_SCRIPT_VERSION {
+                prop_assert_eq!(
+                    try_eval_out_with_settings::<Value>(
+                        &expr,
+                        ctx.clone(),
+                        EvalSettings::new(version)
+                    ),
+                    Ok(Value::Opt(Box::new(Some(c.v.clone().into()))))
+                );
+            }
+        }
     }
 }
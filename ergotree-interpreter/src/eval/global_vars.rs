@@ -11,7 +11,7 @@ use super::Evaluable;
 impl Evaluable for GlobalVars {
     fn eval(&self, _env: &Env, ectx: &mut EvalContext) -> Result<Value, EvalError> {
         match self {
-            GlobalVars::Height => Ok((ectx.ctx.height as i32).into()),
+            GlobalVars::Height => Ok((ectx.ctx.height.as_u32() as i32).into()),
             GlobalVars::SelfBox => Ok(ectx.ctx.self_box.clone().into()),
             GlobalVars::Outputs => Ok(ectx.ctx.outputs.clone().into()),
             GlobalVars::Inputs => Ok(ectx.ctx.inputs.clone().into()),
@@ -42,7 +42,10 @@ mod tests {
     fn eval_height() {
         let ctx = Rc::new(force_any_val::<Context>());
         let expr = compile_expr("HEIGHT", ScriptEnv::new()).unwrap();
-        assert_eq!(eval_out::<i32>(&expr, ctx.clone()), ctx.height as i32);
+        assert_eq!(
+            eval_out::<i32>(&expr, ctx.clone()),
+            ctx.height.as_u32() as i32
+        );
     }
 
     #[test]
@@ -1,5 +1,8 @@
 use ergotree_ir::mir::method_call::MethodCall;
 use ergotree_ir::mir::value::Value;
+use ergotree_ir::types::sbox;
+use ergotree_ir::types::sglobal;
+use ergotree_ir::types::stype::SType;
 
 use super::smethod_eval_fn;
 use super::Env;
@@ -9,9 +12,36 @@ use super::Evaluable;
 
 impl Evaluable for MethodCall {
     fn eval(&self, env: &Env, ectx: &mut EvalContext) -> Result<Value, EvalError> {
+        if !ectx.settings.is_method_available(&self.method) {
+            return Err(EvalError::MethodNotActivated(
+                self.method.name(),
+                ectx.settings.activated_version,
+            ));
+        }
+        let registry_eval_fn = match &ectx.method_registry {
+            Some(registry) => registry.eval_fn(&self.method)?,
+            None => None,
+        };
         let ov = self.obj.eval(env, ectx)?;
         let argsv: Result<Vec<Value>, EvalError> =
             self.args.iter().map(|arg| arg.eval(env, ectx)).collect();
+        if let Some(eval_fn) = registry_eval_fn {
+            return eval_fn(ov, argsv?);
+        }
+        if self.method.obj_type.type_code() == sbox::TYPE_CODE
+            && self.method.method_id() == sbox::GET_REG_METHOD_ID
+        {
+            // the expected register type is known only from the specialized method signature
+            if let SType::SOption(elem_tpe) = &*self.method.tpe().t_range {
+                return super::sbox::get_reg(ov, argsv?, elem_tpe);
+            }
+        }
+        if self.method.obj_type.type_code() == sglobal::TYPE_CODE
+            && self.method.method_id() == sglobal::DESERIALIZE_TO_METHOD_ID
+        {
+            // the type to parse is known only from the specialized method signature
+            return super::sglobal::deserialize_to(argsv?, &self.method.tpe().t_range);
+        }
         smethod_eval_fn(&self.method)?(env, ectx, ov, argsv?)
     }
 }
@@ -27,11 +57,13 @@ mod tests {
     use ergotree_ir::mir::global_vars::GlobalVars;
     use ergotree_ir::mir::option_get::OptionGet;
     use ergotree_ir::mir::unary_op::OneArgOpTryBuild;
-    use ergotree_ir::types::sbox;
+    use ergotree_ir::types::stype_param::STypeVar;
     use sigma_test_util::force_any_val;
 
     use crate::eval::context::Context;
+    use crate::eval::extract_reg_as::tests::ctx_with_self_registers;
     use crate::eval::tests::eval_out;
+    use crate::eval::tests::try_eval_out;
 
     use super::*;
 
@@ -51,4 +83,48 @@ mod tests {
             ctx.self_box.value.as_i64()
         );
     }
+
+    fn get_reg_expr(register_id: i8, elem_tpe: SType) -> Expr {
+        let method = sbox::GET_REG_METHOD
+            .clone()
+            .with_concrete_types(&[(STypeVar::t(), elem_tpe)].iter().cloned().collect());
+        MethodCall::new(
+            GlobalVars::SelfBox.into(),
+            method,
+            vec![Constant::from(register_id).into()],
+        )
+        .unwrap()
+        .into()
+    }
+
+    #[test]
+    fn eval_box_get_reg_typed() {
+        let ctx = ctx_with_self_registers();
+        assert_eq!(
+            eval_out::<Option<i64>>(&get_reg_expr(5, SType::SLong), ctx.clone()),
+            Some(1)
+        );
+        assert_eq!(
+            eval_out::<Option<i64>>(&get_reg_expr(6, SType::SLong), ctx.clone()),
+            None
+        );
+        // None in the register is not the same as an empty register
+        let opt_long = SType::SOption(SType::SLong.into());
+        assert_eq!(
+            eval_out::<Option<Option<i64>>>(&get_reg_expr(4, opt_long.clone()), ctx.clone()),
+            Some(None)
+        );
+        assert_eq!(
+            eval_out::<Option<Option<i64>>>(&get_reg_expr(7, opt_long), ctx.clone()),
+            None
+        );
+        assert_eq!(
+            try_eval_out::<Value>(&get_reg_expr(5, SType::SInt), ctx),
+            Err(EvalError::UnexpectedRegisterType {
+                register_id: 5,
+                expected: SType::SInt,
+                actual: SType::SLong,
+            })
+        );
+    }
 }
@@ -0,0 +1,218 @@
+//! Evaluation with a custom [`MethodRegistry`]
+//!
+//! The trees are parsed with the methods of the registry and the `MethodCall`/`PropertyCall`
+//! nodes are evaluated with the given functions for the added and replaced methods, the unchanged
+//! built-in methods are evaluated as usual.
+
+use ergotree_ir::mir::value::Value;
+use ergotree_ir::types::method_registry::MethodRegistry;
+use ergotree_ir::types::smethod::MethodId;
+use ergotree_ir::types::smethod::SMethod;
+use ergotree_ir::types::stype_companion::STypeCompanion;
+
+use super::EvalError;
+
+/// Evaluation of a method on the object and the arguments values
+pub type MethodEvalFn = fn(obj: Value, args: Vec<Value>) -> Result<Value, EvalError>;
+
+/// Method registry with the evaluation functions of the added and replaced methods
+#[derive(Debug, Clone)]
+pub struct EvalMethodRegistry {
+    methods: MethodRegistry,
+    eval_fns: Vec<(STypeCompanion, MethodId, MethodEvalFn)>,
+}
+
+impl EvalMethodRegistry {
+    /// Registry without the evaluation functions (only the unchanged built-in methods can be
+    /// evaluated)
+    pub fn new(methods: MethodRegistry) -> Self {
+        EvalMethodRegistry {
+            methods,
+            eval_fns: Vec::new(),
+        }
+    }
+
+    /// Set the evaluation function of the method (replaces the previously set one)
+    pub fn with_eval_fn(
+        mut self,
+        obj_type: STypeCompanion,
+        method_id: MethodId,
+        eval_fn: MethodEvalFn,
+    ) -> Self {
+        self.eval_fns
+            .retain(|(t, id, _)| !(*t == obj_type && *id == method_id));
+        self.eval_fns.push((obj_type, method_id, eval_fn));
+        self
+    }
+
+    /// Methods to parse the trees with
+    pub fn methods(&self) -> &MethodRegistry {
+        &self.methods
+    }
+
+    /// Evaluation function of the method, `None` for the unchanged built-in methods (evaluated by
+    /// the interpreter). Fails for the methods removed from the registry and the added or replaced
+    /// methods without the evaluation function.
+    pub(crate) fn eval_fn(&self, method: &SMethod) -> Result<Option<MethodEvalFn>, EvalError> {
+        let method_id = method.method_id();
+        if self
+            .methods
+            .method_by_id(method.obj_type, &method_id)
+            .is_none()
+        {
+            return Err(EvalError::NotFound(format!(
+                "Method {} of {:?} is removed from the registry",
+                method.name(),
+                method.obj_type
+            )));
+        }
+        if let Some((_, _, eval_fn)) = self
+            .eval_fns
+            .iter()
+            .find(|(t, id, _)| *t == method.obj_type && *id == method_id)
+        {
+            return Ok(Some(*eval_fn));
+        }
+        if self.methods.is_builtin_method(method.obj_type, &method_id) {
+            Ok(None)
+        } else {
+            Err(EvalError::NotFound(format!(
+                "Eval fn: no evaluation function for the method {} of {:?}",
+                method.name(),
+                method.obj_type
+            )))
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "arbitrary")]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::rc::Rc;
+
+    use ergotree_ir::ergo_tree::ErgoTree;
+    use ergotree_ir::ergo_tree::ErgoTreeHeader;
+    use ergotree_ir::mir::bin_op::BinOp;
+    use ergotree_ir::mir::bin_op::RelationOp;
+    use ergotree_ir::mir::constant::Constant;
+    use ergotree_ir::mir::constant::TryExtractInto;
+    use ergotree_ir::mir::expr::Expr;
+    use ergotree_ir::mir::method_call::MethodCall;
+    use ergotree_ir::serialization::SigmaSerializable;
+    use ergotree_ir::sigma_protocol::sigma_boolean::SigmaBoolean;
+    use ergotree_ir::types::scoll;
+    use ergotree_ir::types::sfunc::SFunc;
+    use ergotree_ir::types::smethod::SMethodDesc;
+    use ergotree_ir::types::stype::SType;
+    use ergotree_ir::types::stype_param::STypeVar;
+    use sigma_test_util::force_any_val;
+
+    use super::*;
+    use crate::eval::context::Context;
+    use crate::eval::env::Env;
+    use crate::eval::reduce_tree_to_crypto;
+    use crate::eval::reduce_tree_to_crypto_with_methods;
+    use crate::eval::settings::EvalSettings;
+
+    fn reverse_desc(method_id: MethodId) -> SMethodDesc {
+        SMethodDesc::new(
+            "reverse",
+            method_id,
+            SFunc::new(
+                vec![SType::SColl(SType::STypeVar(STypeVar::t()).into())],
+                SType::SColl(SType::STypeVar(STypeVar::t()).into()),
+            ),
+        )
+    }
+
+    fn reverse_eval_fn(obj: Value, _args: Vec<Value>) -> Result<Value, EvalError> {
+        let mut items = obj.try_extract_into::<Vec<i32>>()?;
+        items.reverse();
+        Ok(Constant::from(items).v.into())
+    }
+
+    /// Tree bytes of `Coll(1, 2).reverse == Coll(2, 1)` with the method of the given registry
+    fn reverse_tree_bytes(registry: &MethodRegistry, method_id: MethodId) -> Vec<u8> {
+        let obj: Expr = Constant::from(vec![1i32, 2]).into();
+        let method = registry
+            .from_ids(scoll::TYPE_CODE, method_id)
+            .unwrap()
+            .specialize_for(obj.tpe(), vec![])
+            .unwrap();
+        let call: Expr = MethodCall::new(obj, method, vec![]).unwrap().into();
+        let expr: Expr = BinOp {
+            kind: RelationOp::Eq.into(),
+            left: call.into(),
+            right: Expr::Const(vec![2i32, 1].into()).into(),
+        }
+        .into();
+        ErgoTree::new(ErgoTreeHeader::v1(true), &expr)
+            .unwrap()
+            .sigma_serialize_bytes()
+            .unwrap()
+    }
+
+    fn reduce(tree: &ErgoTree, registry: &EvalMethodRegistry) -> Result<SigmaBoolean, EvalError> {
+        let ctx = Rc::new(force_any_val::<Context>());
+        reduce_tree_to_crypto_with_methods(
+            tree,
+            &Env::empty(),
+            ctx,
+            &EvalSettings::default(),
+            registry,
+        )
+        .map(|res| res.sigma_prop)
+    }
+
+    #[test]
+    fn eval_added_method() {
+        let methods = MethodRegistry::builtin()
+            .with_method(STypeCompanion::Coll, reverse_desc(MethodId(200)))
+            .unwrap();
+        let bytes = reverse_tree_bytes(&methods, MethodId(200));
+        // the tree is parsed with the built-in methods, the root bytes are kept
+        let tree = ErgoTree::sigma_parse_bytes(&bytes).unwrap();
+        let ctx = Rc::new(force_any_val::<Context>());
+        assert!(
+            reduce_tree_to_crypto(&tree, &Env::empty(), ctx, &EvalSettings::default()).is_err()
+        );
+        let registry = EvalMethodRegistry::new(methods);
+        assert!(reduce(&tree, &registry).is_err());
+        let registry = registry.with_eval_fn(STypeCompanion::Coll, MethodId(200), reverse_eval_fn);
+        assert_eq!(reduce(&tree, &registry), Ok(true.into()));
+    }
+
+    #[test]
+    fn eval_replaced_method() {
+        let methods = MethodRegistry::builtin()
+            .with_replaced_method(
+                STypeCompanion::Coll,
+                reverse_desc(scoll::INDEX_OF_METHOD_ID),
+            )
+            .unwrap();
+        let tree =
+            ErgoTree::sigma_parse_bytes(&reverse_tree_bytes(&methods, scoll::INDEX_OF_METHOD_ID))
+                .unwrap();
+        // the built-in evaluation of `indexOf` is not used for the replacement
+        let registry = EvalMethodRegistry::new(methods);
+        assert!(reduce(&tree, &registry).is_err());
+        let registry = registry.with_eval_fn(
+            STypeCompanion::Coll,
+            scoll::INDEX_OF_METHOD_ID,
+            reverse_eval_fn,
+        );
+        assert_eq!(reduce(&tree, &registry), Ok(true.into()));
+    }
+
+    #[test]
+    fn eval_removed_method() {
+        let registry = EvalMethodRegistry::new(
+            MethodRegistry::builtin()
+                .without_method(STypeCompanion::Coll, scoll::INDEX_OF_METHOD_ID)
+                .unwrap(),
+        );
+        assert!(registry.eval_fn(&scoll::INDEX_OF_METHOD).is_err());
+        assert!(registry.eval_fn(&scoll::FLATMAP_METHOD).unwrap().is_none());
+    }
+}
@@ -15,13 +15,18 @@ impl Evaluable for Or {
     }
 }
 
+#[allow(clippy::unwrap_used)]
 #[allow(clippy::panic)]
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::eval::context::Context;
     use crate::eval::tests::eval_out;
+    use crate::eval::tests::failing_bool_expr;
+    use crate::eval::tests::try_eval_out;
+    use ergotree_ir::mir::collection::Collection;
     use ergotree_ir::mir::expr::Expr;
+    use ergotree_ir::types::stype::SType;
     use proptest::collection;
     use proptest::prelude::*;
     use sigma_test_util::force_any_val;
@@ -37,4 +42,17 @@ mod tests {
             prop_assert_eq!(res, bools.iter().any(|b| *b));
         }
     }
+
+    #[test]
+    fn eval_strictness() {
+        // all items are evaluated even if the result is known after the first one (as in the
+        // reference implementation)
+        let items = vec![Expr::Const(true.into()), failing_bool_expr()];
+        let expr: Expr = Or {
+            input: Box::new(Collection::new(SType::SBoolean, items).unwrap().into()),
+        }
+        .into();
+        let ctx = Rc::new(force_any_val::<Context>());
+        assert!(try_eval_out::<bool>(&expr, ctx).is_err());
+    }
 }
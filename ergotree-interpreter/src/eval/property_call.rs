@@ -9,7 +9,20 @@ use super::Evaluable;
 
 impl Evaluable for PropertyCall {
     fn eval(&self, env: &Env, ectx: &mut EvalContext) -> Result<Value, EvalError> {
+        if !ectx.settings.is_method_available(&self.method) {
+            return Err(EvalError::MethodNotActivated(
+                self.method.name(),
+                ectx.settings.activated_version,
+            ));
+        }
+        let registry_eval_fn = match &ectx.method_registry {
+            Some(registry) => registry.eval_fn(&self.method)?,
+            None => None,
+        };
         let ov = self.obj.eval(env, ectx)?;
+        if let Some(eval_fn) = registry_eval_fn {
+            return eval_fn(ov, vec![]);
+        }
         smethod_eval_fn(&self.method)?(env, ectx, ov, vec![])
     }
 }
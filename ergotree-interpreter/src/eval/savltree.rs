@@ -86,6 +86,35 @@ pub(crate) static UPDATE_DIGEST_EVAL_FN: EvalFn = |_env, _ctx, obj, args| {
     Ok(Value::AvlTree(Box::new(avl_tree_data)))
 };
 
+pub(crate) static CONTAINS_EVAL_FN: EvalFn = |_env, _ctx, obj, args| {
+    let avl_tree_data = obj.try_extract_into::<AvlTreeData>()?;
+
+    let key = {
+        let v = args
+            .get(0)
+            .cloned()
+            .ok_or_else(|| EvalError::AvlTree("eval is missing first arg (key)".to_string()))?;
+        v.try_extract_into::<Vec<u8>>()?
+    };
+    let proof = {
+        let v = args
+            .get(1)
+            .cloned()
+            .ok_or_else(|| EvalError::AvlTree("eval is missing second arg (proof)".to_string()))?;
+        Bytes::from(v.try_extract_into::<Vec<u8>>()?)
+    };
+
+    // an invalid proof results in `false` rather than an error (same as in sigmastate)
+    let contains = match create_verifier(&avl_tree_data, &proof) {
+        Ok(mut bv) => matches!(
+            bv.perform_one_operation(&Operation::Lookup(Bytes::from(key))),
+            Ok(Some(_))
+        ),
+        Err(_) => false,
+    };
+    Ok(Value::Boolean(contains))
+};
+
 pub(crate) static GET_EVAL_FN: EvalFn =
     |_env, _ctx, obj, args| {
         let avl_tree_data = obj.try_extract_into::<AvlTreeData>()?;
@@ -103,22 +132,7 @@ pub(crate) static GET_EVAL_FN: EvalFn =
             Bytes::from(v.try_extract_into::<Vec<u8>>()?)
         };
 
-        let starting_digest = Bytes::from(avl_tree_data.digest.0.to_vec());
-        let mut bv = BatchAVLVerifier::new(
-            &starting_digest,
-            &proof,
-            AVLTree::new(
-                |digest| Node::LabelOnly(NodeHeader::new(Some(*digest), None)),
-                avl_tree_data.key_length as usize,
-                avl_tree_data
-                    .value_length_opt
-                    .as_ref()
-                    .map(|v| **v as usize),
-            ),
-            None,
-            None,
-        )
-        .map_err(map_eval_err)?;
+        let mut bv = create_verifier(&avl_tree_data, &proof)?;
 
         match bv.perform_one_operation(&Operation::Lookup(Bytes::from(key))) {
             Ok(opt) => match opt {
@@ -151,22 +165,7 @@ pub(crate) static GET_MANY_EVAL_FN: EvalFn =
             Bytes::from(v.try_extract_into::<Vec<u8>>()?)
         };
 
-        let starting_digest = Bytes::from(avl_tree_data.digest.0.to_vec());
-        let mut bv = BatchAVLVerifier::new(
-            &starting_digest,
-            &proof,
-            AVLTree::new(
-                |digest| Node::LabelOnly(NodeHeader::new(Some(*digest), None)),
-                avl_tree_data.key_length as usize,
-                avl_tree_data
-                    .value_length_opt
-                    .as_ref()
-                    .map(|v| **v as usize),
-            ),
-            None,
-            None,
-        )
-        .map_err(map_eval_err)?;
+        let mut bv = create_verifier(&avl_tree_data, &proof)?;
 
         let mut res = vec![];
         for key in keys {
@@ -214,22 +213,7 @@ pub(crate) static INSERT_EVAL_FN: EvalFn =
             Bytes::from(v.try_extract_into::<Vec<u8>>()?)
         };
 
-        let starting_digest = Bytes::from(avl_tree_data.digest.0.to_vec());
-        let mut bv = BatchAVLVerifier::new(
-            &starting_digest,
-            &proof,
-            AVLTree::new(
-                |digest| Node::LabelOnly(NodeHeader::new(Some(*digest), None)),
-                avl_tree_data.key_length as usize,
-                avl_tree_data
-                    .value_length_opt
-                    .as_ref()
-                    .map(|v| **v as usize),
-            ),
-            None,
-            None,
-        )
-        .map_err(map_eval_err)?;
+        let mut bv = create_verifier(&avl_tree_data, &proof)?;
         for (key, value) in entries {
             if bv
                 .perform_one_operation(&Operation::Insert(KeyValue {
@@ -277,22 +261,7 @@ pub(crate) static REMOVE_EVAL_FN: EvalFn =
             Bytes::from(v.try_extract_into::<Vec<u8>>()?)
         };
 
-        let starting_digest = Bytes::from(avl_tree_data.digest.0.to_vec());
-        let mut bv = BatchAVLVerifier::new(
-            &starting_digest,
-            &proof,
-            AVLTree::new(
-                |digest| Node::LabelOnly(NodeHeader::new(Some(*digest), None)),
-                avl_tree_data.key_length as usize,
-                avl_tree_data
-                    .value_length_opt
-                    .as_ref()
-                    .map(|v| **v as usize),
-            ),
-            None,
-            None,
-        )
-        .map_err(map_eval_err)?;
+        let mut bv = create_verifier(&avl_tree_data, &proof)?;
         for key in keys {
             if bv
                 .perform_one_operation(&Operation::Remove(Bytes::from(key)))
@@ -337,22 +306,7 @@ pub(crate) static UPDATE_EVAL_FN: EvalFn =
             Bytes::from(v.try_extract_into::<Vec<u8>>()?)
         };
 
-        let starting_digest = Bytes::from(avl_tree_data.digest.0.to_vec());
-        let mut bv = BatchAVLVerifier::new(
-            &starting_digest,
-            &proof,
-            AVLTree::new(
-                |digest| Node::LabelOnly(NodeHeader::new(Some(*digest), None)),
-                avl_tree_data.key_length as usize,
-                avl_tree_data
-                    .value_length_opt
-                    .as_ref()
-                    .map(|v| **v as usize),
-            ),
-            None,
-            None,
-        )
-        .map_err(map_eval_err)?;
+        let mut bv = create_verifier(&avl_tree_data, &proof)?;
         for (key, value) in entries {
             if bv
                 .perform_one_operation(&Operation::Update(KeyValue {
@@ -378,6 +332,28 @@ pub(crate) static UPDATE_EVAL_FN: EvalFn =
         }
     };
 
+fn create_verifier(
+    avl_tree_data: &AvlTreeData,
+    proof: &Bytes,
+) -> Result<BatchAVLVerifier, EvalError> {
+    let starting_digest = Bytes::from(avl_tree_data.digest.0.to_vec());
+    BatchAVLVerifier::new(
+        &starting_digest,
+        proof,
+        AVLTree::new(
+            |digest| Node::LabelOnly(NodeHeader::new(Some(*digest), None)),
+            avl_tree_data.key_length as usize,
+            avl_tree_data
+                .value_length_opt
+                .as_ref()
+                .map(|v| **v as usize),
+        ),
+        None,
+        None,
+    )
+    .map_err(map_eval_err)
+}
+
 fn map_eval_err<T: std::fmt::Debug>(e: T) -> EvalError {
     EvalError::AvlTree(format!("{:?}", e))
 }
@@ -473,6 +449,70 @@ mod tests {
         }
     }
 
+    #[test]
+    fn eval_avl_contains() {
+        let mut prover = populate_tree(vec![(vec![1u8], 10u64.to_be_bytes().to_vec())]);
+        let initial_digest =
+            ADDigest::sigma_parse_bytes(&prover.digest().unwrap().into_iter().collect::<Vec<_>>())
+                .unwrap();
+
+        let op1 = Operation::Lookup(Bytes::from(vec![1u8]));
+        let op2 = Operation::Lookup(Bytes::from(vec![2u8]));
+        prover.perform_one_operation(&op1).unwrap();
+        prover.perform_one_operation(&op2).unwrap();
+        let proof: Constant = prover
+            .generate_proof()
+            .into_iter()
+            .collect::<Vec<_>>()
+            .into();
+
+        let tree_flags = AvlTreeFlags::new(false, false, false);
+        let obj = Expr::Const(
+            AvlTreeData {
+                digest: initial_digest,
+                tree_flags: tree_flags.clone(),
+                key_length: 1,
+                value_length_opt: None,
+            }
+            .into(),
+        );
+
+        let expr_found: Expr = MethodCall::new(
+            obj.clone(),
+            savltree::CONTAINS_METHOD.clone(),
+            vec![vec![1i8].into(), proof.clone().into()],
+        )
+        .unwrap()
+        .into();
+        let expr_not_found: Expr = MethodCall::new(
+            obj,
+            savltree::CONTAINS_METHOD.clone(),
+            vec![vec![2i8].into(), proof.clone().into()],
+        )
+        .unwrap()
+        .into();
+        let obj_other_digest = Expr::Const(
+            AvlTreeData {
+                digest: ADDigest::zero(),
+                tree_flags,
+                key_length: 1,
+                value_length_opt: None,
+            }
+            .into(),
+        );
+        let expr_bad_proof: Expr = MethodCall::new(
+            obj_other_digest,
+            savltree::CONTAINS_METHOD.clone(),
+            vec![vec![1i8].into(), proof.into()],
+        )
+        .unwrap()
+        .into();
+
+        assert!(eval_out_wo_ctx::<bool>(&expr_found));
+        assert!(!eval_out_wo_ctx::<bool>(&expr_not_found));
+        assert!(!eval_out_wo_ctx::<bool>(&expr_bad_proof));
+    }
+
     #[test]
     fn eval_avl_get_many() {
         let mut prover = populate_tree(vec![
@@ -987,3 +1027,8 @@ mod tests {
         ]
     }
 }
+
+#[allow(clippy::unwrap_used, clippy::expect_used, clippy::panic)]
+#[cfg(test)]
+#[cfg(feature = "arbitrary")]
+mod scorex_vectors;
@@ -0,0 +1,419 @@
+//! AVL+ tree conformance tests against the vectors in `tests/avl_vectors`.
+//!
+//! A vector is a batch of operations of the same kind over a tree built from the given entries,
+//! with the starting digest, the serialized proof, the lookup results and the ending digest as
+//! produced by the reference implementation. Every vector is checked twice:
+//! - the Rust prover must build the same tree (starting digest) and produce byte-identical proof
+//!   and ending digest, otherwise proofs made in Rust are rejected on-chain;
+//! - the AvlTree methods of the interpreter must accept the reference proof and return the same
+//!   results and digest, otherwise on-chain spending with proofs made by the node (or any other
+//!   Scala tool) fails.
+
+use bytes::Bytes;
+use ergotree_ir::chain::digest32::ADDigest;
+use ergotree_ir::mir::avl_tree_data::AvlTreeData;
+use ergotree_ir::mir::avl_tree_data::AvlTreeFlags;
+use ergotree_ir::mir::constant::Constant;
+use ergotree_ir::mir::constant::Literal;
+use ergotree_ir::mir::expr::Expr;
+use ergotree_ir::mir::method_call::MethodCall;
+use ergotree_ir::mir::value::CollKind;
+use ergotree_ir::mir::value::NativeColl;
+use ergotree_ir::mir::value::Value;
+use ergotree_ir::serialization::SigmaSerializable;
+use ergotree_ir::types::savltree;
+use ergotree_ir::types::stuple::STuple;
+use ergotree_ir::types::stype::SType;
+use ergotree_ir::util::AsVecU8;
+use scorex_crypto_avltree::authenticated_tree_ops::AuthenticatedTreeOps;
+use scorex_crypto_avltree::batch_avl_prover::BatchAVLProver;
+use scorex_crypto_avltree::batch_node::AVLTree;
+use scorex_crypto_avltree::batch_node::Node;
+use scorex_crypto_avltree::batch_node::NodeHeader;
+use scorex_crypto_avltree::operation::KeyValue;
+use scorex_crypto_avltree::operation::Operation;
+use serde_json::json;
+use std::convert::TryFrom;
+
+use crate::eval::tests::eval_out_wo_ctx;
+
+/// Vectors generated with the Scala scorex-crypto library (see `tests/avl_vectors/README.md`)
+const SCOREX_VECTORS: &str = include_str!("../../../tests/avl_vectors/scorex.json");
+
+struct Vector {
+    name: String,
+    key_length: usize,
+    value_length: Option<usize>,
+    entries: Vec<(Vec<u8>, Vec<u8>)>,
+    starting_digest: Vec<u8>,
+    operation: String,
+    keys: Vec<Vec<u8>>,
+    values: Vec<Vec<u8>>,
+    proof: Vec<u8>,
+    results: Vec<Option<Vec<u8>>>,
+    ending_digest: Vec<u8>,
+}
+
+fn hex(json: &serde_json::Value) -> Vec<u8> {
+    base16::decode(json.as_str().expect("hex string expected").as_bytes())
+        .expect("invalid hex string")
+}
+
+fn hex_list(json: &serde_json::Value) -> Vec<Vec<u8>> {
+    json.as_array()
+        .map_or(vec![], |items| items.iter().map(hex).collect())
+}
+
+impl Vector {
+    fn from_json(json: &serde_json::Value) -> Vector {
+        Vector {
+            name: json["name"].as_str().unwrap().to_string(),
+            key_length: json["keyLength"].as_u64().unwrap() as usize,
+            value_length: json["valueLength"].as_u64().map(|l| l as usize),
+            entries: json["entries"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|kv| (hex(&kv[0]), hex(&kv[1])))
+                .collect(),
+            starting_digest: hex(&json["startingDigest"]),
+            operation: json["operation"].as_str().unwrap().to_string(),
+            keys: hex_list(&json["keys"]),
+            values: hex_list(&json["values"]),
+            proof: hex(&json["proof"]),
+            results: json["results"].as_array().map_or(vec![], |items| {
+                items
+                    .iter()
+                    .map(|r| if r.is_null() { None } else { Some(hex(r)) })
+                    .collect()
+            }),
+            ending_digest: hex(&json["endingDigest"]),
+        }
+    }
+
+    fn operations(&self) -> Vec<Operation> {
+        self.keys
+            .iter()
+            .enumerate()
+            .map(|(i, key)| {
+                let key = Bytes::from(key.clone());
+                let kv = || KeyValue {
+                    key: key.clone(),
+                    value: Bytes::from(self.values[i].clone()),
+                };
+                match self.operation.as_str() {
+                    "lookup" => Operation::Lookup(key.clone()),
+                    "insert" => Operation::Insert(kv()),
+                    "update" => Operation::Update(kv()),
+                    "remove" => Operation::Remove(key.clone()),
+                    op => panic!("{}: unknown operation {}", self.name, op),
+                }
+            })
+            .collect()
+    }
+
+    fn avl_tree_data(&self) -> AvlTreeData {
+        AvlTreeData {
+            digest: ADDigest::sigma_parse_bytes(&self.starting_digest).unwrap(),
+            tree_flags: AvlTreeFlags::new(true, true, true),
+            key_length: self.key_length as u32,
+            value_length_opt: self.value_length.map(|l| Box::new(l as u32)),
+        }
+    }
+}
+
+fn new_prover(key_length: usize, value_length: Option<usize>) -> BatchAVLProver {
+    BatchAVLProver::new(
+        AVLTree::new(
+            |digest| Node::LabelOnly(NodeHeader::new(Some(*digest), None)),
+            key_length,
+            value_length,
+        ),
+        true,
+    )
+}
+
+/// Prover with the tree of the given entries, the proof of the inserts is discarded
+fn populated_prover(
+    key_length: usize,
+    value_length: Option<usize>,
+    entries: &[(Vec<u8>, Vec<u8>)],
+) -> BatchAVLProver {
+    let mut prover = new_prover(key_length, value_length);
+    for (key, value) in entries {
+        prover
+            .perform_one_operation(&Operation::Insert(KeyValue {
+                key: Bytes::from(key.clone()),
+                value: Bytes::from(value.clone()),
+            }))
+            .unwrap();
+    }
+    prover.generate_proof();
+    prover
+}
+
+fn check_prover(v: &Vector) {
+    let mut prover = populated_prover(v.key_length, v.value_length, &v.entries);
+    assert_eq!(
+        prover.digest().unwrap().to_vec(),
+        v.starting_digest,
+        "{}: starting digest",
+        v.name
+    );
+    let results: Vec<Option<Vec<u8>>> = v
+        .operations()
+        .iter()
+        .map(|op| {
+            prover
+                .perform_one_operation(op)
+                .unwrap()
+                .map(|r| r.to_vec())
+        })
+        .collect();
+    if v.operation == "lookup" {
+        assert_eq!(results, v.results, "{}: lookup results", v.name);
+    }
+    assert_eq!(
+        prover.digest().unwrap().to_vec(),
+        v.ending_digest,
+        "{}: ending digest",
+        v.name
+    );
+    assert_eq!(
+        prover.generate_proof().to_vec(),
+        v.proof,
+        "{}: proof",
+        v.name
+    );
+}
+
+fn coll_byte_literal(bytes: &[u8]) -> Literal {
+    Literal::try_from(bytes.to_vec()).unwrap()
+}
+
+fn keys_const(keys: &[Vec<u8>]) -> Constant {
+    Constant {
+        tpe: SType::SColl(Box::new(SType::SColl(Box::new(SType::SByte)))),
+        v: Literal::Coll(CollKind::WrappedColl {
+            items: keys.iter().map(|k| coll_byte_literal(k)).collect(),
+            elem_tpe: SType::SColl(Box::new(SType::SByte)),
+        }),
+    }
+}
+
+fn entries_const(keys: &[Vec<u8>], values: &[Vec<u8>]) -> Constant {
+    let pair_tpe = SType::STuple(STuple::pair(
+        SType::SColl(Box::new(SType::SByte)),
+        SType::SColl(Box::new(SType::SByte)),
+    ));
+    Constant {
+        tpe: SType::SColl(Box::new(pair_tpe.clone())),
+        v: Literal::Coll(CollKind::WrappedColl {
+            items: keys
+                .iter()
+                .zip(values)
+                .map(|(k, v)| Literal::Tup([coll_byte_literal(k), coll_byte_literal(v)].into()))
+                .collect(),
+            elem_tpe: pair_tpe,
+        }),
+    }
+}
+
+fn check_interpreter(v: &Vector) {
+    let obj = Expr::Const(v.avl_tree_data().into());
+    let proof: Constant = v.proof.clone().into();
+    let (method, arg) = match v.operation.as_str() {
+        "lookup" => (savltree::GET_MANY_METHOD.clone(), keys_const(&v.keys)),
+        "insert" => (
+            savltree::INSERT_METHOD.clone(),
+            entries_const(&v.keys, &v.values),
+        ),
+        "update" => (
+            savltree::UPDATE_METHOD.clone(),
+            entries_const(&v.keys, &v.values),
+        ),
+        "remove" => (savltree::REMOVE_METHOD.clone(), keys_const(&v.keys)),
+        op => panic!("{}: unknown operation {}", v.name, op),
+    };
+    let expr: Expr = MethodCall::new(obj, method, vec![arg.into(), proof.into()])
+        .unwrap()
+        .into();
+    match eval_out_wo_ctx::<Value>(&expr) {
+        Value::Coll(CollKind::WrappedColl { items, .. }) => {
+            let results: Vec<Option<Vec<u8>>> = items
+                .into_iter()
+                .map(|item| match item {
+                    Value::Opt(opt) => match *opt {
+                        None => None,
+                        Some(Value::Coll(CollKind::NativeColl(NativeColl::CollByte(b)))) => {
+                            Some(b.as_vec_u8())
+                        }
+                        Some(r) => panic!("{}: unexpected lookup result {:?}", v.name, r),
+                    },
+                    r => panic!("{}: unexpected lookup result {:?}", v.name, r),
+                })
+                .collect();
+            assert_eq!(results, v.results, "{}: lookup results", v.name);
+        }
+        Value::Opt(opt) => match *opt {
+            Some(Value::AvlTree(avl)) => assert_eq!(
+                avl.digest,
+                ADDigest::sigma_parse_bytes(&v.ending_digest).unwrap(),
+                "{}: ending digest",
+                v.name
+            ),
+            r => panic!("{}: unexpected result {:?}", v.name, r),
+        },
+        r => panic!("{}: unexpected result {:?}", v.name, r),
+    }
+}
+
+fn check_vectors(json: &str) -> usize {
+    let vectors: Vec<serde_json::Value> =
+        serde_json::from_str(json).expect("vectors fixture should be a JSON array");
+    for json in &vectors {
+        let v = Vector::from_json(json);
+        check_prover(&v);
+        check_interpreter(&v);
+    }
+    vectors.len()
+}
+
+#[test]
+#[ignore = "no scrypto vectors in tests/avl_vectors/scorex.json yet"]
+fn scorex_vectors() {
+    assert!(
+        check_vectors(SCOREX_VECTORS) > 0,
+        "tests/avl_vectors/scorex.json has no vectors"
+    );
+}
+
+/// Vector in the fixture format made by the Rust prover
+fn rust_vector(
+    name: &str,
+    value_length: Option<usize>,
+    entries: &[(Vec<u8>, Vec<u8>)],
+    operation: &str,
+    keys: &[Vec<u8>],
+    values: &[Vec<u8>],
+) -> serde_json::Value {
+    let key_length = 32;
+    let mut prover = populated_prover(key_length, value_length, entries);
+    let starting_digest = prover.digest().unwrap().to_vec();
+    let mut v = Vector {
+        name: name.to_string(),
+        key_length,
+        value_length,
+        entries: entries.to_vec(),
+        starting_digest: starting_digest.clone(),
+        operation: operation.to_string(),
+        keys: keys.to_vec(),
+        values: values.to_vec(),
+        proof: vec![],
+        results: vec![],
+        ending_digest: vec![],
+    };
+    let results: Vec<Option<String>> = v
+        .operations()
+        .iter()
+        .map(|op| {
+            prover
+                .perform_one_operation(op)
+                .unwrap()
+                .map(|r| base16::encode_lower(&r.to_vec()))
+        })
+        .collect();
+    v.ending_digest = prover.digest().unwrap().to_vec();
+    v.proof = prover.generate_proof().to_vec();
+    let results = if operation == "lookup" {
+        json!(results)
+    } else {
+        json!(null)
+    };
+    let entries: Vec<Vec<String>> = entries
+        .iter()
+        .map(|(key, value)| vec![base16::encode_lower(key), base16::encode_lower(value)])
+        .collect();
+    let keys: Vec<String> = keys.iter().map(base16::encode_lower).collect();
+    let values: Vec<String> = values.iter().map(base16::encode_lower).collect();
+    json!({
+        "name": name,
+        "keyLength": key_length,
+        "valueLength": value_length,
+        "entries": entries,
+        "startingDigest": base16::encode_lower(&starting_digest),
+        "operation": operation,
+        "keys": keys,
+        "values": values,
+        "proof": base16::encode_lower(&v.proof),
+        "results": results,
+        "endingDigest": base16::encode_lower(&v.ending_digest),
+    })
+}
+
+fn key(i: u8) -> Vec<u8> {
+    vec![i; 32]
+}
+
+fn value(i: u64) -> Vec<u8> {
+    i.to_be_bytes().to_vec()
+}
+
+#[test]
+fn rust_prover_vectors() {
+    // checks the harness itself and that the interpreter accepts the proofs of the Rust prover,
+    // the vectors can be compared with the Scala ones having the same inputs
+    let entries: Vec<(Vec<u8>, Vec<u8>)> = (1..=10u8).map(|i| (key(i), value(i as u64))).collect();
+    let vectors = vec![
+        rust_vector(
+            "insert into empty tree",
+            None,
+            &[],
+            "insert",
+            &[key(1), key(2), key(3)],
+            &[value(1), value(2), value(3)],
+        ),
+        rust_vector(
+            "insert",
+            None,
+            &entries,
+            "insert",
+            &[key(11), key(12)],
+            &[value(11), value(12)],
+        ),
+        rust_vector(
+            "lookup",
+            None,
+            &entries,
+            "lookup",
+            &[key(1), key(5), key(11)],
+            &[],
+        ),
+        rust_vector(
+            "update",
+            None,
+            &entries,
+            "update",
+            &[key(2), key(7)],
+            &[value(20), value(70)],
+        ),
+        rust_vector(
+            "remove",
+            None,
+            &entries,
+            "remove",
+            &[key(3), key(4), key(10)],
+            &[],
+        ),
+        rust_vector(
+            "fixed value length",
+            Some(8),
+            &entries,
+            "insert",
+            &[key(100), key(200)],
+            &[value(100), value(200)],
+        ),
+    ];
+    let json = serde_json::to_string(&vectors).unwrap();
+    assert_eq!(check_vectors(&json), 6);
+}
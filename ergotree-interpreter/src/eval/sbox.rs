@@ -1,11 +1,13 @@
-use std::convert::TryInto;
 use std::rc::Rc;
 
+use crate::eval::extract_reg_as::get_register_value;
 use crate::eval::EvalError;
 
 use ergotree_ir::chain::ergo_box::ErgoBox;
 use ergotree_ir::mir::constant::TryExtractInto;
 use ergotree_ir::mir::value::Value;
+use ergotree_ir::types::stype::SType;
+use ergotree_ir::types::stype_param::STypeVar;
 
 use super::EvalFn;
 
@@ -15,25 +17,22 @@ pub(crate) static VALUE_EVAL_FN: EvalFn = |_env, _ctx, obj, _args| {
     ))
 };
 
-pub(crate) static GET_REG_EVAL_FN: EvalFn = |_env, _ctx, obj, args| {
-    Ok(Value::Opt(Box::new(
-        obj.try_extract_into::<Rc<ErgoBox>>()?
-            .get_register(
-                args.get(0)
-                    .cloned()
-                    .ok_or_else(|| EvalError::NotFound("register index is missing".to_string()))?
-                    .try_extract_into::<i8>()?
-                    .try_into()
-                    .map_err(|e| {
-                        EvalError::RegisterIdOutOfBounds(format!(
-                            "register index is out of bounds: {:?} ",
-                            e
-                        ))
-                    })?,
-            )
-            .map(|c| Value::from(c.v)),
-    )))
-};
+pub(crate) static GET_REG_EVAL_FN: EvalFn =
+    |_env, _ctx, obj, args| get_reg(obj, args, &SType::STypeVar(STypeVar::t()));
+
+/// `Box.getReg[T]`, where the register value is checked to be of the `elem_tpe` type (`T`)
+pub(crate) fn get_reg(obj: Value, args: Vec<Value>, elem_tpe: &SType) -> Result<Value, EvalError> {
+    let register_id = args
+        .get(0)
+        .cloned()
+        .ok_or_else(|| EvalError::NotFound("register index is missing".to_string()))?
+        .try_extract_into::<i8>()?;
+    get_register_value(
+        &obj.try_extract_into::<Rc<ErgoBox>>()?,
+        register_id,
+        elem_tpe,
+    )
+}
 
 pub(crate) static TOKENS_EVAL_FN: EvalFn = |_env, _ctx, obj, _args| {
     let res: Value = obj.try_extract_into::<Rc<ErgoBox>>()?.tokens_raw().into();
@@ -1,3 +1,4 @@
+use crate::eval::env::Env;
 use crate::eval::EvalError;
 use crate::eval::Evaluable;
 
@@ -35,7 +36,7 @@ pub(crate) static INDEX_OF_EVAL_FN: EvalFn = |_env, _ctx, obj, args| {
     }))
 };
 
-pub(crate) static FLATMAP_EVAL_FN: EvalFn = |env, ctx, obj, args| {
+pub(crate) static FLATMAP_EVAL_FN: EvalFn = |_env, ctx, obj, args| {
     let input_v = obj;
     let lambda_v = args
         .get(0)
@@ -49,11 +50,12 @@ pub(crate) static FLATMAP_EVAL_FN: EvalFn = |env, ctx, obj, args| {
             input_v_clone
         ))),
     }?;
+    let closure_env = Env::closure(lambda);
     let mut lambda_call = |arg: Value| {
         let func_arg = lambda.args.first().ok_or_else(|| {
             EvalError::NotFound("flatmap: lambda has empty arguments list".to_string())
         })?;
-        let env1 = env.clone().extend(func_arg.idx, arg);
+        let env1 = closure_env.extend(func_arg.idx, arg);
         lambda.body.eval(&env1, ctx)
     };
     let mapper_input_tpe = lambda
@@ -301,6 +303,61 @@ pub(crate) static UPDATE_MANY_EVAL_FN: EvalFn =
         Ok(Value::Coll(CollKind::from_vec(input_tpe, res)?))
     };
 
+pub(crate) static DISTINCT_EVAL_FN: EvalFn = |_env, _ctx, obj, _args| {
+    let (input_tpe, normalized_input_vals) = match obj {
+        Value::Coll(coll) => Ok((coll.elem_tpe().clone(), coll.as_vec())),
+        _ => Err(EvalError::UnexpectedValue(format!(
+            "expected obj to be Value::Coll, got: {0:?}",
+            obj
+        ))),
+    }?;
+    // keeps the first occurrence of each element, in the original order
+    let mut res: Vec<Value> = Vec::with_capacity(normalized_input_vals.len());
+    for v in normalized_input_vals {
+        if !res.contains(&v) {
+            res.push(v);
+        }
+    }
+    Ok(Value::Coll(CollKind::from_vec(input_tpe, res)?))
+};
+
+// the collection and the collection argument of `startsWith` and `endsWith`
+fn affix_args(
+    obj: Value,
+    args: Vec<Value>,
+    method: &str,
+) -> Result<(Vec<Value>, Vec<Value>), EvalError> {
+    let input_vals = match obj {
+        Value::Coll(coll) => Ok(coll.as_vec()),
+        _ => Err(EvalError::UnexpectedValue(format!(
+            "expected obj to be Value::Coll, got: {0:?}",
+            obj
+        ))),
+    }?;
+    let affix_arg = args
+        .get(0)
+        .cloned()
+        .ok_or_else(|| EvalError::NotFound(format!("{}: missing first arg", method)))?;
+    let affix_vals = match affix_arg {
+        Value::Coll(coll) => Ok(coll.as_vec()),
+        _ => Err(EvalError::UnexpectedValue(format!(
+            "expected first arg to be Value::Coll, got: {0:?}",
+            affix_arg
+        ))),
+    }?;
+    Ok((input_vals, affix_vals))
+}
+
+pub(crate) static STARTS_WITH_EVAL_FN: EvalFn = |_env, _ctx, obj, args| {
+    let (input_vals, prefix) = affix_args(obj, args, "startsWith")?;
+    Ok(Value::Boolean(input_vals.starts_with(&prefix)))
+};
+
+pub(crate) static ENDS_WITH_EVAL_FN: EvalFn = |_env, _ctx, obj, args| {
+    let (input_vals, suffix) = affix_args(obj, args, "endsWith")?;
+    Ok(Value::Boolean(input_vals.ends_with(&suffix)))
+};
+
 #[allow(clippy::unwrap_used)]
 #[cfg(test)]
 #[cfg(feature = "arbitrary")]
@@ -309,17 +366,26 @@ mod tests {
     use ergotree_ir::mir::bin_op::RelationOp;
     use ergotree_ir::mir::collection::Collection;
     use ergotree_ir::mir::constant::Constant;
+    use ergotree_ir::mir::constant::TryExtractFrom;
+    use ergotree_ir::mir::constant::TryExtractInto;
     use ergotree_ir::mir::expr::Expr;
     use ergotree_ir::mir::func_value::FuncArg;
     use ergotree_ir::mir::func_value::FuncValue;
     use ergotree_ir::mir::method_call::MethodCall;
     use ergotree_ir::mir::val_use::ValUse;
+    use ergotree_ir::mir::value::Value;
     use ergotree_ir::types::scoll;
+    use ergotree_ir::types::smethod::SMethod;
     use ergotree_ir::types::stype::SType;
     use ergotree_ir::types::stype_param::STypeVar;
 
-    use crate::eval::tests::{eval_out_wo_ctx, try_eval_out_wo_ctx};
+    use crate::eval::context::Context;
+    use crate::eval::settings::EvalSettings;
+    use crate::eval::tests::{eval_out_wo_ctx, try_eval_out_with_settings, try_eval_out_wo_ctx};
+    use crate::eval::EvalError;
     use ergotree_ir::types::stype::SType::SBoolean;
+    use sigma_test_util::force_any_val;
+    use std::rc::Rc;
 
     #[test]
     fn eval_index_of() {
@@ -548,6 +614,35 @@ mod tests {
         assert_eq!(res, vec![0i32, 1i32, 2i32]);
     }
 
+    #[test]
+    fn eval_zip_with_indices() {
+        // coll.zip(coll.indices)
+        let coll_const: Constant = vec![5i64, 6i64, 7i64].into();
+        let indices: Expr = MethodCall::new(
+            coll_const.clone().into(),
+            scoll::INDICES_METHOD
+                .clone()
+                .with_concrete_types(&[(STypeVar::t(), SType::SLong)].iter().cloned().collect()),
+            vec![],
+        )
+        .unwrap()
+        .into();
+        let expr: Expr = MethodCall::new(
+            coll_const.into(),
+            scoll::ZIP_METHOD.clone().with_concrete_types(
+                &[(STypeVar::t(), SType::SLong), (STypeVar::iv(), SType::SInt)]
+                    .iter()
+                    .cloned()
+                    .collect(),
+            ),
+            vec![indices],
+        )
+        .unwrap()
+        .into();
+        let res = eval_out_wo_ctx::<Vec<(i64, i32)>>(&expr);
+        assert_eq!(res, vec![(5i64, 0i32), (6, 1), (7, 2)]);
+    }
+
     #[test]
     fn eval_indices_empty_coll() {
         let coll_const: Constant = Vec::<i64>::new().into();
@@ -652,6 +747,25 @@ mod tests {
         assert_eq!(res, vec![1i64, 5i64, 3i64]);
     }
 
+    #[test]
+    fn eval_update_not_activated() {
+        let coll_const: Constant = vec![1i64, 2i64, 3i64].into();
+        let expr: Expr = MethodCall::new(
+            coll_const.into(),
+            scoll::UPDATED_METHOD
+                .clone()
+                .with_concrete_types(&[(STypeVar::t(), SType::SLong)].iter().cloned().collect()),
+            vec![1i32.into(), 5i64.into()],
+        )
+        .unwrap()
+        .into();
+        let ctx = Rc::new(force_any_val::<Context>());
+        assert_eq!(
+            try_eval_out_with_settings::<Value>(&expr, ctx, EvalSettings::new(1)),
+            Err(EvalError::MethodNotActivated("updated", 1))
+        );
+    }
+
     #[test]
     fn eval_update_oob() {
         let coll_const: Constant = vec![1i64, 2i64, 3i64].into();
@@ -721,4 +835,97 @@ mod tests {
         .into();
         assert!(try_eval_out_wo_ctx::<Vec<i64>>(&expr).is_err());
     }
+
+    fn extended_method_expr(
+        method: &SMethod,
+        obj: Constant,
+        elem_tpe: SType,
+        args: Vec<Expr>,
+    ) -> Expr {
+        MethodCall::new(
+            obj.into(),
+            method
+                .clone()
+                .with_concrete_types(&[(STypeVar::t(), elem_tpe)].iter().cloned().collect()),
+            args,
+        )
+        .unwrap()
+        .into()
+    }
+
+    fn try_eval_extended<T: TryExtractFrom<Value>>(expr: &Expr) -> Result<T, EvalError> {
+        let ctx = Rc::new(force_any_val::<Context>());
+        try_eval_out_with_settings(expr, ctx, EvalSettings::extended())
+    }
+
+    #[test]
+    fn eval_distinct() {
+        let expr = extended_method_expr(
+            &scoll::DISTINCT_METHOD,
+            vec![3i64, 1, 3, 2, 1].into(),
+            SType::SLong,
+            vec![],
+        );
+        assert_eq!(try_eval_extended::<Vec<i64>>(&expr), Ok(vec![3i64, 1, 2]));
+        let bytes = extended_method_expr(
+            &scoll::DISTINCT_METHOD,
+            vec![7i8, 7, 7].into(),
+            SType::SByte,
+            vec![],
+        );
+        assert_eq!(try_eval_extended::<Vec<i8>>(&bytes), Ok(vec![7i8]));
+        let empty = extended_method_expr(
+            &scoll::DISTINCT_METHOD,
+            Vec::<i32>::new().into(),
+            SType::SInt,
+            vec![],
+        );
+        assert_eq!(try_eval_extended::<Vec<i32>>(&empty), Ok(vec![]));
+    }
+
+    #[test]
+    fn eval_starts_with_ends_with() {
+        let bytes: Constant = vec![1i8, 2, 3, 4].into();
+        let cases: Vec<(Vec<i8>, bool, bool)> = vec![
+            (vec![], true, true),
+            (vec![1, 2], true, false),
+            (vec![3, 4], false, true),
+            (vec![1, 2, 3, 4], true, true),
+            (vec![1, 2, 3, 4, 5], false, false),
+            (vec![2, 3], false, false),
+        ];
+        for (affix, starts, ends) in cases {
+            let starts_with = extended_method_expr(
+                &scoll::STARTS_WITH_METHOD,
+                bytes.clone(),
+                SType::SByte,
+                vec![Constant::from(affix.clone()).into()],
+            );
+            assert_eq!(try_eval_extended::<bool>(&starts_with), Ok(starts));
+            let ends_with = extended_method_expr(
+                &scoll::ENDS_WITH_METHOD,
+                bytes.clone(),
+                SType::SByte,
+                vec![Constant::from(affix).into()],
+            );
+            assert_eq!(try_eval_extended::<bool>(&ends_with), Ok(ends));
+        }
+    }
+
+    #[test]
+    fn eval_extended_methods_disabled_for_consensus() {
+        let expr = extended_method_expr(
+            &scoll::STARTS_WITH_METHOD,
+            vec![1i8, 2].into(),
+            SType::SByte,
+            vec![Constant::from(vec![1i8]).into()],
+        );
+        assert_eq!(
+            try_eval_out_wo_ctx::<bool>(&expr),
+            Err(EvalError::MethodNotActivated(
+                "startsWith",
+                EvalSettings::default().activated_version
+            ))
+        );
+    }
 }
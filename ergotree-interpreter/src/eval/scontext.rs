@@ -1,5 +1,8 @@
+use ergotree_ir::mir::avl_tree_data::AvlTreeData;
+use ergotree_ir::mir::avl_tree_data::AvlTreeFlags;
 use ergotree_ir::mir::value::CollKind;
 use ergotree_ir::mir::value::Value;
+use ergotree_ir::serialization::SigmaSerializable;
 use ergotree_ir::types::stype::SType;
 
 use super::EvalError;
@@ -70,6 +73,75 @@ pub(crate) static PRE_HEADER_EVAL_FN: EvalFn = |_env, ctx, obj, _args| {
     Ok(Box::from(ctx.ctx.pre_header.clone()).into())
 };
 
+pub(crate) static INPUTS_EVAL_FN: EvalFn = |_env, ctx, obj, _args| {
+    if obj != Value::Context {
+        return Err(EvalError::UnexpectedValue(format!(
+            "Context.INPUTS: expected object of Value::Context, got {:?}",
+            obj
+        )));
+    }
+    Ok(ctx.ctx.inputs.clone().into())
+};
+
+pub(crate) static OUTPUTS_EVAL_FN: EvalFn = |_env, ctx, obj, _args| {
+    if obj != Value::Context {
+        return Err(EvalError::UnexpectedValue(format!(
+            "Context.OUTPUTS: expected object of Value::Context, got {:?}",
+            obj
+        )));
+    }
+    Ok(ctx.ctx.outputs.clone().into())
+};
+
+pub(crate) static HEIGHT_EVAL_FN: EvalFn = |_env, ctx, obj, _args| {
+    if obj != Value::Context {
+        return Err(EvalError::UnexpectedValue(format!(
+            "Context.HEIGHT: expected object of Value::Context, got {:?}",
+            obj
+        )));
+    }
+    Ok((ctx.ctx.height.as_u32() as i32).into())
+};
+
+pub(crate) static SELF_EVAL_FN: EvalFn = |_env, ctx, obj, _args| {
+    if obj != Value::Context {
+        return Err(EvalError::UnexpectedValue(format!(
+            "Context.SELF: expected object of Value::Context, got {:?}",
+            obj
+        )));
+    }
+    Ok(ctx.ctx.self_box.clone().into())
+};
+
+/// Length of the keys (box ids) in the UTXO set tree
+const UTXO_TREE_KEY_LENGTH: u32 = 32;
+
+pub(crate) static LAST_BLOCK_UTXO_ROOT_HASH_EVAL_FN: EvalFn = |_env, ctx, obj, _args| {
+    if obj != Value::Context {
+        return Err(EvalError::UnexpectedValue(format!(
+            "Context.LastBlockUtxoRootHash: expected object of Value::Context, got {:?}",
+            obj
+        )));
+    }
+    // the UTXO set after the last block, as the node makes it (all operations allowed)
+    Ok(Value::AvlTree(Box::new(AvlTreeData {
+        digest: ctx.ctx.headers[0].state_root.clone(),
+        tree_flags: AvlTreeFlags::new(true, true, true),
+        key_length: UTXO_TREE_KEY_LENGTH,
+        value_length_opt: None,
+    })))
+};
+
+pub(crate) static MINER_PUBKEY_EVAL_FN: EvalFn = |_env, ctx, obj, _args| {
+    if obj != Value::Context {
+        return Err(EvalError::UnexpectedValue(format!(
+            "Context.minerPubKey: expected object of Value::Context, got {:?}",
+            obj
+        )));
+    }
+    Ok(ctx.ctx.pre_header.miner_pk.sigma_serialize_bytes()?.into())
+};
+
 #[cfg(test)]
 #[cfg(feature = "arbitrary")]
 #[allow(clippy::unwrap_used, clippy::expect_used)]
@@ -79,18 +151,28 @@ mod tests {
     use ergotree_ir::chain::ergo_box::ErgoBox;
     use ergotree_ir::chain::header::Header;
     use ergotree_ir::chain::preheader::PreHeader;
+    use ergotree_ir::mir::avl_tree_data::AvlTreeData;
     use ergotree_ir::mir::expr::Expr;
     use ergotree_ir::mir::property_call::PropertyCall;
+    use ergotree_ir::serialization::SigmaSerializable;
     use ergotree_ir::types::scontext;
+    use ergotree_ir::types::smethod::SMethod;
+    use proptest::prelude::*;
     use sigma_test_util::force_any_val;
     use std::rc::Rc;
 
+    fn context_property(method: &SMethod) -> Expr {
+        PropertyCall::new(Expr::Context, method.clone())
+            .unwrap()
+            .into()
+    }
+
     fn make_ctx_inputs_includes_self_box() -> Context {
         let ctx = force_any_val::<Context>();
         let self_box = force_any_val::<ErgoBox>();
         let inputs = vec![force_any_val::<ErgoBox>().into(), self_box.clone().into()];
         Context {
-            height: 0u32,
+            height: 0u32.into(),
             self_box: self_box.into(),
             inputs,
             ..ctx
@@ -124,4 +206,71 @@ mod tests {
         let ctx = Rc::new(force_any_val::<Context>());
         assert_eq!(eval_out::<PreHeader>(&expr, ctx.clone()), ctx.pre_header);
     }
+
+    #[test]
+    fn eval_tx_properties() {
+        let ctx = Rc::new(force_any_val::<Context>());
+        assert_eq!(
+            eval_out::<Vec<Rc<ErgoBox>>>(
+                &context_property(&scontext::INPUTS_PROPERTY),
+                ctx.clone()
+            ),
+            ctx.inputs
+        );
+        assert_eq!(
+            eval_out::<Vec<Rc<ErgoBox>>>(
+                &context_property(&scontext::OUTPUTS_PROPERTY),
+                ctx.clone()
+            ),
+            ctx.outputs
+        );
+        assert_eq!(
+            eval_out::<i32>(&context_property(&scontext::HEIGHT_PROPERTY), ctx.clone()),
+            ctx.height.as_u32() as i32
+        );
+        assert_eq!(
+            eval_out::<Rc<ErgoBox>>(&context_property(&scontext::SELF_PROPERTY), ctx.clone()),
+            ctx.self_box
+        );
+    }
+
+    #[test]
+    fn eval_miner_pubkey() {
+        let ctx = Rc::new(force_any_val::<Context>());
+        assert_eq!(
+            eval_out::<Vec<u8>>(
+                &context_property(&scontext::MINER_PUBKEY_PROPERTY),
+                ctx.clone()
+            ),
+            ctx.pre_header.miner_pk.sigma_serialize_bytes().unwrap()
+        );
+    }
+
+    proptest! {
+
+        #![proptest_config(ProptestConfig::with_cases(16))]
+
+        #[test]
+        fn eval_last_block_utxo_root_hash(ctx in any::<Context>()) {
+            let ctx = Rc::new(ctx);
+            let tree = eval_out::<AvlTreeData>(
+                &context_property(&scontext::LAST_BLOCK_UTXO_ROOT_HASH_PROPERTY),
+                ctx.clone(),
+            );
+            prop_assert_eq!(&tree.digest, &ctx.headers[0].state_root);
+            prop_assert_eq!(tree.key_length, 32);
+            prop_assert!(tree.value_length_opt.is_none());
+        }
+
+        #[test]
+        fn eval_headers_linked_to_pre_header(ctx in any::<Context>()) {
+            let ctx = Rc::new(ctx);
+            let headers =
+                eval_out::<[Header; 10]>(&context_property(&scontext::HEADERS_PROPERTY), ctx.clone());
+            let pre_header =
+                eval_out::<PreHeader>(&context_property(&scontext::PRE_HEADER_PROPERTY), ctx);
+            prop_assert_eq!(&pre_header.parent_id, &headers[0].id);
+            prop_assert!(pre_header.height > headers[0].height || pre_header.height.as_u32() == 0);
+        }
+    }
 }
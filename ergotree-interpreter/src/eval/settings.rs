@@ -0,0 +1,140 @@
+use ergotree_ir::compat::VersionedFeature;
+use ergotree_ir::ergo_tree::ErgoTree;
+use ergotree_ir::types::scoll;
+use ergotree_ir::types::smethod::SMethod;
+
+/// Script version activated with the v5.0 protocol (block version 3), where the collection
+/// methods `patch`, `updated` and `updateMany` became available
+pub const JIT_SCRIPT_VERSION: u8 = 2;
+
+/// Script version of the v6.0 protocol (`ErgoTreeVersion::V3`), where `Global.serialize` and
+/// `Global.deserializeTo` became available
+pub const V6_SCRIPT_VERSION: u8 = 3;
+
+/// Maximum script version supported by the interpreter
+pub const MAX_SUPPORTED_SCRIPT_VERSION: u8 = JIT_SCRIPT_VERSION;
+
+/// Settings of the script evaluation, the same as the reference implementation (`ErgoLikeInterpreter`)
+/// uses to decide on the method availability and the soft-fork behavior
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct EvalSettings {
+    /// Script version activated on the network (block version - 1)
+    pub activated_version: u8,
+    /// Version to evaluate the tree as, instead of the version in the tree header
+    pub tree_version_override: Option<u8>,
+    /// If true, the evaluation of a tree with a version higher than the activated one fails,
+    /// otherwise such a tree is reduced to `true` (soft-fork condition)
+    pub strict: bool,
+    /// If true, the methods proposed for the future script versions (`Coll.distinct`,
+    /// `Coll.startsWith`, `Coll.endsWith`) are available regardless of the activated version.
+    /// These are not part of the consensus, so it's only for the off-chain evaluation
+    /// ("extended interpreter" mode) and never for the transaction validation or signing.
+    pub extended: bool,
+}
+
+impl EvalSettings {
+    /// Settings for the given activated script version (not strict, without the tree version override)
+    pub fn new(activated_version: u8) -> Self {
+        EvalSettings {
+            activated_version,
+            tree_version_override: None,
+            strict: false,
+            extended: false,
+        }
+    }
+
+    /// Settings of the "extended interpreter" mode for the off-chain evaluation: the latest
+    /// supported version is activated and the methods proposed for the future script versions
+    /// are available
+    pub fn extended() -> Self {
+        EvalSettings {
+            extended: true,
+            ..EvalSettings::default()
+        }
+    }
+
+    /// Settings for the script version activated by the given block version
+    pub fn from_block_version(block_version: u8) -> Self {
+        EvalSettings::new(block_version.saturating_sub(1))
+    }
+
+    /// Version of the given tree for the evaluation (the override, if set)
+    pub fn tree_version(&self, tree: &ErgoTree) -> u8 {
+        self.tree_version_override
+            .unwrap_or_else(|| tree.header().version().into())
+    }
+
+    /// Returns true if the method can be called in the activated script version
+    pub fn is_method_available(&self, method: &SMethod) -> bool {
+        if is_extended_method(method) {
+            self.extended
+        } else {
+            self.activated_version >= min_script_version(method)
+        }
+    }
+}
+
+impl Default for EvalSettings {
+    /// The latest supported version is activated
+    fn default() -> Self {
+        EvalSettings::new(MAX_SUPPORTED_SCRIPT_VERSION)
+    }
+}
+
+/// Methods proposed for the future script versions (not activated on any version yet)
+fn is_extended_method(method: &SMethod) -> bool {
+    method.obj_type.type_code() == scoll::TYPE_CODE
+        && (method.method_id() == scoll::DISTINCT_METHOD_ID
+            || method.method_id() == scoll::STARTS_WITH_METHOD_ID
+            || method.method_id() == scoll::ENDS_WITH_METHOD_ID)
+}
+
+/// Minimal activated script version for the method (see [`VersionedFeature::of_method`])
+fn min_script_version(method: &SMethod) -> u8 {
+    VersionedFeature::of_method(method)
+        .map(|f| f.min_version().into())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ergotree_ir::ergo_tree::ErgoTreeVersion;
+    use ergotree_ir::types::sglobal;
+    use ergotree_ir::types::sheader;
+
+    #[test]
+    fn method_availability() {
+        let patch = &scoll::PATCH_METHOD;
+        assert!(!EvalSettings::new(1).is_method_available(patch));
+        assert!(EvalSettings::new(JIT_SCRIPT_VERSION).is_method_available(patch));
+        assert!(!EvalSettings::new(1).is_method_available(&scoll::UPDATE_MANY_METHOD));
+        assert!(EvalSettings::new(0).is_method_available(&scoll::INDEX_OF_METHOD));
+        let serialize = &sglobal::SERIALIZE_METHOD;
+        assert!(!EvalSettings::new(JIT_SCRIPT_VERSION).is_method_available(serialize));
+        assert!(EvalSettings::new(V6_SCRIPT_VERSION).is_method_available(serialize));
+        assert!(!EvalSettings::new(JIT_SCRIPT_VERSION)
+            .is_method_available(&sglobal::DESERIALIZE_TO_METHOD));
+        assert_eq!(u8::from(ErgoTreeVersion::V3), V6_SCRIPT_VERSION);
+        assert!(EvalSettings::new(0).is_method_available(&sheader::VOTES_PROPERTY));
+    }
+
+    #[test]
+    fn extended_method_availability() {
+        let distinct = &scoll::DISTINCT_METHOD;
+        assert!(!EvalSettings::default().is_method_available(distinct));
+        assert!(!EvalSettings::new(u8::MAX).is_method_available(&scoll::STARTS_WITH_METHOD));
+        assert!(EvalSettings::extended().is_method_available(distinct));
+        assert!(EvalSettings::extended().is_method_available(&scoll::ENDS_WITH_METHOD));
+        // the rest is as in the latest supported version
+        assert!(EvalSettings::extended().is_method_available(&scoll::PATCH_METHOD));
+        assert!(!EvalSettings::extended().is_method_available(&sglobal::SERIALIZE_METHOD));
+    }
+
+    #[test]
+    fn activated_version_from_block_version() {
+        assert_eq!(EvalSettings::from_block_version(1).activated_version, 0);
+        assert_eq!(EvalSettings::from_block_version(3).activated_version, 2);
+        assert_eq!(EvalSettings::from_block_version(0).activated_version, 0);
+    }
+}
@@ -0,0 +1,295 @@
+use ergotree_ir::mir::constant::TryExtractInto;
+use ergotree_ir::mir::value::Value;
+use ergotree_ir::types::stype::SType;
+use ergotree_ir::types::stype_param::STypeVar;
+use ergotree_ir::util::AsVecU8;
+
+use super::EvalError;
+use super::EvalFn;
+
+pub(crate) static SERIALIZE_EVAL_FN: EvalFn = |_env, _ctx, _obj, args| {
+    let arg = args.get(0).ok_or_else(|| {
+        EvalError::NotFound("Global.serialize: missing value to serialize".to_string())
+    })?;
+    let bytes: Vec<u8> = arg.sigma_serialize_data_bytes()?;
+    Ok(Value::from(bytes))
+};
+
+pub(crate) static DESERIALIZE_TO_EVAL_FN: EvalFn =
+    |_env, _ctx, _obj, args| deserialize_to(args, &SType::STypeVar(STypeVar::t()));
+
+/// `Global.deserializeTo[T]`, where the bytes are parsed as the data of the `tpe` type (`T`)
+pub(crate) fn deserialize_to(args: Vec<Value>, tpe: &SType) -> Result<Value, EvalError> {
+    let bytes = args
+        .get(0)
+        .cloned()
+        .ok_or_else(|| {
+            EvalError::NotFound("Global.deserializeTo: missing bytes to deserialize".to_string())
+        })?
+        .try_extract_into::<Vec<i8>>()?
+        .as_vec_u8();
+    Ok(Value::sigma_parse_data_bytes(tpe, &bytes)?)
+}
+
+#[allow(clippy::unwrap_used)]
+#[cfg(test)]
+#[cfg(feature = "arbitrary")]
+mod tests {
+    use ergotree_ir::chain::header::Header;
+    use ergotree_ir::ergo_lit;
+    use ergotree_ir::ergo_tree::ErgoTree;
+    use ergotree_ir::ergo_tree::ErgoTreeHeader;
+    use ergotree_ir::ergo_tree::ErgoTreeVersion;
+    use ergotree_ir::mir::bin_op::BinOp;
+    use ergotree_ir::mir::bin_op::RelationOp;
+    use ergotree_ir::mir::coll_by_index::ByIndex;
+    use ergotree_ir::mir::constant::Constant;
+    use ergotree_ir::mir::constant::TryExtractInto;
+    use ergotree_ir::mir::expr::Expr;
+    use ergotree_ir::mir::global_vars::GlobalVars;
+    use ergotree_ir::mir::method_call::MethodCall;
+    use ergotree_ir::mir::property_call::PropertyCall;
+    use ergotree_ir::mir::tuple::Tuple;
+    use ergotree_ir::mir::value::Value;
+    use ergotree_ir::serialization::SigmaSerializable;
+    use ergotree_ir::serialization::SigmaSerializationError;
+    use ergotree_ir::types::scontext;
+    use ergotree_ir::types::sglobal;
+    use ergotree_ir::types::stype::SType;
+    use ergotree_ir::types::stype_param::STypeVar;
+    use proptest::prelude::*;
+    use sigma_test_util::force_any_val;
+    use std::rc::Rc;
+
+    use crate::eval::context::Context;
+    use crate::eval::env::Env;
+    use crate::eval::reduce_tree_to_crypto;
+    use crate::eval::settings::EvalSettings;
+    use crate::eval::settings::JIT_SCRIPT_VERSION;
+    use crate::eval::settings::V6_SCRIPT_VERSION;
+    use crate::eval::tests::try_eval_out_with_settings;
+    use crate::eval::EvalError;
+
+    // An `Expr` for such code in ErgoScript `Global.serialize(arg)`
+    fn serialize_expr(arg: Expr) -> Expr {
+        let method = sglobal::SERIALIZE_METHOD
+            .clone()
+            .with_concrete_types(&[(STypeVar::t(), arg.tpe())].iter().cloned().collect());
+        MethodCall::new(Expr::Global, method, vec![arg])
+            .unwrap()
+            .into()
+    }
+
+    // An `Expr` for such code in ErgoScript `Global.deserializeTo[tpe](bytes)`
+    fn deserialize_to_expr(bytes: Expr, tpe: SType) -> Expr {
+        let method = sglobal::DESERIALIZE_TO_METHOD
+            .clone()
+            .with_concrete_types(&[(STypeVar::t(), tpe)].iter().cloned().collect());
+        MethodCall::new(Expr::Global, method, vec![bytes])
+            .unwrap()
+            .into()
+    }
+
+    fn try_eval(expr: Expr, ctx: Rc<Context>, activated_version: u8) -> Result<Value, EvalError> {
+        try_eval_out_with_settings(&expr, ctx, EvalSettings::new(activated_version))
+    }
+
+    fn eval_serialize(
+        arg: Expr,
+        ctx: Rc<Context>,
+        activated_version: u8,
+    ) -> Result<Vec<u8>, EvalError> {
+        try_eval_out_with_settings(
+            &serialize_expr(arg),
+            ctx,
+            EvalSettings::new(activated_version),
+        )
+    }
+
+    proptest! {
+
+        #![proptest_config(ProptestConfig::with_cases(16))]
+
+        #[test]
+        fn eval_serialize_constant(c in any::<Constant>()) {
+            let ctx = Rc::new(force_any_val::<Context>());
+            let res = eval_serialize(c.clone().into(), ctx, V6_SCRIPT_VERSION).unwrap();
+            // the same as the constant serialization without the type
+            let mut bytes = c.tpe.sigma_serialize_bytes().unwrap();
+            bytes.extend(res);
+            prop_assert_eq!(&bytes, &c.sigma_serialize_bytes().unwrap());
+            prop_assert_eq!(Constant::sigma_parse_bytes(&bytes).unwrap(), c);
+        }
+
+        #[test]
+        fn eval_deserialize_to_roundtrip(c in any::<Constant>()) {
+            let ctx = Rc::new(force_any_val::<Context>());
+            let expr = deserialize_to_expr(serialize_expr(c.clone().into()), c.tpe.clone());
+            let res = try_eval(expr, ctx, V6_SCRIPT_VERSION).unwrap();
+            prop_assert_eq!(res, Value::from(c.v));
+        }
+    }
+
+    #[test]
+    fn eval_serialize_nested() {
+        let ctx = Rc::new(force_any_val::<Context>());
+        // CONTEXT.headers(0)
+        let header: Expr = ByIndex::new(
+            PropertyCall::new(Expr::Context, scontext::HEADERS_PROPERTY.clone())
+                .unwrap()
+                .into(),
+            Expr::Const(0i32.into()),
+            None,
+        )
+        .unwrap()
+        .into();
+        let tuple: Expr = Tuple::new(vec![
+            Expr::GlobalVars(GlobalVars::SelfBox),
+            header,
+            ergo_lit!(None[Long]).into(),
+            ergo_lit!(Coll[Coll[Byte]]((1, 2), (3))).into(),
+        ])
+        .unwrap()
+        .into();
+        let res = eval_serialize(tuple, ctx.clone(), V6_SCRIPT_VERSION).unwrap();
+        let mut expected = ctx.self_box.sigma_serialize_bytes().unwrap();
+        expected.extend(ctx.headers[0].sigma_serialize_bytes().unwrap());
+        expected.push(0);
+        expected.extend(vec![2, 2, 1, 2, 1, 3]);
+        assert_eq!(res, expected);
+    }
+
+    #[test]
+    fn eval_serialize_header_roundtrip() {
+        let mut header = force_any_val::<Header>();
+        header.nonce = vec![7; Header::NONCE_SIZE];
+        let mut ctx = force_any_val::<Context>();
+        ctx.headers[0] = header;
+        let ctx = Rc::new(ctx);
+        let header_expr: Expr = ByIndex::new(
+            PropertyCall::new(Expr::Context, scontext::HEADERS_PROPERTY.clone())
+                .unwrap()
+                .into(),
+            Expr::Const(0i32.into()),
+            None,
+        )
+        .unwrap()
+        .into();
+        let res = eval_serialize(header_expr, ctx.clone(), V6_SCRIPT_VERSION).unwrap();
+        let parsed = Header::sigma_parse_bytes(&res).unwrap();
+        // the id is computed on parsing
+        assert_eq!(parsed.sigma_serialize_bytes().unwrap(), res);
+        assert_eq!(parsed.height, ctx.headers[0].height);
+        assert_eq!(parsed.miner_pk, ctx.headers[0].miner_pk);
+    }
+
+    #[test]
+    fn eval_deserialize_to_nested_with_header() {
+        let mut ctx = force_any_val::<Context>();
+        ctx.headers[0].nonce = vec![7; Header::NONCE_SIZE];
+        let ctx = Rc::new(ctx);
+        let header: Expr = ByIndex::new(
+            PropertyCall::new(Expr::Context, scontext::HEADERS_PROPERTY.clone())
+                .unwrap()
+                .into(),
+            Expr::Const(0i32.into()),
+            None,
+        )
+        .unwrap()
+        .into();
+        let tuple: Expr = Tuple::new(vec![header, ergo_lit!(None[Long]).into()])
+            .unwrap()
+            .into();
+        let tpe = tuple.tpe();
+        let bytes = eval_serialize(tuple.clone(), ctx.clone(), V6_SCRIPT_VERSION).unwrap();
+        // serialize(deserializeTo[(Header, Option[Long])](serialize(tuple)))
+        let expr = serialize_expr(deserialize_to_expr(serialize_expr(tuple), tpe));
+        let res = try_eval(expr, ctx, V6_SCRIPT_VERSION)
+            .unwrap()
+            .try_extract_into::<Vec<u8>>()
+            .unwrap();
+        assert_eq!(res, bytes);
+    }
+
+    #[test]
+    fn eval_deserialize_to_from_tree_bytes() {
+        // deserializeTo[Long](Coll(10)) == 5L, the type argument is only in the tree bytes
+        let expr: Expr = BinOp {
+            kind: RelationOp::Eq.into(),
+            left: deserialize_to_expr(Expr::Const(vec![10i8].into()), SType::SLong).into(),
+            right: Expr::Const(5i64.into()).into(),
+        }
+        .into();
+        let header = ErgoTreeHeader::with_version(ErgoTreeVersion::V3, true);
+        let bytes = ErgoTree::new(header, &expr)
+            .unwrap()
+            .sigma_serialize_bytes()
+            .unwrap();
+        let tree = ErgoTree::sigma_parse_bytes(&bytes).unwrap();
+        assert_eq!(tree.proposition().unwrap().as_ref(), &expr);
+        let ctx = Rc::new(force_any_val::<Context>());
+        let res = reduce_tree_to_crypto(
+            &tree,
+            &Env::empty(),
+            ctx,
+            &EvalSettings::new(V6_SCRIPT_VERSION),
+        )
+        .unwrap();
+        assert_eq!(res.sigma_prop, true.into());
+    }
+
+    #[test]
+    fn eval_deserialize_to_invalid_bytes() {
+        let ctx = Rc::new(force_any_val::<Context>());
+        // not all bytes are consumed
+        let expr = deserialize_to_expr(serialize_expr(ergo_lit!((1, 2)).into()), SType::SInt);
+        assert!(matches!(
+            try_eval(expr, ctx.clone(), V6_SCRIPT_VERSION),
+            Err(EvalError::SigmaParsingError(_))
+        ));
+        // not enough bytes
+        let expr = deserialize_to_expr(
+            Expr::Const(Vec::<i8>::new().into()),
+            SType::SColl(SType::SLong.into()),
+        );
+        assert!(matches!(
+            try_eval(expr, ctx.clone(), V6_SCRIPT_VERSION),
+            Err(EvalError::SigmaParsingError(_))
+        ));
+        let expr = deserialize_to_expr(Expr::Const(vec![2i8].into()), SType::SInt);
+        assert!(matches!(
+            try_eval(expr, ctx, JIT_SCRIPT_VERSION),
+            Err(EvalError::MethodNotActivated(
+                "deserializeTo",
+                JIT_SCRIPT_VERSION
+            ))
+        ));
+    }
+
+    #[test]
+    fn eval_serialize_not_supported() {
+        let ctx = Rc::new(force_any_val::<Context>());
+        let pre_header: Expr =
+            PropertyCall::new(Expr::Context, scontext::PRE_HEADER_PROPERTY.clone())
+                .unwrap()
+                .into();
+        assert_eq!(
+            eval_serialize(pre_header, ctx, V6_SCRIPT_VERSION),
+            Err(EvalError::SigmaSerializationError(
+                SigmaSerializationError::NotSupported("PreHeader data")
+            ))
+        );
+    }
+
+    #[test]
+    fn eval_serialize_not_activated() {
+        let ctx = Rc::new(force_any_val::<Context>());
+        assert_eq!(
+            eval_serialize(Expr::Const(1i32.into()), ctx, JIT_SCRIPT_VERSION),
+            Err(EvalError::MethodNotActivated(
+                "serialize",
+                JIT_SCRIPT_VERSION
+            ))
+        );
+    }
+}
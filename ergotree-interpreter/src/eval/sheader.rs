@@ -43,7 +43,7 @@ pub(crate) static EXTENSION_ROOT_EVAL_FN: EvalFn = |_env, _ctx, obj, _args| {
 
 pub(crate) static TIMESTAMP_EVAL_FN: EvalFn = |_env, _ctx, obj, _args| {
     let header = obj.try_extract_into::<Header>()?;
-    Ok((header.timestamp as i64).into())
+    Ok((header.timestamp.as_u64() as i64).into())
 };
 
 pub(crate) static N_BITS_EVAL_FN: EvalFn = |_env, _ctx, obj, _args| {
@@ -53,7 +53,7 @@ pub(crate) static N_BITS_EVAL_FN: EvalFn = |_env, _ctx, obj, _args| {
 
 pub(crate) static HEIGHT_EVAL_FN: EvalFn = |_env, _ctx, obj, _args| {
     let header = obj.try_extract_into::<Header>()?;
-    Ok((header.height as i32).into())
+    Ok((header.height.as_u32() as i32).into())
 };
 
 pub(crate) static MINER_PK_EVAL_FN: EvalFn = |_env, _ctx, obj, _args| {
@@ -101,9 +101,9 @@ mod tests {
         types::{scontext, sheader, smethod::SMethod},
         util::AsVecU8,
     };
-    use sigma_test_util::force_any_val;
 
     use crate::eval::{
+        context::canned::{mainnet_like_context, SEED},
         context::Context,
         tests::{eval_out, try_eval_out_wo_ctx},
     };
@@ -193,14 +193,14 @@ mod tests {
     #[test]
     fn test_eval_version() {
         let expr = create_get_header_property_expr(sheader::VERSION_PROPERTY.clone());
-        let ctx = Rc::new(force_any_val::<Context>());
+        let ctx = Rc::new(mainnet_like_context(SEED));
         let version = ctx.headers[HEADER_INDEX].version as i8;
         assert_eq!(version, eval_out::<i8>(&expr, ctx));
     }
 
     #[test]
     fn test_eval_ids() {
-        let ctx = Rc::new(force_any_val::<Context>());
+        let ctx = Rc::new(mainnet_like_context(SEED));
         let expected = ctx
             .headers
             .get(HEADER_INDEX)
@@ -212,7 +212,7 @@ mod tests {
 
     #[test]
     fn test_eval_roots() {
-        let ctx = Rc::new(force_any_val::<Context>());
+        let ctx = Rc::new(mainnet_like_context(SEED));
         let expected = ctx
             .headers
             .get(HEADER_INDEX)
@@ -231,7 +231,7 @@ mod tests {
     #[test]
     fn test_eval_state_root() {
         let expr = create_get_header_property_expr(sheader::STATE_ROOT_PROPERTY.clone());
-        let ctx = Rc::new(force_any_val::<Context>());
+        let ctx = Rc::new(mainnet_like_context(SEED));
         let expected = ctx.headers[HEADER_INDEX].state_root.clone();
         let actual = digest_from_bytes_signed::<33>(eval_out::<Vec<i8>>(&expr, ctx));
         assert_eq!(expected, actual);
@@ -240,8 +240,8 @@ mod tests {
     #[test]
     fn test_eval_timestamp() {
         let expr = create_get_header_property_expr(sheader::TIMESTAMP_PROPERTY.clone());
-        let ctx = Rc::new(force_any_val::<Context>());
-        let expected = ctx.headers[HEADER_INDEX].timestamp as i64;
+        let ctx = Rc::new(mainnet_like_context(SEED));
+        let expected = ctx.headers[HEADER_INDEX].timestamp.as_u64() as i64;
         let actual = eval_out::<i64>(&expr, ctx);
         assert_eq!(expected, actual);
     }
@@ -249,7 +249,7 @@ mod tests {
     #[test]
     fn test_eval_n_bits() {
         let expr = create_get_header_property_expr(sheader::N_BITS_PROPERTY.clone());
-        let ctx = Rc::new(force_any_val::<Context>());
+        let ctx = Rc::new(mainnet_like_context(SEED));
         let expected = ctx.headers[HEADER_INDEX].n_bits as i64;
         let actual = eval_out::<i64>(&expr, ctx);
         assert_eq!(expected, actual);
@@ -258,15 +258,15 @@ mod tests {
     #[test]
     fn test_eval_height() {
         let expr = create_get_header_property_expr(sheader::HEIGHT_PROPERTY.clone());
-        let ctx = Rc::new(force_any_val::<Context>());
-        let expected = ctx.headers[HEADER_INDEX].height as i32;
+        let ctx = Rc::new(mainnet_like_context(SEED));
+        let expected = ctx.headers[HEADER_INDEX].height.as_u32() as i32;
         let actual = eval_out::<i32>(&expr, ctx);
         assert_eq!(expected, actual);
     }
 
     #[test]
     fn test_eval_pks() {
-        let ctx = Rc::new(force_any_val::<Context>());
+        let ctx = Rc::new(mainnet_like_context(SEED));
         let expected = ctx
             .headers
             .get(HEADER_INDEX)
@@ -279,7 +279,7 @@ mod tests {
     #[test]
     fn test_eval_pow_distance() {
         let expr = create_get_header_property_expr(sheader::POW_DISTANCE_PROPERTY.clone());
-        let ctx = Rc::new(force_any_val::<Context>());
+        let ctx = Rc::new(mainnet_like_context(SEED));
         let expected = ctx.headers[HEADER_INDEX].pow_distance.clone();
         let actual = {
             let bi = eval_out::<BigInt256>(&expr, ctx);
@@ -291,7 +291,7 @@ mod tests {
     #[test]
     fn test_eval_pow_nonce() {
         let expr = create_get_header_property_expr(sheader::POW_NONCE_PROPERTY.clone());
-        let ctx = Rc::new(force_any_val::<Context>());
+        let ctx = Rc::new(mainnet_like_context(SEED));
         let expected = ctx.headers[HEADER_INDEX].nonce.clone();
         let actual = eval_out::<Vec<i8>>(&expr, ctx).as_vec_u8();
         assert_eq!(expected, actual);
@@ -300,7 +300,7 @@ mod tests {
     #[test]
     fn test_eval_votes() {
         let expr = create_get_header_property_expr(sheader::VOTES_PROPERTY.clone());
-        let ctx = Rc::new(force_any_val::<Context>());
+        let ctx = Rc::new(mainnet_like_context(SEED));
         let expected = ctx.headers[HEADER_INDEX].votes.clone();
         let actual = {
             let votes_bytes = eval_out::<Vec<i8>>(&expr, ctx).as_vec_u8();
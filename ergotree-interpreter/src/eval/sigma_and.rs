@@ -32,6 +32,9 @@ mod tests {
 
     use crate::eval::context::Context;
     use crate::eval::tests::eval_out;
+    use crate::eval::tests::failing_bool_expr;
+    use crate::eval::tests::try_eval_out;
+    use ergotree_ir::mir::bool_to_sigma::BoolToSigmaProp;
 
     use super::*;
 
@@ -57,4 +60,20 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn eval_strictness() {
+        // all items are evaluated even if the result is known after the first one (as in the
+        // reference implementation)
+        let items = vec![
+            Expr::Const(SigmaProp::new(false.into()).into()),
+            BoolToSigmaProp {
+                input: Box::new(failing_bool_expr()),
+            }
+            .into(),
+        ];
+        let expr: Expr = SigmaAnd::new(items).unwrap().into();
+        let ctx = Rc::new(force_any_val::<Context>());
+        assert!(try_eval_out::<SigmaProp>(&expr, ctx).is_err());
+    }
 }
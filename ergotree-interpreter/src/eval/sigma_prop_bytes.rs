@@ -25,11 +25,144 @@ impl Evaluable for SigmaPropBytes {
 #[allow(clippy::panic)]
 mod tests {
     use super::*;
+    use crate::eval::context::Context;
+    use crate::eval::tests::eval_out;
     use crate::eval::tests::eval_out_wo_ctx;
+    use ergotree_ir::ergo_tree::ErgoTree;
+    use ergotree_ir::mir::bin_op::BinOp;
+    use ergotree_ir::mir::bin_op::RelationOp;
+    use ergotree_ir::mir::bool_to_sigma::BoolToSigmaProp;
     use ergotree_ir::mir::constant::Constant;
+    use ergotree_ir::mir::constant::TryExtractInto;
     use ergotree_ir::mir::expr::Expr;
+    use ergotree_ir::mir::extract_script_bytes::ExtractScriptBytes;
+    use ergotree_ir::mir::global_vars::GlobalVars;
+    use ergotree_ir::mir::sigma_and::SigmaAnd;
+    use ergotree_ir::serialization::SigmaSerializable;
+    use ergotree_ir::sigma_protocol::sigma_boolean::cthreshold::Cthreshold;
+    use ergotree_ir::sigma_protocol::sigma_boolean::ProveDlog;
+    use ergotree_ir::sigma_protocol::sigma_boolean::SigmaBoolean;
+    use ergotree_ir::sigma_protocol::sigma_boolean::SigmaProofOfKnowledgeTree;
     use ergotree_ir::sigma_protocol::sigma_boolean::SigmaProp;
     use proptest::prelude::*;
+    use sigma_test_util::force_any_val;
+    use std::convert::TryInto;
+    use std::rc::Rc;
+
+    // P2PK trees of mainnet boxes
+    const MAINNET_P2PK_TREES: [&str; 2] = [
+        "0008cd0327e65711a59378c59359c3e1d0f7abe906479eccb76094e50fe79d743ccc15e6",
+        "0008cd03f1102eb87a4166bf9fbd6247d087e92e1412b0e819dbb5fbc4e716091ec4e4ec",
+    ];
+
+    fn prop_bytes_expr(input: Expr) -> Expr {
+        SigmaPropBytes {
+            input: Box::new(input),
+        }
+        .into()
+    }
+
+    fn mainnet_p2pk(tree_hex: &str) -> (ErgoTree, SigmaProp) {
+        let tree = ErgoTree::sigma_parse_bytes(&base16::decode(tree_hex).unwrap()).unwrap();
+        let prop = match &*tree.proposition().unwrap() {
+            Expr::Const(c) => c.clone().try_extract_into::<SigmaProp>().unwrap(),
+            e => panic!("expected P2PK proposition, got {:?}", e),
+        };
+        (tree, prop)
+    }
+
+    #[test]
+    fn eval_mainnet_p2pk() {
+        for tree_hex in MAINNET_P2PK_TREES.iter() {
+            let (_, prop) = mainnet_p2pk(tree_hex);
+            let e = prop_bytes_expr(Constant::from(prop).into());
+            assert_eq!(
+                eval_out_wo_ctx::<Vec<u8>>(&e),
+                base16::decode(tree_hex).unwrap()
+            );
+        }
+    }
+
+    // SELF.propositionBytes == pk.propBytes, the usual check that an output goes to the given key
+    #[test]
+    fn eval_self_prop_bytes_eq() {
+        for tree_hex in MAINNET_P2PK_TREES.iter() {
+            let (tree, prop) = mainnet_p2pk(tree_hex);
+            let e: Expr = BinOp {
+                kind: RelationOp::Eq.into(),
+                left: Box::new(
+                    ExtractScriptBytes {
+                        input: Box::new(GlobalVars::SelfBox.into()),
+                    }
+                    .into(),
+                ),
+                right: Box::new(prop_bytes_expr(Constant::from(prop).into())),
+            }
+            .into();
+            let mut ctx = force_any_val::<Context>();
+            let mut self_box = (*ctx.self_box).clone();
+            self_box.ergo_tree = tree;
+            ctx.self_box = Rc::new(self_box);
+            assert!(eval_out::<bool>(&e, Rc::new(ctx)));
+            // any other key
+            let ctx = force_any_val::<Context>();
+            assert!(!eval_out::<bool>(&e, Rc::new(ctx)));
+        }
+    }
+
+    // allOf(pk, sigmaProp(true)) is normalized to pk
+    #[test]
+    fn eval_normalized() {
+        let (tree, prop) = mainnet_p2pk(MAINNET_P2PK_TREES[0]);
+        let and: Expr = SigmaAnd::new(vec![
+            Constant::from(prop).into(),
+            BoolToSigmaProp {
+                input: Box::new(Expr::Const(true.into())),
+            }
+            .into(),
+        ])
+        .unwrap()
+        .into();
+        assert_eq!(
+            eval_out_wo_ctx::<Vec<u8>>(&prop_bytes_expr(and)),
+            tree.sigma_serialize_bytes().unwrap()
+        );
+    }
+
+    // atLeast(2, pk1, pk2, pk3): header, SigmaProp type, ATLEAST, k (unsigned short), number of
+    // children, children
+    #[test]
+    fn eval_threshold() {
+        let pks: Vec<ProveDlog> = MAINNET_P2PK_TREES
+            .iter()
+            .chain(MAINNET_P2PK_TREES[..1].iter())
+            .map(|tree_hex| match mainnet_p2pk(tree_hex).1.value() {
+                SigmaBoolean::ProofOfKnowledge(SigmaProofOfKnowledgeTree::ProveDlog(pk)) => {
+                    pk.clone()
+                }
+                sb => panic!("expected ProveDlog, got {:?}", sb),
+            })
+            .collect();
+        let prop = SigmaProp::new(SigmaBoolean::from(Cthreshold {
+            n: 2,
+            items: pks
+                .iter()
+                .cloned()
+                .map(SigmaBoolean::from)
+                .collect::<Vec<SigmaBoolean>>()
+                .try_into()
+                .unwrap(),
+        }));
+        let mut expected = vec![0x00, 0x08, 0x98, 0x02, 0x03];
+        for pk in &pks {
+            expected.push(0xcd);
+            expected.extend(pk.h.sigma_serialize_bytes().unwrap());
+        }
+        assert_eq!(
+            eval_out_wo_ctx::<Vec<u8>>(&prop_bytes_expr(Constant::from(prop).into())),
+            expected
+        );
+    }
 
     proptest! {
 
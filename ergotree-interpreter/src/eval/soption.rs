@@ -1,3 +1,4 @@
+use crate::eval::env::Env;
 use crate::eval::EvalError;
 use crate::eval::Evaluable;
 
@@ -5,7 +6,7 @@ use ergotree_ir::mir::value::Value;
 
 use super::EvalFn;
 
-pub(crate) static MAP_EVAL_FN: EvalFn = |env, ctx, obj, args| {
+pub(crate) static MAP_EVAL_FN: EvalFn = |_env, ctx, obj, args| {
     let input_v = obj;
     let lambda_v = args
         .get(0)
@@ -19,11 +20,12 @@ pub(crate) static MAP_EVAL_FN: EvalFn = |env, ctx, obj, args| {
             input_v_clone
         ))),
     }?;
+    let closure_env = Env::closure(lambda);
     let mut lambda_call = |arg: Value| {
         let func_arg = lambda.args.first().ok_or_else(|| {
             EvalError::NotFound("map: lambda has empty arguments list".to_string())
         })?;
-        let env1 = env.clone().extend(func_arg.idx, arg);
+        let env1 = closure_env.extend(func_arg.idx, arg);
         lambda.body.eval(&env1, ctx)
     };
     let normalized_input_val: Option<Value> = match input_v {
@@ -40,7 +42,7 @@ pub(crate) static MAP_EVAL_FN: EvalFn = |env, ctx, obj, args| {
     }
 };
 
-pub(crate) static FILTER_EVAL_FN: EvalFn = |env, ctx, obj, args| {
+pub(crate) static FILTER_EVAL_FN: EvalFn = |_env, ctx, obj, args| {
     let input_v = obj;
     let lambda_v = args
         .get(0)
@@ -54,11 +56,12 @@ pub(crate) static FILTER_EVAL_FN: EvalFn = |env, ctx, obj, args| {
             input_v_clone
         ))),
     }?;
+    let closure_env = Env::closure(lambda);
     let mut predicate_call = |arg: Value| {
         let func_arg = lambda.args.first().ok_or_else(|| {
             EvalError::NotFound("filter: lambda has empty arguments list".to_string())
         })?;
-        let env1 = env.clone().extend(func_arg.idx, arg);
+        let env1 = closure_env.extend(func_arg.idx, arg);
         lambda.body.eval(&env1, ctx)
     };
     let normalized_input_val: Option<Value> = match input_v {
@@ -14,7 +14,7 @@ pub(crate) static PARENT_ID_EVAL_FN: EvalFn = |_env, _ctx, obj, _args| {
 
 pub(crate) static TIMESTAMP_EVAL_FN: EvalFn = |_env, _ctx, obj, _args| {
     let preheader = obj.try_extract_into::<PreHeader>()?;
-    Ok((preheader.timestamp as i64).into())
+    Ok((preheader.timestamp.as_u64() as i64).into())
 };
 
 pub(crate) static N_BITS_EVAL_FN: EvalFn = |_env, _ctx, obj, _args| {
@@ -24,7 +24,7 @@ pub(crate) static N_BITS_EVAL_FN: EvalFn = |_env, _ctx, obj, _args| {
 
 pub(crate) static HEIGHT_EVAL_FN: EvalFn = |_env, _ctx, obj, _args| {
     let preheader = obj.try_extract_into::<PreHeader>()?;
-    Ok((preheader.height as i32).into())
+    Ok((preheader.height.as_u32() as i32).into())
 };
 
 pub(crate) static MINER_PK_EVAL_FN: EvalFn = |_env, _ctx, obj, _args| {
@@ -46,15 +46,21 @@ mod tests {
 
     use ergotree_ir::{
         chain::{block_id::BlockId, votes::Votes},
-        mir::{expr::Expr, property_call::PropertyCall},
+        mir::{
+            bin_op::{BinOp, RelationOp},
+            coll_by_index::ByIndex,
+            expr::Expr,
+            global_vars::GlobalVars,
+            method_call::MethodCall,
+            property_call::PropertyCall,
+        },
         sigma_protocol::dlog_group::EcPoint,
-        types::{scontext, smethod::SMethod, spreheader},
+        types::{scontext, sgroup_elem, sheader, smethod::SMethod, spreheader},
         util::AsVecU8,
     };
-    use sigma_test_util::force_any_val;
 
     use crate::eval::{
-        context::Context,
+        context::canned::{mainnet_like_context, SEED},
         tests::{eval_out, try_eval_out_wo_ctx},
     };
 
@@ -87,7 +93,7 @@ mod tests {
     #[test]
     fn test_eval_version() {
         let expr = create_get_preheader_property_expr(spreheader::VERSION_PROPERTY.clone());
-        let ctx = Rc::new(force_any_val::<Context>());
+        let ctx = Rc::new(mainnet_like_context(SEED));
         let expected = ctx.pre_header.version as i8;
         assert_eq!(expected, eval_out::<i8>(&expr, ctx));
     }
@@ -95,7 +101,7 @@ mod tests {
     #[test]
     fn test_eval_parent_id() {
         let expr = create_get_preheader_property_expr(spreheader::PARENT_ID_PROPERTY.clone());
-        let ctx = Rc::new(force_any_val::<Context>());
+        let ctx = Rc::new(mainnet_like_context(SEED));
         let expected = ctx.pre_header.parent_id.clone();
         let actual = {
             let bs = eval_out::<Vec<i8>>(&expr, ctx);
@@ -107,8 +113,8 @@ mod tests {
     #[test]
     fn test_eval_timestamp() {
         let expr = create_get_preheader_property_expr(spreheader::TIMESTAMP_PROPERTY.clone());
-        let ctx = Rc::new(force_any_val::<Context>());
-        let expected = ctx.pre_header.timestamp as i64;
+        let ctx = Rc::new(mainnet_like_context(SEED));
+        let expected = ctx.pre_header.timestamp.as_u64() as i64;
         let actual = eval_out::<i64>(&expr, ctx);
         assert_eq!(expected, actual);
     }
@@ -116,7 +122,7 @@ mod tests {
     #[test]
     fn test_eval_n_bits() {
         let expr = create_get_preheader_property_expr(spreheader::N_BITS_PROPERTY.clone());
-        let ctx = Rc::new(force_any_val::<Context>());
+        let ctx = Rc::new(mainnet_like_context(SEED));
         let expected = ctx.pre_header.n_bits as i64;
         let actual = eval_out::<i64>(&expr, ctx);
         assert_eq!(expected as i64, actual);
@@ -125,8 +131,8 @@ mod tests {
     #[test]
     fn test_eval_height() {
         let expr = create_get_preheader_property_expr(spreheader::HEIGHT_PROPERTY.clone());
-        let ctx = Rc::new(force_any_val::<Context>());
-        let expected = ctx.pre_header.height as i32;
+        let ctx = Rc::new(mainnet_like_context(SEED));
+        let expected = ctx.pre_header.height.as_u32() as i32;
         let actual = eval_out::<i32>(&expr, ctx);
         assert_eq!(expected, actual);
     }
@@ -134,7 +140,7 @@ mod tests {
     #[test]
     fn test_eval_miner_pk() {
         let expr = create_get_preheader_property_expr(spreheader::MINER_PK_PROPERTY.clone());
-        let ctx = Rc::new(force_any_val::<Context>());
+        let ctx = Rc::new(mainnet_like_context(SEED));
         let expected = ctx.pre_header.miner_pk.clone();
         let actual = {
             let pk = eval_out::<EcPoint>(&expr, ctx);
@@ -146,7 +152,7 @@ mod tests {
     #[test]
     fn test_eval_votes() {
         let expr = create_get_preheader_property_expr(spreheader::VOTES_PROPERTY.clone());
-        let ctx = Rc::new(force_any_val::<Context>());
+        let ctx = Rc::new(mainnet_like_context(SEED));
         let expected = ctx.pre_header.votes.clone();
         let actual = {
             let votes_bytes = eval_out::<Vec<i8>>(&expr, ctx).as_vec_u8();
@@ -156,6 +162,57 @@ mod tests {
         assert_eq!(expected, actual);
     }
 
+    #[test]
+    fn test_eval_consistent_with_chain() {
+        // the pre-header is of the block the transaction is going to be in (as in the node):
+        // CONTEXT.preHeader.parentId == CONTEXT.headers(0).id &&
+        // CONTEXT.preHeader.height == HEIGHT &&
+        // CONTEXT.preHeader.minerPk.getEncoded == minerPubKey
+        let eq = |left: Expr, right: Expr| -> Expr {
+            BinOp {
+                kind: RelationOp::Eq.into(),
+                left: Box::new(left),
+                right: Box::new(right),
+            }
+            .into()
+        };
+        let last_header: Expr = ByIndex::new(
+            PropertyCall::new(Expr::Context, scontext::HEADERS_PROPERTY.clone())
+                .expect("internal error: invalid headers property call of Context")
+                .into(),
+            Expr::Const(0i32.into()),
+            None,
+        )
+        .expect("internal error: invalid ByIndex")
+        .into();
+        let parent_id = eq(
+            create_get_preheader_property_expr(spreheader::PARENT_ID_PROPERTY.clone()),
+            PropertyCall::new(last_header, sheader::ID_PROPERTY.clone())
+                .expect("internal error: invalid id property call of Header")
+                .into(),
+        );
+        let height = eq(
+            create_get_preheader_property_expr(spreheader::HEIGHT_PROPERTY.clone()),
+            GlobalVars::Height.into(),
+        );
+        let miner_pk = eq(
+            MethodCall::new(
+                create_get_preheader_property_expr(spreheader::MINER_PK_PROPERTY.clone()),
+                sgroup_elem::GET_ENCODED_METHOD.clone(),
+                vec![],
+            )
+            .expect("internal error: invalid getEncoded method call")
+            .into(),
+            GlobalVars::MinerPubKey.into(),
+        );
+        for _ in 0..10 {
+            let ctx = Rc::new(mainnet_like_context(SEED));
+            assert!(eval_out::<bool>(&parent_id, ctx.clone()));
+            assert!(eval_out::<bool>(&height, ctx.clone()));
+            assert!(eval_out::<bool>(&miner_pk, ctx));
+        }
+    }
+
     #[test]
     fn test_eval_failed_invalid_obj() {
         // calling for PreHeader property on Context obj
@@ -3,44 +3,29 @@ use crate::eval::EvalContext;
 use crate::eval::EvalError;
 use crate::eval::Evaluable;
 use ergotree_ir::ergo_tree::ErgoTree;
+use ergotree_ir::ergo_tree::ErgoTreeError;
 use ergotree_ir::mir::constant::Constant;
+use ergotree_ir::mir::constant::Literal;
 use ergotree_ir::mir::constant::TryExtractInto;
 use ergotree_ir::mir::subst_const::SubstConstants;
 use ergotree_ir::mir::value::CollKind;
 use ergotree_ir::mir::value::NativeColl;
 use ergotree_ir::mir::value::Value;
 use ergotree_ir::serialization::SigmaSerializable;
-use ergotree_ir::util::AsVecI8;
-use ergotree_ir::util::AsVecU8;
+use ergotree_ir::types::stype::SType;
 use std::convert::TryFrom;
 
 impl Evaluable for SubstConstants {
     fn eval(&self, env: &Env, ctx: &mut EvalContext) -> Result<Value, EvalError> {
-        let script_bytes_v = self.script_bytes.eval(env, ctx)?;
-        let positions_v = self.positions.eval(env, ctx)?;
-        let new_values_v = self.new_values.eval(env, ctx)?;
-
-        let positions: Vec<usize> = positions_v
-            .try_extract_into::<Vec<i32>>()?
-            .into_iter()
-            .map(|i| i as usize)
-            .collect();
-
-        let new_constants = if let Value::Coll(CollKind::WrappedColl { items, .. }) = new_values_v {
-            let mut items_const = vec![];
-            for v in items {
-                let c = Constant::try_from(v).map_err(EvalError::Misc)?;
-                items_const.push(c);
-            }
-            items_const
-        } else {
-            return Err(EvalError::Misc(format!(
-                "SubstConstants: expected evaluation of `new_values` be of type `Coll[_]`, got \
-                    {:?} instead",
-                new_values_v
-            )));
-        };
-
+        let script_bytes = self
+            .script_bytes
+            .eval(env, ctx)?
+            .try_extract_into::<Vec<u8>>()?;
+        let positions = self
+            .positions
+            .eval(env, ctx)?
+            .try_extract_into::<Vec<i32>>()?;
+        let new_constants = coll_items(Constant::try_from(self.new_values.eval(env, ctx)?)?)?;
         if new_constants.len() != positions.len() {
             return Err(EvalError::Misc(format!(
                 "SubstConstants: `positions.len()` (== {}) and `new_values.len()` (== {}) differ",
@@ -48,39 +33,66 @@ impl Evaluable for SubstConstants {
                 new_constants.len()
             )));
         }
-
-        if let Value::Coll(CollKind::NativeColl(NativeColl::CollByte(b))) = script_bytes_v {
-            // Substitue constants with repeated calls to `ErgoTree::with_constant`.
-            let mut ergo_tree = ErgoTree::sigma_parse_bytes(&b.as_vec_u8())?;
-            let num_constants = ergo_tree.constants_len().map_err(to_misc_err)?;
-            for (ix, i) in positions.iter().enumerate() {
-                if *i < num_constants {
-                    ergo_tree = ergo_tree
-                        .with_constant(*i, new_constants[ix].clone())
-                        .map_err(to_misc_err)?;
-                } else {
-                    return Err(EvalError::Misc(format!(
-                        "SubstConstants: positions[{}] == {} is an out of bound index with \
-                       respect to the serialized ErgoTree's constant list",
-                        ix, *i
-                    )));
-                }
+        let mut ergo_tree = ErgoTree::sigma_parse_bytes(&script_bytes)?;
+        let constants_len = ergo_tree
+            .constants_len()
+            .map_err(ErgoTreeError::ConstantsParsingError)?;
+        // as in `positions.indexOf(i)` of the reference implementation, positions that do not
+        // point to a constant of the tree are ignored and the first of the duplicates wins
+        for idx in 0..constants_len {
+            let new_constant = match positions
+                .iter()
+                .position(|p| usize::try_from(*p).ok() == Some(idx))
+                .and_then(|pos_idx| new_constants.get(pos_idx))
+            {
+                Some(c) => c.clone(),
+                None => continue,
+            };
+            let old_tpe = ergo_tree
+                .get_constant(idx)
+                .map_err(ErgoTreeError::ConstantsParsingError)?
+                .map(|c| c.tpe)
+                .ok_or_else(|| {
+                    EvalError::Misc(format!("SubstConstants: no constant at index {}", idx))
+                })?;
+            if new_constant.tpe != old_tpe {
+                return Err(EvalError::SubstConstantsTypeMismatch {
+                    position: idx,
+                    expected: old_tpe,
+                    actual: new_constant.tpe,
+                });
             }
-            Ok(Value::Coll(CollKind::NativeColl(NativeColl::CollByte(
-                ergo_tree.sigma_serialize_bytes()?.as_vec_i8(),
-            ))))
-        } else {
-            Err(EvalError::Misc(format!(
-                "SubstConstants: expected evaluation of `script_bytes` to be of type `Coll[SBytes]`, \
-                 got {:?} instead",
-                script_bytes_v
-            )))
+            ergo_tree = ergo_tree
+                .with_constant(idx, new_constant)
+                .map_err(|e| EvalError::Misc(format!("SubstConstants: {:?}", e)))?;
         }
+        Ok(ergo_tree.sigma_serialize_bytes()?.into())
     }
 }
 
-fn to_misc_err<T: std::fmt::Debug>(e: T) -> EvalError {
-    EvalError::Misc(format!("{:?}", e))
+/// Items of the `new_values` collection as constants of the collection element type (the
+/// declared type is kept, e.g. for the empty collections and `None` values)
+fn coll_items(coll: Constant) -> Result<Vec<Constant>, EvalError> {
+    match coll {
+        Constant {
+            tpe: SType::SColl(elem_tpe),
+            v: Literal::Coll(CollKind::WrappedColl { items, .. }),
+        } => Ok(items
+            .into_iter()
+            .map(|v| Constant {
+                tpe: (*elem_tpe).clone(),
+                v,
+            })
+            .collect()),
+        Constant {
+            v: Literal::Coll(CollKind::NativeColl(NativeColl::CollByte(bytes))),
+            ..
+        } => Ok(bytes.into_iter().map(Constant::from).collect()),
+        c => Err(EvalError::UnexpectedValue(format!(
+            "SubstConstants: expected `new_values` to be a collection, got {:?}",
+            c
+        ))),
+    }
 }
 
 #[cfg(test)]
@@ -97,6 +109,7 @@ mod tests {
         },
         serialization::SigmaSerializable,
         types::stype::LiftIntoSType,
+        util::AsU8Slice,
     };
     use proptest::prelude::*;
 
@@ -138,7 +151,7 @@ mod tests {
 
         let x: Value = try_eval_out_wo_ctx(&subst_const).unwrap();
         if let Value::Coll(CollKind::NativeColl(NativeColl::CollByte(b))) = x {
-            let new_ergo_tree = ErgoTree::sigma_parse_bytes(&b.as_vec_u8()).unwrap();
+            let new_ergo_tree = ErgoTree::sigma_parse_bytes(b.as_u8_slice()).unwrap();
             assert_eq!(new_ergo_tree.constants_len().unwrap(), 1);
             assert_eq!(new_ergo_tree.get_constant(0).unwrap().unwrap(), new.into());
         } else {
@@ -149,15 +162,19 @@ mod tests {
     fn test_3_substitutions(original: (i32, i32, i32), new: (i32, i32, i32)) {
         let (o0, o1, o2) = original;
         let (n0, n1, n2) = new;
-        let expr = Expr::BinOp(BinOp {
+        let expr: Expr = BinOp {
             kind: BinOpKind::Arith(ArithOp::Plus),
             left: Box::new(Expr::Const(o0.into())),
-            right: Box::new(Expr::BinOp(BinOp {
-                kind: BinOpKind::Arith(ArithOp::Multiply),
-                left: Box::new(Expr::Const(o1.into())),
-                right: Box::new(Expr::Const(o2.into())),
-            })),
-        });
+            right: Box::new(
+                BinOp {
+                    kind: BinOpKind::Arith(ArithOp::Multiply),
+                    left: Box::new(Expr::Const(o1.into())),
+                    right: Box::new(Expr::Const(o2.into())),
+                }
+                .into(),
+            ),
+        }
+        .into();
         let ergo_tree = ErgoTree::new(ErgoTreeHeader::v0(true), &expr).unwrap();
         assert_eq!(ergo_tree.constants_len().unwrap(), 3);
         assert_eq!(ergo_tree.get_constant(0).unwrap().unwrap(), o0.into());
@@ -179,7 +196,7 @@ mod tests {
 
         let x: Value = try_eval_out_wo_ctx(&subst_const).unwrap();
         if let Value::Coll(CollKind::NativeColl(NativeColl::CollByte(b))) = x {
-            let new_ergo_tree = ErgoTree::sigma_parse_bytes(&b.as_vec_u8()).unwrap();
+            let new_ergo_tree = ErgoTree::sigma_parse_bytes(b.as_u8_slice()).unwrap();
             assert_eq!(new_ergo_tree.constants_len().unwrap(), 3);
             assert_eq!(new_ergo_tree.get_constant(0).unwrap().unwrap(), n2.into());
             assert_eq!(new_ergo_tree.get_constant(1).unwrap().unwrap(), n0.into());
@@ -188,4 +205,112 @@ mod tests {
             unreachable!();
         }
     }
+
+    fn subst_constants(tree: &ErgoTree, positions: Vec<i32>, new_values: Constant) -> Expr {
+        SubstConstants::new(
+            Constant::from(tree.sigma_serialize_bytes().unwrap()).into(),
+            Constant::from(positions).into(),
+            new_values.into(),
+        )
+        .unwrap()
+        .into()
+    }
+
+    fn two_constants_tree() -> ErgoTree {
+        let expr: Expr = BinOp {
+            kind: BinOpKind::Arith(ArithOp::Plus),
+            left: Box::new(Expr::Const(1i32.into())),
+            right: Box::new(Expr::Const(2i32.into())),
+        }
+        .into();
+        ErgoTree::new(ErgoTreeHeader::v0(true), &expr).unwrap()
+    }
+
+    #[test]
+    fn eval_byte_substitution() {
+        let expr = Expr::Const(Constant::from(5i8));
+        let ergo_tree = ErgoTree::new(ErgoTreeHeader::v0(true), &expr).unwrap();
+        let subst = subst_constants(&ergo_tree, vec![0], vec![7u8].into());
+        let res: Vec<u8> = try_eval_out_wo_ctx(&subst).unwrap();
+        let new_ergo_tree = ErgoTree::sigma_parse_bytes(&res).unwrap();
+        assert_eq!(
+            new_ergo_tree.get_constant(0).unwrap().unwrap(),
+            Constant::from(7i8)
+        );
+    }
+
+    #[test]
+    fn eval_empty_coll_substitution() {
+        let expr = Expr::Const(Constant::from(vec![vec![1i64]]));
+        let ergo_tree = ErgoTree::new(ErgoTreeHeader::v0(true), &expr).unwrap();
+        let empty: Vec<i64> = vec![];
+        let subst = subst_constants(&ergo_tree, vec![0], vec![empty.clone()].into());
+        let res: Vec<u8> = try_eval_out_wo_ctx(&subst).unwrap();
+        let new_ergo_tree = ErgoTree::sigma_parse_bytes(&res).unwrap();
+        assert_eq!(
+            new_ergo_tree.get_constant(0).unwrap().unwrap(),
+            Constant::from(empty)
+        );
+    }
+
+    #[test]
+    fn eval_ignores_unknown_positions() {
+        let ergo_tree = two_constants_tree();
+        let subst = subst_constants(&ergo_tree, vec![2, -1, 1], vec![3i32, 4, 5].into());
+        let res: Vec<u8> = try_eval_out_wo_ctx(&subst).unwrap();
+        let new_ergo_tree = ErgoTree::sigma_parse_bytes(&res).unwrap();
+        assert_eq!(
+            new_ergo_tree.get_constant(0).unwrap().unwrap(),
+            Constant::from(1i32)
+        );
+        assert_eq!(
+            new_ergo_tree.get_constant(1).unwrap().unwrap(),
+            Constant::from(5i32)
+        );
+    }
+
+    #[test]
+    fn eval_only_unknown_positions() {
+        let ergo_tree = two_constants_tree();
+        let subst = subst_constants(&ergo_tree, vec![7], vec![3i64].into());
+        let res: Vec<u8> = try_eval_out_wo_ctx(&subst).unwrap();
+        assert_eq!(res, ergo_tree.sigma_serialize_bytes().unwrap());
+    }
+
+    #[test]
+    fn eval_first_duplicate_wins() {
+        let ergo_tree = two_constants_tree();
+        let subst = subst_constants(&ergo_tree, vec![0, 0], vec![3i32, 4].into());
+        let res: Vec<u8> = try_eval_out_wo_ctx(&subst).unwrap();
+        let new_ergo_tree = ErgoTree::sigma_parse_bytes(&res).unwrap();
+        assert_eq!(
+            new_ergo_tree.get_constant(0).unwrap().unwrap(),
+            Constant::from(3i32)
+        );
+        assert_eq!(
+            new_ergo_tree.get_constant(1).unwrap().unwrap(),
+            Constant::from(2i32)
+        );
+    }
+
+    #[test]
+    fn eval_type_mismatch() {
+        let ergo_tree = two_constants_tree();
+        let subst = subst_constants(&ergo_tree, vec![1], vec![3i64].into());
+        assert_eq!(
+            try_eval_out_wo_ctx::<Value>(&subst),
+            Err(EvalError::SubstConstantsTypeMismatch {
+                position: 1,
+                expected: SType::SInt,
+                actual: SType::SLong,
+            })
+        );
+    }
+
+    #[test]
+    fn eval_length_mismatch() {
+        let ergo_tree = two_constants_tree();
+        let subst = subst_constants(&ergo_tree, vec![0, 1], vec![3i32].into());
+        assert!(try_eval_out_wo_ctx::<Value>(&subst).is_err());
+    }
 }
@@ -175,6 +175,26 @@ mod tests {
         );
     }
 
+    #[test]
+    fn sign_extension_at_bounds() {
+        let upcast_long = |c: Constant| {
+            eval_out_wo_ctx::<i64>(&Upcast::new(c.into(), SType::SLong).unwrap().into())
+        };
+        assert_eq!(upcast_long(i8::MIN.into()), i8::MIN as i64);
+        assert_eq!(upcast_long(i8::MAX.into()), i8::MAX as i64);
+        assert_eq!(upcast_long((-1i8).into()), -1);
+        assert_eq!(upcast_long(i16::MIN.into()), i16::MIN as i64);
+        assert_eq!(upcast_long(i16::MAX.into()), i16::MAX as i64);
+        assert_eq!(upcast_long(i32::MIN.into()), i32::MIN as i64);
+        assert_eq!(upcast_long(i32::MAX.into()), i32::MAX as i64);
+        let upcast_bigint = |c: Constant| {
+            eval_out_wo_ctx::<BigInt256>(&Upcast::new(c.into(), SType::SBigInt).unwrap().into())
+        };
+        assert_eq!(upcast_bigint(i64::MIN.into()), BigInt256::from(i64::MIN));
+        assert_eq!(upcast_bigint(i64::MAX.into()), BigInt256::from(i64::MAX));
+        assert_eq!(upcast_bigint((-1i8).into()), BigInt256::from(-1i64));
+    }
+
     #[test]
     fn from_bigint() {
         let v: BigInt256 = force_any_val::<i64>().into();
@@ -8,7 +8,7 @@ use crate::eval::EvalContext;
 use crate::eval::EvalError;
 use crate::eval::Evaluable;
 
-fn helper_xor(mut x: Vec<i8>, y: Vec<i8>) -> Vec<i8> {
+fn helper_xor(mut x: Vec<i8>, y: &[i8]) -> Vec<i8> {
     x.iter_mut().zip(y.iter()).for_each(|(x1, x2)| *x1 ^= *x2);
     x
 }
@@ -18,15 +18,15 @@ impl Evaluable for Xor {
         let left_v = self.left.eval(env, ctx)?;
         let right_v = self.right.eval(env, ctx)?;
 
-        match (left_v.clone(), right_v.clone()) {
+        match (left_v, right_v) {
             (
                 Value::Coll(CollKind::NativeColl(NativeColl::CollByte(l_byte))),
                 Value::Coll(CollKind::NativeColl(NativeColl::CollByte(r_byte))),
             ) => {
-                let xor = helper_xor(l_byte, r_byte);
+                let xor = helper_xor(l_byte, &r_byte);
                 Ok(xor.into())
             }
-            _ => Err(EvalError::UnexpectedValue(format!(
+            (left_v, right_v) => Err(EvalError::UnexpectedValue(format!(
                 "expected Xor input to be byte array, got: {0:?}",
                 (left_v, right_v)
             ))),
@@ -131,7 +131,7 @@ mod tests {
         #[test]
         fn eval_any(left_bytes in any::<Vec<i8>>(), right_bytes in any::<Vec<i8>>()) {
 
-            let expected_xor = helper_xor(left_bytes.clone(), right_bytes.clone());
+            let expected_xor = helper_xor(left_bytes.clone(), &right_bytes);
 
             let expr: Expr = Xor {
                 left: Box::new(Expr::Const(left_bytes.into())),
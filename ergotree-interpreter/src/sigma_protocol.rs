@@ -2,8 +2,10 @@
 
 #![deny(clippy::unwrap_used)]
 
+pub mod multi_sig;
 pub mod private_input;
 pub mod prover;
+pub mod secret_provider;
 pub mod verifier;
 
 mod challenge;
@@ -11,6 +13,7 @@ mod crypto_utils;
 mod dht_protocol;
 mod dlog_protocol;
 mod fiat_shamir;
+mod gf2_192;
 mod proof_tree;
 mod sig_serializer;
 mod unchecked_tree;
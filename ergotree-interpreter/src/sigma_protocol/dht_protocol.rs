@@ -19,6 +19,18 @@ impl FirstDhTupleProverMessage {
             b: b.into(),
         }
     }
+
+    /// Commitment `a = g^r`
+    #[cfg(feature = "json")]
+    pub(crate) fn a(&self) -> &EcPoint {
+        &self.a
+    }
+
+    /// Commitment `b = h^r`
+    #[cfg(feature = "json")]
+    pub(crate) fn b(&self) -> &EcPoint {
+        &self.b
+    }
 }
 
 impl ProverMessage for FirstDhTupleProverMessage {
@@ -96,9 +108,8 @@ pub(crate) mod interactive_prover {
     pub(crate) fn second_message(
         private_input: &DhTupleProverInput,
         rnd: &Scalar,
-        challenge: &Challenge,
+        e: Scalar,
     ) -> SecondDhTupleProverMessage {
-        let e: Scalar = challenge.clone().into();
         // modulo multiplication, no need to explicit mod op
         let ew = e.mul(&private_input.w);
         // modulo addition, no need to explicit mod op
@@ -88,9 +88,8 @@ pub(crate) mod interactive_prover {
     pub(crate) fn second_message(
         private_input: &DlogProverInput,
         rnd: Scalar,
-        challenge: &Challenge,
+        e: Scalar,
     ) -> SecondDlogProverMessage {
-        let e: Scalar = challenge.clone().into();
         // modulo multiplication, no need to explicit mod op
         let ew = e.mul(&private_input.w);
         // modulo addition, no need to explicit mod op
@@ -136,7 +135,7 @@ mod tests {
         fn test_compute_commitment(secret in any::<DlogProverInput>(), challenge in any::<Challenge>()) {
             let pk = secret.public_image();
             let (r, commitment) = interactive_prover::first_message();
-            let second_message = interactive_prover::second_message(&secret, r, &challenge);
+            let second_message = interactive_prover::second_message(&secret, r, challenge.clone().into());
             let a = interactive_prover::compute_commitment(&pk, &challenge, &second_message);
             prop_assert_eq!(a, *commitment.0);
         }
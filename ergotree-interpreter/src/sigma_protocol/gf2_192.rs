@@ -0,0 +1,184 @@
+//! Arithmetic in GF(2^192) and the polynomials over it, used for the challenges of the THRESHOLD
+//! conjectures (the same field as in sigmastate's `GF2_192` and `GF2_192_Poly`: modulo the
+//! irreducible pentanomial x^192 + x^7 + x^2 + x + 1)
+
+use super::challenge::Challenge;
+use super::fiat_shamir::FiatShamirHash;
+use super::SOUNDNESS_BYTES;
+
+/// Element of GF(2^192), the words are in little-endian order (the lowest degree bits first)
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub(crate) struct Gf2_192([u64; 3]);
+
+impl Gf2_192 {
+    /// Parse from 24 bytes (little-endian, as in sigmastate)
+    pub(crate) fn from_bytes(bytes: &[u8; SOUNDNESS_BYTES]) -> Self {
+        let mut words = [0u64; 3];
+        for (word, chunk) in words.iter_mut().zip(bytes.chunks(8)) {
+            let mut word_bytes = [0u8; 8];
+            word_bytes.copy_from_slice(chunk);
+            *word = u64::from_le_bytes(word_bytes);
+        }
+        Gf2_192(words)
+    }
+
+    /// 24 bytes (little-endian, as in sigmastate)
+    pub(crate) fn to_bytes(self) -> [u8; SOUNDNESS_BYTES] {
+        let mut bytes = [0u8; SOUNDNESS_BYTES];
+        for (chunk, word) in bytes.chunks_mut(8).zip(self.0.iter()) {
+            chunk.copy_from_slice(&word.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// Sum (XOR)
+    pub(crate) fn add(self, other: Gf2_192) -> Gf2_192 {
+        Gf2_192([
+            self.0[0] ^ other.0[0],
+            self.0[1] ^ other.0[1],
+            self.0[2] ^ other.0[2],
+        ])
+    }
+
+    /// Product modulo x^192 + x^7 + x^2 + x + 1
+    pub(crate) fn mul(self, other: Gf2_192) -> Gf2_192 {
+        // carry-less product (up to 383 bits)
+        let mut prod = [0u64; 6];
+        for bit in 0..192 {
+            if (other.0[bit / 64] >> (bit % 64)) & 1 == 1 {
+                let (word_shift, bit_shift) = (bit / 64, bit % 64);
+                for (i, word) in self.0.iter().enumerate() {
+                    prod[i + word_shift] ^= word << bit_shift;
+                    if bit_shift > 0 {
+                        prod[i + word_shift + 1] ^= word >> (64 - bit_shift);
+                    }
+                }
+            }
+        }
+        // x^192 = x^7 + x^2 + x + 1, the highest words are reduced first
+        for i in (3..6).rev() {
+            let word = prod[i];
+            prod[i] = 0;
+            prod[i - 3] ^= word ^ (word << 1) ^ (word << 2) ^ (word << 7);
+            prod[i - 2] ^= (word >> 63) ^ (word >> 62) ^ (word >> 57);
+        }
+        Gf2_192([prod[0], prod[1], prod[2]])
+    }
+}
+
+impl From<u8> for Gf2_192 {
+    fn from(v: u8) -> Self {
+        Gf2_192([v as u64, 0, 0])
+    }
+}
+
+impl From<Challenge> for Gf2_192 {
+    fn from(c: Challenge) -> Self {
+        let bytes: Vec<u8> = c.into();
+        let mut arr = [0u8; SOUNDNESS_BYTES];
+        arr.copy_from_slice(&bytes);
+        Gf2_192::from_bytes(&arr)
+    }
+}
+
+impl From<Gf2_192> for Challenge {
+    fn from(e: Gf2_192) -> Self {
+        FiatShamirHash(Box::new(e.to_bytes())).into()
+    }
+}
+
+/// Polynomial over GF(2^192), the coefficients are in the order of the degree
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub(crate) struct Gf2_192Poly {
+    coefficients: Vec<Gf2_192>,
+}
+
+impl Gf2_192Poly {
+    /// Polynomial with the value at zero (constant term) and the other coefficients (24 bytes
+    /// each, in the order of the degree, as in sigmastate's `GF2_192_Poly.fromByteArray`)
+    pub(crate) fn from_bytes(
+        value_at_zero: Gf2_192,
+        coefficients: &[[u8; SOUNDNESS_BYTES]],
+    ) -> Self {
+        let mut res = vec![value_at_zero];
+        res.extend(coefficients.iter().map(Gf2_192::from_bytes));
+        Gf2_192Poly { coefficients: res }
+    }
+
+    /// Coefficients except the constant term (24 bytes each, in the order of the degree)
+    pub(crate) fn to_bytes_without_constant_term(&self) -> Vec<u8> {
+        self.coefficients
+            .iter()
+            .skip(1)
+            .flat_map(|c| c.to_bytes().to_vec())
+            .collect()
+    }
+
+    /// Value at the point `x`
+    pub(crate) fn evaluate(&self, x: u8) -> Gf2_192 {
+        let x = Gf2_192::from(x);
+        self.coefficients
+            .iter()
+            .rev()
+            .fold(Gf2_192::default(), |acc, c| acc.mul(x).add(*c))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn x_pow(degree: usize) -> Gf2_192 {
+        let mut words = [0u64; 3];
+        words[degree / 64] = 1 << (degree % 64);
+        Gf2_192(words)
+    }
+
+    #[test]
+    fn bytes_roundtrip() {
+        let mut bytes = [0u8; SOUNDNESS_BYTES];
+        bytes.iter_mut().enumerate().for_each(|(i, b)| *b = i as u8);
+        let e = Gf2_192::from_bytes(&bytes);
+        assert_eq!(e.0[0], 0x0706050403020100);
+        assert_eq!(e.to_bytes(), bytes);
+    }
+
+    #[test]
+    fn mul_reduction() {
+        // x^191 * x = x^192 = x^7 + x^2 + x + 1
+        assert_eq!(x_pow(191).mul(x_pow(1)), Gf2_192([0x87, 0, 0]));
+        // x^191 * x^191 = x^190 * (x^7 + x^2 + x + 1) = x^197 + x^192 + x^191 + x^190
+        //               = x^12 + x^6 + x^5 + x^2 + x + 1 + x^191 + x^190
+        assert_eq!(
+            x_pow(191).mul(x_pow(191)),
+            Gf2_192([0x1067, 0, (1 << 63) | (1 << 62)])
+        );
+        let x190 = x_pow(190);
+        assert_eq!(x190.mul(Gf2_192::from(1)), x190);
+        assert_eq!(x190.mul(Gf2_192::default()), Gf2_192::default());
+    }
+
+    #[test]
+    fn mul_commutative_distributive() {
+        let a = Gf2_192([0x0123456789abcdef, 0xfedcba9876543210, 0xdeadbeefcafebabe]);
+        let b = Gf2_192([0x1111111111111111, 0x8000000000000001, 0xffffffffffffffff]);
+        let c = Gf2_192([0x42, 0, 0x8000000000000000]);
+        assert_eq!(a.mul(b), b.mul(a));
+        assert_eq!(a.mul(b.add(c)), a.mul(b).add(a.mul(c)));
+        assert_eq!(a.mul(b).mul(c), a.mul(b.mul(c)));
+    }
+
+    #[test]
+    fn poly_evaluate() {
+        let c0 = Gf2_192([5, 6, 7]);
+        let c1 = Gf2_192([1, 2, 3]);
+        let c2 = Gf2_192([0, 0, 9]);
+        let poly = Gf2_192Poly::from_bytes(c0, &[c1.to_bytes(), c2.to_bytes()]);
+        assert_eq!(poly.evaluate(0), c0);
+        let x = Gf2_192::from(3);
+        assert_eq!(poly.evaluate(3), c0.add(c1.mul(x)).add(c2.mul(x).mul(x)));
+        let mut bytes = c1.to_bytes().to_vec();
+        bytes.extend_from_slice(&c2.to_bytes());
+        assert_eq!(poly.to_bytes_without_constant_term(), bytes);
+    }
+}
@@ -0,0 +1,283 @@
+//! Distributed signing (multi-signature) support: commitment generation and hints extraction
+//!
+//! Signing a statement like "pk1 && pk2" with the secrets held by different parties goes as follows:
+//! 1. the signer with pk2 generates a commitment ([`generate_commitments_for`]) and sends its
+//! public part (see [`HintsBag::sanitize_for_sharing`]) to the signer with pk1;
+//! 2. the signer with pk1 proves with these hints, getting a partial proof (with a random response
+//! for pk2);
+//! 3. the signer with pk2 extracts the pk1 proof from the partial proof ([`bag_for_multi_sig`]) and
+//! proves with the extracted hints and its own commitment, getting a complete proof.
+
+use ergotree_ir::sigma_protocol::sigma_boolean::SigmaBoolean;
+use ergotree_ir::sigma_protocol::sigma_boolean::SigmaConjecture;
+use ergotree_ir::sigma_protocol::sigma_boolean::SigmaProofOfKnowledgeTree;
+
+use super::dht_protocol;
+use super::dlog_protocol;
+use super::proof_tree::ProofTreeLeaf;
+use super::prover::hint::CommitmentHint;
+use super::prover::hint::Hint;
+use super::prover::hint::HintsBag;
+use super::prover::hint::OwnCommitment;
+use super::prover::hint::RealCommitment;
+use super::prover::hint::RealSecretProof;
+use super::prover::hint::SecretProven;
+use super::prover::hint::SimulatedCommitment;
+use super::prover::hint::SimulatedSecretProof;
+use super::prover::ProofBytes;
+use super::sig_serializer::parse_sig_compute_challenges;
+use super::unchecked_tree::UncheckedTree;
+use super::unproven_tree::NodePosition;
+use super::verifier::compute_commitments;
+use super::verifier::VerifierError;
+use super::FirstProverMessage;
+
+/// Generates commitments (along with the secret randomness) for the leaves of `sigma_tree` with
+/// the public images in `generate_for`. Every commitment is added to the bag as an OwnCommitment
+/// (to be used in proving) and a RealCommitment (to be shared with other signers).
+pub fn generate_commitments_for(
+    sigma_tree: &SigmaBoolean,
+    generate_for: &[SigmaBoolean],
+) -> HintsBag {
+    let mut bag = HintsBag::empty();
+    traverse_sigma_tree(
+        sigma_tree,
+        NodePosition::crypto_tree_prefix(),
+        &mut |leaf, position| {
+            if !generate_for.contains(leaf) {
+                return;
+            }
+            let (secret_randomness, commitment): (_, FirstProverMessage) = match leaf {
+                SigmaBoolean::ProofOfKnowledge(SigmaProofOfKnowledgeTree::ProveDhTuple(pdht)) => {
+                    let (r, a) = dht_protocol::interactive_prover::first_message(pdht);
+                    (r, a.into())
+                }
+                _ => {
+                    let (r, a) = dlog_protocol::interactive_prover::first_message();
+                    (r, a.into())
+                }
+            };
+            bag.add_hint(Hint::CommitmentHint(CommitmentHint::OwnCommitment(
+                OwnCommitment {
+                    image: leaf.clone(),
+                    secret_randomness,
+                    commitment: commitment.clone(),
+                    position: position.clone(),
+                },
+            )));
+            bag.add_hint(Hint::CommitmentHint(CommitmentHint::RealCommitment(
+                RealCommitment {
+                    image: leaf.clone(),
+                    commitment,
+                    position,
+                },
+            )));
+        },
+    );
+    bag
+}
+
+/// Extracts hints from a (possibly partial) proof of `sigma_tree`: commitments and proofs of the
+/// leaves with the public images in `real_propositions` (proven with a secret) and
+/// `simulated_propositions` (simulated by the signer). The hints can be used by another signer to
+/// complete the proof.
+pub fn bag_for_multi_sig(
+    sigma_tree: &SigmaBoolean,
+    proof: &ProofBytes,
+    real_propositions: &[SigmaBoolean],
+    simulated_propositions: &[SigmaBoolean],
+) -> Result<HintsBag, VerifierError> {
+    let mut bag = HintsBag::empty();
+    let proof_bytes = match proof {
+        ProofBytes::Empty => return Ok(bag),
+        ProofBytes::Some(bytes) => bytes.clone(),
+    };
+    // commitments are not serialized in the proof, compute them as the verifier does
+    let unchecked_tree =
+        compute_commitments(parse_sig_compute_challenges(sigma_tree, proof_bytes)?);
+    let mut leaves = Vec::new();
+    collect_unchecked_leaves(
+        unchecked_tree,
+        NodePosition::crypto_tree_prefix(),
+        &mut leaves,
+    );
+    for (leaf, position) in leaves {
+        let (image, challenge, commitment) = match &leaf {
+            UncheckedTree::UncheckedLeaf(ul) => (
+                ul.proposition(),
+                ul.challenge(),
+                ul.commitment_opt().ok_or_else(|| {
+                    VerifierError::Unexpected(format!("no commitment in {:?}", ul))
+                })?,
+            ),
+            UncheckedTree::UncheckedConjecture(_) => continue,
+        };
+        if real_propositions.contains(&image) {
+            bag.add_hint(Hint::CommitmentHint(CommitmentHint::RealCommitment(
+                RealCommitment {
+                    image: image.clone(),
+                    commitment,
+                    position: position.clone(),
+                },
+            )));
+            bag.add_hint(Hint::SecretProven(SecretProven::RealSecretProof(
+                RealSecretProof {
+                    image,
+                    challenge,
+                    unchecked_tree: leaf,
+                    position,
+                },
+            )));
+        } else if simulated_propositions.contains(&image) {
+            bag.add_hint(Hint::CommitmentHint(CommitmentHint::SimulatedCommitment(
+                SimulatedCommitment {
+                    image: image.clone(),
+                    commitment,
+                    position: position.clone(),
+                },
+            )));
+            bag.add_hint(Hint::SecretProven(SecretProven::SimulatedSecretProof(
+                SimulatedSecretProof {
+                    image,
+                    challenge,
+                    unchecked_tree: leaf,
+                    position,
+                },
+            )));
+        }
+    }
+    Ok(bag)
+}
+
+/// Calls `f` for every leaf (proof of knowledge) of the tree with its position
+fn traverse_sigma_tree<F: FnMut(&SigmaBoolean, NodePosition)>(
+    sb: &SigmaBoolean,
+    position: NodePosition,
+    f: &mut F,
+) {
+    match sb {
+        SigmaBoolean::ProofOfKnowledge(_) => f(sb, position),
+        SigmaBoolean::SigmaConjecture(conj) => {
+            let items = match conj {
+                SigmaConjecture::Cand(cand) => &cand.items,
+                SigmaConjecture::Cor(cor) => &cor.items,
+                SigmaConjecture::Cthreshold(ct) => &ct.items,
+            };
+            items
+                .iter()
+                .enumerate()
+                .for_each(|(idx, item)| traverse_sigma_tree(item, position.child(idx), f));
+        }
+        SigmaBoolean::TrivialProp(_) => (),
+    }
+}
+
+fn collect_unchecked_leaves(
+    tree: UncheckedTree,
+    position: NodePosition,
+    leaves: &mut Vec<(UncheckedTree, NodePosition)>,
+) {
+    match tree {
+        UncheckedTree::UncheckedLeaf(_) => leaves.push((tree, position)),
+        UncheckedTree::UncheckedConjecture(conj) => conj
+            .children_ust()
+            .into_iter()
+            .enumerate()
+            .for_each(|(idx, child)| collect_unchecked_leaves(child, position.child(idx), leaves)),
+    }
+}
+
+#[allow(clippy::unwrap_used)]
+#[cfg(test)]
+#[cfg(feature = "arbitrary")]
+mod tests {
+    use std::convert::TryFrom;
+    use std::convert::TryInto;
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::eval::context::Context;
+    use crate::eval::env::Env;
+    use crate::sigma_protocol::private_input::DlogProverInput;
+    use crate::sigma_protocol::private_input::PrivateInput;
+    use crate::sigma_protocol::prover::Prover;
+    use crate::sigma_protocol::prover::TestProver;
+    use crate::sigma_protocol::verifier::TestVerifier;
+    use crate::sigma_protocol::verifier::Verifier;
+    use ergotree_ir::ergo_tree::ErgoTree;
+    use ergotree_ir::mir::expr::Expr;
+    use ergotree_ir::mir::sigma_and::SigmaAnd;
+    use ergotree_ir::sigma_protocol::sigma_boolean::cand::Cand;
+    use sigma_test_util::force_any_val;
+
+    fn verify(tree: &ErgoTree, proof: ProofBytes, message: &[u8]) -> bool {
+        TestVerifier
+            .verify(
+                tree,
+                &Env::empty(),
+                Rc::new(force_any_val::<Context>()),
+                proof,
+                message,
+            )
+            .unwrap()
+            .result
+    }
+
+    #[test]
+    fn two_party_and() {
+        let secret1 = DlogProverInput::random();
+        let secret2 = DlogProverInput::random();
+        let pk1: SigmaBoolean = secret1.public_image().into();
+        let pk2: SigmaBoolean = secret2.public_image().into();
+        let expr: Expr = SigmaAnd::new(vec![
+            Expr::Const(pk1.clone().into()),
+            Expr::Const(pk2.clone().into()),
+        ])
+        .unwrap()
+        .into();
+        let tree = ErgoTree::try_from(expr).unwrap();
+        let sigma_tree = Cand::normalized(vec![pk1.clone(), pk2.clone()].try_into().unwrap());
+        let message = force_any_val::<Vec<u8>>();
+        let prover1 = TestProver {
+            secrets: vec![PrivateInput::DlogProverInput(secret1)],
+        };
+        let prover2 = TestProver {
+            secrets: vec![PrivateInput::DlogProverInput(secret2)],
+        };
+
+        // signer 2 generates a commitment and shares its public part
+        let own_bag2 = generate_commitments_for(&sigma_tree, &[pk2.clone()]);
+        assert_eq!(own_bag2.own_commitments().len(), 1);
+        let shared_bag2 = own_bag2.sanitize_for_sharing();
+        assert!(!shared_bag2.contains_secrets());
+
+        // signer 1 makes a partial proof
+        let partial_proof = prover1
+            .prove(
+                &tree,
+                &Env::empty(),
+                Rc::new(force_any_val::<Context>()),
+                message.as_slice(),
+                &shared_bag2,
+            )
+            .unwrap()
+            .proof;
+        assert!(!verify(&tree, partial_proof.clone(), &message));
+
+        // signer 2 extracts signer 1 proof and completes the proof
+        let mut bag = bag_for_multi_sig(&sigma_tree, &partial_proof, &[pk1], &[]).unwrap();
+        assert_eq!(bag.real_proofs().len(), 1);
+        bag.add_hints_from(own_bag2);
+        let proof = prover2
+            .prove(
+                &tree,
+                &Env::empty(),
+                Rc::new(force_any_val::<Context>()),
+                message.as_slice(),
+                &bag,
+            )
+            .unwrap()
+            .proof;
+        assert!(verify(&tree, proof, &message));
+    }
+}
@@ -2,7 +2,9 @@
 use std::convert::TryInto;
 
 use elliptic_curve::group::ff::PrimeField;
+use ergotree_ir::serialization::SigmaSerializable;
 use ergotree_ir::sigma_protocol::dlog_group;
+use ergotree_ir::sigma_protocol::dlog_group::EcPoint;
 use ergotree_ir::sigma_protocol::sigma_boolean::ProveDhTuple;
 use ergotree_ir::sigma_protocol::sigma_boolean::ProveDlog;
 
@@ -42,7 +44,7 @@ impl DlogProverInput {
     /// Attempts to parse the given Base16-encoded byte array as an SEC-1-encoded scalar(secret key).
     /// Returns None if the byte array does not contain a big-endian integer in the range [0, modulus).
     pub fn from_base16_str(str: String) -> Option<DlogProverInput> {
-        base16::decode(&str)
+        sigma_util::hex::decode(&str)
             .ok()
             .map(|bytes| bytes.as_slice().try_into().ok().map(Self::from_bytes))
             .flatten()
@@ -93,6 +95,9 @@ pub struct DhTupleProverInput {
 }
 
 impl DhTupleProverInput {
+    /// Size in bytes of the secret (scalar) and the tuple (four compressed points)
+    pub const SIZE_BYTES: usize = DlogProverInput::SIZE_BYTES + 4 * EcPoint::GROUP_SIZE;
+
     /// Create random secret and Diffie-Hellman tuple
     #[allow(clippy::many_single_char_names)]
     pub fn random() -> DhTupleProverInput {
@@ -108,10 +113,67 @@ impl DhTupleProverInput {
         DhTupleProverInput { w, common_input }
     }
 
+    /// Create the Diffie-Hellman tuple `(g, h, g^w, h^w)` of the secret for the given `h` (e.g.
+    /// a generator derived with [`dlog_group::derive_generator`])
+    pub fn with_h(w: Scalar, h: EcPoint) -> DhTupleProverInput {
+        let g = dlog_group::generator();
+        let u = dlog_group::exponentiate(&g, &w);
+        let v = dlog_group::exponentiate(&h, &w);
+        DhTupleProverInput {
+            w,
+            common_input: ProveDhTuple::new(g, h, u, v),
+        }
+    }
+
     /// Public image (Diffie-Hellman tuple)
     pub fn public_image(&self) -> &ProveDhTuple {
         &self.common_input
     }
+
+    /// Attempts to parse the given byte array as an SEC-1-encoded scalar(secret) followed by the
+    /// compressed points g, h, u, v of the tuple.
+    /// Returns None if the scalar is not in the range [0, modulus), a point is not valid or the
+    /// secret is not the one of the tuple (`u = g^w`, `v = h^w`).
+    #[allow(clippy::many_single_char_names)]
+    pub fn from_bytes(bytes: &[u8; DhTupleProverInput::SIZE_BYTES]) -> Option<DhTupleProverInput> {
+        let (w_bytes, points_bytes) = bytes.split_at(DlogProverInput::SIZE_BYTES);
+        let w_bytes: [u8; DlogProverInput::SIZE_BYTES] = w_bytes.try_into().ok()?;
+        let w = DlogProverInput::from_bytes(&w_bytes)?.w;
+        let points = points_bytes
+            .chunks(EcPoint::GROUP_SIZE)
+            .map(|p| EcPoint::sigma_parse_bytes(p).ok())
+            .collect::<Option<Vec<EcPoint>>>()?;
+        match points.as_slice() {
+            [g, h, u, v]
+                if dlog_group::exponentiate(g, &w) == *u
+                    && dlog_group::exponentiate(h, &w) == *v =>
+            {
+                Some(DhTupleProverInput {
+                    w,
+                    common_input: ProveDhTuple::new(g.clone(), h.clone(), u.clone(), v.clone()),
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Byte representation of the secret (scalar) followed by the compressed points g, h, u, v of
+    /// the tuple
+    pub fn to_bytes(&self) -> [u8; DhTupleProverInput::SIZE_BYTES] {
+        let mut bytes = [0u8; DhTupleProverInput::SIZE_BYTES];
+        let ci = &self.common_input;
+        let w_bytes: [u8; DlogProverInput::SIZE_BYTES] = self.w.to_bytes().into();
+        let parts = [
+            &w_bytes[..],
+            &ci.g.to_compressed_bytes()[..],
+            &ci.h.to_compressed_bytes()[..],
+            &ci.u.to_compressed_bytes()[..],
+            &ci.v.to_compressed_bytes()[..],
+        ]
+        .concat();
+        bytes.copy_from_slice(&parts);
+        bytes
+    }
 }
 
 /// Private inputs (secrets)
@@ -185,4 +247,39 @@ pub(crate) mod arbitrary {
 
 #[cfg(test)]
 #[cfg(feature = "arbitrary")]
-mod tests {}
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    proptest! {
+
+        #[test]
+        fn dht_bytes_roundtrip(dht in any::<DhTupleProverInput>()) {
+            prop_assert_eq![DhTupleProverInput::from_bytes(&dht.to_bytes()), Some(dht)];
+        }
+    }
+
+    #[test]
+    fn dht_from_bytes_other_secret() {
+        let dht = DhTupleProverInput::random();
+        let mut bytes = dht.to_bytes();
+        bytes[..DlogProverInput::SIZE_BYTES].copy_from_slice(&DlogProverInput::random().to_bytes());
+        assert_eq!(DhTupleProverInput::from_bytes(&bytes), None);
+    }
+
+    #[test]
+    fn dht_with_derived_h() {
+        let h = dlog_group::derive_generator(b"protocol", 0);
+        let w = DlogProverInput::random().w;
+        let dht = DhTupleProverInput::with_h(w, h.clone());
+        let ci = dht.public_image();
+        assert_eq!(*ci.g, dlog_group::generator());
+        assert_eq!(*ci.h, h);
+        assert_eq!(*ci.v, dlog_group::exponentiate(&h, &w));
+        assert_eq!(
+            DhTupleProverInput::from_bytes(&dht.to_bytes()),
+            Some(dht.clone())
+        );
+    }
+}
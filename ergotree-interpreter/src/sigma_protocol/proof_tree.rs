@@ -122,12 +122,16 @@ pub(crate) trait ProofTreeLeaf: Debug {
 pub(crate) enum ConjectureType {
     And = 0,
     Or = 1,
-    // Threshold = 2,
+    Threshold = 2,
 }
 
 pub(crate) trait ProofTreeConjecture {
     fn conjecture_type(&self) -> ConjectureType;
     fn children(&self) -> SigmaConjectureItems<ProofTree>;
+    /// Number of the children to be proven for the THRESHOLD conjecture
+    fn threshold_k(&self) -> Option<u8> {
+        None
+    }
 }
 
 pub(crate) enum ProofTreeKind<'a> {
@@ -204,6 +208,12 @@ where
                     }
                     .into()
                 }
+                UncheckedConjecture::CthresholdUnchecked { children, .. } => {
+                    let rewritten_children =
+                        children.clone().try_mapped(|c| rewrite(c.into(), f))?;
+                    let casted_children = cast_to_ust(rewritten_children)?;
+                    conj.clone().with_children(casted_children).into()
+                }
             },
         },
     })
@@ -5,8 +5,12 @@ mod prover_result;
 
 pub mod hint;
 
-use crate::eval::reduce_to_crypto;
+use crate::eval::reduce_tree_to_crypto;
+use crate::eval::settings::EvalSettings;
+use crate::sigma_protocol::crypto_utils;
 use crate::sigma_protocol::dht_protocol;
+use crate::sigma_protocol::dht_protocol::SecondDhTupleProverMessage;
+use crate::sigma_protocol::dlog_protocol::SecondDlogProverMessage;
 use crate::sigma_protocol::fiat_shamir::fiat_shamir_hash_fn;
 use crate::sigma_protocol::fiat_shamir::fiat_shamir_tree_to_bytes;
 use crate::sigma_protocol::proof_tree::ProofTree;
@@ -17,8 +21,10 @@ use crate::sigma_protocol::unproven_tree::NodePosition;
 use crate::sigma_protocol::unproven_tree::UnprovenDhTuple;
 use crate::sigma_protocol::Challenge;
 use crate::sigma_protocol::UnprovenLeaf;
+use ergotree_ir::sigma_protocol::dlog_group;
 use ergotree_ir::sigma_protocol::sigma_boolean::SigmaBoolean;
 use ergotree_ir::sigma_protocol::sigma_boolean::SigmaConjectureItems;
+use k256::Scalar;
 use std::convert::TryInto;
 use std::rc::Rc;
 
@@ -36,6 +42,9 @@ use super::fiat_shamir::FiatShamirTreeSerializationError;
 use super::private_input::PrivateInput;
 use super::proof_tree;
 use super::proof_tree::ProofTreeLeaf;
+use super::secret_provider::InMemorySecrets;
+use super::secret_provider::SecretProvider;
+use super::secret_provider::SecretProviderError;
 use super::sig_serializer::serialize_sig;
 use super::unchecked_tree::UncheckedConjecture;
 use super::unchecked_tree::UncheckedSchnorr;
@@ -85,6 +94,15 @@ pub enum ProverError {
     /// Not yet implemented
     #[error("not yet implemented: {0}")]
     NotYetImplemented(String),
+    /// Secret storage failed to compute the response
+    #[error("Secret provider error: {0}")]
+    SecretProviderError(SecretProviderError),
+}
+
+impl From<SecretProviderError> for ProverError {
+    fn from(e: SecretProviderError) -> Self {
+        ProverError::SecretProviderError(e)
+    }
 }
 
 impl From<ErgoTreeError> for ProverError {
@@ -101,8 +119,9 @@ impl From<FiatShamirTreeSerializationError> for ProverError {
 
 /// Prover
 pub trait Prover {
-    /// Secrets of the prover
-    fn secrets(&self) -> &[PrivateInput];
+    /// Secrets of the prover, requested once per proof (the commitments made by the storage are
+    /// answered within the same proof)
+    fn secrets(&self) -> Box<dyn SecretProvider + '_>;
 
     /// The comments in this section are taken from the algorithm for the
     /// Sigma-protocol prover as described in the ErgoScript white-paper
@@ -117,9 +136,13 @@ pub trait Prover {
         message: &[u8],
         hints_bag: &HintsBag,
     ) -> Result<ProverResult, ProverError> {
-        let expr = tree.proposition()?;
+        // a tree of not yet activated version cannot be proven
+        let settings = EvalSettings {
+            strict: true,
+            ..EvalSettings::default()
+        };
         let reduction_result =
-            reduce_to_crypto(expr.as_ref(), env, ctx).map_err(ProverError::EvalError)?;
+            reduce_tree_to_crypto(tree, env, ctx, &settings).map_err(ProverError::EvalError)?;
         self.generate_proof(reduction_result.sigma_prop, message, hints_bag)
     }
 
@@ -165,13 +188,17 @@ fn prove_to_unchecked<P: Prover + ?Sized>(
     message: &[u8],
     hints_bag: &HintsBag,
 ) -> Result<UncheckedTree, ProverError> {
+    let secrets = prover.secrets();
     // Prover Step 1: Mark as real everything the prover can prove
-    let step1 = mark_real(prover, unproven_tree, hints_bag)?;
-    // dbg!(&step1);
+    let step1 = mark_real(secrets.as_ref(), unproven_tree, hints_bag)?;
+    #[cfg(feature = "log")]
+    log::trace!("prover step 1 (mark real): {:?}", step1);
 
     // Prover Step 2: If the root of the tree is marked "simulated" then the prover does not have enough witnesses
     // to perform the proof. Abort.
     if !step1.is_real() {
+        #[cfg(feature = "log")]
+        log::debug!("prover: tree root is simulated (not enough secrets to prove)");
         return Err(ProverError::TreeRootIsNotReal);
     }
 
@@ -179,11 +206,12 @@ fn prove_to_unchecked<P: Prover + ?Sized>(
     // has the right number of simulated children.
 
     let step3 = polish_simulated(prover, step1)?;
-    // dbg!(&step3);
+    #[cfg(feature = "log")]
+    log::trace!("prover step 3 (polish simulated): {:?}", step3);
 
     // Prover Steps 4, 5, and 6 together: find challenges for simulated nodes; simulate simulated leaves;
     // compute commitments for real leaves
-    let step6 = simulate_and_commit(step3, hints_bag)?;
+    let step6 = simulate_and_commit(secrets.as_ref(), step3, hints_bag)?;
     // dbg!(&step6);
 
     // Prover Steps 7: convert the relevant information in the tree (namely, tree structure, node types,
@@ -200,7 +228,7 @@ fn prove_to_unchecked<P: Prover + ?Sized>(
     // dbg!(&step8);
 
     // Prover Step 9: complete the proof by computing challenges at real nodes and additionally responses at real leaves
-    let step9 = proving(prover, step8.into(), hints_bag)?;
+    let step9 = proving(secrets.as_ref(), step8.into(), hints_bag)?;
     // dbg!(&step9);
     // Prover Step 10: output the right information into the proof
     convert_to_unchecked(step9)
@@ -213,8 +241,8 @@ fn prove_to_unchecked<P: Prover + ?Sized>(
  This will be corrected in the next step.
  In a bottom-up traversal of the tree, do the following for each node:
 */
-fn mark_real<P: Prover + ?Sized>(
-    prover: &P,
+fn mark_real(
+    secrets: &dyn SecretProvider,
     unproven_tree: UnprovenTree,
     hints_bag: &HintsBag,
 ) -> Result<UnprovenTree, ProverError> {
@@ -226,10 +254,7 @@ fn mark_real<P: Prover + ?Sized>(
                     // available or a hint shows the secret is known to an external participant in multi-signing;
                     // else mark it "simulated"
                     let secret_known = hints_bag.real_images().contains(&unp_leaf.proposition())
-                        || prover
-                            .secrets()
-                            .iter()
-                            .any(|s| s.public_image() == unp_leaf.proposition());
+                        || secrets.contains(&unp_leaf.proposition());
                     Some(unp_leaf.clone().with_simulated(!secret_known).into())
                 }
                 UnprovenTree::UnprovenConjecture(unp_conj) => match unp_conj {
@@ -383,6 +408,7 @@ fn polish_simulated<P: Prover + ?Sized>(
  compute the commitment a.
 */
 fn simulate_and_commit(
+    secrets: &dyn SecretProvider,
     unproven_tree: UnprovenTree,
     hints_bag: &HintsBag,
 ) -> Result<UnprovenTree, ProverError> {
@@ -545,8 +571,20 @@ fn simulate_and_commit(
                             } else {
                                 Err(ProverError::SimulatedLeafWithoutChallenge)
                             }
+                        } else if secrets.contains(&us.proposition.clone().into()) {
+                            // Step 6 (real leaf -- the secret storage computes the commitment a
+                            // and keeps the randomness)
+                            let commitment = secrets.dlog_first_message(&us.proposition)?;
+                            Ok(ProofTree::UnprovenTree(
+                                UnprovenSchnorr {
+                                    commitment_opt: Some(commitment),
+                                    ..us.clone()
+                                }
+                                .into(),
+                            ))
                         } else {
-                            // Step 6 (real leaf -- compute the commitment a)
+                            // Step 6 (real leaf of another signer -- compute the commitment a for
+                            // the partial proof)
                             let (r, commitment) =
                                 dlog_protocol::interactive_prover::first_message();
                             Ok(ProofTree::UnprovenTree(
@@ -604,8 +642,20 @@ fn simulate_and_commit(
                             } else {
                                 Err(ProverError::SimulatedLeafWithoutChallenge)
                             }
+                        } else if secrets.contains(&dhu.proposition.clone().into()) {
+                            // Step 6 -- the secret storage computes the commitment
+                            secrets
+                                .dh_tuple_first_message(&dhu.proposition)
+                                .map(|fm| {
+                                    UnprovenDhTuple {
+                                        commitment_opt: Some(fm),
+                                        ..dhu.clone()
+                                    }
+                                    .into()
+                                })
+                                .map_err(ProverError::from)
                         } else {
-                            // Step 6 -- compute the commitment
+                            // Step 6 -- compute the commitment (real leaf of another signer)
                             let (r, fm) =
                                 dht_protocol::interactive_prover::first_message(&dhu.proposition);
                             Ok(UnprovenDhTuple {
@@ -630,8 +680,8 @@ fn simulate_and_commit(
  the challenge e for every node marked "real" below the root and, additionally, the response z for every leaf
  marked "real"
 */
-fn proving<P: Prover + ?Sized>(
-    prover: &P,
+fn proving(
+    secrets: &dyn SecretProvider,
     proof_tree: ProofTree,
     hints_bag: &HintsBag,
 ) -> Result<ProofTree, ProverError> {
@@ -705,36 +755,62 @@ fn proving<P: Prover + ?Sized>(
                 UnprovenTree::UnprovenLeaf(unp_leaf) if unp_leaf.is_real() => match unp_leaf {
                     UnprovenLeaf::UnprovenSchnorr(us) => {
                         if let Some(challenge) = us.challenge_opt.clone() {
-                            if let Some(priv_key) = prover
-                                .secrets()
-                                .iter()
-                                .flat_map(|s| match s {
-                                    PrivateInput::DlogProverInput(dl) => vec![dl],
-                                    _ => vec![],
-                                })
-                                .find(|prover_input| prover_input.public_image() == us.proposition)
-                            {
-                                let z = dlog_protocol::interactive_prover::second_message(
-                                    priv_key,
-                                    us.randomness_opt.ok_or_else(|| {
-                                        ProverError::Unexpected(format!(
-                                            "empty randomness in {:?}",
-                                            us
-                                        ))
-                                    })?,
-                                    &challenge,
-                                );
+                            if secrets.contains(&us.proposition.clone().into()) {
+                                // our own commitment in the hints bag (if the commitment was
+                                // generated beforehand) or the one made by the secret storage
+                                let z = match hints_bag
+                                    .own_commitments()
+                                    .into_iter()
+                                    .find(|c| c.position == us.position)
+                                {
+                                    Some(own_commitment) => secrets
+                                        .dlog_second_message_with_randomness(
+                                            &us.proposition,
+                                            own_commitment.secret_randomness,
+                                            challenge.clone().into(),
+                                        )?,
+                                    None => secrets.dlog_second_message(
+                                        &us.proposition,
+                                        us.commitment_opt.as_ref().ok_or_else(|| {
+                                            ProverError::Unexpected(format!(
+                                                "empty commitment in {:?}",
+                                                us
+                                            ))
+                                        })?,
+                                        challenge.clone().into(),
+                                    )?,
+                                };
                                 Ok(Some(
                                     UncheckedSchnorr {
                                         proposition: us.proposition.clone(),
                                         commitment_opt: None,
                                         challenge,
-                                        second_message: z,
+                                        second_message: z.into(),
                                     }
                                     .into(),
                                 ))
                             } else {
-                                Err(ProverError::SecretNotFound)
+                                // the secret is known to another signer, take the proof from the
+                                // hints bag, or, if it's not there yet, make a partial proof with a
+                                // random response (to extract hints from it later)
+                                Ok(Some(
+                                    hints_bag
+                                        .real_proofs()
+                                        .into_iter()
+                                        .find(|p| p.position == us.position)
+                                        .map(|p| p.unchecked_tree.into())
+                                        .unwrap_or_else(|| {
+                                            UncheckedSchnorr {
+                                                proposition: us.proposition.clone(),
+                                                commitment_opt: None,
+                                                challenge,
+                                                second_message: SecondDlogProverMessage {
+                                                    z: random_response(),
+                                                },
+                                            }
+                                            .into()
+                                        }),
+                                ))
                             }
                         } else {
                             Err(ProverError::RealUnprovenTreeWithoutChallenge)
@@ -745,44 +821,42 @@ fn proving<P: Prover + ?Sized>(
                         // of the Sigma-protocol given the commitment, challenge, and witness, or pull response from
                         // the hints bag
                         if let Some(dhu_challenge) = dhu.challenge_opt.clone() {
-                            let priv_key_opt = prover
-                                .secrets()
-                                .iter()
-                                .find(|s| s.public_image() == dhu.proposition.clone().into());
-                            let z = match priv_key_opt {
-                                Some(PrivateInput::DhTupleProverInput(priv_key)) => match hints_bag
+                            let z = if secrets.contains(&dhu.proposition.clone().into()) {
+                                let z = match hints_bag
                                     .own_commitments()
                                     .iter()
                                     .find(|c| c.position == dhu.position)
                                 {
-                                    Some(commitment_from_hints_bag) => {
-                                        dht_protocol::interactive_prover::second_message(
-                                            priv_key,
-                                            &commitment_from_hints_bag.secret_randomness,
-                                            &dhu_challenge,
-                                        )
-                                    }
-                                    None => dht_protocol::interactive_prover::second_message(
-                                        priv_key,
-                                        &dhu.randomness_opt.ok_or_else(|| {
+                                    Some(commitment_from_hints_bag) => secrets
+                                        .dh_tuple_second_message_with_randomness(
+                                            &dhu.proposition,
+                                            commitment_from_hints_bag.secret_randomness,
+                                            dhu_challenge.clone().into(),
+                                        )?,
+                                    None => secrets.dh_tuple_second_message(
+                                        &dhu.proposition,
+                                        dhu.commitment_opt.as_ref().ok_or_else(|| {
                                             ProverError::Unexpected(format!(
-                                                "empty randomness in {:?}",
+                                                "empty commitment in {:?}",
                                                 dhu
                                             ))
                                         })?,
-                                        &dhu_challenge,
-                                    ),
-                                },
-                                Some(pi) => {
-                                    return Err(ProverError::Unexpected(format!(
-                                        "Expected DH prover input in prover secrets, got {:?}",
-                                        pi
-                                    )))
+                                        dhu_challenge.clone().into(),
+                                    )?,
+                                };
+                                SecondDhTupleProverMessage { z }
+                            } else {
+                                // the secret is known to another signer, take the proof from
+                                // the hints bag or make a partial proof (see Schnorr above)
+                                if let Some(proof) = hints_bag
+                                    .real_proofs()
+                                    .into_iter()
+                                    .find(|p| p.position == dhu.position)
+                                {
+                                    return Ok(Some(proof.unchecked_tree.into()));
                                 }
-                                None => {
-                                    return Err(ProverError::NotYetImplemented(
-                                        "when secret not found".to_string(),
-                                    ))
+                                SecondDhTupleProverMessage {
+                                    z: random_response(),
                                 }
                             };
                             Ok(Some(
@@ -814,6 +888,12 @@ fn proving<P: Prover + ?Sized>(
     })
 }
 
+/// Random response for a real leaf whose secret is known to another signer (partial proof in
+/// distributed signing)
+fn random_response() -> Scalar {
+    dlog_group::random_scalar_in_group_range(crypto_utils::secure_rng())
+}
+
 fn convert_to_unproven(sb: SigmaBoolean) -> Result<UnprovenTree, ProverError> {
     Ok(match sb {
         SigmaBoolean::ProofOfKnowledge(pok) => match pok {
@@ -912,8 +992,27 @@ pub struct TestProver {
 }
 
 impl Prover for TestProver {
-    fn secrets(&self) -> &[PrivateInput] {
-        self.secrets.as_ref()
+    fn secrets(&self) -> Box<dyn SecretProvider + '_> {
+        Box::new(InMemorySecrets::new(&self.secrets))
+    }
+}
+
+/// Prover with the secrets in the given storage (e.g. an HSM or a keystore, see
+/// [`SecretProvider`])
+pub struct SecretProviderProver {
+    secret_provider: Box<dyn SecretProvider>,
+}
+
+impl SecretProviderProver {
+    /// Create a prover with the secrets in the given storage
+    pub fn new(secret_provider: Box<dyn SecretProvider>) -> Self {
+        SecretProviderProver { secret_provider }
+    }
+}
+
+impl Prover for SecretProviderProver {
+    fn secrets(&self) -> Box<dyn SecretProvider + '_> {
+        Box::new(self.secret_provider.as_ref())
     }
 }
 
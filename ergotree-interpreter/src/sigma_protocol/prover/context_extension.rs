@@ -62,13 +62,13 @@ pub struct ConstantParsingError(pub String);
 impl TryFrom<HashMap<String, String>> for ContextExtension {
     type Error = ConstantParsingError;
     fn try_from(values_str: HashMap<String, String>) -> Result<Self, Self::Error> {
-        let values = values_str.iter().try_fold(
+        let mut values = values_str.iter().try_fold(
             IndexMap::with_capacity(values_str.len()),
             |mut acc, pair| {
                 let idx: u8 = pair.0.parse().map_err(|_| {
                     ConstantParsingError(format!("cannot parse index from {0:?}", pair.0))
                 })?;
-                let constant_bytes = base16::decode(pair.1).map_err(|_| {
+                let constant_bytes = sigma_util::hex::decode(pair.1).map_err(|_| {
                     ConstantParsingError(format!(
                         "cannot decode base16 constant bytes from {0:?}",
                         pair.1
@@ -86,6 +86,8 @@ impl TryFrom<HashMap<String, String>> for ContextExtension {
                 Ok(acc)
             },
         )?;
+        // HashMap iteration order is random, keep the values ordered by id
+        values.sort_keys();
         Ok(ContextExtension { values })
     }
 }
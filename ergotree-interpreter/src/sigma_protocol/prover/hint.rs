@@ -8,6 +8,12 @@ use crate::sigma_protocol::unchecked_tree::UncheckedTree;
 use crate::sigma_protocol::unproven_tree::NodePosition;
 use crate::sigma_protocol::FirstProverMessage;
 
+#[cfg(feature = "json")]
+mod json;
+
+#[cfg(feature = "json")]
+pub use json::HintsJsonError;
+
 /// A hint for a prover which helps the prover to prove a statement. For example, if the statement is "pk1 && pk2",
 /// and the prover knows only a secret for the public key pk1, the prover fails on proving without a hint. But if the
 /// prover knows that pk2 is known to another party, the prover may prove the statement (with an empty proof for "pk2").
@@ -147,6 +153,7 @@ impl CommitmentHint {
 }
 
 /// Collection of hints to be used by a prover
+#[derive(PartialEq, Debug, Clone)]
 pub struct HintsBag {
     /// Hints stored in a bag
     hints: Vec<Hint>,
@@ -158,6 +165,45 @@ impl HintsBag {
         HintsBag { hints: vec![] }
     }
 
+    /// Add a hint to the bag
+    pub fn add_hint(&mut self, hint: Hint) {
+        self.hints.push(hint);
+    }
+
+    /// Add all hints from the other bag
+    pub fn add_hints_from(&mut self, other: HintsBag) {
+        self.hints.extend(other.hints);
+    }
+
+    /// Copy of the bag which is safe to share with other signers (e.g. to send from a multisig
+    /// coordinator). Every OwnCommitment is replaced with a RealCommitment (same image, commitment
+    /// and position), so the secret randomness (the only secret a hint holds) is not shared.
+    pub fn sanitize_for_sharing(&self) -> HintsBag {
+        let mut shared = HintsBag::empty();
+        for hint in &self.hints {
+            let shared_hint = match hint {
+                Hint::CommitmentHint(CommitmentHint::OwnCommitment(c)) => {
+                    Hint::CommitmentHint(CommitmentHint::RealCommitment(RealCommitment {
+                        image: c.image.clone(),
+                        commitment: c.commitment.clone(),
+                        position: c.position.clone(),
+                    }))
+                }
+                hint => hint.clone(),
+            };
+            if !shared.hints.contains(&shared_hint) {
+                shared.add_hint(shared_hint);
+            }
+        }
+        shared
+    }
+
+    /// Returns true if the bag holds any secret randomness (OwnCommitment hints), i.e. should not
+    /// be shared (see [`HintsBag::sanitize_for_sharing`])
+    pub fn contains_secrets(&self) -> bool {
+        !self.own_commitments().is_empty()
+    }
+
     /// Commitments from all CommitmentHints in the bag
     pub fn commitments(&self) -> Vec<CommitmentHint> {
         self.hints
@@ -261,3 +307,72 @@ impl HintsBag {
             .collect()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sigma_protocol::dlog_protocol::interactive_prover;
+    use crate::sigma_protocol::private_input::DlogProverInput;
+
+    fn own_commitment(position: NodePosition) -> OwnCommitment {
+        let (r, a) = interactive_prover::first_message();
+        OwnCommitment {
+            image: DlogProverInput::random().public_image().into(),
+            secret_randomness: r,
+            commitment: FirstProverMessage::FirstDlogProverMessage(a),
+            position,
+        }
+    }
+
+    fn public_part(c: &OwnCommitment) -> RealCommitment {
+        RealCommitment {
+            image: c.image.clone(),
+            commitment: c.commitment.clone(),
+            position: c.position.clone(),
+        }
+    }
+
+    #[test]
+    fn sanitize_for_sharing() {
+        let own1 = own_commitment(NodePosition::crypto_tree_prefix().child(0));
+        let own2 = own_commitment(NodePosition::crypto_tree_prefix().child(1));
+        let mut bag = HintsBag::empty();
+        bag.add_hint(Hint::CommitmentHint(CommitmentHint::OwnCommitment(
+            own1.clone(),
+        )));
+        bag.add_hint(Hint::CommitmentHint(CommitmentHint::OwnCommitment(
+            own2.clone(),
+        )));
+        // already shared before
+        bag.add_hint(Hint::CommitmentHint(CommitmentHint::RealCommitment(
+            public_part(&own2),
+        )));
+        assert!(bag.contains_secrets());
+
+        let shared = bag.sanitize_for_sharing();
+        assert!(!shared.contains_secrets());
+        assert_eq!(
+            shared.real_commitments(),
+            vec![public_part(&own1), public_part(&own2)]
+        );
+        assert_eq!(shared.sanitize_for_sharing(), shared);
+    }
+
+    #[test]
+    fn shared_bag_has_no_secret_randomness() {
+        let mut bag = HintsBag::empty();
+        for i in 0..5 {
+            bag.add_hint(Hint::CommitmentHint(CommitmentHint::OwnCommitment(
+                own_commitment(NodePosition::crypto_tree_prefix().child(i)),
+            )));
+        }
+        let shared_str = format!("{:?}", bag.sanitize_for_sharing());
+        let bag_str = format!("{:?}", bag);
+        for c in bag.own_commitments() {
+            let secret_str = format!("{:?}", c.secret_randomness);
+            // make sure the secret would be found if leaked
+            assert!(bag_str.contains(&secret_str));
+            assert!(!shared_str.contains(&secret_str));
+        }
+    }
+}
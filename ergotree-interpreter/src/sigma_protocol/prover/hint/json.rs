@@ -0,0 +1,466 @@
+//! JSON encoding of the hints (the format of the Ergo node `/wallet/generateCommitments` and
+//! `/wallet/extractHints` API)
+
+use std::convert::TryFrom;
+
+use elliptic_curve::group::ff::PrimeField;
+use ergotree_ir::sigma_protocol::dlog_group::EcPoint;
+use ergotree_ir::sigma_protocol::sigma_boolean::ProveDhTuple;
+use ergotree_ir::sigma_protocol::sigma_boolean::ProveDlog;
+use ergotree_ir::sigma_protocol::sigma_boolean::SigmaBoolean;
+use ergotree_ir::sigma_protocol::sigma_boolean::SigmaProofOfKnowledgeTree;
+use k256::Scalar;
+use num_bigint::BigUint;
+use serde_json::json;
+use serde_json::Value;
+use thiserror::Error;
+
+use super::CommitmentHint;
+use super::Hint;
+use super::HintsBag;
+use super::OwnCommitment;
+use super::RealCommitment;
+use super::RealSecretProof;
+use super::SecretProven;
+use super::SimulatedCommitment;
+use super::SimulatedSecretProof;
+use crate::sigma_protocol::challenge::Challenge;
+use crate::sigma_protocol::dht_protocol::FirstDhTupleProverMessage;
+use crate::sigma_protocol::dlog_protocol::FirstDlogProverMessage;
+use crate::sigma_protocol::fiat_shamir::FiatShamirHash;
+use crate::sigma_protocol::sig_serializer::parse_sig_compute_challenges;
+use crate::sigma_protocol::sig_serializer::serialize_sig;
+use crate::sigma_protocol::unchecked_tree::UncheckedTree;
+use crate::sigma_protocol::unproven_tree::NodePosition;
+use crate::sigma_protocol::verifier::compute_commitments;
+use crate::sigma_protocol::FirstProverMessage;
+
+/// `op` of the ProveDlog public image (`OpCode.ProveDlogCode` in sigmastate)
+const PROVE_DLOG_OP: i64 = -51;
+/// `op` of the ProveDhTuple public image (`OpCode.ProveDiffieHellmanTupleCode` in sigmastate)
+const PROVE_DHT_OP: i64 = -50;
+
+/// Errors on the hints JSON encoding and decoding
+#[derive(Error, PartialEq, Eq, Debug, Clone)]
+pub enum HintsJsonError {
+    /// Missing or invalid field
+    #[error("Invalid field {0}: {1}")]
+    InvalidField(&'static str, String),
+    /// Unknown `hint` type
+    #[error("Unknown hint type: {0}")]
+    UnknownHint(String),
+    /// Public image is not ProveDlog or ProveDhTuple
+    #[error("Unsupported public image: {0}")]
+    UnsupportedImage(String),
+}
+
+impl HintsBag {
+    /// Encode as the JSON array of the hints in the Ergo node format. The secret randomness of
+    /// OwnCommitment hints is encoded as a decimal string in the `secret` field, use
+    /// [`HintsBag::sanitize_for_sharing`] before sending the bag to other signers.
+    pub fn to_json(&self) -> Result<Value, HintsJsonError> {
+        self.hints
+            .iter()
+            .map(hint_to_json)
+            .collect::<Result<Vec<Value>, _>>()
+            .map(Value::Array)
+    }
+
+    /// Decode from the JSON array of the hints in the Ergo node format
+    pub fn from_json(json: &Value) -> Result<HintsBag, HintsJsonError> {
+        let hints = json
+            .as_array()
+            .ok_or_else(|| HintsJsonError::InvalidField("hints", "expected array".to_string()))?
+            .iter()
+            .map(hint_from_json)
+            .collect::<Result<Vec<Hint>, _>>()?;
+        Ok(HintsBag { hints })
+    }
+}
+
+fn hint_to_json(hint: &Hint) -> Result<Value, HintsJsonError> {
+    Ok(match hint {
+        Hint::CommitmentHint(CommitmentHint::OwnCommitment(c)) => {
+            let mut json =
+                commitment_to_json("cmtWithSecret", &c.image, &c.commitment, &c.position)?;
+            json["secret"] = BigUint::from_bytes_be(c.secret_randomness.to_bytes().as_slice())
+                .to_string()
+                .into();
+            json
+        }
+        Hint::CommitmentHint(CommitmentHint::RealCommitment(c)) => {
+            commitment_to_json("cmtReal", &c.image, &c.commitment, &c.position)?
+        }
+        Hint::CommitmentHint(CommitmentHint::SimulatedCommitment(c)) => {
+            commitment_to_json("cmtSimulated", &c.image, &c.commitment, &c.position)?
+        }
+        Hint::SecretProven(SecretProven::RealSecretProof(p)) => json!({
+            "hint": "proofReal",
+            "challenge": challenge_to_json(&p.challenge),
+            "pubkey": image_to_json(&p.image)?,
+            "proof": proof_to_json(p.unchecked_tree.clone()),
+            "position": position_to_json(&p.position),
+        }),
+        Hint::SecretProven(SecretProven::SimulatedSecretProof(p)) => json!({
+            "hint": "proofSimulated",
+            "challenge": challenge_to_json(&p.challenge),
+            "pubkey": image_to_json(&p.image)?,
+            "proof": proof_to_json(p.unchecked_tree.clone()),
+            "position": position_to_json(&p.position),
+        }),
+    })
+}
+
+fn hint_from_json(json: &Value) -> Result<Hint, HintsJsonError> {
+    let hint_type = str_field(json, "hint")?;
+    let image = image_from_json(field(json, "pubkey")?)?;
+    let position = position_from_json(str_field(json, "position")?)?;
+    Ok(match hint_type {
+        "cmtWithSecret" => {
+            let secret_randomness = scalar_from_json(field(json, "secret")?)?;
+            let commitment = commitment_from_json(json)?;
+            Hint::CommitmentHint(CommitmentHint::OwnCommitment(OwnCommitment {
+                image,
+                secret_randomness,
+                commitment,
+                position,
+            }))
+        }
+        "cmtReal" => Hint::CommitmentHint(CommitmentHint::RealCommitment(RealCommitment {
+            image,
+            commitment: commitment_from_json(json)?,
+            position,
+        })),
+        "cmtSimulated" => {
+            Hint::CommitmentHint(CommitmentHint::SimulatedCommitment(SimulatedCommitment {
+                image,
+                commitment: commitment_from_json(json)?,
+                position,
+            }))
+        }
+        "proofReal" | "proofSimulated" => {
+            let challenge = challenge_from_json(str_field(json, "challenge")?)?;
+            let proof = hex_field(json, "proof")?;
+            // commitments are not serialized in the proof, compute them as the verifier does
+            let unchecked_tree = compute_commitments(
+                parse_sig_compute_challenges(&image, proof)
+                    .map_err(|e| HintsJsonError::InvalidField("proof", e.to_string()))?,
+            );
+            if unchecked_tree.challenge() != challenge {
+                return Err(HintsJsonError::InvalidField(
+                    "challenge",
+                    "does not match the challenge of the proof".to_string(),
+                ));
+            }
+            if hint_type == "proofReal" {
+                Hint::SecretProven(SecretProven::RealSecretProof(RealSecretProof {
+                    image,
+                    challenge,
+                    unchecked_tree,
+                    position,
+                }))
+            } else {
+                Hint::SecretProven(SecretProven::SimulatedSecretProof(SimulatedSecretProof {
+                    image,
+                    challenge,
+                    unchecked_tree,
+                    position,
+                }))
+            }
+        }
+        _ => return Err(HintsJsonError::UnknownHint(hint_type.to_string())),
+    })
+}
+
+fn commitment_to_json(
+    hint_type: &str,
+    image: &SigmaBoolean,
+    commitment: &FirstProverMessage,
+    position: &NodePosition,
+) -> Result<Value, HintsJsonError> {
+    let mut json = json!({
+        "hint": hint_type,
+        "pubkey": image_to_json(image)?,
+        "position": position_to_json(position),
+    });
+    match commitment {
+        FirstProverMessage::FirstDlogProverMessage(m) => {
+            json["type"] = "dlog".into();
+            json["a"] = point_to_json(&m.0);
+        }
+        FirstProverMessage::FirstDhtProverMessage(m) => {
+            json["type"] = "dht".into();
+            json["a"] = point_to_json(m.a());
+            json["b"] = point_to_json(m.b());
+        }
+    }
+    Ok(json)
+}
+
+fn commitment_from_json(json: &Value) -> Result<FirstProverMessage, HintsJsonError> {
+    match str_field(json, "type")? {
+        "dlog" => Ok(FirstProverMessage::FirstDlogProverMessage(
+            FirstDlogProverMessage::from(point_field(json, "a")?),
+        )),
+        "dht" => Ok(FirstProverMessage::FirstDhtProverMessage(
+            FirstDhTupleProverMessage::new(point_field(json, "a")?, point_field(json, "b")?),
+        )),
+        t => Err(HintsJsonError::InvalidField("type", t.to_string())),
+    }
+}
+
+fn image_to_json(image: &SigmaBoolean) -> Result<Value, HintsJsonError> {
+    match image {
+        SigmaBoolean::ProofOfKnowledge(SigmaProofOfKnowledgeTree::ProveDlog(pd)) => Ok(json!({
+            "op": PROVE_DLOG_OP,
+            "h": point_to_json(&pd.h),
+        })),
+        SigmaBoolean::ProofOfKnowledge(SigmaProofOfKnowledgeTree::ProveDhTuple(dht)) => Ok(json!({
+            "op": PROVE_DHT_OP,
+            "g": point_to_json(&dht.g),
+            "h": point_to_json(&dht.h),
+            "u": point_to_json(&dht.u),
+            "v": point_to_json(&dht.v),
+        })),
+        _ => Err(HintsJsonError::UnsupportedImage(format!("{:?}", image))),
+    }
+}
+
+fn image_from_json(json: &Value) -> Result<SigmaBoolean, HintsJsonError> {
+    match field(json, "op")?.as_i64() {
+        Some(PROVE_DLOG_OP) => Ok(ProveDlog::new(point_field(json, "h")?).into()),
+        Some(PROVE_DHT_OP) => Ok(ProveDhTuple::new(
+            point_field(json, "g")?,
+            point_field(json, "h")?,
+            point_field(json, "u")?,
+            point_field(json, "v")?,
+        )
+        .into()),
+        _ => Err(HintsJsonError::UnsupportedImage(json.to_string())),
+    }
+}
+
+/// Indices from the root joined with `-`, e.g. `0-1`
+fn position_to_json(position: &NodePosition) -> Value {
+    position
+        .positions()
+        .iter()
+        .map(|i| i.to_string())
+        .collect::<Vec<String>>()
+        .join("-")
+        .into()
+}
+
+fn position_from_json(s: &str) -> Result<NodePosition, HintsJsonError> {
+    s.split('-')
+        .map(|i| i.parse::<usize>())
+        .collect::<Result<Vec<usize>, _>>()
+        .map(NodePosition::from_positions)
+        .map_err(|e| HintsJsonError::InvalidField("position", e.to_string()))
+}
+
+fn challenge_to_json(challenge: &Challenge) -> Value {
+    let bytes: Vec<u8> = challenge.clone().into();
+    sigma_util::hex::encode_lower(&bytes).into()
+}
+
+fn challenge_from_json(s: &str) -> Result<Challenge, HintsJsonError> {
+    let bytes = sigma_util::hex::decode(s)
+        .map_err(|e| HintsJsonError::InvalidField("challenge", e.to_string()))?;
+    FiatShamirHash::try_from(bytes.as_slice())
+        .map(Challenge::from)
+        .map_err(|e| HintsJsonError::InvalidField("challenge", e.to_string()))
+}
+
+fn proof_to_json(unchecked_tree: UncheckedTree) -> Value {
+    let bytes: Vec<u8> = serialize_sig(unchecked_tree).into();
+    sigma_util::hex::encode_lower(&bytes).into()
+}
+
+fn point_to_json(point: &EcPoint) -> Value {
+    sigma_util::hex::encode_lower(&point.to_compressed_bytes()).into()
+}
+
+/// The node encodes the randomness as a JSON number, a decimal string is accepted as well
+fn scalar_from_json(json: &Value) -> Result<Scalar, HintsJsonError> {
+    let invalid = |msg: &str| HintsJsonError::InvalidField("secret", msg.to_string());
+    let decimal = match json {
+        Value::String(s) => s.clone(),
+        Value::Number(n) => n.to_string(),
+        _ => return Err(invalid("expected a decimal number")),
+    };
+    let bytes = decimal
+        .parse::<BigUint>()
+        .map_err(|e| invalid(&e.to_string()))?
+        .to_bytes_be();
+    if bytes.len() > 32 {
+        return Err(invalid("out of the group order range"));
+    }
+    let mut repr = [0u8; 32];
+    repr[32 - bytes.len()..].copy_from_slice(&bytes);
+    Scalar::from_repr(repr.into()).ok_or_else(|| invalid("out of the group order range"))
+}
+
+fn field<'a>(json: &'a Value, name: &'static str) -> Result<&'a Value, HintsJsonError> {
+    json.get(name)
+        .ok_or_else(|| HintsJsonError::InvalidField(name, "missing".to_string()))
+}
+
+fn str_field<'a>(json: &'a Value, name: &'static str) -> Result<&'a str, HintsJsonError> {
+    field(json, name)?
+        .as_str()
+        .ok_or_else(|| HintsJsonError::InvalidField(name, "expected string".to_string()))
+}
+
+fn hex_field(json: &Value, name: &'static str) -> Result<Vec<u8>, HintsJsonError> {
+    sigma_util::hex::decode(str_field(json, name)?)
+        .map_err(|e| HintsJsonError::InvalidField(name, e.to_string()))
+}
+
+fn point_field(json: &Value, name: &'static str) -> Result<EcPoint, HintsJsonError> {
+    EcPoint::from_base16_str(str_field(json, name)?.to_string())
+        .ok_or_else(|| HintsJsonError::InvalidField(name, "invalid group element".to_string()))
+}
+
+#[cfg(test)]
+#[cfg(feature = "arbitrary")]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::convert::TryInto;
+    use std::rc::Rc;
+
+    use ergotree_ir::ergo_tree::ErgoTree;
+    use ergotree_ir::mir::expr::Expr;
+    use ergotree_ir::mir::sigma_and::SigmaAnd;
+    use ergotree_ir::sigma_protocol::sigma_boolean::cand::Cand;
+    use sigma_test_util::force_any_val;
+
+    use super::*;
+    use crate::eval::context::Context;
+    use crate::eval::env::Env;
+    use crate::sigma_protocol::multi_sig::bag_for_multi_sig;
+    use crate::sigma_protocol::multi_sig::generate_commitments_for;
+    use crate::sigma_protocol::private_input::DlogProverInput;
+    use crate::sigma_protocol::private_input::PrivateInput;
+    use crate::sigma_protocol::prover::Prover;
+    use crate::sigma_protocol::prover::TestProver;
+    use crate::sigma_protocol::verifier::TestVerifier;
+    use crate::sigma_protocol::verifier::Verifier;
+
+    fn roundtrip(bag: &HintsBag) -> HintsBag {
+        // through the string, as the bag is sent to the other signers
+        let json_str = bag.to_json().unwrap().to_string();
+        let parsed = HintsBag::from_json(&serde_json::from_str(&json_str).unwrap()).unwrap();
+        assert_eq!(&parsed, bag);
+        parsed
+    }
+
+    #[test]
+    fn two_party_and_through_json() {
+        let secret1 = DlogProverInput::random();
+        let secret2 = DlogProverInput::random();
+        let pk1: SigmaBoolean = secret1.public_image().into();
+        let pk2: SigmaBoolean = secret2.public_image().into();
+        let expr: Expr = SigmaAnd::new(vec![
+            Expr::Const(pk1.clone().into()),
+            Expr::Const(pk2.clone().into()),
+        ])
+        .unwrap()
+        .into();
+        let tree = ErgoTree::try_from(expr).unwrap();
+        let sigma_tree = Cand::normalized(vec![pk1.clone(), pk2.clone()].try_into().unwrap());
+        let message = force_any_val::<Vec<u8>>();
+        let prover1 = TestProver {
+            secrets: vec![PrivateInput::DlogProverInput(secret1)],
+        };
+        let prover2 = TestProver {
+            secrets: vec![PrivateInput::DlogProverInput(secret2)],
+        };
+
+        let own_bag2 = roundtrip(&generate_commitments_for(&sigma_tree, &[pk2.clone()]));
+        let own_json = own_bag2.to_json().unwrap();
+        assert_eq!(own_json[0]["hint"], "cmtWithSecret");
+        assert_eq!(own_json[0]["type"], "dlog");
+        assert_eq!(own_json[0]["position"], "0-1");
+        assert_eq!(own_json[0]["pubkey"]["op"], -51);
+        let shared_bag2 = roundtrip(&own_bag2.sanitize_for_sharing());
+        assert_eq!(shared_bag2.to_json().unwrap()[0]["hint"], "cmtReal");
+        assert!(shared_bag2.to_json().unwrap()[0].get("secret").is_none());
+
+        let partial_proof = prover1
+            .prove(
+                &tree,
+                &Env::empty(),
+                Rc::new(force_any_val::<Context>()),
+                message.as_slice(),
+                &shared_bag2,
+            )
+            .unwrap()
+            .proof;
+        let mut bag =
+            roundtrip(&bag_for_multi_sig(&sigma_tree, &partial_proof, &[pk1], &[]).unwrap());
+        let proof_json = &bag.to_json().unwrap()[1];
+        assert_eq!(proof_json["hint"], "proofReal");
+        assert_eq!(proof_json["position"], "0-0");
+        bag.add_hints_from(own_bag2);
+        let proof = prover2
+            .prove(
+                &tree,
+                &Env::empty(),
+                Rc::new(force_any_val::<Context>()),
+                message.as_slice(),
+                &bag,
+            )
+            .unwrap()
+            .proof;
+        assert!(
+            TestVerifier
+                .verify(
+                    &tree,
+                    &Env::empty(),
+                    Rc::new(force_any_val::<Context>()),
+                    proof,
+                    &message,
+                )
+                .unwrap()
+                .result
+        );
+    }
+
+    #[test]
+    fn secret_as_number_or_string() {
+        let json = json!({
+            "hint": "cmtWithSecret",
+            "pubkey": {"op": -51, "h": point_to_json(&DlogProverInput::random().public_image().h)},
+            "position": "0",
+            "type": "dlog",
+            "a": point_to_json(&DlogProverInput::random().public_image().h),
+            "secret": 12345,
+        });
+        let from_number = HintsBag::from_json(&json!([json.clone()])).unwrap();
+        let mut from_string = json;
+        from_string["secret"] = "12345".into();
+        assert_eq!(
+            HintsBag::from_json(&json!([from_string])).unwrap(),
+            from_number
+        );
+        assert_eq!(
+            from_number.own_commitments()[0].secret_randomness,
+            Scalar::from(12345u64)
+        );
+    }
+
+    #[test]
+    fn invalid_hints() {
+        assert!(matches!(
+            HintsBag::from_json(&json!([{"hint": "cmtUnknown", "pubkey": {"op": -51, "h":
+                point_to_json(&DlogProverInput::random().public_image().h)}, "position": "0"}])),
+            Err(HintsJsonError::UnknownHint(_))
+        ));
+        assert!(matches!(
+            HintsBag::from_json(&json!({})),
+            Err(HintsJsonError::InvalidField("hints", _))
+        ));
+        assert!(position_from_json("0-x").is_err());
+    }
+}
@@ -41,7 +41,7 @@ impl From<ProofBytes> for String {
     fn from(v: ProofBytes) -> Self {
         match v {
             ProofBytes::Empty => "".to_string(),
-            ProofBytes::Some(bytes) => base16::encode_lower(&bytes),
+            ProofBytes::Some(bytes) => sigma_util::hex::encode_lower(&bytes),
         }
     }
 }
@@ -51,7 +51,7 @@ impl TryFrom<String> for ProofBytes {
     type Error = base16::DecodeError;
 
     fn try_from(value: String) -> Result<Self, Self::Error> {
-        base16::decode(&value).map(|bytes| bytes.into())
+        sigma_util::hex::decode(&value).map(|bytes| bytes.into())
     }
 }
 
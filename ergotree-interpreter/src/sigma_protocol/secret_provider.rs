@@ -0,0 +1,334 @@
+//! Storage of the prover's secrets
+
+use std::cell::RefCell;
+
+use ergotree_ir::sigma_protocol::sigma_boolean::ProveDhTuple;
+use ergotree_ir::sigma_protocol::sigma_boolean::ProveDlog;
+use ergotree_ir::sigma_protocol::sigma_boolean::SigmaBoolean;
+use k256::Scalar;
+use thiserror::Error;
+
+use super::dht_protocol;
+use super::dht_protocol::FirstDhTupleProverMessage;
+use super::dlog_protocol;
+use super::dlog_protocol::FirstDlogProverMessage;
+use super::private_input::DhTupleProverInput;
+use super::private_input::DlogProverInput;
+use super::private_input::PrivateInput;
+
+/// Errors of the secret storage
+#[derive(Error, PartialEq, Eq, Debug, Clone)]
+pub enum SecretProviderError {
+    /// There is no secret for the public image
+    #[error("Secret for {0:?} is not found")]
+    NotFound(SigmaBoolean),
+    /// The commitment was not made by the storage or is answered already
+    #[error("Commitment is not made by the secret storage or is answered already")]
+    UnknownCommitment,
+    /// The storage does not compute the responses for the randomness generated outside of it
+    #[error("Secret storage does not accept the commitment randomness")]
+    RandomnessNotAccepted,
+    /// Storage failure (e.g. an HSM or a keystore is not available)
+    #[error("Secret storage error: {0}")]
+    Storage(String),
+}
+
+/// Storage of the prover's secrets, looked up by their public images.
+/// The storage runs the prover side of the sigma protocols for its secrets: it generates the
+/// randomness `r`, keeps it and returns the commitment ("a"), then computes the response ("z") to
+/// the challenge ("e") for this commitment (`z = r + e * w (mod q)`, where `w` is the secret) and
+/// discards `r`. Neither the secrets nor the randomness leave the storage, so that the private
+/// keys can stay in an HSM or a keystore.
+/// The prover requests the storage once per proof (see [`super::prover::Prover::secrets`]), the
+/// commitments are answered within the same proof.
+pub trait SecretProvider {
+    /// Public images of the stored secrets
+    fn public_images(&self) -> Vec<SigmaBoolean>;
+
+    /// Returns true if the secret for the given public image is stored
+    fn contains(&self, public_image: &SigmaBoolean) -> bool {
+        self.public_images().contains(public_image)
+    }
+
+    /// Commitment of the Schnorr signature protocol for the secret of the given public key
+    fn dlog_first_message(
+        &self,
+        public_image: &ProveDlog,
+    ) -> Result<FirstDlogProverMessage, SecretProviderError>;
+
+    /// Response of the Schnorr signature protocol to the challenge `e` for the commitment made
+    /// by [`SecretProvider::dlog_first_message`]
+    fn dlog_second_message(
+        &self,
+        public_image: &ProveDlog,
+        commitment: &FirstDlogProverMessage,
+        e: Scalar,
+    ) -> Result<Scalar, SecretProviderError>;
+
+    /// Commitment of the Diffie-Hellman tuple protocol for the secret of the given tuple
+    fn dh_tuple_first_message(
+        &self,
+        public_image: &ProveDhTuple,
+    ) -> Result<FirstDhTupleProverMessage, SecretProviderError>;
+
+    /// Response of the Diffie-Hellman tuple protocol to the challenge `e` for the commitment made
+    /// by [`SecretProvider::dh_tuple_first_message`]
+    fn dh_tuple_second_message(
+        &self,
+        public_image: &ProveDhTuple,
+        commitment: &FirstDhTupleProverMessage,
+        e: Scalar,
+    ) -> Result<Scalar, SecretProviderError>;
+
+    /// Response of the Schnorr signature protocol for the commitment made outside of the storage
+    /// with the randomness `rnd` (own commitments of the multi-signing hints, see
+    /// [`super::prover::hint::OwnCommitment`]). Not accepted by default.
+    fn dlog_second_message_with_randomness(
+        &self,
+        _public_image: &ProveDlog,
+        _rnd: Scalar,
+        _e: Scalar,
+    ) -> Result<Scalar, SecretProviderError> {
+        Err(SecretProviderError::RandomnessNotAccepted)
+    }
+
+    /// Response of the Diffie-Hellman tuple protocol for the commitment made outside of the
+    /// storage with the randomness `rnd` (see
+    /// [`SecretProvider::dlog_second_message_with_randomness`]). Not accepted by default.
+    fn dh_tuple_second_message_with_randomness(
+        &self,
+        _public_image: &ProveDhTuple,
+        _rnd: Scalar,
+        _e: Scalar,
+    ) -> Result<Scalar, SecretProviderError> {
+        Err(SecretProviderError::RandomnessNotAccepted)
+    }
+}
+
+impl<T: SecretProvider + ?Sized> SecretProvider for &T {
+    fn public_images(&self) -> Vec<SigmaBoolean> {
+        (**self).public_images()
+    }
+
+    fn contains(&self, public_image: &SigmaBoolean) -> bool {
+        (**self).contains(public_image)
+    }
+
+    fn dlog_first_message(
+        &self,
+        public_image: &ProveDlog,
+    ) -> Result<FirstDlogProverMessage, SecretProviderError> {
+        (**self).dlog_first_message(public_image)
+    }
+
+    fn dlog_second_message(
+        &self,
+        public_image: &ProveDlog,
+        commitment: &FirstDlogProverMessage,
+        e: Scalar,
+    ) -> Result<Scalar, SecretProviderError> {
+        (**self).dlog_second_message(public_image, commitment, e)
+    }
+
+    fn dh_tuple_first_message(
+        &self,
+        public_image: &ProveDhTuple,
+    ) -> Result<FirstDhTupleProverMessage, SecretProviderError> {
+        (**self).dh_tuple_first_message(public_image)
+    }
+
+    fn dh_tuple_second_message(
+        &self,
+        public_image: &ProveDhTuple,
+        commitment: &FirstDhTupleProverMessage,
+        e: Scalar,
+    ) -> Result<Scalar, SecretProviderError> {
+        (**self).dh_tuple_second_message(public_image, commitment, e)
+    }
+
+    fn dlog_second_message_with_randomness(
+        &self,
+        public_image: &ProveDlog,
+        rnd: Scalar,
+        e: Scalar,
+    ) -> Result<Scalar, SecretProviderError> {
+        (**self).dlog_second_message_with_randomness(public_image, rnd, e)
+    }
+
+    fn dh_tuple_second_message_with_randomness(
+        &self,
+        public_image: &ProveDhTuple,
+        rnd: Scalar,
+        e: Scalar,
+    ) -> Result<Scalar, SecretProviderError> {
+        (**self).dh_tuple_second_message_with_randomness(public_image, rnd, e)
+    }
+}
+
+/// Secrets in memory for a single proof, keeps the randomness of the commitments until they are
+/// answered
+pub struct InMemorySecrets<'a> {
+    secrets: &'a [PrivateInput],
+    dlog_commitments: RefCell<Vec<(FirstDlogProverMessage, Scalar)>>,
+    dh_tuple_commitments: RefCell<Vec<(FirstDhTupleProverMessage, Scalar)>>,
+}
+
+impl<'a> InMemorySecrets<'a> {
+    /// Storage of the given secrets
+    pub fn new(secrets: &'a [PrivateInput]) -> Self {
+        InMemorySecrets {
+            secrets,
+            dlog_commitments: RefCell::new(Vec::new()),
+            dh_tuple_commitments: RefCell::new(Vec::new()),
+        }
+    }
+
+    fn dlog_secret(
+        &self,
+        public_image: &ProveDlog,
+    ) -> Result<&DlogProverInput, SecretProviderError> {
+        self.secrets
+            .iter()
+            .find_map(|s| match s {
+                PrivateInput::DlogProverInput(dl) if &dl.public_image() == public_image => Some(dl),
+                _ => None,
+            })
+            .ok_or_else(|| SecretProviderError::NotFound(public_image.clone().into()))
+    }
+
+    fn dh_tuple_secret(
+        &self,
+        public_image: &ProveDhTuple,
+    ) -> Result<&DhTupleProverInput, SecretProviderError> {
+        self.secrets
+            .iter()
+            .find_map(|s| match s {
+                PrivateInput::DhTupleProverInput(dht) if dht.public_image() == public_image => {
+                    Some(dht)
+                }
+                _ => None,
+            })
+            .ok_or_else(|| SecretProviderError::NotFound(public_image.clone().into()))
+    }
+}
+
+/// Removes the commitment and returns its randomness
+fn take_randomness<M: PartialEq>(
+    commitments: &RefCell<Vec<(M, Scalar)>>,
+    commitment: &M,
+) -> Result<Scalar, SecretProviderError> {
+    let mut commitments = commitments.borrow_mut();
+    let idx = commitments
+        .iter()
+        .position(|(c, _)| c == commitment)
+        .ok_or(SecretProviderError::UnknownCommitment)?;
+    Ok(commitments.swap_remove(idx).1)
+}
+
+impl<'a> SecretProvider for InMemorySecrets<'a> {
+    fn public_images(&self) -> Vec<SigmaBoolean> {
+        self.secrets
+            .iter()
+            .map(PrivateInput::public_image)
+            .collect()
+    }
+
+    fn contains(&self, public_image: &SigmaBoolean) -> bool {
+        self.secrets
+            .iter()
+            .any(|s| &s.public_image() == public_image)
+    }
+
+    fn dlog_first_message(
+        &self,
+        public_image: &ProveDlog,
+    ) -> Result<FirstDlogProverMessage, SecretProviderError> {
+        self.dlog_secret(public_image)?;
+        let (r, commitment) = dlog_protocol::interactive_prover::first_message();
+        self.dlog_commitments
+            .borrow_mut()
+            .push((commitment.clone(), r));
+        Ok(commitment)
+    }
+
+    fn dlog_second_message(
+        &self,
+        public_image: &ProveDlog,
+        commitment: &FirstDlogProverMessage,
+        e: Scalar,
+    ) -> Result<Scalar, SecretProviderError> {
+        let secret = self.dlog_secret(public_image)?;
+        let r = take_randomness(&self.dlog_commitments, commitment)?;
+        Ok(dlog_protocol::interactive_prover::second_message(secret, r, e).z)
+    }
+
+    fn dh_tuple_first_message(
+        &self,
+        public_image: &ProveDhTuple,
+    ) -> Result<FirstDhTupleProverMessage, SecretProviderError> {
+        self.dh_tuple_secret(public_image)?;
+        let (r, commitment) = dht_protocol::interactive_prover::first_message(public_image);
+        self.dh_tuple_commitments
+            .borrow_mut()
+            .push((commitment.clone(), r));
+        Ok(commitment)
+    }
+
+    fn dh_tuple_second_message(
+        &self,
+        public_image: &ProveDhTuple,
+        commitment: &FirstDhTupleProverMessage,
+        e: Scalar,
+    ) -> Result<Scalar, SecretProviderError> {
+        let secret = self.dh_tuple_secret(public_image)?;
+        let r = take_randomness(&self.dh_tuple_commitments, commitment)?;
+        Ok(dht_protocol::interactive_prover::second_message(secret, &r, e).z)
+    }
+
+    fn dlog_second_message_with_randomness(
+        &self,
+        public_image: &ProveDlog,
+        rnd: Scalar,
+        e: Scalar,
+    ) -> Result<Scalar, SecretProviderError> {
+        let secret = self.dlog_secret(public_image)?;
+        Ok(dlog_protocol::interactive_prover::second_message(secret, rnd, e).z)
+    }
+
+    fn dh_tuple_second_message_with_randomness(
+        &self,
+        public_image: &ProveDhTuple,
+        rnd: Scalar,
+        e: Scalar,
+    ) -> Result<Scalar, SecretProviderError> {
+        let secret = self.dh_tuple_secret(public_image)?;
+        Ok(dht_protocol::interactive_prover::second_message(secret, &rnd, e).z)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::sigma_protocol::Challenge;
+
+    #[test]
+    fn commitment_is_answered_once() {
+        let secret = DlogProverInput::random();
+        let pk = secret.public_image();
+        let secrets = vec![PrivateInput::DlogProverInput(secret)];
+        let storage = InMemorySecrets::new(&secrets);
+        let commitment = storage.dlog_first_message(&pk).unwrap();
+        let e: Scalar = Challenge::secure_random().into();
+        assert!(storage.dlog_second_message(&pk, &commitment, e).is_ok());
+        assert_eq!(
+            storage.dlog_second_message(&pk, &commitment, e),
+            Err(SecretProviderError::UnknownCommitment)
+        );
+        let unknown = DlogProverInput::random().public_image();
+        assert_eq!(
+            storage.dlog_first_message(&unknown),
+            Err(SecretProviderError::NotFound(unknown.into()))
+        );
+    }
+}
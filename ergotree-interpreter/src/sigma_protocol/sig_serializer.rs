@@ -1,7 +1,9 @@
 //! Serialization of proof tree signatures
 
+use std::convert::TryFrom;
 use std::convert::TryInto;
 
+use super::gf2_192::Gf2_192Poly;
 use super::prover::ProofBytes;
 use super::unchecked_tree::UncheckedConjecture;
 use super::unchecked_tree::UncheckedLeaf;
@@ -85,6 +87,19 @@ fn sig_write_bytes<W: SigmaByteWrite>(
                 sig_write_bytes(last, w, false)?;
                 Ok(())
             }
+            UncheckedConjecture::CthresholdUnchecked {
+                children,
+                polynomial,
+                ..
+            } => {
+                // write the polynomial except the zero coefficient (the challenge of this node),
+                // don't write children's challenges -- they are the values of the polynomial
+                w.write_all(polynomial.to_bytes_without_constant_term().as_slice())?;
+                for child in children {
+                    sig_write_bytes(child, w, false)?;
+                }
+                Ok(())
+            }
         },
     }
 }
@@ -191,9 +206,43 @@ fn parse_sig_compute_challnges_reader<R: SigmaByteRead>(
                 }
                 .into())
             }
-            SigmaConjecture::Cthreshold(_) => Err(SigParsingError::Unexpected(
-                "parse_sig_compute_challenges: CTHRESHOLD is not yet implemented",
-            )),
+            SigmaConjecture::Cthreshold(ct) => {
+                // Verifier Step 2: If the node is THRESHOLD, evaluate the polynomial Q(x) at
+                // points 1, 2, ..., n to get challenges for child 1, 2, ..., n, respectively.
+                // Read the polynomial -- it has n-k coefficients besides the challenge of this
+                // node (the value at zero)
+                let n = ct.items.len();
+                let k = u8::try_from(ct.n)
+                    .ok()
+                    .filter(|k| *k as usize <= n && n <= u8::MAX as usize)
+                    .ok_or(SigParsingError::Unexpected(
+                        "parse_sig_compute_challenges: invalid CTHRESHOLD bound",
+                    ))?;
+                let mut coefficients = vec![[0u8; SOUNDNESS_BYTES]; n - k as usize];
+                for coefficient in coefficients.iter_mut() {
+                    r.read_exact(coefficient)?;
+                }
+                let polynomial =
+                    Gf2_192Poly::from_bytes(challenge.clone().into(), coefficients.as_slice());
+                let mut children: Vec<UncheckedTree> = Vec::with_capacity(n);
+                for (idx, it) in ct.items.iter().enumerate() {
+                    // n fits in u8 (checked above)
+                    let child_challenge = polynomial.evaluate((idx + 1) as u8).into();
+                    children.push(parse_sig_compute_challnges_reader(
+                        it,
+                        r,
+                        Some(child_challenge),
+                    )?);
+                }
+                #[allow(clippy::unwrap_used)] // since quantity is preserved unwrap is safe here
+                Ok(UncheckedConjecture::CthresholdUnchecked {
+                    challenge,
+                    children: children.try_into().unwrap(),
+                    k,
+                    polynomial,
+                }
+                .into())
+            }
         },
     }
 }
@@ -217,3 +266,325 @@ impl From<std::io::Error> for SigParsingError {
         SigParsingError::IoError(e.to_string())
     }
 }
+
+#[cfg(feature = "arbitrary")]
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::eval::context::Context;
+    use crate::eval::env::Env;
+    use crate::eval::settings::EvalSettings;
+    use crate::eval::settings::MAX_SUPPORTED_SCRIPT_VERSION;
+    use crate::sigma_protocol::dlog_protocol::interactive_prover;
+    use crate::sigma_protocol::fiat_shamir::fiat_shamir_hash_fn;
+    use crate::sigma_protocol::fiat_shamir::fiat_shamir_tree_to_bytes;
+    use crate::sigma_protocol::gf2_192::Gf2_192;
+    use crate::sigma_protocol::private_input::DhTupleProverInput;
+    use crate::sigma_protocol::private_input::DlogProverInput;
+    use crate::sigma_protocol::private_input::PrivateInput;
+    use crate::sigma_protocol::prover::hint::HintsBag;
+    use crate::sigma_protocol::prover::Prover;
+    use crate::sigma_protocol::prover::TestProver;
+    use crate::sigma_protocol::verifier::TestVerifier;
+    use crate::sigma_protocol::verifier::Verifier;
+    use ergotree_ir::ergo_tree::ErgoTree;
+    use ergotree_ir::ergo_tree::ErgoTreeHeader;
+    use ergotree_ir::mir::constant::Constant;
+    use ergotree_ir::mir::expr::Expr;
+    use ergotree_ir::serialization::SigmaSerializable;
+    use ergotree_ir::sigma_protocol::sigma_boolean::cand::Cand;
+    use ergotree_ir::sigma_protocol::sigma_boolean::cor::Cor;
+    use ergotree_ir::sigma_protocol::sigma_boolean::cthreshold::Cthreshold;
+    use ergotree_ir::sigma_protocol::sigma_boolean::ProveDhTuple;
+    use num_bigint::BigUint;
+    use proptest::prelude::*;
+    use sigma_test_util::force_any_val;
+
+    // message and secrets of the sigmastate SigningSpecification test vectors
+    const MSG: &str = "1dc01772ee0171f5f614c673e3c7fa1107a8cf727bdf5a6dadb379e93c0d1d00";
+    const SK1: &[u8] =
+        b"109749205800194830127901595352600384558037183218698112947062497909408298157746";
+    const SK2: &[u8] =
+        b"50415569076448343263191022044468203756975150511337537963383000142821297891310";
+    const SK3: &[u8] =
+        b"34648336872573478681093104997365775365807654884817677358848426648354905397359";
+
+    fn pk(sk: &[u8]) -> SigmaBoolean {
+        DlogProverInput::from_biguint(BigUint::parse_bytes(sk, 10).unwrap())
+            .unwrap()
+            .public_image()
+            .into()
+    }
+
+    fn dht(bytes: &str) -> SigmaBoolean {
+        ProveDhTuple::sigma_parse_bytes(&base16::decode(bytes).unwrap())
+            .unwrap()
+            .into()
+    }
+
+    fn and(items: Vec<SigmaBoolean>) -> SigmaBoolean {
+        Cand::normalized(items.try_into().unwrap())
+    }
+
+    fn or(items: Vec<SigmaBoolean>) -> SigmaBoolean {
+        Cor::normalized(items.try_into().unwrap())
+    }
+
+    /// THRESHOLD(2, pk(SK1), pk(SK2), pk(SK3)) and its proof of the message, all the children are
+    /// real (the challenges are the values of the polynomial `e0 + coefficient * x`)
+    fn threshold_proof(
+        message: &[u8],
+        coefficient: [u8; SOUNDNESS_BYTES],
+    ) -> (SigmaBoolean, Vec<u8>) {
+        let secrets: Vec<DlogProverInput> = [SK1, SK2, SK3]
+            .iter()
+            .map(|sk| DlogProverInput::from_biguint(BigUint::parse_bytes(sk, 10).unwrap()).unwrap())
+            .collect();
+        let sb = Cthreshold::normalized(
+            2,
+            secrets
+                .iter()
+                .map(|s| s.public_image().into())
+                .collect::<Vec<SigmaBoolean>>()
+                .try_into()
+                .unwrap(),
+        );
+        let rnds: Vec<(Scalar, _)> = secrets
+            .iter()
+            .map(|_| interactive_prover::first_message())
+            .collect();
+        let tree = |polynomial: Gf2_192Poly, z: Vec<Scalar>| -> UncheckedTree {
+            let children: Vec<UncheckedTree> = secrets
+                .iter()
+                .zip(rnds.iter())
+                .zip(z)
+                .enumerate()
+                .map(|(idx, ((s, (_, a)), z))| {
+                    UncheckedSchnorr {
+                        proposition: s.public_image(),
+                        commitment_opt: Some(a.clone()),
+                        challenge: polynomial.evaluate(idx as u8 + 1).into(),
+                        second_message: z.into(),
+                    }
+                    .into()
+                })
+                .collect();
+            UncheckedConjecture::CthresholdUnchecked {
+                challenge: polynomial.evaluate(0).into(),
+                children: children.try_into().unwrap(),
+                k: 2,
+                polynomial,
+            }
+            .into()
+        };
+        // the challenges and responses are not a part of the Fiat-Shamir input
+        let commitments_tree = tree(
+            Gf2_192Poly::from_bytes(Gf2_192::default(), &[coefficient]),
+            rnds.iter().map(|(r, _)| *r).collect(),
+        );
+        let mut s = fiat_shamir_tree_to_bytes(&commitments_tree.into()).unwrap();
+        s.extend_from_slice(message);
+        let root_challenge: Challenge = fiat_shamir_hash_fn(s.as_slice()).into();
+        let polynomial = Gf2_192Poly::from_bytes(root_challenge.into(), &[coefficient]);
+        let z = secrets
+            .iter()
+            .zip(rnds.iter())
+            .enumerate()
+            .map(|(idx, (s, (r, _)))| {
+                let e: Challenge = polynomial.evaluate(idx as u8 + 1).into();
+                interactive_prover::second_message(s, *r, e.into()).z
+            })
+            .collect();
+        (sb, serialize_sig(tree(polynomial, z)).into())
+    }
+
+    /// Proofs generated by the reference implementation (sigmastate SigningSpecification)
+    fn reference_vectors() -> Vec<(&'static str, SigmaBoolean, &'static str)> {
+        vec![
+            (
+                "ProveDlog",
+                pk(SK1),
+                "bcb866ba434d5c77869ddcbc3f09ddd62dd2d2539bf99076674d1ae0c32338ea95581fdc18a3b66789904938ac641eba1a66d234070207a2",
+            ),
+            (
+                "ProveDHT",
+                dht("0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f817980280c66feee88d56e47bf3f47c4109d9218c60c373a472a0d9537507c7ee828c4802a96f19e97df31606183c1719400682d1d40b1ce50c9a1ed1b19845e2b1b551bf0255ac02191cb229891fb1b674ea9df7fc8426350131d821fc4a53f29c3b1cb21a"),
+                "eba93a69b28cfdea261e9ea8914fca9a0b3868d50ce68c94f32e875730f8ca361bd3783c5d3e25802e54f49bd4fb9fafe51f4e8aafbf9815",
+            ),
+            (
+                "AND",
+                and(vec![pk(SK1), pk(SK2)]),
+                "9b2ebb226be42df67817e9c56541de061997c3ea84e7e72dbb69edb7318d7bb525f9c16ccb1adc0ede4700a046d0a4ab1e239245460c1ba45e5637f7a2d4cc4cc460e5895125be73a2ca16091db2dcf51d3028043c2b9340",
+            ),
+            (
+                "OR",
+                or(vec![pk(SK1), pk(SK2)]),
+                "ec94d2d5ef0e1e638237f53fd883c339f9771941f70020742a7dc85130aaee535c61321aa1e1367befb500256567b3e6f9c7a3720baa75ba6056305d7595748a93f23f9fc0eb9c1aaabc24acc4197030834d76d3c95ede60c5b59b4b306cd787d010e8217f34677d046646778877c669",
+            ),
+            (
+                "OR with ProveDHT",
+                or(vec![
+                    pk(SK1),
+                    dht("0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f817980214487635ebffa60b13a166bd0721c5f0ab603fc74168d7764d7ec5ef2107f5d40334c5b7efa5a4a22b83d102d2e6521eaa660fa911c5a213af63c8460f2327513b026a0be2a277291d42daad3830cb16a4ef20e4f1f7c36384f3fee065f0f143a355"),
+                ]),
+                "a80daebdcd57874296f49fd9910ddaefbf517ca076b6e16b97678e96a20239978836e7ec5b795cf3a55616d394f07c004f85e0d3e71880d4734b57ea874c7eba724e8887280f1affadaad962ee916b39207af2d2ab2a69a2e6f4d652f7389cc4f582bbe6d7937c59aa64cf2965a8b36a",
+            ),
+            (
+                "AND with OR",
+                and(vec![pk(SK1), or(vec![pk(SK2), pk(SK3)])]),
+                "397e005d85c161990d0e44853fbf14951ff76e393fe1939bb48f68e852cd5af028f6c7eaaed587f6d5435891a564d8f9a77288773ce5b526a670ab0278aa4278891db53a9842df6fba69f95f6d55cfe77dd7b4bdccc1a3378ac4524b51598cb813258f64c94e98c3ef891a6eb8cbfd2e527a9038ca50b5bb50058de55a859a169628e6ae5ba4cb0332c694e450782d6f",
+            ),
+            (
+                "OR with AND",
+                or(vec![pk(SK1), and(vec![pk(SK2), pk(SK3)])]),
+                "a58b251be319a9656c21876b1136a59f42b18835dec6076c92f7a925ba28d2030218c177ab07563003eff5250cfafeb631ef610f4d710ab8e821bf632203adf23f4376580eaa17ddb36c0138f73a88551f45d92cde2b66dfbb5906c02e4d48106ff08be4a2fc29ec242f495468692f9ddeeb029dc5d8f38e2649cf09c44b67cbcfb3de4202026fb84d23ce2b4ff0f69b",
+            ),
+        ]
+    }
+
+    fn assert_round_trip(sb: &SigmaBoolean, proof: Vec<u8>) {
+        let tree = parse_sig_compute_challenges(sb, proof.clone()).unwrap();
+        assert_eq!(serialize_sig(tree), ProofBytes::Some(proof));
+    }
+
+    // the proof bytes do not depend on the tree version and the constant segregation
+    fn assert_verifies_in_all_versions(sb: &SigmaBoolean, proof: Vec<u8>, message: &[u8]) {
+        let expr: Expr = Constant::from(sb.clone()).into();
+        for header in &[
+            ErgoTreeHeader::v0(false),
+            ErgoTreeHeader::v0(true),
+            ErgoTreeHeader::v1(false),
+            ErgoTreeHeader::v1(true),
+        ] {
+            let tree = ErgoTree::new(header.clone(), &expr).unwrap();
+            let tree_version: u8 = header.version().into();
+            for activated_version in tree_version..=MAX_SUPPORTED_SCRIPT_VERSION {
+                let settings = EvalSettings {
+                    strict: true,
+                    ..EvalSettings::new(activated_version)
+                };
+                let res = TestVerifier
+                    .verify_with_settings(
+                        &tree,
+                        &Env::empty(),
+                        Rc::new(force_any_val::<Context>()),
+                        proof.clone().into(),
+                        message,
+                        &settings,
+                    )
+                    .unwrap();
+                assert!(
+                    res.result,
+                    "header {:?}, activated version {}",
+                    header, activated_version
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn reference_vectors_round_trip() {
+        for (name, sb, proof_hex) in reference_vectors() {
+            let proof = base16::decode(proof_hex).unwrap();
+            let tree = parse_sig_compute_challenges(&sb, proof.clone()).unwrap();
+            assert_eq!(
+                serialize_sig(tree),
+                ProofBytes::Some(proof),
+                "proof of {} is re-serialized differently",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn reference_vectors_verify_in_all_versions() {
+        let msg = base16::decode(MSG).unwrap();
+        for (_, sb, proof_hex) in reference_vectors() {
+            let proof = base16::decode(proof_hex).unwrap();
+            assert_verifies_in_all_versions(&sb, proof, &msg);
+        }
+    }
+
+    #[test]
+    fn truncated_and_extended_proofs() {
+        for (_, sb, proof_hex) in reference_vectors() {
+            let proof = base16::decode(proof_hex).unwrap();
+            assert!(parse_sig_compute_challenges(&sb, proof[..proof.len() - 1].to_vec()).is_err());
+            // trailing bytes are ignored by the parser, but not re-serialized
+            let mut extended = proof.clone();
+            extended.push(1);
+            let tree = parse_sig_compute_challenges(&sb, extended).unwrap();
+            assert_eq!(serialize_sig(tree), ProofBytes::Some(proof));
+        }
+    }
+
+    #[test]
+    fn threshold_proofs() {
+        let msg = base16::decode(MSG).unwrap();
+        let (sb, proof) = threshold_proof(&msg, [7u8; SOUNDNESS_BYTES]);
+        // challenge, one coefficient (n - k) and three responses
+        assert_eq!(proof.len(), 2 * SOUNDNESS_BYTES + 3 * GROUP_SIZE);
+        assert_round_trip(&sb, proof.clone());
+        assert_verifies_in_all_versions(&sb, proof.clone(), &msg);
+        assert!(parse_sig_compute_challenges(&sb, proof[..proof.len() - 1].to_vec()).is_err());
+        // the children challenges are the values of the polynomial
+        let mut tampered = proof;
+        tampered[SOUNDNESS_BYTES] ^= 1;
+        let expr: Expr = Constant::from(sb).into();
+        let tree = ErgoTree::new(ErgoTreeHeader::v0(false), &expr).unwrap();
+        let res = TestVerifier
+            .verify(
+                &tree,
+                &Env::empty(),
+                Rc::new(force_any_val::<Context>()),
+                tampered.into(),
+                &msg,
+            )
+            .unwrap();
+        assert!(!res.result);
+    }
+
+    proptest! {
+
+        #![proptest_config(ProptestConfig::with_cases(16))]
+
+        #[test]
+        fn prover_proofs_round_trip(
+            dl1 in any::<DlogProverInput>(),
+            dl2 in any::<DlogProverInput>(),
+            dh in any::<DhTupleProverInput>(),
+            message in proptest::collection::vec(any::<u8>(), 1..100),
+        ) {
+            let pk1: SigmaBoolean = dl1.public_image().into();
+            let pk2: SigmaBoolean = dl2.public_image().into();
+            let pdh: SigmaBoolean = dh.public_image().clone().into();
+            // real and simulated branches, the latter is the last child of OR (challenge is not
+            // serialized)
+            let sb = or(vec![
+                and(vec![pk1.clone(), or(vec![pk2.clone(), pdh.clone()])]),
+                and(vec![pk2, pdh]),
+                pk1,
+            ]);
+            let prover = TestProver {
+                secrets: vec![PrivateInput::DlogProverInput(dl1), PrivateInput::DhTupleProverInput(dh)],
+            };
+            let expr: Expr = Constant::from(sb.clone()).into();
+            let proof: Vec<u8> = prover
+                .prove(
+                    &ErgoTree::new(ErgoTreeHeader::v0(false), &expr).unwrap(),
+                    &Env::empty(),
+                    Rc::new(force_any_val::<Context>()),
+                    &message,
+                    &HintsBag::empty(),
+                )
+                .unwrap()
+                .proof
+                .into();
+            assert_round_trip(&sb, proof.clone());
+            assert_verifies_in_all_versions(&sb, proof, &message);
+        }
+    }
+}
@@ -8,6 +8,7 @@ use ergotree_ir::sigma_protocol::sigma_boolean::SigmaProofOfKnowledgeTree;
 
 use super::dht_protocol::FirstDhTupleProverMessage;
 use super::dht_protocol::SecondDhTupleProverMessage;
+use super::gf2_192::Gf2_192Poly;
 use super::proof_tree::ConjectureType;
 use super::proof_tree::ProofTree;
 use super::proof_tree::ProofTreeConjecture;
@@ -146,6 +147,14 @@ pub enum UncheckedConjecture {
         challenge: Challenge,
         children: SigmaConjectureItems<UncheckedTree>,
     },
+    CthresholdUnchecked {
+        challenge: Challenge,
+        children: SigmaConjectureItems<UncheckedTree>,
+        k: u8,
+        /// Polynomial of the children challenges (the value at `i` is the challenge of the
+        /// `i`-th child, starting with 1, the value at zero is the challenge of the node)
+        polynomial: Gf2_192Poly,
+    },
 }
 
 impl UncheckedConjecture {
@@ -165,6 +174,17 @@ impl UncheckedConjecture {
                 challenge,
                 children: new_children,
             },
+            UncheckedConjecture::CthresholdUnchecked {
+                challenge,
+                children: _,
+                k,
+                polynomial,
+            } => UncheckedConjecture::CthresholdUnchecked {
+                challenge,
+                children: new_children,
+                k,
+                polynomial,
+            },
         }
     }
 
@@ -178,6 +198,7 @@ impl UncheckedConjecture {
                 challenge: _,
                 children,
             } => children,
+            UncheckedConjecture::CthresholdUnchecked { children, .. } => children,
         }
     }
 
@@ -191,6 +212,7 @@ impl UncheckedConjecture {
                 challenge,
                 children: _,
             } => challenge.clone(),
+            UncheckedConjecture::CthresholdUnchecked { challenge, .. } => challenge.clone(),
         }
     }
 
@@ -210,6 +232,17 @@ impl UncheckedConjecture {
                 challenge,
                 children,
             },
+            UncheckedConjecture::CthresholdUnchecked {
+                challenge: _,
+                children,
+                k,
+                polynomial,
+            } => UncheckedConjecture::CthresholdUnchecked {
+                challenge,
+                children,
+                k,
+                polynomial,
+            },
         }
     }
 }
@@ -219,6 +252,7 @@ impl ProofTreeConjecture for UncheckedConjecture {
         match self {
             UncheckedConjecture::CandUnchecked { .. } => ConjectureType::And,
             UncheckedConjecture::CorUnchecked { .. } => ConjectureType::Or,
+            UncheckedConjecture::CthresholdUnchecked { .. } => ConjectureType::Threshold,
         }
     }
 
@@ -232,6 +266,16 @@ impl ProofTreeConjecture for UncheckedConjecture {
                 challenge: _,
                 children,
             } => children.mapped_ref(|ust| ust.clone().into()),
+            UncheckedConjecture::CthresholdUnchecked { children, .. } => {
+                children.mapped_ref(|ust| ust.clone().into())
+            }
+        }
+    }
+
+    fn threshold_k(&self) -> Option<u8> {
+        match self {
+            UncheckedConjecture::CthresholdUnchecked { k, .. } => Some(*k),
+            _ => None,
         }
     }
 }
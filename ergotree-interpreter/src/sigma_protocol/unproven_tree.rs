@@ -369,6 +369,18 @@ impl NodePosition {
         positions.push(child_idx);
         NodePosition { positions }
     }
+
+    /// Position from the indices from the root (inclusive) in top-down order
+    #[cfg(feature = "json")]
+    pub(crate) fn from_positions(positions: Vec<usize>) -> Self {
+        NodePosition { positions }
+    }
+
+    /// Indices from the root (inclusive) in top-down order
+    #[cfg(feature = "json")]
+    pub(crate) fn positions(&self) -> &[usize] {
+        &self.positions
+    }
 }
 
 #[derive(PartialEq, Debug, Clone)]
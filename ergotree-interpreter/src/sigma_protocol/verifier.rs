@@ -17,7 +17,8 @@ use super::{
 };
 use crate::eval::context::Context;
 use crate::eval::env::Env;
-use crate::eval::reduce_to_crypto;
+use crate::eval::reduce_tree_to_crypto;
+use crate::eval::settings::EvalSettings;
 use crate::eval::EvalError;
 use dlog_protocol::FirstDlogProverMessage;
 use ergotree_ir::ergo_tree::ErgoTree;
@@ -47,6 +48,7 @@ pub enum VerifierError {
 }
 
 /// Result of Box.ergoTree verification procedure (see `verify` method).
+#[derive(PartialEq, Eq, Debug, Clone)]
 pub struct VerificationResult {
     /// result of SigmaProp condition verification via sigma protocol
     pub result: bool,
@@ -60,6 +62,7 @@ pub trait Verifier {
     /// Step 1: Deserialize context variables
     /// Step 2: Evaluate expression and produce SigmaProp value, which is zero-knowledge statement (see also `SigmaBoolean`).
     /// Step 3: Verify that the proof is presented to satisfy SigmaProp conditions.
+    /// The script is evaluated with the default settings (see [`EvalSettings::default`]).
     fn verify(
         &self,
         tree: &ErgoTree,
@@ -68,9 +71,22 @@ pub trait Verifier {
         proof: ProofBytes,
         message: &[u8],
     ) -> Result<VerificationResult, VerifierError> {
-        let expr = tree.proposition()?;
-        let cprop = reduce_to_crypto(expr.as_ref(), env, ctx)?.sigma_prop;
-        let res: bool = match cprop {
+        self.verify_with_settings(tree, env, ctx, proof, message, &EvalSettings::default())
+    }
+
+    /// Same as [`Verifier::verify`], with the given evaluation settings (activated script
+    /// version, soft-fork behavior)
+    fn verify_with_settings(
+        &self,
+        tree: &ErgoTree,
+        env: &Env,
+        ctx: Rc<Context>,
+        proof: ProofBytes,
+        message: &[u8],
+        settings: &EvalSettings,
+    ) -> Result<VerificationResult, VerifierError> {
+        let reduction_result = reduce_tree_to_crypto(tree, env, ctx, settings)?;
+        let res: bool = match reduction_result.sigma_prop {
             SigmaBoolean::TrivialProp(b) => b,
             sb => {
                 match proof {
@@ -86,11 +102,30 @@ pub trait Verifier {
         };
         Ok(VerificationResult {
             result: res,
-            cost: 0,
+            cost: reduction_result.cost,
         })
     }
 }
 
+/// Verify the signature of an arbitrary message, generated for the given sigma proposition with
+/// [`super::prover::Prover::generate_proof`] (e.g. a signature of a P2PK address owner)
+pub fn verify_signature(
+    sigma_tree: SigmaBoolean,
+    message: &[u8],
+    signature: &[u8],
+) -> Result<bool, VerifierError> {
+    match sigma_tree {
+        SigmaBoolean::TrivialProp(b) => Ok(b),
+        sb => {
+            if signature.is_empty() {
+                return Ok(false);
+            }
+            let unchecked_tree = parse_sig_compute_challenges(&sb, signature.to_vec())?;
+            check_commitments(unchecked_tree, message)
+        }
+    }
+}
+
 /// Perform Verifier Steps 4-6
 fn check_commitments(sp: UncheckedTree, message: &[u8]) -> Result<bool, VerifierError> {
     // Perform Verifier Step 4
@@ -108,7 +143,7 @@ fn check_commitments(sp: UncheckedTree, message: &[u8]) -> Result<bool, Verifier
 /// Verifier Step 4: For every leaf node, compute the commitment a from the challenge e and response $z$,
 /// per the verifier algorithm of the leaf's Sigma-protocol.
 /// If the verifier algorithm of the Sigma-protocol for any of the leaves rejects, then reject the entire proof.
-fn compute_commitments(sp: UncheckedTree) -> UncheckedTree {
+pub(crate) fn compute_commitments(sp: UncheckedTree) -> UncheckedTree {
     match sp {
         UncheckedTree::UncheckedLeaf(leaf) => match leaf {
             UncheckedLeaf::UncheckedSchnorr(sn) => {
@@ -153,16 +188,24 @@ impl Verifier for TestVerifier {}
 #[cfg(test)]
 #[cfg(feature = "arbitrary")]
 mod tests {
+    use std::cell::RefCell;
     use std::convert::TryFrom;
 
+    use crate::sigma_protocol::crypto_utils;
+    use crate::sigma_protocol::dht_protocol::FirstDhTupleProverMessage;
+    use crate::sigma_protocol::dlog_protocol::FirstDlogProverMessage;
     use crate::sigma_protocol::private_input::{DhTupleProverInput, DlogProverInput, PrivateInput};
     use crate::sigma_protocol::prover::hint::HintsBag;
-    use crate::sigma_protocol::prover::{Prover, TestProver};
+    use crate::sigma_protocol::prover::{Prover, ProverError, SecretProviderProver, TestProver};
+    use crate::sigma_protocol::secret_provider::{SecretProvider, SecretProviderError};
 
     use super::*;
     use ergotree_ir::mir::expr::Expr;
     use ergotree_ir::mir::sigma_and::SigmaAnd;
     use ergotree_ir::mir::sigma_or::SigmaOr;
+    use ergotree_ir::sigma_protocol::dlog_group;
+    use ergotree_ir::sigma_protocol::sigma_boolean::{ProveDhTuple, ProveDlog, SigmaBoolean};
+    use k256::Scalar;
     use proptest::collection::vec;
     use proptest::prelude::*;
     use sigma_test_util::force_any_val;
@@ -181,6 +224,27 @@ mod tests {
 
         #![proptest_config(ProptestConfig::with_cases(16))]
 
+        #[test]
+        fn test_verify_signature(secret in any::<DlogProverInput>(),
+                                 other in any::<DlogProverInput>(),
+                                 message in vec(any::<u8>(), 0..100)) {
+            let pk: SigmaBoolean = secret.public_image().into();
+            let prover = TestProver {
+                secrets: vec![PrivateInput::DlogProverInput(secret)],
+            };
+            let signature: Vec<u8> = prover
+                .generate_proof(pk.clone(), &message, &HintsBag::empty())
+                .unwrap()
+                .proof
+                .into();
+            prop_assert_eq!(verify_signature(pk.clone(), &message, &signature), Ok(true));
+            let mut other_message = message.clone();
+            other_message.push(1);
+            prop_assert_eq!(verify_signature(pk.clone(), &other_message, &signature), Ok(false));
+            prop_assert_eq!(verify_signature(other.public_image().into(), &message, &signature), Ok(false));
+            prop_assert_eq!(verify_signature(pk, &message, &[]), Ok(false));
+        }
+
         #[test]
         fn test_prover_verifier_p2pk(secret in any::<DlogProverInput>(), message in vec(any::<u8>(), 100..200)) {
             let pk = secret.public_image();
@@ -393,4 +457,156 @@ mod tests {
             }
         }
     }
+
+    // keys kept "in a device" which makes the commitments and computes the responses
+    // (z = r + e * w) without exposing the keys and the randomness r
+    struct DeviceSecrets {
+        dlog: DlogProverInput,
+        dht: DhTupleProverInput,
+        dlog_randomness: RefCell<Vec<(FirstDlogProverMessage, Scalar)>>,
+        dht_randomness: RefCell<Vec<(FirstDhTupleProverMessage, Scalar)>>,
+    }
+
+    impl DeviceSecrets {
+        fn new(dlog: DlogProverInput, dht: DhTupleProverInput) -> Self {
+            DeviceSecrets {
+                dlog,
+                dht,
+                dlog_randomness: RefCell::new(vec![]),
+                dht_randomness: RefCell::new(vec![]),
+            }
+        }
+    }
+
+    fn take_randomness<M: PartialEq>(
+        randomness: &RefCell<Vec<(M, Scalar)>>,
+        commitment: &M,
+    ) -> Result<Scalar, SecretProviderError> {
+        let mut randomness = randomness.borrow_mut();
+        let idx = randomness
+            .iter()
+            .position(|(c, _)| c == commitment)
+            .ok_or(SecretProviderError::UnknownCommitment)?;
+        Ok(randomness.remove(idx).1)
+    }
+
+    impl SecretProvider for DeviceSecrets {
+        fn public_images(&self) -> Vec<SigmaBoolean> {
+            vec![
+                self.dlog.public_image().into(),
+                self.dht.public_image().clone().into(),
+            ]
+        }
+
+        fn dlog_first_message(
+            &self,
+            public_image: &ProveDlog,
+        ) -> Result<FirstDlogProverMessage, SecretProviderError> {
+            if public_image != &self.dlog.public_image() {
+                return Err(SecretProviderError::NotFound(public_image.clone().into()));
+            }
+            let r = dlog_group::random_scalar_in_group_range(crypto_utils::secure_rng());
+            let a: FirstDlogProverMessage =
+                dlog_group::exponentiate(&dlog_group::generator(), &r).into();
+            self.dlog_randomness.borrow_mut().push((a.clone(), r));
+            Ok(a)
+        }
+
+        fn dlog_second_message(
+            &self,
+            public_image: &ProveDlog,
+            commitment: &FirstDlogProverMessage,
+            e: Scalar,
+        ) -> Result<Scalar, SecretProviderError> {
+            if public_image != &self.dlog.public_image() {
+                return Err(SecretProviderError::NotFound(public_image.clone().into()));
+            }
+            let r = take_randomness(&self.dlog_randomness, commitment)?;
+            Ok(r + e * self.dlog.w)
+        }
+
+        fn dh_tuple_first_message(
+            &self,
+            public_image: &ProveDhTuple,
+        ) -> Result<FirstDhTupleProverMessage, SecretProviderError> {
+            if public_image != self.dht.public_image() {
+                return Err(SecretProviderError::NotFound(public_image.clone().into()));
+            }
+            let r = dlog_group::random_scalar_in_group_range(crypto_utils::secure_rng());
+            let a = FirstDhTupleProverMessage::new(
+                dlog_group::exponentiate(&public_image.g, &r),
+                dlog_group::exponentiate(&public_image.h, &r),
+            );
+            self.dht_randomness.borrow_mut().push((a.clone(), r));
+            Ok(a)
+        }
+
+        fn dh_tuple_second_message(
+            &self,
+            public_image: &ProveDhTuple,
+            commitment: &FirstDhTupleProverMessage,
+            e: Scalar,
+        ) -> Result<Scalar, SecretProviderError> {
+            if public_image != self.dht.public_image() {
+                return Err(SecretProviderError::NotFound(public_image.clone().into()));
+            }
+            let r = take_randomness(&self.dht_randomness, commitment)?;
+            Ok(r + e * self.dht.w)
+        }
+    }
+
+    #[test]
+    fn test_prover_verifier_secret_provider() {
+        let dlog = DlogProverInput::random();
+        let dht = DhTupleProverInput::random();
+        let unknown = DlogProverInput::random();
+        // (pk && dht) || unknown_pk
+        let expr: Expr = SigmaOr::new(vec![
+            SigmaAnd::new(vec![
+                Expr::Const(dlog.public_image().into()),
+                Expr::Const(dht.public_image().clone().into()),
+            ])
+            .unwrap()
+            .into(),
+            Expr::Const(unknown.public_image().into()),
+        ])
+        .unwrap()
+        .into();
+        let tree = ErgoTree::try_from(expr).unwrap();
+        let message = vec![0u8; 100];
+        let prover = SecretProviderProver::new(Box::new(DeviceSecrets::new(dlog, dht)));
+        let proof = prover
+            .prove(
+                &tree,
+                &Env::empty(),
+                Rc::new(force_any_val::<Context>()),
+                message.as_slice(),
+                &HintsBag::empty(),
+            )
+            .unwrap()
+            .proof;
+        let ver_res = TestVerifier.verify(
+            &tree,
+            &Env::empty(),
+            Rc::new(force_any_val::<Context>()),
+            proof,
+            message.as_slice(),
+        );
+        assert!(ver_res.unwrap().result);
+
+        // only the unknown key's branch
+        let unknown_tree = ErgoTree::try_from(Expr::Const(unknown.public_image().into())).unwrap();
+        assert_eq!(
+            prover
+                .prove(
+                    &unknown_tree,
+                    &Env::empty(),
+                    Rc::new(force_any_val::<Context>()),
+                    message.as_slice(),
+                    &HintsBag::empty(),
+                )
+                .err(),
+            Some(ProverError::TreeRootIsNotReal)
+        );
+    }
 }
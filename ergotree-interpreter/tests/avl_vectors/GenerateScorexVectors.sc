@@ -0,0 +1,86 @@
+// Generates scorex.json (see README.md) with the scrypto BatchAVLProver:
+//   scala-cli run GenerateScorexVectors.sc > scorex.json
+
+//> using scala "2.12.15"
+//> using dep "org.scorexfoundation::scrypto:2.2.1"
+
+import scorex.crypto.authds.{ADKey, ADValue}
+import scorex.crypto.authds.avltree.batch._
+import scorex.crypto.hash.{Blake2b256, Digest32}
+import scorex.util.encode.Base16
+
+val rnd = new scala.util.Random(42)
+
+def bytes(n: Int): Array[Byte] = {
+  val res = new Array[Byte](n)
+  rnd.nextBytes(res)
+  res
+}
+
+def hex(b: Array[Byte]): String = "\"" + Base16.encode(b) + "\""
+
+def arr(items: Seq[String]): String = items.mkString("[", ",", "]")
+
+def vector(
+    name: String,
+    keyLength: Int,
+    valueLength: Option[Int],
+    entriesCount: Int,
+    operation: String,
+    opsCount: Int
+): String = {
+  val prover = new BatchAVLProver[Digest32, Blake2b256.type](keyLength, valueLength)
+  def value(): Array[Byte] = bytes(valueLength.getOrElse(1 + rnd.nextInt(64)))
+  val entries = (1 to entriesCount).map(_ => (bytes(keyLength), value()))
+  entries.foreach { case (k, v) => prover.performOneOperation(Insert(ADKey @@ k, ADValue @@ v)).get }
+  prover.generateProof()
+  val startingDigest = prover.digest
+  // the updated and removed keys are in the tree, half of the looked up ones too
+  val existing = rnd.shuffle(entries.map(_._1)).take(opsCount)
+  val keys = (operation match {
+    case "insert" => (1 to opsCount).map(_ => bytes(keyLength))
+    case "lookup" => existing.take(opsCount / 2) ++ (1 to opsCount / 2).map(_ => bytes(keyLength))
+    case _        => existing
+  }).map(k => ADKey @@ k)
+  val values = keys.map(_ => ADValue @@ value())
+  val results = keys.zip(values).map { case (k, v) =>
+    val op = operation match {
+      case "lookup" => Lookup(k)
+      case "insert" => Insert(k, v)
+      case "update" => Update(k, v)
+      case "remove" => Remove(k)
+    }
+    prover.performOneOperation(op).get
+  }
+  val proof = prover.generateProof()
+  val fields = Seq(
+    "\"name\":\"" + name + "\"",
+    "\"keyLength\":" + keyLength,
+    "\"valueLength\":" + valueLength.map(_.toString).getOrElse("null"),
+    "\"entries\":" + arr(entries.map { case (k, v) => arr(Seq(hex(k), hex(v))) }),
+    "\"startingDigest\":" + hex(startingDigest),
+    "\"operation\":\"" + operation + "\"",
+    "\"keys\":" + arr(keys.map(hex(_))),
+    "\"values\":" + (if (operation == "insert" || operation == "update") arr(values.map(hex(_))) else "[]"),
+    "\"proof\":" + hex(proof),
+    "\"results\":" + (if (operation == "lookup") arr(results.map(_.map(hex(_)).getOrElse("null"))) else "null"),
+    "\"endingDigest\":" + hex(prover.digest)
+  )
+  fields.mkString("{", ",", "}")
+}
+
+val vectors = for {
+  (keyLength, valueLength) <- Seq((32, None), (32, Some(8)), (8, None))
+  entriesCount <- Seq(0, 1, 20)
+  operation <- Seq("lookup", "insert", "update", "remove")
+  if entriesCount > 0 || operation == "lookup" || operation == "insert"
+} yield vector(
+  s"$operation, key length $keyLength, value length ${valueLength.getOrElse("variable")}, $entriesCount entries",
+  keyLength,
+  valueLength,
+  entriesCount,
+  operation,
+  8
+)
+
+println(vectors.mkString("[\n", ",\n", "\n]"))
@@ -0,0 +1,80 @@
+//! Matching the P2PK boxes of a wallet among 1M box propositions (wallet scan workloads):
+//! decompression of every public key vs. batch parsing vs. matching the compressed encodings.
+
+use criterion::criterion_group;
+use criterion::criterion_main;
+use criterion::Criterion;
+use criterion::Throughput;
+use ergotree_ir::chain::address::Address;
+use ergotree_ir::serialization::SigmaSerializable;
+use ergotree_ir::sigma_protocol::dlog_group::derive_generators;
+use ergotree_ir::sigma_protocol::dlog_group::CompressedPointSet;
+use ergotree_ir::sigma_protocol::dlog_group::EcPoint;
+use ergotree_ir::sigma_protocol::sigma_boolean::ProveDlog;
+
+const BOXES: usize = 1_000_000;
+// distinct public keys among the scanned boxes
+const KEYS: u32 = 1000;
+// public keys of the wallet
+const OWN_KEYS: usize = 10;
+
+fn p2pk_tree_bytes(keys: &[EcPoint]) -> Vec<Vec<u8>> {
+    let trees: Vec<Vec<u8>> = keys
+        .iter()
+        .map(|pk| {
+            Address::P2Pk(ProveDlog::from(pk.clone()))
+                .script()
+                .unwrap()
+                .sigma_serialize_bytes()
+                .unwrap()
+        })
+        .collect();
+    (0..BOXES).map(|i| trees[i % trees.len()].clone()).collect()
+}
+
+fn p2pk_scan_benchmark(c: &mut Criterion) {
+    let keys = derive_generators(b"box_scan", KEYS);
+    let own_keys = &keys[..OWN_KEYS];
+    let trees = p2pk_tree_bytes(&keys);
+    let encoded_keys: Vec<&[u8]> = trees
+        .iter()
+        .map(|t| &t[CompressedPointSet::P2PK_TREE_PREFIX.len()..])
+        .collect();
+    let own_set = CompressedPointSet::new(own_keys);
+    let expected = BOXES / KEYS as usize * OWN_KEYS;
+
+    let mut group = c.benchmark_group("p2pk_scan_1m");
+    group.throughput(Throughput::Elements(BOXES as u64));
+    group.sample_size(10);
+    group.bench_function("decompress_each", |b| {
+        b.iter(|| {
+            let found = encoded_keys
+                .iter()
+                .filter(|bytes| own_keys.contains(&EcPoint::sigma_parse_bytes(bytes).unwrap()))
+                .count();
+            assert_eq!(found, expected);
+        })
+    });
+    group.bench_function("parse_many", |b| {
+        b.iter(|| {
+            let found = EcPoint::parse_many(&encoded_keys)
+                .into_iter()
+                .filter(|p| own_keys.contains(p.as_ref().unwrap()))
+                .count();
+            assert_eq!(found, expected);
+        })
+    });
+    group.bench_function("compressed_match", |b| {
+        b.iter(|| {
+            let found = trees
+                .iter()
+                .filter(|t| own_set.contains_p2pk_tree(t))
+                .count();
+            assert_eq!(found, expected);
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, p2pk_scan_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,144 @@
+//! Base16/Base58 encoding and decoding of 1M box ids, addresses and register values (indexer
+//! workloads).
+//!
+//! Run with `--features simd` to compare the SIMD-accelerated Base16 codec.
+
+use std::convert::TryFrom;
+
+use criterion::criterion_group;
+use criterion::criterion_main;
+use criterion::measurement::WallTime;
+use criterion::BenchmarkGroup;
+use criterion::Criterion;
+use criterion::Throughput;
+use ergotree_ir::chain::address::Address;
+use ergotree_ir::chain::address::AddressEncoder;
+use ergotree_ir::chain::address::NetworkPrefix;
+use ergotree_ir::chain::base16_bytes::Base16DecodedBytes;
+use ergotree_ir::chain::base16_bytes::Base16EncodedBytes;
+use ergotree_ir::chain::digest32::Digest32;
+use ergotree_ir::chain::ergo_box::BoxId;
+use ergotree_ir::mir::constant::Constant;
+use ergotree_ir::serialization::SigmaSerializable;
+use rand::rngs::StdRng;
+use rand::Rng;
+use rand::SeedableRng;
+
+const ITEMS: usize = 1_000_000;
+
+fn group<'a>(c: &'a mut Criterion, name: &str) -> BenchmarkGroup<'a, WallTime> {
+    let mut group = c.benchmark_group(name);
+    group.throughput(Throughput::Elements(ITEMS as u64));
+    group.sample_size(10);
+    group
+}
+
+fn box_ids(rng: &mut StdRng) -> Vec<BoxId> {
+    (0..ITEMS)
+        .map(|_| BoxId::from(Digest32::from(rng.gen::<[u8; 32]>())))
+        .collect()
+}
+
+// P2S addresses of the P2PK tree size (36 bytes), so that the encoding (with the checksum) is
+// measured and not the point decoding of P2PK
+fn addresses(rng: &mut StdRng) -> Vec<Address> {
+    (0..ITEMS)
+        .map(|_| Address::P2S((0..36).map(|_| rng.gen()).collect()))
+        .collect()
+}
+
+fn register_values(rng: &mut StdRng) -> Vec<Vec<u8>> {
+    (0..ITEMS)
+        .map(|_| {
+            Constant::from(rng.gen::<i64>())
+                .sigma_serialize_bytes()
+                .unwrap()
+        })
+        .collect()
+}
+
+fn box_id_benchmark(c: &mut Criterion) {
+    let ids = box_ids(&mut StdRng::seed_from_u64(1));
+    let encoded: Vec<String> = ids.iter().cloned().map(String::from).collect();
+    let mut group = group(c, "box_id_base16_1m");
+    group.bench_function("encode", |b| {
+        b.iter(|| {
+            ids.iter()
+                .cloned()
+                .map(String::from)
+                .collect::<Vec<String>>()
+        })
+    });
+    group.bench_function("decode", |b| {
+        b.iter(|| {
+            encoded
+                .iter()
+                .map(|s| BoxId::try_from(s.clone()).unwrap())
+                .collect::<Vec<BoxId>>()
+        })
+    });
+    group.finish();
+}
+
+fn address_benchmark(c: &mut Criterion) {
+    let encoder = AddressEncoder::new(NetworkPrefix::Mainnet);
+    let addresses = addresses(&mut StdRng::seed_from_u64(2));
+    let encoded: Vec<String> = addresses
+        .iter()
+        .map(|a| encoder.address_to_str(a))
+        .collect();
+    let mut group = group(c, "address_base58_1m");
+    group.bench_function("encode", |b| {
+        b.iter(|| {
+            addresses
+                .iter()
+                .map(|a| encoder.address_to_str(a))
+                .collect::<Vec<String>>()
+        })
+    });
+    group.bench_function("decode", |b| {
+        b.iter(|| {
+            encoded
+                .iter()
+                .map(|s| encoder.parse_address_from_str(s).unwrap())
+                .collect::<Vec<Address>>()
+        })
+    });
+    group.finish();
+}
+
+fn register_benchmark(c: &mut Criterion) {
+    let values = register_values(&mut StdRng::seed_from_u64(3));
+    let encoded: Vec<String> = values
+        .iter()
+        .map(|v| Base16EncodedBytes::new(v).into())
+        .collect();
+    let mut group = group(c, "register_base16_1m");
+    group.bench_function("encode", |b| {
+        b.iter(|| {
+            values
+                .iter()
+                .map(Base16EncodedBytes::new)
+                .collect::<Vec<Base16EncodedBytes>>()
+        })
+    });
+    group.bench_function("decode", |b| {
+        b.iter(|| {
+            encoded
+                .iter()
+                .map(|s| {
+                    Constant::try_from(Base16DecodedBytes::try_from(s.as_str()).unwrap()).unwrap()
+                })
+                .collect::<Vec<Constant>>()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    box_id_benchmark,
+    address_benchmark,
+    register_benchmark
+);
+criterion_main!(benches);
@@ -8,6 +8,7 @@ pub mod ergo_box;
 pub mod header;
 #[cfg(feature = "json")]
 pub mod json;
+pub mod merkle;
 pub mod preheader;
 pub mod token;
 pub mod tx_id;
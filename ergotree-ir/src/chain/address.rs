@@ -23,6 +23,7 @@ use crate::sigma_protocol::sigma_boolean::ProveDlog;
 use crate::sigma_protocol::sigma_boolean::SigmaBoolean;
 use crate::sigma_protocol::sigma_boolean::SigmaProofOfKnowledgeTree;
 use crate::sigma_protocol::sigma_boolean::SigmaProp;
+use crate::source_span::Spanned;
 use crate::types::stype::SType;
 
 use crate::util::AsVecU8;
@@ -110,8 +111,14 @@ impl Address {
                     if let [Expr::BoolToSigmaProp(BoolToSigmaProp { input }), Expr::DeserializeContext(DeserializeContext { tpe, id })] =
                         items.as_slice()
                     {
-                        if let (Expr::BinOp(BinOp { kind, left, right }), SType::SSigmaProp, 1) =
-                            (*input.clone(), tpe.clone(), id)
+                        if let (
+                            Expr::BinOp(Spanned {
+                                expr: BinOp { kind, left, right },
+                                ..
+                            }),
+                            SType::SSigmaProp,
+                            1,
+                        ) = (*input.clone(), tpe.clone(), id)
                         {
                             if let (
                                 Relation(RelationOp::Eq),
@@ -212,11 +219,12 @@ impl Address {
                     from: Box::new(0i32.into()),
                     until: Box::new(24i32.into()),
                 });
-                let hash_equals = Expr::BinOp(BinOp {
+                let hash_equals: Expr = BinOp {
                     kind: Relation(RelationOp::Eq),
                     left: Box::new(slice_expr),
                     right: Box::new(Expr::Const(Constant::from(script_hash.to_vec()))),
-                });
+                }
+                .into();
                 let script_is_correct = Expr::DeserializeContext(DeserializeContext {
                     tpe: SType::SSigmaProp,
                     id: 1,
@@ -235,6 +243,26 @@ impl Address {
             }
         }
     }
+
+    /// Check that the serialized ErgoTree (e.g. box proposition bytes) corresponds to this address.
+    /// P2S address content is compared with the tree bytes as is, for P2PK and P2SH the address is
+    /// re-created from the parsed tree (see [`Address::recreate_from_ergo_tree`]).
+    pub fn validate_against_tree_bytes(&self, tree_bytes: &[u8]) -> Result<(), AddressError> {
+        let matches = match self {
+            Address::P2S(bytes) => bytes.as_slice() == tree_bytes,
+            Address::P2Pk(_) | Address::P2SH(_) => match ErgoTree::sigma_parse_bytes(tree_bytes) {
+                Ok(tree) => &Address::recreate_from_ergo_tree(&tree)? == self,
+                Err(_) => false,
+            },
+        };
+        if matches {
+            Ok(())
+        } else {
+            Err(AddressError::TreeMismatch(sigma_util::hex::encode_lower(
+                tree_bytes,
+            )))
+        }
+    }
 }
 
 /// Combination of an Address with a network
@@ -279,6 +307,9 @@ pub enum AddressError {
     /// ErgoTree parsing error
     #[error("ErgoTree error: {0}")]
     ErgoTreeError(#[from] ErgoTreeError),
+    /// Address does not correspond to the ErgoTree
+    #[error("address does not correspond to the ErgoTree bytes: {0}")]
+    TreeMismatch(String),
 }
 
 impl From<SigmaSerializationError> for AddressError {
@@ -316,6 +347,8 @@ pub enum NetworkPrefix {
     Mainnet = 0,
     /// Testnet
     Testnet = 16,
+    /// Devnet (local development network)
+    Devnet = 32,
 }
 
 impl TryFrom<u8> for NetworkPrefix {
@@ -324,6 +357,7 @@ impl TryFrom<u8> for NetworkPrefix {
         match value {
             v if v == NetworkPrefix::Mainnet as u8 => Ok(NetworkPrefix::Mainnet),
             v if v == NetworkPrefix::Testnet as u8 => Ok(NetworkPrefix::Testnet),
+            v if v == NetworkPrefix::Devnet as u8 => Ok(NetworkPrefix::Devnet),
             _v => Err(AddressEncoderError::InvalidNetwork(
                 "Invalid network".to_string(),
             )),
@@ -387,38 +421,21 @@ impl AddressEncoder {
         AddressEncoder { network_prefix }
     }
 
-    fn is_mainnet_address(head_byte: u8) -> bool {
-        head_byte < NetworkPrefix::Testnet as u8
-    }
-
-    fn is_testnet_address(head_byte: u8) -> bool {
-        head_byte > NetworkPrefix::Testnet as u8
-    }
-
     fn check_head_byte(&self, adr_prefix: u8) -> Result<u8, AddressEncoderError> {
-        match self.network_prefix {
-            NetworkPrefix::Mainnet if AddressEncoder::is_testnet_address(adr_prefix) => {
-                Err(AddressEncoderError::InvalidNetwork(
-                    "Expected mainnet address, found testnet".to_string(),
-                ))
-            }
-            NetworkPrefix::Testnet if AddressEncoder::is_mainnet_address(adr_prefix) => {
-                Err(AddressEncoderError::InvalidNetwork(
-                    "Expected testnet address, found mainnet".to_string(),
-                ))
-            }
-            _ => Ok(adr_prefix),
+        let network_byte = adr_prefix & 0xF0;
+        if network_byte != self.network_prefix as u8 {
+            return Err(AddressEncoderError::InvalidNetwork(format!(
+                "Expected {:?} address, found network prefix {}",
+                self.network_prefix, network_byte
+            )));
         }
+        Ok(adr_prefix)
     }
 
     fn calc_checksum(bytes: &[u8]) -> [u8; AddressEncoder::CHECKSUM_LENGTH] {
-        let v: Vec<u8> = blake2b256_hash(bytes)
-            .to_vec()
-            .into_iter()
-            .take(AddressEncoder::CHECKSUM_LENGTH)
-            .collect();
-        #[allow(clippy::unwrap_used)]
-        v.as_slice().try_into().unwrap()
+        let mut checksum = [0; AddressEncoder::CHECKSUM_LENGTH];
+        checksum.copy_from_slice(&blake2b256_hash(bytes)[..AddressEncoder::CHECKSUM_LENGTH]);
+        checksum
     }
 
     /// parse address from Base58 encoded string
@@ -496,11 +513,13 @@ impl AddressEncoder {
     /// encode address as Base58 encoded string
     pub fn encode_address_as_bytes(network_prefix: NetworkPrefix, address: &Address) -> Vec<u8> {
         let prefix_byte = network_prefix as u8 + address.address_type_prefix() as u8;
-        let mut address_bytes = address.content_bytes();
-        let mut bytes = vec![prefix_byte];
-        bytes.append(&mut address_bytes);
-        let mut calculated_checksum = AddressEncoder::calc_checksum(&bytes[..]).to_vec();
-        bytes.append(&mut calculated_checksum);
+        let address_bytes = address.content_bytes();
+        let mut bytes =
+            Vec::with_capacity(1 + address_bytes.len() + AddressEncoder::CHECKSUM_LENGTH);
+        bytes.push(prefix_byte);
+        bytes.extend_from_slice(&address_bytes);
+        let calculated_checksum = AddressEncoder::calc_checksum(&bytes[..]);
+        bytes.extend_from_slice(&calculated_checksum);
         bytes
     }
 
@@ -549,6 +568,7 @@ pub(crate) mod arbitrary {
 mod tests {
 
     use super::*;
+    use proptest::collection::vec;
     use proptest::prelude::*;
 
     proptest! {
@@ -568,10 +588,53 @@ mod tests {
             prop_assert_eq![recreated, v];
         }
 
+        #[test]
+        fn validate_against_tree_bytes(v in any::<Address>(), other in any::<Address>()) {
+            let tree_bytes = v.script().unwrap().sigma_serialize_bytes().unwrap();
+            prop_assert![v.validate_against_tree_bytes(&tree_bytes).is_ok()];
+            let other_tree_bytes = other.script().unwrap().sigma_serialize_bytes().unwrap();
+            if other_tree_bytes != tree_bytes {
+                prop_assert_eq![
+                    v.validate_against_tree_bytes(&other_tree_bytes),
+                    Err(AddressError::TreeMismatch(base16::encode_lower(&other_tree_bytes)))
+                ];
+            }
+        }
+
+        #[test]
+        fn network_mismatch(v in any::<Address>()) {
+            let encoded_addr = AddressEncoder::new(NetworkPrefix::Devnet).address_to_str(&v);
+            prop_assert![AddressEncoder::new(NetworkPrefix::Devnet)
+                .parse_address_from_str(&encoded_addr)
+                .is_ok()];
+            for network in [NetworkPrefix::Mainnet, NetworkPrefix::Testnet] {
+                prop_assert![matches!(
+                    AddressEncoder::new(network).parse_address_from_str(&encoded_addr),
+                    Err(AddressEncoderError::InvalidNetwork(_))
+                )];
+            }
+            let network_addr =
+                AddressEncoder::unchecked_parse_network_address_from_str(&encoded_addr).unwrap();
+            prop_assert_eq![network_addr.network(), NetworkPrefix::Devnet];
+        }
+
         #[test]
         fn doesnt_crash_on_invalid_input(s in "\\w+") {
             let encoder = AddressEncoder::new(NetworkPrefix::Testnet);
             prop_assert![encoder.parse_address_from_str(&s).is_err()];
         }
+
+        #[test]
+        fn doesnt_crash_on_valid_checksum(head in any::<u8>(), content in vec(any::<u8>(), 0..64)) {
+            // random content passes the checksum check and gets to the content parsing
+            let mut bytes = vec![head];
+            bytes.extend(content);
+            let checksum = AddressEncoder::calc_checksum(&bytes);
+            bytes.extend_from_slice(&checksum);
+            let _ = AddressEncoder::unchecked_parse_address_from_bytes(&bytes);
+            let _ = AddressEncoder::unchecked_parse_network_address_from_bytes(&bytes);
+            let _ = AddressEncoder::new(NetworkPrefix::Mainnet)
+                .parse_address_from_str(&bs58::encode(&bytes).into_string());
+        }
     }
 }
@@ -21,7 +21,7 @@ pub struct Base16EncodedBytes(String);
 impl Base16EncodedBytes {
     /// Create from byte array ref (&[u8])
     pub fn new<T: ?Sized + AsRef<[u8]>>(input: &T) -> Base16EncodedBytes {
-        Base16EncodedBytes(base16::encode_lower(input))
+        Base16EncodedBytes(sigma_util::hex::encode_lower(input))
     }
 }
 
@@ -34,20 +34,20 @@ pub struct Base16DecodedBytes(pub Vec<u8>);
 impl TryFrom<String> for Base16DecodedBytes {
     type Error = base16::DecodeError;
     fn try_from(str: String) -> Result<Self, Self::Error> {
-        Ok(Base16DecodedBytes(base16::decode(&str)?))
+        Ok(Base16DecodedBytes(sigma_util::hex::decode(&str)?))
     }
 }
 
 impl From<Base16DecodedBytes> for String {
     fn from(b: Base16DecodedBytes) -> Self {
-        base16::encode_lower(&b.0)
+        sigma_util::hex::encode_lower(&b.0)
     }
 }
 
 impl TryFrom<&str> for Base16DecodedBytes {
     type Error = base16::DecodeError;
     fn try_from(v: &str) -> Result<Self, Self::Error> {
-        Base16DecodedBytes::try_from(v.to_string())
+        Ok(Base16DecodedBytes(sigma_util::hex::decode(v)?))
     }
 }
 
@@ -42,7 +42,7 @@ impl<const N: usize> Digest<N> {
 
 impl<const N: usize> std::fmt::Debug for Digest<N> {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        base16::encode_lower(&(*self.0)).fmt(f)
+        sigma_util::hex::encode_lower(&(*self.0)).fmt(f)
     }
 }
 
@@ -77,7 +77,7 @@ impl<const N: usize> From<Digest<N>> for [u8; N] {
 
 impl<const N: usize> From<Digest<N>> for String {
     fn from(v: Digest<N>) -> Self {
-        base16::encode_lower(&v.0.as_ref())
+        sigma_util::hex::encode_lower(&v.0.as_ref())
     }
 }
 
@@ -85,7 +85,7 @@ impl<const N: usize> TryFrom<String> for Digest<N> {
     type Error = Digest32Error;
 
     fn try_from(value: String) -> Result<Self, Self::Error> {
-        let bytes = base16::decode(&value)?;
+        let bytes = sigma_util::hex::decode(&value)?;
         let arr: [u8; N] = bytes.as_slice().try_into()?;
         Ok(Digest(Box::new(arr)))
     }
@@ -3,29 +3,35 @@
 mod box_id;
 pub mod box_value;
 mod register;
+mod rendered_register;
 
 use crate::ergo_tree::ErgoTree;
 use crate::mir::constant::Constant;
 use crate::serialization::sigma_byte_reader::SigmaByteRead;
 use crate::serialization::sigma_byte_writer::SigmaByteWrite;
+use crate::serialization::sigma_byte_writer::SigmaByteWriter;
 use crate::serialization::SigmaParsingError;
 use crate::serialization::SigmaSerializable;
 use crate::serialization::SigmaSerializationError;
 use crate::serialization::SigmaSerializeResult;
-use crate::util::AsVecI8;
+use crate::util::IntoVecI8;
 
 pub use box_id::*;
 pub use register::*;
+pub use rendered_register::*;
 
 use bounded_vec::BoundedVec;
 use indexmap::IndexSet;
-use sigma_util::hash::blake2b256_hash;
+use sigma_ser::vlq_encode::WriteSigmaVlqExt;
+use sigma_util::hash::Blake2b256Writer;
 use std::convert::TryFrom;
 
 use std::convert::TryInto;
 
 use self::box_value::BoxValue;
 
+use super::address::Address;
+use super::address::AddressError;
 use super::digest32::Digest32;
 use super::token::Token;
 use super::token::TokenId;
@@ -130,8 +136,8 @@ impl ErgoBox {
         transaction_id: TxId,
         index: u16,
     ) -> Result<ErgoBox, SigmaSerializationError> {
-        let box_with_zero_id = ErgoBox {
-            box_id: BoxId::zero(),
+        Ok(ErgoBox {
+            box_id: box_candidate.box_id_with(&transaction_id, index)?,
             value: box_candidate.value,
             ergo_tree: box_candidate.ergo_tree.clone(),
             tokens: box_candidate.tokens.clone(),
@@ -139,35 +145,71 @@ impl ErgoBox {
             creation_height: box_candidate.creation_height,
             transaction_id,
             index,
-        };
-        let box_id = box_with_zero_id.calc_box_id()?;
-        Ok(ErgoBox {
-            box_id,
-            ..box_with_zero_id
         })
     }
 
     pub(crate) fn calc_box_id(&self) -> Result<BoxId, SigmaSerializationError> {
-        let bytes = self.sigma_serialize_bytes()?;
-        let hash = blake2b256_hash(&bytes);
-        Ok(Digest32::from(*hash).into())
+        let mut hasher = Blake2b256Writer::new();
+        self.sigma_serialize(&mut SigmaByteWriter::new(&mut hasher, None))?;
+        Ok(Digest32::from(*hasher.finalize()).into())
     }
 
-    /// Get register value
+    /// Get register value, None if the register value is not parsed (see
+    /// [`ErgoBox::try_get_register`])
     pub fn get_register(&self, id: RegisterId) -> Option<Constant> {
         match id {
             RegisterId::MandatoryRegisterId(id) => match id {
-                MandatoryRegisterId::R0 => Some(self.value.into()),
+                MandatoryRegisterId::R0 => Some(self.value_register()),
                 // chance of box script is not serializable are tiny comparing to returning Result
                 #[allow(clippy::unwrap_used)]
-                MandatoryRegisterId::R1 => Some(self.script_bytes().unwrap().into()),
-                MandatoryRegisterId::R2 => Some(self.tokens_raw().into()),
-                MandatoryRegisterId::R3 => Some(self.creation_info().into()),
+                MandatoryRegisterId::R1 => Some(self.script_register().unwrap()),
+                MandatoryRegisterId::R2 => Some(self.tokens_register()),
+                MandatoryRegisterId::R3 => Some(self.creation_info_register()),
             },
             RegisterId::NonMandatoryRegisterId(id) => self.additional_registers.get(id).cloned(),
         }
     }
 
+    /// Get register value, fails if the register holds the bytes which could not be parsed (see
+    /// [`RegisterValue::Raw`])
+    pub fn try_get_register(
+        &self,
+        id: RegisterId,
+    ) -> Result<Option<Constant>, UnparsedRegisterError> {
+        match id {
+            RegisterId::MandatoryRegisterId(_) => Ok(self.get_register(id)),
+            RegisterId::NonMandatoryRegisterId(id) => {
+                Ok(self.additional_registers.try_get(id)?.cloned())
+            }
+        }
+    }
+
+    /// Get register value by register index (0-9)
+    pub fn get_register_any(&self, id: i8) -> Result<Option<Constant>, RegisterIdOutOfBounds> {
+        Ok(self.get_register(RegisterId::try_from(id)?))
+    }
+
+    /// R0 register value (box value in nanoERGs, `Long`)
+    pub fn value_register(&self) -> Constant {
+        self.value.into()
+    }
+
+    /// R1 register value (serialized ErgoTree, `Coll[Byte]`)
+    pub fn script_register(&self) -> Result<Constant, SigmaSerializationError> {
+        Ok(self.script_bytes()?.into())
+    }
+
+    /// R2 register value (tokens, `Coll[(Coll[Byte], Long)]`)
+    pub fn tokens_register(&self) -> Constant {
+        self.tokens_raw().into()
+    }
+
+    /// R3 register value (creation height and transaction id with box index,
+    /// `(Int, Coll[Byte])`), see [`ErgoBox::creation_info`]
+    pub fn creation_info_register(&self) -> Constant {
+        self.creation_info().into()
+    }
+
     /// Returns tokens as tuple of byte array and amount as primitive types
     pub fn tokens_raw(&self) -> Vec<(Vec<i8>, i64)> {
         self.tokens
@@ -180,7 +222,7 @@ impl ErgoBox {
 
     /// Returns serialized ergo_tree guarding this box
     pub fn script_bytes(&self) -> Result<Vec<i8>, SigmaSerializationError> {
-        Ok(self.ergo_tree.sigma_serialize_bytes()?.as_vec_i8())
+        Ok(self.ergo_tree.sigma_serialize_bytes()?.into_vec_i8())
     }
 
     /// Tuple of height when block got included into the blockchain and transaction identifier with
@@ -189,13 +231,19 @@ impl ErgoBox {
         let mut bytes = Vec::with_capacity(Digest32::SIZE + 2);
         bytes.extend_from_slice(self.transaction_id.0 .0.as_ref());
         bytes.extend_from_slice(&self.index.to_be_bytes());
-        (self.creation_height as i32, bytes.as_vec_i8())
+        (self.creation_height as i32, bytes.into_vec_i8())
     }
 
     /// Returns serialized ErgoBox without tx_id and index
     pub fn bytes_without_ref(&self) -> Result<Vec<i8>, SigmaSerializationError> {
         let candidate: ErgoBoxCandidate = self.clone().into();
-        Ok(candidate.sigma_serialize_bytes()?.as_vec_i8())
+        Ok(candidate.sigma_serialize_bytes()?.into_vec_i8())
+    }
+
+    /// Check that the box is protected by the given address (see
+    /// [`Address::validate_against_tree_bytes`])
+    pub fn validate_address(&self, address: &Address) -> Result<(), AddressError> {
+        address.validate_against_tree_bytes(&self.ergo_tree.sigma_serialize_bytes()?)
     }
 }
 
@@ -291,11 +339,7 @@ impl ErgoBoxCandidate {
         token_ids_in_tx: Option<&IndexSet<TokenId>>,
         w: &mut W,
     ) -> SigmaSerializeResult {
-        let tokens: &[Token] = self
-            .tokens
-            .as_ref()
-            .map(BoundedVec::as_ref)
-            .unwrap_or(&[]);
+        let tokens: &[Token] = self.tokens.as_ref().map(BoundedVec::as_ref).unwrap_or(&[]);
         serialize_box_with_indexed_digests(
             &self.value,
             self.ergo_tree.sigma_serialize_bytes()?,
@@ -307,6 +351,22 @@ impl ErgoBoxCandidate {
         )
     }
 
+    /// Id of the box created from this candidate by the transaction with the given id at the given
+    /// output index (the same as [`ErgoBox::box_id`] of [`ErgoBox::from_box_candidate`]).
+    /// The box bytes are hashed as they are serialized, without building the box.
+    pub fn box_id_with(
+        &self,
+        transaction_id: &TxId,
+        index: u16,
+    ) -> Result<BoxId, SigmaSerializationError> {
+        let mut hasher = Blake2b256Writer::new();
+        let mut w = SigmaByteWriter::new(&mut hasher, None);
+        self.serialize_body_with_indexed_digests(None, &mut w)?;
+        transaction_id.sigma_serialize(&mut w)?;
+        w.put_u16(index)?;
+        Ok(Digest32::from(*hasher.finalize()).into())
+    }
+
     /// Box deserialization with token ids optionally parsed in transaction
     pub fn parse_body_with_indexed_digests<R: SigmaByteRead>(
         digests_in_tx: Option<&IndexSet<TokenId>>,
@@ -491,9 +551,25 @@ pub mod arbitrary {
 mod tests {
     use super::*;
     use crate::serialization::sigma_serialize_roundtrip;
+    use crate::sigma_protocol::sigma_boolean::ProveDlog;
     use proptest::prelude::*;
     use sigma_test_util::force_any_val;
 
+    #[test]
+    fn validate_address() {
+        let address = Address::P2Pk(force_any_val::<ProveDlog>());
+        let b = ErgoBox {
+            ergo_tree: address.script().unwrap(),
+            ..force_any_val::<ErgoBox>()
+        };
+        assert!(b.validate_address(&address).is_ok());
+        let other_address = Address::P2Pk(force_any_val::<ProveDlog>());
+        assert!(matches!(
+            b.validate_address(&other_address),
+            Err(AddressError::TreeMismatch(_))
+        ));
+    }
+
     #[test]
     fn get_register_mandatory() {
         let b = force_any_val::<ErgoBox>();
@@ -510,6 +586,27 @@ mod tests {
             b.get_register(RegisterId::R3).unwrap(),
             b.creation_info().into()
         );
+        assert_eq!(b.value_register(), b.value.into());
+        assert_eq!(
+            b.script_register().unwrap(),
+            b.script_bytes().unwrap().into()
+        );
+        assert_eq!(b.tokens_register(), b.tokens_raw().into());
+        assert_eq!(b.creation_info_register(), b.creation_info().into());
+    }
+
+    #[test]
+    fn get_register_any() {
+        let b = force_any_val::<ErgoBox>();
+        for id in 0..=9i8 {
+            assert_eq!(
+                b.get_register_any(id).unwrap(),
+                b.get_register(RegisterId::try_from(id).unwrap())
+            );
+        }
+        assert_eq!(b.get_register_any(0).unwrap(), Some(b.value_register()));
+        assert_eq!(b.get_register_any(10), Err(RegisterIdOutOfBounds(10)));
+        assert_eq!(b.get_register_any(-1), Err(RegisterIdOutOfBounds(-1)));
     }
 
     #[test]
@@ -519,7 +616,7 @@ mod tests {
         let mut expected_bytes = Vec::new();
         expected_bytes.extend_from_slice(b.transaction_id.0 .0.as_ref());
         expected_bytes.extend_from_slice(&b.index.to_be_bytes());
-        assert_eq!(b.creation_info().1, expected_bytes.to_vec().as_vec_i8());
+        assert_eq!(b.creation_info().1, expected_bytes.to_vec().into_vec_i8());
     }
 
     proptest! {
@@ -533,5 +630,17 @@ mod tests {
         fn ergo_box_ser_roundtrip(v in any::<ErgoBox>()) {
             prop_assert_eq![sigma_serialize_roundtrip(&v), v];
         }
+
+        #[test]
+        fn box_id_with(
+            candidate in any::<ErgoBoxCandidate>(),
+            tx_id in any::<TxId>(),
+            index in any::<u16>()
+        ) {
+            let b = ErgoBox::from_box_candidate(&candidate, tx_id.clone(), index).unwrap();
+            let hash = sigma_util::hash::blake2b256_hash(&b.sigma_serialize_bytes().unwrap());
+            prop_assert_eq!(b.box_id(), BoxId::from(Digest32::from(*hash)));
+            prop_assert_eq!(candidate.box_id_with(&tx_id, index).unwrap(), b.box_id());
+        }
     }
 }
@@ -8,6 +8,7 @@ use crate::serialization::{
     sigma_byte_reader::SigmaByteRead, SigmaParsingError, SigmaSerializable,
 };
 use derive_more::FromStr;
+use ergo_units::NanoErg;
 use std::convert::TryFrom;
 use thiserror::Error;
 
@@ -148,6 +149,19 @@ impl From<BoxValue> for u64 {
     }
 }
 
+impl TryFrom<NanoErg> for BoxValue {
+    type Error = BoxValueError;
+    fn try_from(v: NanoErg) -> Result<Self, Self::Error> {
+        BoxValue::try_from(v.as_u64())
+    }
+}
+
+impl From<BoxValue> for NanoErg {
+    fn from(v: BoxValue) -> Self {
+        NanoErg::new(v.0)
+    }
+}
+
 impl From<BoxValue> for i64 {
     fn from(v: BoxValue) -> Self {
         // it's safe since upper bound is i64::MAX
@@ -248,6 +262,13 @@ pub mod tests {
 
     extern crate derive_more;
 
+    #[test]
+    fn test_nano_erg_conversion() {
+        let v = BoxValue::try_from(NanoErg::new(1000000)).unwrap();
+        assert_eq!(NanoErg::from(v), NanoErg::new(1000000));
+        assert!(BoxValue::try_from(NanoErg::new(0)).is_err());
+    }
+
     #[test]
     fn test_checked_add() {
         let a = BoxValue::try_from(10000000u64)
@@ -6,7 +6,9 @@ use crate::serialization::sigma_byte_reader::SigmaByteRead;
 use crate::serialization::sigma_byte_writer::SigmaByteWrite;
 use crate::serialization::SigmaParsingError;
 use crate::serialization::SigmaSerializable;
+use crate::serialization::SigmaSerializationError;
 use crate::serialization::SigmaSerializeResult;
+use std::collections::BTreeMap;
 use std::convert::TryInto;
 use std::{collections::HashMap, convert::TryFrom};
 use thiserror::Error;
@@ -63,7 +65,7 @@ impl TryFrom<u8> for RegisterId {
 }
 
 /// newtype for additional registers R4 - R9
-#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Clone, Copy)]
 #[cfg_attr(feature = "json", derive(serde::Serialize, serde::Deserialize))]
 #[cfg_attr(feature = "json", serde(into = "String", try_from = "String"))]
 #[repr(u8)]
@@ -159,17 +161,68 @@ impl TryFrom<i8> for NonMandatoryRegisterId {
 /// Error for failed parsing of the register id from string
 pub struct NonMandatoryRegisterIdParsingError();
 
+/// Value of the non-mandatory register
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum RegisterValue {
+    /// Constant parsed from the register bytes
+    Parsed(Constant),
+    /// Register bytes which could not be parsed as a constant (e.g. a constant of a type unknown
+    /// to this version), kept as is so that the box is serialized to the same bytes (and keeps
+    /// its id)
+    Raw(Vec<u8>),
+}
+
+impl RegisterValue {
+    /// Parse the register bytes, falls back to [`RegisterValue::Raw`] if the bytes are not a
+    /// constant which is serialized back to the same bytes
+    pub fn from_bytes(bytes: Vec<u8>) -> RegisterValue {
+        match Constant::sigma_parse_bytes(&bytes) {
+            Ok(c) if c.sigma_serialize_bytes().ok().as_ref() == Some(&bytes) => {
+                RegisterValue::Parsed(c)
+            }
+            _ => RegisterValue::Raw(bytes),
+        }
+    }
+
+    /// Parsed constant, None for [`RegisterValue::Raw`]
+    pub fn as_constant(&self) -> Option<&Constant> {
+        match self {
+            RegisterValue::Parsed(c) => Some(c),
+            RegisterValue::Raw(_) => None,
+        }
+    }
+
+    /// Serialized register value
+    pub fn sigma_serialize_bytes(&self) -> Result<Vec<u8>, SigmaSerializationError> {
+        match self {
+            RegisterValue::Parsed(c) => c.sigma_serialize_bytes(),
+            RegisterValue::Raw(bytes) => Ok(bytes.clone()),
+        }
+    }
+}
+
+impl From<Constant> for RegisterValue {
+    fn from(c: Constant) -> Self {
+        RegisterValue::Parsed(c)
+    }
+}
+
+/// Register holds the bytes which could not be parsed as a constant (see [`RegisterValue::Raw`])
+#[derive(Error, PartialEq, Eq, Debug, Clone)]
+#[error("register {0:?} value could not be parsed as a constant")]
+pub struct UnparsedRegisterError(pub NonMandatoryRegisterId);
+
 /// Stores non-mandatory registers for the box
 #[derive(PartialEq, Eq, Debug, Clone)]
 #[cfg_attr(feature = "json", derive(serde::Serialize, serde::Deserialize))]
 #[cfg_attr(
     feature = "json",
     serde(
-        into = "HashMap<NonMandatoryRegisterId, Base16EncodedBytes>",
+        into = "BTreeMap<NonMandatoryRegisterId, Base16EncodedBytes>",
         try_from = "HashMap<NonMandatoryRegisterId, crate::chain::json::ergo_box::ConstantHolder>"
     )
 )]
-pub struct NonMandatoryRegisters(Vec<Constant>);
+pub struct NonMandatoryRegisters(Vec<RegisterValue>);
 
 impl NonMandatoryRegisters {
     /// Maximum number of non-mandatory registers
@@ -197,23 +250,41 @@ impl NonMandatoryRegisters {
         self.0.is_empty()
     }
 
-    /// Get register value
+    /// Get register value, None if the register is empty or its value is not parsed (see
+    /// [`NonMandatoryRegisters::try_get`])
     pub fn get(&self, reg_id: NonMandatoryRegisterId) -> Option<&Constant> {
+        self.get_value(reg_id).and_then(RegisterValue::as_constant)
+    }
+
+    /// Get register value, fails if its value is not parsed
+    pub fn try_get(
+        &self,
+        reg_id: NonMandatoryRegisterId,
+    ) -> Result<Option<&Constant>, UnparsedRegisterError> {
+        match self.get_value(reg_id) {
+            Some(RegisterValue::Parsed(c)) => Ok(Some(c)),
+            Some(RegisterValue::Raw(_)) => Err(UnparsedRegisterError(reg_id)),
+            None => Ok(None),
+        }
+    }
+
+    /// Get register value (parsed or raw)
+    pub fn get_value(&self, reg_id: NonMandatoryRegisterId) -> Option<&RegisterValue> {
         self.0
             .get(reg_id as usize - NonMandatoryRegisterId::START_INDEX)
     }
 
     /// Get ordered register values (first is R4, and so on, up to R9)
-    pub fn get_ordered_values(&self) -> &Vec<Constant> {
+    pub fn get_ordered_values(&self) -> &Vec<RegisterValue> {
         &self.0
     }
 }
 
 /// Create new from ordered values (first element will be R4, and so on)
-impl TryFrom<Vec<Constant>> for NonMandatoryRegisters {
+impl TryFrom<Vec<RegisterValue>> for NonMandatoryRegisters {
     type Error = NonMandatoryRegistersError;
 
-    fn try_from(values: Vec<Constant>) -> Result<Self, Self::Error> {
+    fn try_from(values: Vec<RegisterValue>) -> Result<Self, Self::Error> {
         if values.len() > NonMandatoryRegisters::MAX_SIZE {
             Err(NonMandatoryRegistersError::InvalidSize(values.len()))
         } else {
@@ -222,22 +293,39 @@ impl TryFrom<Vec<Constant>> for NonMandatoryRegisters {
     }
 }
 
+/// Create new from ordered values (first element will be R4, and so on)
+impl TryFrom<Vec<Constant>> for NonMandatoryRegisters {
+    type Error = NonMandatoryRegistersError;
+
+    fn try_from(values: Vec<Constant>) -> Result<Self, Self::Error> {
+        NonMandatoryRegisters::try_from(
+            values
+                .into_iter()
+                .map(RegisterValue::from)
+                .collect::<Vec<RegisterValue>>(),
+        )
+    }
+}
+
 impl SigmaSerializable for NonMandatoryRegisters {
     fn sigma_serialize<W: SigmaByteWrite>(&self, w: &mut W) -> SigmaSerializeResult {
         let regs_num = self.len();
         w.put_u8(regs_num as u8)?;
 
-        self.get_ordered_values()
-            .iter()
-            .try_for_each(|c| c.sigma_serialize(w))
+        self.get_ordered_values().iter().try_for_each(|v| match v {
+            RegisterValue::Parsed(c) => c.sigma_serialize(w),
+            RegisterValue::Raw(bytes) => Ok(w.write_all(bytes)?),
+        })
     }
 
     fn sigma_parse<R: SigmaByteRead>(r: &mut R) -> Result<Self, SigmaParsingError> {
+        // the size of the register value is not known without parsing it, so there is no
+        // fallback to the raw bytes here
         let regs_num = r.get_u8()?;
         let mut additional_regs = Vec::with_capacity(regs_num as usize);
         for _ in 0..regs_num {
             let v = Constant::sigma_parse(r)?;
-            additional_regs.push(v);
+            additional_regs.push(RegisterValue::Parsed(v));
         }
         Ok(additional_regs.try_into()?)
     }
@@ -255,38 +343,62 @@ pub enum NonMandatoryRegistersError {
 }
 
 impl From<NonMandatoryRegisters> for HashMap<NonMandatoryRegisterId, Base16EncodedBytes> {
+    fn from(v: NonMandatoryRegisters) -> Self {
+        BTreeMap::<NonMandatoryRegisterId, Base16EncodedBytes>::from(v)
+            .into_iter()
+            .collect()
+    }
+}
+
+/// Registers ordered by id (e.g. for JSON encoding with a stable order of the keys)
+impl From<NonMandatoryRegisters> for BTreeMap<NonMandatoryRegisterId, Base16EncodedBytes> {
     fn from(v: NonMandatoryRegisters) -> Self {
         v.0.into_iter()
             .enumerate()
-            .map(|(i, c)| {
+            .map(|(i, v)| {
                 (
                     NonMandatoryRegisterId::get_by_zero_index(i),
                     // no way of returning an error without writing custom JSON serializer
                     #[allow(clippy::unwrap_used)]
-                    Base16EncodedBytes::new(&c.sigma_serialize_bytes().unwrap()),
+                    Base16EncodedBytes::new(&v.sigma_serialize_bytes().unwrap()),
                 )
             })
             .collect()
     }
 }
 
+/// Registers with the parsed values only, the ones with raw (unparsed) values are skipped
 impl From<NonMandatoryRegisters> for HashMap<NonMandatoryRegisterId, Constant> {
     fn from(v: NonMandatoryRegisters) -> Self {
         v.0.into_iter()
             .enumerate()
-            .map(|(i, c)| (NonMandatoryRegisterId::get_by_zero_index(i), c))
+            .filter_map(|(i, v)| match v {
+                RegisterValue::Parsed(c) => Some((NonMandatoryRegisterId::get_by_zero_index(i), c)),
+                RegisterValue::Raw(_) => None,
+            })
+            .collect()
+    }
+}
+
+impl From<NonMandatoryRegisters> for HashMap<NonMandatoryRegisterId, RegisterValue> {
+    fn from(v: NonMandatoryRegisters) -> Self {
+        v.0.into_iter()
+            .enumerate()
+            .map(|(i, v)| (NonMandatoryRegisterId::get_by_zero_index(i), v))
             .collect()
     }
 }
 
-impl TryFrom<HashMap<NonMandatoryRegisterId, Constant>> for NonMandatoryRegisters {
+impl TryFrom<HashMap<NonMandatoryRegisterId, RegisterValue>> for NonMandatoryRegisters {
     type Error = NonMandatoryRegistersError;
-    fn try_from(reg_map: HashMap<NonMandatoryRegisterId, Constant>) -> Result<Self, Self::Error> {
+    fn try_from(
+        reg_map: HashMap<NonMandatoryRegisterId, RegisterValue>,
+    ) -> Result<Self, Self::Error> {
         let regs_num = reg_map.len();
         if regs_num > NonMandatoryRegisters::MAX_SIZE {
             Err(NonMandatoryRegistersError::InvalidSize(regs_num))
         } else {
-            let mut res: Vec<Constant> = vec![];
+            let mut res: Vec<RegisterValue> = vec![];
             NonMandatoryRegisterId::REG_IDS
                 .iter()
                 .take(regs_num)
@@ -299,6 +411,17 @@ impl TryFrom<HashMap<NonMandatoryRegisterId, Constant>> for NonMandatoryRegister
     }
 }
 
+impl TryFrom<HashMap<NonMandatoryRegisterId, Constant>> for NonMandatoryRegisters {
+    type Error = NonMandatoryRegistersError;
+    fn try_from(reg_map: HashMap<NonMandatoryRegisterId, Constant>) -> Result<Self, Self::Error> {
+        let vm: HashMap<NonMandatoryRegisterId, RegisterValue> = reg_map
+            .into_iter()
+            .map(|(k, v)| (k, RegisterValue::from(v)))
+            .collect();
+        NonMandatoryRegisters::try_from(vm)
+    }
+}
+
 #[cfg(feature = "json")]
 impl TryFrom<HashMap<NonMandatoryRegisterId, crate::chain::json::ergo_box::ConstantHolder>>
     for NonMandatoryRegisters
@@ -307,9 +430,9 @@ impl TryFrom<HashMap<NonMandatoryRegisterId, crate::chain::json::ergo_box::Const
     fn try_from(
         value: HashMap<NonMandatoryRegisterId, crate::chain::json::ergo_box::ConstantHolder>,
     ) -> Result<Self, Self::Error> {
-        let cm: HashMap<NonMandatoryRegisterId, Constant> =
+        let vm: HashMap<NonMandatoryRegisterId, RegisterValue> =
             value.into_iter().map(|(k, v)| (k, v.into())).collect();
-        NonMandatoryRegisters::try_from(cm)
+        NonMandatoryRegisters::try_from(vm)
     }
 }
 
@@ -401,6 +524,25 @@ mod tests {
         fn ser_roundtrip(regs in any::<NonMandatoryRegisters>()) {
             prop_assert_eq![sigma_serialize_roundtrip(&regs), regs];
         }
+
+        #[cfg(feature = "json")]
+        #[test]
+        fn json_keys_order(regs in any::<NonMandatoryRegisters>()) {
+            let json = serde_json::to_string(&regs).unwrap();
+            let positions: Vec<usize> = (0..regs.len())
+                .map(|i| {
+                    let key = format!("\"R{}\"", NonMandatoryRegisterId::START_INDEX + i);
+                    json.find(&key).unwrap()
+                })
+                .collect();
+            prop_assert![positions.windows(2).all(|w| w[0] < w[1])];
+            // parsed into a HashMap (random iteration order) and encoded again
+            for _ in 0..5 {
+                let parsed: NonMandatoryRegisters = serde_json::from_str(&json).unwrap();
+                prop_assert_eq![&parsed, &regs];
+                prop_assert_eq![serde_json::to_string(&parsed).unwrap(), json.clone()];
+            }
+        }
     }
 
     #[test]
@@ -416,4 +558,20 @@ mod tests {
         hash_map.insert(NonMandatoryRegisterId::R6, 1i32.into());
         assert!(NonMandatoryRegisters::try_from(hash_map).is_err());
     }
+
+    #[test]
+    fn test_func_type_error() {
+        // one register of a function type (Int => Int)
+        let bytes = [1u8, 112, 4, 4];
+        assert_eq!(
+            NonMandatoryRegisters::sigma_parse_bytes(&bytes)
+                .unwrap_err()
+                .cause(),
+            &SigmaParsingError::FuncTypeCode(112)
+        );
+        assert_eq!(
+            RegisterValue::from_bytes(bytes[1..].to_vec()),
+            RegisterValue::Raw(bytes[1..].to_vec())
+        );
+    }
 }
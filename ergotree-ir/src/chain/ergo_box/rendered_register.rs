@@ -0,0 +1,239 @@
+//! Register values rendered for humans (type name and value as text), in the format of the
+//! Explorer API (`{"R4": {"serializedValue": "0500", "sigmaType": "SLong", "renderedValue": "0"}}`)
+
+use std::collections::BTreeMap;
+
+use crate::chain::base16_bytes::Base16EncodedBytes;
+use crate::mir::constant::Constant;
+use crate::mir::constant::Literal;
+use crate::mir::value::CollKind;
+use crate::mir::value::NativeColl;
+use crate::serialization::SigmaSerializable;
+use crate::serialization::SigmaSerializationError;
+use crate::types::stype::SType;
+
+use super::NonMandatoryRegisterId;
+use super::NonMandatoryRegisters;
+use super::RegisterValue;
+
+/// Register value with its type and value rendered as text
+#[derive(PartialEq, Eq, Debug, Clone)]
+#[cfg_attr(feature = "json", derive(serde::Serialize, serde::Deserialize))]
+pub struct RenderedRegister {
+    /// Serialized constant (Base16-encoded)
+    #[cfg_attr(feature = "json", serde(rename = "serializedValue"))]
+    pub serialized_value: Base16EncodedBytes,
+    /// Type name (e.g. `SInt`, `Coll[SByte]`, `(SInt,SLong)`)
+    #[cfg_attr(feature = "json", serde(rename = "sigmaType"))]
+    pub sigma_type: String,
+    /// Value (e.g. `5`, `[1,2]`, Base16-encoded bytes for `Coll[SByte]`)
+    #[cfg_attr(feature = "json", serde(rename = "renderedValue"))]
+    pub rendered_value: String,
+}
+
+impl RenderedRegister {
+    /// Render the constant
+    pub fn new(c: &Constant) -> Result<Self, SigmaSerializationError> {
+        Ok(RenderedRegister {
+            serialized_value: Base16EncodedBytes::new(&c.sigma_serialize_bytes()?),
+            sigma_type: render_type(&c.tpe),
+            rendered_value: render_value(&c.v)?,
+        })
+    }
+}
+
+impl NonMandatoryRegisters {
+    /// Registers with the rendered values (ordered by id), the ones with raw (unparsed) values are
+    /// skipped since their type is unknown
+    pub fn rendered(
+        &self,
+    ) -> Result<BTreeMap<NonMandatoryRegisterId, RenderedRegister>, SigmaSerializationError> {
+        self.get_ordered_values()
+            .iter()
+            .enumerate()
+            .filter_map(|(i, v)| match v {
+                RegisterValue::Parsed(c) => Some(
+                    RenderedRegister::new(c)
+                        .map(|r| (NonMandatoryRegisterId::get_by_zero_index(i), r)),
+                ),
+                RegisterValue::Raw(_) => None,
+            })
+            .collect()
+    }
+}
+
+/// Type name as in the Explorer API (and sigma-state)
+pub fn render_type(tpe: &SType) -> String {
+    match tpe {
+        SType::STypeVar(v) => v.as_string(),
+        SType::SAny => "SAny".to_string(),
+        SType::SBoolean => "SBoolean".to_string(),
+        SType::SByte => "SByte".to_string(),
+        SType::SShort => "SShort".to_string(),
+        SType::SInt => "SInt".to_string(),
+        SType::SLong => "SLong".to_string(),
+        SType::SBigInt => "SBigInt".to_string(),
+        SType::SGroupElement => "SGroupElement".to_string(),
+        SType::SSigmaProp => "SSigmaProp".to_string(),
+        SType::SBox => "SBox".to_string(),
+        SType::SAvlTree => "SAvlTree".to_string(),
+        SType::SOption(t) => format!("Option[{}]", render_type(t)),
+        SType::SColl(t) => format!("Coll[{}]", render_type(t)),
+        SType::STuple(t) => format!(
+            "({})",
+            t.items
+                .iter()
+                .map(render_type)
+                .collect::<Vec<_>>()
+                .join(",")
+        ),
+        SType::SFunc(f) => format!(
+            "({}) => {}",
+            f.t_dom
+                .iter()
+                .map(render_type)
+                .collect::<Vec<_>>()
+                .join(","),
+            render_type(&f.t_range)
+        ),
+        SType::SContext => "SContext".to_string(),
+        SType::SHeader => "SHeader".to_string(),
+        SType::SPreHeader => "SPreHeader".to_string(),
+        SType::SGlobal => "SGlobal".to_string(),
+    }
+}
+
+/// Value as in the Explorer API: numbers and booleans as is, byte collections and the values of
+/// the other types as their serialized bytes (Base16-encoded), collections and tuples as `[a,b]`
+pub fn render_value(v: &Literal) -> Result<String, SigmaSerializationError> {
+    Ok(match v {
+        Literal::Boolean(b) => b.to_string(),
+        Literal::Byte(b) => b.to_string(),
+        Literal::Short(s) => s.to_string(),
+        Literal::Int(i) => i.to_string(),
+        Literal::Long(l) => l.to_string(),
+        Literal::BigInt(b) => b.to_string(),
+        Literal::SigmaProp(sp) => encode(sp.value().sigma_serialize_bytes()?),
+        Literal::GroupElement(ge) => encode(ge.sigma_serialize_bytes()?),
+        Literal::AvlTree(t) => encode(t.sigma_serialize_bytes()?),
+        Literal::CBox(b) => encode(b.sigma_serialize_bytes()?),
+        Literal::Coll(CollKind::NativeColl(NativeColl::CollByte(bytes))) => {
+            sigma_util::hex::encode_lower(
+                bytes
+                    .iter()
+                    .map(|b| *b as u8)
+                    .collect::<Vec<u8>>()
+                    .as_slice(),
+            )
+        }
+        Literal::Coll(CollKind::WrappedColl { items, .. }) => render_items(items.iter())?,
+        Literal::Opt(opt) => match opt.as_ref() {
+            Some(v) => format!("Some({})", render_value(v)?),
+            None => "None".to_string(),
+        },
+        Literal::Tup(items) => render_items(items.iter())?,
+    })
+}
+
+fn render_items<'a, I: Iterator<Item = &'a Literal>>(
+    items: I,
+) -> Result<String, SigmaSerializationError> {
+    Ok(format!(
+        "[{}]",
+        items
+            .map(render_value)
+            .collect::<Result<Vec<String>, _>>()?
+            .join(",")
+    ))
+}
+
+fn encode(bytes: Vec<u8>) -> String {
+    sigma_util::hex::encode_lower(bytes.as_slice())
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::convert::TryFrom;
+
+    use super::*;
+    use crate::bigint256::BigInt256;
+
+    fn rendered(c: Constant) -> (String, String) {
+        let r = RenderedRegister::new(&c).unwrap();
+        (r.sigma_type, r.rendered_value)
+    }
+
+    #[test]
+    fn render_primitives() {
+        assert_eq!(rendered(5i32.into()), ("SInt".into(), "5".into()));
+        assert_eq!(rendered(0i64.into()), ("SLong".into(), "0".into()));
+        assert_eq!(rendered((-3i16).into()), ("SShort".into(), "-3".into()));
+        assert_eq!(rendered(true.into()), ("SBoolean".into(), "true".into()));
+        assert_eq!(
+            rendered(BigInt256::from(7i64).into()),
+            ("SBigInt".into(), "7".into())
+        );
+    }
+
+    #[test]
+    fn render_colls_and_tuples() {
+        assert_eq!(
+            rendered(vec![1u8, 0xff].into()),
+            ("Coll[SByte]".into(), "01ff".into())
+        );
+        assert_eq!(
+            rendered(vec![1i32, -2].into()),
+            ("Coll[SInt]".into(), "[1,-2]".into())
+        );
+        assert_eq!(
+            rendered(vec![vec![1i64], vec![]].into()),
+            ("Coll[Coll[SLong]]".into(), "[[1],[]]".into())
+        );
+        assert_eq!(
+            rendered((1i32, vec![1u8, 2u8]).into()),
+            ("(SInt,Coll[SByte])".into(), "[1,0102]".into())
+        );
+        assert_eq!(
+            rendered(vec![(1i32, 2i64), (3i32, 4i64)].into()),
+            ("Coll[(SInt,SLong)]".into(), "[[1,2],[3,4]]".into())
+        );
+    }
+
+    #[test]
+    fn render_registers() {
+        let regs = NonMandatoryRegisters::try_from(vec![
+            RegisterValue::Parsed(5i32.into()),
+            RegisterValue::Raw(vec![0, 0xff]),
+            RegisterValue::Parsed(vec![1u8, 2u8].into()),
+        ])
+        .unwrap();
+        let rendered = regs.rendered().unwrap();
+        assert_eq!(
+            rendered.keys().cloned().collect::<Vec<_>>(),
+            vec![NonMandatoryRegisterId::R4, NonMandatoryRegisterId::R6]
+        );
+        assert_eq!(
+            rendered.get(&NonMandatoryRegisterId::R4).unwrap(),
+            &RenderedRegister {
+                serialized_value: Base16EncodedBytes::new(&[0x04, 0x0a]),
+                sigma_type: "SInt".to_string(),
+                rendered_value: "5".to_string(),
+            }
+        );
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn render_registers_json() {
+        let regs = NonMandatoryRegisters::try_from(vec![Constant::from(0i64)]).unwrap();
+        let json = serde_json::to_string(&regs.rendered().unwrap()).unwrap();
+        assert_eq!(
+            json,
+            r#"{"R4":{"serializedValue":"0500","sigmaType":"SLong","renderedValue":"0"}}"#
+        );
+        // parsed back as Explorer API registers
+        let parsed: NonMandatoryRegisters = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, regs);
+    }
+}
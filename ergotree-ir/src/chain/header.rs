@@ -1,67 +1,73 @@
 //! Block header
+use ergo_units::Height;
+use ergo_units::TimestampMillis;
 use num_bigint::BigInt;
+use num_bigint::Sign;
 
+use crate::serialization::sigma_byte_reader::SigmaByteRead;
+use crate::serialization::sigma_byte_writer::SigmaByteWrite;
+use crate::serialization::sigma_byte_writer::SigmaByteWriter;
+use crate::serialization::SigmaParsingError;
+use crate::serialization::SigmaSerializable;
+use crate::serialization::SigmaSerializationError;
+use crate::serialization::SigmaSerializeResult;
 use crate::sigma_protocol::dlog_group;
+use crate::sigma_protocol::dlog_group::EcPoint;
 
 use super::block_id::BlockId;
+use super::digest32::blake2b256_hash;
 use super::digest32::{ADDigest, Digest32};
 use super::preheader::PreHeader;
 use super::votes::Votes;
 
 /// Represents data of the block header available in Sigma propositions.
-#[cfg_attr(feature = "json", derive(serde::Serialize, serde::Deserialize))]
+/// The JSON encoding is in the node format (see [`Header::to_json_value`] for the explorer one),
+/// parsing accepts both.
+#[cfg_attr(feature = "json", derive(serde::Deserialize))]
+#[cfg_attr(
+    feature = "json",
+    serde(try_from = "crate::chain::json::header::HeaderJson")
+)]
 #[derive(PartialEq, Eq, Debug, Clone)]
 pub struct Header {
     /// Block version, to be increased on every soft and hardfork.
-    #[cfg_attr(feature = "json", serde(rename = "version"))]
     pub version: u8,
     /// Bytes representation of ModifierId of this Header
-    #[cfg_attr(feature = "json", serde(rename = "id"))]
     pub id: BlockId,
     /// Bytes representation of ModifierId of the parent block
-    #[cfg_attr(feature = "json", serde(rename = "parentId"))]
     pub parent_id: BlockId,
     /// Hash of ADProofs for transactions in a block
-    #[cfg_attr(feature = "json", serde(rename = "adProofsRoot"))]
     pub ad_proofs_root: Digest32,
     /// AvlTree of a state after block application
-    #[cfg_attr(feature = "json", serde(rename = "stateRoot"))]
     pub state_root: ADDigest,
     /// Root hash (for a Merkle tree) of transactions in a block.
-    #[cfg_attr(feature = "json", serde(rename = "transactionsRoot"))]
     pub transaction_root: Digest32,
     /// Timestamp of a block in ms from UNIX epoch
-    #[cfg_attr(feature = "json", serde(rename = "timestamp"))]
-    pub timestamp: u64,
+    pub timestamp: TimestampMillis,
     /// Current difficulty in a compressed view.
-    #[cfg_attr(feature = "json", serde(rename = "nBits"))]
     pub n_bits: u64,
     /// Block height
-    #[cfg_attr(feature = "json", serde(rename = "height"))]
-    pub height: u32,
+    pub height: Height,
     /// Root hash of extension section
-    #[cfg_attr(feature = "json", serde(rename = "extensionHash"))]
     pub extension_root: Digest32,
     /// Public key of miner. Part of Autolykos solution.
-    #[cfg_attr(feature = "json", serde(skip_serializing, skip_deserializing))]
     pub miner_pk: Box<dlog_group::EcPoint>,
     /// One-time public key. Prevents revealing of miners secret.
-    #[cfg_attr(feature = "json", serde(skip_serializing, skip_deserializing))]
     pub pow_onetime_pk: Box<dlog_group::EcPoint>,
     /// nonce
-    #[cfg_attr(feature = "json", serde(skip_serializing, skip_deserializing))]
     pub nonce: Vec<u8>,
     /// Distance between pseudo-random number, corresponding to nonce `nonce` and a secret,
     /// corresponding to `miner_pk`. The lower `pow_distance` is, the harder it was to find this solution.
-    #[cfg_attr(feature = "json", serde(skip_serializing, skip_deserializing))]
     pub pow_distance: BigInt,
     /// Miner votes for changing system parameters.
     /// 3 bytes in accordance to Scala implementation, but will use `Vec` until further improvements
-    #[cfg_attr(feature = "json", serde(rename = "votes"))]
     pub votes: Votes,
 }
 
 impl Header {
+    /// Size of the Autolykos solution nonce in bytes
+    pub const NONCE_SIZE: usize = 8;
+
     /// Dummy instance intended for tests where actual values are not used
     pub fn dummy() -> Self {
         let empty_digest = Digest32::zero();
@@ -72,9 +78,9 @@ impl Header {
             ad_proofs_root: empty_digest.clone(),
             state_root: ADDigest::zero(),
             transaction_root: empty_digest.clone(),
-            timestamp: 0,
+            timestamp: TimestampMillis::new(0),
             n_bits: 0,
-            height: 0,
+            height: Height::new(0),
             extension_root: empty_digest,
             miner_pk: dlog_group::generator().into(),
             pow_onetime_pk: dlog_group::generator().into(),
@@ -83,6 +89,150 @@ impl Header {
             votes: Votes([0u8; 3]),
         }
     }
+
+    /// Block difficulty decoded from `n_bits` (see [`decode_compact_bits`])
+    pub fn difficulty(&self) -> BigInt {
+        decode_compact_bits(self.n_bits)
+    }
+
+    /// Serialized header fields without the Autolykos solution (the message the miner solves
+    /// the puzzle for)
+    pub fn serialize_without_pow(&self) -> Result<Vec<u8>, SigmaSerializationError> {
+        let mut data = Vec::new();
+        let mut w = SigmaByteWriter::new(&mut data, None);
+        self.serialize_without_pow_to(&mut w)?;
+        Ok(data)
+    }
+
+    fn serialize_without_pow_to<W: SigmaByteWrite>(&self, w: &mut W) -> SigmaSerializeResult {
+        w.put_u8(self.version)?;
+        self.parent_id.0.sigma_serialize(w)?;
+        self.ad_proofs_root.sigma_serialize(w)?;
+        self.transaction_root.sigma_serialize(w)?;
+        self.state_root.sigma_serialize(w)?;
+        w.put_u64(self.timestamp.as_u64())?;
+        self.extension_root.sigma_serialize(w)?;
+        // nBits is written as 4 bytes in big-endian order
+        w.write_all(&(self.n_bits as u32).to_be_bytes())?;
+        w.put_u32(self.height.as_u32())?;
+        w.write_all(&self.votes.0)?;
+        if self.version > 1 {
+            // size of the fields added in the later versions (none so far)
+            w.put_u8(0)?;
+        }
+        Ok(())
+    }
+}
+
+/// Serialization in the format of the reference implementation (`HeaderSerializer`), the block
+/// id is the Blake2b256 hash of the serialized header.
+/// For the block version 1 (Autolykos v1) the Autolykos solution includes the one-time public
+/// key and the distance, while for the later versions they are omitted (the generator and zero
+/// are used on parsing).
+impl SigmaSerializable for Header {
+    fn sigma_serialize<W: SigmaByteWrite>(&self, w: &mut W) -> SigmaSerializeResult {
+        self.serialize_without_pow_to(w)?;
+        self.miner_pk.sigma_serialize(w)?;
+        if self.version == 1 {
+            self.pow_onetime_pk.sigma_serialize(w)?;
+            w.write_all(&self.nonce)?;
+            let (_, d_bytes) = self.pow_distance.to_bytes_be();
+            w.put_u8(d_bytes.len() as u8)?;
+            w.write_all(&d_bytes)?;
+        } else {
+            w.write_all(&self.nonce)?;
+        }
+        Ok(())
+    }
+
+    fn sigma_parse<R: SigmaByteRead>(r: &mut R) -> Result<Self, SigmaParsingError> {
+        let version = r.get_u8()?;
+        let parent_id = BlockId(Digest32::sigma_parse(r)?);
+        let ad_proofs_root = Digest32::sigma_parse(r)?;
+        let transaction_root = Digest32::sigma_parse(r)?;
+        let state_root = ADDigest::sigma_parse(r)?;
+        let timestamp = TimestampMillis::new(r.get_u64()?);
+        let extension_root = Digest32::sigma_parse(r)?;
+        let mut n_bits_buf = [0u8; 4];
+        r.read_exact(&mut n_bits_buf)?;
+        let n_bits = u32::from_be_bytes(n_bits_buf) as u64;
+        let height = Height::new(r.get_u32()?);
+        let mut votes = [0u8; 3];
+        r.read_exact(&mut votes)?;
+        if version > 1 {
+            let new_fields_size = r.get_u8()?;
+            if new_fields_size > 0 {
+                return Err(SigmaParsingError::NotImplementedYet(format!(
+                    "header (version {}) with new fields of size {}",
+                    version, new_fields_size
+                )));
+            }
+        }
+        let miner_pk = Box::new(EcPoint::sigma_parse(r)?);
+        let mut nonce = vec![0u8; Header::NONCE_SIZE];
+        let (pow_onetime_pk, pow_distance) = if version == 1 {
+            let pow_onetime_pk = EcPoint::sigma_parse(r)?;
+            r.read_exact(&mut nonce)?;
+            let mut d_bytes = vec![0u8; r.get_u8()? as usize];
+            r.read_exact(&mut d_bytes)?;
+            (pow_onetime_pk, BigInt::from_bytes_be(Sign::Plus, &d_bytes))
+        } else {
+            r.read_exact(&mut nonce)?;
+            (dlog_group::generator(), BigInt::default())
+        };
+        let mut header = Header {
+            version,
+            id: BlockId(Digest32::zero()),
+            parent_id,
+            ad_proofs_root,
+            state_root,
+            transaction_root,
+            timestamp,
+            n_bits,
+            height,
+            extension_root,
+            miner_pk,
+            pow_onetime_pk: Box::new(pow_onetime_pk),
+            nonce,
+            pow_distance,
+            votes: Votes(votes),
+        };
+        let bytes = header
+            .sigma_serialize_bytes()
+            .map_err(|e| SigmaParsingError::Misc(e.to_string()))?;
+        header.id = BlockId(blake2b256_hash(&bytes));
+        Ok(header)
+    }
+}
+
+/// Decodes the difficulty from its compact representation (`nBits`), as in the reference
+/// implementation (`RequiredDifficulty.decodeCompactBits`). The highest byte is the size of the
+/// number in bytes followed by the 3 most significant bytes of the number with the sign bit.
+pub fn decode_compact_bits(n_bits: u64) -> BigInt {
+    let size = ((n_bits >> 24) & 0xFF) as usize;
+    let mut buf = vec![0u8; size];
+    if size >= 1 {
+        buf[0] = ((n_bits >> 16) & 0xFF) as u8;
+    }
+    if size >= 2 {
+        buf[1] = ((n_bits >> 8) & 0xFF) as u8;
+    }
+    if size >= 3 {
+        buf[2] = (n_bits & 0xFF) as u8;
+    }
+    match buf.first_mut() {
+        None => BigInt::default(),
+        Some(first) => {
+            let is_negative = *first & 0x80 == 0x80;
+            *first &= 0x7F;
+            let v = BigInt::from_bytes_be(Sign::Plus, &buf);
+            if is_negative {
+                -v
+            } else {
+                v
+            }
+        }
+    }
 }
 
 impl From<Header> for PreHeader {
@@ -109,7 +259,7 @@ mod arbitrary {
     use crate::chain::digest32::Digest;
     use crate::sigma_protocol::dlog_group::EcPoint;
 
-    use super::{BlockId, Header, Votes};
+    use super::{BlockId, Header, Height, TimestampMillis, Votes};
 
     impl Arbitrary for Header {
         type Parameters = ();
@@ -155,9 +305,9 @@ mod arbitrary {
                             ad_proofs_root,
                             state_root: ADDigest::zero(),
                             transaction_root,
-                            timestamp,
+                            timestamp: TimestampMillis::new(timestamp),
                             n_bits,
-                            height,
+                            height: Height::new(height),
                             extension_root,
                             miner_pk,
                             pow_onetime_pk,
@@ -177,7 +327,15 @@ mod arbitrary {
 #[allow(clippy::unwrap_used)]
 #[cfg(test)]
 mod tests {
+    use crate::chain::block_id::BlockId;
+    use crate::chain::digest32::blake2b256_hash;
+    use crate::chain::header::decode_compact_bits;
     use crate::chain::header::Header;
+    use crate::serialization::sigma_serialize_roundtrip;
+    use crate::serialization::SigmaSerializable;
+    use crate::sigma_protocol::dlog_group::EcPoint;
+    use ergo_units::Height;
+    use num_bigint::BigInt;
 
     #[test]
     fn parse_block_header() {
@@ -206,7 +364,7 @@ mod tests {
             "parentId": "6481752bace5fa5acba5d5ef7124d48826664742d46c974c98a2d60ace229a34"
         }"#;
         let header: Header = serde_json::from_str(json).unwrap();
-        assert_eq!(header.height, 471746);
+        assert_eq!(header.height, Height::new(471746));
     }
 
     #[test]
@@ -237,6 +395,64 @@ mod tests {
             "parentId": "6481752bace5fa5acba5d5ef7124d48826664742d46c974c98a2d60ace229a34"
         }"#;
         let header: Header = serde_json::from_str(json).unwrap();
-        assert_eq!(header.height, 471746);
+        assert_eq!(header.height, Height::new(471746));
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn serialized_header_hash_is_id() {
+        // block 471746, see `parse_block_header`, PoW solution fields are not in JSON
+        let json = r#"{
+            "votes": "040000",
+            "timestamp": 1618929697400,
+            "stateRoot": "8ad868627ea4f7de6e2a2fe3f98fafe57f914e0f2ef3331c006def36c697f92713",
+            "height": 471746,
+            "nBits": 117586360,
+            "version": 2,
+            "id": "4caa17e62fe66ba7bd69597afdc996ae35b1ff12e0ba90c22ff288a4de10e91b",
+            "adProofsRoot": "d882aaf42e0a95eb95fcce5c3705adf758e591532f733efe790ac3c404730c39",
+            "transactionsRoot": "63eaa9aff76a1de3d71c81e4b2d92e8d97ae572a8e9ab9e66599ed0912dd2f8b",
+            "extensionHash": "3f91f3c680beb26615fdec251aee3f81aaf5a02740806c167c0f3c929471df44",
+            "parentId": "6481752bace5fa5acba5d5ef7124d48826664742d46c974c98a2d60ace229a34"
+        }"#;
+        let mut header: Header = serde_json::from_str(json).unwrap();
+        header.miner_pk = Box::new(
+            EcPoint::sigma_parse_bytes(
+                &base16::decode(
+                    "02b3a06d6eaa8671431ba1db4dd427a77f75a5c2acbd71bfb725d38adc2b55f669",
+                )
+                .unwrap(),
+            )
+            .unwrap(),
+        );
+        header.nonce = base16::decode("5939ecfee6b0d7f4").unwrap();
+        let bytes = header.sigma_serialize_bytes().unwrap();
+        assert_eq!(blake2b256_hash(&bytes), header.id.0);
+        assert_eq!(Header::sigma_parse_bytes(&bytes).unwrap(), header);
+        assert!(bytes.starts_with(&header.serialize_without_pow().unwrap()));
+    }
+
+    #[test]
+    fn ser_roundtrip_v1() {
+        let mut header = Header::dummy();
+        header.nonce = vec![1; Header::NONCE_SIZE];
+        header.pow_distance = BigInt::from(123_456_789u64);
+        header.id = BlockId(blake2b256_hash(&header.sigma_serialize_bytes().unwrap()));
+        assert_eq!(sigma_serialize_roundtrip(&header), header);
+    }
+
+    #[test]
+    fn decode_difficulty() {
+        assert_eq!(
+            decode_compact_bits(117586360),
+            BigInt::from(626412390187008u64)
+        );
+        assert_eq!(decode_compact_bits(0x01003456), BigInt::from(0));
+        assert_eq!(decode_compact_bits(0x02123456), BigInt::from(0x1234));
+        assert_eq!(
+            decode_compact_bits(0x04923456),
+            BigInt::from(-0x12345600i64)
+        );
+        assert_eq!(decode_compact_bits(0), BigInt::from(0));
     }
 }
@@ -4,6 +4,8 @@ use serde::Serializer;
 
 pub(crate) mod ergo_box;
 pub mod ergo_tree;
+pub mod header;
+pub(crate) mod merkle;
 
 /// Serialize bytes ([u8]) as base16 encoded string
 pub fn serialize_bytes<S, T>(bytes: T, serializer: S) -> Result<S::Ok, S::Error>
@@ -11,5 +13,5 @@ where
     S: Serializer,
     T: AsRef<[u8]>,
 {
-    serializer.serialize_str(&base16::encode_lower(bytes.as_ref()))
+    serializer.serialize_str(&sigma_util::hex::encode_lower(bytes.as_ref()))
 }
@@ -3,11 +3,9 @@ use crate::chain::ergo_box::box_value::BoxValue;
 use crate::chain::ergo_box::BoxId;
 use crate::chain::ergo_box::BoxTokens;
 use crate::chain::ergo_box::NonMandatoryRegisters;
+use crate::chain::ergo_box::RegisterValue;
 use crate::chain::tx_id::TxId;
 use crate::ergo_tree::ErgoTree;
-use crate::mir::constant::Constant;
-use crate::serialization::SigmaParsingError;
-use crate::serialization::SigmaSerializable;
 use crate::serialization::SigmaSerializationError;
 use core::fmt;
 use serde::de::{self, MapAccess, Visitor};
@@ -67,7 +65,7 @@ pub enum ErgoBoxFromJsonError {
 #[derive(Deserialize, PartialEq, Eq, Debug, Clone)]
 pub struct ConstantHolder(#[serde(deserialize_with = "constant_as_string_or_struct")] RichConstant);
 
-impl From<ConstantHolder> for Constant {
+impl From<ConstantHolder> for RegisterValue {
     fn from(ch: ConstantHolder) -> Self {
         ch.0.raw_value.0
     }
@@ -81,22 +79,19 @@ struct RichConstant {
 
 #[derive(Deserialize, PartialEq, Eq, Debug, Clone)]
 #[serde(try_from = "Base16DecodedBytes")]
-struct ConstantWrapper(Constant);
+struct ConstantWrapper(RegisterValue);
 
 #[derive(Error, PartialEq, Eq, Debug, Clone, From)]
 pub enum ConstantParsingError {
     #[error("Base16 decoding error: {0}")]
     DecodeError(base16::DecodeError),
-    #[error("Deserialization error: {0}")]
-    DeserializationError(SigmaParsingError),
 }
 
 impl TryFrom<Base16DecodedBytes> for ConstantWrapper {
     type Error = ConstantParsingError;
 
     fn try_from(Base16DecodedBytes(bytes): Base16DecodedBytes) -> Result<Self, Self::Error> {
-        let c = Constant::sigma_parse_bytes(&bytes)?;
-        Ok(ConstantWrapper(c))
+        Ok(ConstantWrapper(RegisterValue::from_bytes(bytes)))
     }
 }
 
@@ -104,9 +99,8 @@ impl FromStr for RichConstant {
     type Err = ConstantParsingError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let Base16DecodedBytes(bytes) = Base16DecodedBytes::try_from(s)?;
-        let c = Constant::sigma_parse_bytes(&bytes)?;
         Ok(RichConstant {
-            raw_value: ConstantWrapper(c),
+            raw_value: ConstantWrapper(RegisterValue::from_bytes(bytes)),
         })
     }
 }
@@ -166,7 +160,11 @@ mod tests {
     use crate::chain::ergo_box::ErgoBox;
     use crate::chain::ergo_box::NonMandatoryRegisterId;
     use crate::chain::ergo_box::NonMandatoryRegisters;
+    use crate::chain::ergo_box::RegisterId;
+    use crate::chain::ergo_box::RegisterValue;
+    use crate::chain::ergo_box::UnparsedRegisterError;
     use crate::chain::token::Token;
+    use crate::serialization::SigmaSerializable;
     use pretty_assertions::assert_eq;
     use proptest::prelude::*;
 
@@ -224,6 +222,64 @@ mod tests {
         assert!(regs.is_err());
     }
 
+    #[test]
+    fn parse_registers_unparsed_value() {
+        // invalid type code, and non-canonical VLQ encoding of Long 0
+        let json = r#"
+            {"R4":"0500","R5":{"serializedValue":"00ff","sigmaType":"?","renderedValue":"?"},"R6":"058000"}
+        "#;
+        let regs: NonMandatoryRegisters = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            regs.get_value(NonMandatoryRegisterId::R4),
+            Some(&RegisterValue::Parsed(0i64.into()))
+        );
+        assert_eq!(
+            regs.get_value(NonMandatoryRegisterId::R5),
+            Some(&RegisterValue::Raw(vec![0, 0xff]))
+        );
+        assert_eq!(
+            regs.get_value(NonMandatoryRegisterId::R6),
+            Some(&RegisterValue::Raw(vec![5, 0x80, 0]))
+        );
+        assert!(regs.get(NonMandatoryRegisterId::R5).is_none());
+        assert_eq!(
+            regs.try_get(NonMandatoryRegisterId::R5),
+            Err(UnparsedRegisterError(NonMandatoryRegisterId::R5))
+        );
+        // node format, the bytes of the unparsed values are kept as is
+        assert_eq!(
+            serde_json::to_value(&regs).unwrap(),
+            serde_json::json!({"R4": "0500", "R5": "00ff", "R6": "058000"})
+        );
+    }
+
+    #[test]
+    fn ergo_box_with_unparsed_register_roundtrip() {
+        let box_json = r#"{
+          "value": 67500000000,
+          "ergoTree": "100204a00b08cd021dde34603426402615658f1d970cfa7c7bd92ac81a8b16eeebff264d59ce4604ea02d192a39a8cc7a70173007301",
+          "assets": [],
+          "creationHeight": 284761,
+          "additionalRegisters": {"R4": "00ff"},
+          "transactionId": "9148408c04c2e38a6402a7950d6157730fa7d49e9ab3b9cadec481d7769918e9",
+          "index": 1
+        }"#;
+        let b: ErgoBox = serde_json::from_str(box_json).unwrap();
+        // the register bytes are serialized as is (box id is calculated from the same bytes)
+        let bytes = b.sigma_serialize_bytes().unwrap();
+        let ergo_tree_end = bytes.len() - (1 + 2 + 32 + 1);
+        assert_eq!(&bytes[ergo_tree_end..ergo_tree_end + 3], &[1, 0, 0xff]);
+        let j = serde_json::to_string(&b).unwrap();
+        let b_parsed: ErgoBox = serde_json::from_str(&j).unwrap();
+        assert_eq!(b_parsed, b);
+        assert_eq!(
+            b.try_get_register(RegisterId::NonMandatoryRegisterId(
+                NonMandatoryRegisterId::R4
+            )),
+            Err(UnparsedRegisterError(NonMandatoryRegisterId::R4))
+        );
+    }
+
     #[test]
     fn parse_ergo_box() {
         let box_json = r#"{
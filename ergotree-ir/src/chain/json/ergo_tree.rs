@@ -25,7 +25,7 @@ where
 {
     use serde::de::Error;
     String::deserialize(deserializer)
-        .and_then(|str| base16::decode(&str).map_err(|err| Error::custom(err.to_string())))
+        .and_then(|str| sigma_util::hex::decode(&str).map_err(|err| Error::custom(err.to_string())))
         .and_then(|bytes| {
             ErgoTree::sigma_parse_bytes(&bytes).map_err(|error| Error::custom(error.to_string()))
         })
@@ -0,0 +1,344 @@
+//! Block header JSON in the formats of the node and explorer APIs
+
+use std::convert::TryFrom;
+
+use ergo_units::Height;
+use ergo_units::TimestampMillis;
+use num_bigint::BigInt;
+use serde::Deserialize;
+use serde::Serialize;
+use serde::Serializer;
+use serde_json::json;
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::chain::base16_bytes::Base16DecodedBytes;
+use crate::chain::block_id::BlockId;
+use crate::chain::digest32::blake2b256_hash;
+use crate::chain::digest32::ADDigest;
+use crate::chain::digest32::Digest32;
+use crate::chain::header::Header;
+use crate::chain::votes::Votes;
+use crate::serialization::SigmaParsingError;
+use crate::serialization::SigmaSerializable;
+use crate::sigma_protocol::dlog_group::EcPoint;
+
+/// Modifier type id of the ADProofs block section
+const AD_PROOFS_TYPE_ID: u8 = 104;
+/// Modifier type id of the BlockTransactions block section
+const TRANSACTIONS_TYPE_ID: u8 = 102;
+/// Modifier type id of the Extension block section
+const EXTENSION_TYPE_ID: u8 = 108;
+/// Number of blocks in the explorer's `epoch`
+const EPOCH_LENGTH: u32 = 1024;
+
+/// Block header JSON format of the API the header comes from or is sent to.
+/// Parsing (via `Deserialize`) accepts both formats, the dialect selects the format on encoding.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum HeaderJsonDialect {
+    /// Node REST API: votes as a hex string, difficulty as a decimal string
+    Node,
+    /// Explorer API (v1): votes as an array of bytes, difficulty as a number
+    Explorer,
+}
+
+impl Header {
+    /// Encode the header as JSON in the format of the given API, with the fields the API adds
+    /// to the header fields: the Autolykos solution (`powSolutions`), `size`, `difficulty`
+    /// (decoded from `nBits`), and the ids of the block sections and `unparsedBytes` for the
+    /// node or `epoch` for the explorer.
+    pub fn to_json_value(&self, dialect: HeaderJsonDialect) -> Result<Value, serde_json::Error> {
+        let bytes = self
+            .sigma_serialize_bytes()
+            .map_err(<serde_json::Error as serde::ser::Error>::custom)?;
+        let difficulty = self.difficulty();
+        let pow_distance = match u64::try_from(&self.pow_distance) {
+            Ok(v) if dialect == HeaderJsonDialect::Node => v.into(),
+            _ => self.pow_distance.to_string().into(),
+        };
+        let mut value = json!({
+            "version": self.version,
+            "id": hex(self.id.0 .0.as_ref()),
+            "parentId": hex(self.parent_id.0 .0.as_ref()),
+            "adProofsRoot": hex(self.ad_proofs_root.0.as_ref()),
+            "stateRoot": hex(self.state_root.0.as_ref()),
+            "transactionsRoot": hex(self.transaction_root.0.as_ref()),
+            "timestamp": self.timestamp.as_u64(),
+            "nBits": self.n_bits,
+            "height": self.height.as_u32(),
+            "extensionHash": hex(self.extension_root.0.as_ref()),
+            "powSolutions": {
+                "pk": hex(&self.miner_pk.to_compressed_bytes()),
+                "w": hex(&self.pow_onetime_pk.to_compressed_bytes()),
+                "n": hex(&self.nonce),
+                "d": pow_distance,
+            },
+            // the node and the explorer report the size of the stored block section, i.e. with
+            // the modifier type byte
+            "size": bytes.len() + 1,
+        });
+        if let Value::Object(fields) = &mut value {
+            match dialect {
+                HeaderJsonDialect::Node => {
+                    fields.insert("difficulty".to_string(), difficulty.to_string().into());
+                    fields.insert("votes".to_string(), hex(&self.votes.0).into());
+                    // fields of the later header versions, none so far
+                    fields.insert("unparsedBytes".to_string(), "".into());
+                    let sections = [
+                        ("adProofsId", AD_PROOFS_TYPE_ID, &self.ad_proofs_root),
+                        (
+                            "transactionsId",
+                            TRANSACTIONS_TYPE_ID,
+                            &self.transaction_root,
+                        ),
+                        ("extensionId", EXTENSION_TYPE_ID, &self.extension_root),
+                    ];
+                    for &(name, type_id, root) in sections.iter() {
+                        let id = self.section_id(type_id, root);
+                        fields.insert(name.to_string(), hex(id.0.as_ref()).into());
+                    }
+                }
+                HeaderJsonDialect::Explorer => {
+                    let difficulty = match u64::try_from(&difficulty) {
+                        Ok(v) => v.into(),
+                        Err(_) => difficulty.to_string().into(),
+                    };
+                    fields.insert("difficulty".to_string(), difficulty);
+                    fields.insert("votes".to_string(), self.votes.0.to_vec().into());
+                    fields.insert(
+                        "epoch".to_string(),
+                        (self.height.as_u32() / EPOCH_LENGTH).into(),
+                    );
+                }
+            }
+        }
+        Ok(value)
+    }
+
+    /// Id of the block section (as in the reference implementation's
+    /// `NonHeaderBlockSection.computeId`)
+    fn section_id(&self, type_id: u8, root: &Digest32) -> Digest32 {
+        let mut bytes = vec![type_id];
+        bytes.extend_from_slice(self.id.0 .0.as_ref());
+        bytes.extend_from_slice(root.0.as_ref());
+        blake2b256_hash(&bytes)
+    }
+}
+
+impl Serialize for Header {
+    /// Encoded in the node format (see [`Header::to_json_value`])
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_json_value(HeaderJsonDialect::Node)
+            .map_err(<S::Error as serde::ser::Error>::custom)?
+            .serialize(serializer)
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    sigma_util::hex::encode_lower(bytes)
+}
+
+/// Header fields in the node or explorer JSON (the fields computed from them, e.g. `size` or
+/// `difficulty`, are ignored)
+#[derive(Deserialize)]
+pub(crate) struct HeaderJson {
+    version: u8,
+    id: BlockId,
+    #[serde(rename = "parentId")]
+    parent_id: BlockId,
+    #[serde(rename = "adProofsRoot")]
+    ad_proofs_root: Digest32,
+    #[serde(rename = "stateRoot")]
+    state_root: ADDigest,
+    #[serde(rename = "transactionsRoot")]
+    transaction_root: Digest32,
+    timestamp: TimestampMillis,
+    #[serde(rename = "nBits")]
+    n_bits: u64,
+    height: Height,
+    #[serde(rename = "extensionHash")]
+    extension_root: Digest32,
+    #[serde(rename = "powSolutions", default)]
+    pow_solutions: Option<AutolykosSolutionJson>,
+    votes: Votes,
+}
+
+/// Autolykos solution (`powSolutions`)
+#[derive(Deserialize)]
+struct AutolykosSolutionJson {
+    pk: Base16DecodedBytes,
+    w: Base16DecodedBytes,
+    n: Base16DecodedBytes,
+    d: PowDistanceJson,
+}
+
+/// The node encodes the distance as a number, the explorer as a decimal string. Numbers which do
+/// not fit into `u64` (version 1 headers) can only be parsed as strings, since they are not
+/// exact in JSON numbers.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum PowDistanceJson {
+    Number(u64),
+    Decimal(String),
+}
+
+/// Errors on parsing the header from JSON
+#[derive(Error, PartialEq, Eq, Debug, Clone)]
+pub enum HeaderJsonError {
+    /// Invalid miner public key or one-time public key
+    #[error("Invalid powSolutions public key: {0}")]
+    InvalidPublicKey(SigmaParsingError),
+    /// Invalid distance
+    #[error("Invalid powSolutions distance: {0}")]
+    InvalidDistance(String),
+}
+
+impl TryFrom<HeaderJson> for Header {
+    type Error = HeaderJsonError;
+
+    fn try_from(json: HeaderJson) -> Result<Self, Self::Error> {
+        let (miner_pk, pow_onetime_pk, nonce, pow_distance) = match json.pow_solutions {
+            Some(s) => (
+                EcPoint::sigma_parse_bytes(&s.pk.0).map_err(HeaderJsonError::InvalidPublicKey)?,
+                EcPoint::sigma_parse_bytes(&s.w.0).map_err(HeaderJsonError::InvalidPublicKey)?,
+                s.n.0,
+                match s.d {
+                    PowDistanceJson::Number(d) => BigInt::from(d),
+                    PowDistanceJson::Decimal(d) => d
+                        .parse::<BigInt>()
+                        .map_err(|e| HeaderJsonError::InvalidDistance(e.to_string()))?,
+                },
+            ),
+            None => Default::default(),
+        };
+        Ok(Header {
+            version: json.version,
+            id: json.id,
+            parent_id: json.parent_id,
+            ad_proofs_root: json.ad_proofs_root,
+            state_root: json.state_root,
+            transaction_root: json.transaction_root,
+            timestamp: json.timestamp,
+            n_bits: json.n_bits,
+            height: json.height,
+            extension_root: json.extension_root,
+            miner_pk: Box::new(miner_pk),
+            pow_onetime_pk: Box::new(pow_onetime_pk),
+            nonce,
+            pow_distance,
+            votes: json.votes,
+        })
+    }
+}
+
+#[allow(clippy::unwrap_used)]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ergo_units::Height;
+    use pretty_assertions::assert_eq;
+
+    // captured from the node API (/blocks/{id}/header)
+    const NODE_HEADER_JSON: &str = r#"{
+        "extensionId": "d16f25b14457186df4c5f6355579cc769261ce1aebc8209949ca6feadbac5a3f",
+        "difficulty": "626412390187008",
+        "votes": "040000",
+        "timestamp": 1618929697400,
+        "size": 221,
+        "unparsedBytes": "",
+        "stateRoot": "8ad868627ea4f7de6e2a2fe3f98fafe57f914e0f2ef3331c006def36c697f92713",
+        "height": 471746,
+        "nBits": 117586360,
+        "version": 2,
+        "id": "4caa17e62fe66ba7bd69597afdc996ae35b1ff12e0ba90c22ff288a4de10e91b",
+        "adProofsRoot": "d882aaf42e0a95eb95fcce5c3705adf758e591532f733efe790ac3c404730c39",
+        "transactionsRoot": "63eaa9aff76a1de3d71c81e4b2d92e8d97ae572a8e9ab9e66599ed0912dd2f8b",
+        "extensionHash": "3f91f3c680beb26615fdec251aee3f81aaf5a02740806c167c0f3c929471df44",
+        "powSolutions": {
+          "pk": "02b3a06d6eaa8671431ba1db4dd427a77f75a5c2acbd71bfb725d38adc2b55f669",
+          "w": "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+          "n": "5939ecfee6b0d7f4",
+          "d": 0
+        },
+        "adProofsId": "86eaa41f328bee598e33e52c9e515952ad3b7874102f762847f17318a776a7ae",
+        "transactionsId": "ac80245714f25aa2fafe5494ad02a26d46e7955b8f5709f3659f1b9440797b3e",
+        "parentId": "6481752bace5fa5acba5d5ef7124d48826664742d46c974c98a2d60ace229a34"
+    }"#;
+
+    // captured from the explorer API (/api/v1/blocks/{id}, "header" field)
+    const EXPLORER_HEADER_JSON: &str = r#"{
+        "id": "4caa17e62fe66ba7bd69597afdc996ae35b1ff12e0ba90c22ff288a4de10e91b",
+        "parentId": "6481752bace5fa5acba5d5ef7124d48826664742d46c974c98a2d60ace229a34",
+        "version": 2,
+        "height": 471746,
+        "epoch": 460,
+        "difficulty": 626412390187008,
+        "adProofsRoot": "d882aaf42e0a95eb95fcce5c3705adf758e591532f733efe790ac3c404730c39",
+        "stateRoot": "8ad868627ea4f7de6e2a2fe3f98fafe57f914e0f2ef3331c006def36c697f92713",
+        "transactionsRoot": "63eaa9aff76a1de3d71c81e4b2d92e8d97ae572a8e9ab9e66599ed0912dd2f8b",
+        "timestamp": 1618929697400,
+        "nBits": 117586360,
+        "size": 221,
+        "extensionHash": "3f91f3c680beb26615fdec251aee3f81aaf5a02740806c167c0f3c929471df44",
+        "powSolutions": {
+          "pk": "02b3a06d6eaa8671431ba1db4dd427a77f75a5c2acbd71bfb725d38adc2b55f669",
+          "w": "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+          "n": "5939ecfee6b0d7f4",
+          "d": "0"
+        },
+        "votes": [4, 0, 0]
+    }"#;
+
+    /// The encoded header must be the same as the captured payload (all the fields and values)
+    fn check_roundtrip(json: &str, dialect: HeaderJsonDialect) {
+        let captured: Value = serde_json::from_str(json).unwrap();
+        let header: Header = serde_json::from_value(captured.clone()).unwrap();
+        assert_eq!(header.height, Height::new(471746));
+        let encoded = header.to_json_value(dialect).unwrap();
+        assert_eq!(encoded, captured);
+        let decoded: Header = serde_json::from_value(encoded).unwrap();
+        assert_eq!(decoded, header);
+    }
+
+    #[test]
+    fn node_header_roundtrip() {
+        check_roundtrip(NODE_HEADER_JSON, HeaderJsonDialect::Node);
+    }
+
+    #[test]
+    fn explorer_header_roundtrip() {
+        check_roundtrip(EXPLORER_HEADER_JSON, HeaderJsonDialect::Explorer);
+    }
+
+    #[test]
+    fn same_header_from_both_apis() {
+        let from_node: Header = serde_json::from_str(NODE_HEADER_JSON).unwrap();
+        let from_explorer: Header = serde_json::from_str(EXPLORER_HEADER_JSON).unwrap();
+        assert_eq!(from_node, from_explorer);
+    }
+
+    #[test]
+    fn serialized_in_node_format() {
+        let header: Header = serde_json::from_str(NODE_HEADER_JSON).unwrap();
+        // the PoW solution is parsed, so the serialized header hashes to the id
+        assert_eq!(
+            blake2b256_hash(&header.sigma_serialize_bytes().unwrap()),
+            header.id.0
+        );
+        assert_eq!(
+            serde_json::to_value(&header).unwrap(),
+            serde_json::from_str::<Value>(NODE_HEADER_JSON).unwrap()
+        );
+    }
+
+    #[test]
+    fn pow_distance_as_decimal_string() {
+        let mut json: Value = serde_json::from_str(NODE_HEADER_JSON).unwrap();
+        let d = "123456789012345678901234567890";
+        json["powSolutions"]["d"] = d.into();
+        let header: Header = serde_json::from_value(json).unwrap();
+        assert_eq!(header.pow_distance, d.parse::<BigInt>().unwrap());
+        let encoded = header.to_json_value(HeaderJsonDialect::Node).unwrap();
+        assert_eq!(encoded["powSolutions"]["d"], d);
+    }
+}
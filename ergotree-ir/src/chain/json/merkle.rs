@@ -0,0 +1,67 @@
+//! Merkle proof JSON (node API format)
+
+use std::convert::TryFrom;
+
+use serde::{Deserialize, Serialize};
+
+use crate::chain::base16_bytes::Base16DecodedBytes;
+use crate::chain::digest32::Digest32;
+use crate::chain::merkle::LevelNode;
+use crate::chain::merkle::MerkleProof;
+use crate::chain::merkle::MerkleProofError;
+use crate::chain::merkle::NodeSide;
+
+/// Level node as `[hash, side]`, where the hash of the missing node is an empty string
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+pub struct LevelNodeJson(Base16DecodedBytes, u8);
+
+impl From<LevelNode> for LevelNodeJson {
+    fn from(node: LevelNode) -> Self {
+        LevelNodeJson(
+            Base16DecodedBytes(node.hash.map(Vec::from).unwrap_or_default()),
+            node.side as u8,
+        )
+    }
+}
+
+impl TryFrom<LevelNodeJson> for LevelNode {
+    type Error = MerkleProofError;
+
+    fn try_from(LevelNodeJson(hash, side): LevelNodeJson) -> Result<Self, Self::Error> {
+        Ok(LevelNode {
+            hash: if hash.0.is_empty() {
+                None
+            } else {
+                Some(Digest32::try_from(hash.0)?)
+            },
+            side: NodeSide::try_from(side)?,
+        })
+    }
+}
+
+/// Merkle proof as `{"leafData": .., "levels": [..]}`
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+pub struct MerkleProofJson {
+    #[serde(rename = "leafData")]
+    leaf_data: Base16DecodedBytes,
+    #[serde(rename = "levels")]
+    levels: Vec<LevelNode>,
+}
+
+impl From<MerkleProof> for MerkleProofJson {
+    fn from(proof: MerkleProof) -> Self {
+        MerkleProofJson {
+            leaf_data: Base16DecodedBytes(proof.leaf_data),
+            levels: proof.levels,
+        }
+    }
+}
+
+impl From<MerkleProofJson> for MerkleProof {
+    fn from(json: MerkleProofJson) -> Self {
+        MerkleProof {
+            leaf_data: json.leaf_data.0,
+            levels: json.levels,
+        }
+    }
+}
@@ -0,0 +1,549 @@
+//! Merkle tree with membership proofs, as in the reference implementation (scrypto `MerkleTree`,
+//! `MerkleProof`, `BatchMerkleProof`). Leaf hash is `blake2b256(0 ++ data)`, internal node hash is
+//! `blake2b256(1 ++ left ++ right)`, where the hash of the missing right node (odd number of nodes
+//! on the level) is empty.
+
+use std::convert::TryFrom;
+use std::io::Read;
+
+use crate::chain::digest32::blake2b256_hash;
+use crate::chain::digest32::Digest32;
+use crate::chain::digest32::Digest32Error;
+use crate::serialization::sigma_byte_reader::SigmaByteRead;
+use crate::serialization::sigma_byte_writer::SigmaByteWrite;
+use crate::serialization::SigmaParsingError;
+use crate::serialization::SigmaSerializable;
+use crate::serialization::SigmaSerializeResult;
+#[cfg(feature = "json")]
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+const LEAF_PREFIX: u8 = 0;
+const INTERNAL_NODE_PREFIX: u8 = 1;
+
+/// Errors on Merkle proof decoding
+#[derive(Error, Debug)]
+pub enum MerkleProofError {
+    /// Node side is neither 0 (left) nor 1 (right)
+    #[error("Invalid node side {0}, expected 0 (left) or 1 (right)")]
+    InvalidNodeSide(u8),
+    /// Invalid node hash
+    #[error("Invalid node hash: {0}")]
+    InvalidDigest(#[from] Digest32Error),
+}
+
+/// Side of the node in the pair of nodes hashed together
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+#[repr(u8)]
+pub enum NodeSide {
+    /// Left node
+    Left = 0,
+    /// Right node
+    Right = 1,
+}
+
+impl TryFrom<u8> for NodeSide {
+    type Error = MerkleProofError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(NodeSide::Left),
+            1 => Ok(NodeSide::Right),
+            _ => Err(MerkleProofError::InvalidNodeSide(value)),
+        }
+    }
+}
+
+/// Node of the proof: hash of the node (`None` for the missing right node) and the side
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "json",
+    serde(
+        into = "crate::chain::json::merkle::LevelNodeJson",
+        try_from = "crate::chain::json::merkle::LevelNodeJson"
+    )
+)]
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct LevelNode {
+    /// Node hash, `None` for the missing node
+    pub hash: Option<Digest32>,
+    /// Node side
+    pub side: NodeSide,
+}
+
+impl LevelNode {
+    /// Scorex serialization of the missing node hash
+    const EMPTY_HASH: [u8; Digest32::SIZE] = [0; Digest32::SIZE];
+
+    fn serialize_hash<W: SigmaByteWrite>(&self, w: &mut W) -> SigmaSerializeResult {
+        match &self.hash {
+            Some(hash) => hash.sigma_serialize(w),
+            None => Ok(w.write_all(&Self::EMPTY_HASH)?),
+        }
+    }
+
+    fn parse_hash<R: SigmaByteRead>(r: &mut R) -> Result<Option<Digest32>, SigmaParsingError> {
+        let mut hash = [0u8; Digest32::SIZE];
+        r.read_exact(&mut hash)?;
+        Ok(Self::non_empty_hash(hash))
+    }
+
+    fn non_empty_hash(hash: [u8; Digest32::SIZE]) -> Option<Digest32> {
+        if hash == Self::EMPTY_HASH {
+            None
+        } else {
+            Some(hash.into())
+        }
+    }
+
+    fn parse_side<R: SigmaByteRead>(r: &mut R) -> Result<NodeSide, SigmaParsingError> {
+        NodeSide::try_from(r.get_u8()?)
+            .map_err(|e| SigmaParsingError::ValueOutOfBounds(e.to_string()))
+    }
+}
+
+/// Proof of the leaf membership in the tree (e.g. transaction in the block, see
+/// `/blocks/{headerId}/proofFor/{txId}` node API)
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "json",
+    serde(
+        into = "crate::chain::json::merkle::MerkleProofJson",
+        from = "crate::chain::json::merkle::MerkleProofJson"
+    )
+)]
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct MerkleProof {
+    /// Leaf data (e.g. transaction id)
+    pub leaf_data: Vec<u8>,
+    /// Nodes from the leaf level to the root, each is the sibling of the node on the path to the
+    /// root and the side of the node on the path
+    pub levels: Vec<LevelNode>,
+}
+
+impl MerkleProof {
+    /// Root hash of the tree calculated from the leaf data and the levels
+    pub fn root_hash(&self) -> Digest32 {
+        self.levels
+            .iter()
+            .fold(leaf_hash(&self.leaf_data), |hash, node| match node.side {
+                NodeSide::Left => internal_node_hash(Some(&hash), node.hash.as_ref()),
+                NodeSide::Right => internal_node_hash(node.hash.as_ref(), Some(&hash)),
+            })
+    }
+
+    /// Checks that the proof leads to the expected root hash
+    pub fn valid(&self, expected_root: &Digest32) -> bool {
+        self.root_hash() == *expected_root
+    }
+}
+
+/// Scorex serialization: leaf data size (4 bytes, big-endian), leaf data, then the levels, each is
+/// the side (1 byte) and the hash (32 bytes, all zeros for the missing node)
+impl SigmaSerializable for MerkleProof {
+    fn sigma_serialize<W: SigmaByteWrite>(&self, w: &mut W) -> SigmaSerializeResult {
+        w.write_all(&(self.leaf_data.len() as u32).to_be_bytes())?;
+        w.write_all(&self.leaf_data)?;
+        self.levels.iter().try_for_each(|node| {
+            w.put_u8(node.side as u8)?;
+            node.serialize_hash(w)
+        })
+    }
+
+    fn sigma_parse<R: SigmaByteRead>(r: &mut R) -> Result<Self, SigmaParsingError> {
+        let leaf_data_size = read_u32_be(r)? as usize;
+        let mut leaf_data = Vec::new();
+        r.by_ref()
+            .take(leaf_data_size as u64)
+            .read_to_end(&mut leaf_data)?;
+        if leaf_data.len() != leaf_data_size {
+            return Err(SigmaParsingError::ValueOutOfBounds(format!(
+                "leaf data size {} exceeds the remaining {} bytes",
+                leaf_data_size,
+                leaf_data.len()
+            )));
+        }
+        let mut levels_bytes = Vec::new();
+        r.read_to_end(&mut levels_bytes)?;
+        if levels_bytes.len() % (1 + Digest32::SIZE) != 0 {
+            return Err(SigmaParsingError::ValueOutOfBounds(format!(
+                "levels size {} is not a multiple of {}",
+                levels_bytes.len(),
+                1 + Digest32::SIZE
+            )));
+        }
+        let levels = levels_bytes
+            .chunks(1 + Digest32::SIZE)
+            .map(|node| {
+                let side = NodeSide::try_from(node[0])
+                    .map_err(|e| SigmaParsingError::ValueOutOfBounds(e.to_string()))?;
+                let mut hash = [0u8; Digest32::SIZE];
+                hash.copy_from_slice(&node[1..]);
+                Ok(LevelNode {
+                    hash: LevelNode::non_empty_hash(hash),
+                    side,
+                })
+            })
+            .collect::<Result<Vec<LevelNode>, SigmaParsingError>>()?;
+        Ok(MerkleProof { leaf_data, levels })
+    }
+}
+
+/// Leaf of the batch proof: index and hash of the leaf
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct BatchMerkleProofIndex {
+    /// Leaf index
+    #[cfg_attr(feature = "json", serde(rename = "index"))]
+    pub index: usize,
+    /// Leaf hash
+    #[cfg_attr(feature = "json", serde(rename = "digest"))]
+    pub hash: Digest32,
+}
+
+/// Proof of the membership of multiple leaves in the tree (compact, the nodes shared by the
+/// paths to the root are not repeated)
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct BatchMerkleProof {
+    /// Leaves (sorted by index)
+    #[cfg_attr(feature = "json", serde(rename = "indices"))]
+    pub indices: Vec<BatchMerkleProofIndex>,
+    /// Nodes required to calculate the root hash, level by level from the leaves to the root.
+    /// Side is the side of the proof node (vs. the side of the node on the path in
+    /// [`MerkleProof`]).
+    #[cfg_attr(feature = "json", serde(rename = "proofs"))]
+    pub proofs: Vec<LevelNode>,
+}
+
+impl BatchMerkleProof {
+    /// Root hash of the tree calculated from the leaves and the proof nodes, `None` if the proof
+    /// is malformed (unsorted indices, not enough or excessive proof nodes)
+    pub fn root_hash(&self) -> Option<Digest32> {
+        if self.indices.is_empty()
+            || self
+                .indices
+                .windows(2)
+                .any(|pair| pair[0].index >= pair[1].index)
+        {
+            return None;
+        }
+        let mut indices: Vec<usize> = self.indices.iter().map(|i| i.index).collect();
+        let mut hashes: Vec<Digest32> = self.indices.iter().map(|i| i.hash.clone()).collect();
+        let mut proofs = self.proofs.iter();
+        loop {
+            let pairs: Vec<(usize, usize)> = indices.iter().map(|i| sibling_pair(*i)).collect();
+            let mut level_hashes = Vec::new();
+            let mut i = 0;
+            while i < pairs.len() {
+                if pairs.get(i + 1) == Some(&pairs[i]) {
+                    // both nodes of the pair are known
+                    level_hashes.push(internal_node_hash(Some(&hashes[i]), Some(&hashes[i + 1])));
+                    i += 2;
+                } else {
+                    let node = proofs.next()?;
+                    level_hashes.push(match node.side {
+                        NodeSide::Left => internal_node_hash(node.hash.as_ref(), Some(&hashes[i])),
+                        NodeSide::Right => internal_node_hash(Some(&hashes[i]), node.hash.as_ref()),
+                    });
+                    i += 1;
+                }
+            }
+            indices = pairs.iter().map(|(left, _)| left / 2).collect();
+            indices.dedup();
+            hashes = level_hashes;
+            if proofs.len() == 0 && hashes.len() == 1 {
+                return hashes.pop();
+            }
+        }
+    }
+
+    /// Checks that the proof leads to the expected root hash
+    pub fn valid(&self, expected_root: &Digest32) -> bool {
+        self.root_hash().as_ref() == Some(expected_root)
+    }
+}
+
+/// Scorex serialization: number of the indices and number of the proof nodes (4 bytes,
+/// big-endian, each), then the indices, each is the index (4 bytes, big-endian) and the hash (32
+/// bytes), then the proof nodes, each is the hash (32 bytes, all zeros for the missing node) and
+/// the side (1 byte)
+impl SigmaSerializable for BatchMerkleProof {
+    fn sigma_serialize<W: SigmaByteWrite>(&self, w: &mut W) -> SigmaSerializeResult {
+        w.write_all(&(self.indices.len() as u32).to_be_bytes())?;
+        w.write_all(&(self.proofs.len() as u32).to_be_bytes())?;
+        self.indices.iter().try_for_each(|i| {
+            w.write_all(&(i.index as u32).to_be_bytes())?;
+            i.hash.sigma_serialize(w)
+        })?;
+        self.proofs.iter().try_for_each(|node| {
+            node.serialize_hash(w)?;
+            Ok(w.put_u8(node.side as u8)?)
+        })
+    }
+
+    fn sigma_parse<R: SigmaByteRead>(r: &mut R) -> Result<Self, SigmaParsingError> {
+        let indices_count = read_u32_be(r)?;
+        let proofs_count = read_u32_be(r)?;
+        let mut indices = Vec::new();
+        for _ in 0..indices_count {
+            indices.push(BatchMerkleProofIndex {
+                index: read_u32_be(r)? as usize,
+                hash: Digest32::sigma_parse(r)?,
+            });
+        }
+        let mut proofs = Vec::new();
+        for _ in 0..proofs_count {
+            let hash = LevelNode::parse_hash(r)?;
+            let side = LevelNode::parse_side(r)?;
+            proofs.push(LevelNode { hash, side });
+        }
+        Ok(BatchMerkleProof { indices, proofs })
+    }
+}
+
+/// Merkle tree built from the leaves data
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct MerkleTree {
+    leaves: Vec<Vec<u8>>,
+    /// Node hashes level by level, from the leaf hashes to the root
+    levels: Vec<Vec<Digest32>>,
+}
+
+impl MerkleTree {
+    /// Build the tree from the leaves data
+    pub fn new(leaves: Vec<Vec<u8>>) -> MerkleTree {
+        let mut level: Vec<Digest32> = leaves.iter().map(|l| leaf_hash(l)).collect();
+        let mut levels = Vec::new();
+        // the root is always an internal node, even for a single leaf
+        while !level.is_empty() && (levels.is_empty() || level.len() > 1) {
+            let next = level
+                .chunks(2)
+                .map(|pair| internal_node_hash(pair.first(), pair.get(1)))
+                .collect();
+            levels.push(level);
+            level = next;
+        }
+        levels.push(level);
+        MerkleTree { leaves, levels }
+    }
+
+    /// Root hash, all zeros for the empty tree
+    pub fn root_hash(&self) -> Digest32 {
+        self.levels
+            .last()
+            .and_then(|level| level.first())
+            .cloned()
+            .unwrap_or_else(Digest32::zero)
+    }
+
+    /// Proof of the leaf with the given index, `None` if the index is out of bounds
+    pub fn proof_by_index(&self, index: usize) -> Option<MerkleProof> {
+        let leaf_data = self.leaves.get(index)?.clone();
+        let mut i = index;
+        let levels = self.levels[..self.levels.len() - 1]
+            .iter()
+            .map(|level| {
+                let (sibling, side) = if i % 2 == 0 {
+                    (i + 1, NodeSide::Left)
+                } else {
+                    (i - 1, NodeSide::Right)
+                };
+                i /= 2;
+                LevelNode {
+                    hash: level.get(sibling).cloned(),
+                    side,
+                }
+            })
+            .collect();
+        Some(MerkleProof { leaf_data, levels })
+    }
+
+    /// Proof of the (first) leaf with the given data, `None` if there is no such leaf
+    pub fn proof_by_element(&self, leaf_data: &[u8]) -> Option<MerkleProof> {
+        let index = self.leaves.iter().position(|l| l.as_slice() == leaf_data)?;
+        self.proof_by_index(index)
+    }
+
+    /// Batch proof of the leaves with the given indices, `None` if the indices are empty or some
+    /// index is out of bounds
+    pub fn proof_by_indices(&self, indices: &[usize]) -> Option<BatchMerkleProof> {
+        let mut indices = indices.to_vec();
+        indices.sort_unstable();
+        indices.dedup();
+        if indices.is_empty() || indices.iter().any(|i| *i >= self.leaves.len()) {
+            return None;
+        }
+        let proof_indices = indices
+            .iter()
+            .map(|i| BatchMerkleProofIndex {
+                index: *i,
+                hash: self.levels[0][*i].clone(),
+            })
+            .collect();
+        let mut proofs = Vec::new();
+        for level in &self.levels[..self.levels.len() - 1] {
+            let pairs: Vec<(usize, usize)> = indices.iter().map(|i| sibling_pair(*i)).collect();
+            let mut i = 0;
+            while i < pairs.len() {
+                if pairs.get(i + 1) == Some(&pairs[i]) {
+                    i += 2;
+                } else {
+                    let (left, right) = pairs[i];
+                    proofs.push(if indices[i] == left {
+                        LevelNode {
+                            hash: level.get(right).cloned(),
+                            side: NodeSide::Right,
+                        }
+                    } else {
+                        LevelNode {
+                            hash: level.get(left).cloned(),
+                            side: NodeSide::Left,
+                        }
+                    });
+                    i += 1;
+                }
+            }
+            indices = pairs.iter().map(|(left, _)| left / 2).collect();
+            indices.dedup();
+        }
+        Some(BatchMerkleProof {
+            indices: proof_indices,
+            proofs,
+        })
+    }
+}
+
+/// Root hash of the Merkle tree with the given leaves, all zeros for the empty tree
+pub fn merkle_tree_root(leaves: &[Vec<u8>]) -> Digest32 {
+    MerkleTree::new(leaves.to_vec()).root_hash()
+}
+
+fn leaf_hash(data: &[u8]) -> Digest32 {
+    let mut bytes = vec![LEAF_PREFIX];
+    bytes.extend_from_slice(data);
+    blake2b256_hash(&bytes)
+}
+
+fn internal_node_hash(left: Option<&Digest32>, right: Option<&Digest32>) -> Digest32 {
+    let mut bytes = vec![INTERNAL_NODE_PREFIX];
+    left.iter()
+        .chain(right.iter())
+        .for_each(|h| bytes.extend_from_slice(h.as_ref()));
+    blake2b256_hash(&bytes)
+}
+
+/// Indices of the pair of nodes (hashed together) containing the given node
+fn sibling_pair(index: usize) -> (usize, usize) {
+    if index % 2 == 0 {
+        (index, index + 1)
+    } else {
+        (index - 1, index)
+    }
+}
+
+fn read_u32_be<R: SigmaByteRead>(r: &mut R) -> Result<u32, SigmaParsingError> {
+    let mut bytes = [0u8; 4];
+    r.read_exact(&mut bytes)?;
+    Ok(u32::from_be_bytes(bytes))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::serialization::sigma_serialize_roundtrip;
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    fn leaves(count: u8) -> Vec<Vec<u8>> {
+        (0..count).map(|i| vec![i; 3]).collect()
+    }
+
+    #[test]
+    fn empty_tree() {
+        assert_eq!(merkle_tree_root(&[]), Digest32::zero());
+        let tree = MerkleTree::new(vec![]);
+        assert_eq!(tree.proof_by_index(0), None);
+        assert_eq!(tree.proof_by_indices(&[0]), None);
+    }
+
+    #[test]
+    fn single_leaf() {
+        let root = merkle_tree_root(&[vec![1, 2, 3]]);
+        assert_eq!(root, internal_node_hash(Some(&leaf_hash(&[1, 2, 3])), None));
+    }
+
+    #[test]
+    fn odd_number_of_leaves() {
+        let root = merkle_tree_root(&[vec![1], vec![2], vec![3]]);
+        let left = internal_node_hash(Some(&leaf_hash(&[1])), Some(&leaf_hash(&[2])));
+        let right = internal_node_hash(Some(&leaf_hash(&[3])), None);
+        assert_eq!(root, internal_node_hash(Some(&left), Some(&right)));
+    }
+
+    #[test]
+    fn invalid_proofs() {
+        let tree = MerkleTree::new(leaves(5));
+        let root = tree.root_hash();
+        let mut proof = tree.proof_by_element(&[2, 2, 2]).unwrap();
+        assert!(proof.valid(&root));
+        proof.leaf_data = vec![5, 5, 5];
+        assert!(!proof.valid(&root));
+
+        let batch = tree.proof_by_indices(&[4, 1]).unwrap();
+        assert!(batch.valid(&root));
+        let mut unsorted = batch.clone();
+        unsorted.indices.reverse();
+        assert!(!unsorted.valid(&root));
+        let mut missing_node = batch.clone();
+        missing_node.proofs.pop();
+        assert!(!missing_node.valid(&root));
+        let mut extra_node = batch;
+        extra_node.proofs.push(extra_node.proofs[0].clone());
+        assert!(!extra_node.valid(&root));
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn parse_node_proof_json() {
+        // GET /blocks/{headerId}/proofFor/{txId}
+        let tree = MerkleTree::new(leaves(3));
+        let proof = tree.proof_by_index(2).unwrap();
+        let json = serde_json::to_value(&proof).unwrap();
+        assert_eq!(json["leafData"], "020202");
+        assert_eq!(json["levels"][0], serde_json::json!(["", 0]));
+        assert_eq!(json["levels"][1][1], 1);
+        let parsed: MerkleProof = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed, proof);
+        assert!(
+            serde_json::from_str::<MerkleProof>(r#"{"leafData":"00","levels":[["",2]]}"#).is_err()
+        );
+    }
+
+    proptest! {
+
+        #[test]
+        fn proofs_valid(count in 1u8..40, index_seeds in vec(any::<usize>(), 1..8)) {
+            let tree = MerkleTree::new(leaves(count));
+            let root = tree.root_hash();
+            for i in 0..count as usize {
+                prop_assert!(tree.proof_by_index(i).unwrap().valid(&root));
+            }
+            prop_assert_eq!(tree.proof_by_index(count as usize), None);
+            let indices: Vec<usize> = index_seeds.iter().map(|s| s % count as usize).collect();
+            let batch = tree.proof_by_indices(&indices).unwrap();
+            prop_assert!(batch.valid(&root));
+        }
+
+        #[test]
+        fn ser_roundtrip(count in 1u8..20, index in any::<usize>()) {
+            let tree = MerkleTree::new(leaves(count));
+            let index = index % count as usize;
+            let proof = tree.proof_by_index(index).unwrap();
+            prop_assert_eq![sigma_serialize_roundtrip(&proof), proof];
+            let batch = tree.proof_by_indices(&[0, index]).unwrap();
+            prop_assert_eq![sigma_serialize_roundtrip(&batch), batch];
+        }
+    }
+}
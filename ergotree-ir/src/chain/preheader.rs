@@ -1,6 +1,8 @@
 //! Block header with fields that can be predicted by miner
 
 use crate::sigma_protocol::dlog_group;
+use ergo_units::Height;
+use ergo_units::TimestampMillis;
 
 use super::{block_id::BlockId, digest32::Digest32, votes::Votes};
 
@@ -13,11 +15,11 @@ pub struct PreHeader {
     /// Hash of parent block
     pub parent_id: BlockId,
     /// Timestamp of a block in ms from UNIX epoch
-    pub timestamp: u64,
+    pub timestamp: TimestampMillis,
     /// Current difficulty in a compressed view.
     pub n_bits: u64,
     /// Block height
-    pub height: u32,
+    pub height: Height,
     /// Public key of miner
     pub miner_pk: Box<dlog_group::EcPoint>,
     /// Votes
@@ -30,9 +32,9 @@ impl PreHeader {
         PreHeader {
             version: 1,
             parent_id: BlockId(Digest32::zero()),
-            timestamp: 0,
+            timestamp: TimestampMillis::new(0),
             n_bits: 0,
-            height: 0,
+            height: Height::new(0),
             miner_pk: dlog_group::generator().into(),
             votes: Votes([0u8; 3]),
         }
@@ -66,9 +68,9 @@ mod arbitrary {
                     Self {
                         version: 1,
                         parent_id,
-                        timestamp,
+                        timestamp: TimestampMillis::new(timestamp),
                         n_bits,
-                        height,
+                        height: Height::new(height),
                         miner_pk,
                         votes,
                     }
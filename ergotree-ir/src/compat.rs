@@ -0,0 +1,318 @@
+//! Compatibility of the trees between the script versions (protocol v5.x and v6.x)
+//!
+//! Most of the tree encoding is the same in all script versions, but a tree of a newer version
+//! can use the constructs which the nodes of an older version do not understand: a node which
+//! has not activated the script version introducing them fails to parse such a tree (or rejects
+//! the method call on evaluation). Some of the constructs also change the encoding, which is
+//! then selected by the version in the tree header: the values of the `Option` types have a data
+//! encoding only in the v3 trees, and the method calls of the v3 trees carry the types of their
+//! explicit type arguments (e.g. `Global.deserializeTo[T]`).
+//! Each divergence is a [`VersionedFeature`] with the minimal version of the trees it can be used
+//! in, [`check_tree`] checks a tree against its header version and [`tree_header`] selects the
+//! header version for a new tree.
+
+use thiserror::Error;
+
+use crate::ergo_tree::ErgoTree;
+use crate::ergo_tree::ErgoTreeError;
+use crate::ergo_tree::ErgoTreeHeader;
+use crate::ergo_tree::ErgoTreeVersion;
+use crate::mir::expr::Expr;
+use crate::types::scoll;
+use crate::types::sglobal;
+use crate::types::smethod::SMethod;
+use crate::types::stype::SType;
+
+/// Construct introduced in a script version after v0
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
+pub enum VersionedFeature {
+    /// `Coll.patch`, `Coll.updated` and `Coll.updateMany` methods, added in v5.0 (JIT costing)
+    CollUpdateMethods,
+    /// Constants of the `Option` types (at any nesting, e.g. `Coll[Option[Int]]`), the data
+    /// serializer of v5.x has no encoding for the `Option` values, added in v6.0
+    OptionConstants,
+    /// `Global.serialize` and `Global.deserializeTo` methods, added in v6.0
+    GlobalSerialization,
+    /// Types of the explicit type arguments of the method (see
+    /// [`SMethod::explicit_type_args`]) serialized after the arguments of the method call, added
+    /// in v6.0 (the trees of the earlier versions are serialized without them)
+    MethodCallTypeArgs,
+}
+
+impl VersionedFeature {
+    /// Minimal tree (and activated script) version the feature can be used in
+    pub fn min_version(&self) -> ErgoTreeVersion {
+        match self {
+            VersionedFeature::CollUpdateMethods => ErgoTreeVersion::V2,
+            VersionedFeature::OptionConstants
+            | VersionedFeature::GlobalSerialization
+            | VersionedFeature::MethodCallTypeArgs => ErgoTreeVersion::V3,
+        }
+    }
+
+    /// The feature of the method, None if the method is available in all versions
+    pub fn of_method(method: &SMethod) -> Option<VersionedFeature> {
+        let type_code = method.obj_type.type_code();
+        let method_id = method.method_id();
+        if type_code == scoll::TYPE_CODE
+            && (method_id == scoll::PATCH_METHOD_ID
+                || method_id == scoll::UPDATED_METHOD_ID
+                || method_id == scoll::UPDATE_MANY_METHOD_ID)
+        {
+            Some(VersionedFeature::CollUpdateMethods)
+        } else if type_code == sglobal::TYPE_CODE
+            && (method_id == sglobal::SERIALIZE_METHOD_ID
+                || method_id == sglobal::DESERIALIZE_TO_METHOD_ID)
+        {
+            Some(VersionedFeature::GlobalSerialization)
+        } else {
+            None
+        }
+    }
+
+    /// The feature of the constant type, None if the constants of the type are serializable in
+    /// all versions
+    pub fn of_constant_type(tpe: &SType) -> Option<VersionedFeature> {
+        if has_option(tpe) {
+            Some(VersionedFeature::OptionConstants)
+        } else {
+            None
+        }
+    }
+}
+
+/// Tree uses a construct its header version does not allow
+#[derive(Error, PartialEq, Eq, Debug, Clone)]
+pub enum CompatError {
+    /// Feature requires a higher tree version
+    #[error(
+        "{feature:?} requires the tree version {required:?}, tree version is {tree_version:?}"
+    )]
+    FeatureNotAvailable {
+        /// used feature
+        feature: VersionedFeature,
+        /// minimal version of the feature
+        required: ErgoTreeVersion,
+        /// version in the tree header
+        tree_version: ErgoTreeVersion,
+    },
+    /// Tree root parsing error
+    #[error("ErgoTree error: {0}")]
+    ErgoTreeError(#[from] ErgoTreeError),
+}
+
+/// Features used in the expression (each one once, in the pre-order of the nodes)
+pub fn features(expr: &Expr) -> Vec<VersionedFeature> {
+    let mut res = Vec::new();
+    let mut stack = vec![expr];
+    while let Some(e) = stack.pop() {
+        let node_features = match e {
+            Expr::Const(c) => vec![VersionedFeature::of_constant_type(&c.tpe)],
+            Expr::MethodCall(mc) => vec![
+                VersionedFeature::of_method(&mc.method),
+                if mc.method.explicit_type_args().is_empty() {
+                    None
+                } else {
+                    Some(VersionedFeature::MethodCallTypeArgs)
+                },
+            ],
+            Expr::ProperyCall(pc) => vec![VersionedFeature::of_method(&pc.method)],
+            _ => vec![],
+        };
+        for f in node_features.into_iter().flatten() {
+            if !res.contains(&f) {
+                res.push(f);
+            }
+        }
+        stack.extend(e.children().into_iter().rev());
+    }
+    res
+}
+
+/// Minimal tree version the expression can be used in
+pub fn min_tree_version(expr: &Expr) -> ErgoTreeVersion {
+    features(expr)
+        .iter()
+        .map(VersionedFeature::min_version)
+        .max()
+        .unwrap_or(ErgoTreeVersion::V0)
+}
+
+/// Checks the features used in the tree are allowed by its header version
+pub fn check_tree(tree: &ErgoTree) -> Result<(), CompatError> {
+    let tree_version = tree.header().version();
+    let expr = tree.proposition()?;
+    match features(expr.as_ref())
+        .into_iter()
+        .find(|f| f.min_version() > tree_version)
+    {
+        Some(feature) => Err(CompatError::FeatureNotAvailable {
+            feature,
+            required: feature.min_version(),
+            tree_version,
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Header for a new tree of the expression: version 0 if the expression uses no versioned
+/// features (the trees are understood by the nodes of all versions), otherwise the minimal
+/// version of the features used
+pub fn tree_header(expr: &Expr, constant_segregation: bool) -> ErgoTreeHeader {
+    match min_tree_version(expr) {
+        ErgoTreeVersion::V0 => ErgoTreeHeader::v0(constant_segregation),
+        version => ErgoTreeHeader::with_version(version, constant_segregation),
+    }
+}
+
+fn has_option(tpe: &SType) -> bool {
+    match tpe {
+        SType::SOption(_) => true,
+        SType::SColl(elem) => has_option(elem),
+        SType::STuple(t) => t.items.iter().any(has_option),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "arbitrary")]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::mir::constant::Constant;
+    use crate::mir::global_vars::GlobalVars;
+    use crate::mir::method_call::MethodCall;
+    use crate::mir::sigma_prop_bytes::SigmaPropBytes;
+    use crate::serialization::SigmaSerializable;
+    use crate::sigma_protocol::sigma_boolean::ProveDlog;
+    use crate::types::sheader;
+    use sigma_test_util::force_any_val;
+    use std::convert::TryInto;
+
+    fn option_const() -> Expr {
+        Constant::from(Some(1i32)).into()
+    }
+
+    fn p2pk() -> Expr {
+        Expr::Const(force_any_val::<ProveDlog>().into())
+    }
+
+    #[test]
+    fn method_features() {
+        assert_eq!(
+            VersionedFeature::of_method(&scoll::PATCH_METHOD),
+            Some(VersionedFeature::CollUpdateMethods)
+        );
+        assert_eq!(VersionedFeature::of_method(&scoll::INDEX_OF_METHOD), None);
+        assert_eq!(
+            VersionedFeature::of_method(&sglobal::SERIALIZE_METHOD),
+            Some(VersionedFeature::GlobalSerialization)
+        );
+        assert_eq!(VersionedFeature::of_method(&sheader::VOTES_PROPERTY), None);
+        assert_eq!(
+            VersionedFeature::CollUpdateMethods.min_version(),
+            ErgoTreeVersion::V2
+        );
+        assert_eq!(
+            VersionedFeature::GlobalSerialization.min_version(),
+            ErgoTreeVersion::V3
+        );
+    }
+
+    #[test]
+    fn constant_features() {
+        let opt_in_tuple = SType::STuple(
+            vec![SType::SInt, SType::SOption(SType::SLong.into())]
+                .try_into()
+                .unwrap(),
+        );
+        assert_eq!(
+            VersionedFeature::of_constant_type(&SType::SColl(opt_in_tuple.into())),
+            Some(VersionedFeature::OptionConstants)
+        );
+        assert_eq!(
+            VersionedFeature::of_constant_type(&SType::SColl(SType::SInt.into())),
+            None
+        );
+        assert_eq!(
+            features(&option_const()),
+            vec![VersionedFeature::OptionConstants]
+        );
+        assert_eq!(min_tree_version(&p2pk()), ErgoTreeVersion::V0);
+    }
+
+    #[test]
+    fn features_in_nested_nodes() {
+        // Global.serialize(Some(1))
+        let serialize: Expr = MethodCall {
+            obj: Box::new(Expr::Global),
+            method: sglobal::SERIALIZE_METHOD.clone(),
+            args: vec![option_const()],
+        }
+        .into();
+        assert_eq!(
+            features(&serialize),
+            vec![
+                VersionedFeature::GlobalSerialization,
+                VersionedFeature::OptionConstants
+            ]
+        );
+        assert_eq!(min_tree_version(&serialize), ErgoTreeVersion::V3);
+        // Global.deserializeTo[Long](Coll(10))
+        let deserialize_to: Expr = MethodCall {
+            obj: Box::new(Expr::Global),
+            method: sglobal::DESERIALIZE_TO_METHOD.clone(),
+            args: vec![Constant::from(vec![10i8]).into()],
+        }
+        .into();
+        assert_eq!(
+            features(&deserialize_to),
+            vec![
+                VersionedFeature::GlobalSerialization,
+                VersionedFeature::MethodCallTypeArgs
+            ]
+        );
+        let height: Expr = GlobalVars::Height.into();
+        assert_eq!(features(&height), vec![]);
+        let prop_bytes: Expr = SigmaPropBytes {
+            input: Box::new(p2pk()),
+        }
+        .into();
+        assert_eq!(min_tree_version(&prop_bytes), ErgoTreeVersion::V0);
+    }
+
+    #[test]
+    fn check_tree_versions() {
+        let tree_v0 = ErgoTree::new(ErgoTreeHeader::v0(true), &p2pk()).unwrap();
+        assert_eq!(check_tree(&tree_v0), Ok(()));
+        // SigmaProp(opt.isDefined) with an Option constant
+        let expr: Expr = crate::mir::bool_to_sigma::BoolToSigmaProp {
+            input: Box::new(
+                crate::mir::option_is_defined::OptionIsDefined {
+                    input: Box::new(option_const()),
+                }
+                .into(),
+            ),
+        }
+        .into();
+        for segregation in [false, true].iter() {
+            let tree = ErgoTree::new(ErgoTreeHeader::v1(*segregation), &expr).unwrap();
+            assert_eq!(
+                check_tree(&tree),
+                Err(CompatError::FeatureNotAvailable {
+                    feature: VersionedFeature::OptionConstants,
+                    required: ErgoTreeVersion::V3,
+                    tree_version: ErgoTreeVersion::V1,
+                })
+            );
+            let header = tree_header(&expr, *segregation);
+            assert_eq!(header.version(), ErgoTreeVersion::V3);
+            assert_eq!(header.is_constant_segregation(), *segregation);
+            let tree = ErgoTree::new(header, &expr).unwrap();
+            assert_eq!(check_tree(&tree), Ok(()));
+            let bytes = tree.sigma_serialize_bytes().unwrap();
+            assert_eq!(ErgoTree::sigma_parse_bytes(&bytes).unwrap(), tree);
+        }
+        assert_eq!(tree_header(&p2pk(), false), ErgoTreeHeader::v0(false));
+    }
+}
@@ -1,15 +1,24 @@
 //! ErgoTree
+use crate::chain::digest32::blake2b256_hash;
+use crate::chain::digest32::Digest32;
+use crate::mir::complexity::complexity;
+use crate::mir::complexity::Complexity;
 use crate::mir::constant::Constant;
 use crate::mir::constant::TryExtractFromError;
 use crate::mir::expr::Expr;
+use crate::pretty_printer::print_constant;
+use crate::pretty_printer::print_expr;
+use crate::pretty_printer::print_type;
 use crate::serialization::SigmaSerializationError;
 use crate::serialization::SigmaSerializeResult;
 use crate::serialization::{
-    sigma_byte_reader::{SigmaByteRead, SigmaByteReader},
+    sigma_byte_reader::{DeserializePolicy, SigmaByteRead, SigmaByteReader},
     sigma_byte_writer::{SigmaByteWrite, SigmaByteWriter},
     SigmaParsingError, SigmaSerializable,
 };
 use crate::sigma_protocol::sigma_boolean::ProveDlog;
+use crate::templates::TemplateRegistry;
+use crate::types::method_registry::MethodRegistry;
 use crate::types::stype::SType;
 use io::Cursor;
 use sigma_ser::vlq_encode::ReadSigmaVlqExt;
@@ -22,13 +31,17 @@ use std::convert::TryFrom;
 use std::io;
 use std::io::Read;
 use std::io::Write;
-use std::rc::Rc;
+use std::sync::Arc;
 use thiserror::Error;
 
+use self::cache::ErgoTreeCache;
+
+pub mod cache;
+
 #[derive(PartialEq, Eq, Debug, Clone)]
 struct ParsedTree {
     constants: Vec<Constant>,
-    root: Result<Rc<Expr>, ErgoTreeRootParsingError>,
+    root: Result<Arc<Expr>, ErgoTreeRootParsingError>,
 }
 
 impl ParsedTree {
@@ -70,7 +83,7 @@ impl ParsedTree {
         header: &ErgoTreeHeader,
     ) -> Result<Vec<u8>, SigmaSerializationError> {
         let mut data = Vec::new();
-        let mut w = SigmaByteWriter::new(&mut data, None);
+        let mut w = SigmaByteWriter::new(&mut data, None).with_tree_version(header.version());
         header.sigma_serialize(&mut w)?;
         if header.is_constant_segregation() {
             w.put_usize_as_u32_unwrapped(self.constants.len())?;
@@ -154,6 +167,20 @@ impl ErgoTreeHeader {
         header_byte.into()
     }
 
+    /// Return a header with the given version (with size flag set for the versions above 0) and
+    /// constant segregation flag set to the given value
+    pub fn with_version(version: ErgoTreeVersion, constant_segregation: bool) -> Self {
+        let version: u8 = version.into();
+        let mut header_byte = version & ErgoTreeVersion::VERSION_MASK;
+        if header_byte > 0 {
+            header_byte |= Self::HAS_SIZE_FLAG;
+        }
+        if constant_segregation {
+            header_byte |= Self::CONSTANT_SEGREGATION_FLAG;
+        }
+        header_byte.into()
+    }
+
     /// Returns true if constant segregation flag is set
     pub fn is_constant_segregation(&self) -> bool {
         self.0 & ErgoTreeHeader::CONSTANT_SEGREGATION_FLAG != 0
@@ -177,7 +204,7 @@ impl Default for ErgoTreeHeader {
 }
 
 /// ErgoTree version 0..=7, should fit in 3 bits
-#[derive(PartialEq, Eq, Debug, Clone, Into)]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Clone, Into)]
 pub struct ErgoTreeVersion(u8);
 
 impl ErgoTreeVersion {
@@ -187,6 +214,10 @@ impl ErgoTreeVersion {
     pub const V0: Self = ErgoTreeVersion(0);
     /// Version 1 (size flag is mandatory)
     pub const V1: Self = ErgoTreeVersion(1);
+    /// Version 2 (v5.0 protocol)
+    pub const V2: Self = ErgoTreeVersion(2);
+    /// Version 3 (v6.0 protocol)
+    pub const V3: Self = ErgoTreeVersion(3);
 
     /// Returns a value of the version bits from the given header byte.
     pub fn parse_version(header: &ErgoTreeHeader) -> ErgoTreeVersion {
@@ -240,24 +271,70 @@ impl ErgoTree {
         header: ErgoTreeHeader,
         size: u32,
     ) -> Result<Self, SigmaParsingError> {
-        let mut buf = vec![0u8; size as usize];
-        r.read_exact(buf.as_mut_slice())?;
+        let buf = read_sized(r, size)?;
+        let deserialize_policy = r.deserialize_policy();
+        if *r.method_registry() != MethodRegistry::builtin() {
+            return ErgoTree::sigma_parse_buf(
+                header,
+                size,
+                buf,
+                deserialize_policy,
+                r.method_registry(),
+            );
+        }
+        // the trees parsed with the built-in methods are shared through the cache, by the hash of
+        // the tree bytes (header, size and the rest)
+        let mut tree_bytes = Vec::with_capacity(buf.len() + 6);
+        let mut w = SigmaByteWriter::new(&mut tree_bytes, None);
+        header.sigma_serialize(&mut w)?;
+        if header.has_size() {
+            w.put_u32(size)?;
+        }
+        w.write_all(&buf)?;
+        let tree =
+            ErgoTreeCache::shared().get_or_insert_with(&tree_bytes, deserialize_policy, || {
+                ErgoTree::sigma_parse_buf(
+                    header,
+                    size,
+                    buf,
+                    deserialize_policy,
+                    &MethodRegistry::builtin(),
+                )
+            })?;
+        Ok(tree.as_ref().clone())
+    }
+
+    /// Parse the tree from the bytes after the header and the size (without the cache)
+    fn sigma_parse_buf(
+        header: ErgoTreeHeader,
+        size: u32,
+        mut buf: Vec<u8>,
+        deserialize_policy: DeserializePolicy,
+        method_registry: &MethodRegistry,
+    ) -> Result<Self, SigmaParsingError> {
         if let Ok((constants, mut tree_bytes)) =
-            ErgoTree::sigma_parse_tree_bytes(buf.as_mut_slice(), header.is_constant_segregation())
+            ErgoTree::sigma_parse_tree_bytes(buf.as_mut_slice(), &header)
         {
             let tree_bytes_copy = tree_bytes.clone();
             let mut tree_reader = SigmaByteReader::new(
                 Cursor::new(&mut tree_bytes[..]),
                 ConstantStore::new(constants.clone()),
-            );
+            )
+            .with_deserialize_policy(deserialize_policy)
+            .with_method_registry(method_registry.clone())
+            .with_tree_version(header.version());
             match Expr::sigma_parse(&mut tree_reader) {
                 Ok(parsed) => Ok(ErgoTree {
                     header,
                     tree: Ok(ParsedTree {
                         constants,
-                        root: Ok(Rc::new(parsed)),
+                        root: Ok(Arc::new(parsed)),
                     }),
                 }),
+                // rejected by the policy, fail the whole parsing instead of keeping the root bytes
+                Err(err) if matches!(err.cause(), SigmaParsingError::DeserializeNotAllowed(_)) => {
+                    Err(err)
+                }
                 Err(err) => Ok(ErgoTree {
                     header,
                     tree: Ok(ParsedTree {
@@ -289,12 +366,33 @@ impl ErgoTree {
         }
     }
 
+    fn sigma_parse_unsized<R: SigmaByteRead>(
+        r: &mut R,
+        header: ErgoTreeHeader,
+    ) -> Result<Self, SigmaParsingError> {
+        let constants = if header.is_constant_segregation() {
+            ErgoTree::sigma_parse_constants(r)?
+        } else {
+            vec![]
+        };
+        r.set_constant_store(ConstantStore::new(constants.clone()));
+        let root = Expr::sigma_parse(r)?;
+        Ok(ErgoTree {
+            header,
+            tree: Ok(ParsedTree {
+                constants,
+                root: Ok(Arc::new(root)),
+            }),
+        })
+    }
+
     fn sigma_parse_tree_bytes(
         bytes: &mut [u8],
-        is_constant_segregation: bool,
+        header: &ErgoTreeHeader,
     ) -> Result<(Vec<Constant>, Vec<u8>), SigmaParsingError> {
-        let mut r = SigmaByteReader::new(Cursor::new(&bytes), ConstantStore::empty());
-        let constants = if is_constant_segregation {
+        let mut r = SigmaByteReader::new(Cursor::new(&bytes), ConstantStore::empty())
+            .with_tree_version(header.version());
+        let constants = if header.is_constant_segregation() {
             ErgoTree::sigma_parse_constants(&mut r)?
         } else {
             vec![]
@@ -321,7 +419,11 @@ impl ErgoTree {
         Ok(constants)
     }
 
-    /// Creates a tree using provided header and root expression
+    /// Creates a tree using provided header and root expression.
+    /// If the header has the constant segregation flag, every `Constant` node of the expression is
+    /// moved to the tree constants (in the order of the expression serialization) and replaced
+    /// with a `ConstantPlaceholder`, as the Scala implementation does, so the tree template is
+    /// the same as of the tree compiled by the Scala compiler.
     pub fn new(header: ErgoTreeHeader, expr: &Expr) -> Result<Self, ErgoTreeError> {
         Ok(if header.is_constant_segregation() {
             let mut data = Vec::new();
@@ -343,7 +445,7 @@ impl ErgoTree {
                 header: ErgoTreeHeader(ErgoTreeHeader::CONSTANT_SEGREGATION_FLAG | header.0),
                 tree: Ok(ParsedTree {
                     constants,
-                    root: Ok(Rc::new(parsed_expr)),
+                    root: Ok(Arc::new(parsed_expr)),
                 }),
             }
         } else {
@@ -351,7 +453,7 @@ impl ErgoTree {
                 header,
                 tree: Ok(ParsedTree {
                     constants: Vec::new(),
-                    root: Ok(Rc::new(expr.clone())),
+                    root: Ok(Arc::new(expr.clone())),
                 }),
             }
         })
@@ -360,8 +462,22 @@ impl ErgoTree {
     /// Reasonable limit for the number of constants allowed in the ErgoTree
     pub const MAX_CONSTANTS_COUNT: usize = 4096;
 
+    /// Tree header (version and flags)
+    pub fn header(&self) -> &ErgoTreeHeader {
+        &self.header
+    }
+
     /// get Expr out of ErgoTree
-    pub fn proposition(&self) -> Result<Rc<Expr>, ErgoTreeError> {
+    pub fn proposition(&self) -> Result<Arc<Expr>, ErgoTreeError> {
+        self.proposition_with_methods(&MethodRegistry::builtin())
+    }
+
+    /// get Expr out of ErgoTree, the tree with the segregated constants is parsed again with the
+    /// given method registry (for the trees parsed with the additional methods)
+    pub fn proposition_with_methods(
+        &self,
+        method_registry: &MethodRegistry,
+    ) -> Result<Arc<Expr>, ErgoTreeError> {
         let tree = self
             .tree
             .clone()
@@ -369,38 +485,117 @@ impl ErgoTree {
         // This tree has ConstantPlaceholder nodes instead of Constant nodes.
         // We need to substitute placeholders with constant values.
         // So far the easiest way to do it is during deserialization (after the serialization)
-        let root = tree.root.map_err(ErgoTreeError::RootParsingError)?;
+        let root = match tree.root {
+            Ok(root) => root,
+            // the root might have failed to parse because of the methods unknown to the built-in
+            // registry, the kept bytes are parsed again with the given one
+            Err(ErgoTreeRootParsingError {
+                root_expr_bytes, ..
+            }) => {
+                let mut sr = SigmaByteReader::new_with_substitute_placeholders(
+                    Cursor::new(root_expr_bytes.clone()),
+                    ConstantStore::new(tree.constants),
+                )
+                .with_method_registry(method_registry.clone())
+                .with_tree_version(self.header.version());
+                let parsed_expr =
+                    Expr::sigma_parse(&mut sr).map_err(|error| ErgoTreeRootParsingError {
+                        root_expr_bytes,
+                        error,
+                    })?;
+                return Ok(Arc::new(parsed_expr));
+            }
+        };
         if self.header.is_constant_segregation() {
             let mut data = Vec::new();
             let cs = ConstantStore::empty();
-            let mut w = SigmaByteWriter::new(&mut data, Some(cs));
+            let mut w =
+                SigmaByteWriter::new(&mut data, Some(cs)).with_tree_version(self.header.version());
             root.sigma_serialize(&mut w)?;
-            let cursor = Cursor::new(&mut data[..]);
-            let mut sr = SigmaByteReader::new_with_substitute_placeholders(
-                cursor,
-                ConstantStore::new(tree.constants),
-            );
-            let parsed_expr =
-                Expr::sigma_parse(&mut sr).map_err(|error| ErgoTreeRootParsingError {
-                    root_expr_bytes: data,
-                    error,
-                })?;
-            Ok(Rc::new(parsed_expr))
+            if *method_registry != MethodRegistry::builtin() {
+                return self.substitute_placeholders(data, tree.constants, method_registry);
+            }
+            // the propositions of the trees with the built-in methods are shared through the
+            // cache, by the hash of the tree bytes (without the size)
+            let mut tree_bytes = Vec::with_capacity(data.len() + 1);
+            let mut w = SigmaByteWriter::new(&mut tree_bytes, None)
+                .with_tree_version(self.header.version());
+            self.header
+                .sigma_serialize(&mut w)
+                .map_err(SigmaSerializationError::from)?;
+            w.put_usize_as_u32_unwrapped(tree.constants.len())
+                .map_err(SigmaSerializationError::from)?;
+            tree.constants
+                .iter()
+                .try_for_each(|c| c.sigma_serialize(&mut w))?;
+            w.write_all(&data).map_err(SigmaSerializationError::from)?;
+            ErgoTreeCache::shared().get_or_insert_proposition_with(&tree_bytes, || {
+                self.substitute_placeholders(data, tree.constants, method_registry)
+            })
         } else {
             Ok(root)
         }
     }
 
+    /// Parse the root expression bytes (with the placeholders) substituting the placeholders with
+    /// the constants
+    fn substitute_placeholders(
+        &self,
+        mut data: Vec<u8>,
+        constants: Vec<Constant>,
+        method_registry: &MethodRegistry,
+    ) -> Result<Arc<Expr>, ErgoTreeError> {
+        let cursor = Cursor::new(&mut data[..]);
+        let mut sr = SigmaByteReader::new_with_substitute_placeholders(
+            cursor,
+            ConstantStore::new(constants),
+        )
+        .with_method_registry(method_registry.clone())
+        .with_tree_version(self.header.version());
+        let parsed_expr = Expr::sigma_parse(&mut sr).map_err(|error| ErgoTreeRootParsingError {
+            root_expr_bytes: data,
+            error,
+        })?;
+        Ok(Arc::new(parsed_expr))
+    }
+
     /// Prints with newlines
     pub fn debug_tree(&self) -> String {
         let tree = format!("{:#?}", self);
         tree
     }
 
+    /// Prints the proposition in an ErgoScript-like syntax (see [`crate::pretty_printer`]). If
+    /// `templates` is given and the tree was created from one of the registered contract
+    /// templates, the output starts with the template name and the tree constants (contract
+    /// parameters).
+    pub fn to_string_pretty(&self, templates: Option<&TemplateRegistry>) -> String {
+        let mut out = String::new();
+        if let Some(name) = templates.and_then(|t| t.detect(self)) {
+            out.push_str(&format!("// template: {}\n", name));
+            let constants_len = self.constants_len().unwrap_or(0);
+            (0..constants_len).for_each(|i| {
+                if let Ok(Some(c)) = self.get_constant(i) {
+                    out.push_str(&format!(
+                        "// constant {}: {} = {}\n",
+                        i,
+                        print_type(&c.tpe),
+                        print_constant(&c)
+                    ));
+                }
+            });
+        }
+        match self.proposition() {
+            Ok(expr) => out.push_str(&print_expr(&expr)),
+            Err(e) => out.push_str(&format!("// cannot parse the tree: {}", e)),
+        }
+        out
+    }
+
     /// Returns Base16-encoded serialized bytes
     pub fn to_base16_bytes(&self) -> Result<String, SigmaSerializationError> {
         let bytes = self.sigma_serialize_bytes()?;
-        Ok(base16::encode_lower(&bytes))
+        Ok(sigma_util::hex::encode_lower(&bytes))
     }
 
     /// Returns constants number as stored in serialized ErgoTree or error if the parsing of
@@ -445,6 +640,80 @@ impl ErgoTree {
     pub fn template_bytes(&self) -> Result<Vec<u8>, ErgoTreeError> {
         self.clone().tree?.template_bytes()
     }
+
+    /// Blake2b256 hash of the template bytes (see [`ErgoTree::template_bytes`]). Trees compiled
+    /// from the same contract with different constant values have the same template hash.
+    pub fn template_hash(&self) -> Result<Digest32, ErgoTreeError> {
+        Ok(blake2b256_hash(&self.template_bytes()?))
+    }
+
+    /// Static complexity estimate of the proposition (see [`complexity`]), with the size of the
+    /// whole serialized tree. This is not the evaluation cost checked by the node.
+    pub fn estimate_complexity(&self) -> Result<Complexity, ErgoTreeError> {
+        let expr = self.proposition()?;
+        Ok(Complexity {
+            size: self.sigma_serialize_bytes()?.len(),
+            ..complexity(expr.as_ref())?
+        })
+    }
+
+    /// Returns true if the tree contains `DeserializeContext` or `DeserializeRegister` nodes,
+    /// i.e. the script executed on evaluation is not fully known from the tree bytes
+    pub fn has_deserialize(&self) -> Result<bool, ErgoTreeError> {
+        let tree = self.tree.clone()?;
+        let root_expr_bytes = tree.template_bytes()?;
+        let mut r = SigmaByteReader::new(
+            Cursor::new(&root_expr_bytes[..]),
+            ConstantStore::new(tree.constants),
+        );
+        Expr::sigma_parse(&mut r).map_err(|error| ErgoTreeRootParsingError {
+            root_expr_bytes: root_expr_bytes.clone(),
+            error,
+        })?;
+        Ok(r.was_deserialize())
+    }
+
+    /// Parse ErgoTree from the bytes with the given policy on Deserialize* nodes.
+    /// With [`DeserializePolicy::Reject`] the trees containing `DeserializeContext` or
+    /// `DeserializeRegister` nodes fail to parse (for the contexts where script bytes must be
+    /// static, e.g. cold signing).
+    pub fn sigma_parse_bytes_with_policy(
+        bytes: &[u8],
+        deserialize_policy: DeserializePolicy,
+    ) -> Result<Self, SigmaParsingError> {
+        let cursor = Cursor::new(bytes);
+        let mut r = SigmaByteReader::new(cursor, ConstantStore::empty())
+            .with_deserialize_policy(deserialize_policy);
+        let header = ErgoTreeHeader::sigma_parse(&mut r)?;
+        let rest_of_the_bytes_len = if header.has_size() {
+            r.get_u32()?
+        } else {
+            bytes.len() as u32 - 1 // skip the header byte
+        };
+        ErgoTree::sigma_parse_sized(&mut r, header, rest_of_the_bytes_len)
+    }
+
+    /// Same as [`ErgoTree::sigma_parse_bytes_with_policy`], without the shared cache
+    pub(crate) fn sigma_parse_bytes_uncached(
+        bytes: &[u8],
+        deserialize_policy: DeserializePolicy,
+    ) -> Result<Self, SigmaParsingError> {
+        let mut r = SigmaByteReader::new(Cursor::new(bytes), ConstantStore::empty());
+        let header = ErgoTreeHeader::sigma_parse(&mut r)?;
+        let size = if header.has_size() {
+            r.get_u32()?
+        } else {
+            bytes.len() as u32 - 1 // skip the header byte
+        };
+        let buf = read_sized(&mut r, size)?;
+        ErgoTree::sigma_parse_buf(
+            header,
+            size,
+            buf,
+            deserialize_policy,
+            &MethodRegistry::builtin(),
+        )
+    }
 }
 
 /// Constants related errors
@@ -496,33 +765,18 @@ impl SigmaSerializable for ErgoTree {
             let tree_size_bytes = r.get_u32()?;
             ErgoTree::sigma_parse_sized(r, header, tree_size_bytes)
         } else {
-            let constants = if header.is_constant_segregation() {
-                ErgoTree::sigma_parse_constants(r)?
-            } else {
-                vec![]
-            };
-            r.set_constant_store(ConstantStore::new(constants.clone()));
-            let root = Expr::sigma_parse(r)?;
-            Ok(ErgoTree {
-                header,
-                tree: Ok(ParsedTree {
-                    constants,
-                    root: Ok(Rc::new(root)),
-                }),
-            })
+            // the tree data is parsed by the rules of the tree version, restored afterwards for
+            // the data that follows the tree (e.g. box registers)
+            let outer_tree_version = r.tree_version();
+            r.set_tree_version(header.version());
+            let res = ErgoTree::sigma_parse_unsized(r, header);
+            r.set_tree_version(outer_tree_version);
+            res
         }
     }
 
     fn sigma_parse_bytes(bytes: &[u8]) -> Result<Self, SigmaParsingError> {
-        let cursor = Cursor::new(bytes);
-        let mut r = SigmaByteReader::new(cursor, ConstantStore::empty());
-        let header = ErgoTreeHeader::sigma_parse(&mut r)?;
-        let rest_of_the_bytes_len = if header.has_size() {
-            r.get_u32()?
-        } else {
-            bytes.len() as u32 - 1 // skip the header byte
-        };
-        ErgoTree::sigma_parse_sized(&mut r, header, rest_of_the_bytes_len)
+        ErgoTree::sigma_parse_bytes_with_policy(bytes, DeserializePolicy::Allow)
     }
 }
 
@@ -545,21 +799,47 @@ impl TryFrom<ErgoTree> for ProveDlog {
     }
 }
 
+/// Reads `size` bytes, the size is not trusted (read from the input), the buffer grows as the
+/// bytes are read
+fn read_sized<R: Read>(r: &mut R, size: u32) -> Result<Vec<u8>, io::Error> {
+    let mut buf = Vec::new();
+    r.by_ref().take(size as u64).read_to_end(&mut buf)?;
+    if buf.len() != size as usize {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(buf)
+}
+
+/// Arbitrary impl
 #[cfg(feature = "arbitrary")]
 #[allow(clippy::unwrap_used)]
-pub(crate) mod arbitrary {
+pub mod arbitrary {
 
     use crate::mir::expr::arbitrary::ArbExprParams;
 
     use super::*;
     use proptest::prelude::*;
 
+    /// Parameters for arbitrary ErgoTree generation
+    #[derive(PartialEq, Eq, Debug, Clone)]
+    pub struct ArbErgoTreeParams {
+        /// Tree depth (levels) of the root expression (for non-P2PK trees)
+        pub depth: usize,
+    }
+
+    impl Default for ArbErgoTreeParams {
+        fn default() -> Self {
+            ArbErgoTreeParams { depth: 1 }
+        }
+    }
+
     impl Arbitrary for ErgoTree {
-        type Parameters = ();
+        type Parameters = ArbErgoTreeParams;
         type Strategy = BoxedStrategy<Self>;
 
-        fn arbitrary_with(_args: Self::Parameters) -> Self::Strategy {
+        fn arbitrary_with(args: Self::Parameters) -> Self::Strategy {
             // make sure that P2PK tree is included
+            // (P2PK trees go first, so failing cases shrink towards them)
             prop_oneof![
                 any::<ProveDlog>().prop_map(|p| ErgoTree::new(
                     ErgoTreeHeader::v0(false),
@@ -574,12 +854,12 @@ pub(crate) mod arbitrary {
                 // SigmaProp with constant segregation using both v0 and v1 versions
                 any_with::<Expr>(ArbExprParams {
                     tpe: SType::SSigmaProp,
-                    depth: 1
+                    depth: args.depth
                 })
                 .prop_map(|e| ErgoTree::new(ErgoTreeHeader::v1(true), &e).unwrap()),
                 any_with::<Expr>(ArbExprParams {
                     tpe: SType::SSigmaProp,
-                    depth: 1
+                    depth: args.depth
                 })
                 .prop_map(|e| ErgoTree::new(ErgoTreeHeader::v0(true), &e).unwrap()),
             ]
@@ -597,7 +877,10 @@ mod tests {
     use super::*;
     use crate::chain::address::AddressEncoder;
     use crate::chain::address::NetworkPrefix;
+    use crate::mir::bool_to_sigma::BoolToSigmaProp;
     use crate::mir::constant::Literal;
+    use crate::mir::deserialize_context::DeserializeContext;
+    use crate::mir::option_is_defined::OptionIsDefined;
     use proptest::prelude::*;
 
     proptest! {
@@ -790,4 +1073,192 @@ mod tests {
         assert_eq!(new_tree.get_constant(8).unwrap().unwrap(), 2i64.into());
         assert!(new_tree.sigma_serialize_bytes().unwrap().len() > 1);
     }
+
+    #[test]
+    fn segregation_matches_scala_tree() {
+        // the same DEX pool tree as in dex_t2tpool_parse, compiled by the Scala compiler
+        let base16_str = "19a3030f0400040204020404040404060406058080a0f6f4acdbe01b058080a0f6f4acdbe01b050004d00f0400040005000500d81ad601b2a5730000d602e4c6a70405d603db63087201d604db6308a7d605b27203730100d606b27204730200d607b27203730300d608b27204730400d609b27203730500d60ab27204730600d60b9973078c720602d60c999973088c720502720bd60d8c720802d60e998c720702720dd60f91720e7309d6108c720a02d6117e721006d6127e720e06d613998c7209027210d6147e720d06d615730ad6167e721306d6177e720c06d6187e720b06d6199c72127218d61a9c72167218d1edededededed93c27201c2a793e4c672010405720292c17201c1a793b27203730b00b27204730c00938c7205018c720601ed938c7207018c720801938c7209018c720a019593720c730d95720f929c9c721172127e7202069c7ef07213069a9c72147e7215067e9c720e720206929c9c721472167e7202069c7ef0720e069a9c72117e7215067e9c721372020695ed720f917213730e907217a19d721972149d721a7211ed9272199c7217721492721a9c72177211";
+        let tree_bytes = base16::decode(base16_str.as_bytes()).unwrap();
+        let tree = ErgoTree::sigma_parse_bytes(&tree_bytes).unwrap();
+        // the proposition has the constants in place of the placeholders
+        let prop = tree.proposition().unwrap();
+        let segregated = ErgoTree::new(tree.header().clone(), &prop).unwrap();
+        assert_eq!(segregated.sigma_serialize_bytes().unwrap(), tree_bytes);
+        assert_eq!(
+            segregated.template_bytes().unwrap(),
+            tree.template_bytes().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_deserialize_policy() {
+        let expr: Expr = BoolToSigmaProp {
+            input: Box::new(
+                DeserializeContext {
+                    tpe: SType::SBoolean,
+                    id: 1,
+                }
+                .into(),
+            ),
+        }
+        .into();
+        let tree = ErgoTree::new(ErgoTreeHeader::v0(true), &expr).unwrap();
+        assert!(tree.has_deserialize().unwrap());
+        let bytes = tree.sigma_serialize_bytes().unwrap();
+        assert_eq!(
+            ErgoTree::sigma_parse_bytes_with_policy(&bytes, DeserializePolicy::Allow).unwrap(),
+            tree
+        );
+        assert_eq!(
+            ErgoTree::sigma_parse_bytes_with_policy(&bytes, DeserializePolicy::Reject),
+            Err(SigmaParsingError::AtPosition {
+                error: Box::new(SigmaParsingError::DeserializeNotAllowed(
+                    "DeserializeContext"
+                )),
+                offset: 2,
+                path: vec!["BoolToSigmaProp", "DeserializeContext"],
+            })
+        );
+        // tree embedded in another structure (e.g. box)
+        let mut r = SigmaByteReader::new(Cursor::new(&bytes[..]), ConstantStore::empty())
+            .with_deserialize_policy(DeserializePolicy::Reject);
+        assert!(ErgoTree::sigma_parse(&mut r).is_err());
+
+        let static_tree = ErgoTree::new(ErgoTreeHeader::v0(true), &true.into()).unwrap();
+        assert!(!static_tree.has_deserialize().unwrap());
+        let static_bytes = static_tree.sigma_serialize_bytes().unwrap();
+        assert_eq!(
+            ErgoTree::sigma_parse_bytes_with_policy(&static_bytes, DeserializePolicy::Reject)
+                .unwrap(),
+            static_tree
+        );
+    }
+
+    #[test]
+    fn estimate_complexity() {
+        // miner fee contract
+        let tree_hex = "1005040004000e36100204a00b08cd0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798ea02d192a39a8cc7a701730073011001020402d19683030193a38cc7b2a57300000193c2b2a57301007473027303830108cdeeac93b1a57304";
+        let bytes = base16::decode(tree_hex).unwrap();
+        let tree = ErgoTree::sigma_parse_bytes(&bytes).unwrap();
+        let c = tree.estimate_complexity().unwrap();
+        assert_eq!(c.size, bytes.len());
+        assert_eq!(c.group_ops, 0);
+        assert_eq!(c.hash_ops, 0);
+        assert_eq!(c.coll_iterations, 0);
+        assert_eq!(c.deserializations, 0);
+        let expr_c = complexity(tree.proposition().unwrap().as_ref()).unwrap();
+        assert_eq!(c.node_count, expr_c.node_count);
+        assert_eq!(c.depth, expr_c.depth);
+
+        let deserialize_tree = ErgoTree::new(
+            ErgoTreeHeader::v0(true),
+            &BoolToSigmaProp {
+                input: Box::new(
+                    DeserializeContext {
+                        tpe: SType::SBoolean,
+                        id: 1,
+                    }
+                    .into(),
+                ),
+            }
+            .into(),
+        )
+        .unwrap();
+        let c = deserialize_tree.estimate_complexity().unwrap();
+        assert_eq!(c.node_count, 2);
+        assert_eq!(c.deserializations, 1);
+    }
+
+    #[test]
+    fn option_constant_requires_tree_v3() {
+        // sigmaProp(Some(1).isDefined)
+        let expr: Expr = BoolToSigmaProp {
+            input: Box::new(
+                OptionIsDefined {
+                    input: Box::new(Constant::from(Some(1i32)).into()),
+                }
+                .into(),
+            ),
+        }
+        .into();
+        let lower_versions = [
+            ErgoTreeVersion::V0,
+            ErgoTreeVersion::V1,
+            ErgoTreeVersion::V2,
+        ];
+        for version in lower_versions.iter() {
+            for constant_segregation in [false, true].iter() {
+                let header = ErgoTreeHeader::with_version(version.clone(), *constant_segregation);
+                let tree = ErgoTree::new(header, &expr).unwrap();
+                assert!(matches!(
+                    tree.sigma_serialize_bytes(),
+                    Err(SigmaSerializationError::NotSupported(_))
+                ));
+            }
+        }
+
+        let v3_tree = ErgoTree::new(
+            ErgoTreeHeader::with_version(ErgoTreeVersion::V3, true),
+            &expr,
+        )
+        .unwrap();
+        let v3_bytes = v3_tree.sigma_serialize_bytes().unwrap();
+        assert_eq!(ErgoTree::sigma_parse_bytes(&v3_bytes).unwrap(), v3_tree);
+        assert_eq!(v3_tree.proposition().unwrap().as_ref(), &expr);
+
+        // the same bytes with the v1 header, the constants fail to parse, the tree keeps the bytes
+        let mut v1_bytes = v3_bytes.clone();
+        v1_bytes[0] = ErgoTreeHeader::v1(true).0;
+        let v1_tree = ErgoTree::sigma_parse_bytes(&v1_bytes).unwrap();
+        assert!(matches!(
+            v1_tree.proposition(),
+            Err(ErgoTreeError::ConstantsParsingError(
+                ErgoTreeConstantsParsingError {
+                    error: SigmaParsingError::NotImplementedYet(_),
+                    ..
+                }
+            ))
+        ));
+        assert_eq!(v1_tree.sigma_serialize_bytes().unwrap(), v1_bytes);
+
+        // the root expression fails to parse without the constant segregation
+        let v3_inline_bytes = ErgoTree::new(
+            ErgoTreeHeader::with_version(ErgoTreeVersion::V3, false),
+            &expr,
+        )
+        .unwrap()
+        .sigma_serialize_bytes()
+        .unwrap();
+        let mut v1_inline_bytes = v3_inline_bytes;
+        v1_inline_bytes[0] = ErgoTreeHeader::v1(false).0;
+        let v1_inline_tree = ErgoTree::sigma_parse_bytes(&v1_inline_bytes).unwrap();
+        assert!(matches!(
+            v1_inline_tree.proposition(),
+            Err(ErgoTreeError::RootParsingError(_))
+        ));
+        assert_eq!(
+            v1_inline_tree.sigma_serialize_bytes().unwrap(),
+            v1_inline_bytes
+        );
+
+        // v0 tree without the size fails to parse
+        let mut v0_bytes = vec![ErgoTreeHeader::v0(true).0];
+        v0_bytes.extend_from_slice(&v3_bytes[2..]);
+        assert!(ErgoTree::sigma_parse_bytes(&v0_bytes).is_err());
+    }
+
+    #[test]
+    fn to_string_pretty_with_template() {
+        // miner fee contract
+        let tree_hex = "1005040004000e36100204a00b08cd0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798ea02d192a39a8cc7a701730073011001020402d19683030193a38cc7b2a57300000193c2b2a57301007473027303830108cdeeac93b1a57304";
+        let tree = ErgoTree::sigma_parse_bytes(&base16::decode(tree_hex).unwrap()).unwrap();
+        let plain = tree.to_string_pretty(None);
+        assert_eq!(plain, print_expr(&tree.proposition().unwrap()));
+        assert!(plain.starts_with("sigmaProp(allOf(Coll(\n"));
+        let annotated = tree.to_string_pretty(Some(&TemplateRegistry::known()));
+        assert!(annotated.starts_with("// template: Miner fee\n// constant 0: Int = 0\n"));
+        assert!(annotated.contains("// constant 3: Int = 1\n"));
+        assert_eq!(annotated.matches("// constant").count(), 5);
+        assert!(annotated.ends_with(&plain));
+    }
 }
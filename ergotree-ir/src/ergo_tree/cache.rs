@@ -0,0 +1,368 @@
+//! Cache of parsed ErgoTrees (by the hash of the tree bytes)
+//!
+//! The same trees (P2PK, miner's fee, popular contracts) are parsed over and over when boxes are
+//! parsed from a block. The cache keeps the recently parsed trees, so that a tree is parsed (and
+//! its public keys are decompressed) once, and the propositions of the trees with the segregated
+//! constants (the placeholders substituted with the constants), so that a tree evaluated again
+//! is not parsed again.
+//!
+//! The cache is thread-safe and the shared instance ([`ErgoTreeCache::shared`]) is used
+//! automatically: by [`ErgoTree::sigma_parse_bytes`], the parsing of the trees with the size
+//! (version 1 and above) from a stream (e.g. boxes and transactions) and
+//! [`ErgoTree::proposition`] (so the evaluation of a tree). Trees without the size (version 0)
+//! parsed from a stream are not cached (their length is not known before the parsing), nor are the
+//! trees parsed with a custom method registry. Set the capacity of the shared cache to 0 to
+//! disable the caching.
+
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::hash::Hash;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::MutexGuard;
+
+use lazy_static::lazy_static;
+
+use crate::chain::digest32::blake2b256_hash;
+use crate::chain::digest32::Digest32;
+use crate::mir::expr::Expr;
+use crate::serialization::sigma_byte_reader::DeserializePolicy;
+use crate::serialization::SigmaParsingError;
+
+use super::ErgoTree;
+use super::ErgoTreeError;
+
+/// Default maximum number of trees (and of propositions) in the cache
+pub const DEFAULT_CAPACITY: usize = 1024;
+
+lazy_static! {
+    static ref SHARED: ErgoTreeCache = ErgoTreeCache::default();
+}
+
+/// Size-bounded map, evicts the oldest entry when the capacity is reached
+#[derive(Debug)]
+struct BoundedMap<K, V> {
+    entries: HashMap<K, V>,
+    order: VecDeque<K>,
+}
+
+impl<K: Hash + Eq + Clone, V: Clone> BoundedMap<K, V> {
+    fn new() -> Self {
+        BoundedMap {
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    fn insert(&mut self, key: K, value: V, capacity: usize) {
+        if capacity == 0 || self.entries.contains_key(&key) {
+            return;
+        }
+        self.order.push_back(key.clone());
+        self.entries.insert(key, value);
+        self.evict(capacity);
+    }
+
+    fn evict(&mut self, capacity: usize) {
+        while self.entries.len() > capacity {
+            match self.order.pop_front() {
+                Some(oldest) => {
+                    self.entries.remove(&oldest);
+                }
+                None => break,
+            }
+        }
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+    }
+}
+
+#[derive(Debug)]
+struct CacheState {
+    capacity: usize,
+    trees: BoundedMap<(Digest32, DeserializePolicy), Arc<ErgoTree>>,
+    propositions: BoundedMap<Digest32, Arc<Expr>>,
+    hits: u64,
+    misses: u64,
+}
+
+impl CacheState {
+    fn count<T>(&mut self, found: Option<T>) -> Option<T> {
+        match found {
+            Some(_) => self.hits += 1,
+            None => self.misses += 1,
+        }
+        found
+    }
+}
+
+/// Thread-safe size-bounded cache of parsed ErgoTrees and of their propositions, evicts the
+/// oldest tree (proposition) when the capacity is reached. Trees that failed to parse are not
+/// cached. The cached trees are shared (`Arc`) between the threads.
+#[derive(Debug)]
+pub struct ErgoTreeCache {
+    state: Mutex<CacheState>,
+}
+
+impl ErgoTreeCache {
+    /// Create an empty cache with the given maximum number of trees (0 disables the caching)
+    pub fn new(capacity: usize) -> Self {
+        ErgoTreeCache {
+            state: Mutex::new(CacheState {
+                capacity,
+                trees: BoundedMap::new(),
+                propositions: BoundedMap::new(),
+                hits: 0,
+                misses: 0,
+            }),
+        }
+    }
+
+    /// The cache used by the parsing and the evaluation of the trees (see the module docs)
+    pub fn shared() -> &'static ErgoTreeCache {
+        &SHARED
+    }
+
+    fn state(&self) -> MutexGuard<'_, CacheState> {
+        // a panic while the lock is held cannot leave the maps inconsistent (only complete
+        // entries are inserted), so the poisoned state is used as is
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Maximum number of trees in the cache
+    pub fn capacity(&self) -> usize {
+        self.state().capacity
+    }
+
+    /// Number of trees in the cache
+    pub fn len(&self) -> usize {
+        self.state().trees.entries.len()
+    }
+
+    /// Returns true if the cache is empty
+    pub fn is_empty(&self) -> bool {
+        let state = self.state();
+        state.trees.entries.is_empty() && state.propositions.entries.is_empty()
+    }
+
+    /// Number of lookups which found the tree (proposition) in the cache
+    pub fn hits(&self) -> u64 {
+        self.state().hits
+    }
+
+    /// Number of lookups which did not find the tree (proposition) in the cache
+    pub fn misses(&self) -> u64 {
+        self.state().misses
+    }
+
+    /// Change the maximum number of trees, evicting the oldest trees if needed
+    pub fn set_capacity(&self, capacity: usize) {
+        let mut state = self.state();
+        state.capacity = capacity;
+        state.trees.evict(capacity);
+        state.propositions.evict(capacity);
+    }
+
+    /// Remove all trees (the hit/miss counters are kept)
+    pub fn clear(&self) {
+        let mut state = self.state();
+        state.trees.clear();
+        state.propositions.clear();
+    }
+
+    /// Returns the tree parsed from the given bytes, from the cache or parsing the bytes (and
+    /// caching the result). The trees are parsed with the builtin method registry.
+    pub fn get_or_parse(
+        &self,
+        bytes: &[u8],
+        deserialize_policy: DeserializePolicy,
+    ) -> Result<Arc<ErgoTree>, SigmaParsingError> {
+        self.get_or_insert_with(bytes, deserialize_policy, || {
+            ErgoTree::sigma_parse_bytes_uncached(bytes, deserialize_policy)
+        })
+    }
+
+    /// Returns the cached tree of the given tree bytes or the tree parsed by `parse` (and caches
+    /// it). The lock is not held while parsing, so the same tree might be parsed concurrently.
+    pub(crate) fn get_or_insert_with<F>(
+        &self,
+        bytes: &[u8],
+        deserialize_policy: DeserializePolicy,
+        parse: F,
+    ) -> Result<Arc<ErgoTree>, SigmaParsingError>
+    where
+        F: FnOnce() -> Result<ErgoTree, SigmaParsingError>,
+    {
+        let key = (blake2b256_hash(bytes), deserialize_policy);
+        {
+            let mut state = self.state();
+            let found = state.trees.entries.get(&key).cloned();
+            if let Some(tree) = state.count(found) {
+                return Ok(tree);
+            }
+        }
+        let tree = Arc::new(parse()?);
+        let mut state = self.state();
+        let capacity = state.capacity;
+        state.trees.insert(key, tree.clone(), capacity);
+        Ok(tree)
+    }
+
+    /// Returns the cached proposition of the given tree bytes or the one made by `substitute`
+    /// (and caches it)
+    pub(crate) fn get_or_insert_proposition_with<F>(
+        &self,
+        tree_bytes: &[u8],
+        substitute: F,
+    ) -> Result<Arc<Expr>, ErgoTreeError>
+    where
+        F: FnOnce() -> Result<Arc<Expr>, ErgoTreeError>,
+    {
+        let key = blake2b256_hash(tree_bytes);
+        {
+            let mut state = self.state();
+            let found = state.propositions.entries.get(&key).cloned();
+            if let Some(expr) = state.count(found) {
+                return Ok(expr);
+            }
+        }
+        let expr = substitute()?;
+        let mut state = self.state();
+        let capacity = state.capacity;
+        state.propositions.insert(key, expr.clone(), capacity);
+        Ok(expr)
+    }
+}
+
+impl Default for ErgoTreeCache {
+    fn default() -> Self {
+        ErgoTreeCache::new(DEFAULT_CAPACITY)
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "arbitrary")]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::ergo_tree::ErgoTreeHeader;
+    use crate::mir::bin_op::BinOp;
+    use crate::mir::bin_op::RelationOp;
+    use crate::mir::constant::Constant;
+    use crate::serialization::SigmaSerializable;
+    use proptest::prelude::*;
+    use std::convert::TryFrom;
+    use std::thread;
+
+    proptest! {
+
+        #![proptest_config(ProptestConfig::with_cases(16))]
+
+        #[test]
+        fn parse_cached(tree in any::<ErgoTree>()) {
+            let bytes = tree.sigma_serialize_bytes().unwrap();
+            let cache = ErgoTreeCache::new(2);
+            let parsed = cache.get_or_parse(&bytes, DeserializePolicy::Allow).unwrap();
+            prop_assert_eq!(parsed.as_ref(), &tree);
+            prop_assert_eq!(cache.get_or_parse(&bytes, DeserializePolicy::Allow).unwrap().as_ref(), &tree);
+            prop_assert_eq!(cache.hits(), 1);
+            prop_assert_eq!(cache.misses(), 1);
+            prop_assert_eq!(cache.len(), 1);
+        }
+    }
+
+    fn tree_bytes(c: Constant) -> Vec<u8> {
+        ErgoTree::try_from(Expr::Const(c))
+            .unwrap()
+            .sigma_serialize_bytes()
+            .unwrap()
+    }
+
+    #[test]
+    fn eviction() {
+        let trees = vec![
+            tree_bytes(true.into()),
+            tree_bytes(false.into()),
+            tree_bytes(1i32.into()),
+        ];
+        let cache = ErgoTreeCache::new(2);
+        for bytes in &trees {
+            cache.get_or_parse(bytes, DeserializePolicy::Allow).unwrap();
+        }
+        assert_eq!(cache.len(), 2);
+        // the oldest tree is evicted
+        cache
+            .get_or_parse(&trees[0], DeserializePolicy::Allow)
+            .unwrap();
+        assert_eq!(cache.hits(), 0);
+        cache
+            .get_or_parse(&trees[0], DeserializePolicy::Allow)
+            .unwrap();
+        assert_eq!(cache.hits(), 1);
+        cache.set_capacity(0);
+        assert!(cache.is_empty());
+        cache
+            .get_or_parse(&trees[0], DeserializePolicy::Allow)
+            .unwrap();
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn policy_is_part_of_the_key() {
+        let cache = ErgoTreeCache::default();
+        let bytes = tree_bytes(true.into());
+        cache
+            .get_or_parse(&bytes, DeserializePolicy::Allow)
+            .unwrap();
+        cache
+            .get_or_parse(&bytes, DeserializePolicy::Reject)
+            .unwrap();
+        assert_eq!(cache.hits(), 0);
+        assert_eq!(cache.len(), 2);
+    }
+
+    #[test]
+    fn shared_between_threads() {
+        let cache = Arc::new(ErgoTreeCache::default());
+        let bytes = tree_bytes(true.into());
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let cache = cache.clone();
+                let bytes = bytes.clone();
+                thread::spawn(move || cache.get_or_parse(&bytes, DeserializePolicy::Allow))
+            })
+            .collect();
+        let trees: Vec<Arc<ErgoTree>> = handles
+            .into_iter()
+            .map(|h| h.join().unwrap().unwrap())
+            .collect();
+        assert!(trees.iter().all(|t| t == &trees[0]));
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.hits() + cache.misses(), 4);
+    }
+
+    #[test]
+    fn used_by_parsing_and_proposition() {
+        // a tree not parsed by the other tests (the shared cache is used by all of them)
+        let expr: Expr = BinOp {
+            kind: RelationOp::Eq.into(),
+            left: Box::new(Constant::from(7_654_321i32).into()),
+            right: Box::new(Constant::from(1_234_567i32).into()),
+        }
+        .into();
+        let tree = ErgoTree::new(ErgoTreeHeader::v1(true), &expr).unwrap();
+        let bytes = tree.sigma_serialize_bytes().unwrap();
+        let shared = ErgoTreeCache::shared();
+        let hits = shared.hits();
+        let parsed = ErgoTree::sigma_parse_bytes(&bytes).unwrap();
+        ErgoTree::sigma_parse_bytes(&bytes).unwrap();
+        assert!(shared.hits() > hits);
+        let hits = shared.hits();
+        let prop = parsed.proposition().unwrap();
+        assert_eq!(parsed.proposition().unwrap(), prop);
+        assert!(shared.hits() > hits);
+    }
+}
@@ -23,10 +23,14 @@ mod has_opcode;
 pub mod base16_str;
 pub mod bigint256;
 pub mod chain;
+pub mod compat;
 pub mod ergo_tree;
 pub mod mir;
+pub mod pretty_printer;
 pub mod serialization;
 pub mod sigma_protocol;
+pub mod source_span;
+pub mod templates;
 pub mod type_check;
 pub mod types;
 pub mod util;
@@ -36,7 +36,9 @@ pub mod coll_size;
 pub mod coll_slice;
 /// Collection of elements
 pub mod collection;
+pub mod complexity;
 pub mod constant;
+pub mod context_vars;
 /// Create proveDHTuple
 pub mod create_prove_dh_tuple;
 /// Create proveDlog from GroupElement(PK)
@@ -289,19 +289,22 @@ mod tests {
         let e = Expr::sigma_parse_bytes(&[0xed, 0x85, 0x03]);
         assert_eq!(
             e,
-            Ok(Expr::BinOp(BinOp {
-                kind: BinOpKind::Logical(LogicalOp::And,),
-                left: Expr::Const(Constant {
-                    tpe: SType::SBoolean,
-                    v: Boolean(true),
-                })
-                .into(),
-                right: Expr::Const(Constant {
-                    tpe: SType::SBoolean,
-                    v: Boolean(true),
-                })
-                .into(),
-            }))
+            Ok(Expr::BinOp(
+                BinOp {
+                    kind: BinOpKind::Logical(LogicalOp::And,),
+                    left: Expr::Const(Constant {
+                        tpe: SType::SBoolean,
+                        v: Boolean(true),
+                    })
+                    .into(),
+                    right: Expr::Const(Constant {
+                        tpe: SType::SBoolean,
+                        v: Boolean(true),
+                    })
+                    .into(),
+                }
+                .into()
+            ))
         );
     }
 }
@@ -0,0 +1,205 @@
+//! Static complexity estimate of the expression (without the evaluation): the size and the counts
+//! of the nodes and of the expensive operations. It is not the node's cost (the weights are not
+//! the node's operation costs), so it compares the scripts with each other but does not predict
+//! whether the evaluation fits the cost limit.
+
+use super::expr::Expr;
+use crate::serialization::SigmaSerializable;
+use crate::serialization::SigmaSerializationError;
+
+/// Complexity of the expression (counts of the nodes and of the expensive operations)
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub struct Complexity {
+    /// Serialized size in bytes
+    pub size: usize,
+    /// Number of the nodes
+    pub node_count: usize,
+    /// Maximum nesting depth of the nodes (1 for a single node)
+    pub depth: usize,
+    /// Group element operations (`exp`, `multiply`, `decodePoint`)
+    pub group_ops: usize,
+    /// Hash calculations (`blake2b256`, `sha256`)
+    pub hash_ops: usize,
+    /// Operations iterating over the collection elements (`map`, `filter`, `fold`, `exists`,
+    /// `forall` and the collection methods taking a function, e.g. `flatMap`), the cost of which
+    /// depends on the collection size
+    pub coll_iterations: usize,
+    /// Scripts deserialized from the context variables and the registers (their complexity is
+    /// unknown until the evaluation)
+    pub deserializations: usize,
+}
+
+/// Weights of the counts in [`Complexity::weighted`]. These are relative weights of this
+/// estimate (a node being a unit), they are not derived from the node's operation costs.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct ComplexityWeights {
+    /// Weight of a serialized byte
+    pub byte: u64,
+    /// Weight of a node
+    pub node: u64,
+    /// Weight of a group element operation
+    pub group_op: u64,
+    /// Weight of a hash calculation
+    pub hash_op: u64,
+    /// Weight of an operation iterating over the collection elements
+    pub coll_iteration: u64,
+    /// Weight of a deserialized script
+    pub deserialization: u64,
+}
+
+impl ComplexityWeights {
+    /// Default weights (the order of magnitude of an operation relative to a node, not
+    /// calibrated against the evaluation cost)
+    pub const DEFAULT: ComplexityWeights = ComplexityWeights {
+        byte: 1,
+        node: 1,
+        group_op: 100,
+        hash_op: 10,
+        coll_iteration: 100,
+        deserialization: 1000,
+    };
+}
+
+impl Default for ComplexityWeights {
+    fn default() -> Self {
+        ComplexityWeights::DEFAULT
+    }
+}
+
+impl Complexity {
+    /// Sum of the counts multiplied by their weights (saturating)
+    pub fn weighted(&self, weights: &ComplexityWeights) -> u64 {
+        [
+            (self.size, weights.byte),
+            (self.node_count, weights.node),
+            (self.group_ops, weights.group_op),
+            (self.hash_ops, weights.hash_op),
+            (self.coll_iterations, weights.coll_iteration),
+            (self.deserializations, weights.deserialization),
+        ]
+        .iter()
+        .fold(0u64, |acc, (count, weight)| {
+            acc.saturating_add((*count as u64).saturating_mul(*weight))
+        })
+    }
+
+    /// Returns true if the weighted complexity is over the limit
+    pub fn exceeds(&self, weights: &ComplexityWeights, limit: u64) -> bool {
+        self.weighted(weights) > limit
+    }
+}
+
+/// Complexity of the expression. Scripts deserialized from the context (or the constants
+/// substituted with `substConstants`) are not analyzed.
+pub fn complexity(expr: &Expr) -> Result<Complexity, SigmaSerializationError> {
+    let mut res = Complexity {
+        size: expr.sigma_serialize_bytes()?.len(),
+        ..Complexity::default()
+    };
+    let mut stack: Vec<(&Expr, usize)> = vec![(expr, 1)];
+    while let Some((e, depth)) = stack.pop() {
+        res.node_count += 1;
+        res.depth = res.depth.max(depth);
+        match e {
+            Expr::Exponentiate(_) | Expr::MultiplyGroup(_) | Expr::DecodePoint(_) => {
+                res.group_ops += 1
+            }
+            Expr::CalcBlake2b256(_) | Expr::CalcSha256(_) => res.hash_ops += 1,
+            Expr::Map(_) | Expr::Filter(_) | Expr::Fold(_) | Expr::Exists(_) | Expr::ForAll(_) => {
+                res.coll_iterations += 1
+            }
+            Expr::MethodCall(mc) if mc.args.iter().any(|a| matches!(a, Expr::FuncValue(_))) => {
+                res.coll_iterations += 1
+            }
+            Expr::DeserializeContext(_) | Expr::DeserializeRegister(_) => res.deserializations += 1,
+            _ => (),
+        }
+        stack.extend(e.children().into_iter().map(|c| (c, depth + 1)));
+    }
+    Ok(res)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::mir::bin_op::ArithOp;
+    use crate::mir::bin_op::BinOp;
+    use crate::mir::calc_blake2b256::CalcBlake2b256;
+    use crate::mir::coll_map::Map;
+    use crate::mir::expr::Expr;
+    use crate::mir::func_value::FuncArg;
+    use crate::mir::func_value::FuncValue;
+    use crate::mir::val_use::ValUse;
+    use crate::types::stype::SType;
+
+    #[test]
+    fn single_node() {
+        let c = complexity(&Expr::Const(1i32.into())).unwrap();
+        assert_eq!(
+            c,
+            Complexity {
+                size: 2,
+                node_count: 1,
+                depth: 1,
+                ..Complexity::default()
+            }
+        );
+        assert_eq!(c.weighted(&ComplexityWeights::DEFAULT), 3);
+    }
+
+    #[test]
+    fn map_with_hash() {
+        // coll.map({ (x: Coll[Byte]) => blake2b256(x) })
+        let arg = FuncArg {
+            idx: 1.into(),
+            tpe: SType::SColl(SType::SByte.into()),
+        };
+        let body: Expr = CalcBlake2b256 {
+            input: Box::new(
+                ValUse {
+                    val_id: 1.into(),
+                    tpe: arg.tpe.clone(),
+                }
+                .into(),
+            ),
+        }
+        .into();
+        let mapper: Expr = FuncValue::new(vec![arg], body).into();
+        let input: Expr = vec![vec![1u8], vec![2u8]].into();
+        let expr: Expr = Map::new(input, mapper).unwrap().into();
+        let c = complexity(&expr).unwrap();
+        assert_eq!(c.node_count, 5);
+        assert_eq!(c.depth, 4);
+        assert_eq!(c.hash_ops, 1);
+        assert_eq!(c.coll_iterations, 1);
+        assert_eq!(c.group_ops, 0);
+        let weights = ComplexityWeights {
+            byte: 0,
+            node: 1,
+            group_op: 0,
+            hash_op: 10,
+            coll_iteration: 100,
+            deserialization: 0,
+        };
+        assert_eq!(c.weighted(&weights), 115);
+        assert!(c.exceeds(&weights, 114));
+        assert!(!c.exceeds(&weights, 115));
+    }
+
+    #[test]
+    fn nested_depth() {
+        let sum = |l: Expr, r: Expr| -> Expr {
+            BinOp {
+                kind: ArithOp::Plus.into(),
+                left: Box::new(l),
+                right: Box::new(r),
+            }
+            .into()
+        };
+        let expr = sum(sum(1i32.into(), sum(2i32.into(), 3i32.into())), 4i32.into());
+        let c = complexity(&expr).unwrap();
+        assert_eq!(c.node_count, 7);
+        assert_eq!(c.depth, 4);
+    }
+}
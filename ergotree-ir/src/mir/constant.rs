@@ -14,12 +14,15 @@ use crate::types::stuple::STuple;
 use crate::types::stuple::TupleItems;
 use crate::types::stype::LiftIntoSType;
 use crate::types::stype::SType;
+use crate::util::IntoVecI8;
 use impl_trait_for_tuples::impl_for_tuples;
 use std::convert::TryFrom;
 use std::convert::TryInto;
 use std::rc::Rc;
+use std::sync::Arc;
 
 mod constant_placeholder;
+pub mod ergo_lit;
 
 pub use constant_placeholder::*;
 
@@ -60,8 +63,9 @@ pub enum Literal {
     GroupElement(Box<EcPoint>),
     /// AVL tree
     AvlTree(Box<AvlTreeData>),
-    /// Ergo box
-    CBox(Rc<ErgoBox>),
+    /// Ergo box (shared with `Arc`, so that the trees with the box constants can be shared between
+    /// the threads)
+    CBox(Arc<ErgoBox>),
     /// Collection
     Coll(CollKind<Literal>),
     /// Option type
@@ -120,21 +124,25 @@ impl From<EcPoint> for Literal {
 
 impl From<Rc<ErgoBox>> for Literal {
     fn from(b: Rc<ErgoBox>) -> Self {
+        Literal::CBox(Arc::new(Rc::try_unwrap(b).unwrap_or_else(|b| (*b).clone())))
+    }
+}
+
+impl From<Arc<ErgoBox>> for Literal {
+    fn from(b: Arc<ErgoBox>) -> Self {
         Literal::CBox(b)
     }
 }
 
 impl From<ErgoBox> for Literal {
     fn from(b: ErgoBox) -> Self {
-        Literal::CBox(Rc::new(b))
+        Literal::CBox(Arc::new(b))
     }
 }
 
 impl From<Vec<u8>> for Literal {
     fn from(v: Vec<u8>) -> Self {
-        Literal::Coll(CollKind::NativeColl(NativeColl::CollByte(
-            v.into_iter().map(|b| b as i8).collect(),
-        )))
+        Literal::Coll(CollKind::NativeColl(NativeColl::CollByte(v.into_vec_i8())))
     }
 }
 
@@ -159,8 +167,22 @@ impl<T: LiftIntoSType + Into<Literal>> From<Option<T>> for Literal {
     }
 }
 
+/// Errors of the [`Value`] conversion into [`Constant`]
+#[derive(Error, PartialEq, Eq, Debug, Clone)]
+pub enum ValueToConstantError {
+    /// Function value (lambda), function types have no data serialization format
+    #[error("function value (Lambda) cannot be converted into a constant")]
+    Lambda,
+    /// `None` value, the element type is unknown
+    #[error("Option without a value (None) cannot be converted into a constant")]
+    EmptyOption,
+    /// Value of a type without the data serialization format (e.g. `Context`)
+    #[error("{0} value cannot be converted into a constant")]
+    NotData(&'static str),
+}
+
 impl TryFrom<Value> for Constant {
-    type Error = String;
+    type Error = ValueToConstantError;
     #[allow(clippy::unwrap_used)]
     fn try_from(value: Value) -> Result<Self, Self::Error> {
         match value {
@@ -201,34 +223,32 @@ impl TryFrom<Value> for Constant {
                     let c = Constant::try_from(v)?;
                     Ok(Constant {
                         v: Literal::Opt(Box::new(Some(c.v))),
-                        tpe: c.tpe,
+                        tpe: SType::SOption(Box::new(c.tpe)),
                     })
                 }
-                None => Err("Can't convert from Value::Opt(None) to Constant".into()),
+                None => Err(ValueToConstantError::EmptyOption),
             },
             Value::Tup(t) => {
-                if let Ok(t) = t.try_mapped::<_, _, String>(|v| {
+                let t = t.try_mapped::<_, _, ValueToConstantError>(|v| {
                     let c = Constant::try_from(v)?;
                     Ok((c.v, c.tpe))
-                }) {
-                    let tuple_items = t.mapped_ref(|(l, _)| l.clone());
-                    let tuple_item_types = SType::STuple(STuple {
-                        items: t.mapped(|(_, tpe)| tpe),
-                    });
-                    Ok(Constant {
-                        v: Literal::Tup(tuple_items),
-                        tpe: tuple_item_types,
-                    })
-                } else {
-                    Err("Can't convert Value:Tup element".into())
-                }
+                })?;
+                let tuple_items = t.mapped_ref(|(l, _)| l.clone());
+                let tuple_item_types = SType::STuple(STuple {
+                    items: t.mapped(|(_, tpe)| tpe),
+                });
+                Ok(Constant {
+                    v: Literal::Tup(tuple_items),
+                    tpe: tuple_item_types,
+                })
             }
             Value::AvlTree(a) => Ok(Constant::from(*a)),
-            Value::Context => Err("Cannot convert Value::Context into Constant".into()),
-            Value::Header(_) => Err("Cannot convert Value::Header(_) into Constant".into()),
-            Value::PreHeader(_) => Err("Cannot convert Value::PreHeader(_) into Constant".into()),
-            Value::Global => Err("Cannot convert Value::Global into Constant".into()),
-            Value::Lambda(_) => Err("Cannot convert Value::Lambda(_) into Constant".into()),
+            Value::UnboundedBigInt(_) => Err(ValueToConstantError::NotData("unbounded BigInt")),
+            Value::Context => Err(ValueToConstantError::NotData("Context")),
+            Value::Header(_) => Err(ValueToConstantError::NotData("Header")),
+            Value::PreHeader(_) => Err(ValueToConstantError::NotData("PreHeader")),
+            Value::Global => Err(ValueToConstantError::NotData("Global")),
+            Value::Lambda(_) => Err(ValueToConstantError::Lambda),
         }
     }
 }
@@ -510,7 +530,7 @@ impl TryExtractFrom<Literal> for SigmaProp {
     }
 }
 
-impl TryExtractFrom<Literal> for Rc<ErgoBox> {
+impl TryExtractFrom<Literal> for Arc<ErgoBox> {
     fn try_extract_from(c: Literal) -> Result<Self, TryExtractFromError> {
         match c {
             Literal::CBox(b) => Ok(b),
@@ -675,20 +695,20 @@ impl TryFrom<Literal> for ProveDlog {
 impl Base16Str for &Constant {
     fn base16_str(&self) -> Result<String, SigmaSerializationError> {
         self.sigma_serialize_bytes()
-            .map(|bytes| base16::encode_lower(&bytes))
+            .map(|bytes| sigma_util::hex::encode_lower(&bytes))
     }
 }
 
 impl Base16Str for Constant {
     fn base16_str(&self) -> Result<String, SigmaSerializationError> {
         self.sigma_serialize_bytes()
-            .map(|bytes| base16::encode_lower(&bytes))
+            .map(|bytes| sigma_util::hex::encode_lower(&bytes))
     }
 }
 
 #[cfg(feature = "arbitrary")]
 #[allow(clippy::unwrap_used)]
-#[allow(clippy::todo)]
+#[allow(clippy::panic)]
 /// Arbitrary impl
 pub(crate) mod arbitrary {
     use std::convert::TryFrom;
@@ -754,18 +774,60 @@ pub(crate) mod arbitrary {
             SType::SGroupElement => any::<EcPoint>().prop_map_into().boxed(),
             SType::SSigmaProp => any::<SigmaProp>().prop_map_into().boxed(),
             SType::SBox => any::<ErgoBox>().prop_map_into().boxed(),
-            // SType::SAvlTree => {}
-            // SType::SOption(tpe) =>
+            SType::SAvlTree => any::<AvlTreeData>().prop_map_into().boxed(),
+            SType::SOption(elem_tpe) => prop_oneof![
+                const_with_type(*elem_tpe.clone()).prop_map(|c| Constant {
+                    tpe: SType::SOption(Box::new(c.tpe)),
+                    v: Literal::Opt(Box::new(Some(c.v))),
+                }),
+                Just(Constant {
+                    tpe: SType::SOption(elem_tpe),
+                    v: Literal::Opt(Box::new(None)),
+                }),
+            ]
+            .boxed(),
             SType::SColl(elem_tpe) => match *elem_tpe {
                 SType::SBoolean => vec(any::<bool>(), 0..400).prop_map_into().boxed(),
                 SType::SByte => vec(any::<u8>(), 0..400).prop_map_into().boxed(),
                 SType::SShort => vec(any::<i16>(), 0..400).prop_map_into().boxed(),
                 SType::SInt => vec(any::<i32>(), 0..400).prop_map_into().boxed(),
                 SType::SLong => vec(any::<i64>(), 0..400).prop_map_into().boxed(),
-                _ => todo!(),
+                elem_tpe => vec(const_with_type(elem_tpe.clone()), 0..4)
+                    .prop_map(move |items| Constant {
+                        tpe: SType::SColl(Box::new(elem_tpe.clone())),
+                        v: Literal::Coll(CollKind::WrappedColl {
+                            elem_tpe: elem_tpe.clone(),
+                            items: items.into_iter().map(|c| c.v).collect(),
+                        }),
+                    })
+                    .boxed(),
             },
-            // SType::STuple(_) => {}
-            _ => todo!("{0:?} not yet implemented", tpe),
+            SType::STuple(STuple { items }) => items
+                .iter()
+                .cloned()
+                .map(const_with_type)
+                .collect::<Vec<_>>()
+                .prop_map(|items| Constant {
+                    tpe: SType::STuple(
+                        STuple::try_from(items.iter().map(|c| c.tpe.clone()).collect::<Vec<_>>())
+                            .unwrap(),
+                    ),
+                    v: Literal::Tup(
+                        items
+                            .into_iter()
+                            .map(|c| c.v)
+                            .collect::<Vec<Literal>>()
+                            .try_into()
+                            .unwrap(),
+                    ),
+                })
+                .boxed(),
+            SType::STypeVar(_)
+            | SType::SFunc(_)
+            | SType::SContext
+            | SType::SHeader
+            | SType::SPreHeader
+            | SType::SGlobal => panic!("{0:?} has no constant values", tpe),
         }
     }
 
@@ -802,18 +864,16 @@ pub(crate) mod arbitrary {
                                 elem.clone().prop_map(|c| coll_from_constant(c, 1)),
                                 elem.clone().prop_map(|c| coll_from_constant(c, 2)),
                                 elem.clone().prop_map(|c| coll_from_constant(c, 10)),
-                                // no Option[_] since it cannot be serialized (for now)
-                                // // Some(v)
-                                // elem.clone().prop_map(|c| Constant {
-                                //     tpe: SType::SOption(Box::new(c.tpe)),
-                                //     v: Value::Opt(Box::new(Some(c.v)))
-                                // }),
-                                // // None
-                                // elem.prop_map(|c| Constant {
-                                //     tpe: SType::SOption(Box::new(c.tpe)),
-                                //     v: Value::Opt(Box::new(None))
-                                // })
-
+                                // Some(v)
+                                elem.clone().prop_map(|c| Constant {
+                                    tpe: SType::SOption(Box::new(c.tpe)),
+                                    v: Literal::Opt(Box::new(Some(c.v)))
+                                }),
+                                // None
+                                elem.clone().prop_map(|c| Constant {
+                                    tpe: SType::SOption(Box::new(c.tpe)),
+                                    v: Literal::Opt(Box::new(None))
+                                }),
                                 // Tuple
                                 vec(elem, 2..=4).prop_map(|constants| Constant {
                                     tpe: SType::STuple(
@@ -863,6 +923,16 @@ pub mod tests {
         assert_eq!(v, v_extracted);
     }
 
+    #[test]
+    fn option_from_value() {
+        let c = Constant::try_from(Value::from(Some(1i64))).unwrap();
+        assert_eq!(c, Some(1i64).into());
+        assert_eq!(
+            Constant::try_from(Value::Opt(Box::new(None))),
+            Err(ValueToConstantError::EmptyOption)
+        );
+    }
+
     proptest! {
 
         #![proptest_config(ProptestConfig::with_cases(8))]
@@ -0,0 +1,241 @@
+//! [`crate::ergo_lit`] macro support
+
+use crate::mir::constant::Constant;
+use crate::mir::constant::Literal;
+use crate::types::stype::SType;
+
+/// Write a [`Constant`] in ErgoScript-like syntax, the type is inferred as in ErgoScript:
+/// - `ergo_lit!(1)` - `Int`, `ergo_lit!(1L)` - `Long`, `ergo_lit!(-1L)`, `ergo_lit!(true)`;
+/// - `ergo_lit!(Coll[Int](1, 2, 3))` - collection of the given element type (`Byte`, `Short`,
+/// `Int`, `Long`, `Boolean` or a nested `Coll`), nested collection items are in parentheses:
+/// `ergo_lit!(Coll[Coll[Byte]]((1, 2), (3)))`;
+/// - `ergo_lit!((1L, true))` - tuple (2 to 4 items) of any of the above;
+/// - `ergo_lit!(Some(1L))`, `ergo_lit!(None[Long])` - option.
+///
+/// An invalid number literal (or a number out of the type range) fails the compilation.
+#[macro_export]
+macro_rules! ergo_lit {
+    // Int or Long literal, parsed at the compile time
+    (@num $lit:expr) => {{
+        const LIT: $crate::mir::constant::ergo_lit::NumLit =
+            match $crate::mir::constant::ergo_lit::parse_num($lit) {
+                Some(lit) => lit,
+                None => panic!("invalid Int or Long literal"),
+            };
+        $crate::mir::constant::Constant::from(LIT)
+    }};
+    // Rust type of the ErgoScript type
+    (@ty Byte) => { i8 };
+    (@ty Short) => { i16 };
+    (@ty Int) => { i32 };
+    (@ty Long) => { i64 };
+    (@ty Boolean) => { bool };
+    (@ty Coll[$($t:tt)+]) => { Vec<$crate::ergo_lit!(@ty $($t)+)> };
+    // SType of the ErgoScript type
+    (@stype Byte) => { $crate::types::stype::SType::SByte };
+    (@stype Short) => { $crate::types::stype::SType::SShort };
+    (@stype Int) => { $crate::types::stype::SType::SInt };
+    (@stype Long) => { $crate::types::stype::SType::SLong };
+    (@stype Boolean) => { $crate::types::stype::SType::SBoolean };
+    (@stype Coll[$($t:tt)+]) => {
+        $crate::types::stype::SType::SColl(Box::new($crate::ergo_lit!(@stype $($t)+)))
+    };
+    // collection items (Rust values)
+    (@coll [Coll $elem:tt] $(($($e:tt)*)),*) => {{
+        let items: Vec<$crate::ergo_lit!(@ty Coll $elem)> =
+            vec![$($crate::ergo_lit!(@coll $elem $($e)*)),*];
+        items
+    }};
+    (@coll [$t:ident] $($e:expr),*) => {{
+        let items: Vec<$crate::ergo_lit!(@ty $t)> = vec![$($e),*];
+        items
+    }};
+    // tuple items, split by commas
+    (@tuple [$($done:expr),*] [$($cur:tt)+] , $($rest:tt)*) => {
+        $crate::ergo_lit!(@tuple [$($done,)* $crate::ergo_lit!($($cur)+)] [] $($rest)*)
+    };
+    (@tuple [$($done:expr),*] [$($cur:tt)*] $next:tt $($rest:tt)*) => {
+        $crate::ergo_lit!(@tuple [$($done),*] [$($cur)* $next] $($rest)*)
+    };
+    (@tuple [$($done:expr),*] [$($cur:tt)+]) => {{
+        let c: $crate::mir::constant::Constant = ($($done,)* $crate::ergo_lit!($($cur)+)).into();
+        c
+    }};
+    (Coll[$($t:tt)+]($($e:tt)*)) => {
+        $crate::mir::constant::Constant::from($crate::ergo_lit!(@coll [$($t)+] $($e)*))
+    };
+    (Some($($e:tt)+)) => {
+        $crate::mir::constant::ergo_lit::some($crate::ergo_lit!($($e)+))
+    };
+    (None[$($t:tt)+]) => {
+        $crate::mir::constant::ergo_lit::none($crate::ergo_lit!(@stype $($t)+))
+    };
+    (($($e:tt)+)) => {
+        $crate::ergo_lit!(@tuple [] [] $($e)+)
+    };
+    (true) => {
+        $crate::mir::constant::Constant::from(true)
+    };
+    (false) => {
+        $crate::mir::constant::Constant::from(false)
+    };
+    (- $l:tt) => {
+        $crate::ergo_lit!(@num concat!("-", stringify!($l)))
+    };
+    ($l:tt) => {
+        $crate::ergo_lit!(@num stringify!($l))
+    };
+}
+
+/// `Int` or `Long` number literal
+#[doc(hidden)]
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum NumLit {
+    /// `Int` literal
+    Int(i32),
+    /// `Long` literal (with `L` suffix)
+    Long(i64),
+}
+
+impl From<NumLit> for Constant {
+    fn from(lit: NumLit) -> Self {
+        match lit {
+            NumLit::Int(v) => v.into(),
+            NumLit::Long(v) => v.into(),
+        }
+    }
+}
+
+/// Parse `Int` or `Long` (with `L` suffix) literal, optionally negative. Returns `None` if the
+/// literal is invalid or out of the type range.
+#[doc(hidden)]
+pub const fn parse_num(lit: &str) -> Option<NumLit> {
+    let bytes = lit.as_bytes();
+    let mut start = 0;
+    let mut end = bytes.len();
+    let negative = end > 0 && bytes[0] == b'-';
+    if negative {
+        start = 1;
+    }
+    let long = end > start && bytes[end - 1] == b'L';
+    if long {
+        end -= 1;
+    }
+    if start == end {
+        return None;
+    }
+    let mut abs: i128 = 0;
+    let mut i = start;
+    while i < end {
+        let digit = bytes[i];
+        if !digit.is_ascii_digit() {
+            return None;
+        }
+        abs = abs * 10 + (digit - b'0') as i128;
+        if abs > i64::MAX as i128 + 1 {
+            return None;
+        }
+        i += 1;
+    }
+    let v = if negative { -abs } else { abs };
+    if long {
+        if v < i64::MIN as i128 || v > i64::MAX as i128 {
+            None
+        } else {
+            Some(NumLit::Long(v as i64))
+        }
+    } else if v < i32::MIN as i128 || v > i32::MAX as i128 {
+        None
+    } else {
+        Some(NumLit::Int(v as i32))
+    }
+}
+
+/// `Option` constant with the given value
+#[doc(hidden)]
+pub fn some(c: Constant) -> Constant {
+    Constant {
+        tpe: SType::SOption(Box::new(c.tpe)),
+        v: Literal::Opt(Box::new(Some(c.v))),
+    }
+}
+
+/// Empty `Option` constant with the given element type
+#[doc(hidden)]
+pub fn none(elem_tpe: SType) -> Constant {
+    Constant {
+        tpe: SType::SOption(Box::new(elem_tpe)),
+        v: Literal::Opt(Box::new(None)),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::types::stype::LiftIntoSType;
+
+    #[test]
+    fn primitives() {
+        assert_eq!(ergo_lit!(1), 1i32.into());
+        assert_eq!(ergo_lit!(1L), 1i64.into());
+        assert_eq!(ergo_lit!(-5L), (-5i64).into());
+        assert_eq!(ergo_lit!(-5), (-5i32).into());
+        assert_eq!(ergo_lit!(true), true.into());
+        assert_eq!(ergo_lit!(false), false.into());
+    }
+
+    #[test]
+    fn number_literals() {
+        assert_eq!(parse_num("1"), Some(NumLit::Int(1)));
+        assert_eq!(parse_num("-2147483648"), Some(NumLit::Int(i32::MIN)));
+        assert_eq!(
+            parse_num("-9223372036854775808L"),
+            Some(NumLit::Long(i64::MIN))
+        );
+        assert_eq!(
+            parse_num("9223372036854775807L"),
+            Some(NumLit::Long(i64::MAX))
+        );
+        assert_eq!(parse_num("2147483648"), None);
+        assert_eq!(parse_num("9223372036854775808L"), None);
+        assert_eq!(parse_num("99999999999999999999999L"), None);
+        assert_eq!(parse_num("1x"), None);
+        assert_eq!(parse_num("-"), None);
+        assert_eq!(parse_num("L"), None);
+        assert_eq!(parse_num(""), None);
+    }
+
+    #[test]
+    fn collections() {
+        assert_eq!(ergo_lit!(Coll[Int](1, 2, 3)), vec![1i32, 2, 3].into());
+        assert_eq!(ergo_lit!(Coll[Long](-1, 2)), vec![-1i64, 2].into());
+        assert_eq!(ergo_lit!(Coll[Byte](1, -2)), vec![1i8, -2].into());
+        assert_eq!(ergo_lit!(Coll[Short]()), Vec::<i16>::new().into());
+        assert_eq!(ergo_lit!(Coll[Boolean](true)), vec![true].into());
+        assert_eq!(
+            ergo_lit!(Coll[Coll[Byte]]((1, 2), (3))),
+            vec![vec![1i8, 2], vec![3i8]].into()
+        );
+        assert_eq!(
+            ergo_lit!(Coll[Coll[Int]]()).tpe,
+            SType::SColl(Box::new(SType::SColl(Box::new(SType::SInt))))
+        );
+    }
+
+    #[test]
+    fn tuples() {
+        assert_eq!(ergo_lit!((1L, true)), (1i64, true).into());
+        assert_eq!(
+            ergo_lit!((-1, Coll[Byte](1, 2), (2L, false))),
+            (-1i32, vec![1i8, 2], (2i64, false)).into()
+        );
+    }
+
+    #[test]
+    fn options() {
+        assert_eq!(ergo_lit!(Some(1L)), Some(1i64).into());
+        assert_eq!(ergo_lit!(Some((1, 2))).tpe, Option::<(i32, i32)>::stype());
+        assert_eq!(ergo_lit!(None[Long]), Option::<i64>::None.into());
+    }
+}
@@ -0,0 +1,108 @@
+//! Context variables read by the expression (dependency of the script on the context extension)
+
+use super::expr::Expr;
+use super::get_var::GetVar;
+use crate::types::stype::SType;
+
+/// Context variables (ids and expected types) read by the expression with `getVar` and
+/// `deserializeContext` (the variable holds the serialized script, `Coll[Byte]`), in the order of
+/// the first use and without duplicates.
+/// Scripts deserialized from the context (or the constants substituted with `substConstants`) are
+/// not analyzed.
+pub fn context_vars(expr: &Expr) -> Vec<GetVar> {
+    let mut res: Vec<GetVar> = Vec::new();
+    let mut stack: Vec<&Expr> = vec![expr];
+    while let Some(e) = stack.pop() {
+        let var = match e {
+            Expr::GetVar(v) => Some(v.clone()),
+            Expr::DeserializeContext(v) => Some(GetVar {
+                var_id: v.id,
+                var_tpe: SType::SColl(SType::SByte.into()),
+            }),
+            _ => None,
+        };
+        if let Some(var) = var {
+            if !res.contains(&var) {
+                res.push(var);
+            }
+        }
+        // reversed, so that the children are visited from left to right
+        stack.extend(e.children().into_iter().rev());
+    }
+    res
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::mir::bin_op::ArithOp;
+    use crate::mir::bin_op::BinOp;
+    use crate::mir::deserialize_context::DeserializeContext;
+    use crate::mir::if_op::If;
+    use crate::mir::option_get::OptionGet;
+    use crate::mir::option_get_or_else::OptionGetOrElse;
+    use crate::mir::unary_op::OneArgOpTryBuild;
+
+    fn get_var(var_id: u8, var_tpe: SType) -> GetVar {
+        GetVar { var_id, var_tpe }
+    }
+
+    #[test]
+    fn no_vars() {
+        assert!(context_vars(&Expr::Const(1i32.into())).is_empty());
+    }
+
+    #[test]
+    fn nested_vars() {
+        let sum: Expr = BinOp {
+            kind: ArithOp::Plus.into(),
+            left: Box::new(
+                OptionGetOrElse::new(get_var(2, SType::SInt).into(), 0i32.into())
+                    .unwrap()
+                    .into(),
+            ),
+            right: Box::new(
+                OptionGet::try_build(get_var(1, SType::SInt).into())
+                    .unwrap()
+                    .into(),
+            ),
+        }
+        .into();
+        let expr: Expr = If {
+            condition: Box::new(
+                OptionGet::try_build(get_var(0, SType::SBoolean).into())
+                    .unwrap()
+                    .into(),
+            ),
+            true_branch: Box::new(sum),
+            false_branch: Box::new(
+                OptionGet::try_build(get_var(2, SType::SInt).into())
+                    .unwrap()
+                    .into(),
+            ),
+        }
+        .into();
+        assert_eq!(
+            context_vars(&expr),
+            vec![
+                get_var(0, SType::SBoolean),
+                get_var(2, SType::SInt),
+                get_var(1, SType::SInt)
+            ]
+        );
+    }
+
+    #[test]
+    fn deserialize_context() {
+        let expr: Expr = DeserializeContext {
+            tpe: SType::SBoolean,
+            id: 5,
+        }
+        .into();
+        assert_eq!(
+            context_vars(&expr),
+            vec![get_var(5, SType::SColl(SType::SByte.into()))]
+        );
+    }
+}
@@ -2,6 +2,7 @@
 
 use crate::has_opcode::HasStaticOpCode;
 use crate::serialization::op_code::OpCode;
+use crate::serialization::sigma_byte_reader::DeserializePolicy;
 use crate::serialization::sigma_byte_reader::SigmaByteRead;
 use crate::serialization::sigma_byte_writer::SigmaByteWrite;
 use crate::serialization::SigmaParsingError;
@@ -39,6 +40,12 @@ impl SigmaSerializable for DeserializeContext {
     }
 
     fn sigma_parse<R: SigmaByteRead>(r: &mut R) -> Result<Self, SigmaParsingError> {
+        if r.deserialize_policy() == DeserializePolicy::Reject {
+            return Err(SigmaParsingError::DeserializeNotAllowed(
+                "DeserializeContext",
+            ));
+        }
+        r.set_deserialize(true);
         let tpe = SType::sigma_parse(r)?;
         let id = r.get_u8()?;
         Ok(Self { tpe, id })
@@ -3,6 +3,7 @@
 use super::expr::Expr;
 use crate::has_opcode::HasStaticOpCode;
 use crate::serialization::op_code::OpCode;
+use crate::serialization::sigma_byte_reader::DeserializePolicy;
 use crate::serialization::sigma_byte_reader::SigmaByteRead;
 use crate::serialization::sigma_byte_writer::SigmaByteWrite;
 use crate::serialization::SigmaParsingError;
@@ -39,6 +40,12 @@ impl SigmaSerializable for DeserializeRegister {
     }
 
     fn sigma_parse<R: SigmaByteRead>(r: &mut R) -> Result<Self, SigmaParsingError> {
+        if r.deserialize_policy() == DeserializePolicy::Reject {
+            return Err(SigmaParsingError::DeserializeNotAllowed(
+                "DeserializeRegister",
+            ));
+        }
+        r.set_deserialize(true);
         let reg = r.get_u8()?;
         let tpe = SType::sigma_parse(r)?;
         let default = Option::<Box<Expr>>::sigma_parse(r)?;
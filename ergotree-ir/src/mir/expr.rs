@@ -73,6 +73,7 @@ use crate::mir::deserialize_register::DeserializeRegister;
 use crate::mir::downcast::Downcast;
 use crate::mir::get_var::GetVar;
 use crate::mir::xor_of::XorOf;
+use crate::source_span::Spanned;
 use bounded_vec::BoundedVecOutOfBounds;
 use derive_more::From;
 use derive_more::TryInto;
@@ -127,7 +128,7 @@ pub enum Expr {
     /// If, non-lazy - evaluate both branches
     If(If),
     /// Binary operation
-    BinOp(BinOp),
+    BinOp(Spanned<BinOp>),
     /// Logical AND
     And(And),
     /// Logical OR
@@ -241,7 +242,7 @@ impl Expr {
             Expr::BlockValue(v) => v.tpe(),
             Expr::ValDef(v) => v.tpe(),
             Expr::ValUse(v) => v.tpe.clone(),
-            Expr::BinOp(v) => v.tpe(),
+            Expr::BinOp(v) => v.expr().tpe(),
             Expr::OptionGet(v) => v.tpe(),
             Expr::ExtractRegisterAs(v) => v.tpe(),
             Expr::Fold(v) => v.tpe(),
@@ -317,6 +318,84 @@ impl Expr {
         let tree = format!("{:#?}", self);
         tree
     }
+
+    /// Direct subexpressions (operands) of the expression
+    pub fn children(&self) -> Vec<&Expr> {
+        match self {
+            Expr::Const(_)
+            | Expr::ConstPlaceholder(_)
+            | Expr::Context
+            | Expr::Global
+            | Expr::GlobalVars(_)
+            | Expr::ValUse(_)
+            | Expr::GetVar(_)
+            | Expr::DeserializeContext(_) => vec![],
+            Expr::Append(v) => vec![&v.input, &v.col_2],
+            Expr::SubstConstants(v) => vec![&v.script_bytes, &v.positions, &v.new_values],
+            Expr::ByteArrayToLong(v) => vec![&v.input],
+            Expr::ByteArrayToBigInt(v) => vec![&v.input],
+            Expr::LongToByteArray(v) => vec![&v.input],
+            Expr::Collection(Collection::BoolConstants(_)) => vec![],
+            Expr::Collection(Collection::Exprs { items, .. }) => items.iter().collect(),
+            Expr::Tuple(v) => v.items.iter().collect(),
+            Expr::CalcBlake2b256(v) => vec![&v.input],
+            Expr::CalcSha256(v) => vec![&v.input],
+            Expr::FuncValue(v) => vec![v.body()],
+            Expr::Apply(v) => std::iter::once(v.func.as_ref()).chain(&v.args).collect(),
+            Expr::MethodCall(v) => std::iter::once(v.obj.as_ref()).chain(&v.args).collect(),
+            Expr::ProperyCall(v) => vec![&v.obj],
+            Expr::BlockValue(v) => v
+                .items
+                .iter()
+                .chain(std::iter::once(v.result.as_ref()))
+                .collect(),
+            Expr::ValDef(v) => vec![&v.rhs],
+            Expr::If(v) => vec![&v.condition, &v.true_branch, &v.false_branch],
+            Expr::BinOp(v) => vec![&v.expr.left, &v.expr.right],
+            Expr::And(v) => vec![&v.input],
+            Expr::Or(v) => vec![&v.input],
+            Expr::Xor(v) => vec![&v.left, &v.right],
+            Expr::Atleast(v) => vec![&v.n_required, &v.expressions],
+            Expr::LogicalNot(v) => vec![&v.input],
+            Expr::Negation(v) => vec![&v.input],
+            Expr::BitInversion(v) => vec![&v.input],
+            Expr::OptionGet(v) => vec![&v.input],
+            Expr::OptionIsDefined(v) => vec![&v.input],
+            Expr::OptionGetOrElse(v) => vec![&v.input, &v.default],
+            Expr::ExtractAmount(v) => vec![&v.input],
+            Expr::ExtractRegisterAs(v) => vec![&v.input],
+            Expr::ExtractBytes(v) => vec![&v.input],
+            Expr::ExtractBytesWithNoRef(v) => vec![&v.input],
+            Expr::ExtractScriptBytes(v) => vec![&v.input],
+            Expr::ExtractCreationInfo(v) => vec![&v.input],
+            Expr::ExtractId(v) => vec![&v.input],
+            Expr::ByIndex(v) => std::iter::once(v.input.as_ref())
+                .chain(std::iter::once(v.index.as_ref()))
+                .chain(v.default.as_deref())
+                .collect(),
+            Expr::SizeOf(v) => vec![&v.input],
+            Expr::Slice(v) => vec![&v.input, &v.from, &v.until],
+            Expr::Fold(v) => vec![&v.input, &v.zero, &v.fold_op],
+            Expr::Map(v) => vec![&v.input, &v.mapper],
+            Expr::Filter(v) => vec![&v.input, &v.condition],
+            Expr::Exists(v) => vec![&v.input, &v.condition],
+            Expr::ForAll(v) => vec![&v.input, &v.condition],
+            Expr::SelectField(v) => vec![&v.input],
+            Expr::BoolToSigmaProp(v) => vec![&v.input],
+            Expr::Upcast(v) => vec![&v.input],
+            Expr::Downcast(v) => vec![&v.input],
+            Expr::CreateProveDlog(v) => vec![&v.input],
+            Expr::CreateProveDhTuple(v) => vec![&v.g, &v.h, &v.u, &v.v],
+            Expr::SigmaPropBytes(v) => vec![&v.input],
+            Expr::DecodePoint(v) => vec![&v.input],
+            Expr::SigmaAnd(v) => v.items.iter().collect(),
+            Expr::SigmaOr(v) => v.items.iter().collect(),
+            Expr::DeserializeRegister(v) => v.default.as_deref().into_iter().collect(),
+            Expr::MultiplyGroup(v) => vec![&v.left, &v.right],
+            Expr::Exponentiate(v) => vec![&v.left, &v.right],
+            Expr::XorOf(v) => vec![&v.input],
+        }
+    }
 }
 
 impl<T: Into<Literal> + LiftIntoSType> From<T> for Expr {
@@ -328,6 +407,12 @@ impl<T: Into<Literal> + LiftIntoSType> From<T> for Expr {
     }
 }
 
+impl From<BinOp> for Expr {
+    fn from(op: BinOp) -> Self {
+        Expr::BinOp(op.into())
+    }
+}
+
 /// Unexpected argument on node construction (i.e non-Option input in OptionGet)
 #[derive(Error, PartialEq, Eq, Debug, Clone)]
 #[error("InvalidArgumentError: {0}")]
@@ -367,7 +452,7 @@ impl<T: TryFrom<Expr>> TryExtractFrom<Expr> for T {
 #[allow(clippy::panic)]
 #[allow(clippy::todo)]
 /// Arbitrary impl
-pub(crate) mod arbitrary {
+pub mod arbitrary {
     use super::*;
     use crate::mir::func_value::FuncArg;
     use crate::sigma_protocol::sigma_boolean::ProveDlog;
@@ -1,20 +1,31 @@
 //! Ergo data type
 
 use std::convert::TryInto;
+use std::io::Cursor;
+use std::io::Read;
 use std::rc::Rc;
+use std::sync::Arc;
 
 use impl_trait_for_tuples::impl_for_tuples;
+use num_bigint::BigInt;
 
 use crate::bigint256::BigInt256;
 use crate::chain::ergo_box::ErgoBox;
 use crate::chain::header::Header;
 use crate::chain::preheader::PreHeader;
+use crate::serialization::constant_store::ConstantStore;
+use crate::serialization::data::DataSerializer;
+use crate::serialization::sigma_byte_reader::SigmaByteReader;
+use crate::serialization::sigma_byte_writer::SigmaByteWriter;
+use crate::serialization::SigmaParsingError;
+use crate::serialization::SigmaSerializationError;
 use crate::sigma_protocol::dlog_group::EcPoint;
 use crate::sigma_protocol::sigma_boolean::SigmaProp;
+use crate::types::sfunc::SFunc;
 use crate::types::stuple::TupleItems;
 use crate::types::stype::LiftIntoSType;
 use crate::types::stype::SType;
-use crate::util::AsVecI8;
+use crate::util::IntoVecI8;
 
 use super::avl_tree_data::AvlTreeData;
 use super::constant::Literal;
@@ -23,6 +34,7 @@ use super::constant::TryExtractFromError;
 use super::constant::TryExtractInto;
 use super::expr::Expr;
 use super::func_value::FuncArg;
+use super::val_def::ValId;
 
 extern crate derive_more;
 use derive_more::From;
@@ -130,6 +142,20 @@ pub struct Lambda {
     pub args: Vec<FuncArg>,
     /// Body
     pub body: Box<Expr>,
+    /// Values of the outer ValDefs (and arguments of the enclosing lambdas) used in the body,
+    /// captured when the lambda is created
+    pub captured: Vec<(ValId, Value)>,
+}
+
+impl Lambda {
+    /// Function type
+    pub fn tpe(&self) -> SType {
+        SType::SFunc(SFunc {
+            t_dom: self.args.iter().map(|fa| fa.tpe.clone()).collect(),
+            t_range: Box::new(self.body.tpe()),
+            tpe_params: vec![],
+        })
+    }
 }
 
 /// Runtime value
@@ -147,6 +173,10 @@ pub enum Value {
     Long(i64),
     /// Big integer
     BigInt(BigInt256),
+    /// Big integer out of the 256-bit range of [`Value::BigInt`], made only by the off-chain
+    /// (non-consensus) analysis evaluation. Not valid in ErgoTree, cannot be serialized.
+    #[from(ignore)]
+    UnboundedBigInt(BigInt),
     /// GroupElement
     GroupElement(Box<EcPoint>),
     /// Sigma property
@@ -178,6 +208,32 @@ impl Value {
     pub fn sigma_prop(prop: SigmaProp) -> Value {
         Value::SigmaProp(Box::new(prop))
     }
+
+    /// Serialized value data (without the type), as the constant value is serialized.
+    /// Fails for the values which cannot be serialized (e.g. `Context`, lambda)
+    pub fn sigma_serialize_data_bytes(&self) -> Result<Vec<u8>, SigmaSerializationError> {
+        let mut data = Vec::new();
+        let mut w = SigmaByteWriter::new(&mut data, None);
+        DataSerializer::sigma_serialize_value(self, &mut w)?;
+        Ok(data)
+    }
+
+    /// Parse the value of the given type from the serialized data (see
+    /// [`Value::sigma_serialize_data_bytes`]), fails if not all bytes are consumed
+    pub fn sigma_parse_data_bytes(tpe: &SType, bytes: &[u8]) -> Result<Value, SigmaParsingError> {
+        let mut r = SigmaByteReader::new(Cursor::new(bytes), ConstantStore::empty());
+        let value = DataSerializer::sigma_parse_value(tpe, &mut r)?;
+        let mut rest = Vec::new();
+        r.read_to_end(&mut rest)?;
+        if !rest.is_empty() {
+            return Err(SigmaParsingError::ValueOutOfBounds(format!(
+                "{} bytes left after parsing a value of type {:?}",
+                rest.len(),
+                tpe
+            )));
+        }
+        Ok(value)
+    }
 }
 
 impl<T: Into<SigmaProp>> From<T> for Value {
@@ -200,7 +256,7 @@ impl From<Vec<i8>> for Value {
 
 impl From<Vec<u8>> for Value {
     fn from(v: Vec<u8>) -> Self {
-        Value::Coll(CollKind::NativeColl(NativeColl::CollByte(v.as_vec_i8())))
+        Value::Coll(CollKind::NativeColl(NativeColl::CollByte(v.into_vec_i8())))
     }
 }
 
@@ -221,7 +277,9 @@ impl From<Literal> for Value {
             Literal::BigInt(b) => Value::BigInt(b),
             Literal::SigmaProp(s) => Value::SigmaProp(s),
             Literal::GroupElement(e) => Value::GroupElement(e),
-            Literal::CBox(b) => Value::CBox(b),
+            Literal::CBox(b) => {
+                Value::CBox(Rc::new(Arc::try_unwrap(b).unwrap_or_else(|b| (*b).clone())))
+            }
             Literal::Coll(coll) => {
                 let converted_coll = match coll {
                     CollKind::NativeColl(n) => CollKind::NativeColl(n),
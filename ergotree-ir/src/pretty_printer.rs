@@ -0,0 +1,560 @@
+//! Pretty printer of the IR in an ErgoScript-like syntax
+//!
+//! The output is for reading (e.g. to inspect the contract of a box), it is not guaranteed to be
+//! compilable: the names of the bound values are `v<id>`, the constant placeholders and the nodes
+//! without a counterpart in ErgoScript are printed as pseudo-functions.
+
+use crate::mir::bin_op::ArithOp;
+use crate::mir::bin_op::BinOpKind;
+use crate::mir::bin_op::BitOp;
+use crate::mir::bin_op::LogicalOp;
+use crate::mir::bin_op::RelationOp;
+use crate::mir::coll_by_index::ByIndex;
+use crate::mir::collection::Collection;
+use crate::mir::constant::Constant;
+use crate::mir::constant::Literal;
+use crate::mir::expr::Expr;
+use crate::mir::global_vars::GlobalVars;
+use crate::mir::value::CollKind;
+use crate::mir::value::NativeColl;
+use crate::sigma_protocol::dlog_group::EcPoint;
+use crate::sigma_protocol::sigma_boolean::SigmaBoolean;
+use crate::sigma_protocol::sigma_boolean::SigmaConjecture;
+use crate::sigma_protocol::sigma_boolean::SigmaProofOfKnowledgeTree;
+use crate::types::stype::SType;
+
+/// Number of spaces per indentation level
+const INDENT: usize = 2;
+
+/// Prints the expression in an ErgoScript-like syntax
+pub fn print_expr(expr: &Expr) -> String {
+    let mut printer = Printer::default();
+    printer.expr(expr);
+    printer.out
+}
+
+/// Prints the constant value in an ErgoScript-like syntax
+pub fn print_constant(constant: &Constant) -> String {
+    literal(&constant.v, &constant.tpe)
+}
+
+/// Prints the type in the ErgoScript syntax (e.g. `Coll[(Int, Box)]`)
+pub fn print_type(tpe: &SType) -> String {
+    match tpe {
+        SType::STypeVar(v) => v.as_string(),
+        SType::SAny => "Any".to_string(),
+        SType::SBoolean => "Boolean".to_string(),
+        SType::SByte => "Byte".to_string(),
+        SType::SShort => "Short".to_string(),
+        SType::SInt => "Int".to_string(),
+        SType::SLong => "Long".to_string(),
+        SType::SBigInt => "BigInt".to_string(),
+        SType::SGroupElement => "GroupElement".to_string(),
+        SType::SSigmaProp => "SigmaProp".to_string(),
+        SType::SBox => "Box".to_string(),
+        SType::SAvlTree => "AvlTree".to_string(),
+        SType::SOption(t) => format!("Option[{}]", print_type(t)),
+        SType::SColl(t) => format!("Coll[{}]", print_type(t)),
+        SType::STuple(t) => format!("({})", join(t.items.iter().map(print_type))),
+        SType::SFunc(f) => format!(
+            "({}) => {}",
+            join(f.t_dom.iter().map(print_type)),
+            print_type(&f.t_range)
+        ),
+        SType::SContext => "Context".to_string(),
+        SType::SHeader => "Header".to_string(),
+        SType::SPreHeader => "PreHeader".to_string(),
+        SType::SGlobal => "Global".to_string(),
+    }
+}
+
+#[derive(Default)]
+struct Printer {
+    out: String,
+    indent: usize,
+}
+
+impl Printer {
+    fn write(&mut self, s: &str) {
+        self.out.push_str(s);
+    }
+
+    fn newline(&mut self) {
+        self.out.push('\n');
+        self.out.push_str(&" ".repeat(self.indent * INDENT));
+    }
+
+    /// `name(arg1, arg2)`
+    fn call(&mut self, name: &str, args: &[&Expr]) {
+        self.write(name);
+        self.write("(");
+        self.list(args.iter().copied());
+        self.write(")");
+    }
+
+    /// `obj.name(arg1, arg2)`, or `obj.name` without the arguments
+    fn method(&mut self, obj: &Expr, name: &str, args: &[&Expr]) {
+        self.operand(obj);
+        self.write(".");
+        self.write(name);
+        if !args.is_empty() {
+            self.write("(");
+            self.list(args.iter().copied());
+            self.write(")");
+        }
+    }
+
+    fn list<'a>(&mut self, items: impl Iterator<Item = &'a Expr>) {
+        for (i, item) in items.enumerate() {
+            if i > 0 {
+                self.write(", ");
+            }
+            self.expr(item);
+        }
+    }
+
+    /// Items on separate lines, joined with the separator
+    fn lines<'a>(&mut self, items: impl Iterator<Item = &'a Expr>, separator: &str) {
+        self.indent += 1;
+        for (i, item) in items.enumerate() {
+            if i > 0 {
+                self.write(separator);
+            }
+            self.newline();
+            self.expr(item);
+        }
+        self.indent -= 1;
+        self.newline();
+    }
+
+    /// Expression in the parentheses unless it's a simple one (so that the operator precedence
+    /// does not need to be considered)
+    fn operand(&mut self, expr: &Expr) {
+        let simple = matches!(
+            expr,
+            Expr::Const(_)
+                | Expr::ConstPlaceholder(_)
+                | Expr::GlobalVars(_)
+                | Expr::ValUse(_)
+                | Expr::Tuple(_)
+                | Expr::Collection(_)
+                | Expr::GetVar(_)
+                | Expr::MethodCall(_)
+                | Expr::ProperyCall(_)
+                | Expr::ExtractAmount(_)
+                | Expr::ExtractRegisterAs(_)
+                | Expr::ExtractBytes(_)
+                | Expr::ExtractBytesWithNoRef(_)
+                | Expr::ExtractScriptBytes(_)
+                | Expr::ExtractCreationInfo(_)
+                | Expr::ExtractId(_)
+                | Expr::ByIndex(_)
+                | Expr::SizeOf(_)
+                | Expr::SelectField(_)
+                | Expr::OptionGet(_)
+                | Expr::OptionIsDefined(_)
+                | Expr::OptionGetOrElse(_)
+                | Expr::Apply(_)
+        );
+        if simple {
+            self.expr(expr);
+        } else {
+            self.write("(");
+            self.expr(expr);
+            self.write(")");
+        }
+    }
+
+    /// `allOf`/`anyOf` of a collection, with the items on separate lines for the literal
+    /// collections
+    fn all_or_any(&mut self, name: &str, input: &Expr) {
+        match input {
+            Expr::Collection(Collection::Exprs { items, .. }) if !items.is_empty() => {
+                self.write(name);
+                self.write("(Coll(");
+                self.lines(items.iter(), ",");
+                self.write("))");
+            }
+            _ => self.call(name, &[input]),
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Append(a) => self.method(&a.input, "append", &[&a.col_2]),
+            Expr::Const(c) => self.write(&print_constant(c)),
+            Expr::ConstPlaceholder(p) => {
+                self.write(&format!("placeholder[{}]({})", print_type(&p.tpe), p.id))
+            }
+            Expr::SubstConstants(s) => self.call(
+                "substConstants",
+                &[&s.script_bytes, &s.positions, &s.new_values],
+            ),
+            Expr::ByteArrayToLong(e) => self.call("byteArrayToLong", &[&e.input]),
+            Expr::ByteArrayToBigInt(e) => self.call("byteArrayToBigInt", &[&e.input]),
+            Expr::LongToByteArray(e) => self.call("longToByteArray", &[&e.input]),
+            Expr::Collection(Collection::BoolConstants(bools)) => {
+                let items: Vec<String> = bools.iter().map(|b| b.to_string()).collect();
+                self.write(&format!("Coll({})", items.join(", ")));
+            }
+            Expr::Collection(Collection::Exprs { elem_tpe, items }) => {
+                if items.is_empty() {
+                    self.write(&format!("Coll[{}]()", print_type(elem_tpe)));
+                } else {
+                    self.write("Coll(");
+                    self.list(items.iter());
+                    self.write(")");
+                }
+            }
+            Expr::Tuple(t) => {
+                self.write("(");
+                self.list(t.items.iter());
+                self.write(")");
+            }
+            Expr::CalcBlake2b256(e) => self.call("blake2b256", &[&e.input]),
+            Expr::CalcSha256(e) => self.call("sha256", &[&e.input]),
+            Expr::GlobalVars(v) => self.write(match v {
+                GlobalVars::Inputs => "INPUTS",
+                GlobalVars::Outputs => "OUTPUTS",
+                GlobalVars::Height => "HEIGHT",
+                GlobalVars::SelfBox => "SELF",
+                GlobalVars::MinerPubKey => "minerPubKey",
+                GlobalVars::GroupGenerator => "groupGenerator",
+            }),
+            Expr::FuncValue(f) => {
+                let args: Vec<String> = f
+                    .args()
+                    .iter()
+                    .map(|a| format!("v{}: {}", a.idx.0, print_type(&a.tpe)))
+                    .collect();
+                self.write(&format!("{{ ({}) =>", args.join(", ")));
+                self.indent += 1;
+                self.newline();
+                self.expr(f.body());
+                self.indent -= 1;
+                self.newline();
+                self.write("}");
+            }
+            Expr::Apply(a) => {
+                self.operand(&a.func);
+                self.write("(");
+                self.list(a.args.iter());
+                self.write(")");
+            }
+            Expr::MethodCall(m) => {
+                let args: Vec<&Expr> = m.args.iter().collect();
+                self.method(&m.obj, m.method.name(), &args);
+                if args.is_empty() {
+                    self.write("()");
+                }
+            }
+            Expr::ProperyCall(p) => self.method(&p.obj, p.method.name(), &[]),
+            Expr::BlockValue(b) => {
+                self.write("{");
+                self.indent += 1;
+                for item in &b.items {
+                    self.newline();
+                    self.expr(item);
+                }
+                self.newline();
+                self.expr(&b.result);
+                self.indent -= 1;
+                self.newline();
+                self.write("}");
+            }
+            Expr::ValDef(v) => {
+                self.write(&format!("val v{} = ", v.id.0));
+                self.expr(&v.rhs);
+            }
+            Expr::ValUse(v) => self.write(&format!("v{}", v.val_id.0)),
+            Expr::If(i) => {
+                self.write("if (");
+                self.expr(&i.condition);
+                self.write(") ");
+                self.operand(&i.true_branch);
+                self.write(" else ");
+                self.operand(&i.false_branch);
+            }
+            Expr::BinOp(op) => {
+                let op = &op.expr;
+                match op.kind {
+                    BinOpKind::Arith(ArithOp::Max) => self.call("max", &[&op.left, &op.right]),
+                    BinOpKind::Arith(ArithOp::Min) => self.call("min", &[&op.left, &op.right]),
+                    kind => {
+                        self.operand(&op.left);
+                        self.write(&format!(" {} ", infix_symbol(kind)));
+                        self.operand(&op.right);
+                    }
+                }
+            }
+            Expr::And(a) => self.all_or_any("allOf", &a.input),
+            Expr::Or(o) => self.all_or_any("anyOf", &o.input),
+            Expr::Xor(x) => self.call("xor", &[&x.left, &x.right]),
+            Expr::Atleast(a) => self.call("atLeast", &[&a.n_required, &a.expressions]),
+            Expr::LogicalNot(n) => {
+                self.write("!");
+                self.operand(&n.input);
+            }
+            Expr::Negation(n) => {
+                self.write("-");
+                self.operand(&n.input);
+            }
+            Expr::BitInversion(b) => {
+                self.write("~");
+                self.operand(&b.input);
+            }
+            Expr::OptionGet(o) => self.method(&o.input, "get", &[]),
+            Expr::OptionIsDefined(o) => self.method(&o.input, "isDefined", &[]),
+            Expr::OptionGetOrElse(o) => self.method(&o.input, "getOrElse", &[&o.default]),
+            Expr::ExtractAmount(e) => self.method(&e.input, "value", &[]),
+            Expr::ExtractRegisterAs(e) => {
+                let name = format!("R{}[{}]", e.register_id, print_type(&e.elem_tpe));
+                self.method(&e.input, &name, &[]);
+            }
+            Expr::ExtractBytes(e) => self.method(&e.input, "bytes", &[]),
+            Expr::ExtractBytesWithNoRef(e) => self.method(&e.input, "bytesWithoutRef", &[]),
+            Expr::ExtractScriptBytes(e) => self.method(&e.input, "propositionBytes", &[]),
+            Expr::ExtractCreationInfo(e) => self.method(&e.input, "creationInfo", &[]),
+            Expr::ExtractId(e) => self.method(&e.input, "id", &[]),
+            Expr::ByIndex(ByIndex {
+                input,
+                index,
+                default,
+            }) => match default {
+                Some(default) => self.method(input, "getOrElse", &[index, default]),
+                None => {
+                    self.operand(input);
+                    self.write("(");
+                    self.expr(index);
+                    self.write(")");
+                }
+            },
+            Expr::SizeOf(s) => self.method(&s.input, "size", &[]),
+            Expr::Slice(s) => self.method(&s.input, "slice", &[&s.from, &s.until]),
+            Expr::Fold(f) => self.method(&f.input, "fold", &[&f.zero, &f.fold_op]),
+            Expr::Map(m) => self.method(&m.input, "map", &[&m.mapper]),
+            Expr::Filter(f) => self.method(&f.input, "filter", &[&f.condition]),
+            Expr::Exists(e) => self.method(&e.input, "exists", &[&e.condition]),
+            Expr::ForAll(f) => self.method(&f.input, "forall", &[&f.condition]),
+            Expr::SelectField(s) => {
+                let name = format!("_{}", s.field_index.zero_based_index() + 1);
+                self.method(&s.input, &name, &[]);
+            }
+            Expr::BoolToSigmaProp(b) => self.call("sigmaProp", &[&b.input]),
+            Expr::Upcast(c) => self.method(&c.input, &conversion(&c.tpe), &[]),
+            Expr::Downcast(c) => self.method(&c.input, &conversion(&c.tpe), &[]),
+            Expr::CreateProveDlog(p) => self.call("proveDlog", &[&p.input]),
+            Expr::CreateProveDhTuple(p) => self.call("proveDHTuple", &[&p.g, &p.h, &p.u, &p.v]),
+            Expr::SigmaPropBytes(s) => self.method(&s.input, "propBytes", &[]),
+            Expr::DecodePoint(d) => self.call("decodePoint", &[&d.input]),
+            Expr::SigmaAnd(s) => {
+                self.write("(");
+                self.lines(s.items.iter(), " &&");
+                self.write(")");
+            }
+            Expr::SigmaOr(s) => {
+                self.write("(");
+                self.lines(s.items.iter(), " ||");
+                self.write(")");
+            }
+            Expr::GetVar(v) => {
+                self.write(&format!("getVar[{}]({})", print_type(&v.var_tpe), v.var_id))
+            }
+            Expr::DeserializeRegister(d) => {
+                let tpe = print_type(&d.tpe);
+                match &d.default {
+                    Some(default) => {
+                        self.write(&format!("executeFromSelfReg[{}]({}, ", tpe, d.reg));
+                        self.expr(default);
+                        self.write(")");
+                    }
+                    None => self.write(&format!("executeFromSelfReg[{}]({})", tpe, d.reg)),
+                }
+            }
+            Expr::DeserializeContext(d) => {
+                self.write(&format!("executeFromVar[{}]({})", print_type(&d.tpe), d.id))
+            }
+            Expr::MultiplyGroup(m) => self.method(&m.left, "multiply", &[&m.right]),
+            Expr::Exponentiate(e) => self.method(&e.left, "exp", &[&e.right]),
+            Expr::XorOf(x) => self.call("xorOf", &[&x.input]),
+        }
+    }
+}
+
+/// Infix operator (`max` and `min` are printed as functions)
+fn infix_symbol(kind: BinOpKind) -> &'static str {
+    match kind {
+        BinOpKind::Arith(ArithOp::Plus) => "+",
+        BinOpKind::Arith(ArithOp::Minus) => "-",
+        BinOpKind::Arith(ArithOp::Multiply) => "*",
+        BinOpKind::Arith(ArithOp::Divide) => "/",
+        BinOpKind::Arith(ArithOp::Max) => "max",
+        BinOpKind::Arith(ArithOp::Min) => "min",
+        BinOpKind::Relation(RelationOp::Eq) => "==",
+        BinOpKind::Relation(RelationOp::NEq) => "!=",
+        BinOpKind::Relation(RelationOp::Ge) => ">=",
+        BinOpKind::Relation(RelationOp::Gt) => ">",
+        BinOpKind::Relation(RelationOp::Le) => "<=",
+        BinOpKind::Relation(RelationOp::Lt) => "<",
+        BinOpKind::Logical(LogicalOp::And) => "&&",
+        BinOpKind::Logical(LogicalOp::Or) => "||",
+        BinOpKind::Logical(LogicalOp::Xor) => "^",
+        BinOpKind::Bit(BitOp::BitOr) => "|",
+        BinOpKind::Bit(BitOp::BitAnd) => "&",
+        BinOpKind::Bit(BitOp::BitXor) => "^",
+    }
+}
+
+/// Numeric conversion method name (`toInt`, `toLong`, ...)
+fn conversion(tpe: &SType) -> String {
+    format!("to{}", print_type(tpe))
+}
+
+fn join(items: impl Iterator<Item = String>) -> String {
+    items.collect::<Vec<String>>().join(", ")
+}
+
+fn hex(bytes: &[u8]) -> String {
+    sigma_util::hex::encode_lower(bytes)
+}
+
+fn point(p: &EcPoint) -> String {
+    format!(
+        "decodePoint(fromBase16(\"{}\"))",
+        hex(&p.to_compressed_bytes())
+    )
+}
+
+fn literal(v: &Literal, tpe: &SType) -> String {
+    let elem_tpe = |i: usize| match tpe {
+        SType::SColl(t) | SType::SOption(t) => (**t).clone(),
+        SType::STuple(t) => t.items.as_slice().get(i).cloned().unwrap_or(SType::SAny),
+        _ => SType::SAny,
+    };
+    match v {
+        Literal::Boolean(b) => b.to_string(),
+        Literal::Byte(b) => format!("{}.toByte", b),
+        Literal::Short(s) => format!("{}.toShort", s),
+        Literal::Int(i) => i.to_string(),
+        Literal::Long(l) => format!("{}L", l),
+        Literal::BigInt(b) => format!("bigInt(\"{}\")", b),
+        Literal::GroupElement(p) => point(p),
+        Literal::SigmaProp(sp) => sigma_boolean(sp.value()),
+        Literal::Coll(CollKind::NativeColl(NativeColl::CollByte(bytes))) => {
+            let bytes: Vec<u8> = bytes.iter().map(|b| *b as u8).collect();
+            format!("fromBase16(\"{}\")", hex(&bytes))
+        }
+        Literal::Coll(CollKind::WrappedColl { elem_tpe, items }) => {
+            if items.is_empty() {
+                format!("Coll[{}]()", print_type(elem_tpe))
+            } else {
+                format!(
+                    "Coll({})",
+                    join(items.iter().map(|item| literal(item, elem_tpe)))
+                )
+            }
+        }
+        Literal::Opt(opt) => match opt.as_ref() {
+            Some(item) => format!("Some({})", literal(item, &elem_tpe(0))),
+            None => format!("none[{}]", print_type(&elem_tpe(0))),
+        },
+        Literal::Tup(items) => format!(
+            "({})",
+            join(
+                items
+                    .iter()
+                    .enumerate()
+                    .map(|(i, item)| literal(item, &elem_tpe(i)))
+            )
+        ),
+        // no literal syntax in ErgoScript
+        Literal::AvlTree(t) => format!("{:?}", t),
+        Literal::CBox(b) => format!("box(\"{}\")", hex(b.box_id().as_ref())),
+    }
+}
+
+fn sigma_boolean(sb: &SigmaBoolean) -> String {
+    let items = |items: &[SigmaBoolean]| join(items.iter().map(sigma_boolean));
+    match sb {
+        SigmaBoolean::TrivialProp(b) => format!("sigmaProp({})", b),
+        SigmaBoolean::ProofOfKnowledge(SigmaProofOfKnowledgeTree::ProveDlog(pd)) => {
+            format!("proveDlog({})", point(&pd.h))
+        }
+        SigmaBoolean::ProofOfKnowledge(SigmaProofOfKnowledgeTree::ProveDhTuple(dht)) => {
+            format!(
+                "proveDHTuple({}, {}, {}, {})",
+                point(&dht.g),
+                point(&dht.h),
+                point(&dht.u),
+                point(&dht.v)
+            )
+        }
+        SigmaBoolean::SigmaConjecture(SigmaConjecture::Cand(c)) => {
+            format!("allOf(Coll({}))", items(c.items.as_slice()))
+        }
+        SigmaBoolean::SigmaConjecture(SigmaConjecture::Cor(c)) => {
+            format!("anyOf(Coll({}))", items(c.items.as_slice()))
+        }
+        SigmaBoolean::SigmaConjecture(SigmaConjecture::Cthreshold(c)) => {
+            format!("atLeast({}, Coll({}))", c.n, items(c.items.as_slice()))
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::chain::address::AddressEncoder;
+    use crate::ergo_tree::ErgoTree;
+    use crate::serialization::SigmaSerializable;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn print_fee_contract() {
+        // miner fee contract
+        let tree_hex = "1005040004000e36100204a00b08cd0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798ea02d192a39a8cc7a701730073011001020402d19683030193a38cc7b2a57300000193c2b2a57301007473027303830108cdeeac93b1a57304";
+        let tree = ErgoTree::sigma_parse_bytes(&base16::decode(tree_hex).unwrap()).unwrap();
+        let printed = print_expr(&tree.proposition().unwrap());
+        assert!(printed.starts_with("sigmaProp(allOf(Coll(\n  "));
+        assert!(printed.contains("\n  HEIGHT == OUTPUTS(placeholder[Int](0)).creationInfo._1,\n"));
+        assert!(printed.contains(".propositionBytes"));
+        assert!(printed.ends_with("\n)))"));
+    }
+
+    #[test]
+    fn print_p2pk() {
+        let address = "9hzP24a2q8KLPVCUk7gdMDXYc7vinmGuxmLp5KU7k9UwptgYBYV";
+        let tree = AddressEncoder::unchecked_parse_address_from_str(address)
+            .unwrap()
+            .script()
+            .unwrap();
+        let printed = print_expr(&tree.proposition().unwrap());
+        assert!(printed.starts_with("proveDlog(decodePoint(fromBase16(\"0"));
+    }
+
+    #[test]
+    fn print_literals() {
+        assert_eq!(print_constant(&1i32.into()), "1");
+        assert_eq!(print_constant(&1i64.into()), "1L");
+        assert_eq!(print_constant(&(-1i8).into()), "-1.toByte");
+        assert_eq!(print_constant(&vec![1i8, 2].into()), "fromBase16(\"0102\")");
+        assert_eq!(print_constant(&vec![1i32, 2].into()), "Coll(1, 2)");
+        assert_eq!(
+            print_constant(&Constant::from(Vec::<i64>::new())),
+            "Coll[Long]()"
+        );
+        assert_eq!(print_constant(&Some(3i32).into()), "Some(3)");
+        assert_eq!(print_constant(&(1i32, 2i64).into()), "(1, 2L)");
+    }
+
+    #[test]
+    fn print_types() {
+        assert_eq!(
+            print_type(&SType::SColl(Box::new(SType::SOption(Box::new(
+                SType::SBox
+            ))))),
+            "Coll[Option[Box]]"
+        );
+    }
+}
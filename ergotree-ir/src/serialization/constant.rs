@@ -38,7 +38,9 @@ impl SigmaSerializable for Constant {
 mod tests {
     use super::*;
     use crate::mir::constant::arbitrary::ArbConstantParams;
+    use crate::mir::constant::Literal;
     use crate::serialization::sigma_serialize_roundtrip;
+    use crate::types::stype::tests::data_type;
     use proptest::prelude::*;
 
     proptest! {
@@ -48,9 +50,29 @@ mod tests {
             prop_assert_eq![sigma_serialize_roundtrip(&v), v];
         }
 
+        #[test]
+        fn ser_roundtrip_any_data_type(
+            v in data_type().prop_flat_map(|tpe| any_with::<Constant>(tpe.into()))
+        ) {
+            prop_assert_eq![sigma_serialize_roundtrip(&v), v];
+        }
+
         #[test]
         fn ser_roundtrip_sbox(v in any_with::<Constant>(ArbConstantParams::Exact(SType::SBox))) {
             prop_assert_eq![sigma_serialize_roundtrip(&v), v];
         }
     }
+
+    #[test]
+    fn ser_roundtrip_option() {
+        let some: Constant = Some(1i64).into();
+        assert_eq![sigma_serialize_roundtrip(&some), some];
+        let none: Constant = None::<i64>.into();
+        assert_eq![sigma_serialize_roundtrip(&none), none];
+        let nested = Constant {
+            tpe: SType::SOption(SType::SOption(SType::SInt.into()).into()),
+            v: Literal::Opt(Box::new(Some(Literal::Opt(Box::new(None))))),
+        };
+        assert_eq![sigma_serialize_roundtrip(&nested), nested];
+    }
 }
@@ -1,10 +1,13 @@
 use crate::chain::ergo_box::ErgoBox;
+use crate::chain::header::Header;
+use crate::ergo_tree::ErgoTreeVersion;
 use crate::mir::avl_tree_data::AvlTreeData;
 use crate::mir::constant::Literal;
 use crate::mir::constant::TryExtractFromError;
 use crate::mir::constant::TryExtractInto;
 use crate::mir::value::CollKind;
 use crate::mir::value::NativeColl;
+use crate::mir::value::Value;
 use crate::serialization::SigmaSerializationError;
 use crate::serialization::SigmaSerializeResult;
 use crate::serialization::{
@@ -15,11 +18,15 @@ use crate::sigma_protocol::{
 };
 use crate::types::stuple;
 use crate::types::stype::SType;
-use crate::util::AsVecU8;
+use crate::util::AsU8Slice;
 
 use super::sigma_byte_writer::SigmaByteWrite;
 use std::convert::TryInto;
-use std::rc::Rc;
+use std::sync::Arc;
+
+/// Option data is serializable since ErgoTree v3 (v6.0 protocol)
+const OPTION_DATA_VERSION: ErgoTreeVersion = ErgoTreeVersion::V3;
+const OPTION_DATA_NOT_SUPPORTED: &str = "Option data before ErgoTree v3";
 
 /// Used to serialize and parse `Literal` and `Value`.
 pub struct DataSerializer {}
@@ -45,7 +52,7 @@ impl DataSerializer {
             Literal::Coll(ct) => match ct {
                 CollKind::NativeColl(NativeColl::CollByte(b)) => {
                     w.put_usize_as_u16_unwrapped(b.len())?;
-                    w.write_all(b.clone().as_vec_u8().as_slice())?
+                    w.write_all(b.as_u8_slice())?
                 }
                 CollKind::WrappedColl {
                     elem_tpe: SType::SBoolean,
@@ -71,11 +78,133 @@ impl DataSerializer {
             Literal::Tup(items) => items
                 .iter()
                 .try_for_each(|i| DataSerializer::sigma_serialize(i, w))?,
-            // unsupported, see
+            // v6.0 format (flag byte, then the value if present), see
             // https://github.com/ScorexFoundation/sigmastate-interpreter/issues/659
-            Literal::Opt(_) => {
-                return Err(SigmaSerializationError::NotSupported("Option"));
+            Literal::Opt(_) if w.tree_version() < OPTION_DATA_VERSION => {
+                return Err(SigmaSerializationError::NotSupported(
+                    OPTION_DATA_NOT_SUPPORTED,
+                ))
+            }
+            Literal::Opt(opt) => match opt.as_ref() {
+                Some(v) => {
+                    w.put_u8(1)?;
+                    DataSerializer::sigma_serialize(v, w)?
+                }
+                None => w.put_u8(0)?,
+            },
+        })
+    }
+
+    /// Serialize the value data in the same format as [`DataSerializer::sigma_serialize`], but
+    /// also for the values without the `Literal` counterpart (e.g. `Header`) as the
+    /// `Global.serialize` method does
+    pub fn sigma_serialize_value<W: SigmaByteWrite>(v: &Value, w: &mut W) -> SigmaSerializeResult {
+        Ok(match v {
+            Value::Boolean(v) => w.put_u8(if *v { 1 } else { 0 })?,
+            Value::Byte(v) => w.put_i8(*v)?,
+            Value::Short(v) => w.put_i16(*v)?,
+            Value::Int(v) => w.put_i32(*v)?,
+            Value::Long(v) => w.put_i64(*v)?,
+            Value::BigInt(v) => {
+                let bytes = v.to_signed_bytes_be();
+                w.put_u16(bytes.len() as u16)?;
+                w.write_all(&bytes)?
+            }
+            Value::GroupElement(ecp) => ecp.sigma_serialize(w)?,
+            Value::SigmaProp(s) => s.value().sigma_serialize(w)?,
+            Value::AvlTree(a) => a.sigma_serialize(w)?,
+            Value::CBox(b) => b.sigma_serialize(w)?,
+            Value::Header(h) => h.sigma_serialize(w)?,
+            Value::Coll(ct) => match ct {
+                CollKind::NativeColl(NativeColl::CollByte(b)) => {
+                    w.put_usize_as_u16_unwrapped(b.len())?;
+                    w.write_all(b.as_u8_slice())?
+                }
+                CollKind::WrappedColl {
+                    elem_tpe: SType::SBoolean,
+                    items: v,
+                } => {
+                    w.put_usize_as_u16_unwrapped(v.len())?;
+                    let maybe_bools: Result<Vec<bool>, TryExtractFromError> = v
+                        .clone()
+                        .into_iter()
+                        .map(|i| i.try_extract_into::<bool>())
+                        .collect();
+                    w.put_bits(maybe_bools?.as_slice())?
+                }
+                CollKind::WrappedColl {
+                    elem_tpe: _,
+                    items: v,
+                } => {
+                    w.put_usize_as_u16_unwrapped(v.len())?;
+                    v.iter()
+                        .try_for_each(|e| DataSerializer::sigma_serialize_value(e, w))?
+                }
+            },
+            Value::Tup(items) => items
+                .iter()
+                .try_for_each(|i| DataSerializer::sigma_serialize_value(i, w))?,
+            Value::Opt(_) if w.tree_version() < OPTION_DATA_VERSION => {
+                return Err(SigmaSerializationError::NotSupported(
+                    OPTION_DATA_NOT_SUPPORTED,
+                ))
+            }
+            Value::Opt(opt) => match opt.as_ref() {
+                Some(v) => {
+                    w.put_u8(1)?;
+                    DataSerializer::sigma_serialize_value(v, w)?
+                }
+                None => w.put_u8(0)?,
+            },
+            Value::UnboundedBigInt(_) => {
+                return Err(SigmaSerializationError::NotSupported(
+                    "unbounded BigInt data",
+                ))
+            }
+            Value::Context => return Err(SigmaSerializationError::NotSupported("Context data")),
+            Value::PreHeader(_) => {
+                return Err(SigmaSerializationError::NotSupported("PreHeader data"))
             }
+            Value::Global => return Err(SigmaSerializationError::NotSupported("Global data")),
+            Value::Lambda(l) => return Err(SigmaSerializationError::FuncType(l.tpe())),
+        })
+    }
+
+    /// Parse the value data of the given type (see [`DataSerializer::sigma_serialize_value`])
+    pub fn sigma_parse_value<R: SigmaByteRead>(
+        tpe: &SType,
+        r: &mut R,
+    ) -> Result<Value, SigmaParsingError> {
+        use SType::*;
+        Ok(match tpe {
+            SHeader => Value::Header(Box::new(Header::sigma_parse(r)?)),
+            SColl(elem_type) if **elem_type != SByte && **elem_type != SBoolean => {
+                let len = r.get_u16()? as usize;
+                let mut items = Vec::with_capacity(len);
+                for _ in 0..len {
+                    items.push(DataSerializer::sigma_parse_value(elem_type, r)?);
+                }
+                Value::Coll(CollKind::WrappedColl {
+                    elem_tpe: *elem_type.clone(),
+                    items,
+                })
+            }
+            STuple(stuple::STuple { items: types }) => {
+                let items = types
+                    .iter()
+                    .map(|tpe| DataSerializer::sigma_parse_value(tpe, r))
+                    .collect::<Result<Vec<Value>, _>>()?;
+                Value::Tup(items.try_into()?)
+            }
+            SOption(_) if r.tree_version() < OPTION_DATA_VERSION => {
+                return Err(SigmaParsingError::NotSupported(OPTION_DATA_NOT_SUPPORTED))
+            }
+            SOption(elem_type) => Value::Opt(Box::new(if r.get_u8()? != 0 {
+                Some(DataSerializer::sigma_parse_value(elem_type, r)?)
+            } else {
+                None
+            })),
+            _ => DataSerializer::sigma_parse(tpe, r)?.into(),
         })
     }
 
@@ -147,12 +276,19 @@ impl DataSerializer {
                 // is correct
                 Literal::Tup(items.try_into()?)
             }
-            SBox => Literal::CBox(Rc::new(ErgoBox::sigma_parse(r)?)),
+            SBox => Literal::CBox(Arc::new(ErgoBox::sigma_parse(r)?)),
             SAvlTree => Literal::AvlTree(Box::new(AvlTreeData::sigma_parse(r)?)),
             STypeVar(_) => return Err(SigmaParsingError::NotSupported("TypeVar data")),
             SAny => return Err(SigmaParsingError::NotSupported("SAny data")),
-            SOption(_) => return Err(SigmaParsingError::NotSupported("SOption data")),
-            SFunc(_) => return Err(SigmaParsingError::NotSupported("SFunc data")),
+            SOption(_) if r.tree_version() < OPTION_DATA_VERSION => {
+                return Err(SigmaParsingError::NotSupported(OPTION_DATA_NOT_SUPPORTED))
+            }
+            SOption(elem_type) => Literal::Opt(Box::new(if r.get_u8()? != 0 {
+                Some(DataSerializer::sigma_parse(elem_type, r)?)
+            } else {
+                None
+            })),
+            SFunc(_) => return Err(SigmaParsingError::FuncTypeData(tpe.clone())),
             SContext => return Err(SigmaParsingError::NotSupported("SContext data")),
             SHeader => return Err(SigmaParsingError::NotSupported("SHeader data")),
             SPreHeader => return Err(SigmaParsingError::NotSupported("SPreHeader data")),
@@ -160,3 +296,163 @@ impl DataSerializer {
         })
     }
 }
+
+#[cfg(test)]
+#[cfg(feature = "arbitrary")]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::mir::constant::Constant;
+    use crate::mir::constant::ValueToConstantError;
+    use crate::mir::expr::Expr;
+    use crate::mir::func_value::FuncArg;
+    use crate::mir::val_def::ValId;
+    use crate::mir::value::Lambda;
+    use crate::serialization::sigma_byte_reader::from_bytes;
+    use crate::serialization::sigma_byte_writer::SigmaByteWriter;
+    use crate::types::sfunc::SFunc;
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+    use std::convert::TryFrom;
+
+    fn serialize(c: &Constant) -> Vec<u8> {
+        let mut data = Vec::new();
+        let mut w = SigmaByteWriter::new(&mut data, None);
+        DataSerializer::sigma_serialize(&c.v, &mut w).unwrap();
+        data
+    }
+
+    fn serialize_value(tpe: &SType, v: &Value) -> Vec<u8> {
+        let mut data = Vec::new();
+        let mut w = SigmaByteWriter::new(&mut data, None);
+        DataSerializer::sigma_serialize_value(v, &mut w).unwrap();
+        let value = DataSerializer::sigma_parse_value(tpe, &mut from_bytes(&data)).unwrap();
+        assert_eq!(&value, v);
+        data
+    }
+
+    #[test]
+    fn option_format() {
+        let some: Constant = Some(1i32).into();
+        assert_eq!(serialize(&some), vec![1, 2]);
+        let none: Constant = None::<i32>.into();
+        assert_eq!(serialize(&none), vec![0]);
+        let some_none = Constant {
+            tpe: SType::SOption(SType::SOption(SType::SInt.into()).into()),
+            v: Literal::Opt(Box::new(Some(Literal::Opt(Box::new(None))))),
+        };
+        assert_eq!(serialize(&some_none), vec![1, 0]);
+    }
+
+    #[test]
+    fn option_requires_tree_v3() {
+        let some: Constant = Some(1i32).into();
+        let lower_versions = [
+            ErgoTreeVersion::V0,
+            ErgoTreeVersion::V1,
+            ErgoTreeVersion::V2,
+        ];
+        for version in lower_versions.iter() {
+            let mut data = Vec::new();
+            let mut w = SigmaByteWriter::new(&mut data, None).with_tree_version(version.clone());
+            assert_eq!(
+                DataSerializer::sigma_serialize(&some.v, &mut w),
+                Err(SigmaSerializationError::NotSupported(
+                    OPTION_DATA_NOT_SUPPORTED
+                ))
+            );
+            let value: Value = some.v.clone().into();
+            assert!(DataSerializer::sigma_serialize_value(&value, &mut w).is_err());
+            let mut r = from_bytes(vec![1u8, 2]).with_tree_version(version.clone());
+            assert!(DataSerializer::sigma_parse(&some.tpe, &mut r).is_err());
+            let mut r = from_bytes(vec![1u8, 2]).with_tree_version(version.clone());
+            assert!(DataSerializer::sigma_parse_value(&some.tpe, &mut r).is_err());
+        }
+        let mut r = from_bytes(vec![1u8, 2]).with_tree_version(ErgoTreeVersion::V3);
+        assert_eq!(
+            DataSerializer::sigma_parse(&some.tpe, &mut r).unwrap(),
+            some.v
+        );
+    }
+
+    #[test]
+    fn func_type_error() {
+        let tpe = SType::SFunc(SFunc::new(vec![SType::SInt], SType::SInt));
+        assert_eq!(
+            DataSerializer::sigma_parse_value(&tpe, &mut from_bytes(&[0u8])),
+            Err(SigmaParsingError::FuncTypeData(tpe.clone()))
+        );
+        let lambda = Value::Lambda(Lambda {
+            args: vec![FuncArg {
+                idx: ValId(1),
+                tpe: SType::SInt,
+            }],
+            body: Box::new(Expr::Const(1i32.into())),
+            captured: vec![],
+        });
+        let mut data = Vec::new();
+        let mut w = SigmaByteWriter::new(&mut data, None);
+        assert_eq!(
+            DataSerializer::sigma_serialize_value(&lambda, &mut w),
+            Err(SigmaSerializationError::FuncType(tpe))
+        );
+        assert_eq!(
+            Constant::try_from(lambda),
+            Err(ValueToConstantError::Lambda)
+        );
+    }
+
+    #[test]
+    fn coll_bool_bit_packed() {
+        // length, then the bits packed into bytes (first element in the lowest bit), as the node
+        // serializes them
+        assert_eq!(serialize(&Vec::<bool>::new().into()), vec![0]);
+        assert_eq!(serialize(&vec![true, false, true].into()), vec![3, 0b101]);
+        assert_eq!(
+            serialize(&vec![true; 9].into()),
+            vec![9, 0b1111_1111, 0b0000_0001]
+        );
+        assert_eq!(
+            serialize(&vec![false, false, false, false, false, false, false, true].into()),
+            vec![8, 0b1000_0000]
+        );
+        // Coll[Boolean] type code and the data
+        let c: Constant = vec![true, false, true].into();
+        assert_eq!(c.sigma_serialize_bytes().unwrap(), vec![0x0d, 3, 0b101]);
+        assert_eq!(Constant::sigma_parse_bytes(&[0x0d, 3, 0b101]).unwrap(), c);
+        // nested collections are packed per inner collection
+        let nested: Constant = vec![vec![true, true], vec![], vec![false, true, true]].into();
+        let bytes = serialize(&nested);
+        assert_eq!(bytes, vec![3, 2, 0b11, 0, 3, 0b110]);
+        assert_eq!(
+            DataSerializer::sigma_parse(&nested.tpe, &mut from_bytes(&bytes)).unwrap(),
+            nested.v
+        );
+    }
+
+    proptest! {
+
+        #[test]
+        fn coll_bool_roundtrip(bools in vec(any::<bool>(), 0..100)) {
+            let c: Constant = bools.clone().into();
+            let bytes = serialize(&c);
+            // VLQ length (one byte for < 128 items) and a bit per item
+            prop_assert_eq!(bytes.len(), 1 + (bools.len() + 7) / 8);
+            let parsed = DataSerializer::sigma_parse(&c.tpe, &mut from_bytes(&bytes)).unwrap();
+            prop_assert_eq!(parsed, c.v.clone());
+            prop_assert_eq!(serialize_value(&c.tpe, &c.v.into()), bytes);
+        }
+
+        #[test]
+        fn option_roundtrip(c in any::<Constant>(), present in any::<bool>()) {
+            let tpe = SType::SOption(c.tpe.clone().into());
+            let v = if present { Some(c.v) } else { None };
+            let opt = Constant { tpe, v: Literal::Opt(Box::new(v)) };
+            let bytes = serialize(&opt);
+            prop_assert_eq!(bytes[0], present as u8);
+            let parsed = DataSerializer::sigma_parse(&opt.tpe, &mut from_bytes(&bytes)).unwrap();
+            prop_assert_eq!(parsed, opt.v.clone());
+            prop_assert_eq!(serialize_value(&opt.tpe, &opt.v.into()), bytes);
+        }
+    }
+}
@@ -81,7 +81,16 @@ use crate::serialization::types::TypeCode;
 impl Expr {
     /// Parse expression from byte stream. This function should be used instead of
     /// `sigma_parse` when tag byte is already read for look-ahead
+    /// On error the stream position and the path of the nodes being parsed are attached
+    /// (see [`SigmaParsingError::AtPosition`])
     pub fn parse_with_tag<R: SigmaByteRead>(r: &mut R, tag: u8) -> Result<Self, SigmaParsingError> {
+        r.enter_node(node_name(tag));
+        let res = Self::parse_node(r, tag).map_err(|e| r.error_at_position(e));
+        r.exit_node();
+        res
+    }
+
+    fn parse_node<R: SigmaByteRead>(r: &mut R, tag: u8) -> Result<Self, SigmaParsingError> {
         let res = if tag <= OpCode::LAST_CONSTANT_CODE.value() {
             let t_code = TypeCode::parse(tag)?;
             let constant = Constant::parse_with_type_code(r, t_code)?;
@@ -196,6 +205,103 @@ impl Expr {
     }
 }
 
+/// Name of the node with the given tag (op code or constant type code) for the parse path
+fn node_name(tag: u8) -> &'static str {
+    if tag <= OpCode::LAST_CONSTANT_CODE.value() {
+        "Constant"
+    } else {
+        match OpCode::parse(tag) {
+            OpCode::APPEND => "Append",
+            OpCode::FOLD => "Fold",
+            ConstantPlaceholder::OP_CODE => "ConstantPlaceholder",
+            OpCode::HEIGHT => "Height",
+            OpCode::SELF_BOX => "Self",
+            OpCode::INPUTS => "Inputs",
+            OpCode::OUTPUTS => "Outputs",
+            OpCode::MINER_PUBKEY => "MinerPubKey",
+            OpCode::GROUP_GENERATOR => "GroupGenerator",
+            OpCode::GLOBAL => "Global",
+            OpCode::PROPERTY_CALL => "PropertyCall",
+            OpCode::METHOD_CALL => "MethodCall",
+            OpCode::CONTEXT => "Context",
+            OptionGet::OP_CODE => "OptionGet",
+            OptionIsDefined::OP_CODE => "OptionIsDefined",
+            OptionGetOrElse::OP_CODE => "OptionGetOrElse",
+            ExtractRegisterAs::OP_CODE => "ExtractRegisterAs",
+            ExtractScriptBytes::OP_CODE => "ExtractScriptBytes",
+            ExtractBytes::OP_CODE => "ExtractBytes",
+            ExtractBytesWithNoRef::OP_CODE => "ExtractBytesWithNoRef",
+            ExtractCreationInfo::OP_CODE => "ExtractCreationInfo",
+            ExtractId::OP_CODE => "ExtractId",
+            OpCode::EQ => "EQ",
+            OpCode::NEQ => "NEQ",
+            Negation::OP_CODE => "Negation",
+            BitInversion::OP_CODE => "BitInversion",
+            OpCode::LOGICAL_NOT => "LogicalNot",
+            OpCode::BIN_AND => "BinAnd",
+            OpCode::BIN_OR => "BinOr",
+            OpCode::BIN_XOR => "BinXor",
+            OpCode::GT => "GT",
+            OpCode::LT => "LT",
+            OpCode::GE => "GE",
+            OpCode::LE => "LE",
+            OpCode::PLUS => "Plus",
+            OpCode::MINUS => "Minus",
+            OpCode::MULTIPLY => "Multiply",
+            OpCode::DIVISION => "Division",
+            OpCode::MAX => "Max",
+            OpCode::MIN => "Min",
+            OpCode::BIT_OR => "BitOr",
+            OpCode::BIT_AND => "BitAnd",
+            OpCode::BIT_XOR => "BitXor",
+            OpCode::BLOCK_VALUE => "BlockValue",
+            OpCode::FUNC_VALUE => "FuncValue",
+            OpCode::APPLY => "Apply",
+            OpCode::VAL_DEF => "ValDef",
+            OpCode::VAL_USE => "ValUse",
+            ExtractAmount::OP_CODE => "ExtractAmount",
+            OpCode::SELECT_FIELD => "SelectField",
+            OpCode::CALC_BLAKE2B256 => "CalcBlake2b256",
+            CalcSha256::OP_CODE => "CalcSha256",
+            And::OP_CODE => "And",
+            Or::OP_CODE => "Or",
+            Xor::OP_CODE => "Xor",
+            Atleast::OP_CODE => "Atleast",
+            OpCode::COLL => "Coll",
+            OpCode::COLL_OF_BOOL_CONST => "CollOfBoolConst",
+            Map::OP_CODE => "Map",
+            Filter::OP_CODE => "Filter",
+            Exists::OP_CODE => "Exists",
+            ForAll::OP_CODE => "ForAll",
+            BoolToSigmaProp::OP_CODE => "BoolToSigmaProp",
+            Upcast::OP_CODE => "Upcast",
+            Downcast::OP_CODE => "Downcast",
+            If::OP_CODE => "If",
+            ByIndex::OP_CODE => "ByIndex",
+            SizeOf::OP_CODE => "SizeOf",
+            Slice::OP_CODE => "Slice",
+            CreateProveDlog::OP_CODE => "CreateProveDlog",
+            CreateProveDhTuple::OP_CODE => "CreateProveDhTuple",
+            SigmaPropBytes::OP_CODE => "SigmaPropBytes",
+            Tuple::OP_CODE => "Tuple",
+            DecodePoint::OP_CODE => "DecodePoint",
+            SubstConstants::OP_CODE => "SubstConstants",
+            ByteArrayToLong::OP_CODE => "ByteArrayToLong",
+            ByteArrayToBigInt::OP_CODE => "ByteArrayToBigInt",
+            LongToByteArray::OP_CODE => "LongToByteArray",
+            SigmaAnd::OP_CODE => "SigmaAnd",
+            SigmaOr::OP_CODE => "SigmaOr",
+            GetVar::OP_CODE => "GetVar",
+            DeserializeRegister::OP_CODE => "DeserializeRegister",
+            DeserializeContext::OP_CODE => "DeserializeContext",
+            MultiplyGroup::OP_CODE => "MultiplyGroup",
+            Exponentiate::OP_CODE => "Exponentiate",
+            XorOf::OP_CODE => "XorOf",
+            _ => "Unknown",
+        }
+    }
+}
+
 trait SigmaSerializableWithOpCode: SigmaSerializable + HasOpCode {
     fn sigma_serialize_w_opcode<W: SigmaByteWrite>(&self, w: &mut W) -> SigmaSerializeResult {
         self.op_code().sigma_serialize(w)?;
@@ -231,8 +337,8 @@ impl SigmaSerializable for Expr {
             Expr::OptionGet(v) => v.sigma_serialize_w_opcode(w),
             Expr::ExtractRegisterAs(v) => v.sigma_serialize_w_opcode(w),
             Expr::BinOp(op) => {
-                op.op_code().sigma_serialize(w)?;
-                bin_op_sigma_serialize(op, w)
+                op.expr().op_code().sigma_serialize(w)?;
+                bin_op_sigma_serialize(op.expr(), w)
             }
             Expr::BlockValue(op) => op.sigma_serialize_w_opcode(w),
             Expr::ValUse(op) => op.sigma_serialize_w_opcode(w),
@@ -312,6 +418,33 @@ mod tests {
         assert_eq!(sigma_serialize_roundtrip(&e), e);
     }
 
+    #[test]
+    fn parse_error_position() {
+        let e: Expr = SizeOf {
+            input: Box::new(Expr::Const(vec![1u8, 2, 3].into())),
+        }
+        .into();
+        let bytes = e.sigma_serialize_bytes().unwrap();
+        assert_eq!(bytes.len(), 6);
+        let err = Expr::sigma_parse_bytes(&bytes[..5]).unwrap_err();
+        match &err {
+            SigmaParsingError::AtPosition {
+                error,
+                offset,
+                path,
+            } => {
+                assert!(matches!(**error, SigmaParsingError::Io(_)));
+                assert_eq!(*offset, 5);
+                assert_eq!(path, &vec!["SizeOf", "Constant"]);
+            }
+            _ => panic!("unexpected error: {:?}", err),
+        }
+        assert!(matches!(err.cause(), SigmaParsingError::Io(_)));
+        assert!(err
+            .to_string()
+            .ends_with("at offset 0x5 while parsing SizeOf->Constant"));
+    }
+
     proptest! {
 
         #[test]
@@ -1,13 +1,18 @@
+use std::collections::HashMap;
+
+use crate::compat::VersionedFeature;
 use crate::mir::expr::Expr;
 use crate::mir::method_call::MethodCall;
 use crate::types::smethod::MethodId;
-use crate::types::smethod::SMethod;
+use crate::types::stype::SType;
+use crate::types::stype_param::STypeVar;
 
 use super::sigma_byte_reader::SigmaByteRead;
 use super::sigma_byte_writer::SigmaByteWrite;
 use super::types::TypeCode;
 use super::SigmaParsingError;
 use super::SigmaSerializable;
+use super::SigmaSerializationError;
 use super::SigmaSerializeResult;
 
 impl SigmaSerializable for MethodCall {
@@ -16,6 +21,19 @@ impl SigmaSerializable for MethodCall {
         self.method.method_id().sigma_serialize(w)?;
         self.obj.sigma_serialize(w)?;
         self.args.sigma_serialize(w)?;
+        if w.tree_version() >= VersionedFeature::MethodCallTypeArgs.min_version() {
+            // types of the type arguments not inferred from the object and the arguments, as
+            // the v6.0 `MethodCallSerializer` does
+            self.method
+                .explicit_type_subst()
+                .map_err(|_| {
+                    SigmaSerializationError::NotSupported(
+                        "MethodCall: explicit type arguments are not specified",
+                    )
+                })?
+                .iter()
+                .try_for_each(|tpe| tpe.sigma_serialize(w))?;
+        }
         Ok(())
     }
 
@@ -24,8 +42,21 @@ impl SigmaSerializable for MethodCall {
         let method_id = MethodId::sigma_parse(r)?;
         let obj = Expr::sigma_parse(r)?;
         let args = Vec::<Expr>::sigma_parse(r)?;
+        let method = r.method_registry().from_ids(type_id, method_id)?;
+        let explicit_subst =
+            if r.tree_version() >= VersionedFeature::MethodCallTypeArgs.min_version() {
+                method
+                    .explicit_type_args()
+                    .into_iter()
+                    .map(|type_arg| Ok((type_arg, SType::sigma_parse(r)?)))
+                    .collect::<Result<HashMap<STypeVar, SType>, SigmaParsingError>>()?
+            } else {
+                HashMap::new()
+            };
         let arg_types = args.iter().map(|arg| arg.tpe()).collect();
-        let method = SMethod::from_ids(type_id, method_id)?.specialize_for(obj.tpe(), arg_types)?;
+        let method = method
+            .specialize_for(obj.tpe(), arg_types)?
+            .with_concrete_types(&explicit_subst);
         Ok(MethodCall::new(obj, method, args)?)
     }
 }
@@ -34,10 +65,16 @@ impl SigmaSerializable for MethodCall {
 #[cfg(feature = "arbitrary")]
 #[allow(clippy::unwrap_used)]
 mod tests {
+    use crate::ergo_tree::ErgoTreeVersion;
     use crate::mir::expr::Expr;
     use crate::mir::method_call::MethodCall;
+    use crate::serialization::sigma_byte_reader::from_bytes;
+    use crate::serialization::sigma_byte_writer::SigmaByteWriter;
     use crate::serialization::sigma_serialize_roundtrip;
+    use crate::serialization::SigmaSerializable;
+    use crate::serialization::SigmaSerializationError;
     use crate::types::scoll;
+    use crate::types::sglobal;
     use crate::types::stype::SType;
     use crate::types::stype_param::STypeVar;
 
@@ -54,4 +91,83 @@ mod tests {
         .into();
         assert_eq![sigma_serialize_roundtrip(&mc), mc];
     }
+
+    fn deserialize_to_long() -> Expr {
+        let method = sglobal::DESERIALIZE_TO_METHOD
+            .clone()
+            .with_concrete_types(&[(STypeVar::t(), SType::SLong)].iter().cloned().collect());
+        MethodCall::new(Expr::Global, method, vec![vec![10i8].into()])
+            .unwrap()
+            .into()
+    }
+
+    #[test]
+    fn ser_explicit_type_args() {
+        let mc = deserialize_to_long();
+        let bytes = mc.sigma_serialize_bytes().unwrap();
+        // the type argument follows the arguments in the v3 trees
+        assert_eq!(
+            bytes.last(),
+            Some(&SType::SLong.sigma_serialize_bytes().unwrap()[0])
+        );
+        assert_eq![sigma_serialize_roundtrip(&mc), mc];
+        // not specified
+        let mc: Expr = MethodCall::new(
+            Expr::Global,
+            sglobal::DESERIALIZE_TO_METHOD.clone(),
+            vec![vec![10i8].into()],
+        )
+        .unwrap()
+        .into();
+        assert_eq!(
+            mc.sigma_serialize_bytes(),
+            Err(SigmaSerializationError::NotSupported(
+                "MethodCall: explicit type arguments are not specified"
+            ))
+        );
+    }
+
+    #[test]
+    fn ser_explicit_type_args_pre_v3() {
+        let mc = deserialize_to_long();
+        let v3_bytes = mc.sigma_serialize_bytes().unwrap();
+        let mut bytes = Vec::new();
+        let mut w = SigmaByteWriter::new(&mut bytes, None).with_tree_version(ErgoTreeVersion::V2);
+        mc.sigma_serialize(&mut w).unwrap();
+        assert_eq!(bytes, v3_bytes[..v3_bytes.len() - 1].to_vec());
+        // the type argument is not parsed
+        let mut r = from_bytes(&bytes).with_tree_version(ErgoTreeVersion::V2);
+        let parsed = Expr::sigma_parse(&mut r).unwrap();
+        assert_eq!(parsed.tpe(), SType::STypeVar(STypeVar::t()),);
+    }
+
+    #[test]
+    fn ser_multi_type_args_deterministic() {
+        // the type args substitution is a HashMap, a new map has a new (random) iteration order
+        let zip = || -> Expr {
+            MethodCall::new(
+                vec![1i64, 2i64].into(),
+                scoll::ZIP_METHOD.clone().with_concrete_types(
+                    &[
+                        (STypeVar::t(), SType::SLong),
+                        (STypeVar::iv(), SType::SBoolean),
+                    ]
+                    .iter()
+                    .cloned()
+                    .collect(),
+                ),
+                vec![vec![true, false].into()],
+            )
+            .unwrap()
+            .into()
+        };
+        let mc = zip();
+        let bytes = mc.sigma_serialize_bytes().unwrap();
+        for _ in 0..20 {
+            let other = zip();
+            assert_eq!(other, mc);
+            assert_eq!(other.sigma_serialize_bytes().unwrap(), bytes);
+        }
+        assert_eq![sigma_serialize_roundtrip(&mc), mc];
+    }
 }
@@ -1,7 +1,6 @@
 use crate::mir::expr::Expr;
 use crate::mir::property_call::PropertyCall;
 use crate::types::smethod::MethodId;
-use crate::types::smethod::SMethod;
 
 use super::sigma_byte_reader::SigmaByteRead;
 use super::sigma_byte_writer::SigmaByteWrite;
@@ -24,7 +23,7 @@ impl SigmaSerializable for PropertyCall {
         let obj = Expr::sigma_parse(r)?;
         Ok(PropertyCall::new(
             obj,
-            SMethod::from_ids(type_id, method_id)?,
+            r.method_registry().from_ids(type_id, method_id)?,
         )?)
     }
 }
@@ -1,6 +1,7 @@
 //! Serialization of Ergo types
 use crate::mir::val_def::ValId;
 use crate::mir::{constant::TryExtractFromError, expr::InvalidArgumentError};
+use crate::types::stype::SType;
 use crate::types::type_unify::TypeUnificationError;
 
 use super::{
@@ -32,6 +33,13 @@ pub enum SigmaSerializationError {
     /// Serialization not supported
     #[error("serialization not supported: {0}")]
     NotSupported(&'static str),
+    /// Deserialize* node is rejected by the reader's `DeserializePolicy`
+    #[error("{0} node is not allowed by the deserialize policy")]
+    DeserializeNotAllowed(&'static str),
+    /// Function type or value (lambda) in a constant or a register, function types have no
+    /// serialization format
+    #[error("function type {0:?} values cannot be serialized")]
+    FuncType(SType),
 }
 
 impl From<io::Error> for SigmaSerializationError {
@@ -58,6 +66,13 @@ pub enum SigmaParsingError {
     /// Failed to parse type
     #[error("type parsing error")]
     InvalidTypeCode(u8),
+    /// Type code of a function type (e.g. in a constant or a register), function types have no
+    /// serialization format
+    #[error("function type (type code {0}) is not supported")]
+    FuncTypeCode(u8),
+    /// Data (constant, register) of a function type
+    #[error("function type {0:?} values cannot be parsed")]
+    FuncTypeData(SType),
     /// Failed to decode VLQ
     #[error("vlq encode error: {0}")]
     VlqEncode(#[from] vlq_encode::VlqEncodingError),
@@ -97,6 +112,26 @@ pub enum SigmaParsingError {
     /// Invalid item quantity in BoundedVec
     #[error("Invalid item quantity in BoundedVec: {0}")]
     BoundedVecOutOfBounds(#[from] BoundedVecOutOfBounds),
+    /// Error with the stream position and the nodes being parsed when it occurred
+    #[error("{error} at offset {offset:#x} while parsing {}", .path.join("->"))]
+    AtPosition {
+        /// parsing error
+        error: Box<SigmaParsingError>,
+        /// number of bytes read from the stream when the error occurred
+        offset: u64,
+        /// names of the nodes being parsed (from the outermost to the innermost)
+        path: Vec<&'static str>,
+    },
+}
+
+impl SigmaParsingError {
+    /// The error without the position info (see [`SigmaParsingError::AtPosition`])
+    pub fn cause(&self) -> &SigmaParsingError {
+        match self {
+            SigmaParsingError::AtPosition { error, .. } => error.cause(),
+            _ => self,
+        }
+    }
 }
 
 impl From<io::Error> for SigmaParsingError {
@@ -149,6 +184,9 @@ pub trait SigmaSerializable: Sized {
     }
 }
 
+/// Max. number of items preallocated for a collection before parsing its items
+const MAX_PREALLOCATED_ITEMS: usize = 4096;
+
 impl<T: SigmaSerializable> SigmaSerializable for Vec<T> {
     fn sigma_serialize<W: SigmaByteWrite>(&self, w: &mut W) -> SigmaSerializeResult {
         w.put_u32(self.len() as u32)?;
@@ -157,7 +195,9 @@ impl<T: SigmaSerializable> SigmaSerializable for Vec<T> {
 
     fn sigma_parse<R: SigmaByteRead>(r: &mut R) -> Result<Self, SigmaParsingError> {
         let items_count = r.get_u32()?;
-        let mut items = Vec::with_capacity(items_count as usize);
+        // the count is not trusted, preallocate only a reasonable number of items (the rest
+        // are allocated as they are parsed)
+        let mut items = Vec::with_capacity((items_count as usize).min(MAX_PREALLOCATED_ITEMS));
         for _ in 0..items_count {
             items.push(T::sigma_parse(r)?);
         }
@@ -220,3 +260,74 @@ pub fn sigma_serialize_roundtrip<T: SigmaSerializable>(v: &T) -> T {
     let mut sr = SigmaByteReader::new(cursor, ConstantStore::empty());
     T::sigma_parse(&mut sr).expect("parse failed")
 }
+
+#[cfg(test)]
+#[cfg(feature = "arbitrary")]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::chain::ergo_box::ErgoBox;
+    use crate::chain::header::Header;
+    use crate::ergo_tree::ErgoTree;
+    use crate::mir::constant::Constant;
+    use crate::mir::expr::Expr;
+    use crate::sigma_protocol::dlog_group::EcPoint;
+    use crate::sigma_protocol::sigma_boolean::SigmaBoolean;
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    // the parsers fail with an error on malformed input, the results are not checked
+    fn parse_all(bytes: &[u8]) {
+        let _ = ErgoTree::sigma_parse_bytes(bytes);
+        let _ = Constant::sigma_parse_bytes(bytes);
+        let _ = Expr::sigma_parse_bytes(bytes);
+        let _ = ErgoBox::sigma_parse_bytes(bytes);
+        let _ = Header::sigma_parse_bytes(bytes);
+        let _ = EcPoint::sigma_parse_bytes(bytes);
+        let _ = SigmaBoolean::sigma_parse_bytes(bytes);
+        let _ = SType::sigma_parse_bytes(bytes);
+        let _ = Vec::<Constant>::sigma_parse_bytes(bytes);
+    }
+
+    #[test]
+    fn parse_huge_length_prefix() {
+        // collection of u32::MAX items without the items
+        parse_all(&[0xff, 0xff, 0xff, 0xff, 0x0f]);
+        assert!(Vec::<Constant>::sigma_parse_bytes(&[0xff, 0xff, 0xff, 0xff, 0x0f]).is_err());
+        // ErgoTree with the size flag and the size of u32::MAX without the body
+        assert!(ErgoTree::sigma_parse_bytes(&[0x08, 0xff, 0xff, 0xff, 0xff, 0x0f]).is_err());
+    }
+
+    proptest! {
+
+        #[test]
+        fn parse_arbitrary_bytes(bytes in vec(any::<u8>(), 0..256)) {
+            parse_all(&bytes);
+        }
+    }
+
+    proptest! {
+
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn parse_corrupted_tree(tree in any::<ErgoTree>(), pos in any::<usize>(), byte in any::<u8>()) {
+            let bytes = tree.sigma_serialize_bytes().unwrap();
+            let pos = pos % bytes.len();
+            let mut corrupted = bytes.clone();
+            corrupted[pos] = byte;
+            parse_all(&corrupted);
+            parse_all(&bytes[..pos]);
+        }
+
+        #[test]
+        fn parse_corrupted_box(b in any::<ErgoBox>(), pos in any::<usize>(), byte in any::<u8>()) {
+            let bytes = b.sigma_serialize_bytes().unwrap();
+            let pos = pos % bytes.len();
+            let mut corrupted = bytes.clone();
+            corrupted[pos] = byte;
+            parse_all(&corrupted);
+            parse_all(&bytes[..pos]);
+        }
+    }
+}
@@ -1,6 +1,9 @@
 //! Sigma byte stream writer
 use super::constant_store::ConstantStore;
 use super::val_def_type_store::ValDefTypeStore;
+use super::SigmaParsingError;
+use crate::ergo_tree::ErgoTreeVersion;
+use crate::types::method_registry::MethodRegistry;
 use sigma_ser::vlq_encode::ReadSigmaVlqExt;
 use std::io::Cursor;
 use std::io::Read;
@@ -11,6 +14,28 @@ pub struct SigmaByteReader<R> {
     constant_store: ConstantStore,
     substitute_placeholders: bool,
     val_def_type_store: ValDefTypeStore,
+    deserialize_policy: DeserializePolicy,
+    was_deserialize: bool,
+    method_registry: MethodRegistry,
+    tree_version: ErgoTreeVersion,
+    position: u64,
+    parse_path: Vec<&'static str>,
+}
+
+/// Policy on parsing of `DeserializeContext` and `DeserializeRegister` nodes
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
+pub enum DeserializePolicy {
+    /// Parse Deserialize* nodes (default)
+    Allow,
+    /// Fail the parsing on any Deserialize* node, for the contexts where script bytes must be
+    /// static (e.g. cold signing)
+    Reject,
+}
+
+impl Default for DeserializePolicy {
+    fn default() -> Self {
+        DeserializePolicy::Allow
+    }
 }
 
 impl<R: Read> SigmaByteReader<R> {
@@ -21,6 +46,12 @@ impl<R: Read> SigmaByteReader<R> {
             constant_store,
             substitute_placeholders: false,
             val_def_type_store: ValDefTypeStore::new(),
+            deserialize_policy: DeserializePolicy::default(),
+            was_deserialize: false,
+            method_registry: MethodRegistry::builtin(),
+            tree_version: ErgoTreeVersion::V3,
+            position: 0,
+            parse_path: Vec::new(),
         }
     }
 
@@ -35,8 +66,34 @@ impl<R: Read> SigmaByteReader<R> {
             constant_store,
             substitute_placeholders: true,
             val_def_type_store: ValDefTypeStore::new(),
+            deserialize_policy: DeserializePolicy::default(),
+            was_deserialize: false,
+            method_registry: MethodRegistry::builtin(),
+            tree_version: ErgoTreeVersion::V3,
+            position: 0,
+            parse_path: Vec::new(),
         }
     }
+
+    /// Set the policy on parsing of Deserialize* nodes
+    pub fn with_deserialize_policy(mut self, deserialize_policy: DeserializePolicy) -> Self {
+        self.deserialize_policy = deserialize_policy;
+        self
+    }
+
+    /// Set the registry to resolve the methods of `MethodCall` and `PropertyCall` nodes (the
+    /// built-in methods by default)
+    pub fn with_method_registry(mut self, method_registry: MethodRegistry) -> Self {
+        self.method_registry = method_registry;
+        self
+    }
+
+    /// Set the version of the ErgoTree being parsed (the latest version by default, i.e. the data
+    /// outside of an ErgoTree, e.g. in registers, is parsed by the latest version rules)
+    pub fn with_tree_version(mut self, tree_version: ErgoTreeVersion) -> Self {
+        self.tree_version = tree_version;
+        self
+    }
 }
 
 /// Create SigmaByteReader from a byte array (with empty constant store)
@@ -46,6 +103,12 @@ pub fn from_bytes<T: AsRef<[u8]>>(bytes: T) -> SigmaByteReader<Cursor<T>> {
         constant_store: ConstantStore::empty(),
         substitute_placeholders: false,
         val_def_type_store: ValDefTypeStore::new(),
+        deserialize_policy: DeserializePolicy::default(),
+        was_deserialize: false,
+        method_registry: MethodRegistry::builtin(),
+        tree_version: ErgoTreeVersion::V3,
+        position: 0,
+        parse_path: Vec::new(),
     }
 }
 
@@ -62,11 +125,56 @@ pub trait SigmaByteRead: ReadSigmaVlqExt {
 
     /// ValDef types store (resolves tpe on ValUse parsing)
     fn val_def_type_store(&mut self) -> &mut ValDefTypeStore;
+
+    /// Policy on parsing of Deserialize* nodes
+    fn deserialize_policy(&self) -> DeserializePolicy;
+
+    /// Returns true if a Deserialize* node was parsed
+    fn was_deserialize(&self) -> bool;
+
+    /// Registry to resolve the methods of `MethodCall` and `PropertyCall` nodes
+    fn method_registry(&self) -> &MethodRegistry;
+
+    /// Mark that a Deserialize* node was parsed
+    fn set_deserialize(&mut self, has_deserialize: bool);
+
+    /// Version of the ErgoTree being parsed (the data formats of the later versions are rejected)
+    fn tree_version(&self) -> ErgoTreeVersion;
+
+    /// Set the version of the ErgoTree being parsed
+    fn set_tree_version(&mut self, tree_version: ErgoTreeVersion);
+
+    /// Number of bytes read from the stream
+    fn position(&self) -> u64;
+
+    /// Names of the nodes being parsed (from the outermost to the current one)
+    fn parse_path(&self) -> &[&'static str];
+
+    /// Start parsing of the node with the given name (adds it to the parse path)
+    fn enter_node(&mut self, node_name: &'static str);
+
+    /// Finish parsing of the current node (removes it from the parse path)
+    fn exit_node(&mut self);
+
+    /// Attach the current stream position and parse path to the error (unless it already has
+    /// them, i.e. the error came from the nested node)
+    fn error_at_position(&self, error: SigmaParsingError) -> SigmaParsingError {
+        match error {
+            SigmaParsingError::AtPosition { .. } => error,
+            _ => SigmaParsingError::AtPosition {
+                error: Box::new(error),
+                offset: self.position(),
+                path: self.parse_path().to_vec(),
+            },
+        }
+    }
 }
 
 impl<R: Read> Read for SigmaByteReader<R> {
     fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
-        self.inner.read(buf)
+        let read = self.inner.read(buf)?;
+        self.position += read as u64;
+        Ok(read)
     }
 }
 
@@ -86,4 +194,44 @@ impl<R: ReadSigmaVlqExt> SigmaByteRead for SigmaByteReader<R> {
     fn val_def_type_store(&mut self) -> &mut ValDefTypeStore {
         &mut self.val_def_type_store
     }
+
+    fn deserialize_policy(&self) -> DeserializePolicy {
+        self.deserialize_policy
+    }
+
+    fn was_deserialize(&self) -> bool {
+        self.was_deserialize
+    }
+
+    fn set_deserialize(&mut self, has_deserialize: bool) {
+        self.was_deserialize = has_deserialize;
+    }
+
+    fn method_registry(&self) -> &MethodRegistry {
+        &self.method_registry
+    }
+
+    fn tree_version(&self) -> ErgoTreeVersion {
+        self.tree_version.clone()
+    }
+
+    fn set_tree_version(&mut self, tree_version: ErgoTreeVersion) {
+        self.tree_version = tree_version;
+    }
+
+    fn position(&self) -> u64 {
+        self.position
+    }
+
+    fn parse_path(&self) -> &[&'static str] {
+        &self.parse_path
+    }
+
+    fn enter_node(&mut self, node_name: &'static str) {
+        self.parse_path.push(node_name);
+    }
+
+    fn exit_node(&mut self) {
+        self.parse_path.pop();
+    }
 }
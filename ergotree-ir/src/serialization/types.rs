@@ -113,9 +113,7 @@ pub enum TypeCode {
 
 impl TypeCode {
     /// SFunc types occupy remaining space of byte values [FirstFuncType .. 255]
-    #[allow(dead_code)]
-    const FIRST_FUNC_TYPE: u8 = OpCode::LAST_DATA_TYPE.value();
-    #[allow(dead_code)]
+    const FIRST_FUNC_TYPE: u8 = OpCode::LAST_DATA_TYPE.value() + 1;
     const LAST_FUNC_TYPE: u8 = 255;
 
     /// Type code of the last valid prim type so that (1 to LastPrimTypeCode) is a range of valid codes.
@@ -149,6 +147,9 @@ impl TypeCode {
     pub(crate) fn parse(b: u8) -> Result<Self, SigmaParsingError> {
         match FromPrimitive::from_u8(b) {
             Some(t) => Ok(t),
+            None if (TypeCode::FIRST_FUNC_TYPE..=TypeCode::LAST_FUNC_TYPE).contains(&b) => {
+                Err(SigmaParsingError::FuncTypeCode(b))
+            }
             None => Err(SigmaParsingError::InvalidTypeCode(b)),
         }
     }
@@ -341,7 +342,7 @@ impl SigmaSerializable for SType {
         // for reference see http://github.com/ScorexFoundation/sigmastate-interpreter/blob/25251c1313b0131835f92099f02cef8a5d932b5e/sigmastate/src/main/scala/sigmastate/serialization/TypeSerializer.scala#L25-L25
         use SType::*;
         match self {
-            SType::SFunc(_) => Err(SigmaSerializationError::NotSupported("SFunc")),
+            SType::SFunc(_) => Err(SigmaSerializationError::FuncType(self.clone())),
             SType::SAny => TypeCode::SANY.sigma_serialize(w),
             SType::SBoolean => TypeCode::SBOOLEAN.sigma_serialize(w),
             SType::SByte => TypeCode::SBYTE.sigma_serialize(w),
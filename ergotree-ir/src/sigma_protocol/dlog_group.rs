@@ -16,6 +16,7 @@
 //! On the other hand, any group element can be mapped to some string.
 
 use crate::bigint256::BigInt256;
+use crate::ergo_tree::ErgoTreeVersion;
 use crate::serialization::sigma_byte_writer::SigmaByteWrite;
 use crate::serialization::SigmaSerializeResult;
 use crate::serialization::{
@@ -30,6 +31,8 @@ use num_bigint::BigUint;
 use num_bigint::Sign;
 use num_bigint::ToBigUint;
 use num_traits::ToPrimitive;
+use std::collections::HashMap;
+use std::collections::HashSet;
 use std::convert::TryFrom;
 use std::ops::{Add, Mul, Neg};
 
@@ -37,13 +40,10 @@ use std::ops::{Add, Mul, Neg};
 #[derive(PartialEq, Clone, Default)]
 pub struct EcPoint(ProjectivePoint);
 
-#[allow(clippy::unwrap_used)]
 impl std::fmt::Debug for EcPoint {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         f.write_str("EC:")?;
-        f.write_str(&base16::encode_lower(
-            &self.sigma_serialize_bytes().unwrap(),
-        ))
+        f.write_str(&sigma_util::hex::encode_lower(&self.to_compressed_bytes()))
     }
 }
 
@@ -53,11 +53,110 @@ impl EcPoint {
 
     /// Attempts to parse from Base16-encoded string
     pub fn from_base16_str(str: String) -> Option<Self> {
-        base16::decode(&str)
+        sigma_util::hex::decode(&str)
             .ok()
             .map(|bytes| Self::sigma_parse_bytes(&bytes).ok())
             .flatten()
     }
+
+    /// Compressed (SEC1) encoding, all zeros for the identity (infinity) point
+    pub fn to_compressed_bytes(&self) -> [u8; EcPoint::GROUP_SIZE] {
+        let mut bytes = [0u8; EcPoint::GROUP_SIZE];
+        let caff = self.0.to_affine();
+        if !bool::from(caff.is_identity()) {
+            bytes.copy_from_slice(caff.to_encoded_point(true).as_bytes());
+        }
+        bytes
+    }
+
+    /// Parse (decompress) points from the compressed encodings (e.g. public keys of P2PK boxes
+    /// found in a wallet scan). Repeated encodings are decompressed only once.
+    pub fn parse_many(encoded: &[&[u8]]) -> Vec<Result<EcPoint, SigmaParsingError>> {
+        let mut parsed: HashMap<&[u8], Result<EcPoint, SigmaParsingError>> = HashMap::new();
+        encoded
+            .iter()
+            .map(|bytes| {
+                parsed
+                    .entry(*bytes)
+                    .or_insert_with(|| EcPoint::from_compressed_bytes(bytes))
+                    .clone()
+            })
+            .collect()
+    }
+
+    fn from_compressed_bytes(bytes: &[u8]) -> Result<EcPoint, SigmaParsingError> {
+        if bytes.len() != EcPoint::GROUP_SIZE {
+            return Err(SigmaParsingError::Misc(format!(
+                "invalid encoded point size {}, expected {}",
+                bytes.len(),
+                EcPoint::GROUP_SIZE
+            )));
+        }
+        if bytes[0] != 0 {
+            let pubkey = PublicKey::from_sec1_bytes(bytes).map_err(|e| {
+                SigmaParsingError::Misc(format!("failed to parse PK from bytes: {:?}", e))
+            })?;
+            Ok(EcPoint(pubkey.to_projective()))
+        } else {
+            // infinity point
+            Ok(EcPoint(ProjectivePoint::identity()))
+        }
+    }
+}
+
+/// Set of points (e.g. wallet's own public keys) kept in the compressed encoding to match the
+/// encoded points without decompression
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct CompressedPointSet(HashSet<[u8; EcPoint::GROUP_SIZE]>);
+
+impl CompressedPointSet {
+    /// Serialized P2PK ErgoTree prefix (v0 header, SigmaProp constant, ProveDlog), followed by
+    /// the compressed public key
+    pub const P2PK_TREE_PREFIX: [u8; 3] = [0x00, 0x08, 0xcd];
+
+    /// Serialized P2PK proposition prefix (SigmaProp constant, ProveDlog), followed by the
+    /// compressed public key
+    const P2PK_PROPOSITION_PREFIX: [u8; 2] = [0x08, 0xcd];
+
+    /// ErgoTree header bit of the tree size (mandatory for the versions above 0)
+    const TREE_HAS_SIZE_FLAG: u8 = 0x08;
+
+    /// Create the set of the given points
+    pub fn new(points: &[EcPoint]) -> CompressedPointSet {
+        CompressedPointSet(points.iter().map(EcPoint::to_compressed_bytes).collect())
+    }
+
+    /// Returns true if the compressed encoding is of a point in the set
+    pub fn contains_encoded(&self, bytes: &[u8]) -> bool {
+        <[u8; EcPoint::GROUP_SIZE]>::try_from(bytes)
+            .map(|b| self.0.contains(&b))
+            .unwrap_or(false)
+    }
+
+    /// Returns true if the serialized ErgoTree (e.g. box proposition bytes) is P2PK of a point in
+    /// the set. The trees of any version are matched (v0 without the size, see
+    /// [`Self::P2PK_TREE_PREFIX`], or the higher versions with the size), but only without the
+    /// constant segregation, i.e. in the form created by the node and the wallets for P2PK
+    /// addresses. Other trees with the public key (e.g. with the segregated constant) have to be
+    /// parsed to be matched.
+    pub fn contains_p2pk_tree(&self, tree_bytes: &[u8]) -> bool {
+        let proposition = match tree_bytes.split_first() {
+            Some((&0, proposition)) => proposition,
+            // version above 0 with the size (a single VLQ byte for the P2PK proposition) and
+            // no other flags
+            Some((&header, [size, proposition @ ..]))
+                if header & !ErgoTreeVersion::VERSION_MASK == Self::TREE_HAS_SIZE_FLAG
+                    && header != Self::TREE_HAS_SIZE_FLAG
+                    && *size as usize == proposition.len() =>
+            {
+                proposition
+            }
+            _ => return false,
+        };
+        proposition.len() == Self::P2PK_PROPOSITION_PREFIX.len() + EcPoint::GROUP_SIZE
+            && proposition.starts_with(&Self::P2PK_PROPOSITION_PREFIX)
+            && self.contains_encoded(&proposition[Self::P2PK_PROPOSITION_PREFIX.len()..])
+    }
 }
 
 impl Eq for EcPoint {}
@@ -94,6 +193,43 @@ pub fn is_identity(ge: &EcPoint) -> bool {
     *ge == identity()
 }
 
+/// Domain separation tag of the generators derived with [`derive_generator`]
+pub const GENERATOR_DERIVATION_TAG: &[u8] = b"sigma-rust/generator/v1";
+
+/// Derives a secondary generator (e.g. `h` of a Diffie-Hellman tuple) from the seed and the index
+/// deterministically, with no known discrete logarithm to the base of [`generator`]
+/// ("nothing-up-my-sleeve" generator).
+/// Try-and-increment: `x = blake2b256(tag || index || counter || seed)` (`index` and `counter` are
+/// 4-byte big-endian integers, `tag` is [`GENERATOR_DERIVATION_TAG`]), the first `x` (from
+/// `counter` 0) that is the x coordinate of a curve point gives the point with the even y
+/// coordinate (compressed encoding `02 || x`). About half of `x` values are on the curve.
+pub fn derive_generator(seed: &[u8], index: u32) -> EcPoint {
+    let mut counter: u32 = 0;
+    loop {
+        let input = [
+            GENERATOR_DERIVATION_TAG,
+            &index.to_be_bytes()[..],
+            &counter.to_be_bytes()[..],
+            seed,
+        ]
+        .concat();
+        let x = sigma_util::hash::blake2b256_hash(&input);
+        let mut encoded = [0u8; EcPoint::GROUP_SIZE];
+        encoded[0] = 0x02;
+        encoded[1..].copy_from_slice(x.as_ref());
+        if let Ok(point) = EcPoint::from_compressed_bytes(&encoded) {
+            return point;
+        }
+        counter = counter.wrapping_add(1);
+    }
+}
+
+/// Derives `n` secondary generators from the seed (with the indices `0..n`, see
+/// [`derive_generator`])
+pub fn derive_generators(seed: &[u8], n: u32) -> Vec<EcPoint> {
+    (0..n).map(|index| derive_generator(seed, index)).collect()
+}
+
 /// Calculates the inverse of the given group element
 pub fn inverse(ec: &EcPoint) -> EcPoint {
     -ec.clone()
@@ -178,15 +314,7 @@ impl SigmaSerializable for EcPoint {
     fn sigma_parse<R: SigmaByteRead>(r: &mut R) -> Result<Self, SigmaParsingError> {
         let mut buf = [0; EcPoint::GROUP_SIZE];
         r.read_exact(&mut buf[..])?;
-        if buf[0] != 0 {
-            let pubkey = PublicKey::from_sec1_bytes(&buf[..]).map_err(|e| {
-                SigmaParsingError::Misc(format!("failed to parse PK from bytes: {:?}", e))
-            })?;
-            Ok(EcPoint(pubkey.to_projective()))
-        } else {
-            // infinity point
-            Ok(EcPoint(ProjectivePoint::identity()))
-        }
+        EcPoint::from_compressed_bytes(&buf)
     }
 }
 
@@ -215,8 +343,13 @@ mod arbitrary {
 #[allow(clippy::panic)]
 mod tests {
     use super::*;
+    use crate::chain::address::Address;
+    use crate::ergo_tree::ErgoTree;
+    use crate::ergo_tree::ErgoTreeHeader;
+    use crate::mir::constant::Constant;
     use crate::mir::expr::Expr;
     use crate::serialization::sigma_serialize_roundtrip;
+    use crate::sigma_protocol::sigma_boolean::ProveDlog;
     use num_bigint::BigUint;
     use num_bigint::ToBigUint;
     use proptest::prelude::*;
@@ -260,6 +393,82 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parse_many_invalid() {
+        let invalid = [2u8; EcPoint::GROUP_SIZE];
+        let parsed =
+            EcPoint::parse_many(&[&invalid[..], &[2u8; 3][..], &[0u8; EcPoint::GROUP_SIZE][..]]);
+        assert!(parsed[0].is_err());
+        assert!(parsed[1].is_err());
+        assert_eq!(parsed[2], Ok(identity()));
+    }
+
+    #[test]
+    fn derive_generator_vectors() {
+        let vectors: [(&[u8], u32, &str); 4] = [
+            (
+                b"",
+                0,
+                "02e7acbfeff24f3befcbc11684da06b8b48fa50962a7dad6427e53b8b449f735e7",
+            ),
+            // found with the counter 6
+            (
+                b"ergo",
+                0,
+                "025ff24199254a663f03b83fbed6dc2c53d401c3243aefdda984e445be3f821d3d",
+            ),
+            (
+                b"ergo",
+                1,
+                "02ff71ee0d373e767b4dede8037354fee46aa56332166614b29ba46a7d1b568d4f",
+            ),
+            (
+                b"mixer",
+                0,
+                "02bd793f9332f62ec993198977d00fc75ed11b5d2f1781db7f3b46c5741a86bf64",
+            ),
+        ];
+        for (seed, index, expected) in vectors.iter() {
+            let h = derive_generator(seed, *index);
+            assert_eq!(
+                sigma_util::hex::encode_lower(&h.to_compressed_bytes()),
+                *expected
+            );
+            assert!(!is_identity(&h));
+            assert_ne!(h, generator());
+        }
+        let hs = derive_generators(b"ergo", 2);
+        assert_eq!(hs.len(), 2);
+        assert_eq!(hs[1], derive_generator(b"ergo", 1));
+    }
+
+    #[test]
+    fn p2pk_tree_prefix() {
+        let pk = generator();
+        let tree = Address::P2Pk(ProveDlog::from(pk.clone())).script().unwrap();
+        let set = CompressedPointSet::new(&[pk.clone()]);
+        assert!(set.contains_p2pk_tree(&tree.sigma_serialize_bytes().unwrap()));
+        let prop: Expr = Constant::from(ProveDlog::from(pk)).into();
+        let tree_bytes = |header: ErgoTreeHeader| {
+            ErgoTree::new(header, &prop)
+                .unwrap()
+                .sigma_serialize_bytes()
+                .unwrap()
+        };
+        let sized_versions = [
+            ErgoTreeVersion::V1,
+            ErgoTreeVersion::V2,
+            ErgoTreeVersion::V3,
+        ];
+        for version in sized_versions.iter() {
+            let header = ErgoTreeHeader::with_version(version.clone(), false);
+            assert!(set.contains_p2pk_tree(&tree_bytes(header)));
+        }
+        // the documented limit: the segregated constant is not matched
+        assert!(!set.contains_p2pk_tree(&tree_bytes(ErgoTreeHeader::v0(true))));
+        assert!(!set.contains_p2pk_tree(&tree_bytes(ErgoTreeHeader::v1(true))));
+    }
+
     proptest! {
 
         #[test]
@@ -268,6 +477,49 @@ mod tests {
             prop_assert_eq![sigma_serialize_roundtrip(&e), e];
         }
 
+        #[test]
+        fn parse_many(scalars in proptest::collection::vec(scalar(), 1..10)) {
+            let points: Vec<EcPoint> = scalars
+                .iter()
+                .map(|s| exponentiate(&generator(), s))
+                .chain(vec![identity()])
+                .collect();
+            let encoded: Vec<Vec<u8>> = points
+                .iter()
+                .map(|p| p.sigma_serialize_bytes().unwrap())
+                .collect();
+            let mut encoded_refs: Vec<&[u8]> = encoded.iter().map(Vec::as_slice).collect();
+            // repeated encodings
+            encoded_refs.extend(encoded.iter().map(Vec::as_slice));
+            let parsed: Vec<EcPoint> = EcPoint::parse_many(&encoded_refs)
+                .into_iter()
+                .map(Result::unwrap)
+                .collect();
+            prop_assert_eq!(&parsed[..points.len()], points.as_slice());
+            prop_assert_eq!(&parsed[points.len()..], points.as_slice());
+            for (p, bytes) in points.iter().zip(&encoded) {
+                prop_assert_eq!(p.to_compressed_bytes().to_vec(), bytes.clone());
+            }
+        }
+
+        #[test]
+        fn compressed_point_set(own in scalar(), other in scalar()) {
+            let own_point = exponentiate(&generator(), &own);
+            let other_point = exponentiate(&generator(), &other);
+            prop_assume!(own_point != other_point);
+            let set = CompressedPointSet::new(&[own_point.clone()]);
+            let p2pk_tree = |p: &EcPoint| {
+                let mut bytes = CompressedPointSet::P2PK_TREE_PREFIX.to_vec();
+                bytes.extend_from_slice(&p.to_compressed_bytes());
+                bytes
+            };
+            prop_assert!(set.contains_encoded(&own_point.to_compressed_bytes()));
+            prop_assert!(!set.contains_encoded(&other_point.to_compressed_bytes()));
+            prop_assert!(set.contains_p2pk_tree(&p2pk_tree(&own_point)));
+            prop_assert!(!set.contains_p2pk_tree(&p2pk_tree(&other_point)));
+            prop_assert!(!set.contains_p2pk_tree(&p2pk_tree(&own_point)[1..]));
+        }
+
         #[test]
         fn scalar_biguint_roundtrip(scalar in scalar()) {
             let bu = scalar_to_biguint(&scalar).unwrap();
@@ -153,6 +153,76 @@ impl HasOpCode for SigmaBoolean {
     }
 }
 
+impl SigmaBoolean {
+    /// Folds trivial propositions (`true`/`false`) in the conjectures of this proposition (at any
+    /// depth), e.g. CAND with `false` becomes `false`, COR with `true` becomes `true`, and trivial
+    /// items of THRESHOLD are accounted in its bound. The result is either a trivial proposition
+    /// or a proposition without trivial propositions in it.
+    pub fn reduce_trivial(self) -> SigmaBoolean {
+        match self {
+            SigmaBoolean::SigmaConjecture(SigmaConjecture::Cand(cand)) => {
+                Cand::normalized(cand.items.mapped(SigmaBoolean::reduce_trivial))
+            }
+            SigmaBoolean::SigmaConjecture(SigmaConjecture::Cor(cor)) => {
+                Cor::normalized(cor.items.mapped(SigmaBoolean::reduce_trivial))
+            }
+            SigmaBoolean::SigmaConjecture(SigmaConjecture::Cthreshold(ct)) => {
+                Cthreshold::normalized(ct.n, ct.items.mapped(SigmaBoolean::reduce_trivial))
+            }
+            _ => self,
+        }
+    }
+
+    /// Normal form of this proposition: trivial propositions are folded (see
+    /// [`SigmaBoolean::reduce_trivial`]) and the nested conjectures of the same kind are
+    /// flattened, e.g. `CAND(pk1, CAND(pk2, pk3))` becomes `CAND(pk1, pk2, pk3)` (the items of
+    /// THRESHOLD are normalized, but not merged). The normal form is satisfied by the same sets of
+    /// secrets as this proposition, so it can be used to compare the propositions.
+    /// Note, the proof of a proposition is made for its tree as is (the challenges are computed
+    /// from the tree bytes), so the reduction of the scripts (by the prover and the verifier) does
+    /// not flatten the conjectures (only the trivial propositions are folded).
+    pub fn normalized(self) -> SigmaBoolean {
+        match self.reduce_trivial() {
+            SigmaBoolean::SigmaConjecture(SigmaConjecture::Cand(cand)) => Cand {
+                items: flattened(cand.items, true),
+            }
+            .into(),
+            SigmaBoolean::SigmaConjecture(SigmaConjecture::Cor(cor)) => Cor {
+                items: flattened(cor.items, false),
+            }
+            .into(),
+            SigmaBoolean::SigmaConjecture(SigmaConjecture::Cthreshold(ct)) => Cthreshold {
+                n: ct.n,
+                items: ct.items.mapped(SigmaBoolean::normalized),
+            }
+            .into(),
+            sb => sb,
+        }
+    }
+}
+
+/// Normalized items with the items of the nested CAND (`and` is true) or COR merged in, the items
+/// are left nested if there are too many of them
+fn flattened(
+    items: SigmaConjectureItems<SigmaBoolean>,
+    and: bool,
+) -> SigmaConjectureItems<SigmaBoolean> {
+    let items = items.mapped(SigmaBoolean::normalized);
+    let mut res: Vec<SigmaBoolean> = Vec::new();
+    for item in items.iter() {
+        match item {
+            SigmaBoolean::SigmaConjecture(SigmaConjecture::Cand(c)) if and => {
+                res.extend(c.items.iter().cloned())
+            }
+            SigmaBoolean::SigmaConjecture(SigmaConjecture::Cor(c)) if !and => {
+                res.extend(c.items.iter().cloned())
+            }
+            _ => res.push(item.clone()),
+        }
+    }
+    SigmaConjectureItems::from_vec(res).unwrap_or(items)
+}
+
 /// Failed to extract specified underlying type from SigmaBoolean
 #[derive(PartialEq, Eq, Debug, Clone)]
 pub struct ConversionError;
@@ -364,13 +434,239 @@ mod arbitrary {
 #[allow(clippy::panic)]
 #[cfg(test)]
 #[allow(clippy::panic)]
+#[allow(clippy::unwrap_used)]
 mod tests {
     use super::*;
     use crate::serialization::sigma_serialize_roundtrip;
     use proptest::prelude::*;
+    use sigma_test_util::force_any_val;
+
+    fn has_trivial(sb: &SigmaBoolean) -> bool {
+        match sb {
+            SigmaBoolean::TrivialProp(_) => true,
+            SigmaBoolean::ProofOfKnowledge(_) => false,
+            SigmaBoolean::SigmaConjecture(SigmaConjecture::Cand(c)) => {
+                c.items.iter().any(has_trivial)
+            }
+            SigmaBoolean::SigmaConjecture(SigmaConjecture::Cor(c)) => {
+                c.items.iter().any(has_trivial)
+            }
+            SigmaBoolean::SigmaConjecture(SigmaConjecture::Cthreshold(c)) => {
+                c.items.iter().any(has_trivial)
+            }
+        }
+    }
+
+    #[test]
+    fn reduce_trivial_nested() {
+        let pk1: SigmaBoolean = force_any_val::<ProveDlog>().into();
+        let pk2: SigmaBoolean = force_any_val::<ProveDlog>().into();
+        let cand = |items: Vec<SigmaBoolean>| -> SigmaBoolean {
+            Cand {
+                items: items.try_into().unwrap(),
+            }
+            .into()
+        };
+        let cor = |items: Vec<SigmaBoolean>| -> SigmaBoolean {
+            Cor {
+                items: items.try_into().unwrap(),
+            }
+            .into()
+        };
+        // CAND(pk1, COR(pk2, true)) -> pk1
+        assert_eq!(
+            cand(vec![pk1.clone(), cor(vec![pk2.clone(), true.into()])]).reduce_trivial(),
+            pk1
+        );
+        // COR(pk1, CAND(pk2, false)) -> pk1
+        assert_eq!(
+            cor(vec![pk1.clone(), cand(vec![pk2.clone(), false.into()])]).reduce_trivial(),
+            pk1
+        );
+        // CAND(COR(false, false), pk1) -> false
+        assert_eq!(
+            cand(vec![cor(vec![false.into(), false.into()]), pk1.clone()]).reduce_trivial(),
+            false.into()
+        );
+        // COR(CAND(true, true), pk1) -> true
+        assert_eq!(
+            cor(vec![cand(vec![true.into(), true.into()]), pk1.clone()]).reduce_trivial(),
+            true.into()
+        );
+        // THRESHOLD(2, pk1, pk2, COR(true, pk1)) -> COR(pk1, pk2)
+        assert_eq!(
+            SigmaBoolean::from(Cthreshold {
+                n: 2,
+                items: vec![
+                    pk1.clone(),
+                    pk2.clone(),
+                    cor(vec![true.into(), pk1.clone()])
+                ]
+                .try_into()
+                .unwrap()
+            })
+            .reduce_trivial(),
+            cor(vec![pk1.clone(), pk2.clone()])
+        );
+        // no trivial props, nothing changes
+        let sb = cand(vec![pk1.clone(), cor(vec![pk2, pk1])]);
+        assert_eq!(sb.clone().reduce_trivial(), sb);
+    }
+
+    /// True if the proposition is satisfied by the secrets of the given public keys
+    fn is_satisfied_by(sb: &SigmaBoolean, keys: &[SigmaBoolean]) -> bool {
+        match sb {
+            SigmaBoolean::TrivialProp(b) => *b,
+            SigmaBoolean::ProofOfKnowledge(_) => keys.contains(sb),
+            SigmaBoolean::SigmaConjecture(SigmaConjecture::Cand(c)) => {
+                c.items.iter().all(|i| is_satisfied_by(i, keys))
+            }
+            SigmaBoolean::SigmaConjecture(SigmaConjecture::Cor(c)) => {
+                c.items.iter().any(|i| is_satisfied_by(i, keys))
+            }
+            SigmaBoolean::SigmaConjecture(SigmaConjecture::Cthreshold(c)) => {
+                c.items.iter().filter(|i| is_satisfied_by(i, keys)).count() as i64 >= c.n as i64
+            }
+        }
+    }
+
+    fn keys(sb: &SigmaBoolean) -> Vec<SigmaBoolean> {
+        match sb {
+            SigmaBoolean::TrivialProp(_) => vec![],
+            SigmaBoolean::ProofOfKnowledge(_) => vec![sb.clone()],
+            SigmaBoolean::SigmaConjecture(SigmaConjecture::Cand(c)) => {
+                c.items.iter().flat_map(keys).collect()
+            }
+            SigmaBoolean::SigmaConjecture(SigmaConjecture::Cor(c)) => {
+                c.items.iter().flat_map(keys).collect()
+            }
+            SigmaBoolean::SigmaConjecture(SigmaConjecture::Cthreshold(c)) => {
+                c.items.iter().flat_map(keys).collect()
+            }
+        }
+    }
+
+    fn has_nested_same_kind(sb: &SigmaBoolean) -> bool {
+        match sb {
+            SigmaBoolean::SigmaConjecture(SigmaConjecture::Cand(c)) => c.items.iter().any(|i| {
+                matches!(i, SigmaBoolean::SigmaConjecture(SigmaConjecture::Cand(_)))
+                    || has_nested_same_kind(i)
+            }),
+            SigmaBoolean::SigmaConjecture(SigmaConjecture::Cor(c)) => c.items.iter().any(|i| {
+                matches!(i, SigmaBoolean::SigmaConjecture(SigmaConjecture::Cor(_)))
+                    || has_nested_same_kind(i)
+            }),
+            SigmaBoolean::SigmaConjecture(SigmaConjecture::Cthreshold(c)) => {
+                c.items.iter().any(has_nested_same_kind)
+            }
+            _ => false,
+        }
+    }
+
+    #[test]
+    fn normalized_nested() {
+        let pk1: SigmaBoolean = force_any_val::<ProveDlog>().into();
+        let pk2: SigmaBoolean = force_any_val::<ProveDlog>().into();
+        let pk3: SigmaBoolean = force_any_val::<ProveDlog>().into();
+        let cand = |items: Vec<SigmaBoolean>| -> SigmaBoolean {
+            Cand {
+                items: items.try_into().unwrap(),
+            }
+            .into()
+        };
+        let cor = |items: Vec<SigmaBoolean>| -> SigmaBoolean {
+            Cor {
+                items: items.try_into().unwrap(),
+            }
+            .into()
+        };
+        // CAND(pk1, CAND(pk2, COR(pk3, false))) -> CAND(pk1, pk2, pk3)
+        assert_eq!(
+            cand(vec![
+                pk1.clone(),
+                cand(vec![pk2.clone(), cor(vec![pk3.clone(), false.into()])])
+            ])
+            .normalized(),
+            cand(vec![pk1.clone(), pk2.clone(), pk3.clone()])
+        );
+        // COR(COR(pk1, pk2), CAND(pk2, pk3)) -> COR(pk1, pk2, CAND(pk2, pk3))
+        assert_eq!(
+            cor(vec![
+                cor(vec![pk1.clone(), pk2.clone()]),
+                cand(vec![pk2.clone(), pk3.clone()])
+            ])
+            .normalized(),
+            cor(vec![
+                pk1.clone(),
+                pk2.clone(),
+                cand(vec![pk2.clone(), pk3.clone()])
+            ])
+        );
+        // THRESHOLD(2, CAND(pk1, CAND(pk2, true)), pk2, pk3) -> THRESHOLD(2, CAND(pk1, pk2), pk2, pk3)
+        assert_eq!(
+            SigmaBoolean::from(Cthreshold {
+                n: 2,
+                items: vec![
+                    cand(vec![pk1.clone(), cand(vec![pk2.clone(), true.into()])]),
+                    pk2.clone(),
+                    pk3.clone()
+                ]
+                .try_into()
+                .unwrap()
+            })
+            .normalized(),
+            SigmaBoolean::from(Cthreshold {
+                n: 2,
+                items: vec![cand(vec![pk1, pk2.clone()]), pk2, pk3]
+                    .try_into()
+                    .unwrap()
+            })
+        );
+    }
 
     proptest! {
 
+        #[test]
+        fn reduce_trivial_leaves_no_trivial_props(v in any::<SigmaBoolean>(), b in any::<bool>()) {
+            let sb: SigmaBoolean = Cand {
+                items: vec![v.clone(), Cor { items: vec![b.into(), v].try_into().unwrap() }.into()]
+                    .try_into()
+                    .unwrap(),
+            }
+            .into();
+            let res = sb.reduce_trivial();
+            prop_assert!(matches!(res, SigmaBoolean::TrivialProp(_)) || !has_trivial(&res));
+        }
+
+        #[test]
+        fn normalized_preserves_satisfiability(
+            v in any::<SigmaBoolean>(),
+            w in any::<SigmaBoolean>(),
+            b in any::<bool>(),
+            mask in any::<u64>(),
+        ) {
+            let sb: SigmaBoolean = Cor {
+                items: vec![
+                    Cand { items: vec![v.clone(), b.into()].try_into().unwrap() }.into(),
+                    Cthreshold { n: 2, items: vec![v, w, (!b).into()].try_into().unwrap() }.into(),
+                ]
+                .try_into()
+                .unwrap(),
+            }
+            .into();
+            let keys: Vec<SigmaBoolean> = keys(&sb)
+                .into_iter()
+                .enumerate()
+                .filter(|(idx, _)| mask & (1 << (idx % 64)) != 0)
+                .map(|(_, k)| k)
+                .collect();
+            let res = sb.clone().normalized();
+            prop_assert_eq!(is_satisfied_by(&res, &keys), is_satisfied_by(&sb, &keys));
+            prop_assert!(matches!(res, SigmaBoolean::TrivialProp(_)) || !has_trivial(&res));
+            prop_assert!(!has_nested_same_kind(&res));
+            prop_assert_eq!(res.clone().normalized(), res);
+        }
+
         #[test]
         fn sigma_boolean_ser_roundtrip(
             v in any::<SigmaBoolean>()) {
@@ -1,15 +1,18 @@
 //! THRESHOLD conjunction for sigma proposition
 
+use super::cand::Cand;
+use super::cor::Cor;
 use super::SigmaBoolean;
+use super::SigmaConjecture;
 use super::SigmaConjectureItems;
 use crate::has_opcode::HasStaticOpCode;
 use crate::serialization::op_code::OpCode;
 use crate::serialization::sigma_byte_reader::SigmaByteRead;
 use crate::serialization::sigma_byte_writer::SigmaByteWrite;
+use crate::serialization::SigmaSerializationError;
 use crate::serialization::SigmaSerializeResult;
 use crate::serialization::{SigmaParsingError, SigmaSerializable};
-
-// use crate::sigma_protocol::sigma_boolean::SigmaConjecture;
+use std::convert::TryFrom;
 
 /// THRESHOLD conjunction for sigma proposition
 #[derive(PartialEq, Eq, Debug, Clone)]
@@ -24,15 +27,151 @@ impl HasStaticOpCode for Cthreshold {
     const OP_CODE: OpCode = OpCode::ATLEAST;
 }
 
+impl Cthreshold {
+    /// Connects the given sigma propositions into THRESHOLD proposition (at least `k` of `items`
+    /// should be proven) performing partial evaluation when some of them are trivial propositions.
+    /// Collapses into CAND (`k` equals the number of non-trivial items) or COR (`k` is 1).
+    pub fn normalized(k: i32, items: SigmaConjectureItems<SigmaBoolean>) -> SigmaBoolean {
+        let mut k = k as i64;
+        let mut items_left = items.len() as i64;
+        if k <= 0 {
+            return true.into();
+        }
+        if k > items_left {
+            return false.into();
+        }
+        let mut res: Vec<SigmaBoolean> = Vec::new();
+        for it in items {
+            match it {
+                SigmaBoolean::TrivialProp(false) => {
+                    items_left -= 1;
+                    if k > items_left {
+                        return false.into();
+                    }
+                }
+                SigmaBoolean::TrivialProp(true) => {
+                    k -= 1;
+                    items_left -= 1;
+                    if k <= 0 {
+                        return true.into();
+                    }
+                }
+                _ => res.push(it),
+            }
+        }
+        // here 1 <= k <= items_left == res.len()
+        if res.len() == 1 {
+            return res.remove(0);
+        }
+        match SigmaConjectureItems::from_vec(res) {
+            Ok(items) if k == 1 => Cor::normalized(items),
+            Ok(items) if k == items_left => Cand::normalized(items),
+            Ok(items) => SigmaConjecture::Cthreshold(Cthreshold { n: k as i32, items }).into(),
+            // more than the max items is not possible, since there were no more in the input
+            Err(_) => false.into(),
+        }
+    }
+}
+
 impl SigmaSerializable for Cthreshold {
     fn sigma_serialize<W: SigmaByteWrite>(&self, w: &mut W) -> SigmaSerializeResult {
-        w.put_i32(self.n)?;
+        // `k` is an unsigned short (VLQ) as in the reference implementation, so that `propBytes`
+        // and the proposition bytes of the boxes are the same
+        let k = u16::try_from(self.n).map_err(|_| {
+            SigmaSerializationError::NotSupported("CTHRESHOLD k out of the 0..=65535 range")
+        })?;
+        w.put_u16(k)?;
         self.items.sigma_serialize(w)
     }
 
     fn sigma_parse<R: SigmaByteRead>(r: &mut R) -> Result<Self, SigmaParsingError> {
-        let n = r.get_i32()?;
+        let n = r.get_u16()? as i32;
         let items = SigmaConjectureItems::<_>::sigma_parse(r)?;
         Ok(Cthreshold { n, items })
     }
 }
+
+#[allow(clippy::unwrap_used)]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sigma_protocol::sigma_boolean::ProveDlog;
+    use sigma_test_util::force_any_val;
+    use std::convert::TryInto;
+
+    fn pk() -> SigmaBoolean {
+        force_any_val::<ProveDlog>().into()
+    }
+
+    fn normalized(k: i32, items: Vec<SigmaBoolean>) -> SigmaBoolean {
+        Cthreshold::normalized(k, items.try_into().unwrap())
+    }
+
+    #[test]
+    fn bounds() {
+        assert_eq!(normalized(0, vec![pk(), pk()]), true.into());
+        assert_eq!(normalized(3, vec![pk(), pk()]), false.into());
+    }
+
+    #[test]
+    fn trivial_items() {
+        assert_eq!(
+            normalized(2, vec![true.into(), true.into(), pk()]),
+            true.into()
+        );
+        assert_eq!(
+            normalized(2, vec![false.into(), false.into(), pk()]),
+            false.into()
+        );
+        let (pk1, pk2) = (pk(), pk());
+        assert_eq!(
+            normalized(2, vec![true.into(), pk1.clone(), false.into()]),
+            pk1
+        );
+        assert_eq!(
+            normalized(2, vec![true.into(), pk1.clone(), pk2.clone()]),
+            Cor {
+                items: vec![pk1.clone(), pk2.clone()].try_into().unwrap()
+            }
+            .into()
+        );
+        assert_eq!(
+            normalized(2, vec![false.into(), pk1.clone(), pk2.clone()]),
+            Cand {
+                items: vec![pk1.clone(), pk2.clone()].try_into().unwrap()
+            }
+            .into()
+        );
+        let pk3 = pk();
+        assert_eq!(
+            normalized(2, vec![pk1.clone(), false.into(), pk2.clone(), pk3.clone()]),
+            Cthreshold {
+                n: 2,
+                items: vec![pk1, pk2, pk3].try_into().unwrap()
+            }
+            .into()
+        );
+    }
+
+    #[test]
+    fn ser_k_as_unsigned_short() {
+        let pks: Vec<SigmaBoolean> = vec![pk(), pk(), pk()];
+        let c = Cthreshold {
+            n: 2,
+            items: pks.clone().try_into().unwrap(),
+        };
+        // k and the number of items (VLQ), followed by the items
+        let mut expected = vec![2u8, 3];
+        for pk in &pks {
+            expected.extend(pk.sigma_serialize_bytes().unwrap());
+        }
+        assert_eq!(c.sigma_serialize_bytes().unwrap(), expected);
+        assert_eq!(Cthreshold::sigma_parse_bytes(&expected).unwrap(), c);
+        assert!(Cthreshold {
+            n: -1,
+            items: pks.try_into().unwrap(),
+        }
+        .sigma_serialize_bytes()
+        .is_err());
+    }
+}
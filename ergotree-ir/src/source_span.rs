@@ -0,0 +1,91 @@
+//! Source position for an IR node in the source code (ErgoScript)
+
+/// Source position for the IR node (offset and length in the source code).
+/// Empty if the node was not created from the source code (e.g. deserialized).
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy, Default)]
+pub struct SourceSpan {
+    /// Start position in the source code (zero-based byte offset)
+    pub offset: usize,
+    /// Length of the span in bytes
+    pub length: usize,
+}
+
+impl SourceSpan {
+    /// Span for the node without the source code
+    pub fn empty() -> Self {
+        SourceSpan {
+            offset: 0,
+            length: 0,
+        }
+    }
+
+    /// Returns true if the span points to nothing in the source code
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    /// End position in the source code (exclusive)
+    pub fn end(&self) -> usize {
+        self.offset + self.length
+    }
+}
+
+/// Wrapper for the IR node with its position in the source code.
+/// Only [`crate::mir::expr::Expr::BinOp`] nodes carry the position so far, the other nodes
+/// created from the source code have none.
+/// The position is not a part of the node's identity, the nodes are equal if the wrapped nodes are
+/// equal (the position is lost on serialization).
+#[derive(Debug, Clone)]
+pub struct Spanned<T> {
+    /// Source position
+    pub source_span: SourceSpan,
+    /// Wrapped node
+    pub expr: T,
+}
+
+impl<T> Spanned<T> {
+    /// Wrap the node with the given source position
+    pub fn new(expr: T, source_span: SourceSpan) -> Self {
+        Spanned { source_span, expr }
+    }
+
+    /// Wrapped node
+    pub fn expr(&self) -> &T {
+        &self.expr
+    }
+}
+
+impl<T: PartialEq> PartialEq for Spanned<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.expr == other.expr
+    }
+}
+
+impl<T: Eq> Eq for Spanned<T> {}
+
+impl<T> From<T> for Spanned<T> {
+    fn from(expr: T) -> Self {
+        Spanned {
+            source_span: SourceSpan::empty(),
+            expr,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn eq_ignores_span() {
+        let spanned = Spanned::new(
+            1,
+            SourceSpan {
+                offset: 2,
+                length: 3,
+            },
+        );
+        assert_eq!(spanned, Spanned::from(1));
+        assert_ne!(spanned, Spanned::from(2));
+    }
+}
@@ -0,0 +1,173 @@
+//! Registry of well-known contract templates, to detect the contract behind an ErgoTree by its
+//! template hash (see [`ErgoTree::template_hash`])
+
+use std::collections::HashMap;
+
+use crate::chain::address::AddressEncoder;
+use crate::chain::digest32::Digest32;
+use crate::ergo_tree::ErgoTree;
+use crate::ergo_tree::ErgoTreeError;
+
+/// Miner fee contract (mainnet)
+const MINERS_FEE_ADDRESS: &str = "2iHkR7CWvD1R4j1yZg5bkeDRQavjAaVPeTDFGGLZduHyfWMuYpmhHocX8GJoaieTx78FntzJbCBVL6rf96ocJoZdmWBL2fci7NqWgAirppPQmZ7fN9V6z13Ay6brPriBKYqLp1bT2Fk4FkFLCfdPpe";
+
+/// AgeUSD (SigmaUSD) bank contract, from EIP-15
+const AGEUSD_BANK_ADDRESS: &str = "MUbV38YgqHy7XbsoXWF5z7EZm524Ybdwe5p9WDrbhruZRtehkRPT92imXer2eTkjwPDfboa1pR3zb3deVKVq3H7Xt98qcTqLuSBSbHb7izzo5jphEpcnqyKJ2xhmpNPVvmtbdJNdvdopPrHHDBbAGGeW7XYTQwEeoRfosXzcDtiGgw97b2aqjTsNFmZk7khBEQywjYfmoDc9nUCJMZ3vbSspnYo3LarLe55mh2Np8MNJqUN9APA6XkhZCrTTDRZb1B4krgFY1sVMswg2ceqguZRvC9pqt3tUUxmSnB24N6dowfVJKhLXwHPbrkHViBv1AKAJTmEaQW2DN1fRmD9ypXxZk8GXmYtxTtrj3BiunQ4qzUCu1eGzxSREjpkFSi2ATLSSDqUwxtRz639sHM6Lav4axoJNPCHbY8pvuBKUxgnGRex8LEGM8DeEJwaJCaoy8dBw9Lz49nq5mSsXLeoC4xpTUmp47Bh7GAZtwkaNreCu74m9rcZ8Di4w1cmdsiK1NWuDh9pJ2Bv7u3EfcurHFVqCkT3P86JUbKnXeNxCypfrWsFuYNKYqmjsix82g9vWcGMmAcu5nagxD4iET86iE2tMMfZZ5vqZNvntQswJyQqv2Wc6MTh4jQx1q2qJZCQe4QdEK63meTGbZNNKMctHQbp3gRkZYNrBtxQyVtNLR8xEY8zGp85GeQKbb37vqLXxRpGiigAdMe3XZA4hhYPmAAU5hpSMYaRAjtvvMT3bNiHRACGrfjvSsEG9G2zY5in2YWz5X9zXQLGTYRsQ4uNFkYoQRCBdjNxGv6R58Xq74zCgt19TxYZ87gPWxkXpWwTaHogG1eps8WXt8QzwJ9rVx6Vu9a5GjtcGsQxHovWmYixgBU8X9fPNJ9UQhYyAWbjtRSuVBtDAmoV1gCBEPwnYVP5GCGhCocbwoYhZkZjFZy6ws4uxVLid3FxuvhWvQrVEDYp7WRvGXbNdCbcSXnbeTrPMey1WPaXX";
+
+/// AgeUSD (SigmaUSD) update contract, from EIP-15
+const AGEUSD_UPDATE_ADDRESS: &str = "VLyjpv3dse3PbatT83GnDkBQasGqY52dAEdi9XpXhuSUn1FS1Tm7XxtAgmBiqY9pJXtEAsDKwX9ygSjrFu7vnUQZudhC2sSmxhxqgD3ZxJ2VsGwmPG77F6EiEZhcq71oqEq31y9XvCCXL5nqqszdENPAVhu7xT296qZ7w1x6hmwdh9ZE89bjfgbhfNYopoqsCaNLWYHJ12TDSY93kaGqCVKSu6gEF1gLpXBfRCnAPPxYswJPmK8oWDn8PKrUGs3MjVsj6bGXiW3VTGP4VsNH8YSSkjyj1FZ9azLsyfnNJ3zah2zUHdCCqY6PjH9JfHf9joCPf6TusvXgr71XWvh5e2HPEPQr4eJMD4S96cGTiSs3J5XcRd1tCDYoiis8nxv99zFFhHgpqXHgeqjhJ5sPot9eRYTsmm4cRTVLXYAiuKPS2qW5";
+
+/// AgeUSD (SigmaUSD) ballot contract, from EIP-15
+const AGEUSD_BALLOT_ADDRESS: &str = "22ELWBHzyWGjPRE48ZJDfFmD24myYdG3vHz8CipSS7rgE65ABmEj9QJiy3rG2PTJeCaZw9VX56GY6uoA3hQch7i5BfFU3AprUWTABi4X1VWtRdK9yrYJkmN6fq8hGfvmWTrsyh4fXZoGETpLuXQViYo194ajej2h7dr3oqNATdMskSXzxJi83bFdAvQ";
+
+/// Well-known contracts (name, mainnet P2S address)
+const KNOWN_TEMPLATES: [(&str, &str); 4] = [
+    ("Miner fee", MINERS_FEE_ADDRESS),
+    ("AgeUSD (SigmaUSD) bank", AGEUSD_BANK_ADDRESS),
+    ("AgeUSD (SigmaUSD) update", AGEUSD_UPDATE_ADDRESS),
+    ("AgeUSD (SigmaUSD) ballot", AGEUSD_BALLOT_ADDRESS),
+];
+
+/// Contracts of an EIP-23 (oracle pool v2) deployment. The token ids and the other parameters
+/// are the tree constants, so the templates of the trees compiled by the oracle core (from its
+/// pool config) match every pool deployed with the same contract version.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Eip23OracleContracts {
+    /// Pool contract
+    pub pool: ErgoTree,
+    /// Refresh contract
+    pub refresh: ErgoTree,
+    /// Oracle contract
+    pub oracle: ErgoTree,
+    /// Ballot contract
+    pub ballot: ErgoTree,
+    /// Update contract
+    pub update: ErgoTree,
+}
+
+/// Contract templates keyed by the template hash
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct TemplateRegistry {
+    templates: HashMap<Digest32, String>,
+}
+
+impl TemplateRegistry {
+    /// Empty registry
+    pub fn empty() -> TemplateRegistry {
+        TemplateRegistry {
+            templates: HashMap::new(),
+        }
+    }
+
+    /// Registry with the well-known mainnet contracts (miner fee, AgeUSD/SigmaUSD bank, update
+    /// and ballot)
+    pub fn known() -> TemplateRegistry {
+        let mut registry = TemplateRegistry::empty();
+        KNOWN_TEMPLATES.iter().for_each(|(name, address)| {
+            if let Some(tree) = AddressEncoder::unchecked_parse_address_from_str(address)
+                .ok()
+                .and_then(|a| a.script().ok())
+            {
+                // a template that cannot be hashed is never detected, so it's safe to skip it
+                let _ = registry.insert(name, &tree);
+            }
+        });
+        registry
+    }
+
+    /// Add the template of the given tree (e.g. an oracle pool contract compiled with some
+    /// parameters) under the given name
+    pub fn insert(&mut self, name: &str, tree: &ErgoTree) -> Result<(), ErgoTreeError> {
+        self.templates
+            .insert(tree.template_hash()?, name.to_string());
+        Ok(())
+    }
+
+    /// Add the templates of the EIP-23 oracle pool contracts (named `EIP-23 oracle pool`,
+    /// `EIP-23 refresh`, `EIP-23 oracle`, `EIP-23 ballot` and `EIP-23 update`)
+    pub fn insert_eip23_oracle_pool(
+        &mut self,
+        contracts: &Eip23OracleContracts,
+    ) -> Result<(), ErgoTreeError> {
+        self.insert("EIP-23 oracle pool", &contracts.pool)?;
+        self.insert("EIP-23 refresh", &contracts.refresh)?;
+        self.insert("EIP-23 oracle", &contracts.oracle)?;
+        self.insert("EIP-23 ballot", &contracts.ballot)?;
+        self.insert("EIP-23 update", &contracts.update)
+    }
+
+    /// Name of the template the tree was created from, if it's in the registry
+    pub fn detect(&self, tree: &ErgoTree) -> Option<&str> {
+        tree.template_hash()
+            .ok()
+            .and_then(|hash| self.templates.get(&hash))
+            .map(String::as_str)
+    }
+}
+
+impl Default for TemplateRegistry {
+    fn default() -> Self {
+        TemplateRegistry::known()
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+#[allow(clippy::panic)]
+mod tests {
+    use super::*;
+    use crate::mir::constant::Constant;
+    use crate::serialization::SigmaSerializable;
+
+    fn tree(address: &str) -> ErgoTree {
+        AddressEncoder::unchecked_parse_address_from_str(address)
+            .unwrap()
+            .script()
+            .unwrap()
+    }
+
+    #[test]
+    fn detect_known_templates() {
+        let registry = TemplateRegistry::known();
+        KNOWN_TEMPLATES.iter().for_each(|(name, address)| {
+            assert_eq!(registry.detect(&tree(address)), Some(*name));
+        });
+    }
+
+    #[test]
+    fn detect_with_other_constants() {
+        let registry = TemplateRegistry::known();
+        let fee_tree = tree(MINERS_FEE_ADDRESS);
+        let constant = fee_tree.get_constant(0).unwrap().unwrap();
+        let new_constant = match constant.v.clone() {
+            crate::mir::constant::Literal::Int(v) => Constant::from(v + 1),
+            _ => panic!("expected Int constant, got {:?}", constant),
+        };
+        let modified_tree = fee_tree.clone().with_constant(0, new_constant).unwrap();
+        assert_ne!(
+            modified_tree.sigma_serialize_bytes().unwrap(),
+            fee_tree.sigma_serialize_bytes().unwrap()
+        );
+        assert_eq!(registry.detect(&modified_tree), Some("Miner fee"));
+        assert_eq!(TemplateRegistry::empty().detect(&fee_tree), None);
+    }
+
+    #[test]
+    fn detect_eip23_oracle_pool() {
+        // any contracts with distinct templates will do
+        let contracts = Eip23OracleContracts {
+            pool: tree(AGEUSD_BANK_ADDRESS),
+            refresh: tree(AGEUSD_UPDATE_ADDRESS),
+            oracle: tree(AGEUSD_BALLOT_ADDRESS),
+            ballot: tree(MINERS_FEE_ADDRESS),
+            update: tree("9hzP24a2q8KLPVCUk7gdMDXYc7vinmGuxmLp5KU7k9UwptgYBYV"),
+        };
+        let mut registry = TemplateRegistry::empty();
+        registry.insert_eip23_oracle_pool(&contracts).unwrap();
+        assert_eq!(registry.detect(&contracts.pool), Some("EIP-23 oracle pool"));
+        assert_eq!(registry.detect(&contracts.refresh), Some("EIP-23 refresh"));
+        assert_eq!(registry.detect(&contracts.oracle), Some("EIP-23 oracle"));
+        assert_eq!(registry.detect(&contracts.ballot), Some("EIP-23 ballot"));
+        assert_eq!(registry.detect(&contracts.update), Some("EIP-23 update"));
+    }
+}
@@ -24,7 +24,8 @@ impl TypeCheckError {
 pub fn type_check(e: Expr) -> Result<Expr, TypeCheckError> {
     // not really a relevant check, since such kind of check should be in BinOp::new()
     match &e {
-        Expr::BinOp(bin) => {
+        Expr::BinOp(spanned) => {
+            let bin = spanned.expr();
             if bin.left.tpe() == bin.right.tpe() {
                 Ok(e)
             } else {
@@ -2,6 +2,8 @@
 
 pub mod stype;
 
+/// Registry of the object methods resolved by the parser
+pub mod method_registry;
 /// AVL tree methods
 pub mod savltree;
 /// Box object type companion
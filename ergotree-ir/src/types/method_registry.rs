@@ -0,0 +1,430 @@
+//! Methods of the object types resolved on the parsing of `MethodCall` and `PropertyCall`
+//!
+//! The built-in methods are the static tables of the type companions (see [`STypeCompanion`]),
+//! shared by all the registries. A registry instance can have additional methods, replace or
+//! remove the built-in ones (e.g. the methods of a soft-fork proposal in the tests or off-chain
+//! tools), which is only seen by the readers it is set to (see
+//! [`crate::serialization::sigma_byte_reader::SigmaByteReader::with_method_registry`]) and the
+//! interpreter it is passed to.
+
+use std::convert::TryFrom;
+
+use thiserror::Error;
+
+use crate::serialization::types::TypeCode;
+use crate::serialization::SigmaParsingError;
+
+use super::smethod::MethodId;
+use super::smethod::SMethod;
+use super::smethod::SMethodDesc;
+use super::stype_companion::STypeCompanion;
+
+/// Built-in methods and the changes of this instance
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct MethodRegistry {
+    /// Added methods and the replacements of the built-in ones
+    added: Vec<SMethod>,
+    /// Removed built-in methods
+    removed: Vec<(STypeCompanion, MethodId)>,
+}
+
+/// Errors of the method registration
+#[derive(Error, PartialEq, Eq, Debug, Clone)]
+pub enum MethodRegistryError {
+    /// Method id is already used in the type companion
+    #[error("Method id {method_id:?} is already used by {name} in {obj_type:?}")]
+    DuplicateMethodId {
+        /// Type companion
+        obj_type: STypeCompanion,
+        /// Method id
+        method_id: MethodId,
+        /// Name of the registered method
+        name: &'static str,
+    },
+    /// Method to replace or remove is not in the registry
+    #[error("Method id {method_id:?} is not found in {obj_type:?}")]
+    UnknownMethod {
+        /// Type companion
+        obj_type: STypeCompanion,
+        /// Method id
+        method_id: MethodId,
+    },
+}
+
+impl MethodRegistry {
+    /// Registry of the built-in methods
+    pub fn builtin() -> Self {
+        MethodRegistry::default()
+    }
+
+    /// Add the method to the type companion, fails if the method id is already used (by a
+    /// built-in or added method)
+    pub fn add_method(
+        &mut self,
+        obj_type: STypeCompanion,
+        desc: SMethodDesc,
+    ) -> Result<(), MethodRegistryError> {
+        if let Some(registered) = self.method_by_id(obj_type, &desc.method_id) {
+            return Err(MethodRegistryError::DuplicateMethodId {
+                obj_type,
+                method_id: desc.method_id,
+                name: registered.name(),
+            });
+        }
+        self.added.push(desc.as_method(obj_type));
+        Ok(())
+    }
+
+    /// Registry with the added method (see [`MethodRegistry::add_method`])
+    pub fn with_method(
+        mut self,
+        obj_type: STypeCompanion,
+        desc: SMethodDesc,
+    ) -> Result<Self, MethodRegistryError> {
+        self.add_method(obj_type, desc)?;
+        Ok(self)
+    }
+
+    /// Replace the method of the type companion (a built-in or added one) with the given one with
+    /// the same method id, fails if there is no such method
+    pub fn replace_method(
+        &mut self,
+        obj_type: STypeCompanion,
+        desc: SMethodDesc,
+    ) -> Result<(), MethodRegistryError> {
+        self.remove_method(obj_type, desc.method_id.clone())?;
+        self.added.push(desc.as_method(obj_type));
+        Ok(())
+    }
+
+    /// Registry with the replaced method (see [`MethodRegistry::replace_method`])
+    pub fn with_replaced_method(
+        mut self,
+        obj_type: STypeCompanion,
+        desc: SMethodDesc,
+    ) -> Result<Self, MethodRegistryError> {
+        self.replace_method(obj_type, desc)?;
+        Ok(self)
+    }
+
+    /// Remove the method of the type companion (a built-in or added one), fails if there is no
+    /// such method
+    pub fn remove_method(
+        &mut self,
+        obj_type: STypeCompanion,
+        method_id: MethodId,
+    ) -> Result<(), MethodRegistryError> {
+        if self.method_by_id(obj_type, &method_id).is_none() {
+            return Err(MethodRegistryError::UnknownMethod {
+                obj_type,
+                method_id,
+            });
+        }
+        self.added
+            .retain(|m| !(m.obj_type == obj_type && m.method_id() == method_id));
+        if obj_type.method_by_id(&method_id).is_some() {
+            self.removed.push((obj_type, method_id));
+        }
+        Ok(())
+    }
+
+    /// Registry without the method (see [`MethodRegistry::remove_method`])
+    pub fn without_method(
+        mut self,
+        obj_type: STypeCompanion,
+        method_id: MethodId,
+    ) -> Result<Self, MethodRegistryError> {
+        self.remove_method(obj_type, method_id)?;
+        Ok(self)
+    }
+
+    /// Get method signature of the type companion by a method id
+    pub fn method_by_id(&self, obj_type: STypeCompanion, method_id: &MethodId) -> Option<SMethod> {
+        self.added
+            .iter()
+            .find(|m| m.obj_type == obj_type && m.method_id() == *method_id)
+            .cloned()
+            .or_else(|| {
+                if self.is_removed(obj_type, method_id) {
+                    None
+                } else {
+                    obj_type.method_by_id(method_id)
+                }
+            })
+    }
+
+    /// Returns true if the method is the unchanged built-in one (neither replaced nor removed)
+    pub fn is_builtin_method(&self, obj_type: STypeCompanion, method_id: &MethodId) -> bool {
+        obj_type.method_by_id(method_id).is_some()
+            && !self.is_removed(obj_type, method_id)
+            && !self
+                .added
+                .iter()
+                .any(|m| m.obj_type == obj_type && m.method_id() == *method_id)
+    }
+
+    /// Get list of method signatures of the type companion (built-in first)
+    pub fn methods(&self, obj_type: STypeCompanion) -> Vec<SMethod> {
+        let mut res: Vec<SMethod> = obj_type
+            .methods()
+            .iter()
+            .filter_map(|m| self.method_by_id(obj_type, &m.method_id()))
+            .collect();
+        res.extend(
+            self.added
+                .iter()
+                .filter(|m| {
+                    m.obj_type == obj_type && obj_type.method_by_id(&m.method_id()).is_none()
+                })
+                .cloned(),
+        );
+        res
+    }
+
+    fn is_removed(&self, obj_type: STypeCompanion, method_id: &MethodId) -> bool {
+        self.removed
+            .iter()
+            .any(|(t, id)| *t == obj_type && id == method_id)
+    }
+
+    /// Get method from type and method ids
+    pub fn from_ids(
+        &self,
+        type_id: TypeCode,
+        method_id: MethodId,
+    ) -> Result<SMethod, SigmaParsingError> {
+        let obj_type = STypeCompanion::try_from(type_id)?;
+        match self.method_by_id(obj_type, &method_id) {
+            Some(m) => Ok(m),
+            None => Err(SigmaParsingError::UnknownMethodId(
+                method_id,
+                type_id.value(),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "arbitrary")]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::ergo_tree::ErgoTree;
+    use crate::ergo_tree::ErgoTreeHeader;
+    use crate::mir::constant::Constant;
+    use crate::mir::expr::Expr;
+    use crate::mir::method_call::MethodCall;
+    use crate::serialization::constant_store::ConstantStore;
+    use crate::serialization::sigma_byte_reader::SigmaByteReader;
+    use crate::serialization::SigmaSerializable;
+    use crate::types::scoll;
+    use crate::types::sfunc::SFunc;
+    use crate::types::stype::SType;
+    use crate::types::stype_param::STypeVar;
+
+    /// `Coll[T].reverse: Coll[T]` with the unused method id
+    fn reverse_desc() -> SMethodDesc {
+        SMethodDesc::new(
+            "reverse",
+            MethodId(200),
+            SFunc::new(
+                vec![SType::SColl(SType::STypeVar(STypeVar::t()).into())],
+                SType::SColl(SType::STypeVar(STypeVar::t()).into()),
+            ),
+        )
+    }
+
+    #[test]
+    fn builtin_methods() {
+        let registry = MethodRegistry::builtin();
+        assert_eq!(
+            registry.from_ids(scoll::TYPE_CODE, scoll::INDEX_OF_METHOD_ID),
+            Ok(scoll::INDEX_OF_METHOD.clone())
+        );
+        assert_eq!(
+            registry.methods(STypeCompanion::Coll),
+            STypeCompanion::Coll.methods()
+        );
+        assert_eq!(
+            registry.from_ids(scoll::TYPE_CODE, MethodId(200)),
+            Err(SigmaParsingError::UnknownMethodId(
+                MethodId(200),
+                scoll::TYPE_CODE.value()
+            ))
+        );
+    }
+
+    #[test]
+    fn added_method() {
+        let registry = MethodRegistry::builtin()
+            .with_method(STypeCompanion::Coll, reverse_desc())
+            .unwrap();
+        assert_eq!(
+            registry
+                .from_ids(scoll::TYPE_CODE, MethodId(200))
+                .map(|m| m.name()),
+            Ok("reverse")
+        );
+        assert_eq!(
+            registry.methods(STypeCompanion::Coll).len(),
+            STypeCompanion::Coll.methods().len() + 1
+        );
+        // the added method is not seen by the other registries
+        assert!(MethodRegistry::builtin()
+            .method_by_id(STypeCompanion::Coll, &MethodId(200))
+            .is_none());
+        assert!(registry
+            .method_by_id(STypeCompanion::Option, &MethodId(200))
+            .is_none());
+    }
+
+    #[test]
+    fn duplicate_method_id() {
+        let mut registry = MethodRegistry::builtin();
+        let desc = SMethodDesc::new("reverse", scoll::INDEX_OF_METHOD_ID, reverse_desc().tpe);
+        assert_eq!(
+            registry.add_method(STypeCompanion::Coll, desc),
+            Err(MethodRegistryError::DuplicateMethodId {
+                obj_type: STypeCompanion::Coll,
+                method_id: scoll::INDEX_OF_METHOD_ID,
+                name: "indexOf",
+            })
+        );
+        registry
+            .add_method(STypeCompanion::Coll, reverse_desc())
+            .unwrap();
+        assert!(registry
+            .add_method(STypeCompanion::Coll, reverse_desc())
+            .is_err());
+    }
+
+    #[test]
+    fn replaced_builtin_method() {
+        let desc = SMethodDesc::new("reverse", scoll::INDEX_OF_METHOD_ID, reverse_desc().tpe);
+        let registry = MethodRegistry::builtin()
+            .with_replaced_method(STypeCompanion::Coll, desc)
+            .unwrap();
+        assert_eq!(
+            registry
+                .from_ids(scoll::TYPE_CODE, scoll::INDEX_OF_METHOD_ID)
+                .map(|m| m.name()),
+            Ok("reverse")
+        );
+        assert!(!registry.is_builtin_method(STypeCompanion::Coll, &scoll::INDEX_OF_METHOD_ID));
+        // the replacement keeps the place of the built-in method
+        let methods = registry.methods(STypeCompanion::Coll);
+        let builtin = STypeCompanion::Coll.methods();
+        assert_eq!(methods.len(), builtin.len());
+        let idx = builtin
+            .iter()
+            .position(|m| m.method_id() == scoll::INDEX_OF_METHOD_ID)
+            .unwrap();
+        assert_eq!(methods[idx].name(), "reverse");
+        assert_eq!(
+            MethodRegistry::builtin()
+                .with_replaced_method(STypeCompanion::Coll, reverse_desc())
+                .err(),
+            Some(MethodRegistryError::UnknownMethod {
+                obj_type: STypeCompanion::Coll,
+                method_id: MethodId(200),
+            })
+        );
+    }
+
+    #[test]
+    fn removed_method() {
+        let registry = MethodRegistry::builtin()
+            .without_method(STypeCompanion::Coll, scoll::INDEX_OF_METHOD_ID)
+            .unwrap();
+        assert_eq!(
+            registry.from_ids(scoll::TYPE_CODE, scoll::INDEX_OF_METHOD_ID),
+            Err(SigmaParsingError::UnknownMethodId(
+                scoll::INDEX_OF_METHOD_ID,
+                scoll::TYPE_CODE.value()
+            ))
+        );
+        assert_eq!(
+            registry.methods(STypeCompanion::Coll).len(),
+            STypeCompanion::Coll.methods().len() - 1
+        );
+        assert!(registry
+            .clone()
+            .without_method(STypeCompanion::Coll, scoll::INDEX_OF_METHOD_ID)
+            .is_err());
+        // the id of the removed built-in method can be used by an added method
+        let desc = SMethodDesc::new("reverse", scoll::INDEX_OF_METHOD_ID, reverse_desc().tpe);
+        let registry = registry.with_method(STypeCompanion::Coll, desc).unwrap();
+        assert_eq!(
+            registry
+                .from_ids(scoll::TYPE_CODE, scoll::INDEX_OF_METHOD_ID)
+                .map(|m| m.name()),
+            Ok("reverse")
+        );
+        // the added method is removed entirely
+        let registry = MethodRegistry::builtin()
+            .with_method(STypeCompanion::Coll, reverse_desc())
+            .unwrap()
+            .without_method(STypeCompanion::Coll, MethodId(200))
+            .unwrap();
+        assert_eq!(registry, MethodRegistry::builtin());
+    }
+
+    /// `Coll(1, 2).reverse` with the method of the given registry
+    fn reverse_call(registry: &MethodRegistry) -> Expr {
+        let obj: Expr = Constant::from(vec![1i32, 2]).into();
+        let method = registry
+            .from_ids(scoll::TYPE_CODE, MethodId(200))
+            .unwrap()
+            .specialize_for(obj.tpe(), vec![])
+            .unwrap();
+        MethodCall::new(obj, method, vec![]).unwrap().into()
+    }
+
+    #[test]
+    fn parse_with_registry() {
+        let registry = MethodRegistry::builtin()
+            .with_method(STypeCompanion::Coll, reverse_desc())
+            .unwrap();
+        let expr = reverse_call(&registry);
+        let bytes = expr.sigma_serialize_bytes().unwrap();
+        assert!(Expr::sigma_parse_bytes(&bytes).is_err());
+        let mut r = SigmaByteReader::new(Cursor::new(&bytes), ConstantStore::empty())
+            .with_method_registry(registry);
+        assert_eq!(Expr::sigma_parse(&mut r).unwrap(), expr);
+    }
+
+    #[test]
+    fn parse_sized_tree_with_registry() {
+        let registry = MethodRegistry::builtin()
+            .with_method(STypeCompanion::Coll, reverse_desc())
+            .unwrap();
+        let expr = reverse_call(&registry);
+        // sized tree, the root expression is parsed with a separate reader
+        let tree = ErgoTree::new(ErgoTreeHeader::v1(true), &expr).unwrap();
+        let bytes = tree.sigma_serialize_bytes().unwrap();
+
+        // the method is unknown to the builtin registry, the root bytes are kept
+        let builtin_tree = ErgoTree::sigma_parse_bytes(&bytes).unwrap();
+        assert!(builtin_tree.proposition().is_err());
+        assert_eq!(builtin_tree.sigma_serialize_bytes().unwrap(), bytes);
+        // the kept root bytes are parsed again with the registry
+        assert_eq!(
+            builtin_tree
+                .proposition_with_methods(&registry)
+                .unwrap()
+                .as_ref(),
+            &expr
+        );
+
+        let mut r = SigmaByteReader::new(Cursor::new(&bytes), ConstantStore::empty())
+            .with_method_registry(registry.clone());
+        let parsed = ErgoTree::sigma_parse(&mut r).unwrap();
+        assert_eq!(parsed, tree);
+        assert_eq!(
+            parsed.proposition_with_methods(&registry).unwrap().as_ref(),
+            &expr
+        );
+    }
+}
@@ -29,6 +29,8 @@ pub const IS_UPDATE_ALLOWED_METHOD_ID: MethodId = MethodId(6);
 pub const IS_REMOVE_ALLOWED_METHOD_ID: MethodId = MethodId(7);
 /// AvlTree.updateOperations property
 pub const UPDATE_OPERATIONS_METHOD_ID: MethodId = MethodId(8);
+/// AvlTree.contains property
+pub const CONTAINS_METHOD_ID: MethodId = MethodId(9);
 /// AvlTree.get property
 pub const GET_METHOD_ID: MethodId = MethodId(10);
 /// AvlTree.getMany property
@@ -54,6 +56,7 @@ lazy_static! {
             &IS_UPDATE_ALLOWED_METHOD_DESC,
             &IS_REMOVE_ALLOWED_METHOD_DESC,
             &UPDATE_OPERATIONS_METHOD_DESC,
+            &CONTAINS_METHOD_DESC,
             &GET_METHOD_DESC,
             &GET_MANY_METHOD_DESC,
             &INSERT_METHOD_DESC,
@@ -184,6 +187,25 @@ lazy_static! {
         SMethod::new(STypeCompanion::AvlTree, UPDATE_OPERATIONS_METHOD_DESC.clone(),);
 }
 
+lazy_static! {
+    static ref CONTAINS_METHOD_DESC: SMethodDesc = SMethodDesc {
+        method_id: CONTAINS_METHOD_ID,
+        name: "contains",
+        tpe: SFunc {
+            t_dom: vec![ SType::SAvlTree,
+                         SType::SColl(SType::SByte.into()),
+                         SType::SColl(SType::SByte.into()),
+                       ],
+            t_range: SType::SBoolean.into(),
+            tpe_params: vec![],
+        },
+    };
+
+    /// AvlTree.contains
+    pub static ref CONTAINS_METHOD: SMethod =
+        SMethod::new(STypeCompanion::AvlTree, CONTAINS_METHOD_DESC.clone(),);
+}
+
 lazy_static! {
     static ref GET_METHOD_DESC: SMethodDesc = SMethodDesc {
         method_id: GET_METHOD_ID,
@@ -347,6 +369,9 @@ mod tests {
             SMethod::from_ids(TYPE_CODE, UPDATE_OPERATIONS_METHOD_ID).map(|e| e.name())
                 == Ok("updateOperations")
         );
+        assert!(
+            SMethod::from_ids(TYPE_CODE, CONTAINS_METHOD_ID).map(|e| e.name()) == Ok("contains")
+        );
         assert!(SMethod::from_ids(TYPE_CODE, GET_METHOD_ID).map(|e| e.name()) == Ok("get"));
         assert!(
             SMethod::from_ids(TYPE_CODE, GET_MANY_METHOD_ID).map(|e| e.name()) == Ok("getMany")
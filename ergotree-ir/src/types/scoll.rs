@@ -28,6 +28,12 @@ pub const PATCH_METHOD_ID: MethodId = MethodId(19);
 pub const UPDATED_METHOD_ID: MethodId = MethodId(20);
 /// Coll.updateMany
 pub const UPDATE_MANY_METHOD_ID: MethodId = MethodId(21);
+/// Coll.distinct (proposed for the v6.x script versions, not activated on the network)
+pub const DISTINCT_METHOD_ID: MethodId = MethodId(31);
+/// Coll.startsWith (proposed for the v6.x script versions, not activated on the network)
+pub const STARTS_WITH_METHOD_ID: MethodId = MethodId(32);
+/// Coll.endsWith (proposed for the v6.x script versions, not activated on the network)
+pub const ENDS_WITH_METHOD_ID: MethodId = MethodId(33);
 
 lazy_static! {
     /// Coll method descriptors
@@ -40,6 +46,9 @@ lazy_static! {
             &UPDATED_METHOD_DESC,
             &UPDATE_MANY_METHOD_DESC,
             &PATCH_METHOD_DESC,
+            &DISTINCT_METHOD_DESC,
+            &STARTS_WITH_METHOD_DESC,
+            &ENDS_WITH_METHOD_DESC,
         ]
     ;
 }
@@ -167,6 +176,53 @@ lazy_static! {
     pub static ref UPDATE_MANY_METHOD: SMethod = SMethod::new(STypeCompanion::Coll, UPDATE_MANY_METHOD_DESC.clone());
 }
 
+lazy_static! {
+    static ref DISTINCT_METHOD_DESC: SMethodDesc = SMethodDesc {
+        method_id: DISTINCT_METHOD_ID,
+        name: "distinct",
+        tpe: SFunc::new(
+            vec![
+                SType::SColl(SType::STypeVar(STypeVar::t()).into()),
+            ],
+            SType::SColl(SType::STypeVar(STypeVar::t()).into())
+        )
+    };
+    /// Coll.distinct
+    pub static ref DISTINCT_METHOD: SMethod = SMethod::new(STypeCompanion::Coll, DISTINCT_METHOD_DESC.clone());
+}
+
+lazy_static! {
+    static ref STARTS_WITH_METHOD_DESC: SMethodDesc = SMethodDesc {
+        method_id: STARTS_WITH_METHOD_ID,
+        name: "startsWith",
+        tpe: SFunc::new(
+            vec![
+                SType::SColl(SType::STypeVar(STypeVar::t()).into()),
+                SType::SColl(SType::STypeVar(STypeVar::t()).into()),
+            ],
+            SType::SBoolean
+        )
+    };
+    /// Coll.startsWith
+    pub static ref STARTS_WITH_METHOD: SMethod = SMethod::new(STypeCompanion::Coll, STARTS_WITH_METHOD_DESC.clone());
+}
+
+lazy_static! {
+    static ref ENDS_WITH_METHOD_DESC: SMethodDesc = SMethodDesc {
+        method_id: ENDS_WITH_METHOD_ID,
+        name: "endsWith",
+        tpe: SFunc::new(
+            vec![
+                SType::SColl(SType::STypeVar(STypeVar::t()).into()),
+                SType::SColl(SType::STypeVar(STypeVar::t()).into()),
+            ],
+            SType::SBoolean
+        )
+    };
+    /// Coll.endsWith
+    pub static ref ENDS_WITH_METHOD: SMethod = SMethod::new(STypeCompanion::Coll, ENDS_WITH_METHOD_DESC.clone());
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -185,5 +241,15 @@ mod tests {
             SMethod::from_ids(TYPE_CODE, UPDATE_MANY_METHOD_ID).map(|e| e.name())
                 == Ok("updateMany")
         );
+        assert!(
+            SMethod::from_ids(TYPE_CODE, DISTINCT_METHOD_ID).map(|e| e.name()) == Ok("distinct")
+        );
+        assert!(
+            SMethod::from_ids(TYPE_CODE, STARTS_WITH_METHOD_ID).map(|e| e.name())
+                == Ok("startsWith")
+        );
+        assert!(
+            SMethod::from_ids(TYPE_CODE, ENDS_WITH_METHOD_ID).map(|e| e.name()) == Ok("endsWith")
+        );
     }
 }
@@ -78,7 +78,7 @@ lazy_static! {
         property("INPUTS", SColl(SBox.into()), INPUTS_PROPERTY_METHOD_ID);
 }
 lazy_static! {
-    static ref INPUTS_PROPERTY: SMethod =
+    pub static ref INPUTS_PROPERTY: SMethod =
         SMethod::new(STypeCompanion::Context, INPUTS_PROPERTY_METHOD_DESC.clone());
 }
 
@@ -88,7 +88,7 @@ lazy_static! {
         property("OUTPUTS", SColl(SBox.into()), OUTPUTS_PROPERTY_METHOD_ID);
 }
 lazy_static! {
-    static ref OUTPUTS_PROPERTY: SMethod = SMethod::new(
+    pub static ref OUTPUTS_PROPERTY: SMethod = SMethod::new(
         STypeCompanion::Context,
         OUTPUTS_PROPERTY_METHOD_DESC.clone()
     );
@@ -100,7 +100,7 @@ lazy_static! {
         property("HEIGHT", SInt, HEIGHT_PROPERTY_METHOD_ID);
 }
 lazy_static! {
-    static ref HEIGHT_PROPERTY: SMethod =
+    pub static ref HEIGHT_PROPERTY: SMethod =
         SMethod::new(STypeCompanion::Context, HEIGHT_PROPERTY_METHOD_DESC.clone());
 }
 
@@ -110,7 +110,7 @@ lazy_static! {
         property("SELF", SBox, SELF_PROPERTY_METHOD_ID);
 }
 lazy_static! {
-    static ref SELF_PROPERTY: SMethod =
+    pub static ref SELF_PROPERTY: SMethod =
         SMethod::new(STypeCompanion::Context, SELF_PROPERTY_METHOD_DESC.clone());
 }
 
@@ -135,7 +135,7 @@ lazy_static! {
     );
 }
 lazy_static! {
-    static ref LAST_BLOCK_UTXO_ROOT_HASH_PROPERTY: SMethod = SMethod::new(
+    pub static ref LAST_BLOCK_UTXO_ROOT_HASH_PROPERTY: SMethod = SMethod::new(
         STypeCompanion::Context,
         LAST_BLOCK_UTXO_ROOT_HASH_PROPERTY_METHOD_DESC.clone()
     );
@@ -150,7 +150,7 @@ lazy_static! {
     );
 }
 lazy_static! {
-    static ref MINER_PUBKEY_PROPERTY: SMethod = SMethod::new(
+    pub static ref MINER_PUBKEY_PROPERTY: SMethod = SMethod::new(
         STypeCompanion::Context,
         MINER_PUBKEY_PROPERTY_METHOD_DESC.clone()
     );
@@ -170,5 +170,10 @@ mod tests {
             SMethod::from_ids(TYPE_CODE, DATA_INPUTS_PROPERTY_METHOD_ID).map(|e| e.name())
                 == Ok("dataInputs")
         );
+        assert!(
+            SMethod::from_ids(TYPE_CODE, LAST_BLOCK_UTXO_ROOT_HASH_PROPERTY_METHOD_ID)
+                .map(|e| e.name())
+                == Ok("LastBlockUtxoRootHash")
+        );
     }
 }
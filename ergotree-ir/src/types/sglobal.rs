@@ -1,9 +1,12 @@
 use crate::serialization::types::TypeCode;
+use crate::types::stype_companion::STypeCompanion;
 
 use super::sfunc::SFunc;
 use super::smethod::MethodId;
+use super::smethod::SMethod;
 use super::smethod::SMethodDesc;
 use super::stype::SType;
+use super::stype_param::STypeVar;
 use lazy_static::lazy_static;
 
 /// SGlobal type code
@@ -15,11 +18,15 @@ pub static TYPE_NAME: &str = "Global";
 pub const GROUP_GENERATOR_METHOD_ID: MethodId = MethodId(1);
 /// "xor" predefined function
 pub const XOR_METHOD_ID: MethodId = MethodId(2);
+/// Global.serialize (v6.0)
+pub const SERIALIZE_METHOD_ID: MethodId = MethodId(3);
+/// Global.deserializeTo (v6.0)
+pub const DESERIALIZE_TO_METHOD_ID: MethodId = MethodId(4);
 
 lazy_static! {
     /// Global method descriptors
     pub(crate) static ref METHOD_DESC: Vec<&'static SMethodDesc> =
-        vec![&GROUP_GENERATOR_METHOD_DESC, &XOR_METHOD_DESC,];
+        vec![&GROUP_GENERATOR_METHOD_DESC, &XOR_METHOD_DESC, &SERIALIZE_METHOD_DESC, &DESERIALIZE_TO_METHOD_DESC,];
 }
 
 lazy_static! {
@@ -49,3 +56,33 @@ lazy_static! {
         },
     };
 }
+
+lazy_static! {
+    static ref SERIALIZE_METHOD_DESC: SMethodDesc = SMethodDesc {
+        method_id: SERIALIZE_METHOD_ID,
+        name: "serialize",
+        tpe: SFunc {
+            t_dom: vec![SType::SGlobal, STypeVar::t().into()],
+            t_range: SType::SColl(SType::SByte.into()).into(),
+            tpe_params: vec![],
+        },
+    };
+    /// Global.serialize
+    pub static ref SERIALIZE_METHOD: SMethod =
+        SMethod::new(STypeCompanion::Global, SERIALIZE_METHOD_DESC.clone(),);
+}
+
+lazy_static! {
+    static ref DESERIALIZE_TO_METHOD_DESC: SMethodDesc = SMethodDesc {
+        method_id: DESERIALIZE_TO_METHOD_ID,
+        name: "deserializeTo",
+        tpe: SFunc {
+            t_dom: vec![SType::SGlobal, SType::SColl(SType::SByte.into())],
+            t_range: Box::new(STypeVar::t().into()),
+            tpe_params: vec![],
+        },
+    };
+    /// Global.deserializeTo
+    pub static ref DESERIALIZE_TO_METHOD: SMethod =
+        SMethod::new(STypeCompanion::Global, DESERIALIZE_TO_METHOD_DESC.clone(),);
+}
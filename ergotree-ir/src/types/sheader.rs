@@ -157,3 +157,17 @@ lazy_static! {
 fn property(name: &'static str, res_tpe: SType, id: MethodId) -> SMethodDesc {
     SMethodDesc::property(SType::SHeader, name, res_tpe, id)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_ids() {
+        assert!(SMethod::from_ids(TYPE_CODE, VOTES_METHOD_ID).map(|e| e.name()) == Ok("votes"));
+        // `Header.checkPow` (16) is not implemented, 17 and 18 are not defined in v6.0
+        for id in 16..=18 {
+            assert!(SMethod::from_ids(TYPE_CODE, MethodId(id)).is_err());
+        }
+    }
+}
@@ -3,15 +3,16 @@ use crate::serialization::sigma_byte_writer::SigmaByteWrite;
 use crate::serialization::types::TypeCode;
 use crate::serialization::SigmaParsingError;
 use std::collections::HashMap;
-use std::convert::TryFrom;
 
+use super::method_registry::MethodRegistry;
 use super::sfunc::SFunc;
+use super::sglobal;
 use super::stype::SType;
 use super::stype_companion::STypeCompanion;
 use super::stype_param::STypeVar;
 use super::type_unify::unify_many;
+use super::type_unify::unify_one;
 use super::type_unify::TypeUnificationError;
-use crate::serialization::SigmaParsingError::UnknownMethodId;
 
 /// Method id unique among the methods of the same object
 #[derive(PartialEq, Eq, Debug, Clone)]
@@ -44,16 +45,10 @@ impl SMethod {
         }
     }
 
-    /// Get method from type and method ids
-    pub(crate) fn from_ids(
-        type_id: TypeCode,
-        method_id: MethodId,
-    ) -> Result<Self, SigmaParsingError> {
-        let obj_type = STypeCompanion::try_from(type_id)?;
-        match obj_type.method_by_id(&method_id) {
-            Some(m) => Ok(m),
-            None => Err(UnknownMethodId(method_id, type_id.value())),
-        }
+    /// Get built-in method from type and method ids (see [`MethodRegistry`] for the parsing with
+    /// the additional methods)
+    pub fn from_ids(type_id: TypeCode, method_id: MethodId) -> Result<Self, SigmaParsingError> {
+        MethodRegistry::builtin().from_ids(type_id, method_id)
     }
 
     /// Type
@@ -92,6 +87,46 @@ impl SMethod {
         items2.append(args.as_mut());
         unify_many(self.tpe().t_dom.clone(), items2).map(|subst| self.with_concrete_types(&subst))
     }
+
+    /// Type variables of the method which are not inferred from the object and the arguments
+    /// types (`T` of `Global.deserializeTo[T]`), their types are serialized with the method call
+    /// (see [`crate::compat::VersionedFeature::MethodCallTypeArgs`])
+    pub fn explicit_type_args(&self) -> Vec<STypeVar> {
+        if self.obj_type == STypeCompanion::Global
+            && self.method_id() == sglobal::DESERIALIZE_TO_METHOD_ID
+        {
+            vec![STypeVar::t()]
+        } else {
+            vec![]
+        }
+    }
+
+    /// Types of the explicit type arguments (see [`SMethod::explicit_type_args`]) in this
+    /// specialized method, fails if any of them is not substituted
+    pub fn explicit_type_subst(&self) -> Result<Vec<SType>, TypeUnificationError> {
+        let type_args = self.explicit_type_args();
+        if type_args.is_empty() {
+            return Ok(vec![]);
+        }
+        let generic = self
+            .obj_type
+            .method_by_id(&self.method_id())
+            .ok_or_else(|| {
+                TypeUnificationError(format!("no method {} in {:?}", self.name(), self.obj_type))
+            })?;
+        let subst = unify_one(&generic.tpe().t_range, &self.tpe().t_range)?;
+        type_args
+            .iter()
+            .map(|type_arg| match subst.get(type_arg) {
+                Some(tpe) if !matches!(tpe, SType::STypeVar(_)) => Ok(tpe.clone()),
+                _ => Err(TypeUnificationError(format!(
+                    "type argument {:?} of {} is not specified",
+                    type_arg,
+                    self.name()
+                ))),
+            })
+            .collect()
+    }
 }
 
 /// Object method description
@@ -103,6 +138,15 @@ pub struct SMethodDesc {
 }
 
 impl SMethodDesc {
+    /// Initialize method description
+    pub fn new(name: &'static str, method_id: MethodId, tpe: SFunc) -> SMethodDesc {
+        SMethodDesc {
+            name,
+            method_id,
+            tpe,
+        }
+    }
+
     /// Initialize property method description
     pub fn property(
         obj_tpe: SType,
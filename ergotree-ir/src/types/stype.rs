@@ -255,6 +255,32 @@ pub(crate) mod tests {
         .boxed()
     }
 
+    /// Types of the values with the data serialization format (constants, registers)
+    #[cfg(test)]
+    pub(crate) fn data_type() -> BoxedStrategy<SType> {
+        prop_oneof![
+            Just(SType::SBoolean),
+            Just(SType::SByte),
+            Just(SType::SShort),
+            Just(SType::SInt),
+            Just(SType::SLong),
+            Just(SType::SBigInt),
+            Just(SType::SGroupElement),
+            Just(SType::SSigmaProp),
+            Just(SType::SBox),
+            Just(SType::SAvlTree),
+        ]
+        .prop_recursive(3, 16, 4, |elem| {
+            prop_oneof![
+                prop::collection::vec(elem.clone(), 2..=4)
+                    .prop_map(|elems| SType::STuple(elems.try_into().unwrap())),
+                elem.clone().prop_map(|tpe| SType::SColl(Box::new(tpe))),
+                elem.prop_map(|tpe| SType::SOption(Box::new(tpe))),
+            ]
+        })
+        .boxed()
+    }
+
     impl Arbitrary for SType {
         type Parameters = ();
         type Strategy = BoxedStrategy<Self>;
@@ -26,7 +26,7 @@ pub trait FromVecI8 {
 
 impl FromVecI8 for Vec<u8> {
     fn from_vec_i8(bs: Vec<i8>) -> Self {
-        bs.iter().map(|b| *b as u8).collect()
+        bs.into_vec_u8()
     }
 }
 
@@ -38,7 +38,7 @@ pub trait AsVecU8 {
 
 impl AsVecU8 for Vec<i8> {
     fn as_vec_u8(&self) -> Vec<u8> {
-        Vec::<u8>::from_vec_i8(self.clone())
+        self.as_u8_slice().to_vec()
     }
 }
 
@@ -50,6 +50,81 @@ pub trait AsVecI8 {
 
 impl AsVecI8 for Vec<u8> {
     fn as_vec_i8(&self) -> Vec<i8> {
-        self.iter().map(|b| *b as i8).collect()
+        self.as_i8_slice().to_vec()
+    }
+}
+
+/// Zero-copy view of an i8 slice as u8 slice
+pub trait AsU8Slice {
+    /// Returns the same bytes as &[u8] (no allocation)
+    fn as_u8_slice(&self) -> &[u8];
+}
+
+impl AsU8Slice for [i8] {
+    fn as_u8_slice(&self) -> &[u8] {
+        bytemuck::cast_slice(self)
+    }
+}
+
+/// Zero-copy view of an u8 slice as i8 slice
+pub trait AsI8Slice {
+    /// Returns the same bytes as &[i8] (no allocation)
+    fn as_i8_slice(&self) -> &[i8];
+}
+
+impl AsI8Slice for [u8] {
+    fn as_i8_slice(&self) -> &[i8] {
+        bytemuck::cast_slice(self)
+    }
+}
+
+/// Zero-copy conversion of Vec<i8> into Vec<u8> (reuses the allocation)
+pub trait IntoVecU8 {
+    /// Converts into Vec<u8>
+    fn into_vec_u8(self) -> Vec<u8>;
+}
+
+impl IntoVecU8 for Vec<i8> {
+    fn into_vec_u8(self) -> Vec<u8> {
+        bytemuck::allocation::cast_vec(self)
+    }
+}
+
+/// Zero-copy conversion of Vec<u8> into Vec<i8> (reuses the allocation)
+pub trait IntoVecI8 {
+    /// Converts into Vec<i8>
+    fn into_vec_i8(self) -> Vec<i8>;
+}
+
+impl IntoVecI8 for Vec<u8> {
+    fn into_vec_i8(self) -> Vec<i8> {
+        bytemuck::allocation::cast_vec(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    proptest! {
+
+        #[test]
+        fn slice_views_match_casts(bytes in any::<Vec<u8>>()) {
+            let expected: Vec<i8> = bytes.iter().map(|b| *b as i8).collect();
+            prop_assert_eq![bytes.as_i8_slice(), expected.as_slice()];
+            prop_assert_eq![expected.as_u8_slice(), bytes.as_slice()];
+        }
+
+        #[test]
+        fn into_vec_roundtrip(bytes in any::<Vec<u8>>()) {
+            let ptr = bytes.as_ptr() as usize;
+            let signed = bytes.clone().into_vec_i8();
+            prop_assert_eq![signed, bytes.as_vec_i8()];
+            let unsigned = signed.into_vec_u8();
+            prop_assert_eq![&unsigned, &bytes];
+            let reused = bytes.into_vec_i8();
+            prop_assert_eq![reused.as_ptr() as usize, ptr];
+        }
     }
 }
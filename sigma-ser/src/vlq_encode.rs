@@ -296,6 +296,19 @@ mod tests {
         assert_eq!(w.into_inner(), vec![0, 1, 255])
     }
 
+    #[test]
+    fn test_write_bits() {
+        // first bit in the lowest bit of the first byte, the last byte is padded with zeroes
+        let mut w = Cursor::new(vec![]);
+        w.put_bits(&[true, false, true]).unwrap();
+        w.put_bits(&[]).unwrap();
+        w.put_bits(&[false, false, false, false, false, false, false, true, true])
+            .unwrap();
+        assert_eq!(w.into_inner(), vec![0b101, 0b1000_0000, 0b1]);
+        let mut r = Cursor::new(vec![0b1111_0101]);
+        assert_eq!(r.get_bits(3).unwrap(), vec![true, false, true]);
+    }
+
     #[test]
     fn test_read_u8() {
         let mut r = Cursor::new(vec![0, 1, 255]);
@@ -1,4 +1,7 @@
 use proptest::strategy::ValueTree;
+use proptest::test_runner::Config;
+use proptest::test_runner::RngAlgorithm;
+use proptest::test_runner::TestRng;
 use proptest::test_runner::TestRunner;
 use proptest::{arbitrary::Arbitrary, prelude::*};
 
@@ -11,3 +14,43 @@ pub fn force_any_val_with<T: Arbitrary>(args: T::Parameters) -> T {
     let mut runner = TestRunner::default();
     any_with::<T>(args).new_tree(&mut runner).unwrap().current()
 }
+
+/// Same as [`force_any_val`], but the value is generated from the given seed (the same seed
+/// gives the same value on every run), for the reproducible tests
+pub fn force_any_val_seeded<T: Arbitrary>(seed: u64) -> T {
+    any::<T>()
+        .new_tree(&mut seeded_runner(seed))
+        .unwrap()
+        .current()
+}
+
+/// Same as [`force_any_val_with`], but the value is generated from the given seed
+pub fn force_any_val_with_seeded<T: Arbitrary>(args: T::Parameters, seed: u64) -> T {
+    any_with::<T>(args)
+        .new_tree(&mut seeded_runner(seed))
+        .unwrap()
+        .current()
+}
+
+fn seeded_runner(seed: u64) -> TestRunner {
+    let mut seed_bytes = [0u8; 32];
+    seed_bytes[..8].copy_from_slice(&seed.to_le_bytes());
+    TestRunner::new_with_rng(
+        Config::default(),
+        TestRng::from_seed(RngAlgorithm::ChaCha, &seed_bytes),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seeded_is_reproducible() {
+        let v1 = force_any_val_seeded::<(u64, Vec<u8>, String)>(7);
+        let v2 = force_any_val_seeded::<(u64, Vec<u8>, String)>(7);
+        assert_eq!(v1, v2);
+        let v3 = force_any_val_seeded::<(u64, Vec<u8>, String)>(8);
+        assert_ne!(v1, v3);
+    }
+}
@@ -1,18 +1,59 @@
 //! Hash functions
 
-use std::convert::TryInto;
-
 /// Blake2b256 hash (256 bit)
 pub fn blake2b256_hash(bytes: &[u8]) -> Box<[u8; 32]> {
-    use blake2::digest::{Update, VariableOutput};
-    use blake2::VarBlake2b;
+    use blake2::digest::Update;
 
-    // unwrap is safe 32 bytes is a valid hash size (<= 512 && 32 % 8 == 0)
-    let mut hasher = VarBlake2b::new(32).unwrap();
+    let mut hasher = blake2b256_hasher();
     hasher.update(bytes);
-    let hash = hasher.finalize_boxed();
-    // unwrap is safe due to hash size is expected to be 32
-    hash.try_into().unwrap()
+    blake2b256_finalize(hasher)
+}
+
+/// Incremental Blake2b256 hash of the bytes written into it (e.g. by a serializer, without
+/// collecting the bytes into a buffer first)
+pub struct Blake2b256Writer(blake2::VarBlake2b);
+
+impl Blake2b256Writer {
+    /// Create the writer with no bytes hashed
+    pub fn new() -> Self {
+        Blake2b256Writer(blake2b256_hasher())
+    }
+
+    /// Hash of the written bytes (same as [`blake2b256_hash`] of the bytes)
+    pub fn finalize(self) -> Box<[u8; 32]> {
+        blake2b256_finalize(self.0)
+    }
+}
+
+fn blake2b256_hasher() -> blake2::VarBlake2b {
+    // unkeyed hash, 32 bytes is a valid output size (<= 64)
+    blake2::VarBlake2b::new_keyed(&[], 32)
+}
+
+fn blake2b256_finalize(hasher: blake2::VarBlake2b) -> Box<[u8; 32]> {
+    use blake2::digest::VariableOutput;
+
+    let mut hash = [0u8; 32];
+    hasher.finalize_variable(|res| hash.copy_from_slice(res));
+    Box::new(hash)
+}
+
+impl Default for Blake2b256Writer {
+    fn default() -> Self {
+        Blake2b256Writer::new()
+    }
+}
+
+impl std::io::Write for Blake2b256Writer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        use blake2::digest::Update;
+        self.0.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
 }
 
 /// Sha256 hash (256 bit)
@@ -23,3 +64,78 @@ pub fn sha256_hash(bytes: &[u8]) -> Box<[u8; 32]> {
     hasher.update(bytes);
     Box::new(*hasher.finalize().as_ref())
 }
+
+/// Incremental Sha256 hash of the bytes written into it
+pub struct Sha256Writer(sha2::Sha256);
+
+impl Sha256Writer {
+    /// Create the writer with no bytes hashed
+    pub fn new() -> Self {
+        use sha2::Digest;
+        Sha256Writer(sha2::Sha256::new())
+    }
+
+    /// Hash of the written bytes (same as [`sha256_hash`] of the bytes)
+    pub fn finalize(self) -> Box<[u8; 32]> {
+        use sha2::Digest;
+        Box::new(*self.0.finalize().as_ref())
+    }
+}
+
+impl Default for Sha256Writer {
+    fn default() -> Self {
+        Sha256Writer::new()
+    }
+}
+
+impl std::io::Write for Sha256Writer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        use sha2::Digest;
+        self.0.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn blake2b256_vectors() {
+        assert_eq!(
+            crate::hex::encode_lower(&blake2b256_hash(&[])[..]),
+            "0e5751c026e543b2e8ab2eb06099daa1d1e5df47778f7787faab45cdf12fe3a8"
+        );
+        assert_eq!(
+            crate::hex::encode_lower(&blake2b256_hash(b"abc")[..]),
+            "bddd813c634239723171ef3fee98579b94964e3bb1cb3e427262c8c068d52319"
+        );
+    }
+
+    #[test]
+    fn blake2b256_writer() {
+        let bytes: Vec<u8> = (0..=255u8).collect();
+        let mut w = Blake2b256Writer::new();
+        w.write_all(&bytes[..100]).unwrap();
+        w.write_all(&[]).unwrap();
+        w.write_all(&bytes[100..]).unwrap();
+        assert_eq!(w.finalize(), blake2b256_hash(&bytes));
+        assert_eq!(Blake2b256Writer::new().finalize(), blake2b256_hash(&[]));
+    }
+
+    #[test]
+    fn sha256_writer() {
+        let bytes: Vec<u8> = (0..=255u8).collect();
+        let mut w = Sha256Writer::new();
+        w.write_all(&bytes[..10]).unwrap();
+        w.write_all(&bytes[10..]).unwrap();
+        assert_eq!(w.finalize(), sha256_hash(&bytes));
+        assert_eq!(Sha256Writer::new().finalize(), sha256_hash(&[]));
+    }
+}
@@ -0,0 +1,72 @@
+//! Base16 (hex) encoding of the ids, serialized values and keys.
+//!
+//! The output and the errors are the same as of the `base16` crate. With the `simd` feature the
+//! SIMD-accelerated codec of the `faster-hex` crate is used (AVX2/SSE4.1, detected at runtime, with
+//! a fallback on other targets).
+
+pub use base16::DecodeError;
+
+/// Encode bytes as a lower case hex string
+pub fn encode_lower<T: ?Sized + AsRef<[u8]>>(input: &T) -> String {
+    #[cfg(feature = "simd")]
+    {
+        faster_hex::hex_string(input.as_ref())
+    }
+    #[cfg(not(feature = "simd"))]
+    {
+        base16::encode_lower(input)
+    }
+}
+
+/// Decode a hex string (lower, upper or mixed case)
+pub fn decode<T: ?Sized + AsRef<[u8]>>(input: &T) -> Result<Vec<u8>, DecodeError> {
+    #[cfg(feature = "simd")]
+    {
+        let src = input.as_ref();
+        let mut bytes = vec![0; src.len() / 2];
+        // faster-hex fails on empty input and its errors have no position of the invalid byte, so
+        // the invalid input is decoded again to get the same error
+        if !src.is_empty() && faster_hex::hex_decode(src, &mut bytes).is_ok() {
+            return Ok(bytes);
+        }
+    }
+    base16::decode(input)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn bytes(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i * 97 + 13) as u8).collect()
+    }
+
+    #[test]
+    fn same_as_base16() {
+        // lengths around the SIMD block sizes
+        for len in 0..100 {
+            let b = bytes(len);
+            let encoded = encode_lower(&b);
+            assert_eq!(encoded, base16::encode_lower(&b));
+            assert_eq!(decode(&encoded).unwrap(), b);
+            let upper = base16::encode_upper(&b);
+            assert_eq!(decode(&upper).unwrap(), b);
+        }
+    }
+
+    #[test]
+    fn same_errors_as_base16() {
+        let valid = base16::encode_lower(&bytes(40));
+        let mut inputs: Vec<Vec<u8>> = vec![b"0".to_vec(), b"abc".to_vec(), valid[1..].into()];
+        for &(index, invalid) in &[(0, b'g'), (17, b' '), (38, 0xff), (79, b'G')] {
+            let mut input = valid.clone().into_bytes();
+            input[index] = invalid;
+            inputs.push(input);
+        }
+        for input in inputs {
+            assert!(decode(&input).is_err());
+            assert_eq!(decode(&input), base16::decode(&input));
+        }
+    }
+}
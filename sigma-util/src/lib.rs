@@ -12,8 +12,14 @@
 // Clippy exclusions
 #![allow(clippy::unit_arg)]
 #![deny(rustdoc::broken_intra_doc_links)]
+#![deny(clippy::unwrap_used)]
+#![deny(clippy::expect_used)]
+#![deny(clippy::todo)]
+#![deny(clippy::unimplemented)]
+#![deny(clippy::panic)]
 
 pub mod hash;
+pub mod hex;
 
 /// 32 byte array used in box, transaction ids (hash)
 pub const DIGEST32_SIZE: usize = 32;